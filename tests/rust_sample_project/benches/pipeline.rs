@@ -0,0 +1,142 @@
+// rust_sample_project/benches/pipeline.rs
+
+// Benchmarks for the load, process and save stages on synthetic datasets.
+// Run with: cargo bench --features bench
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+use rust_sample_project::bench_support::{synthetic_items, write_synthetic_data_file, DATASET_SIZES};
+use rust_sample_project::classify::{self, Thresholds};
+use rust_sample_project::config::get_threshold;
+use rust_sample_project::data_handler::{DataFormat, DataHandler};
+use rust_sample_project::item_processor::ItemProcessor;
+use rust_sample_project::item_ref;
+use rust_sample_project::logging::{self, LogLevel};
+use rust_sample_project::money::Money;
+
+/// Seed shared by all benchmarks, so they measure the same data.
+const SEED: u64 = 42;
+
+/**
+ * @brief Keep the per-item log output from dominating the measurements.
+ */
+fn quiet_logging() {
+    logging::set_min_level(LogLevel::Critical);
+}
+
+/**
+ * @brief Benchmark reading and parsing a JSON data file.
+ */
+fn bench_load(c: &mut Criterion) {
+    quiet_logging();
+    let mut group = c.benchmark_group("load_json");
+    group.sample_size(10);
+    for size in DATASET_SIZES {
+        let path = write_synthetic_data_file(size, SEED).expect("failed to write the benchmark data file");
+        let data_handler = DataHandler::new(&path);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| data_handler.load_items().expect("failed to load items"));
+        });
+        let _ = std::fs::remove_file(path);
+    }
+    group.finish();
+}
+
+/**
+ * @brief Benchmark parsing a JSON data file as borrowed items (see item_ref.rs),
+ *        to compare with load_json.
+ */
+fn bench_load_borrowed(c: &mut Criterion) {
+    quiet_logging();
+    let mut group = c.benchmark_group("load_json_borrowed");
+    group.sample_size(10);
+    for size in DATASET_SIZES {
+        let path = write_synthetic_data_file(size, SEED).expect("failed to write the benchmark data file");
+        let location = path.display().to_string();
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| {
+                let contents = std::fs::read_to_string(&path).expect("failed to read the data file");
+                item_ref::parse_items(&contents, DataFormat::Json, &location)
+                    .expect("failed to parse items")
+                    .len()
+            });
+        });
+        let _ = std::fs::remove_file(path);
+    }
+    group.finish();
+}
+
+/**
+ * @brief Benchmark the threshold logic of ItemProcessor over a whole dataset.
+ */
+fn bench_process(c: &mut Criterion) {
+    quiet_logging();
+    let item_processor = ItemProcessor::new(get_threshold());
+    let mut group = c.benchmark_group("process");
+    group.sample_size(10);
+    for size in DATASET_SIZES {
+        let items = synthetic_items(size, SEED);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter_batched_ref(
+                || items.clone(),
+                |items| {
+                    for item in items.iter_mut() {
+                        item_processor.process_item(item);
+                    }
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+/**
+ * @brief Benchmark classifying a column of values against the threshold,
+ *        vectorized and with the scalar loop (see classify.rs).
+ */
+fn bench_classify(c: &mut Criterion) {
+    let threshold = Money::from_f64_saturating(get_threshold());
+    let mut group = c.benchmark_group("classify");
+    for size in DATASET_SIZES {
+        let values: Vec<Money> = synthetic_items(size, SEED).iter().map(|item| item.value).collect();
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::new("vectorized", size), &size, |b, _| {
+            b.iter(|| classify::classify_values(&values, Thresholds::Uniform(threshold)).expect("failed to classify"));
+        });
+        group.bench_with_input(BenchmarkId::new("scalar", size), &size, |b, _| {
+            b.iter(|| {
+                classify::classify_values_scalar(&values, Thresholds::Uniform(threshold)).expect("failed to classify")
+            });
+        });
+    }
+    group.finish();
+}
+
+/**
+ * @brief Benchmark serializing and atomically writing a JSON data file.
+ */
+fn bench_save(c: &mut Criterion) {
+    quiet_logging();
+    let mut group = c.benchmark_group("save_json");
+    group.sample_size(10);
+    for size in DATASET_SIZES {
+        let items = synthetic_items(size, SEED);
+        let path = std::env::temp_dir().join(format!("rust_sample_project_bench_save_{}.json", size));
+        let data_handler = DataHandler::new(&path);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| data_handler.save_items_to(&path, &items).expect("failed to save items"));
+        });
+        let _ = std::fs::remove_file(path);
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_load, bench_load_borrowed, bench_process, bench_classify, bench_save);
+criterion_main!(benches);
+
+// End of rust_sample_project/benches/pipeline.rs
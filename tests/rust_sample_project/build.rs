@@ -0,0 +1,112 @@
+// rust_sample_project/build.rs
+
+// Code generation:
+//
+//   the description of every config key (OUT_DIR/config_docs.rs, see
+//   config::describe), taken from the doc comment of its default in
+//   src/config.rs so `gen-docs` documents exactly what the code says.
+//
+// and for optional features:
+//
+//   ffi   the C header of the C API (include/rust_sample_project.h, see
+//         src/ffi.rs). Only src/ffi.rs is parsed, so the header declares
+//         exactly the C API; settings are in cbindgen.toml.
+//   grpc  the gRPC types and service traits from proto/item_processing.proto
+//         (see src/grpc_server.rs), compiled with protox so no protoc is needed.
+
+fn main() {
+    generate_config_docs();
+    #[cfg(feature = "ffi")]
+    generate_header();
+    #[cfg(feature = "grpc")]
+    compile_protos();
+}
+
+/**
+ * @brief Return the @brief paragraph of a doc comment as one line.
+ */
+fn brief(comment: &str) -> Option<String> {
+    let mut words = Vec::new();
+    let mut in_brief = false;
+    for line in comment.lines() {
+        let line = line.trim().trim_start_matches('*').trim();
+        if let Some(text) = line.strip_prefix("@brief") {
+            in_brief = true;
+            words.extend(text.split_whitespace());
+        } else if in_brief && (line.is_empty() || line.starts_with('@')) {
+            break;
+        } else if in_brief {
+            words.extend(line.split_whitespace());
+        }
+    }
+    in_brief.then(|| words.join(" "))
+}
+
+/**
+ * @brief Write the descriptions of the config keys to OUT_DIR/config_docs.rs.
+ *
+ * A Config field documented as "See NAME." takes the @brief of the constant
+ * NAME, its default.
+ */
+fn generate_config_docs() {
+    let crate_dir = std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo"));
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo"));
+    println!("cargo:rerun-if-changed=src/config.rs");
+    let source = std::fs::read_to_string(crate_dir.join("src/config.rs")).expect("src/config.rs is readable");
+
+    let mut briefs = std::collections::HashMap::new();
+    for chunk in source.split("/**").skip(1) {
+        let Some((comment, rest)) = chunk.split_once("*/") else { continue };
+        let Some(declaration) = rest.trim_start().strip_prefix("pub const ") else { continue };
+        if let (Some((name, _)), Some(brief)) = (declaration.split_once(':'), brief(comment)) {
+            briefs.insert(name.to_string(), brief);
+        }
+    }
+    let mut entries = String::new();
+    let mut lines = source.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        let Some(name) = line.strip_prefix("/// See ").and_then(|rest| rest.strip_suffix('.')) else { continue };
+        let field = lines.next().and_then(|next| next.strip_prefix("pub ")).and_then(|next| next.split_once(':'));
+        if let (Some((key, _)), Some(brief)) = (field, briefs.get(name)) {
+            entries.push_str(&format!("    ({:?}, {:?}),\n", key, brief));
+        }
+    }
+    let code = format!(
+        "/// Description of each config key, from the doc comment of its default (generated by build.rs).\n\
+         const CONFIG_KEY_DOCS: &[(&str, &str)] = &[\n{}];\n",
+        entries
+    );
+    std::fs::write(out_dir.join("config_docs.rs"), code).expect("OUT_DIR is writable");
+}
+
+/**
+ * @brief Write include/rust_sample_project.h from src/ffi.rs.
+ */
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    let crate_dir = std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo"));
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).expect("cbindgen.toml is valid");
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src/ffi.rs"))
+        .generate()
+        .expect("the C header can be generated from src/ffi.rs")
+        .write_to_file(crate_dir.join("include/rust_sample_project.h"));
+}
+
+/**
+ * @brief Generate the gRPC code from proto/item_processing.proto into OUT_DIR.
+ */
+#[cfg(feature = "grpc")]
+fn compile_protos() {
+    println!("cargo:rerun-if-changed=proto/item_processing.proto");
+    let descriptors = protox::compile(["item_processing.proto"], ["proto"]).expect("proto/item_processing.proto is valid");
+    tonic_prost_build::configure()
+        .build_client(false)
+        .compile_fds(descriptors)
+        .expect("the gRPC code can be generated from proto/item_processing.proto");
+}
+
+// End of rust_sample_project/build.rs
@@ -0,0 +1,159 @@
+/* C API of rust_sample_project: link against librust_sample_project (built with --features ffi). */
+
+#ifndef RUST_SAMPLE_PROJECT_H
+#define RUST_SAMPLE_PROJECT_H
+
+/* Generated by build.rs from src/ffi.rs with cbindgen; do not edit. */
+
+/*
+ * @enum SpStatus
+ * @brief Result codes of the C API; 0 to 5 and 130 are the binary's exit codes.
+ */
+typedef enum SpStatus {
+  /*
+   Success.
+   */
+  SP_STATUS_OK = 0,
+  /*
+   A pipeline hook vetoed the run.
+   */
+  SP_STATUS_ABORTED = 1,
+  /*
+   Invalid configuration.
+   */
+  SP_STATUS_CONFIG_ERROR = 2,
+  /*
+   The items could not be loaded.
+   */
+  SP_STATUS_LOAD_ERROR = 3,
+  /*
+   The processed items could not be saved.
+   */
+  SP_STATUS_SAVE_ERROR = 4,
+  /*
+   The run completed, but some items failed to process.
+   */
+  SP_STATUS_PARTIAL_PROCESSING = 5,
+  /*
+   A NULL handle or pointer, invalid UTF-8 or malformed JSON was passed in.
+   */
+  SP_STATUS_INVALID_ARGUMENT = 64,
+  /*
+   The call panicked; the handle may be left in any state.
+   */
+  SP_STATUS_PANIC = 70,
+  /*
+   The run was stopped by SIGINT/SIGTERM; what was done is saved.
+   */
+  SP_STATUS_INTERRUPTED = 130,
+} SpStatus;
+
+/*
+ * @struct SpPipeline
+ * @brief A pipeline handle, opaque to C.
+ */
+typedef struct SpPipeline SpPipeline;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ * @brief Create a pipeline handle.
+ *
+ * # Safety
+ *
+ * config_file and profile must each be NULL or a NUL-terminated string.
+ *
+ * @param config_file Config file to read, or NULL for the default lookup
+ *        (SAMPLE_PROJECT_CONFIG, then the default path).
+ * @param profile Config file profile to apply, or NULL for none (or
+ *        SAMPLE_PROJECT_PROFILE).
+ * @return SpPipeline* The handle, to be released with sp_pipeline_free, or
+ *         NULL if an argument is not valid UTF-8. Config file problems are
+ *         reported by sp_pipeline_run.
+ */
+struct SpPipeline *sp_pipeline_new(const char *config_file, const char *profile);
+
+/*
+ * @brief Set configuration values of a handle.
+ *
+ * Takes a JSON object of config keys to values, e.g.
+ * {"threshold": 150, "custom_stages": ["normalize"]}: strings, numbers and
+ * booleans for single values, arrays for list keys. Either all values are
+ * set or, if any is invalid, none is.
+ *
+ * # Safety
+ *
+ * pipeline must be a handle from sp_pipeline_new or NULL, and json a
+ * NUL-terminated string or NULL.
+ *
+ * @param pipeline The handle.
+ * @param json The values.
+ * @return SpStatus SP_STATUS_OK, SP_STATUS_CONFIG_ERROR for unknown keys or
+ *         invalid values, or SP_STATUS_INVALID_ARGUMENT.
+ */
+enum SpStatus sp_pipeline_configure(struct SpPipeline *pipeline, const char *json);
+
+/*
+ * @brief Run the pipeline once with the configuration of a handle.
+ *
+ * Blocks until the run is over. The configuration is validated first; an
+ * invalid one fails the run with SP_STATUS_CONFIG_ERROR.
+ *
+ * # Safety
+ *
+ * pipeline must be a handle from sp_pipeline_new or NULL, and result_json a
+ * valid pointer or NULL.
+ *
+ * @param pipeline The handle.
+ * @param result_json If not NULL, receives the run result as JSON (the
+ *        binary's --output json document), to be released with
+ *        sp_string_free; also on failure, except for SP_STATUS_INVALID_ARGUMENT
+ *        and SP_STATUS_PANIC, where it receives NULL.
+ * @return SpStatus How the run ended.
+ */
+enum SpStatus sp_pipeline_run(struct SpPipeline *pipeline, char **result_json);
+
+/*
+ * @brief Return the message of the last failed call on a handle.
+ *
+ * # Safety
+ *
+ * pipeline must be a handle from sp_pipeline_new or NULL.
+ *
+ * @param pipeline The handle.
+ * @return const char* The message, owned by the handle and valid until the
+ *         next call on it, or NULL if the last call succeeded.
+ */
+const char *sp_pipeline_last_error(const struct SpPipeline *pipeline);
+
+/*
+ * @brief Release a pipeline handle.
+ *
+ * # Safety
+ *
+ * pipeline must be a handle from sp_pipeline_new or NULL, and not be used
+ * afterwards.
+ *
+ * @param pipeline The handle; NULL is ignored.
+ */
+void sp_pipeline_free(struct SpPipeline *pipeline);
+
+/*
+ * @brief Release a string returned by the API.
+ *
+ * # Safety
+ *
+ * string must be a string from sp_pipeline_run or NULL, and not be used
+ * afterwards.
+ *
+ * @param string The string; NULL is ignored.
+ */
+void sp_string_free(char *string);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RUST_SAMPLE_PROJECT_H */
@@ -0,0 +1,268 @@
+// rust_sample_project/src/aggregate.rs
+
+// Grouped summaries of the saved items (see the aggregate_by, aggregate_path
+// and aggregate_format config keys). Each grouping in aggregate_by splits
+// the items into groups:
+//
+//     tag                  one group per tag (an item with several tags is in
+//                          each of their groups, one without tags in "-")
+//     name-prefix:<n>      the first n characters of the name
+//     value-bucket:<width> values in [k * width, (k + 1) * width)
+//
+// and the summary file gets one row per group with its count, sum and
+// average value, as JSON or CSV. Sums are exact (see money.rs); averages are
+// rounded to value_decimals.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::item::Item;
+use crate::money::Money;
+use crate::storage;
+
+/**
+ * @enum Grouping
+ * @brief How items are split into groups.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Grouping {
+    /// By tag ("tag").
+    Tag,
+    /// By the first characters of the name ("name-prefix:<n>").
+    NamePrefix(usize),
+    /// By value range of the given width ("value-bucket:<width>").
+    ValueBucket(f64),
+}
+
+impl Grouping {
+    /**
+     * @brief Parse a grouping as used in config.
+     *
+     * @param spec "tag", "name-prefix:<n>" or "value-bucket:<width>".
+     * @return Result<Grouping, String> The grouping, or an error message.
+     */
+    pub fn parse(spec: &str) -> Result<Grouping, String> {
+        let (name, argument) = match spec.split_once(':') {
+            Some((name, argument)) => (name.trim(), Some(argument.trim())),
+            None => (spec.trim(), None),
+        };
+        match (name, argument) {
+            ("tag", None) => Ok(Grouping::Tag),
+            ("name-prefix", Some(length)) => match length.parse::<usize>() {
+                Ok(length) if length > 0 => Ok(Grouping::NamePrefix(length)),
+                _ => Err(format!("Invalid prefix length in '{}' (expected a positive integer)", spec)),
+            },
+            ("value-bucket", Some(width)) => match width.parse::<f64>() {
+                Ok(width) if width.is_finite() && width > 0.0 => Ok(Grouping::ValueBucket(width)),
+                _ => Err(format!("Invalid bucket width in '{}' (expected a positive number)", spec)),
+            },
+            _ => Err(format!(
+                "Unknown grouping '{}' (expected 'tag', 'name-prefix:<n>' or 'value-bucket:<width>')",
+                spec
+            )),
+        }
+    }
+
+    /**
+     * @brief Return the name of the grouping, as written in summaries.
+     */
+    fn name(&self) -> String {
+        match self {
+            Grouping::Tag => String::from("tag"),
+            Grouping::NamePrefix(length) => format!("name-prefix:{}", length),
+            Grouping::ValueBucket(width) => format!("value-bucket:{}", width),
+        }
+    }
+
+    /**
+     * @brief Return the keys of the groups an item belongs to.
+     */
+    fn keys(&self, item: &Item) -> Vec<GroupKey> {
+        match self {
+            Grouping::Tag if item.tags.is_empty() => vec![GroupKey::Text(String::from("-"))],
+            Grouping::Tag => item.tags.iter().map(|tag| GroupKey::Text(tag.clone())).collect(),
+            Grouping::NamePrefix(length) => vec![GroupKey::Text(item.name.chars().take(*length).collect())],
+            Grouping::ValueBucket(width) => vec![GroupKey::Bucket((item.value.to_f64() / width).floor() as i64)],
+        }
+    }
+}
+
+/**
+ * @brief Key of a group, ordering value buckets numerically.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum GroupKey {
+    Text(String),
+    Bucket(i64),
+}
+
+/**
+ * @enum SummaryFormat
+ * @brief Format of the summary file.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryFormat {
+    /// A JSON document with a "groups" array.
+    Json,
+    /// Comma-separated values with a header row.
+    Csv,
+}
+
+impl SummaryFormat {
+    /**
+     * @brief Parse a format name as used in config.
+     *
+     * @param name "auto" (by the extension of path, JSON unless ".csv"), "json" or "csv".
+     * @param path Path of the summary file.
+     * @return Result<SummaryFormat, String> The format, or an error message for unknown names.
+     */
+    pub fn parse(name: &str, path: &Path) -> Result<SummaryFormat, String> {
+        match name {
+            "auto" if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("csv")) => {
+                Ok(SummaryFormat::Csv)
+            }
+            "auto" | "json" => Ok(SummaryFormat::Json),
+            "csv" => Ok(SummaryFormat::Csv),
+            other => Err(format!("Unknown summary format '{}' (expected 'auto', 'json' or 'csv')", other)),
+        }
+    }
+}
+
+/**
+ * @struct GroupSummary
+ * @brief Count, sum and average value of one group.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GroupSummary {
+    /// The grouping, as in config.
+    pub grouping: String,
+    /// The group: a tag, a name prefix or a value range.
+    pub group: String,
+    /// Number of items in the group.
+    pub count: usize,
+    /// Sum of their values.
+    pub sum: Money,
+    /// Average of their values.
+    pub avg: Money,
+}
+
+/**
+ * @brief Layout of a JSON summary file.
+ */
+#[derive(Serialize)]
+struct SummaryFile<'a> {
+    groups: &'a [GroupSummary],
+}
+
+/**
+ * @brief Summarize items by each grouping.
+ *
+ * @param items The items to summarize.
+ * @param groupings The groupings, in the order their groups are listed.
+ * @param decimals Decimal places the sums and averages are rounded to (value_decimals).
+ * @return Result<Vec<GroupSummary>, String> One row per non-empty group;
+ *         within a grouping, groups are sorted by key (value buckets from low
+ *         to high). An error message if a sum is out of range.
+ */
+pub fn summarize(items: &[Item], groupings: &[Grouping], decimals: u32) -> Result<Vec<GroupSummary>, String> {
+    let mut summaries = Vec::new();
+    for grouping in groupings {
+        let mut groups: BTreeMap<GroupKey, (usize, Money)> = BTreeMap::new();
+        for item in items {
+            for key in grouping.keys(item) {
+                let (count, sum) = groups.entry(key).or_insert((0, Money::from_int(0)));
+                *count += 1;
+                *sum = sum
+                    .checked_add(item.value)
+                    .ok_or_else(|| format!("A sum of the {} groups is out of range", grouping.name()))?;
+            }
+        }
+        for (key, (count, sum)) in groups {
+            let group = match key {
+                GroupKey::Text(text) => text,
+                GroupKey::Bucket(index) => match grouping {
+                    Grouping::ValueBucket(width) => {
+                        format!("{}..{}", index as f64 * width, (index + 1) as f64 * width)
+                    }
+                    _ => index.to_string(),
+                },
+            };
+            let out_of_range = || format!("Sum of group '{}' of {} is out of range", group, grouping.name());
+            let avg = sum.checked_div(count as i64, decimals).ok_or_else(out_of_range)?;
+            let sum = sum.round_to(decimals).map_err(|_| out_of_range())?;
+            summaries.push(GroupSummary {
+                grouping: grouping.name(),
+                group,
+                count,
+                sum,
+                avg,
+            });
+        }
+    }
+    Ok(summaries)
+}
+
+/**
+ * @brief Serialize summaries in the given format.
+ *
+ * @param summaries The summaries, see summarize.
+ * @param format JSON or CSV.
+ * @return Result<Vec<u8>, String> The document, or an error message.
+ */
+pub fn encode(summaries: &[GroupSummary], format: SummaryFormat) -> Result<Vec<u8>, String> {
+    match format {
+        SummaryFormat::Json => serde_json::to_vec_pretty(&SummaryFile { groups: summaries })
+            .map_err(|e| format!("Failed to serialize summary: {}", e)),
+        SummaryFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for summary in summaries {
+                writer
+                    .serialize(summary)
+                    .map_err(|e| format!("Failed to serialize summary: {}", e))?;
+            }
+            writer
+                .into_inner()
+                .map_err(|e| format!("Failed to serialize summary: {}", e))
+        }
+    }
+}
+
+/**
+ * @brief Write summaries to the summary file.
+ *
+ * @param path Path (or URL) of the summary file.
+ * @param summaries The summaries, see summarize.
+ * @param format JSON or CSV.
+ * @return Result<(), String> Ok, or an error message.
+ */
+pub fn save(path: &Path, summaries: &[GroupSummary], format: SummaryFormat) -> Result<(), String> {
+    let contents = encode(summaries, format)?;
+    storage::open(path)?
+        .write(&contents)
+        .map_err(|e| format!("Failed to write summary '{}': {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item_id::ItemId;
+
+    #[test]
+    fn sums_and_averages_are_exact_amounts() {
+        let items: Vec<Item> = ["0.10", "0.20", "0.05"]
+            .iter()
+            .enumerate()
+            .map(|(index, value)| Item::new(ItemId::from(index as i64 + 1), "Item".into(), Money::parse(value).unwrap()))
+            .collect();
+
+        let summaries = summarize(&items, &[Grouping::Tag], 2).unwrap();
+
+        assert_eq!((summaries[0].sum.to_string(), summaries[0].avg.to_string()), ("0.35".into(), "0.12".into()));
+        let json = String::from_utf8(encode(&summaries, SummaryFormat::Json).unwrap()).unwrap();
+        assert!(json.contains("\"sum\": \"0.35\""));
+    }
+}
+
+// End of rust_sample_project/src/aggregate.rs
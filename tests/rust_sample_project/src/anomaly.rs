@@ -0,0 +1,183 @@
+// rust_sample_project/src/anomaly.rs
+
+// Anomaly detection (see the anomaly_detection, anomaly_factor and
+// anomaly_review_path config keys). Right after loading, the values of all
+// loaded items are summarised, either by mean and standard deviation
+// ("stddev") or by quartiles ("iqr"), and items whose value lies further out
+// than anomaly_factor times the spread are tagged 'anomaly'. Those items are
+// not processed; they are saved unchanged to the review sink, ahead of any
+// configured output route, for someone to look at.
+
+use serde::Serialize;
+
+use crate::item::Item;
+
+/// Tag attached to the items found to be outliers.
+pub const ANOMALY_TAG: &str = "anomaly";
+
+/**
+ * @enum AnomalyMethod
+ * @brief How the range of normal values is derived from the loaded values.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnomalyMethod {
+    /// No anomaly detection ("off").
+    Off,
+    /// Mean ± factor × standard deviation ("stddev").
+    StdDev,
+    /// Quartiles extended by factor × interquartile range ("iqr").
+    Iqr,
+}
+
+impl AnomalyMethod {
+    /**
+     * @brief Parse a method name as used in config.
+     *
+     * @param name One of "off", "stddev" or "iqr".
+     * @return Result<AnomalyMethod, String> The method, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<AnomalyMethod, String> {
+        match name {
+            "off" => Ok(AnomalyMethod::Off),
+            "stddev" => Ok(AnomalyMethod::StdDev),
+            "iqr" => Ok(AnomalyMethod::Iqr),
+            other => Err(format!("Unknown anomaly detection '{}' (expected 'off', 'stddev' or 'iqr')", other)),
+        }
+    }
+}
+
+/**
+ * @struct AnomalyStats
+ * @brief What anomaly detection found, for the run report.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AnomalyStats {
+    /// The method the range of normal values was derived with.
+    pub method: AnomalyMethod,
+    /// Lowest normal value.
+    pub lower: f64,
+    /// Highest normal value.
+    pub upper: f64,
+    /// Number of items outside the range, sent to the review sink.
+    pub items: usize,
+}
+
+/**
+ * @struct Anomalies
+ * @brief The items set aside as outliers, to be saved unprocessed.
+ */
+#[derive(Debug)]
+pub struct Anomalies {
+    /// The outliers, with their positions in the input.
+    set_aside: Vec<(usize, Item)>,
+    /// What detection found.
+    pub stats: AnomalyStats,
+}
+
+/**
+ * @brief Return the range of normal values.
+ *
+ * @param method How the range is derived; Off has none.
+ * @param values The loaded values.
+ * @param factor How many standard deviations (or interquartile ranges) a
+ *        value may lie outside the mean (or the quartiles).
+ * @return Option<(f64, f64)> The lowest and highest normal value, or None
+ *         with fewer than two values.
+ */
+pub fn normal_range(method: AnomalyMethod, values: &[f64], factor: f64) -> Option<(f64, f64)> {
+    if values.len() < 2 {
+        return None;
+    }
+    match method {
+        AnomalyMethod::Off => None,
+        AnomalyMethod::StdDev => {
+            let count = values.len() as f64;
+            let mean = values.iter().sum::<f64>() / count;
+            let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / count;
+            let spread = factor * variance.sqrt();
+            Some((mean - spread, mean + spread))
+        }
+        AnomalyMethod::Iqr => {
+            let mut sorted = values.to_vec();
+            sorted.sort_by(f64::total_cmp);
+            let (q1, q3) = (quantile(&sorted, 0.25), quantile(&sorted, 0.75));
+            let spread = factor * (q3 - q1);
+            Some((q1 - spread, q3 + spread))
+        }
+    }
+}
+
+/**
+ * @brief Linearly interpolated quantile of sorted values (at least one).
+ */
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+/**
+ * @brief Tag the outliers among the loaded items and set them aside.
+ *
+ * @param items The loaded items, in input order.
+ * @param method How the range of normal values is derived.
+ * @param factor See normal_range.
+ * @return (Vec<Item>, Option<Anomalies>) The items to process, in input
+ *         order, and the outliers set aside (None if detection is off or
+ *         there are too few items).
+ */
+pub fn detect(items: Vec<Item>, method: AnomalyMethod, factor: f64) -> (Vec<Item>, Option<Anomalies>) {
+    let values: Vec<f64> = items.iter().map(|item| item.value.to_f64()).collect();
+    let Some((lower, upper)) = normal_range(method, &values, factor) else {
+        return (items, None);
+    };
+    let mut normal = Vec::with_capacity(items.len());
+    let mut set_aside = Vec::new();
+    for (position, mut item) in items.into_iter().enumerate() {
+        let value = values[position];
+        if (lower..=upper).contains(&value) {
+            normal.push(item);
+        } else {
+            log_debug!(
+                "anomaly:detect",
+                "Item {} has value {} outside {:.2}..{:.2}, sending it to review.",
+                item.item_id,
+                item.value,
+                lower,
+                upper
+            );
+            item.add_tag(ANOMALY_TAG);
+            set_aside.push((position, item));
+        }
+    }
+    let stats = AnomalyStats {
+        method,
+        lower,
+        upper,
+        items: set_aside.len(),
+    };
+    (normal, Some(Anomalies { set_aside, stats }))
+}
+
+impl Anomalies {
+    /**
+     * @brief Put the outliers back among the processed items.
+     *
+     * @param processed The other items after processing, in input order.
+     * @return Vec<Item> All items, in input order.
+     */
+    pub fn merge(self, processed: Vec<Item>) -> Vec<Item> {
+        let mut merged = Vec::with_capacity(processed.len() + self.set_aside.len());
+        let mut processed = processed.into_iter();
+        for (position, item) in self.set_aside {
+            merged.extend(processed.by_ref().take(position - merged.len()));
+            merged.push(item);
+        }
+        merged.extend(processed);
+        merged
+    }
+}
+
+// End of rust_sample_project/src/anomaly.rs
@@ -0,0 +1,209 @@
+// rust_sample_project/src/audit.rs
+
+// Audit trail of the changes the pipeline makes to items. Every change
+// (value adjusted, tag added, marked processed, enrichment fields set) is
+// appended to a JSON Lines file, one event per line, with the item id, the
+// old and new value, the rule that made the change and when. The file is only
+// ever opened for appending; earlier entries are never rewritten. The events
+// are part of the processing core; the file (AuditLog) needs the `native`
+// feature. read_item_events reads an item's events back, for the lineage
+// subcommand.
+
+use std::fmt;
+#[cfg(feature = "native")]
+use std::fs::{File, OpenOptions};
+#[cfg(feature = "native")]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(feature = "native")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "native")]
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::item_id::ItemId;
+#[cfg(feature = "native")]
+use crate::storage;
+
+/**
+ * @enum AuditChange
+ * @brief The kind of change recorded by an audit event.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditChange {
+    /// The value was changed by a transformation.
+    ValueAdjusted,
+    /// A tag was added.
+    TagAdded,
+    /// A tag was removed.
+    TagRemoved,
+    /// The item was marked as processed.
+    MarkedProcessed,
+    /// The category was set from the enrichment lookup table.
+    CategorySet,
+    /// The supplier was set from the enrichment lookup table.
+    SupplierSet,
+}
+
+impl fmt::Display for AuditChange {
+    /**
+     * @brief Format the change as in the audit log, e.g. "value_adjusted".
+     */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuditChange::ValueAdjusted => "value_adjusted",
+            AuditChange::TagAdded => "tag_added",
+            AuditChange::TagRemoved => "tag_removed",
+            AuditChange::MarkedProcessed => "marked_processed",
+            AuditChange::CategorySet => "category_set",
+            AuditChange::SupplierSet => "supplier_set",
+        };
+        f.write_str(name)
+    }
+}
+
+/**
+ * @struct AuditEvent
+ * @brief One change to one item, as written to the audit log.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    /// When the change was made.
+    pub timestamp: DateTime<Utc>,
+    /// The changed item.
+    pub item_id: ItemId,
+    /// What changed.
+    pub change: AuditChange,
+    /// The value before the change (null if there was none).
+    pub old_value: Value,
+    /// The value after the change.
+    pub new_value: Value,
+    /// The rule that made the change, e.g. a transformation as configured.
+    pub rule: String,
+}
+
+impl AuditEvent {
+    /**
+     * @brief Constructs an event for a change made now.
+     *
+     * @param item_id The changed item.
+     * @param change What changed.
+     * @param old_value The value before the change.
+     * @param new_value The value after the change.
+     * @param rule The rule that made the change.
+     * @return AuditEvent The event.
+     */
+    pub fn new(item_id: &ItemId, change: AuditChange, old_value: Value, new_value: Value, rule: &str) -> Self {
+        AuditEvent {
+            timestamp: Utc::now(),
+            item_id: item_id.clone(),
+            change,
+            old_value,
+            new_value,
+            rule: rule.to_string(),
+        }
+    }
+}
+
+/**
+ * @struct AuditLog
+ * @brief An append-only JSON Lines file of audit events, shared by worker threads.
+ */
+#[cfg(feature = "native")]
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+#[cfg(feature = "native")]
+impl AuditLog {
+    /**
+     * @brief Open the audit log for appending, creating it if needed.
+     *
+     * @param path Path of the audit file (parent directories are created).
+     * @return Result<AuditLog, String> The log, or an error message.
+     */
+    pub fn open(path: &Path) -> Result<Self, String> {
+        storage::create_parent_dir(path)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open audit log '{}': {}", path.display(), e))?;
+        log_info!("audit:open", "Recording item changes in {}.", path.display());
+        Ok(AuditLog {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+        })
+    }
+
+    /**
+     * @brief Append events to the log.
+     *
+     * The events are written in a single write, so the events of one item
+     * stay together when several worker threads record at once.
+     *
+     * @param events The events to append, in the order they happened.
+     * @return Result<(), String> Ok once the events are written, or an error message.
+     */
+    pub fn record(&self, events: &[AuditEvent]) -> Result<(), String> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut lines = String::new();
+        for event in events {
+            let line = serde_json::to_string(event)
+                .map_err(|e| format!("Failed to serialize audit event: {}", e))?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(lines.as_bytes())
+            .and_then(|()| file.flush())
+            .map_err(|e| format!("Failed to write audit log '{}': {}", self.path.display(), e))
+    }
+}
+
+/**
+ * @brief Read the events recorded for one item from an audit log.
+ *
+ * Lines that are not an audit event are skipped with a warning.
+ *
+ * @param path Path of the audit file.
+ * @param item_id The item.
+ * @return Result<Vec<AuditEvent>, String> The item's events in the order they
+ *         were recorded (none if the file does not exist), or an error message.
+ */
+#[cfg(feature = "native")]
+pub fn read_item_events(path: &Path, item_id: &ItemId) -> Result<Vec<AuditEvent>, String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to open audit log '{}': {}", path.display(), e)),
+    };
+    let mut events = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read audit log '{}': {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<AuditEvent>(&line) {
+            Ok(event) if event.item_id == *item_id => events.push(event),
+            Ok(_) => {}
+            Err(e) => log_warning!(
+                "audit:read_item_events",
+                "Skipping line {} of {}: {}",
+                index + 1,
+                path.display(),
+                e
+            ),
+        }
+    }
+    Ok(events)
+}
+
+// End of rust_sample_project/src/audit.rs
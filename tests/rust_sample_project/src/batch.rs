@@ -0,0 +1,326 @@
+// rust_sample_project/src/batch.rs
+
+// Batched saving for record-oriented sinks (REST endpoints, databases). Items
+// are collected into batches of up to batch_size items; a batch is flushed
+// to the store when it is full, or when flush_interval has passed since the
+// last flush and more items arrive, so a slow producer does not hold items
+// back indefinitely. If a batch fails, the error names the batch and the
+// items in it, and how many items were already stored by earlier batches.
+// With delivery tracking (see delivery.rs), items already in the delivery
+// ledger are left out, each batch carries an idempotency key, and stored
+// items are added to the ledger.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::delivery::{self, Delivery};
+use crate::item::Item;
+use crate::item_id::ItemId;
+use crate::storage::ItemStore;
+
+/// Turns the items of a batch into the bytes handed to the store.
+pub type BatchEncoder<'a> = dyn Fn(&[Item]) -> Result<Vec<u8>, String> + 'a;
+
+/**
+ * @struct BatchPolicy
+ * @brief When BatchWriter flushes a batch.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchPolicy {
+    /// Largest number of items per batch (at least 1).
+    pub batch_size: usize,
+    /// Flush a non-empty batch once this much time has passed since the last
+    /// flush (None = flush only when full, and at the end).
+    pub flush_interval: Option<Duration>,
+}
+
+impl BatchPolicy {
+    /**
+     * @brief Constructs a policy from the config values.
+     *
+     * @param batch_size Largest number of items per batch (0 is treated as 1).
+     * @param flush_interval_ms Milliseconds between time-based flushes (0 = none).
+     * @return BatchPolicy The policy.
+     */
+    pub fn new(batch_size: usize, flush_interval_ms: u64) -> Self {
+        BatchPolicy {
+            batch_size: batch_size.max(1),
+            flush_interval: (flush_interval_ms > 0).then(|| Duration::from_millis(flush_interval_ms)),
+        }
+    }
+}
+
+/**
+ * @struct SaveBatch
+ * @brief Which part of a save a batch covers.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveBatch {
+    /// Number of the batch within the save, starting at 1.
+    pub number: usize,
+    /// Position of the batch's first item within the save, starting at 0.
+    pub first_item: usize,
+    /// Number of items in the batch.
+    pub len: usize,
+    /// IDs of the batch's first and last item.
+    pub id_range: Option<(ItemId, ItemId)>,
+    /// Idempotency key of the batch, with delivery tracking (see delivery.rs).
+    pub idempotency_key: Option<Box<str>>,
+}
+
+impl fmt::Display for SaveBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "batch {} (items {}-{}",
+            self.number,
+            self.first_item + 1,
+            self.first_item + self.len
+        )?;
+        if let Some((first, last)) = &self.id_range {
+            write!(f, ", IDs {}..{}", first, last)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/**
+ * @struct BatchError
+ * @brief A batch that could not be stored.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchError {
+    /// The failed batch.
+    pub batch: SaveBatch,
+    /// Number of items stored by the batches before it.
+    pub items_written: usize,
+    /// Why storing the batch failed.
+    pub message: String,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Failed to store {} ({} items were stored by earlier batches): {}",
+            self.batch, self.items_written, self.message
+        )
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+/**
+ * @struct BatchWriter
+ * @brief Collects items and stores them in batches.
+ *
+ * After a failed batch the writer stops; further calls return the same error.
+ */
+pub struct BatchWriter<'a> {
+    store: &'a dyn ItemStore,
+    policy: BatchPolicy,
+    encode: &'a BatchEncoder<'a>,
+    /// Assigns idempotency keys and skips delivered items (None = no tracking).
+    delivery: Option<&'a Delivery>,
+    pending: Vec<Item>,
+    /// Idempotency keys of the pending items, with delivery tracking.
+    pending_keys: Vec<String>,
+    batches_written: usize,
+    items_written: usize,
+    /// Items left out because the ledger lists them as delivered.
+    items_skipped: usize,
+    last_flush: Instant,
+    failed: Option<BatchError>,
+}
+
+impl<'a> BatchWriter<'a> {
+    /**
+     * @brief Constructs a writer for a store that accepts batches.
+     *
+     * @param store The store (see ItemStore::accepts_batches).
+     * @param policy When batches are flushed.
+     * @param encode Turns the items of a batch into the stored bytes, e.g. a
+     *        data file document holding just those items.
+     * @return BatchWriter The writer, with nothing pending.
+     */
+    pub fn new(
+        store: &'a dyn ItemStore,
+        policy: BatchPolicy,
+        encode: &'a BatchEncoder<'a>,
+    ) -> Self {
+        BatchWriter {
+            store,
+            policy,
+            encode,
+            delivery: None,
+            pending: Vec::with_capacity(policy.batch_size),
+            pending_keys: Vec::new(),
+            batches_written: 0,
+            items_written: 0,
+            items_skipped: 0,
+            last_flush: Instant::now(),
+            failed: None,
+        }
+    }
+
+    /**
+     * @brief Track delivery: key the batches and skip items delivered before.
+     *
+     * @param delivery The run's delivery tracking.
+     * @return BatchWriter The writer.
+     */
+    pub fn with_delivery(mut self, delivery: &'a Delivery) -> Self {
+        self.delivery = Some(delivery);
+        self
+    }
+
+    /**
+     * @brief Add an item, flushing the pending batch if the policy says so.
+     *
+     * With delivery tracking, an item whose idempotency key is in the
+     * ledger is left out.
+     *
+     * @param item The item to store.
+     * @return Result<(), BatchError> Ok, or the batch that failed.
+     */
+    pub fn push(&mut self, item: Item) -> Result<(), BatchError> {
+        if let Some(error) = &self.failed {
+            return Err(error.clone());
+        }
+        if let Some(delivery) = self.delivery {
+            let key = delivery
+                .key(&item)
+                .and_then(|key| delivery.is_delivered(&key).map(|delivered| (key, delivered)));
+            match key {
+                Ok((_, true)) => {
+                    log_debug!(
+                        "batch:push",
+                        "Item {} was already delivered to {}, skipping it.",
+                        item.item_id,
+                        self.store.location()
+                    );
+                    self.items_skipped += 1;
+                    return Ok(());
+                }
+                Ok((key, false)) => self.pending_keys.push(key),
+                Err(message) => return Err(self.fail_pending(message)),
+            }
+        }
+        self.pending.push(item);
+        let interval_elapsed = self
+            .policy
+            .flush_interval
+            .is_some_and(|interval| self.last_flush.elapsed() >= interval);
+        if self.pending.len() >= self.policy.batch_size || interval_elapsed {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /**
+     * @brief Store the pending items as one batch, if there are any.
+     *
+     * @return Result<(), BatchError> Ok, or the batch that failed.
+     */
+    pub fn flush(&mut self) -> Result<(), BatchError> {
+        if let Some(error) = &self.failed {
+            return Err(error.clone());
+        }
+        self.last_flush = Instant::now();
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let batch = self.pending_batch();
+        log_debug!("batch:flush", "Storing {} in {}...", batch, self.store.location());
+        let result = (self.encode)(&self.pending)
+            .and_then(|contents| self.store.write_batch(&batch, &contents));
+        if let Err(message) = result {
+            return Err(self.fail(batch, message));
+        }
+
+        self.batches_written += 1;
+        self.items_written += self.pending.len();
+        if let Some(delivery) = self.delivery {
+            let delivered: Vec<(String, ItemId)> = self
+                .pending_keys
+                .drain(..)
+                .zip(self.pending.iter().map(|item| item.item_id.clone()))
+                .collect();
+            if let Err(message) = delivery.record(self.store.location(), &delivered) {
+                let message = format!("the batch was stored, but not recorded as delivered: {}", message);
+                return Err(self.fail(batch, message));
+            }
+        }
+        self.pending.clear();
+        Ok(())
+    }
+
+    /**
+     * @brief Stop the writer after a failed batch.
+     *
+     * @param batch The failed batch.
+     * @param message Why it failed.
+     * @return BatchError The error, also returned by every further call.
+     */
+    fn fail(&mut self, batch: SaveBatch, message: String) -> BatchError {
+        let error = BatchError {
+            batch,
+            items_written: self.items_written,
+            message,
+        };
+        self.failed = Some(error.clone());
+        error
+    }
+
+    /**
+     * @brief Stop the writer because the pending batch cannot be completed.
+     *
+     * @param message Why.
+     * @return BatchError The error, naming the batch that was being collected.
+     */
+    fn fail_pending(&mut self, message: String) -> BatchError {
+        let batch = self.pending_batch();
+        self.fail(batch, message)
+    }
+
+    /**
+     * @brief Describe the pending items as the next batch.
+     *
+     * @return SaveBatch The batch.
+     */
+    fn pending_batch(&self) -> SaveBatch {
+        SaveBatch {
+            number: self.batches_written + 1,
+            first_item: self.items_written,
+            len: self.pending.len(),
+            id_range: self
+                .pending
+                .first()
+                .zip(self.pending.last())
+                .map(|(first, last)| (first.item_id.clone(), last.item_id.clone())),
+            idempotency_key: self.delivery.map(|_| delivery::batch_key(&self.pending_keys).into()),
+        }
+    }
+
+    /**
+     * @brief Flush the remaining items and end the save.
+     *
+     * @return Result<usize, BatchError> The number of batches written, or the
+     *         batch that failed.
+     */
+    pub fn finish(mut self) -> Result<usize, BatchError> {
+        self.flush()?;
+        if self.items_skipped > 0 {
+            log_info!(
+                "batch:finish",
+                "Left out {} items already delivered to {}.",
+                self.items_skipped,
+                self.store.location()
+            );
+        }
+        Ok(self.batches_written)
+    }
+}
+
+// End of rust_sample_project/src/batch.rs
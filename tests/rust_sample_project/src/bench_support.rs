@@ -0,0 +1,47 @@
+// rust_sample_project/src/bench_support.rs
+
+// Synthetic datasets for the benchmarks in benches/ (enabled by the `bench`
+// feature). The data is generated from a seed, so every run measures the same
+// input. The items come from the datagen module.
+
+use std::path::PathBuf;
+
+use crate::data_handler::DataHandler;
+use crate::datagen::{self, DatagenOptions, ValueDistribution};
+use crate::item::Item;
+
+/// Dataset sizes the benchmarks are run with.
+pub const DATASET_SIZES: [usize; 3] = [10_000, 100_000, 1_000_000];
+
+/**
+ * @brief Generate a dataset of synthetic items.
+ *
+ * @param count Number of items.
+ * @param seed Seed of the generator.
+ * @return Vec<Item> The items, with IDs 1..=count.
+ */
+pub fn synthetic_items(count: usize, seed: u64) -> Vec<Item> {
+    // Values between 0 and 200, so roughly half of the items exceed the default threshold.
+    datagen::generate_items(&DatagenOptions {
+        count,
+        seed: Some(seed),
+        distribution: ValueDistribution::Uniform { min: 0.0, max: 200.0 },
+        name_pattern: String::from("Item {id}"),
+        invalid_ratio: 0.0,
+    })
+}
+
+/**
+ * @brief Write a synthetic dataset to a data file in the temp directory.
+ *
+ * @param count Number of items.
+ * @param seed Seed of the generator.
+ * @return Result<PathBuf, String> The path of the written file, or an error message.
+ */
+pub fn write_synthetic_data_file(count: usize, seed: u64) -> Result<PathBuf, String> {
+    let path = std::env::temp_dir().join(format!("rust_sample_project_bench_{}_{}.json", count, seed));
+    DataHandler::new(&path).save_items_to(&path, &synthetic_items(count, seed))?;
+    Ok(path)
+}
+
+// End of rust_sample_project/src/bench_support.rs
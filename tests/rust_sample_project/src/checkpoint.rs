@@ -0,0 +1,116 @@
+// rust_sample_project/src/checkpoint.rs
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::item_id::ItemId;
+use crate::storage;
+
+/**
+ * @struct Checkpoint
+ * @brief Records how far an interrupted pipeline run got.
+ *
+ * The checkpoint is written periodically while items are processed, so a
+ * crashed or interrupted run can be resumed with `--resume` instead of
+ * reprocessing everything from the beginning. The resumed run takes over
+ * the interrupted run's id, so items it delivers again get the same
+ * idempotency keys (see delivery.rs) and are not sent twice.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The data source the checkpoint belongs to.
+    pub data_source_path: PathBuf,
+    /// The interrupted run; empty in checkpoints written before it was recorded.
+    #[serde(default)]
+    pub run_id: String,
+    /// IDs of the items the run finished, independent of the processing order.
+    pub completed: Vec<ItemId>,
+}
+
+impl Checkpoint {
+    /**
+     * @brief Constructs a new Checkpoint object.
+     *
+     * @param data_source_path The data source the progress refers to.
+     * @param run_id The run that made the progress.
+     * @param completed IDs of the items already processed.
+     * @return Checkpoint A new Checkpoint instance.
+     */
+    pub fn new(data_source_path: PathBuf, run_id: &str, completed: Vec<ItemId>) -> Self {
+        Checkpoint {
+            data_source_path,
+            run_id: run_id.to_string(),
+            completed,
+        }
+    }
+
+    /**
+     * @brief Load a checkpoint from the given file, if one exists.
+     *
+     * @param path Path to the checkpoint file.
+     * @return Result<Option<Checkpoint>, String> The stored checkpoint, None if
+     *         there is no checkpoint file, or an error message if it is unreadable.
+     */
+    pub fn load(path: &Path) -> Result<Option<Checkpoint>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read checkpoint file '{}': {}", path.display(), e))?;
+        let checkpoint: Checkpoint = serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse checkpoint file '{}': {}", path.display(), e))?;
+        log_info!(
+            "checkpoint:load",
+            "Loaded checkpoint from {} (completed items: {}).",
+            path.display(), checkpoint.completed.len()
+        );
+        Ok(Some(checkpoint))
+    }
+
+    /**
+     * @brief Persist the checkpoint to the given file.
+     *
+     * The file is written to a temporary path first and then renamed, so an
+     * interruption during the write never leaves a truncated checkpoint behind.
+     * Missing parent directories are created.
+     *
+     * @param path Path to the checkpoint file.
+     * @return Result<(), String> Ok on success, or an error message.
+     */
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize checkpoint: {}", e))?;
+        storage::create_parent_dir(path)?;
+        let tmp_path = storage::with_suffix(path, ".tmp");
+        fs::write(&tmp_path, json)
+            .map_err(|e| format!("Failed to write checkpoint file '{}': {}", tmp_path.display(), e))?;
+        fs::rename(&tmp_path, path).map_err(|e| {
+            format!("Failed to move checkpoint into place at '{}': {}", path.display(), e)
+        })?;
+        log_debug!(
+            "checkpoint:save",
+            "Checkpoint saved to {} (completed items: {}).",
+            path.display(), self.completed.len()
+        );
+        Ok(())
+    }
+
+    /**
+     * @brief Remove the checkpoint file after a run completed successfully.
+     *
+     * @param path Path to the checkpoint file.
+     * @return Result<(), String> Ok if the file was removed or did not exist.
+     */
+    pub fn clear(path: &Path) -> Result<(), String> {
+        if path.exists() {
+            fs::remove_file(path)
+                .map_err(|e| format!("Failed to remove checkpoint file '{}': {}", path.display(), e))?;
+            log_info!("checkpoint:clear", "Cleared checkpoint file {}.", path.display());
+        }
+        Ok(())
+    }
+}
+
+// End of rust_sample_project/src/checkpoint.rs
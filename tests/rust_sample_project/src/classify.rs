@@ -0,0 +1,246 @@
+// rust_sample_project/src/classify.rs
+
+// Vectorized over/under threshold classification, for callers that only need
+// to know which items are over their threshold (see
+// ItemProcessor::classify_items), not to process them. The values and
+// thresholds are amounts (see money.rs); they are brought to their largest
+// number of decimal places, taken into contiguous i64 columns of minor units
+// and compared four at a time with AVX2 where the CPU has it. Other CPUs and
+// targets, and columns too large for i64 minor units at that scale, use the
+// scalar loop over the amounts. Both compare exactly, as
+// ThresholdRules::threshold_tag does, so they classify every item the way
+// processing tags it; debug builds check the vectorized result against the
+// scalar one.
+
+use crate::money::Money;
+
+/**
+ * @enum Thresholds
+ * @brief The thresholds to compare a column of values with.
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum Thresholds<'a> {
+    /// The same threshold for every value.
+    Uniform(Money),
+    /// One threshold per value, at the same index.
+    PerValue(&'a [Money]),
+}
+
+impl Thresholds<'_> {
+    /**
+     * @brief Return the threshold of the value at an index.
+     */
+    fn at(&self, index: usize) -> Money {
+        match self {
+            Thresholds::Uniform(threshold) => *threshold,
+            Thresholds::PerValue(thresholds) => thresholds[index],
+        }
+    }
+}
+
+/**
+ * @enum MinorUnits
+ * @brief The thresholds in minor units at the scale of the value column.
+ */
+#[cfg_attr(not(target_arch = "x86_64"), allow(dead_code))]
+enum MinorUnits {
+    Uniform(i64),
+    PerValue(Vec<i64>),
+}
+
+/**
+ * @brief Classify a column of values against their thresholds.
+ *
+ * @param values The values.
+ * @param thresholds The thresholds; per-value thresholds must be as many as the values.
+ * @return Result<Vec<bool>, String> For each value, true if it is over its
+ *         threshold, or an error message if the column lengths differ.
+ */
+pub fn classify_values(values: &[Money], thresholds: Thresholds) -> Result<Vec<bool>, String> {
+    check_lengths(values, thresholds)?;
+
+    #[cfg(target_arch = "x86_64")]
+    if std::is_x86_feature_detected!("avx2") {
+        if let Some((value_units, threshold_units)) = minor_unit_columns(values, thresholds) {
+            let mut over = vec![false; values.len()];
+            // SAFETY: the CPU supports AVX2 (checked above), and the columns
+            // have the same length (checked by check_lengths).
+            unsafe { classify_avx2(&value_units, &threshold_units, &mut over) };
+            debug_assert_eq!(over, classify_values_scalar(values, thresholds)?);
+            return Ok(over);
+        }
+    }
+
+    classify_values_scalar(values, thresholds)
+}
+
+/**
+ * @brief Classify a column of values one value at a time.
+ *
+ * The reference for classify_values, and what it falls back to.
+ *
+ * @param values The values.
+ * @param thresholds The thresholds; per-value thresholds must be as many as the values.
+ * @return Result<Vec<bool>, String> For each value, true if it is over its
+ *         threshold, or an error message if the column lengths differ.
+ */
+pub fn classify_values_scalar(values: &[Money], thresholds: Thresholds) -> Result<Vec<bool>, String> {
+    check_lengths(values, thresholds)?;
+    Ok(values.iter().enumerate().map(|(index, value)| *value > thresholds.at(index)).collect())
+}
+
+/**
+ * @brief Reject per-value thresholds that do not line up with the values.
+ */
+fn check_lengths(values: &[Money], thresholds: Thresholds) -> Result<(), String> {
+    match thresholds {
+        Thresholds::PerValue(thresholds) if thresholds.len() != values.len() => Err(format!(
+            "Got {} thresholds for {} values",
+            thresholds.len(),
+            values.len()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/**
+ * @brief Take the values and thresholds into minor units at their largest scale.
+ *
+ * @return Option<(Vec<i64>, MinorUnits)> The columns, or None if an amount
+ *         does not fit in i64 minor units at that scale.
+ */
+#[cfg_attr(not(target_arch = "x86_64"), allow(dead_code))]
+fn minor_unit_columns(values: &[Money], thresholds: Thresholds) -> Option<(Vec<i64>, MinorUnits)> {
+    let threshold_scale = match thresholds {
+        Thresholds::Uniform(threshold) => threshold.scale(),
+        Thresholds::PerValue(thresholds) => thresholds.iter().map(|threshold| threshold.scale()).max().unwrap_or(0),
+    };
+    let scale = values.iter().map(|value| value.scale()).fold(threshold_scale, u32::max);
+    let units = |amount: &Money| amount.round_to(scale).ok().map(Money::minor_units);
+    let value_units = values.iter().map(units).collect::<Option<Vec<i64>>>()?;
+    let threshold_units = match thresholds {
+        Thresholds::Uniform(threshold) => MinorUnits::Uniform(units(&threshold)?),
+        Thresholds::PerValue(thresholds) => MinorUnits::PerValue(thresholds.iter().map(units).collect::<Option<_>>()?),
+    };
+    Some((value_units, threshold_units))
+}
+
+/**
+ * @brief Classify minor units four at a time with AVX2, and the rest one at a time.
+ *
+ * # Safety
+ *
+ * The CPU must support AVX2, and per-value thresholds must be as many as the values.
+ */
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn classify_avx2(values: &[i64], thresholds: &MinorUnits, over: &mut [bool]) {
+    use std::arch::x86_64::{
+        __m256i, _mm256_castsi256_pd, _mm256_cmpgt_epi64, _mm256_loadu_si256, _mm256_movemask_pd,
+        _mm256_set1_epi64x,
+    };
+
+    const LANES: usize = 4;
+    let vectorized = values.len() - values.len() % LANES;
+    for start in (0..vectorized).step_by(LANES) {
+        let value_lanes = _mm256_loadu_si256(values.as_ptr().add(start) as *const __m256i);
+        let threshold_lanes = match thresholds {
+            MinorUnits::Uniform(threshold) => _mm256_set1_epi64x(*threshold),
+            MinorUnits::PerValue(thresholds) => _mm256_loadu_si256(thresholds.as_ptr().add(start) as *const __m256i),
+        };
+        let mask = _mm256_movemask_pd(_mm256_castsi256_pd(_mm256_cmpgt_epi64(value_lanes, threshold_lanes)));
+        for lane in 0..LANES {
+            over[start + lane] = mask & (1 << lane) != 0;
+        }
+    }
+    for index in vectorized..values.len() {
+        let threshold = match thresholds {
+            MinorUnits::Uniform(threshold) => *threshold,
+            MinorUnits::PerValue(thresholds) => thresholds[index],
+        };
+        over[index] = values[index] > threshold;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item_core::{ThresholdRules, TAG_OVER_THRESHOLD};
+
+    /// Classify with both implementations and check that they agree.
+    fn classified(values: &[Money], thresholds: Thresholds) -> Vec<bool> {
+        let over = classify_values(values, thresholds).unwrap();
+        assert_eq!(over, classify_values_scalar(values, thresholds).unwrap(), "values {:?}", values);
+        over
+    }
+
+    fn amounts(texts: &[&str]) -> Vec<Money> {
+        texts.iter().map(|text| Money::parse(text).unwrap()).collect()
+    }
+
+    #[test]
+    fn classifies_columns_of_every_length_including_the_tail() {
+        let values: Vec<Money> = (0..11).map(|n| Money::from_int(n * 10)).collect();
+        let threshold = Money::parse("45.5").unwrap();
+        for len in 0..=values.len() {
+            let over = classified(&values[..len], Thresholds::Uniform(threshold));
+            let expected: Vec<bool> = values[..len].iter().map(|value| *value > threshold).collect();
+            assert_eq!(over, expected, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn a_value_equal_to_its_threshold_is_not_over() {
+        let values = amounts(&["100.00", "100", "100.01", "99.99", "100.000"]);
+        assert_eq!(classified(&values, Thresholds::Uniform(Money::from_int(100))), [false, false, true, false, false]);
+        let thresholds = amounts(&["100", "99", "100.01", "99.99", "0"]);
+        assert_eq!(classified(&values, Thresholds::PerValue(&thresholds)), [false, true, false, false, true]);
+    }
+
+    #[test]
+    fn amounts_are_compared_exactly_across_scales() {
+        let values = amounts(&["0.3", "0.300000001", "9007199254740993", "-0.000000001"]);
+        let thresholds = [
+            Money::from_f64_saturating(0.1 + 0.2),
+            Money::parse("0.3").unwrap(),
+            Money::from_minor(9_007_199_254_740_992, 0),
+            Money::from_int(0),
+        ];
+        assert_eq!(classified(&values, Thresholds::PerValue(&thresholds)), [false, true, true, false]);
+    }
+
+    #[test]
+    fn amounts_too_large_for_the_common_scale_are_still_classified() {
+        let values = [Money::from_minor(i64::MAX, 0), Money::parse("1.5").unwrap()];
+        assert!(minor_unit_columns(&values, Thresholds::Uniform(Money::parse("1.000000001").unwrap())).is_none());
+        assert_eq!(classified(&values, Thresholds::Uniform(Money::parse("1.000000001").unwrap())), [true, true]);
+        assert_eq!(classified(&values, Thresholds::Uniform(Money::from_f64_saturating(f64::NAN))), [false, false]);
+    }
+
+    #[test]
+    fn classification_agrees_with_the_threshold_tag() {
+        let values: Vec<Money> = (-20..20).map(|n| Money::from_minor(n * 7, 3)).collect();
+        for threshold in [-0.05, 0.0, 0.021, 0.07, 0.1 + 0.02] {
+            let threshold = Money::from_f64_saturating(threshold);
+            let expected: Vec<bool> = values
+                .iter()
+                .map(|value| ThresholdRules::threshold_tag(*value, threshold) == TAG_OVER_THRESHOLD)
+                .collect();
+            assert_eq!(classified(&values, Thresholds::Uniform(threshold)), expected, "threshold {}", threshold);
+        }
+    }
+
+    #[test]
+    fn per_value_thresholds_line_up_with_their_values() {
+        let values: Vec<Money> = (0..9).map(Money::from_int).collect();
+        let thresholds: Vec<Money> = (0..9).map(|n| Money::from_int(n % 3 * 3)).collect();
+        let expected: Vec<bool> = values.iter().zip(&thresholds).map(|(value, threshold)| value > threshold).collect();
+        assert_eq!(classified(&values, Thresholds::PerValue(&thresholds)), expected);
+        assert_eq!(
+            classify_values(&values, Thresholds::PerValue(&thresholds[..8])),
+            Err(String::from("Got 8 thresholds for 9 values"))
+        );
+    }
+}
+
+// End of rust_sample_project/src/classify.rs
@@ -0,0 +1,314 @@
+// rust_sample_project/src/completions.rs
+
+// Shell completion scripts for the `completions <shell>` subcommand. The
+// command line is described once as a Cli (subcommands, flags and what
+// values they take, see the CLI table in main.rs) and a script for bash,
+// zsh, fish or PowerShell is generated from that description:
+//
+//     rust_sample_project completions bash > /etc/bash_completion.d/rust_sample_project
+//     rust_sample_project completions zsh > "${fpath[1]}/_rust_sample_project"
+//     rust_sample_project completions fish > ~/.config/fish/completions/rust_sample_project.fish
+//     rust_sample_project completions powershell >> $PROFILE
+//
+// Like the command-line parser, the scripts accept every flag after every
+// subcommand; they offer the subcommands and flags, the words following
+// subcommands such as `config show`, choices of flags such as
+// `--log-format <text|json>`, and file names where paths are expected.
+
+use std::fmt::Write;
+
+/**
+ * @enum Shell
+ * @brief A shell completion scripts are generated for.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+impl Shell {
+    /// The names accepted by parse, e.g. to offer them as completions.
+    pub const NAMES: &'static [&'static str] = &["bash", "zsh", "fish", "powershell"];
+
+    /**
+     * @brief Parse a shell name ("bash", "zsh", "fish" or "powershell", case-insensitive).
+     *
+     * @param name The shell name from the command line.
+     * @return Result<Shell, String> The shell, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<Shell, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            "powershell" | "pwsh" => Ok(Shell::PowerShell),
+            other => Err(format!(
+                "Unknown shell '{}' (expected 'bash', 'zsh', 'fish' or 'powershell')",
+                other
+            )),
+        }
+    }
+}
+
+/**
+ * @enum Value
+ * @brief What follows a flag or subcommand on the command line.
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum Value {
+    /// Nothing, e.g. --force.
+    None,
+    /// A file or directory, completed with file names.
+    Path,
+    /// Free text such as a filter expression, not completed.
+    Text,
+    /// One of a few words, e.g. "text" or "json".
+    Choices(&'static [&'static str]),
+}
+
+/**
+ * @struct Arg
+ * @brief A subcommand or flag of the command line.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Arg {
+    /// The word as typed, e.g. "history" or "--log-format".
+    pub name: &'static str,
+    /// One-line description shown by shells that display one.
+    pub help: &'static str,
+    /// What follows it.
+    pub value: Value,
+}
+
+/**
+ * @struct Cli
+ * @brief The command line completions are generated for.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Cli {
+    /// The program name the completions are registered for.
+    pub program: &'static str,
+    /// The subcommands, e.g. "serve".
+    pub subcommands: &'static [Arg],
+    /// The flags, e.g. "--force"; every subcommand accepts every flag.
+    pub flags: &'static [Arg],
+    /// The usage message, one "program ..." line per form; the synopsis of the docs.
+    pub usage: &'static str,
+}
+
+impl Cli {
+    /**
+     * @brief Return the subcommands and flags followed by a value.
+     */
+    fn taking_values(&self) -> impl Iterator<Item = &Arg> {
+        self.subcommands.iter().chain(self.flags).filter(|arg| !matches!(arg.value, Value::None))
+    }
+
+    /**
+     * @brief Return the name of the shell function the completions are in,
+     *        e.g. "_rust_sample_project".
+     */
+    fn function_name(&self) -> String {
+        format!("_{}", self.program.replace('-', "_"))
+    }
+}
+
+/**
+ * @brief Generate the completion script of a command line for a shell.
+ *
+ * @param cli The command line.
+ * @param shell The shell.
+ * @return String The script, to be sourced by (or installed for) the shell.
+ */
+pub fn script(cli: &Cli, shell: Shell) -> String {
+    match shell {
+        Shell::Bash => bash(cli),
+        Shell::Zsh => zsh(cli),
+        Shell::Fish => fish(cli),
+        Shell::PowerShell => powershell(cli),
+    }
+}
+
+/**
+ * @brief Join the names of subcommands or flags with spaces.
+ */
+fn names<'a>(args: impl IntoIterator<Item = &'a Arg>) -> String {
+    args.into_iter().map(|arg| arg.name).collect::<Vec<_>>().join(" ")
+}
+
+/**
+ * @brief Generate the bash script: a function for `complete -F`.
+ */
+fn bash(cli: &Cli) -> String {
+    let function = cli.function_name();
+    let mut script = format!("# bash completion for {}\n\n{}() {{\n", cli.program, function);
+    script.push_str("    local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n");
+    script.push_str("    case \"$prev\" in\n");
+    for arg in cli.taking_values() {
+        let action = match arg.value {
+            Value::Path => String::from("COMPREPLY=($(compgen -f -- \"$cur\"))"),
+            Value::Choices(choices) => format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", choices.join(" ")),
+            Value::Text | Value::None => String::from("COMPREPLY=()"),
+        };
+        let _ = writeln!(script, "        {})\n            {}\n            return ;;", arg.name, action);
+    }
+    script.push_str("    esac\n");
+    let _ = writeln!(
+        script,
+        "    COMPREPLY=($(compgen -W \"{} {}\" -- \"$cur\"))",
+        names(cli.subcommands),
+        names(cli.flags)
+    );
+    let _ = write!(script, "}}\n\ncomplete -o default -F {} {}\n", function, cli.program);
+    script
+}
+
+/**
+ * @brief Quote text for a zsh _arguments spec or _describe entry.
+ */
+fn zsh_quote(text: &str) -> String {
+    text.replace('\'', "'\\''").replace('[', "\\[").replace(']', "\\]").replace(':', "\\:")
+}
+
+/**
+ * @brief Return the zsh action completing a value.
+ */
+fn zsh_action(value: Value) -> String {
+    match value {
+        Value::Path => String::from("_files"),
+        Value::Choices(choices) => format!("compadd -- {}", choices.join(" ")),
+        Value::Text | Value::None => String::from("_message value"),
+    }
+}
+
+/**
+ * @brief Generate the zsh script: a #compdef function using _arguments.
+ */
+fn zsh(cli: &Cli) -> String {
+    let function = cli.function_name();
+    let mut script = format!("#compdef {}\n\n{}() {{\n    local state line\n", cli.program, function);
+    script.push_str("    local -a subcommands\n    subcommands=(\n");
+    for subcommand in cli.subcommands {
+        let _ = writeln!(script, "        '{}:{}'", subcommand.name, zsh_quote(subcommand.help));
+    }
+    script.push_str("    )\n    _arguments -C \\\n");
+    for flag in cli.flags {
+        // "--flag=" takes the value in the same word after '=' or as the next word
+        let (equals, value) = match flag.value {
+            Value::None => ("", String::new()),
+            Value::Path => ("=", String::from(":path:_files")),
+            Value::Text => ("=", String::from(":value: ")),
+            Value::Choices(choices) => ("=", format!(":value:({})", choices.join(" "))),
+        };
+        let _ = writeln!(script, "        '*{}{}[{}]{}' \\", flag.name, equals, zsh_quote(flag.help), value);
+    }
+    script.push_str("        '*: :->words'\n");
+    script.push_str("    case $state in\n        words)\n            case $words[CURRENT-1] in\n");
+    for subcommand in cli.subcommands.iter().filter(|arg| !matches!(arg.value, Value::None)) {
+        let _ = writeln!(script, "                {}) {} ;;", subcommand.name, zsh_action(subcommand.value));
+    }
+    script.push_str("                *) _describe 'subcommand' subcommands ;;\n");
+    script.push_str("            esac ;;\n    esac\n}\n\n");
+    let _ = writeln!(script, "{} \"$@\"", function);
+    script
+}
+
+/**
+ * @brief Quote text for a single-quoted fish string.
+ */
+fn fish_quote(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+/**
+ * @brief Generate the fish script: one `complete` command per word.
+ */
+fn fish(cli: &Cli) -> String {
+    let program = cli.program;
+    let mut script = format!("# fish completion for {}\n\ncomplete -c {} -f\n", program, program);
+    let subcommands = names(cli.subcommands);
+    for subcommand in cli.subcommands {
+        let _ = writeln!(
+            script,
+            "complete -c {} -n 'not __fish_seen_subcommand_from {}' -a {} -d '{}'",
+            program,
+            subcommands,
+            subcommand.name,
+            fish_quote(subcommand.help)
+        );
+        let condition = format!("-n '__fish_seen_subcommand_from {}'", subcommand.name);
+        match subcommand.value {
+            Value::Path => {
+                let _ = writeln!(script, "complete -c {} {} -F", program, condition);
+            }
+            Value::Choices(choices) => {
+                let _ = writeln!(script, "complete -c {} {} -a '{}'", program, condition, choices.join(" "));
+            }
+            Value::Text | Value::None => {}
+        }
+    }
+    for flag in cli.flags {
+        let option = match flag.name.strip_prefix("--") {
+            Some(long) => format!("-l {}", long),
+            None if flag.name.len() == 2 => format!("-s {}", &flag.name[1..]),
+            None => format!("-o {}", &flag.name[1..]),
+        };
+        let value = match flag.value {
+            Value::None => String::new(),
+            Value::Path => String::from(" -r -F"),
+            Value::Text => String::from(" -x"),
+            Value::Choices(choices) => format!(" -x -a '{}'", choices.join(" ")),
+        };
+        let _ = writeln!(script, "complete -c {} {}{} -d '{}'", program, option, value, fish_quote(flag.help));
+    }
+    script
+}
+
+/**
+ * @brief Quote text for a single-quoted PowerShell string.
+ */
+fn powershell_quote(text: &str) -> String {
+    text.replace('\'', "''")
+}
+
+/**
+ * @brief Generate the PowerShell script: a native argument completer.
+ */
+fn powershell(cli: &Cli) -> String {
+    let mut script = format!(
+        "# PowerShell completion for {}\n\nRegister-ArgumentCompleter -Native -CommandName '{}' -ScriptBlock {{\n",
+        cli.program, cli.program
+    );
+    script.push_str("    param($wordToComplete, $commandAst, $cursorPosition)\n");
+    script.push_str("    $words = @($commandAst.CommandElements | ForEach-Object { $_.ToString() })\n");
+    script.push_str("    $previous = if ($wordToComplete) { $words[-2] } else { $words[-1] }\n");
+    script.push_str("    $candidates = switch -CaseSensitive ($previous) {\n");
+    for arg in cli.taking_values() {
+        let candidates = match arg.value {
+            Value::Choices(choices) => {
+                let quoted: Vec<String> = choices.iter().map(|choice| format!("'{}'", choice)).collect();
+                format!("@({})", quoted.join(", "))
+            }
+            // Nothing to offer; PowerShell falls back to file names
+            Value::Path | Value::Text | Value::None => String::from("return"),
+        };
+        let _ = writeln!(script, "        '{}' {{ {} }}", arg.name, candidates);
+    }
+    script.push_str("        default {\n");
+    for arg in cli.subcommands.iter().chain(cli.flags) {
+        let _ = writeln!(script, "            @{{ Name = '{}'; Help = '{}' }}", arg.name, powershell_quote(arg.help));
+    }
+    script.push_str("        }\n    }\n");
+    script.push_str("    $candidates | ForEach-Object {\n");
+    script.push_str("        $name, $help = if ($_ -is [hashtable]) { $_.Name, $_.Help } else { $_, $_ }\n");
+    script.push_str("        if ($name -like \"$wordToComplete*\") {\n");
+    script.push_str("            [System.Management.Automation.CompletionResult]::new(\n");
+    script.push_str("                $name, $name, 'ParameterValue', $help)\n");
+    script.push_str("        }\n    }\n}\n");
+    script
+}
+
+// End of rust_sample_project/src/completions.rs
@@ -0,0 +1,491 @@
+// rust_sample_project/src/composition.rs
+
+// Pipelines assembled in code, for embedding applications whose flow does not
+// fit the fixed load -> deduplicate -> process -> save run of
+// pipeline::run_processing_pipeline:
+//
+//     let report = Pipeline::new()
+//         .source(DataHandler::new(Path::new("data/in.json")))
+//         .stage(|item: &mut Item| if item.name.is_empty() {
+//             StageResult::Finish(ItemOutcome::FilteredOut)
+//         } else {
+//             StageResult::Continue
+//         })
+//         .stage(ItemProcessor::new(100.0))
+//         .sink(DataHandler::new(Path::new("data/out.json")))
+//         .run(&Metrics::new())?;
+//
+// Sources are loaded in order and concatenated. Every item then goes through
+// the stages in order until one finishes it (an item passing all of them
+// counts as processed), and the items are handed to every sink. Stages run on
+// the worker pool, so they must be Send + Sync; the existing building blocks
+// (FilterExpr, Enricher, ItemProcessor, DataHandler) implement the traits, as
+// do closures. Hooks, metrics, reports and graceful shutdown work as in a
+// regular run; checkpoints, locking and output routing are left to the caller.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::data_handler::DataHandler;
+use crate::dedup::{self, deduplicate, DedupStrategy, DuplicatePolicy};
+use crate::enrichment::{Enricher, Enrichment};
+use crate::error::PipelineError;
+use crate::filter::FilterExpr;
+use crate::hooks::{HookDecision, ItemOutcome, PipelineHooks};
+use crate::item::Item;
+use crate::item_processor::{ItemProcessor, ProcessError};
+use crate::metrics::Metrics;
+use crate::pipeline;
+use crate::report::PipelineReport;
+use crate::shutdown;
+use crate::worker_pool::WorkerPool;
+
+/**
+ * @enum StageResult
+ * @brief What a stage decided about an item.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageResult {
+    /// Hand the item on to the next stage.
+    Continue,
+    /// Stop here with this outcome; the remaining stages do not see the item.
+    Finish(ItemOutcome),
+}
+
+/**
+ * @enum ErrorPolicy
+ * @brief What a composed pipeline does when an item fails.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Mark the item failed and carry on; the run reports partial processing ("continue").
+    Continue,
+    /// Stop at the first failed item and save nothing ("abort").
+    Abort,
+}
+
+impl ErrorPolicy {
+    /**
+     * @brief Parse a policy name as used in pipeline definition files.
+     *
+     * @param name One of "continue" or "abort".
+     * @return Result<ErrorPolicy, String> The policy, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<ErrorPolicy, String> {
+        match name {
+            "continue" => Ok(ErrorPolicy::Continue),
+            "abort" => Ok(ErrorPolicy::Abort),
+            other => Err(format!("Unknown error policy '{}' (expected 'continue' or 'abort')", other)),
+        }
+    }
+}
+
+/**
+ * @brief Where a composed pipeline gets its items from.
+ */
+pub trait ItemSource {
+    /**
+     * @brief Load the items.
+     *
+     * @return Result<Vec<Item>, String> The items, or an error message.
+     */
+    fn load(&self) -> Result<Vec<Item>, String>;
+}
+
+/**
+ * @brief One step of a composed pipeline, applied to each item in turn.
+ *
+ * Items are handled on worker threads, hence the Send + Sync bound.
+ */
+pub trait Stage: Send + Sync {
+    /**
+     * @brief Apply the stage to an item.
+     *
+     * @param item The item, modified in place.
+     * @return StageResult Whether the item goes on to the next stage.
+     */
+    fn apply(&self, item: &mut Item) -> StageResult;
+}
+
+/**
+ * @brief Where a composed pipeline delivers its items.
+ */
+pub trait ItemSink {
+    /**
+     * @brief Store the items.
+     *
+     * @param items All items of the run, in order, whatever their outcome.
+     * @return Result<(), String> Ok, or an error message.
+     */
+    fn save(&self, items: &[Item]) -> Result<(), String>;
+}
+
+impl ItemSource for DataHandler {
+    fn load(&self) -> Result<Vec<Item>, String> {
+        self.load_items()
+    }
+}
+
+impl ItemSource for Vec<Item> {
+    fn load(&self) -> Result<Vec<Item>, String> {
+        Ok(self.clone())
+    }
+}
+
+impl<F> ItemSource for F
+where
+    F: Fn() -> Result<Vec<Item>, String>,
+{
+    fn load(&self) -> Result<Vec<Item>, String> {
+        self()
+    }
+}
+
+impl Stage for FilterExpr {
+    fn apply(&self, item: &mut Item) -> StageResult {
+        if self.matches(item) {
+            StageResult::Continue
+        } else {
+            StageResult::Finish(ItemOutcome::FilteredOut)
+        }
+    }
+}
+
+impl Stage for Enricher {
+    fn apply(&self, item: &mut Item) -> StageResult {
+        match self.enrich(item) {
+            Enrichment::Enriched | Enrichment::Missing => StageResult::Continue,
+            Enrichment::Skip => StageResult::Finish(ItemOutcome::SkippedByLookup),
+            Enrichment::Fail => StageResult::Finish(ItemOutcome::Failed),
+        }
+    }
+}
+
+impl Stage for ItemProcessor {
+    /**
+     * @brief Apply the tag rules and process the item, without a timeout.
+     */
+    fn apply(&self, item: &mut Item) -> StageResult {
+        if !self.is_selected(item) {
+            return StageResult::Finish(ItemOutcome::SkippedByTag);
+        }
+        if self.process_item(item) {
+            StageResult::Continue
+        } else {
+            StageResult::Finish(ItemOutcome::Failed)
+        }
+    }
+}
+
+impl Stage for Arc<ItemProcessor> {
+    /**
+     * @brief Apply the tag rules and process the item within the processor's timeout.
+     */
+    fn apply(&self, item: &mut Item) -> StageResult {
+        if !self.is_selected(item) {
+            return StageResult::Finish(ItemOutcome::SkippedByTag);
+        }
+        match self.process_item_with_timeout(item) {
+            Ok(()) => StageResult::Continue,
+            Err(ProcessError::Failed) => StageResult::Finish(ItemOutcome::Failed),
+            Err(ProcessError::Timeout(_)) => StageResult::Finish(ItemOutcome::TimedOut),
+        }
+    }
+}
+
+impl Stage for Box<dyn Stage> {
+    fn apply(&self, item: &mut Item) -> StageResult {
+        (**self).apply(item)
+    }
+}
+
+impl<F> Stage for F
+where
+    F: Fn(&mut Item) -> StageResult + Send + Sync,
+{
+    fn apply(&self, item: &mut Item) -> StageResult {
+        self(item)
+    }
+}
+
+impl ItemSink for DataHandler {
+    /**
+     * @brief Save the items to the handler's data source path.
+     */
+    fn save(&self, items: &[Item]) -> Result<(), String> {
+        self.save_items_to(self.data_source_path(), items)
+    }
+}
+
+impl<F> ItemSink for F
+where
+    F: Fn(&[Item]) -> Result<(), String>,
+{
+    fn save(&self, items: &[Item]) -> Result<(), String> {
+        self(items)
+    }
+}
+
+/**
+ * @struct Pipeline
+ * @brief A pipeline assembled from sources, stages and sinks.
+ */
+pub struct Pipeline {
+    sources: Vec<Box<dyn ItemSource>>,
+    stages: Vec<Box<dyn Stage>>,
+    sinks: Vec<Box<dyn ItemSink>>,
+    /// How items with the same id are merged after loading; None keeps them all.
+    dedup_strategy: Option<DedupStrategy>,
+    /// What saving does about items sharing an id (allow by default).
+    duplicate_policy: DuplicatePolicy,
+    /// Number of worker threads the stages run on.
+    workers: usize,
+    error_policy: ErrorPolicy,
+    hooks: PipelineHooks,
+}
+
+impl Pipeline {
+    /**
+     * @brief Constructs an empty pipeline: no sources, stages or sinks, one worker.
+     *
+     * @return Pipeline The pipeline.
+     */
+    pub fn new() -> Self {
+        Pipeline {
+            sources: Vec::new(),
+            stages: Vec::new(),
+            sinks: Vec::new(),
+            dedup_strategy: None,
+            duplicate_policy: DuplicatePolicy::Allow,
+            workers: 1,
+            error_policy: ErrorPolicy::Continue,
+            hooks: PipelineHooks::new(),
+        }
+    }
+
+    /**
+     * @brief Add a source; items of several sources are concatenated in order.
+     *
+     * @param source Where to load items from.
+     * @return Pipeline The pipeline with the source added.
+     */
+    pub fn source(mut self, source: impl ItemSource + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /**
+     * @brief Add a stage after the existing ones.
+     *
+     * @param stage The step to apply to each item.
+     * @return Pipeline The pipeline with the stage added.
+     */
+    pub fn stage(mut self, stage: impl Stage + 'static) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    /**
+     * @brief Add a sink; every sink receives all items.
+     *
+     * @param sink Where to deliver the items.
+     * @return Pipeline The pipeline with the sink added.
+     */
+    pub fn sink(mut self, sink: impl ItemSink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /**
+     * @brief Drop items with duplicate ids after loading.
+     *
+     * @param strategy Which of the duplicates is kept.
+     * @return Pipeline The pipeline with deduplication configured.
+     */
+    pub fn with_dedup(mut self, strategy: DedupStrategy) -> Self {
+        self.dedup_strategy = Some(strategy);
+        self
+    }
+
+    /**
+     * @brief Check the items for duplicate ids before they are saved.
+     *
+     * @param policy Whether duplicates are saved, saved with a warning, or fail the save.
+     * @return Pipeline The pipeline with the check configured.
+     */
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /**
+     * @brief Set the number of worker threads the stages run on.
+     *
+     * @param workers Number of threads (at least 1; items keep their order).
+     * @return Pipeline The pipeline with the worker count configured.
+     */
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /**
+     * @brief Set what happens when an item fails.
+     *
+     * @param error_policy Carry on (the default) or abort the run.
+     * @return Pipeline The pipeline with the error policy configured.
+     */
+    pub fn with_error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /**
+     * @brief Set the observers of the pipeline's runs.
+     *
+     * @param hooks The observers (see hooks.rs).
+     * @return Pipeline The pipeline with the hooks configured.
+     */
+    pub fn with_hooks(mut self, hooks: PipelineHooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /**
+     * @brief Run the pipeline once: load, apply the stages, save.
+     *
+     * @param metrics Collector for the run's counters and timings.
+     * @return Result<PipelineReport, PipelineError> The run's report (also
+     *         when individual items failed, see PipelineReport::outcome), or
+     *         the classified failure.
+     */
+    pub fn run(&self, metrics: &Metrics) -> Result<PipelineReport, PipelineError> {
+        let result = self.run_steps(metrics);
+        if let Err(e) = &result {
+            self.hooks.notify_error(e);
+        }
+        result
+    }
+
+    /**
+     * @brief Run the pipeline's steps, see run.
+     */
+    fn run_steps(&self, metrics: &Metrics) -> Result<PipelineReport, PipelineError> {
+        if self.sources.is_empty() || self.sinks.is_empty() {
+            return Err(PipelineError::Config(String::from(
+                "A composed pipeline needs at least one source and one sink",
+            )));
+        }
+        let mut report = PipelineReport::new();
+
+        let mut items = Vec::new();
+        for source in &self.sources {
+            items.extend(source.load().map_err(PipelineError::Load)?);
+        }
+        log_info!("composition:run", "Loaded {} items from {} sources.", items.len(), self.sources.len());
+        metrics.add_items_loaded(items.len() as u64);
+        report.items_loaded = items.len();
+        if let Some(strategy) = self.dedup_strategy {
+            let (unique_items, duplicates_dropped) = deduplicate(items, strategy);
+            items = unique_items;
+            report.duplicates_dropped = duplicates_dropped;
+        }
+        if let HookDecision::Veto(reason) = self.hooks.notify_load_complete(&items) {
+            log_warning!("composition:run", "{}", tr!(RunVetoed, reason));
+            return Err(PipelineError::Aborted(reason));
+        }
+
+        let total_items = items.len();
+        let mut handled = Vec::with_capacity(total_items);
+        let worker_pool = WorkerPool::new(self.workers, self.workers * 2);
+        let (stages, hooks) = (&self.stages, &self.hooks);
+        let abort_on_failure = self.error_policy == ErrorPolicy::Abort;
+        let failed = AtomicBool::new(false);
+        let not_started = worker_pool.map_ordered(
+            items,
+            || shutdown::requested() || failed.load(Ordering::SeqCst),
+            |mut item: Item| {
+                let outcome = apply_stages(stages, hooks, &mut item, metrics);
+                pipeline::record_status(&mut item, outcome);
+                (item, outcome)
+            },
+            |(item, outcome)| {
+                report.record_outcome(outcome);
+                hooks.notify_after_item(&item, outcome);
+                if abort_on_failure && matches!(outcome, ItemOutcome::Failed | ItemOutcome::TimedOut) {
+                    failed.store(true, Ordering::SeqCst);
+                }
+                handled.push(item);
+            },
+        );
+        if failed.load(Ordering::SeqCst) {
+            let reason = format!("{} items failed and the error policy is 'abort'", report.items_failed);
+            log_error!("composition:run", "Stopping the run: {}.", reason);
+            return Err(PipelineError::Aborted(reason));
+        }
+        if !not_started.is_empty() {
+            log_warning!("composition:run", "{}", tr!(ShutdownStopping, handled.len(), total_items));
+            report.interrupted = true;
+            handled.extend(not_started);
+        }
+
+        report.duplicate_ids_saved = dedup::check_before_save(&handled, self.duplicate_policy).map_err(|e| {
+            log_error!("composition:run", "{}", tr!(SaveFailed, e));
+            PipelineError::Save(e)
+        })?;
+        for sink in &self.sinks {
+            sink.save(&handled).map_err(|e| {
+                log_error!("composition:run", "{}", tr!(SaveFailed, e));
+                PipelineError::Save(e)
+            })?;
+        }
+
+        report.finish(&handled);
+        self.hooks.notify_save_complete(&handled, &report);
+        report.log_summary();
+        Ok(report)
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+ * @brief Take one item through the hooks and the stages.
+ *
+ * @param stages The stages, in order.
+ * @param hooks The observers asked before the item is handled.
+ * @param item The item, modified in place.
+ * @param metrics Collector for the run's counters and timings.
+ * @return ItemOutcome What happened to the item.
+ */
+fn apply_stages(stages: &[Box<dyn Stage>], hooks: &PipelineHooks, item: &mut Item, metrics: &Metrics) -> ItemOutcome {
+    if let HookDecision::Veto(reason) = hooks.notify_before_item(item) {
+        log_debug!("composition:apply_stages", "Skipping item vetoed by a hook ({}): {}", reason, item);
+        return ItemOutcome::Vetoed;
+    }
+    let started = Instant::now();
+    let outcome = stages
+        .iter()
+        .find_map(|stage| match stage.apply(item) {
+            StageResult::Continue => None,
+            StageResult::Finish(outcome) => Some(outcome),
+        })
+        .unwrap_or(ItemOutcome::Processed);
+    metrics.observe_processing_duration(started.elapsed());
+    match outcome {
+        ItemOutcome::Processed => metrics.inc_items_processed(),
+        ItemOutcome::Failed | ItemOutcome::TimedOut => {
+            log_error!("composition:apply_stages", "Failed to process item: {}", item);
+            metrics.inc_items_failed();
+        }
+        _ => {}
+    }
+    outcome
+}
+
+// End of rust_sample_project/src/composition.rs
@@ -0,0 +1,183 @@
+// rust_sample_project/src/compression.rs
+
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+/// Magic bytes at the start of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// Magic bytes at the start of a zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+/// Compression level used for zstd outputs (the library default).
+const ZSTD_LEVEL: i32 = 3;
+
+/**
+ * @enum Compression
+ * @brief Compression applied to a data file.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Plain, uncompressed file.
+    None,
+    /// gzip (`.gz`).
+    Gzip,
+    /// Zstandard (`.zst`).
+    Zstd,
+}
+
+impl Compression {
+    /**
+     * @brief Parse a compression setting as used in config.
+     *
+     * @param name One of "auto", "none", "gzip" or "zstd".
+     * @return Result<Option<Compression>, String> None for "auto" (choose by
+     *         file extension), the forced compression otherwise, or an error
+     *         message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<Option<Compression>, String> {
+        match name {
+            "auto" => Ok(None),
+            "none" => Ok(Some(Compression::None)),
+            "gzip" => Ok(Some(Compression::Gzip)),
+            "zstd" => Ok(Some(Compression::Zstd)),
+            other => Err(format!(
+                "Unknown compression '{}' (expected 'auto', 'none', 'gzip' or 'zstd')",
+                other
+            )),
+        }
+    }
+
+    /**
+     * @brief Determine the compression implied by a file name.
+     *
+     * @param path Path of the data file, e.g. "data/items.json.gz".
+     * @return Compression Gzip for ".gz", Zstd for ".zst", None otherwise.
+     */
+    pub fn from_path(path: &str) -> Compression {
+        let lower = path.to_ascii_lowercase();
+        if lower.ends_with(".gz") {
+            Compression::Gzip
+        } else if lower.ends_with(".zst") {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    /**
+     * @brief Determine the compression of file contents from their magic bytes.
+     *
+     * @param bytes The raw file contents.
+     * @return Compression The detected compression, None for plain data.
+     */
+    fn detect(bytes: &[u8]) -> Compression {
+        if bytes.starts_with(&GZIP_MAGIC) {
+            Compression::Gzip
+        } else if bytes.starts_with(&ZSTD_MAGIC) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    /**
+     * @brief Compress data.
+     *
+     * @param data The uncompressed bytes.
+     * @return Result<Vec<u8>, String> The compressed bytes, or an error message.
+     */
+    pub fn encode(self, data: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder
+                    .write_all(data)
+                    .and_then(|_| encoder.finish())
+                    .map_err(|e| format!("Failed to gzip data: {}", e))
+            }
+            Compression::Zstd => zstd::encode_all(data, ZSTD_LEVEL)
+                .map_err(|e| format!("Failed to zstd-compress data: {}", e)),
+        }
+    }
+
+    /**
+     * @brief Decompress data.
+     *
+     * @param data The compressed bytes.
+     * @return Result<Vec<u8>, String> The decompressed bytes, or an error message.
+     */
+    pub fn decode(self, data: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Gzip => {
+                let mut decoded = Vec::new();
+                GzDecoder::new(data)
+                    .read_to_end(&mut decoded)
+                    .map_err(|e| format!("Failed to decompress gzip data: {}", e))?;
+                Ok(decoded)
+            }
+            Compression::Zstd => zstd::decode_all(data)
+                .map_err(|e| format!("Failed to decompress zstd data: {}", e)),
+        }
+    }
+}
+
+/**
+ * @brief Tell whether raw file contents are gzip or zstd compressed.
+ *
+ * @param raw The raw file contents.
+ * @return bool True if they start with a gzip or zstd magic number.
+ */
+pub fn is_compressed(raw: &[u8]) -> bool {
+    Compression::detect(raw) != Compression::None
+}
+
+/**
+ * @brief Decode the raw contents of a possibly compressed text file.
+ *
+ * The compression is detected from the contents, so a compressed file is
+ * read correctly whatever its name.
+ *
+ * @param raw The raw file contents.
+ * @param location Path or URL of the file, used in messages.
+ * @return Result<String, String> The decompressed UTF-8 contents, or an error message.
+ */
+pub fn decode_text(raw: &[u8], location: &str) -> Result<String, String> {
+    String::from_utf8(decode_bytes(raw, location)?)
+        .map_err(|e| format!("Failed to read data file '{}': {}", location, e))
+}
+
+/**
+ * @brief Decode the raw contents of a possibly compressed binary file.
+ *
+ * @param raw The raw file contents.
+ * @param location Path or URL of the file, used in messages.
+ * @return Result<Vec<u8>, String> The decompressed contents, or an error message.
+ */
+pub fn decode_bytes(raw: &[u8], location: &str) -> Result<Vec<u8>, String> {
+    let compression = Compression::detect(raw);
+    if compression != Compression::None {
+        log_debug!("compression:decode_bytes", "Reading {:?}-compressed file {}.", compression, location);
+    }
+    compression
+        .decode(raw)
+        .map_err(|e| format!("Failed to read data file '{}': {}", location, e))
+}
+
+/**
+ * @brief Strip a compression extension from a file name.
+ *
+ * @param path Path of the data file, e.g. "data/items.json.gz".
+ * @return &str The path without ".gz"/".zst", e.g. "data/items.json".
+ */
+pub fn strip_extension(path: &str) -> &str {
+    match Compression::from_path(path) {
+        Compression::Gzip => &path[..path.len() - ".gz".len()],
+        Compression::Zstd => &path[..path.len() - ".zst".len()],
+        Compression::None => path,
+    }
+}
+
+// End of rust_sample_project/src/compression.rs
@@ -4,57 +4,3497 @@
 // In a more complex application, these might be loaded from a file or environment variables
 // using crates like `config`, `dotenv`, or `serde`.
 
-// No specific `use` statements needed for this simple config module.
-// If logging were integrated, `use log::{info, debug};` might be here.
+// The constants are the defaults. Config collects the effective values of a
+// run and validates them as a whole. Values are resolved in a fixed order,
+// each layer overriding the previous one:
+//
+//     defaults (the constants below)
+//       < config file (TOML, CONFIG_FILE_PATH or --config <path>)
+//       < active profile ([profiles.<name>] of the config file, selected
+//         with --profile <name> or APP_PROFILE)
+//       < pipeline ([pipelines.<name>] of the config file, for each of the
+//         pipelines run together with --pipelines <names|all>)
+//       < tenant ([tenants.<name>] of the config file, selected with
+//         --tenant <name>, or per run in serve mode)
+//       < environment (SAMPLE_PROJECT_<KEY>, e.g. SAMPLE_PROJECT_THRESHOLD=150)
+//       < command line (--log-format, --filter, --stage, --listen)
+//
+// File keys are the Config field names. In the environment, list values are
+// comma-separated, except transformations and output_routes, which are
+// separated by ';' (routes as "sink=condition"). A profile table holds the
+// same keys as the top level, and so do pipeline and tenant tables, e.g.
+//
+//     threshold = 100
+//     [profiles.prod]
+//     data_path = "/srv/data/items.json"
+//     log_level = "warning"
+//     [pipelines.orders]
+//     data_path = "data/orders.json"
+//     [pipelines.returns]
+//     data_path = "data/returns.json"
+//     threshold = 50
+//     [tenants.acme]
+//     data_path = "data/acme/items.json"
+//     checkpoint_path = "data/acme/items.checkpoint.json"
+//     history_path = "data/acme/runs.jsonl"
+//
+// A selected tenant must set its data_path, checkpoint_path and, if they are
+// used, history_path and audit_log_path in its own table, each different
+// from those of the other tenants, so tenants never read or write each
+// other's files.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::aggregate::{Grouping, SummaryFormat};
+use crate::anomaly::AnomalyMethod;
+use crate::compression::Compression;
+use crate::data_handler::DataFormat;
+use crate::dedup::{DedupStrategy, DuplicatePolicy};
+use crate::enrichment::MissingLookupPolicy;
+use crate::filter::FilterExpr;
+use crate::integrity;
+use crate::invalid_records::Strictness;
+use crate::item_id::{self, IdKind};
+use crate::join::{JoinField, JoinType};
+use crate::lock::LockPolicy;
+use crate::logging::{ColorChoice, LogFormat, LogLevel};
+use crate::messages::{self, Locale};
+use crate::money::MAX_DECIMALS;
+use crate::notify::{self, NotifyOn};
+use crate::path_template;
+use crate::priority::PriorityPolicy;
+use crate::rate_limit;
+use crate::registry;
+use crate::secret::Secret;
+use crate::router::OutputRouter;
+use crate::schedule::{CronSchedule, MissedRunPolicy};
+use crate::storage;
+use crate::transform::Transformation;
+use crate::xlsx_format;
+use crate::xml_format::{self, XmlMode};
+
+/**
+ * @brief Config file read when --config is not given; it is optional, a
+ *        missing file just leaves the defaults in place.
+ */
+pub const CONFIG_FILE_PATH: &str = "sample_project.toml";
+
+/**
+ * @brief Seconds between two checks of the config file for changes in watch
+ *        and serve modes.
+ */
+pub const CONFIG_RELOAD_POLL_SECS: u64 = 2;
+
+/**
+ * @brief Prefix of the environment variables overriding config values.
+ */
+pub const CONFIG_ENV_PREFIX: &str = "SAMPLE_PROJECT_";
+
+/**
+ * @brief Environment variable selecting the config profile when --profile is
+ *        not given.
+ */
+pub const PROFILE_ENV_VAR: &str = "APP_PROFILE";
+
+/**
+ * @brief Path to a (simulated) data file used by DataHandler; with the `http`
+ *        feature this may also be an http(s) URL, with the `s3` feature an
+ *        s3://bucket/key location.
+ */
+pub const DATA_FILE_PATH: &str = "data/items.json";
+
+/**
+ * @brief A processing parameter used by ItemProcessor: items with a value
+ *        above it are tagged "over_threshold", all others "under_threshold".
+ */
+pub const PROCESSING_THRESHOLD: f64 = 100.0;
+
+/**
+ * @brief Thresholds replacing PROCESSING_THRESHOLD for items of a category,
+ *        as (category, threshold) pairs, e.g. &[("books", 20.0)].
+ */
+pub const CATEGORY_THRESHOLDS: &[(&str, f64)] = &[];
+
+/**
+ * @brief Thresholds replacing PROCESSING_THRESHOLD for items carrying a tag,
+ *        as (tag, threshold) pairs. A category threshold takes precedence; of
+ *        several matching tags, the lowest threshold applies.
+ */
+pub const TAG_THRESHOLDS: &[(&str, f64)] = &[];
+
+/**
+ * @brief Number of decimal places processed item values are kept at (at most
+ *        money::MAX_DECIMALS); values and transformation results with more
+ *        decimals are rounded. Items a run does not process keep theirs.
+ */
+pub const VALUE_DECIMALS: u32 = 2;
+
+/**
+ * @brief Kind of item id the data sources use: "int", "uuid" or "string".
+ *        Records with an id of another kind are skipped when loaded.
+ */
+pub const ITEM_ID_KIND: &str = "int";
+
+/**
+ * @brief Language of log and report messages: "en", "de" or "sk" (empty =
+ *        from LC_ALL / LC_MESSAGES / LANG, English if unsupported).
+ */
+pub const LOCALE: &str = "";
+
+/**
+ * @brief Lowest level of log records that are printed: "trace", "debug",
+ *        "info", "warning", "error" or "critical" (-q, -v and -vv override it).
+ */
+pub const LOG_LEVEL: &str = "INFO";
+
+/**
+ * @brief Output format of log records: "text" or "json" (overridable with --log-format).
+ */
+pub const LOG_FORMAT: &str = "text";
+
+/**
+ * @brief When text log records are colored: "auto" (on terminals, unless
+ *        NO_COLOR is set), "always" or "never" (overridable with --color).
+ */
+pub const LOG_COLOR: &str = "auto";
+
+/**
+ * @brief Input files or glob patterns (JSON/CSV) to load and merge instead of
+ *        DATA_FILE_PATH, e.g. a glob such as "data/in/items_?.csv".
+ *        Processed items are still saved to DATA_FILE_PATH.
+ */
+pub const INPUT_SOURCES: &[&str] = &[];
+
+/**
+ * @brief How items with the same id from different input sources are merged:
+ *        "keep-first", "keep-last" or "merge-by-max-value".
+ */
+pub const MERGE_STRATEGY: &str = "keep-first";
+
+/**
+ * @brief Output routes as (sink path, condition) pairs; each processed item is
+ *        saved to the first sink whose condition (filter expression syntax)
+ *        it matches, and to DATA_FILE_PATH otherwise. For example:
+ *        &[("data/accepted.json", "tags contains 'over_threshold'"),
+ *          ("data/review.json", "tags contains 'under_threshold'")]
+ */
+pub const OUTPUT_ROUTES: &[(&str, &str)] = &[];
+
+/**
+ * @brief Where processed items are saved instead of DATA_FILE_PATH ("" =
+ *        back to DATA_FILE_PATH). May hold the placeholders {date}, {time},
+ *        {run_id}, {profile} and {tenant}, resolved at save time, so repeated
+ *        runs do not overwrite each other, e.g. "out/items-{date}-{run_id}.json"
+ *        (see path_template.rs). Output routes, ANOMALY_REVIEW_PATH and
+ *        AGGREGATE_PATH take the same placeholders.
+ */
+pub const OUTPUT_PATH: &str = "";
+
+/**
+ * @brief Compression of saved data files: "auto" (by extension, ".gz" or
+ *        ".zst"), "none", "gzip" or "zstd". Compressed inputs are always
+ *        detected automatically.
+ */
+pub const OUTPUT_COMPRESSION: &str = "auto";
+
+/**
+ * @brief Format of data files read and saved: "auto" (by extension, ".json",
+ *        ".csv", ".ndjson"/".jsonl" or ".parquet"), "json", "csv", "ndjson"
+ *        or "parquet" (which needs the `parquet` feature). With --stream, an
+ *        NDJSON data file is processed line by line instead of read from Kafka.
+ */
+pub const DATA_FORMAT: &str = "auto";
+
+/**
+ * @brief What loading does with records of a data file that are not a valid
+ *        item: "lenient" (skip them quietly), "warn" (skip them with a
+ *        warning) or "strict" (fail the load, listing every invalid record).
+ */
+pub const INPUT_STRICTNESS: &str = "warn";
+
+/**
+ * @brief Let a run that skipped invalid records save over the data file they
+ *        came from, which drops them. Off by default: such a run fails once
+ *        the file is loaded, before anything is processed or saved, so the
+ *        records stay in the file until they are fixed.
+ */
+pub const DROP_INVALID_RECORDS: bool = false;
+
+/**
+ * @brief Element holding one item in XML data files (see xml_format.rs).
+ */
+pub const XML_ITEM_ELEMENT: &str = "item";
+
+/**
+ * @brief Where the item id is in an XML item element: an attribute ("@id")
+ *        or a child element ("id").
+ */
+pub const XML_ID_FIELD: &str = "@id";
+
+/**
+ * @brief Where the item name is in an XML item element ("@attribute" or child element).
+ */
+pub const XML_NAME_FIELD: &str = "name";
+
+/**
+ * @brief Where the item value is in an XML item element ("@attribute" or child element).
+ */
+pub const XML_VALUE_FIELD: &str = "value";
+
+/**
+ * @brief How XML data files are parsed: "strict" (malformed XML or an invalid
+ *        item fails the load) or "lenient" (invalid items are skipped, reading
+ *        stops at malformed XML).
+ */
+pub const XML_MODE: &str = "lenient";
+
+/**
+ * @brief Sheet items are read from in Excel data files (see xlsx_format.rs);
+ *        empty for the first sheet.
+ */
+pub const XLSX_SHEET: &str = "";
+
+/**
+ * @brief Header of the item id column in Excel data files.
+ */
+pub const XLSX_ID_COLUMN: &str = "item_id";
+
+/**
+ * @brief Header of the item name column in Excel data files.
+ */
+pub const XLSX_NAME_COLUMN: &str = "name";
+
+/**
+ * @brief Header of the item value column in Excel data files.
+ */
+pub const XLSX_VALUE_COLUMN: &str = "value";
+
+/**
+ * @brief Name of the sheet that saved Excel data files hold the results in.
+ */
+pub const XLSX_RESULTS_SHEET: &str = "Results";
+
+/**
+ * @brief Environment variable holding the key (64 hex digits) saved data
+ *        files are encrypted with (see encryption.rs); empty for none.
+ */
+pub const ENCRYPTION_KEY_ENV: &str = "";
+
+/**
+ * @brief File holding the key saved data files are encrypted with, as 64 hex
+ *        digits or 32 raw bytes; empty for none.
+ */
+pub const ENCRYPTION_KEY_FILE: &str = "";
+
+/**
+ * @brief Load data files that are not encrypted although a key is configured.
+ *        Off by default, so a plain file put in place of an encrypted one is
+ *        refused; turn it on to encrypt existing plain files on the next save.
+ */
+pub const ENCRYPTION_ALLOW_PLAINTEXT: bool = false;
+
+/**
+ * @brief Write a ".sha256" checksum file next to each saved local data file
+ *        and verify it when the file is loaded (see integrity.rs).
+ */
+pub const DATA_CHECKSUMS: bool = true;
+
+/**
+ * @brief Share one copy of each distinct item name among the loaded items
+ *        (see intern.rs); the run report shows the memory saved.
+ */
+pub const INTERN_NAMES: bool = false;
+
+/**
+ * @brief Lines per arena batch when streaming an NDJSON data file (--stream,
+ *        see item_arena.rs); 0 reads line by line. Needs the `arena` feature.
+ */
+pub const STREAM_ARENA_BATCH: usize = 0;
+
+/**
+ * @brief Items the load stage of a stream (--stream on an NDJSON data file)
+ *        may read ahead of the process stage.
+ */
+pub const STREAM_PROCESS_BUFFER: usize = 256;
+
+/**
+ * @brief Processed items of a stream that may wait for the save stage; when
+ *        the queue is full, processing and reading wait for the disk.
+ */
+pub const STREAM_SAVE_BUFFER: usize = 256;
+
+/**
+ * @brief Add statistics of the saved items' values to the run report,
+ *        computed over a columnar copy of the items (see item_batch.rs).
+ */
+pub const ANALYTICS: bool = false;
+
+/**
+ * @brief Timeout in seconds for each request to an http(s) data source.
+ */
+#[cfg(feature = "http")]
+pub const HTTP_TIMEOUT_SECS: u64 = 30;
+
+/**
+ * @brief How many times a failed request to an http(s) data source is retried.
+ */
+#[cfg(feature = "http")]
+pub const HTTP_RETRIES: u32 = 3;
+
+/**
+ * @brief Extra headers sent with every request to an http(s) data source.
+ */
+#[cfg(feature = "http")]
+pub const HTTP_HEADERS: &[(&str, &str)] = &[("Accept", "application/json")];
+
+/**
+ * @brief Environment variable holding the bearer token for http(s) data
+ *        sources; no Authorization header is sent when it is unset.
+ */
+#[cfg(feature = "http")]
+pub const HTTP_AUTH_TOKEN_ENV: &str = "SAMPLE_PROJECT_API_TOKEN";
+
+/**
+ * @brief Whether processed items are POSTed back to an http(s) data source.
+ */
+#[cfg(feature = "http")]
+pub const HTTP_POST_RESULTS: bool = false;
+
+/**
+ * @brief Kafka bootstrap brokers used in streaming mode (`--stream`).
+ */
+#[cfg(feature = "kafka")]
+pub const KAFKA_BROKERS: &[&str] = &["localhost:9092"];
+
+/**
+ * @brief Kafka topic items are consumed from in streaming mode.
+ */
+#[cfg(feature = "kafka")]
+pub const KAFKA_INPUT_TOPIC: &str = "items";
+
+/**
+ * @brief Kafka topic processed items are emitted to in streaming mode.
+ */
+#[cfg(feature = "kafka")]
+pub const KAFKA_OUTPUT_TOPIC: &str = "items-processed";
+
+/**
+ * @brief Kafka consumer group whose committed offsets track streaming progress.
+ */
+#[cfg(feature = "kafka")]
+pub const KAFKA_CONSUMER_GROUP: &str = "rust_sample_project";
+
+/**
+ * @brief Address the HTTP API listens on in daemon mode (`serve`).
+ */
+pub const DAEMON_ADDR: &str = "127.0.0.1:8080";
+
+/**
+ * @brief Address the gRPC service listens on (`serve --grpc`).
+ */
+pub const GRPC_ADDR: &str = "127.0.0.1:50051";
+
+/**
+ * @brief Number of worker threads processing items; 1 processes them one by
+ *        one. Items are saved in their input order either way.
+ */
+pub const WORKER_THREADS: usize = 1;
+
+/**
+ * @brief Capacity of the bounded queues between the item feeder, the worker
+ *        threads and the collector.
+ */
+pub const WORKER_QUEUE_CAPACITY: usize = 64;
+
+/**
+ * @brief Most items per second handed to processing, and sent by the HTTP
+ *        and Kafka sinks (0 = no limit, see rate_limit.rs).
+ */
+pub const RATE_LIMIT: f64 = 0.0;
+
+/**
+ * @brief Most items let through at once under the rate limit, after an idle period.
+ */
+pub const RATE_LIMIT_BURST: usize = 100;
+
+/**
+ * @brief Which items are processed first: "input-order", "highest-value",
+ *        "lowest-value" or "matching:<filter expression>" (see priority.rs).
+ */
+pub const PRIORITY_POLICY: &str = "input-order";
+
+/**
+ * @brief Longest time in seconds a run spends processing; items not started
+ *        by then are left for the next run (0 = no limit). Overridable with
+ *        --max-duration.
+ */
+pub const MAX_DURATION_SECS: u64 = 0;
+
+/**
+ * @brief Process only a random sample of this many loaded items; the others
+ *        are saved unchanged (0 = all items, see sampling.rs). Overridable
+ *        with --sample.
+ */
+pub const SAMPLE_SIZE: usize = 0;
+
+/**
+ * @brief Process only a random sample of this percentage of the loaded items
+ *        (0 = all items). Overridable with --sample-percent.
+ */
+pub const SAMPLE_PERCENT: f64 = 0.0;
+
+/**
+ * @brief Seed of the random generator drawing samples, so runs with the same
+ *        seed sample the same items.
+ */
+pub const SAMPLE_SEED: u64 = 0;
+
+/**
+ * @brief Anomaly detection over the loaded values: "off", "stddev" (mean and
+ *        standard deviation) or "iqr" (quartiles). Outliers are tagged
+ *        'anomaly' and saved unprocessed to ANOMALY_REVIEW_PATH (see anomaly.rs).
+ */
+pub const ANOMALY_DETECTION: &str = "off";
+
+/**
+ * @brief How many standard deviations (stddev) or interquartile ranges (iqr)
+ *        a value may lie outside the mean or the quartiles before it counts
+ *        as an outlier, e.g. 3 for stddev or 1.5 for iqr.
+ */
+pub const ANOMALY_FACTOR: f64 = 3.0;
+
+/**
+ * @brief Path (or URL) of the review sink receiving the outliers.
+ */
+pub const ANOMALY_REVIEW_PATH: &str = "data/review.json";
+
+/**
+ * @brief Groupings of the saved items summarized into AGGREGATE_PATH after
+ *        each run: "tag", "name-prefix:<n>" or "value-bucket:<width>"
+ *        (empty = no summary, see aggregate.rs).
+ */
+pub const AGGREGATE_BY: &[&str] = &[];
+
+/**
+ * @brief Path (or URL) of the summary file with count, sum and average value per group.
+ */
+pub const AGGREGATE_PATH: &str = "data/summary.json";
+
+/**
+ * @brief Format of the summary file: "auto" (CSV for ".csv", JSON otherwise),
+ *        "json" or "csv".
+ */
+pub const AGGREGATE_FORMAT: &str = "auto";
+
+/**
+ * @brief Longest time in milliseconds a single item may take to process;
+ *        slower items are recorded as failed (0 = no limit).
+ */
+pub const ITEM_TIMEOUT_MS: u64 = 0;
+
+/**
+ * @brief Largest number of items sent in one write to sinks that accept
+ *        batches (REST endpoints, databases); data files are written whole.
+ */
+pub const SAVE_BATCH_SIZE: usize = 500;
+
+/**
+ * @brief Milliseconds after which a pending, not yet full batch is flushed
+ *        (0 = flush only full batches and the final one).
+ */
+pub const SAVE_FLUSH_INTERVAL_MS: u64 = 1000;
+
+/**
+ * @brief Largest number of items per shard when local data files are saved
+ *        as numbered shards with a manifest (0 = no limit, see shard.rs).
+ */
+pub const SHARD_MAX_ITEMS: usize = 0;
+
+/**
+ * @brief Largest size in bytes of the items of a shard, as uncompressed JSON
+ *        (0 = no limit). With both shard limits at 0, files are saved whole.
+ */
+pub const SHARD_MAX_BYTES: u64 = 0;
+
+/**
+ * @brief Path to the checkpoint file used to resume interrupted runs.
+ */
+pub const CHECKPOINT_FILE_PATH: &str = "data/items.checkpoint.json";
+
+/**
+ * @brief Directory holding the manifests of in-progress saves, used by the
+ *        recover subcommand to clean up after a run that died while saving.
+ */
+pub const STAGING_DIR: &str = "data/.staging";
+
+/**
+ * @brief Directory holding the snapshots of the data files taken before each
+ *        save, restored by the rollback subcommand (see snapshot.rs).
+ */
+pub const SNAPSHOT_DIR: &str = "data/snapshots";
+
+/**
+ * @brief Number of snapshots kept; older ones are removed (0 = take none).
+ */
+pub const SNAPSHOT_RETENTION: usize = 10;
+
+/**
+ * @brief Let join and generate replace existing non-empty data files, and
+ *        rollback replace the current data files (see guard.rs); runs always
+ *        rewrite their data source and configured outputs. --allow-overwrite
+ *        sets it for one invocation.
+ */
+pub const ALLOW_OVERWRITE: bool = false;
+
+/**
+ * @brief Number of processed items between two checkpoint writes.
+ */
+pub const CHECKPOINT_INTERVAL: usize = 10;
+
+/**
+ * @brief What a run does when another run holds the data file's lock:
+ *        "wait" for it to be released, or "fail" at once.
+ */
+pub const LOCK_POLICY: &str = "wait";
+
+/**
+ * @brief Seconds a run waits for the data file's lock with the "wait"
+ *        policy before failing (0 = no limit).
+ */
+pub const LOCK_TIMEOUT_SECS: u64 = 300;
+
+/**
+ * @brief Incremental runs: items already marked processed are left as they
+ *        are, so re-running the pipeline only touches new records.
+ */
+pub const INCREMENTAL: bool = false;
+
+/**
+ * @brief Only items carrying at least one of these tags are processed (empty = all items).
+ */
+pub const REQUIRED_TAGS: &[&str] = &[];
+
+/**
+ * @brief Items carrying any of these tags are skipped by the processor.
+ */
+pub const EXCLUDED_TAGS: &[&str] = &[];
+
+/**
+ * @brief Filter expression selecting the items to process, e.g.
+ *        "value > 100 && name contains 'Widget'" (empty = all items).
+ *        Overridable with --filter.
+ */
+pub const FILTER_EXPRESSION: &str = "";
+
+/**
+ * @brief Value transformations applied by ItemProcessor, in order, e.g.
+ *        "value = value * 1.2 if value > threshold". Items already marked
+ *        processed are not transformed again, also when INCREMENTAL is off.
+ */
+pub const TRANSFORMATIONS: &[&str] = &[];
+
+/**
+ * @brief Registered stages (see registry.rs), applied in order to each item
+ *        after the filter and before the processor (empty = none). Stages
+ *        named here get no parameters. Extended with --stage.
+ */
+pub const CUSTOM_STAGES: &[&str] = &[];
+
+/**
+ * @brief How duplicate item ids are resolved after loading:
+ *        "keep-first", "keep-last" or "merge-by-max-value".
+ */
+pub const DEDUP_STRATEGY: &str = "keep-first";
+
+/**
+ * @brief What saving does when the output has items with the same id, e.g.
+ *        after a join: "allow", "warn" (save them, listing the ids in the
+ *        report) or "refuse" (fail the save).
+ */
+pub const DUPLICATE_ID_POLICY: &str = "warn";
+
+/**
+ * @brief Lookup file (CSV or JSON, item_id -> category, supplier) joined
+ *        against each item before processing (empty = no enrichment).
+ */
+pub const ENRICHMENT_LOOKUP_PATH: &str = "";
+
+/**
+ * @brief What happens to items missing from the enrichment lookup table:
+ *        "warn" (process them anyway), "skip" or "fail".
+ */
+pub const ENRICHMENT_MISSING_POLICY: &str = "warn";
+
+/**
+ * @brief Secondary data file joined into the loaded items by item_id before
+ *        processing (empty = no join, see join.rs).
+ */
+pub const JOIN_PATH: &str = "";
+
+/**
+ * @brief Which loaded items the join keeps: "left" (all) or "inner" (only
+ *        those in the secondary file). Overridable with --join-type.
+ */
+pub const JOIN_TYPE: &str = "left";
+
+/**
+ * @brief Fields taken over from the secondary item: "name", "value", "tags",
+ *        "category" and "supplier".
+ */
+pub const JOIN_FIELDS: &[&str] = &["category", "supplier"];
+
+/**
+ * @brief JSON Lines file every change made to items is appended to
+ *        (empty = no audit log).
+ */
+pub const AUDIT_LOG_PATH: &str = "";
+
+/**
+ * @brief JSON Lines file a record of every pipeline run is appended to, for
+ *        the history subcommand (empty = no run history).
+ */
+pub const HISTORY_PATH: &str = "";
+
+/**
+ * @brief JSON Lines file the idempotency keys of items delivered to REST
+ *        endpoints and Kafka are appended to; items already in it are not
+ *        delivered again (empty = no delivery ledger, see delivery.rs).
+ */
+pub const DELIVERY_LEDGER_PATH: &str = "";
+
+/**
+ * @brief Webhook URLs notified when a run ends, with a Slack-compatible JSON
+ *        payload (needs the `http` feature; see notify.rs).
+ */
+pub const NOTIFY_WEBHOOKS: &[&str] = &[];
+
+/**
+ * @brief SMTP relay ("host" or "host:port") notified runs are emailed
+ *        through (empty = no email). Port 465 is TLS from the start; on other
+ *        ports STARTTLS is used if the relay offers it. Without
+ *        notify_smtp_username the relay must accept mail from this host
+ *        without authentication, e.g. the local MTA.
+ */
+pub const NOTIFY_SMTP_SERVER: &str = "";
+
+/**
+ * @brief User name to log in to the SMTP relay with (AUTH PLAIN; empty = no
+ *        login). The login is only sent over TLS, so the relay must offer
+ *        STARTTLS or listen on port 465.
+ */
+pub const NOTIFY_SMTP_USERNAME: &str = "";
+
+/**
+ * @brief Password for notify_smtp_username; a secret (see secret.rs), best
+ *        given as "env:NAME" or "file:PATH" rather than in the config file.
+ */
+pub const NOTIFY_SMTP_PASSWORD: &str = "";
+
+/**
+ * @brief Sender address of notification emails.
+ */
+pub const NOTIFY_EMAIL_FROM: &str = "";
+
+/**
+ * @brief Recipients of notification emails.
+ */
+pub const NOTIFY_EMAIL_TO: &[&str] = &[];
+
+/**
+ * @brief Which runs are notified: "always" (every run, with the summary
+ *        report) or "failure" (only runs that failed or had failed items).
+ */
+pub const NOTIFY_ON: &str = "always";
+
+/**
+ * @brief OTLP/HTTP endpoint the spans of pipeline runs are exported to,
+ *        e.g. "http://localhost:4318/v1/traces" (empty = no export; needs the
+ *        `otel` feature, see telemetry.rs).
+ */
+pub const OTEL_ENDPOINT: &str = "";
+
+/**
+ * @brief Service name the exported spans are reported under.
+ */
+pub const OTEL_SERVICE_NAME: &str = "rust_sample_project";
+
+/**
+ * @brief Record on each item the source it came from, the stages that
+ *        handled it and the rules that changed it, in its lineage field.
+ */
+pub const LINEAGE: bool = false;
+
+/**
+ * @brief Seconds to wait between two pipeline runs in watch mode.
+ */
+pub const WATCH_INTERVAL_SECS: u64 = 60;
+
+/**
+ * @brief Cron expression of the times the schedule mode runs the pipeline,
+ *        in UTC, e.g. "30 2 * * *" for 02:30 every day (see schedule.rs).
+ */
+pub const SCHEDULE: &str = "";
+
+/**
+ * @brief What the schedule mode does about scheduled runs missed while a run
+ *        was in progress or the process was down: "skip" (wait for the next
+ *        scheduled time) or "run_once" (run once right away to catch up).
+ *        Downtime is only noticed if HISTORY_PATH is set.
+ */
+pub const SCHEDULE_MISSED_RUNS: &str = "skip";
+
+/**
+ * @brief Default number of records written by the generate subcommand.
+ */
+pub const DATAGEN_COUNT: usize = 1000;
+
+/**
+ * @brief Default value distribution of generated items: "uniform:<min>,<max>",
+ *        "normal:<mean>,<std_dev>" or "exponential:<mean>".
+ */
+pub const DATAGEN_DISTRIBUTION: &str = "uniform:0,200";
+
+/**
+ * @brief Default name pattern of generated items; {id} is replaced by the
+ *        item ID and {word} by a random word.
+ */
+pub const DATAGEN_NAME_PATTERN: &str = "{word} {id}";
+
+/**
+ * @brief Default share of deliberately invalid generated records (0.0 to 1.0).
+ */
+pub const DATAGEN_INVALID_RATIO: f64 = 0.0;
+
+/**
+ * @brief Return the configured path for the data file.
+ *
+ * This function demonstrates accessing a configuration value.
+ *
+ * @return &'static str A static string slice representing the data file path.
+ */
+pub fn get_data_path() -> &'static str {
+    // In a real app, this might involve more complex logic.
+    // For demonstration, mirroring Python's print statement (commented out).
+    // Consider using the `log` crate for actual logging.
+    // println!("Config: Providing data file path: {}", DATA_FILE_PATH);
+    DATA_FILE_PATH
+}
+
+/**
+ * @brief Return the configured processing threshold.
+ *
+ * @return f64 The threshold value.
+ */
+pub fn get_threshold() -> f64 {
+    // println!("Config: Providing processing threshold: {}", PROCESSING_THRESHOLD);
+    PROCESSING_THRESHOLD
+}
+
+/**
+ * @brief Return the number of decimal places item values are kept at.
+ *
+ * @return u32 The number of decimal places.
+ */
+pub fn get_value_decimals() -> u32 {
+    VALUE_DECIMALS
+}
+
+/**
+ * @brief Return the configured kind of item id.
+ *
+ * @return &'static str A static string slice naming the id kind.
+ */
+pub fn get_item_id_kind() -> &'static str {
+    ITEM_ID_KIND
+}
+
+/**
+ * @brief Return the configured message locale.
+ *
+ * @return &'static str A static string slice naming the locale (empty = from the environment).
+ */
+pub fn get_locale() -> &'static str {
+    LOCALE
+}
+
+/**
+ * @brief Return the configured logging level.
+ *
+ * @return &'static str A static string slice representing the log level.
+ */
+pub fn get_log_level() -> &'static str {
+    // println!("Config: Providing log level: {}", LOG_LEVEL);
+    LOG_LEVEL
+}
+
+/**
+ * @brief Return the configured log output format.
+ *
+ * @return &'static str A static string slice naming the log format ("text" or "json").
+ */
+pub fn get_log_format() -> &'static str {
+    LOG_FORMAT
+}
+
+/**
+ * @brief Return when text log records are colored.
+ *
+ * @return &'static str A static string slice: "auto", "always" or "never".
+ */
+pub fn get_log_color() -> &'static str {
+    LOG_COLOR
+}
+
+/**
+ * @brief Return the configured input sources for multi-source loading.
+ *
+ * @return Vec<String> The input file paths or glob patterns (empty = use the data file).
+ */
+pub fn get_input_sources() -> Vec<String> {
+    INPUT_SOURCES.iter().map(|source| source.to_string()).collect()
+}
+
+/**
+ * @brief Return the configured merge strategy for multi-source loading.
+ *
+ * @return &'static str A static string slice naming the merge strategy.
+ */
+pub fn get_merge_strategy() -> &'static str {
+    MERGE_STRATEGY
+}
+
+/**
+ * @brief Return the configured per-category thresholds.
+ *
+ * @return &'static [(&'static str, f64)] The (category, threshold) pairs.
+ */
+pub fn get_category_thresholds() -> &'static [(&'static str, f64)] {
+    CATEGORY_THRESHOLDS
+}
+
+/**
+ * @brief Return the configured per-tag thresholds.
+ *
+ * @return &'static [(&'static str, f64)] The (tag, threshold) pairs.
+ */
+pub fn get_tag_thresholds() -> &'static [(&'static str, f64)] {
+    TAG_THRESHOLDS
+}
+
+/**
+ * @brief Return the configured output routes.
+ *
+ * @return &'static [(&'static str, &'static str)] The (sink path, condition) pairs.
+ */
+pub fn get_output_routes() -> &'static [(&'static str, &'static str)] {
+    OUTPUT_ROUTES
+}
+
+/**
+ * @brief Return the configured output path template.
+ *
+ * @return &'static str The path, or "" to save back to the data file.
+ */
+pub fn get_output_path() -> &'static str {
+    OUTPUT_PATH
+}
+
+/**
+ * @brief Return the configured compression setting for saved data files.
+ *
+ * @return &'static str A static string slice naming the compression.
+ */
+pub fn get_output_compression() -> &'static str {
+    OUTPUT_COMPRESSION
+}
+
+/**
+ * @brief Return the configured format of data files.
+ *
+ * @return &'static str A static string slice naming the data format.
+ */
+pub fn get_data_format() -> &'static str {
+    DATA_FORMAT
+}
+
+/**
+ * @brief Return the configured treatment of invalid input records.
+ *
+ * @return &'static str "lenient", "warn" or "strict".
+ */
+pub fn get_input_strictness() -> &'static str {
+    INPUT_STRICTNESS
+}
+
+/**
+ * @brief Return whether runs may drop skipped invalid records from their data file.
+ *
+ * @return bool True if the data file may be saved without them.
+ */
+pub fn get_drop_invalid_records() -> bool {
+    DROP_INVALID_RECORDS
+}
+
+/**
+ * @brief Return the configured element holding one item in XML data files.
+ *
+ * @return &'static str The element name.
+ */
+pub fn get_xml_item_element() -> &'static str {
+    XML_ITEM_ELEMENT
+}
+
+/**
+ * @brief Return the configured location of the item id in XML item elements.
+ *
+ * @return &'static str An element name, or "@" and an attribute name.
+ */
+pub fn get_xml_id_field() -> &'static str {
+    XML_ID_FIELD
+}
+
+/**
+ * @brief Return the configured location of the item name in XML item elements.
+ *
+ * @return &'static str An element name, or "@" and an attribute name.
+ */
+pub fn get_xml_name_field() -> &'static str {
+    XML_NAME_FIELD
+}
+
+/**
+ * @brief Return the configured location of the item value in XML item elements.
+ *
+ * @return &'static str An element name, or "@" and an attribute name.
+ */
+pub fn get_xml_value_field() -> &'static str {
+    XML_VALUE_FIELD
+}
+
+/**
+ * @brief Return the configured parsing mode of XML data files.
+ *
+ * @return &'static str "strict" or "lenient".
+ */
+pub fn get_xml_mode() -> &'static str {
+    XML_MODE
+}
+
+/**
+ * @brief Return the configured sheet of Excel data files.
+ *
+ * @return &'static str The sheet name, empty for the first sheet.
+ */
+pub fn get_xlsx_sheet() -> &'static str {
+    XLSX_SHEET
+}
+
+/**
+ * @brief Return the configured header of the id column in Excel data files.
+ *
+ * @return &'static str The column header.
+ */
+pub fn get_xlsx_id_column() -> &'static str {
+    XLSX_ID_COLUMN
+}
+
+/**
+ * @brief Return the configured header of the name column in Excel data files.
+ *
+ * @return &'static str The column header.
+ */
+pub fn get_xlsx_name_column() -> &'static str {
+    XLSX_NAME_COLUMN
+}
+
+/**
+ * @brief Return the configured header of the value column in Excel data files.
+ *
+ * @return &'static str The column header.
+ */
+pub fn get_xlsx_value_column() -> &'static str {
+    XLSX_VALUE_COLUMN
+}
+
+/**
+ * @brief Return the configured name of the results sheet in saved Excel data files.
+ *
+ * @return &'static str The sheet name.
+ */
+pub fn get_xlsx_results_sheet() -> &'static str {
+    XLSX_RESULTS_SHEET
+}
+
+/**
+ * @brief Return the configured environment variable holding the encryption key.
+ *
+ * @return &'static str The variable name, or "" for none.
+ */
+pub fn get_encryption_key_env() -> &'static str {
+    ENCRYPTION_KEY_ENV
+}
+
+/**
+ * @brief Return the configured encryption key file.
+ *
+ * @return &'static str The file path, or "" for none.
+ */
+pub fn get_encryption_key_file() -> &'static str {
+    ENCRYPTION_KEY_FILE
+}
+
+/**
+ * @brief Return whether plain data files load when a key is configured.
+ *
+ * @return bool True if unencrypted data files are accepted.
+ */
+pub fn get_encryption_allow_plaintext() -> bool {
+    ENCRYPTION_ALLOW_PLAINTEXT
+}
+
+/**
+ * @brief Return whether data files get checksum files that are verified on load.
+ *
+ * @return bool True if checksums are written and verified.
+ */
+pub fn get_data_checksums() -> bool {
+    DATA_CHECKSUMS
+}
+
+/**
+ * @brief Return whether loaded item names are interned.
+ *
+ * @return bool True if items with the same name share it.
+ */
+pub fn get_intern_names() -> bool {
+    INTERN_NAMES
+}
+
+/**
+ * @brief Return the lines per arena batch of streamed NDJSON data files.
+ *
+ * @return usize The batch size, 0 for line-by-line reading.
+ */
+pub fn get_stream_arena_batch() -> usize {
+    STREAM_ARENA_BATCH
+}
+
+/**
+ * @brief Return the size of a stream's queue to the process stage.
+ *
+ * @return usize The queue size in items (at least 1).
+ */
+pub fn get_stream_process_buffer() -> usize {
+    STREAM_PROCESS_BUFFER.max(1)
+}
+
+/**
+ * @brief Return the size of a stream's queue to the save stage.
+ *
+ * @return usize The queue size in items (at least 1).
+ */
+pub fn get_stream_save_buffer() -> usize {
+    STREAM_SAVE_BUFFER.max(1)
+}
+
+/**
+ * @brief Return whether the run report includes value statistics.
+ *
+ * @return bool True if analytics mode is on.
+ */
+pub fn get_analytics() -> bool {
+    ANALYTICS
+}
+
+/**
+ * @brief Return the request timeout for http(s) data sources.
+ *
+ * @return u64 The timeout in seconds.
+ */
+#[cfg(feature = "http")]
+pub fn get_http_timeout_secs() -> u64 {
+    HTTP_TIMEOUT_SECS
+}
+
+/**
+ * @brief Return the number of retries for http(s) data sources.
+ *
+ * @return u32 The number of retries after the first attempt.
+ */
+#[cfg(feature = "http")]
+pub fn get_http_retries() -> u32 {
+    HTTP_RETRIES
+}
+
+/**
+ * @brief Return the extra headers for http(s) data sources.
+ *
+ * @return &'static [(&'static str, &'static str)] The (name, value) pairs.
+ */
+#[cfg(feature = "http")]
+pub fn get_http_headers() -> &'static [(&'static str, &'static str)] {
+    HTTP_HEADERS
+}
+
+/**
+ * @brief Return the name of the environment variable holding the bearer token.
+ *
+ * @return &'static str The environment variable name.
+ */
+#[cfg(feature = "http")]
+pub fn get_http_auth_token_env() -> &'static str {
+    HTTP_AUTH_TOKEN_ENV
+}
+
+/**
+ * @brief Return whether processed items are POSTed back to http(s) data sources.
+ *
+ * @return bool True if results are posted back.
+ */
+#[cfg(feature = "http")]
+pub fn get_http_post_results() -> bool {
+    HTTP_POST_RESULTS
+}
+
+/**
+ * @brief Return the Kafka bootstrap brokers.
+ *
+ * @return Vec<String> The broker addresses (host:port).
+ */
+#[cfg(feature = "kafka")]
+pub fn get_kafka_brokers() -> Vec<String> {
+    KAFKA_BROKERS.iter().map(|broker| broker.to_string()).collect()
+}
+
+/**
+ * @brief Return the Kafka topic items are consumed from.
+ *
+ * @return &'static str The input topic.
+ */
+#[cfg(feature = "kafka")]
+pub fn get_kafka_input_topic() -> &'static str {
+    KAFKA_INPUT_TOPIC
+}
+
+/**
+ * @brief Return the Kafka topic processed items are emitted to.
+ *
+ * @return &'static str The output topic.
+ */
+#[cfg(feature = "kafka")]
+pub fn get_kafka_output_topic() -> &'static str {
+    KAFKA_OUTPUT_TOPIC
+}
+
+/**
+ * @brief Return the Kafka consumer group.
+ *
+ * @return &'static str The consumer group id.
+ */
+#[cfg(feature = "kafka")]
+pub fn get_kafka_consumer_group() -> &'static str {
+    KAFKA_CONSUMER_GROUP
+}
+
+/**
+ * @brief Return the address the daemon API listens on.
+ *
+ * @return &'static str The socket address, e.g. "127.0.0.1:8080".
+ */
+pub fn get_daemon_addr() -> &'static str {
+    DAEMON_ADDR
+}
+
+/**
+ * @brief Return the address the gRPC service listens on.
+ *
+ * @return &'static str The socket address, e.g. "127.0.0.1:50051".
+ */
+pub fn get_grpc_addr() -> &'static str {
+    GRPC_ADDR
+}
+
+/**
+ * @brief Return the number of worker threads processing items.
+ *
+ * @return usize The number of worker threads (at least 1).
+ */
+pub fn get_worker_threads() -> usize {
+    WORKER_THREADS.max(1)
+}
+
+/**
+ * @brief Return the capacity of the worker pool queues.
+ *
+ * @return usize The queue capacity (at least 1).
+ */
+pub fn get_worker_queue_capacity() -> usize {
+    WORKER_QUEUE_CAPACITY.max(1)
+}
+
+/**
+ * @brief Return the rate limit in items per second.
+ *
+ * @return f64 The limit, 0 for none.
+ */
+pub fn get_rate_limit() -> f64 {
+    RATE_LIMIT
+}
+
+/**
+ * @brief Return the burst size of the rate limit.
+ *
+ * @return usize The most items let through at once (at least 1).
+ */
+pub fn get_rate_limit_burst() -> usize {
+    RATE_LIMIT_BURST.max(1)
+}
+
+/**
+ * @brief Return the configured priority policy.
+ *
+ * @return &'static str The policy, see PriorityPolicy::parse.
+ */
+pub fn get_priority_policy() -> &'static str {
+    PRIORITY_POLICY
+}
+
+/**
+ * @brief Return the longest time a run spends processing.
+ *
+ * @return u64 The limit in seconds (0 = no limit).
+ */
+pub fn get_max_duration_secs() -> u64 {
+    MAX_DURATION_SECS
+}
+
+/**
+ * @brief Return the number of items a sample run processes.
+ *
+ * @return usize The sample size (0 = all items).
+ */
+pub fn get_sample_size() -> usize {
+    SAMPLE_SIZE
+}
+
+/**
+ * @brief Return the percentage of items a sample run processes.
+ *
+ * @return f64 The percentage (0 = all items).
+ */
+pub fn get_sample_percent() -> f64 {
+    SAMPLE_PERCENT
+}
+
+/**
+ * @brief Return the seed samples are drawn with.
+ *
+ * @return u64 The seed.
+ */
+pub fn get_sample_seed() -> u64 {
+    SAMPLE_SEED
+}
+
+/**
+ * @brief Return the configured anomaly detection method.
+ *
+ * @return &'static str "off", "stddev" or "iqr".
+ */
+pub fn get_anomaly_detection() -> &'static str {
+    ANOMALY_DETECTION
+}
+
+/**
+ * @brief Return how far outside the normal range a value must lie to be an outlier.
+ *
+ * @return f64 The factor applied to the standard deviation or interquartile range.
+ */
+pub fn get_anomaly_factor() -> f64 {
+    ANOMALY_FACTOR
+}
+
+/**
+ * @brief Return the path of the review sink for outliers.
+ *
+ * @return &'static str A static string slice representing the review sink path.
+ */
+pub fn get_anomaly_review_path() -> &'static str {
+    ANOMALY_REVIEW_PATH
+}
+
+/**
+ * @brief Return the groupings summarized after each run.
+ *
+ * @return Vec<String> The groupings, see AGGREGATE_BY.
+ */
+pub fn get_aggregate_by() -> Vec<String> {
+    AGGREGATE_BY.iter().map(|grouping| grouping.to_string()).collect()
+}
+
+/**
+ * @brief Return the path of the summary file.
+ *
+ * @return &'static str A static string slice representing the summary file path.
+ */
+pub fn get_aggregate_path() -> &'static str {
+    AGGREGATE_PATH
+}
+
+/**
+ * @brief Return the configured summary file format.
+ *
+ * @return &'static str "auto", "json" or "csv".
+ */
+pub fn get_aggregate_format() -> &'static str {
+    AGGREGATE_FORMAT
+}
+
+/**
+ * @brief Return the per-item processing timeout.
+ *
+ * @return u64 The timeout in milliseconds (0 = no limit).
+ */
+pub fn get_item_timeout_ms() -> u64 {
+    ITEM_TIMEOUT_MS
+}
+
+/**
+ * @brief Return the largest number of items per batched write.
+ *
+ * @return usize The batch size.
+ */
+pub fn get_save_batch_size() -> usize {
+    SAVE_BATCH_SIZE.max(1)
+}
+
+/**
+ * @brief Return the interval after which a pending batch is flushed.
+ *
+ * @return u64 The flush interval in milliseconds (0 = size-based flushing only).
+ */
+pub fn get_save_flush_interval_ms() -> u64 {
+    SAVE_FLUSH_INTERVAL_MS
+}
+
+/**
+ * @brief Return the largest number of items per shard.
+ *
+ * @return usize The item limit (0 = none).
+ */
+pub fn get_shard_max_items() -> usize {
+    SHARD_MAX_ITEMS
+}
+
+/**
+ * @brief Return the largest size of a shard's items.
+ *
+ * @return u64 The size limit in bytes (0 = none).
+ */
+pub fn get_shard_max_bytes() -> u64 {
+    SHARD_MAX_BYTES
+}
+
+/**
+ * @brief Return the configured path for the checkpoint file.
+ *
+ * @return &'static str A static string slice representing the checkpoint path.
+ */
+pub fn get_checkpoint_path() -> &'static str {
+    CHECKPOINT_FILE_PATH
+}
+
+/**
+ * @brief Return the configured staging directory for two-phase saves.
+ *
+ * @return &'static str A static string slice representing the staging directory.
+ */
+pub fn get_staging_dir() -> &'static str {
+    STAGING_DIR
+}
+
+/**
+ * @brief Return the configured snapshot directory.
+ *
+ * @return &'static str The directory holding the data file snapshots.
+ */
+pub fn get_snapshot_dir() -> &'static str {
+    SNAPSHOT_DIR
+}
+
+/**
+ * @brief Return how many snapshots are kept.
+ *
+ * @return usize The number of snapshots (0 = none are taken).
+ */
+pub fn get_snapshot_retention() -> usize {
+    SNAPSHOT_RETENTION
+}
+
+/**
+ * @brief Return whether destructive writes are allowed.
+ *
+ * @return bool True if existing data files may be overwritten.
+ */
+pub fn get_allow_overwrite() -> bool {
+    ALLOW_OVERWRITE
+}
+
+/**
+ * @brief Return how many items are processed between checkpoint writes.
+ *
+ * @return usize The checkpoint interval (always at least 1).
+ */
+pub fn get_checkpoint_interval() -> usize {
+    CHECKPOINT_INTERVAL.max(1)
+}
 
 /**
- * @brief Path to a (simulated) data file used by DataHandler.
+ * @brief Return the configured policy for a data file locked by another run.
+ *
+ * @return &'static str A static string slice naming the policy.
  */
-pub const DATA_FILE_PATH: &str = "data/items.json";
+pub fn get_lock_policy() -> &'static str {
+    LOCK_POLICY
+}
 
 /**
- * @brief A processing parameter used by ItemProcessor.
+ * @brief Return how long a run waits for the data file's lock.
+ *
+ * @return u64 The lock timeout in seconds (0 = no limit).
  */
-pub const PROCESSING_THRESHOLD: i32 = 100;
+pub fn get_lock_timeout_secs() -> u64 {
+    LOCK_TIMEOUT_SECS
+}
 
 /**
- * @brief Example setting for logging level (could be used by main for a logging crate).
+ * @brief Return whether items already marked processed are skipped.
+ *
+ * @return bool True for incremental runs.
  */
-pub const LOG_LEVEL: &str = "INFO";
+pub fn get_incremental() -> bool {
+    INCREMENTAL
+}
 
 /**
- * @brief Return the configured path for the data file.
+ * @brief Return the tags of which an item needs at least one to be processed.
  *
- * This function demonstrates accessing a configuration value.
+ * @return Vec<String> The required tags (empty means every item is processed).
+ */
+pub fn get_required_tags() -> Vec<String> {
+    REQUIRED_TAGS.iter().map(|tag| tag.to_string()).collect()
+}
+
+/**
+ * @brief Return the tags that exclude an item from processing.
  *
- * @return &'static str A static string slice representing the data file path.
+ * @return Vec<String> The excluded tags.
  */
-pub fn get_data_path() -> &'static str {
-    // In a real app, this might involve more complex logic.
-    // For demonstration, mirroring Python's print statement (commented out).
-    // Consider using the `log` crate for actual logging.
-    // println!("Config: Providing data file path: {}", DATA_FILE_PATH);
-    DATA_FILE_PATH
+pub fn get_excluded_tags() -> Vec<String> {
+    EXCLUDED_TAGS.iter().map(|tag| tag.to_string()).collect()
 }
 
 /**
- * @brief Return the configured processing threshold.
+ * @brief Return the configured filter expression.
  *
- * @return i32 The integer threshold value.
+ * @return &'static str The filter expression (empty means no filtering).
  */
-pub fn get_threshold() -> i32 {
-    // println!("Config: Providing processing threshold: {}", PROCESSING_THRESHOLD);
-    PROCESSING_THRESHOLD
+pub fn get_filter_expression() -> &'static str {
+    FILTER_EXPRESSION
 }
 
 /**
- * @brief Return the configured logging level.
+ * @brief Return the configured value transformation rules.
  *
- * @return &'static str A static string slice representing the log level.
+ * @return &'static [&'static str] The transformation rules as written in config.
  */
-pub fn get_log_level() -> &'static str {
-    // println!("Config: Providing log level: {}", LOG_LEVEL);
-    LOG_LEVEL
+pub fn get_transformations() -> &'static [&'static str] {
+    TRANSFORMATIONS
+}
+
+/**
+ * @brief Return the names of the registered stages applied to each item.
+ *
+ * @return Vec<String> The stage names (empty means none).
+ */
+pub fn get_custom_stages() -> Vec<String> {
+    CUSTOM_STAGES.iter().map(|name| name.to_string()).collect()
+}
+
+/**
+ * @brief Return the configured strategy for resolving duplicate item ids.
+ *
+ * @return &'static str A static string slice naming the dedup strategy.
+ */
+pub fn get_dedup_strategy() -> &'static str {
+    DEDUP_STRATEGY
+}
+
+/**
+ * @brief Return the configured policy for duplicate item ids in saved output.
+ *
+ * @return &'static str "allow", "warn" or "refuse".
+ */
+pub fn get_duplicate_id_policy() -> &'static str {
+    DUPLICATE_ID_POLICY
+}
+
+/**
+ * @brief Return the configured path of the enrichment lookup file.
+ *
+ * @return &'static str The lookup file path (empty = no enrichment).
+ */
+pub fn get_enrichment_lookup_path() -> &'static str {
+    ENRICHMENT_LOOKUP_PATH
+}
+
+/**
+ * @brief Return the configured policy for items missing from the lookup table.
+ *
+ * @return &'static str A static string slice naming the policy.
+ */
+pub fn get_enrichment_missing_policy() -> &'static str {
+    ENRICHMENT_MISSING_POLICY
+}
+
+/**
+ * @brief Return the path of the secondary data file to join.
+ *
+ * @return &'static str A static string slice representing the path (empty = no join).
+ */
+pub fn get_join_path() -> &'static str {
+    JOIN_PATH
+}
+
+/**
+ * @brief Return the configured join type.
+ *
+ * @return &'static str "left" or "inner".
+ */
+pub fn get_join_type() -> &'static str {
+    JOIN_TYPE
+}
+
+/**
+ * @brief Return the fields taken over by the join.
+ *
+ * @return Vec<String> The field names, see JOIN_FIELDS.
+ */
+pub fn get_join_fields() -> Vec<String> {
+    JOIN_FIELDS.iter().map(|field| field.to_string()).collect()
+}
+
+/**
+ * @brief Return the configured audit log path.
+ *
+ * @return &'static str A static string slice representing the audit log path.
+ */
+pub fn get_audit_log_path() -> &'static str {
+    AUDIT_LOG_PATH
+}
+
+/**
+ * @brief Return the configured run history path.
+ *
+ * @return &'static str The path, or "" for no run history.
+ */
+pub fn get_history_path() -> &'static str {
+    HISTORY_PATH
+}
+
+/**
+ * @brief Return the configured delivery ledger path.
+ *
+ * @return &'static str The path, or "" for no delivery ledger.
+ */
+pub fn get_delivery_ledger_path() -> &'static str {
+    DELIVERY_LEDGER_PATH
+}
+
+/**
+ * @brief Return the configured notification webhooks.
+ *
+ * @return Vec<String> The webhook URLs.
+ */
+pub fn get_notify_webhooks() -> Vec<String> {
+    NOTIFY_WEBHOOKS.iter().map(|url| url.to_string()).collect()
+}
+
+/**
+ * @brief Return the configured SMTP relay for notification emails.
+ *
+ * @return &'static str The relay, or "" for no email.
+ */
+pub fn get_notify_smtp_server() -> &'static str {
+    NOTIFY_SMTP_SERVER
+}
+
+/**
+ * @brief Return the configured SMTP login.
+ *
+ * @return &'static str The user name, or "" for no login.
+ */
+pub fn get_notify_smtp_username() -> &'static str {
+    NOTIFY_SMTP_USERNAME
+}
+
+/**
+ * @brief Return the configured SMTP password.
+ *
+ * @return Secret<String> The password, or a reference to it.
+ */
+pub fn get_notify_smtp_password() -> Secret<String> {
+    Secret::new(NOTIFY_SMTP_PASSWORD.to_string())
+}
+
+/**
+ * @brief Return the configured sender of notification emails.
+ *
+ * @return &'static str The sender address.
+ */
+pub fn get_notify_email_from() -> &'static str {
+    NOTIFY_EMAIL_FROM
+}
+
+/**
+ * @brief Return the configured recipients of notification emails.
+ *
+ * @return Vec<String> The recipient addresses.
+ */
+pub fn get_notify_email_to() -> Vec<String> {
+    NOTIFY_EMAIL_TO.iter().map(|address| address.to_string()).collect()
+}
+
+/**
+ * @brief Return which runs are notified.
+ *
+ * @return &'static str "always" or "failure".
+ */
+pub fn get_notify_on() -> &'static str {
+    NOTIFY_ON
+}
+
+/**
+ * @brief Return the configured OTLP endpoint.
+ *
+ * @return &'static str The endpoint, or "" for no span export.
+ */
+pub fn get_otel_endpoint() -> &'static str {
+    OTEL_ENDPOINT
+}
+
+/**
+ * @brief Return the configured service name of exported spans.
+ *
+ * @return &'static str The service name.
+ */
+pub fn get_otel_service_name() -> &'static str {
+    OTEL_SERVICE_NAME
+}
+
+/**
+ * @brief Return whether items record their lineage.
+ *
+ * @return bool True if lineage is tracked.
+ */
+pub fn get_lineage() -> bool {
+    LINEAGE
+}
+
+/**
+ * @brief Return the delay between pipeline runs in watch mode.
+ *
+ * @return u64 The watch interval in seconds.
+ */
+pub fn get_watch_interval_secs() -> u64 {
+    WATCH_INTERVAL_SECS
+}
+
+/**
+ * @brief Return the configured run schedule.
+ *
+ * @return &'static str The cron expression, or "" if none is set.
+ */
+pub fn get_schedule() -> &'static str {
+    SCHEDULE
+}
+
+/**
+ * @brief Return the configured missed run policy of the schedule mode.
+ *
+ * @return &'static str "skip" or "run_once".
+ */
+pub fn get_schedule_missed_runs() -> &'static str {
+    SCHEDULE_MISSED_RUNS
+}
+
+/**
+ * @brief Return the default number of generated records.
+ *
+ * @return usize The record count.
+ */
+pub fn get_datagen_count() -> usize {
+    DATAGEN_COUNT
+}
+
+/**
+ * @brief Return the default value distribution of generated items.
+ *
+ * @return &'static str The distribution spec.
+ */
+pub fn get_datagen_distribution() -> &'static str {
+    DATAGEN_DISTRIBUTION
+}
+
+/**
+ * @brief Return the default name pattern of generated items.
+ *
+ * @return &'static str The name pattern.
+ */
+pub fn get_datagen_name_pattern() -> &'static str {
+    DATAGEN_NAME_PATTERN
+}
+
+/**
+ * @brief Return the default share of invalid generated records.
+ *
+ * @return f64 The ratio, between 0.0 and 1.0.
+ */
+pub fn get_datagen_invalid_ratio() -> f64 {
+    DATAGEN_INVALID_RATIO
+}
+
+/**
+ * @enum ConfigSource
+ * @brief Where an effective configuration value came from.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// The built-in default (the constants above).
+    Default,
+    /// The config file at this path.
+    File(String),
+    /// The profile of this name in the config file at this path.
+    Profile { file: String, name: String },
+    /// The pipeline of this name in the config file at this path.
+    Pipeline { file: String, name: String },
+    /// The tenant of this name in the config file at this path.
+    Tenant { file: String, name: String },
+    /// This environment variable.
+    Env(String),
+    /// A command-line flag.
+    Cli,
+    /// An embedding application, e.g. a Python caller of run_pipeline or a C
+    /// caller of sp_pipeline_configure.
+    Api,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::File(path) => write!(f, "file {}", path),
+            ConfigSource::Profile { file, name } => write!(f, "profile {} in file {}", name, file),
+            ConfigSource::Pipeline { file, name } => write!(f, "pipeline {} in file {}", name, file),
+            ConfigSource::Tenant { file, name } => write!(f, "tenant {} in file {}", name, file),
+            ConfigSource::Env(var) => write!(f, "env {}", var),
+            ConfigSource::Cli => write!(f, "command line"),
+            ConfigSource::Api => write!(f, "api"),
+        }
+    }
+}
+
+/// Keys of all configuration values, in the order they are listed and checked.
+pub const CONFIG_KEYS: &[&str] = &[
+    "data_path",
+    "threshold",
+    "category_thresholds",
+    "tag_thresholds",
+    "value_decimals",
+    "item_id_kind",
+    "locale",
+    "log_level",
+    "log_format",
+    "log_color",
+    "input_sources",
+    "merge_strategy",
+    "output_routes",
+    "output_path",
+    "output_compression",
+    "data_format",
+    "input_strictness",
+    "drop_invalid_records",
+    "xml_item_element",
+    "xml_id_field",
+    "xml_name_field",
+    "xml_value_field",
+    "xml_mode",
+    "xlsx_sheet",
+    "xlsx_id_column",
+    "xlsx_name_column",
+    "xlsx_value_column",
+    "xlsx_results_sheet",
+    "encryption_key_env",
+    "encryption_key_file",
+    "encryption_allow_plaintext",
+    "data_checksums",
+    "intern_names",
+    "stream_arena_batch",
+    "stream_process_buffer",
+    "stream_save_buffer",
+    "analytics",
+    "daemon_addr",
+    "grpc_addr",
+    "worker_threads",
+    "worker_queue_capacity",
+    "rate_limit",
+    "rate_limit_burst",
+    "priority_policy",
+    "max_duration_secs",
+    "sample_size",
+    "sample_percent",
+    "sample_seed",
+    "anomaly_detection",
+    "anomaly_factor",
+    "anomaly_review_path",
+    "aggregate_by",
+    "aggregate_path",
+    "aggregate_format",
+    "item_timeout_ms",
+    "save_batch_size",
+    "save_flush_interval_ms",
+    "shard_max_items",
+    "shard_max_bytes",
+    "checkpoint_path",
+    "checkpoint_interval",
+    "staging_dir",
+    "snapshot_dir",
+    "snapshot_retention",
+    "allow_overwrite",
+    "lock_policy",
+    "lock_timeout_secs",
+    "incremental",
+    "required_tags",
+    "excluded_tags",
+    "filter_expression",
+    "transformations",
+    "custom_stages",
+    "dedup_strategy",
+    "duplicate_id_policy",
+    "enrichment_lookup_path",
+    "enrichment_missing_policy",
+    "join_path",
+    "join_type",
+    "join_fields",
+    "audit_log_path",
+    "history_path",
+    "delivery_ledger_path",
+    "notify_webhooks",
+    "notify_smtp_server",
+    "notify_smtp_username",
+    "notify_smtp_password",
+    "notify_email_from",
+    "notify_email_to",
+    "notify_on",
+    "otel_endpoint",
+    "otel_service_name",
+    "lineage",
+    "watch_interval_secs",
+    "schedule",
+    "schedule_missed_runs",
+];
+
+/// Path keys a selected tenant must set in its own table (unless empty), so
+/// tenants never share data, checkpoints, run history or audit logs.
+const TENANT_PATH_KEYS: [&str; 4] = ["data_path", "checkpoint_path", "history_path", "audit_log_path"];
+
+include!(concat!(env!("OUT_DIR"), "/config_docs.rs"));
+
+/**
+ * @brief Return the description of a configuration key, the doc comment of
+ *        its default constant (collected by build.rs).
+ *
+ * @param key The configuration key (see CONFIG_KEYS).
+ * @return Option<&'static str> The description, or None for unknown keys.
+ */
+pub fn describe(key: &str) -> Option<&'static str> {
+    CONFIG_KEY_DOCS.iter().find(|(name, _)| *name == key).map(|(_, description)| *description)
+}
+
+/**
+ * @struct ConfigProblem
+ * @brief A single invalid or conflicting configuration value.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProblem {
+    /// The configuration key, e.g. "threshold" or "transformations[2]".
+    pub key: String,
+    /// Where the offending value came from.
+    pub source: ConfigSource,
+    /// What is wrong with it.
+    pub message: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (from {}): {}", self.key, self.source, self.message)
+    }
+}
+
+/**
+ * @struct ConfigErrors
+ * @brief All problems found by Config::validate, reported together.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigErrors {
+    /// The problems, in the order the keys were checked.
+    pub problems: Vec<ConfigProblem>,
+}
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = self.problems.len();
+        let plural = if count == 1 { "" } else { "s" };
+        write!(f, "Invalid configuration ({} problem{}):", count, plural)?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+/**
+ * @struct Config
+ * @brief The effective configuration of a run.
+ *
+ * Starts from the defaults above; Config::load applies the config file and
+ * the environment, callers apply command-line values with Config::set. Every
+ * override records where it came from, so problems can be reported against
+ * the exact key and source.
+ */
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// See DATA_FILE_PATH.
+    pub data_path: PathBuf,
+    /// See PROCESSING_THRESHOLD.
+    pub threshold: f64,
+    /// See CATEGORY_THRESHOLDS.
+    pub category_thresholds: Vec<(String, f64)>,
+    /// See TAG_THRESHOLDS.
+    pub tag_thresholds: Vec<(String, f64)>,
+    /// See VALUE_DECIMALS.
+    pub value_decimals: u32,
+    /// See ITEM_ID_KIND.
+    pub item_id_kind: String,
+    /// See LOCALE.
+    pub locale: String,
+    /// See LOG_LEVEL.
+    pub log_level: String,
+    /// See LOG_FORMAT.
+    pub log_format: String,
+    /// See LOG_COLOR.
+    pub log_color: String,
+    /// See INPUT_SOURCES.
+    pub input_sources: Vec<String>,
+    /// See MERGE_STRATEGY.
+    pub merge_strategy: String,
+    /// See OUTPUT_ROUTES.
+    pub output_routes: Vec<(String, String)>,
+    /// See OUTPUT_PATH.
+    pub output_path: PathBuf,
+    /// See OUTPUT_COMPRESSION.
+    pub output_compression: String,
+    /// See DATA_FORMAT.
+    pub data_format: String,
+    /// See INPUT_STRICTNESS.
+    pub input_strictness: String,
+    /// See DROP_INVALID_RECORDS.
+    pub drop_invalid_records: bool,
+    /// See XML_ITEM_ELEMENT.
+    pub xml_item_element: String,
+    /// See XML_ID_FIELD.
+    pub xml_id_field: String,
+    /// See XML_NAME_FIELD.
+    pub xml_name_field: String,
+    /// See XML_VALUE_FIELD.
+    pub xml_value_field: String,
+    /// See XML_MODE.
+    pub xml_mode: String,
+    /// See XLSX_SHEET.
+    pub xlsx_sheet: String,
+    /// See XLSX_ID_COLUMN.
+    pub xlsx_id_column: String,
+    /// See XLSX_NAME_COLUMN.
+    pub xlsx_name_column: String,
+    /// See XLSX_VALUE_COLUMN.
+    pub xlsx_value_column: String,
+    /// See XLSX_RESULTS_SHEET.
+    pub xlsx_results_sheet: String,
+    /// See ENCRYPTION_KEY_ENV.
+    pub encryption_key_env: String,
+    /// See ENCRYPTION_KEY_FILE.
+    pub encryption_key_file: PathBuf,
+    /// See ENCRYPTION_ALLOW_PLAINTEXT.
+    pub encryption_allow_plaintext: bool,
+    /// See DATA_CHECKSUMS.
+    pub data_checksums: bool,
+    /// See INTERN_NAMES.
+    pub intern_names: bool,
+    /// See STREAM_ARENA_BATCH.
+    pub stream_arena_batch: usize,
+    /// See STREAM_PROCESS_BUFFER.
+    pub stream_process_buffer: usize,
+    /// See STREAM_SAVE_BUFFER.
+    pub stream_save_buffer: usize,
+    /// See ANALYTICS.
+    pub analytics: bool,
+    /// See DAEMON_ADDR.
+    pub daemon_addr: String,
+    /// See GRPC_ADDR.
+    pub grpc_addr: String,
+    /// See WORKER_THREADS.
+    pub worker_threads: usize,
+    /// See WORKER_QUEUE_CAPACITY.
+    pub worker_queue_capacity: usize,
+    /// See RATE_LIMIT.
+    pub rate_limit: f64,
+    /// See RATE_LIMIT_BURST.
+    pub rate_limit_burst: usize,
+    /// See PRIORITY_POLICY.
+    pub priority_policy: String,
+    /// See MAX_DURATION_SECS.
+    pub max_duration_secs: u64,
+    /// See SAMPLE_SIZE.
+    pub sample_size: usize,
+    /// See SAMPLE_PERCENT.
+    pub sample_percent: f64,
+    /// See SAMPLE_SEED.
+    pub sample_seed: u64,
+    /// See ANOMALY_DETECTION.
+    pub anomaly_detection: String,
+    /// See ANOMALY_FACTOR.
+    pub anomaly_factor: f64,
+    /// See ANOMALY_REVIEW_PATH.
+    pub anomaly_review_path: PathBuf,
+    /// See AGGREGATE_BY.
+    pub aggregate_by: Vec<String>,
+    /// See AGGREGATE_PATH.
+    pub aggregate_path: PathBuf,
+    /// See AGGREGATE_FORMAT.
+    pub aggregate_format: String,
+    /// See ITEM_TIMEOUT_MS.
+    pub item_timeout_ms: u64,
+    /// See SAVE_BATCH_SIZE.
+    pub save_batch_size: usize,
+    /// See SAVE_FLUSH_INTERVAL_MS.
+    pub save_flush_interval_ms: u64,
+    /// See SHARD_MAX_ITEMS.
+    pub shard_max_items: usize,
+    /// See SHARD_MAX_BYTES.
+    pub shard_max_bytes: u64,
+    /// See CHECKPOINT_FILE_PATH.
+    pub checkpoint_path: PathBuf,
+    /// See STAGING_DIR.
+    pub staging_dir: PathBuf,
+    /// See SNAPSHOT_DIR.
+    pub snapshot_dir: PathBuf,
+    /// See SNAPSHOT_RETENTION.
+    pub snapshot_retention: usize,
+    /// See ALLOW_OVERWRITE.
+    pub allow_overwrite: bool,
+    /// See LOCK_POLICY.
+    pub lock_policy: String,
+    /// See LOCK_TIMEOUT_SECS.
+    pub lock_timeout_secs: u64,
+    /// See INCREMENTAL.
+    pub incremental: bool,
+    /// See CHECKPOINT_INTERVAL.
+    pub checkpoint_interval: usize,
+    /// See REQUIRED_TAGS.
+    pub required_tags: Vec<String>,
+    /// See EXCLUDED_TAGS.
+    pub excluded_tags: Vec<String>,
+    /// See FILTER_EXPRESSION.
+    pub filter_expression: String,
+    /// See TRANSFORMATIONS.
+    pub transformations: Vec<String>,
+    /// See CUSTOM_STAGES.
+    pub custom_stages: Vec<String>,
+    /// See DEDUP_STRATEGY.
+    pub dedup_strategy: String,
+    /// See DUPLICATE_ID_POLICY.
+    pub duplicate_id_policy: String,
+    /// See ENRICHMENT_LOOKUP_PATH.
+    pub enrichment_lookup_path: PathBuf,
+    /// See ENRICHMENT_MISSING_POLICY.
+    pub enrichment_missing_policy: String,
+    /// See JOIN_PATH.
+    pub join_path: PathBuf,
+    /// See JOIN_TYPE.
+    pub join_type: String,
+    /// See JOIN_FIELDS.
+    pub join_fields: Vec<String>,
+    /// See AUDIT_LOG_PATH.
+    pub audit_log_path: PathBuf,
+    /// See HISTORY_PATH.
+    pub history_path: PathBuf,
+    /// See DELIVERY_LEDGER_PATH.
+    pub delivery_ledger_path: PathBuf,
+    /// See NOTIFY_WEBHOOKS.
+    pub notify_webhooks: Vec<String>,
+    /// See NOTIFY_SMTP_SERVER.
+    pub notify_smtp_server: String,
+    /// See NOTIFY_SMTP_USERNAME.
+    pub notify_smtp_username: String,
+    /// See NOTIFY_SMTP_PASSWORD.
+    pub notify_smtp_password: Secret<String>,
+    /// See NOTIFY_EMAIL_FROM.
+    pub notify_email_from: String,
+    /// See NOTIFY_EMAIL_TO.
+    pub notify_email_to: Vec<String>,
+    /// See NOTIFY_ON.
+    pub notify_on: String,
+    /// See OTEL_ENDPOINT.
+    pub otel_endpoint: String,
+    /// See OTEL_SERVICE_NAME.
+    pub otel_service_name: String,
+    /// See LINEAGE.
+    pub lineage: bool,
+    /// See WATCH_INTERVAL_SECS.
+    pub watch_interval_secs: u64,
+    /// See SCHEDULE.
+    pub schedule: String,
+    /// See SCHEDULE_MISSED_RUNS.
+    pub schedule_missed_runs: String,
+    /// The config profile applied on top of the config file, if any.
+    pub profile: Option<String>,
+    /// The pipeline of the config file applied on top of the profile, if any.
+    pub pipeline: Option<String>,
+    /// Names of the pipelines defined in the config file ([pipelines.<name>]).
+    pub pipelines: Vec<String>,
+    /// The tenant of the config file applied on top of the profile, if any.
+    pub tenant: Option<String>,
+    /// Names of the tenants defined in the config file ([tenants.<name>]).
+    pub tenants: Vec<String>,
+    /// Sources of the values that do not come from the defaults, by key.
+    sources: BTreeMap<&'static str, ConfigSource>,
+}
+
+impl Config {
+    /**
+     * @brief Constructs a configuration holding the built-in defaults.
+     *
+     * @return Config The default configuration.
+     */
+    pub fn from_defaults() -> Self {
+        Config {
+            data_path: PathBuf::from(DATA_FILE_PATH),
+            threshold: PROCESSING_THRESHOLD,
+            category_thresholds: owned_thresholds(CATEGORY_THRESHOLDS),
+            tag_thresholds: owned_thresholds(TAG_THRESHOLDS),
+            value_decimals: VALUE_DECIMALS,
+            item_id_kind: ITEM_ID_KIND.to_string(),
+            locale: LOCALE.to_string(),
+            log_level: LOG_LEVEL.to_string(),
+            log_format: LOG_FORMAT.to_string(),
+            log_color: LOG_COLOR.to_string(),
+            input_sources: get_input_sources(),
+            merge_strategy: MERGE_STRATEGY.to_string(),
+            output_routes: OUTPUT_ROUTES
+                .iter()
+                .map(|(sink, condition)| (sink.to_string(), condition.to_string()))
+                .collect(),
+            output_path: PathBuf::from(OUTPUT_PATH),
+            output_compression: OUTPUT_COMPRESSION.to_string(),
+            data_format: DATA_FORMAT.to_string(),
+            input_strictness: INPUT_STRICTNESS.to_string(),
+            drop_invalid_records: DROP_INVALID_RECORDS,
+            xml_item_element: XML_ITEM_ELEMENT.to_string(),
+            xml_id_field: XML_ID_FIELD.to_string(),
+            xml_name_field: XML_NAME_FIELD.to_string(),
+            xml_value_field: XML_VALUE_FIELD.to_string(),
+            xml_mode: XML_MODE.to_string(),
+            xlsx_sheet: XLSX_SHEET.to_string(),
+            xlsx_id_column: XLSX_ID_COLUMN.to_string(),
+            xlsx_name_column: XLSX_NAME_COLUMN.to_string(),
+            xlsx_value_column: XLSX_VALUE_COLUMN.to_string(),
+            xlsx_results_sheet: XLSX_RESULTS_SHEET.to_string(),
+            encryption_key_env: ENCRYPTION_KEY_ENV.to_string(),
+            encryption_key_file: PathBuf::from(ENCRYPTION_KEY_FILE),
+            encryption_allow_plaintext: ENCRYPTION_ALLOW_PLAINTEXT,
+            data_checksums: DATA_CHECKSUMS,
+            intern_names: INTERN_NAMES,
+            stream_arena_batch: STREAM_ARENA_BATCH,
+            stream_process_buffer: STREAM_PROCESS_BUFFER,
+            stream_save_buffer: STREAM_SAVE_BUFFER,
+            analytics: ANALYTICS,
+            daemon_addr: DAEMON_ADDR.to_string(),
+            grpc_addr: GRPC_ADDR.to_string(),
+            worker_threads: WORKER_THREADS,
+            worker_queue_capacity: WORKER_QUEUE_CAPACITY,
+            rate_limit: RATE_LIMIT,
+            rate_limit_burst: RATE_LIMIT_BURST,
+            priority_policy: PRIORITY_POLICY.to_string(),
+            max_duration_secs: MAX_DURATION_SECS,
+            sample_size: SAMPLE_SIZE,
+            sample_percent: SAMPLE_PERCENT,
+            sample_seed: SAMPLE_SEED,
+            anomaly_detection: ANOMALY_DETECTION.to_string(),
+            anomaly_factor: ANOMALY_FACTOR,
+            anomaly_review_path: PathBuf::from(ANOMALY_REVIEW_PATH),
+            aggregate_by: get_aggregate_by(),
+            aggregate_path: PathBuf::from(AGGREGATE_PATH),
+            aggregate_format: AGGREGATE_FORMAT.to_string(),
+            item_timeout_ms: ITEM_TIMEOUT_MS,
+            save_batch_size: SAVE_BATCH_SIZE,
+            save_flush_interval_ms: SAVE_FLUSH_INTERVAL_MS,
+            shard_max_items: SHARD_MAX_ITEMS,
+            shard_max_bytes: SHARD_MAX_BYTES,
+            checkpoint_path: PathBuf::from(CHECKPOINT_FILE_PATH),
+            staging_dir: PathBuf::from(STAGING_DIR),
+            snapshot_dir: PathBuf::from(SNAPSHOT_DIR),
+            snapshot_retention: SNAPSHOT_RETENTION,
+            allow_overwrite: ALLOW_OVERWRITE,
+            lock_policy: LOCK_POLICY.to_string(),
+            lock_timeout_secs: LOCK_TIMEOUT_SECS,
+            incremental: INCREMENTAL,
+            checkpoint_interval: CHECKPOINT_INTERVAL,
+            required_tags: get_required_tags(),
+            excluded_tags: get_excluded_tags(),
+            filter_expression: FILTER_EXPRESSION.to_string(),
+            transformations: TRANSFORMATIONS.iter().map(|rule| rule.to_string()).collect(),
+            custom_stages: get_custom_stages(),
+            dedup_strategy: DEDUP_STRATEGY.to_string(),
+            duplicate_id_policy: DUPLICATE_ID_POLICY.to_string(),
+            enrichment_lookup_path: PathBuf::from(ENRICHMENT_LOOKUP_PATH),
+            enrichment_missing_policy: ENRICHMENT_MISSING_POLICY.to_string(),
+            join_path: PathBuf::from(JOIN_PATH),
+            join_type: JOIN_TYPE.to_string(),
+            join_fields: get_join_fields(),
+            audit_log_path: PathBuf::from(AUDIT_LOG_PATH),
+            history_path: PathBuf::from(HISTORY_PATH),
+            delivery_ledger_path: PathBuf::from(DELIVERY_LEDGER_PATH),
+            notify_webhooks: get_notify_webhooks(),
+            notify_smtp_server: NOTIFY_SMTP_SERVER.to_string(),
+            notify_smtp_username: NOTIFY_SMTP_USERNAME.to_string(),
+            notify_smtp_password: get_notify_smtp_password(),
+            notify_email_from: NOTIFY_EMAIL_FROM.to_string(),
+            notify_email_to: get_notify_email_to(),
+            notify_on: NOTIFY_ON.to_string(),
+            otel_endpoint: OTEL_ENDPOINT.to_string(),
+            otel_service_name: OTEL_SERVICE_NAME.to_string(),
+            lineage: LINEAGE,
+            watch_interval_secs: WATCH_INTERVAL_SECS,
+            schedule: SCHEDULE.to_string(),
+            schedule_missed_runs: SCHEDULE_MISSED_RUNS.to_string(),
+            profile: None,
+            pipeline: None,
+            pipelines: Vec::new(),
+            tenant: None,
+            tenants: Vec::new(),
+            sources: BTreeMap::new(),
+        }
+    }
+
+    /**
+     * @brief Resolve the configuration from the defaults, the config file, the
+     *        active profile and the environment.
+     *
+     * @param config_file The config file to read; None reads CONFIG_FILE_PATH
+     *        if it exists.
+     * @param profile The profile to apply (--profile); None uses PROFILE_ENV_VAR
+     *        if it is set and not empty.
+     * @return (Config, Vec<ConfigProblem>) The configuration, and the problems
+     *         found while reading it (unknown keys, values of the wrong type,
+     *         an unreadable file, an unknown profile). Invalid values keep their
+     *         previous layer's value.
+     */
+    pub fn load(config_file: Option<&Path>, profile: Option<&str>) -> (Config, Vec<ConfigProblem>) {
+        Config::load_layers(config_file, profile, None, None)
+    }
+
+    /**
+     * @brief Resolve the configuration of one of the pipelines of the config
+     *        file, like load, with the values of the pipeline applied on top
+     *        of the active profile.
+     *
+     * @param config_file The config file to read; None reads CONFIG_FILE_PATH
+     *        if it exists.
+     * @param profile The profile to apply (--profile), as for load.
+     * @param pipeline The name of the pipeline ([pipelines.<name>]).
+     * @return (Config, Vec<ConfigProblem>) As for load; an unknown pipeline is a problem.
+     */
+    pub fn load_pipeline(
+        config_file: Option<&Path>,
+        profile: Option<&str>,
+        pipeline: &str,
+    ) -> (Config, Vec<ConfigProblem>) {
+        Config::load_layers(config_file, profile, Some(pipeline), None)
+    }
+
+    /**
+     * @brief Resolve the configuration of one of the tenants of the config
+     *        file, like load, with the values of the tenant applied on top
+     *        of the active profile.
+     *
+     * @param config_file The config file to read; None reads CONFIG_FILE_PATH
+     *        if it exists.
+     * @param profile The profile to apply (--profile), as for load.
+     * @param tenant The name of the tenant ([tenants.<name>]).
+     * @return (Config, Vec<ConfigProblem>) As for load; an unknown tenant is a problem.
+     */
+    pub fn load_tenant(
+        config_file: Option<&Path>,
+        profile: Option<&str>,
+        tenant: &str,
+    ) -> (Config, Vec<ConfigProblem>) {
+        Config::load_layers(config_file, profile, None, Some(tenant))
+    }
+
+    /**
+     * @brief Resolve the configuration, see load, load_pipeline and load_tenant.
+     */
+    fn load_layers(
+        config_file: Option<&Path>,
+        profile: Option<&str>,
+        pipeline: Option<&str>,
+        tenant: Option<&str>,
+    ) -> (Config, Vec<ConfigProblem>) {
+        let mut config = Config::from_defaults();
+        let mut problems = Vec::new();
+        let profile = match profile {
+            Some(name) => Some((name.to_string(), ConfigSource::Cli)),
+            None => env::var(PROFILE_ENV_VAR)
+                .ok()
+                .filter(|name| !name.is_empty())
+                .map(|name| (name, ConfigSource::Env(PROFILE_ENV_VAR.to_string()))),
+        };
+        let path = match config_file {
+            Some(path) => Some(path),
+            None if fs::metadata(CONFIG_FILE_PATH).is_ok() => Some(Path::new(CONFIG_FILE_PATH)),
+            None => None,
+        };
+        match (path, profile) {
+            (Some(path), profile) => problems.extend(config.apply_file(path, profile, pipeline, tenant)),
+            (None, Some((name, source))) => problems.push(ConfigProblem {
+                key: String::from("profile"),
+                source,
+                message: format!("profile '{}' selected, but there is no config file", name),
+            }),
+            (None, None) => {}
+        }
+        if let (None, Some(name)) = (path, pipeline) {
+            problems.push(ConfigProblem {
+                key: String::from("--pipelines"),
+                source: ConfigSource::Cli,
+                message: format!("pipeline '{}' selected, but there is no config file", name),
+            });
+        }
+        if let (None, Some(name)) = (path, tenant) {
+            problems.push(ConfigProblem {
+                key: String::from("--tenant"),
+                source: ConfigSource::Cli,
+                message: format!("tenant '{}' selected, but there is no config file", name),
+            });
+        }
+        problems.extend(config.apply_env());
+        (config, problems)
+    }
+
+    /**
+     * @brief Apply the values of a TOML config file, then those of a profile
+     *        and of a pipeline or tenant in it.
+     *
+     * @param path Path of the config file.
+     * @param profile The name of the profile to apply and where it was
+     *        selected, or None to apply no profile.
+     * @param pipeline The name of the pipeline to apply, or None to apply no pipeline.
+     * @param tenant The name of the tenant to apply, or None to apply no tenant.
+     * @return Vec<ConfigProblem> The problems found, empty if all values applied.
+     */
+    pub fn apply_file(
+        &mut self,
+        path: &Path,
+        profile: Option<(String, ConfigSource)>,
+        pipeline: Option<&str>,
+        tenant: Option<&str>,
+    ) -> Vec<ConfigProblem> {
+        let source = ConfigSource::File(path.display().to_string());
+        let mut table = match fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file '{}': {}", path.display(), e))
+            .and_then(|text| {
+                text.parse::<toml::Table>()
+                    .map_err(|e| format!("Failed to parse config file '{}': {}", path.display(), e))
+            }) {
+            Ok(table) => table,
+            Err(message) => {
+                return vec![ConfigProblem {
+                    key: String::from("(file)"),
+                    source,
+                    message,
+                }]
+            }
+        };
+
+        let profiles = table.remove("profiles");
+        let pipelines = table.remove("pipelines");
+        let tenants = table.remove("tenants");
+        let mut problems = self.apply_table(&table, &source);
+        if let Some((name, selected_by)) = profile {
+            match profiles.as_ref().and_then(|profiles| profiles.get(&name)) {
+                Some(toml::Value::Table(profile_table)) => {
+                    let profile_source = ConfigSource::Profile {
+                        file: path.display().to_string(),
+                        name: name.clone(),
+                    };
+                    problems.extend(self.apply_table(profile_table, &profile_source));
+                    self.profile = Some(name);
+                }
+                Some(_) => problems.push(ConfigProblem {
+                    key: format!("profiles.{}", name),
+                    source: source.clone(),
+                    message: String::from("expected a table"),
+                }),
+                None => {
+                    let known: Vec<&str> = profiles
+                        .as_ref()
+                        .and_then(toml::Value::as_table)
+                        .map(|profiles| profiles.keys().map(String::as_str).collect())
+                        .unwrap_or_default();
+                    problems.push(ConfigProblem {
+                        key: String::from("profile"),
+                        source: selected_by,
+                        message: format!(
+                            "unknown profile '{}' (profiles in {}: {})",
+                            name,
+                            path.display(),
+                            if known.is_empty() { String::from("none") } else { known.join(", ") }
+                        ),
+                    });
+                }
+            }
+        }
+        self.pipelines = match &pipelines {
+            Some(toml::Value::Table(pipelines)) => pipelines.keys().cloned().collect(),
+            Some(_) => {
+                problems.push(ConfigProblem {
+                    key: String::from("pipelines"),
+                    source: source.clone(),
+                    message: String::from("expected a table of pipelines"),
+                });
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        if let Some(name) = pipeline {
+            match pipelines.as_ref().and_then(|pipelines| pipelines.get(name)) {
+                Some(toml::Value::Table(pipeline_table)) => {
+                    let pipeline_source = ConfigSource::Pipeline {
+                        file: path.display().to_string(),
+                        name: name.to_string(),
+                    };
+                    problems.extend(self.apply_table(pipeline_table, &pipeline_source));
+                    self.pipeline = Some(name.to_string());
+                }
+                Some(_) => problems.push(ConfigProblem {
+                    key: format!("pipelines.{}", name),
+                    source,
+                    message: String::from("expected a table"),
+                }),
+                None => problems.push(ConfigProblem {
+                    key: String::from("--pipelines"),
+                    source: ConfigSource::Cli,
+                    message: format!("unknown pipeline '{}' in {}", name, path.display()),
+                }),
+            }
+        }
+        self.tenants = match &tenants {
+            Some(toml::Value::Table(tenants)) => tenants.keys().cloned().collect(),
+            Some(_) => {
+                problems.push(ConfigProblem {
+                    key: String::from("tenants"),
+                    source: ConfigSource::File(path.display().to_string()),
+                    message: String::from("expected a table of tenants"),
+                });
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        if let Some(name) = tenant {
+            match tenants.as_ref().and_then(|tenants| tenants.get(name)) {
+                Some(toml::Value::Table(tenant_table)) => {
+                    let tenant_source = ConfigSource::Tenant {
+                        file: path.display().to_string(),
+                        name: name.to_string(),
+                    };
+                    problems.extend(self.apply_table(tenant_table, &tenant_source));
+                    self.tenant = Some(name.to_string());
+                    let others = tenants.iter().flat_map(toml::Value::as_table).flatten();
+                    for (other, other_table) in others.filter(|(other, _)| *other != name) {
+                        for key in TENANT_PATH_KEYS {
+                            let own = tenant_table.get(key).and_then(toml::Value::as_str).unwrap_or_default();
+                            let theirs = other_table.get(key).and_then(toml::Value::as_str).unwrap_or_default();
+                            if !own.is_empty() && Path::new(own) == Path::new(theirs) {
+                                problems.push(ConfigProblem {
+                                    key: key.to_string(),
+                                    source: tenant_source.clone(),
+                                    message: format!("'{}' is also the {} of tenant '{}'", own, key, other),
+                                });
+                            }
+                        }
+                    }
+                }
+                Some(_) => problems.push(ConfigProblem {
+                    key: format!("tenants.{}", name),
+                    source: ConfigSource::File(path.display().to_string()),
+                    message: String::from("expected a table"),
+                }),
+                None => problems.push(ConfigProblem {
+                    key: String::from("--tenant"),
+                    source: ConfigSource::Cli,
+                    message: format!(
+                        "unknown tenant '{}' (tenants in {}: {})",
+                        name,
+                        path.display(),
+                        if self.tenants.is_empty() { String::from("none") } else { self.tenants.join(", ") }
+                    ),
+                }),
+            }
+        }
+        problems
+    }
+
+    /**
+     * @brief Apply the values of one table of a config file.
+     *
+     * @param table The top-level table or a profile table.
+     * @param source Where the values come from.
+     * @return Vec<ConfigProblem> The problems found, empty if all values applied.
+     */
+    fn apply_table(&mut self, table: &toml::Table, source: &ConfigSource) -> Vec<ConfigProblem> {
+        let problem = |key: &str, message: String| ConfigProblem {
+            key: key.to_string(),
+            source: source.clone(),
+            message,
+        };
+        let mut problems = Vec::new();
+        for (key, value) in table {
+            let result = match value {
+                toml::Value::String(text) => self.set(key, text, source.clone()),
+                toml::Value::Array(entries) if key == "output_routes" => entries
+                    .iter()
+                    .map(|entry| match entry.as_array().map(Vec::as_slice) {
+                        Some([toml::Value::String(sink), toml::Value::String(condition)]) => {
+                            Ok(format!("{}={}", sink, condition))
+                        }
+                        _ => Err(String::from("expected [\"<sink>\", \"<condition>\"] pairs")),
+                    })
+                    .collect::<Result<Vec<String>, String>>()
+                    .and_then(|routes| self.set_list(key, routes, source.clone())),
+                toml::Value::Array(entries) => entries
+                    .iter()
+                    .map(|entry| entry.as_str().map(str::to_string))
+                    .collect::<Option<Vec<String>>>()
+                    .ok_or_else(|| String::from("expected an array of strings"))
+                    .and_then(|list| self.set_list(key, list, source.clone())),
+                toml::Value::Table(entries) if key.ends_with("_thresholds") => entries
+                    .iter()
+                    .map(|(name, threshold)| match threshold {
+                        toml::Value::Integer(_) | toml::Value::Float(_) => {
+                            Ok(format!("{}={}", name, threshold))
+                        }
+                        _ => Err(format!("threshold of '{}' must be a number", name)),
+                    })
+                    .collect::<Result<Vec<String>, String>>()
+                    .and_then(|thresholds| self.set_list(key, thresholds, source.clone())),
+                toml::Value::Table(_) => Err(String::from("expected a value, not a table")),
+                other => self.set(key, &other.to_string(), source.clone()),
+            };
+            if let Err(message) = result {
+                problems.push(problem(key, message));
+            }
+        }
+        problems
+    }
+
+    /**
+     * @brief Apply the SAMPLE_PROJECT_<KEY> environment variables that are set.
+     *
+     * @return Vec<ConfigProblem> The problems found, empty if all values applied.
+     */
+    pub fn apply_env(&mut self) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+        for key in CONFIG_KEYS {
+            let var = format!("{}{}", CONFIG_ENV_PREFIX, key.to_ascii_uppercase());
+            if let Some(value) = env::var_os(&var) {
+                // Paths are taken as they are; everything else must be text
+                let source = ConfigSource::Env(var.clone());
+                let result = match value.into_string() {
+                    Ok(text) => self.set(key, &text, source),
+                    Err(raw) if self.set_path(key, PathBuf::from(&raw), source.clone()).is_ok() => Ok(()),
+                    Err(_) => Err(String::from("value is not valid UTF-8")),
+                };
+                if let Err(message) = result {
+                    problems.push(ConfigProblem {
+                        key: key.to_string(),
+                        source: ConfigSource::Env(var),
+                        message,
+                    });
+                }
+            }
+        }
+        problems
+    }
+
+    /**
+     * @brief Override a value given as text, recording where it came from.
+     *
+     * Numbers and flags are parsed; list values are split on ',' (transformations and
+     * output_routes on ';', routes written as "sink=condition").
+     *
+     * @param key The configuration key (see CONFIG_KEYS).
+     * @param value The new value as text.
+     * @param source Where the value came from.
+     * @return Result<(), String> Ok, or an error message for unknown keys and
+     *         values that do not parse.
+     */
+    pub fn set(&mut self, key: &str, value: &str, source: ConfigSource) -> Result<(), String> {
+        let key = known_key(key)?;
+        let split = |separator: char| -> Vec<String> {
+            value
+                .split(separator)
+                .map(str::trim)
+                .filter(|part| !part.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        match key {
+            "threshold" => self.threshold = parse_number(value)?,
+            "value_decimals" => self.value_decimals = parse_number(value)?,
+            "worker_threads" => self.worker_threads = parse_number(value)?,
+            "worker_queue_capacity" => self.worker_queue_capacity = parse_number(value)?,
+            "rate_limit" => self.rate_limit = parse_number(value)?,
+            "rate_limit_burst" => self.rate_limit_burst = parse_number(value)?,
+            "max_duration_secs" => self.max_duration_secs = parse_number(value)?,
+            "snapshot_retention" => self.snapshot_retention = parse_number(value)?,
+            "allow_overwrite" => self.allow_overwrite = parse_bool(value)?,
+            "drop_invalid_records" => self.drop_invalid_records = parse_bool(value)?,
+            "sample_size" => self.sample_size = parse_number(value)?,
+            "sample_percent" => self.sample_percent = parse_number(value)?,
+            "sample_seed" => self.sample_seed = parse_number(value)?,
+            "anomaly_factor" => self.anomaly_factor = parse_number(value)?,
+            "item_timeout_ms" => self.item_timeout_ms = parse_number(value)?,
+            "save_batch_size" => self.save_batch_size = parse_number(value)?,
+            "save_flush_interval_ms" => self.save_flush_interval_ms = parse_number(value)?,
+            "shard_max_items" => self.shard_max_items = parse_number(value)?,
+            "shard_max_bytes" => self.shard_max_bytes = parse_number(value)?,
+            "checkpoint_interval" => self.checkpoint_interval = parse_number(value)?,
+            "lock_timeout_secs" => self.lock_timeout_secs = parse_number(value)?,
+            "incremental" => self.incremental = parse_bool(value)?,
+            "encryption_allow_plaintext" => self.encryption_allow_plaintext = parse_bool(value)?,
+            "data_checksums" => self.data_checksums = parse_bool(value)?,
+            "intern_names" => self.intern_names = parse_bool(value)?,
+            "stream_arena_batch" => self.stream_arena_batch = parse_number(value)?,
+            "stream_process_buffer" => self.stream_process_buffer = parse_number(value)?,
+            "stream_save_buffer" => self.stream_save_buffer = parse_number(value)?,
+            "analytics" => self.analytics = parse_bool(value)?,
+            "lineage" => self.lineage = parse_bool(value)?,
+            "watch_interval_secs" => self.watch_interval_secs = parse_number(value)?,
+            "input_sources"
+            | "required_tags"
+            | "excluded_tags"
+            | "custom_stages"
+            | "aggregate_by"
+            | "join_fields"
+            | "notify_webhooks"
+            | "notify_email_to"
+            | "category_thresholds"
+            | "tag_thresholds" => {
+                return self.set_list(key, split(','), source)
+            }
+            "transformations" | "output_routes" => return self.set_list(key, split(';'), source),
+            "data_path"
+            | "checkpoint_path"
+            | "staging_dir"
+            | "snapshot_dir"
+            | "output_path"
+            | "anomaly_review_path"
+            | "aggregate_path"
+            | "enrichment_lookup_path"
+            | "join_path"
+            | "audit_log_path"
+            | "history_path"
+            | "delivery_ledger_path"
+            | "encryption_key_file" => return self.set_path(key, PathBuf::from(value), source),
+            "notify_smtp_password" => self.notify_smtp_password = Secret::new(value.to_string()),
+            _ => *self.text_field(key)? = value.to_string(),
+        }
+        self.sources.insert(key, source);
+        Ok(())
+    }
+
+    /**
+     * @brief Override a path value, recording where it came from.
+     *
+     * Unlike set, the path need not be valid UTF-8.
+     *
+     * @param key One of the path keys (data_path, checkpoint_path, staging_dir,
+     *        snapshot_dir, output_path, anomaly_review_path, aggregate_path,
+     *        enrichment_lookup_path, join_path, audit_log_path, history_path,
+     *        delivery_ledger_path, encryption_key_file).
+     * @param value The new path.
+     * @param source Where the value came from.
+     * @return Result<(), String> Ok, or an error message for other keys.
+     */
+    pub fn set_path(&mut self, key: &str, value: PathBuf, source: ConfigSource) -> Result<(), String> {
+        let key = known_key(key)?;
+        let field = match key {
+            "data_path" => &mut self.data_path,
+            "checkpoint_path" => &mut self.checkpoint_path,
+            "staging_dir" => &mut self.staging_dir,
+            "snapshot_dir" => &mut self.snapshot_dir,
+            "output_path" => &mut self.output_path,
+            "anomaly_review_path" => &mut self.anomaly_review_path,
+            "aggregate_path" => &mut self.aggregate_path,
+            "enrichment_lookup_path" => &mut self.enrichment_lookup_path,
+            "join_path" => &mut self.join_path,
+            "audit_log_path" => &mut self.audit_log_path,
+            "history_path" => &mut self.history_path,
+            "delivery_ledger_path" => &mut self.delivery_ledger_path,
+            "encryption_key_file" => &mut self.encryption_key_file,
+            other => return Err(format!("'{}' is not a path value", other)),
+        };
+        *field = value;
+        self.sources.insert(key, source);
+        Ok(())
+    }
+
+    /**
+     * @brief Override a list value, recording where it came from.
+     *
+     * @param key One of the list keys (input_sources, output_routes,
+     *        required_tags, excluded_tags, transformations, custom_stages,
+     *        aggregate_by, join_fields, notify_webhooks, notify_email_to,
+     *        category_thresholds, tag_thresholds).
+     * @param values The new entries; routes are written as "sink=condition",
+     *        thresholds as "name=threshold".
+     * @param source Where the value came from.
+     * @return Result<(), String> Ok, or an error message for other keys,
+     *         malformed routes and malformed thresholds.
+     */
+    pub fn set_list(
+        &mut self,
+        key: &str,
+        values: Vec<String>,
+        source: ConfigSource,
+    ) -> Result<(), String> {
+        let key = known_key(key)?;
+        match key {
+            "input_sources" => self.input_sources = values,
+            "required_tags" => self.required_tags = values,
+            "excluded_tags" => self.excluded_tags = values,
+            "transformations" => self.transformations = values,
+            "custom_stages" => self.custom_stages = values,
+            "aggregate_by" => self.aggregate_by = values,
+            "join_fields" => self.join_fields = values,
+            "notify_webhooks" => self.notify_webhooks = values,
+            "notify_email_to" => self.notify_email_to = values,
+            "output_routes" => {
+                self.output_routes = values
+                    .iter()
+                    .map(|route| {
+                        let (sink, condition) = route.split_once('=').ok_or_else(|| {
+                            format!("Route '{}' is not of the form sink=condition", route)
+                        })?;
+                        Ok((sink.trim().to_string(), condition.trim().to_string()))
+                    })
+                    .collect::<Result<Vec<(String, String)>, String>>()?
+            }
+            "category_thresholds" => self.category_thresholds = parse_thresholds(&values)?,
+            "tag_thresholds" => self.tag_thresholds = parse_thresholds(&values)?,
+            other => return Err(format!("'{}' is not a list value", other)),
+        }
+        self.sources.insert(key, source);
+        Ok(())
+    }
+
+    /**
+     * @brief Return the field holding a plain text value.
+     *
+     * @param key The configuration key.
+     * @return Result<&mut String, String> The field, or an error message for
+     *         keys that are not text values.
+     */
+    fn text_field(&mut self, key: &str) -> Result<&mut String, String> {
+        Ok(match key {
+            "item_id_kind" => &mut self.item_id_kind,
+            "locale" => &mut self.locale,
+            "log_level" => &mut self.log_level,
+            "log_format" => &mut self.log_format,
+            "log_color" => &mut self.log_color,
+            "merge_strategy" => &mut self.merge_strategy,
+            "output_compression" => &mut self.output_compression,
+            "data_format" => &mut self.data_format,
+            "input_strictness" => &mut self.input_strictness,
+            "xml_item_element" => &mut self.xml_item_element,
+            "xml_id_field" => &mut self.xml_id_field,
+            "xml_name_field" => &mut self.xml_name_field,
+            "xml_value_field" => &mut self.xml_value_field,
+            "xml_mode" => &mut self.xml_mode,
+            "xlsx_sheet" => &mut self.xlsx_sheet,
+            "xlsx_id_column" => &mut self.xlsx_id_column,
+            "xlsx_name_column" => &mut self.xlsx_name_column,
+            "xlsx_value_column" => &mut self.xlsx_value_column,
+            "xlsx_results_sheet" => &mut self.xlsx_results_sheet,
+            "encryption_key_env" => &mut self.encryption_key_env,
+            "daemon_addr" => &mut self.daemon_addr,
+            "grpc_addr" => &mut self.grpc_addr,
+            "filter_expression" => &mut self.filter_expression,
+            "dedup_strategy" => &mut self.dedup_strategy,
+            "duplicate_id_policy" => &mut self.duplicate_id_policy,
+            "enrichment_missing_policy" => &mut self.enrichment_missing_policy,
+            "join_type" => &mut self.join_type,
+            "lock_policy" => &mut self.lock_policy,
+            "priority_policy" => &mut self.priority_policy,
+            "anomaly_detection" => &mut self.anomaly_detection,
+            "aggregate_format" => &mut self.aggregate_format,
+            "notify_smtp_server" => &mut self.notify_smtp_server,
+            "notify_smtp_username" => &mut self.notify_smtp_username,
+            "notify_email_from" => &mut self.notify_email_from,
+            "notify_on" => &mut self.notify_on,
+            "otel_endpoint" => &mut self.otel_endpoint,
+            "otel_service_name" => &mut self.otel_service_name,
+            "schedule" => &mut self.schedule,
+            "schedule_missed_runs" => &mut self.schedule_missed_runs,
+            other => return Err(format!("'{}' is not a text value", other)),
+        })
+    }
+
+    /**
+     * @brief Return the effective value of a key, as it would be written in a config file.
+     *
+     * @param key The configuration key (see CONFIG_KEYS).
+     * @return Option<toml::Value> The value, or None for unknown keys.
+     */
+    pub fn value_of(&self, key: &str) -> Option<toml::Value> {
+        let list = |values: &[String]| {
+            toml::Value::Array(values.iter().cloned().map(toml::Value::String).collect())
+        };
+        let path = |value: &Path| toml::Value::String(value.display().to_string());
+        let thresholds = |values: &[(String, f64)]| {
+            toml::Value::Table(
+                values
+                    .iter()
+                    .map(|(name, threshold)| (name.clone(), toml::Value::Float(*threshold)))
+                    .collect(),
+            )
+        };
+        let value = match key {
+            "threshold" => toml::Value::Float(self.threshold),
+            "category_thresholds" => thresholds(&self.category_thresholds),
+            "tag_thresholds" => thresholds(&self.tag_thresholds),
+            "value_decimals" => toml::Value::Integer(i64::from(self.value_decimals)),
+            "worker_threads" => toml::Value::Integer(self.worker_threads as i64),
+            "worker_queue_capacity" => toml::Value::Integer(self.worker_queue_capacity as i64),
+            "rate_limit" => toml::Value::Float(self.rate_limit),
+            "rate_limit_burst" => toml::Value::Integer(self.rate_limit_burst as i64),
+            "priority_policy" => toml::Value::String(self.priority_policy.clone()),
+            "max_duration_secs" => toml::Value::Integer(self.max_duration_secs as i64),
+            "sample_size" => toml::Value::Integer(self.sample_size as i64),
+            "sample_percent" => toml::Value::Float(self.sample_percent),
+            "sample_seed" => toml::Value::Integer(self.sample_seed as i64),
+            "anomaly_detection" => toml::Value::String(self.anomaly_detection.clone()),
+            "anomaly_factor" => toml::Value::Float(self.anomaly_factor),
+            "output_path" => path(&self.output_path),
+            "anomaly_review_path" => path(&self.anomaly_review_path),
+            "aggregate_by" => list(&self.aggregate_by),
+            "aggregate_path" => path(&self.aggregate_path),
+            "aggregate_format" => toml::Value::String(self.aggregate_format.clone()),
+            "item_timeout_ms" => toml::Value::Integer(self.item_timeout_ms as i64),
+            "save_batch_size" => toml::Value::Integer(self.save_batch_size as i64),
+            "save_flush_interval_ms" => toml::Value::Integer(self.save_flush_interval_ms as i64),
+            "shard_max_items" => toml::Value::Integer(self.shard_max_items as i64),
+            "shard_max_bytes" => toml::Value::Integer(self.shard_max_bytes as i64),
+            "checkpoint_interval" => toml::Value::Integer(self.checkpoint_interval as i64),
+            "watch_interval_secs" => toml::Value::Integer(self.watch_interval_secs as i64),
+            "input_sources" => list(&self.input_sources),
+            "required_tags" => list(&self.required_tags),
+            "excluded_tags" => list(&self.excluded_tags),
+            "transformations" => list(&self.transformations),
+            "custom_stages" => list(&self.custom_stages),
+            "output_routes" => toml::Value::Array(
+                self.output_routes
+                    .iter()
+                    .map(|(sink, condition)| {
+                        toml::Value::Array(vec![
+                            toml::Value::String(sink.clone()),
+                            toml::Value::String(condition.clone()),
+                        ])
+                    })
+                    .collect(),
+            ),
+            "data_path" => path(&self.data_path),
+            "item_id_kind" => toml::Value::String(self.item_id_kind.clone()),
+            "locale" => toml::Value::String(self.locale.clone()),
+            "log_level" => toml::Value::String(self.log_level.clone()),
+            "log_format" => toml::Value::String(self.log_format.clone()),
+            "log_color" => toml::Value::String(self.log_color.clone()),
+            "merge_strategy" => toml::Value::String(self.merge_strategy.clone()),
+            "output_compression" => toml::Value::String(self.output_compression.clone()),
+            "data_format" => toml::Value::String(self.data_format.clone()),
+            "input_strictness" => toml::Value::String(self.input_strictness.clone()),
+            "drop_invalid_records" => toml::Value::Boolean(self.drop_invalid_records),
+            "xml_item_element" => toml::Value::String(self.xml_item_element.clone()),
+            "xml_id_field" => toml::Value::String(self.xml_id_field.clone()),
+            "xml_name_field" => toml::Value::String(self.xml_name_field.clone()),
+            "xml_value_field" => toml::Value::String(self.xml_value_field.clone()),
+            "xml_mode" => toml::Value::String(self.xml_mode.clone()),
+            "xlsx_sheet" => toml::Value::String(self.xlsx_sheet.clone()),
+            "xlsx_id_column" => toml::Value::String(self.xlsx_id_column.clone()),
+            "xlsx_name_column" => toml::Value::String(self.xlsx_name_column.clone()),
+            "xlsx_value_column" => toml::Value::String(self.xlsx_value_column.clone()),
+            "xlsx_results_sheet" => toml::Value::String(self.xlsx_results_sheet.clone()),
+            "encryption_key_env" => toml::Value::String(self.encryption_key_env.clone()),
+            "encryption_key_file" => path(&self.encryption_key_file),
+            "encryption_allow_plaintext" => toml::Value::Boolean(self.encryption_allow_plaintext),
+            "data_checksums" => toml::Value::Boolean(self.data_checksums),
+            "intern_names" => toml::Value::Boolean(self.intern_names),
+            "stream_arena_batch" => toml::Value::Integer(self.stream_arena_batch as i64),
+            "stream_process_buffer" => toml::Value::Integer(self.stream_process_buffer as i64),
+            "stream_save_buffer" => toml::Value::Integer(self.stream_save_buffer as i64),
+            "analytics" => toml::Value::Boolean(self.analytics),
+            "lineage" => toml::Value::Boolean(self.lineage),
+            "daemon_addr" => toml::Value::String(self.daemon_addr.clone()),
+            "grpc_addr" => toml::Value::String(self.grpc_addr.clone()),
+            "checkpoint_path" => path(&self.checkpoint_path),
+            "staging_dir" => path(&self.staging_dir),
+            "snapshot_dir" => path(&self.snapshot_dir),
+            "snapshot_retention" => toml::Value::Integer(self.snapshot_retention as i64),
+            "allow_overwrite" => toml::Value::Boolean(self.allow_overwrite),
+            "lock_policy" => toml::Value::String(self.lock_policy.clone()),
+            "lock_timeout_secs" => toml::Value::Integer(self.lock_timeout_secs as i64),
+            "incremental" => toml::Value::Boolean(self.incremental),
+            "filter_expression" => toml::Value::String(self.filter_expression.clone()),
+            "dedup_strategy" => toml::Value::String(self.dedup_strategy.clone()),
+            "duplicate_id_policy" => toml::Value::String(self.duplicate_id_policy.clone()),
+            "enrichment_lookup_path" => path(&self.enrichment_lookup_path),
+            "enrichment_missing_policy" => {
+                toml::Value::String(self.enrichment_missing_policy.clone())
+            }
+            "join_path" => path(&self.join_path),
+            "join_type" => toml::Value::String(self.join_type.clone()),
+            "join_fields" => list(&self.join_fields),
+            "audit_log_path" => path(&self.audit_log_path),
+            "history_path" => path(&self.history_path),
+            "delivery_ledger_path" => path(&self.delivery_ledger_path),
+            // Webhook URLs carry their credentials, see notify::redact
+            "notify_webhooks" => list(&self.notify_webhooks.iter().map(|url| notify::redact(url)).collect::<Vec<_>>()),
+            "notify_smtp_server" => toml::Value::String(self.notify_smtp_server.clone()),
+            "notify_smtp_username" => toml::Value::String(self.notify_smtp_username.clone()),
+            // Never the password itself, see secret.rs
+            "notify_smtp_password" => toml::Value::String(self.notify_smtp_password.redacted()),
+            "notify_email_from" => toml::Value::String(self.notify_email_from.clone()),
+            "notify_email_to" => list(&self.notify_email_to),
+            "notify_on" => toml::Value::String(self.notify_on.clone()),
+            "otel_endpoint" => toml::Value::String(self.otel_endpoint.clone()),
+            "otel_service_name" => toml::Value::String(self.otel_service_name.clone()),
+            "schedule" => toml::Value::String(self.schedule.clone()),
+            "schedule_missed_runs" => toml::Value::String(self.schedule_missed_runs.clone()),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /**
+     * @brief Report where every effective value came from.
+     *
+     * @return Vec<(&'static str, ConfigSource)> Each key of CONFIG_KEYS with its source.
+     */
+    pub fn sources(&self) -> Vec<(&'static str, ConfigSource)> {
+        CONFIG_KEYS.iter().map(|key| (*key, self.source_of(key))).collect()
+    }
+
+    /**
+     * @brief Describe how another configuration differs from this one.
+     *
+     * @param newer The configuration to compare with.
+     * @return Vec<String> One "key: old -> new" entry per changed value.
+     */
+    pub fn changes(&self, newer: &Config) -> Vec<String> {
+        CONFIG_KEYS
+            .iter()
+            .filter_map(|key| match (self.value_of(key), newer.value_of(key)) {
+                (Some(old), Some(new)) if old != new => Some(format!("{}: {} -> {}", key, old, new)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /**
+     * @brief Return a hash of the effective values, to tell runs with
+     *        different configurations apart in the run history.
+     *
+     * @return String The first 16 hex digits of the SHA-256 of all key = value pairs.
+     */
+    pub fn fingerprint(&self) -> String {
+        let mut text = String::new();
+        for key in CONFIG_KEYS {
+            if let Some(value) = self.value_of(key) {
+                text.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        let mut digest = integrity::digest(text.as_bytes());
+        digest.truncate(16);
+        digest
+    }
+
+    /**
+     * @brief Describe the config file as a JSON Schema (draft 2020-12), for
+     *        editor completion and for checking config files in CI.
+     *
+     * Each key of CONFIG_KEYS is typed after its default value and described
+     * with describe; profile, pipeline and tenant tables hold the same keys as
+     * the top level. Values the schema rejects may still be accepted as text (e.g.
+     * threshold = "150"), as with environment variables.
+     *
+     * @return serde_json::Value The schema.
+     */
+    pub fn json_schema() -> serde_json::Value {
+        let defaults = Config::from_defaults();
+        let mut keys = serde_json::Map::new();
+        for key in CONFIG_KEYS {
+            let Some(default) = defaults.value_of(key) else {
+                continue;
+            };
+            let mut schema = match &default {
+                toml::Value::String(_) => json!({"type": "string"}),
+                toml::Value::Integer(_) => json!({"type": "integer", "minimum": 0}),
+                toml::Value::Float(_) => json!({"type": "number"}),
+                toml::Value::Boolean(_) => json!({"type": "boolean"}),
+                toml::Value::Table(_) => json!({"type": "object", "additionalProperties": {"type": "number"}}),
+                // [["<sink>", "<condition>"], ...], see apply_table
+                toml::Value::Array(_) if *key == "output_routes" => json!({
+                    "type": "array",
+                    "items": {
+                        "type": "array",
+                        "prefixItems": [{"type": "string"}, {"type": "string"}],
+                        "minItems": 2,
+                        "maxItems": 2
+                    }
+                }),
+                toml::Value::Array(_) => json!({"type": "array", "items": {"type": "string"}}),
+                toml::Value::Datetime(_) => json!({}),
+            };
+            if let Some(description) = describe(key) {
+                schema["description"] = json!(description);
+            }
+            schema["default"] = serde_json::to_value(&default).unwrap_or_default();
+            keys.insert(key.to_string(), schema);
+        }
+
+        let mut properties = keys.clone();
+        properties.insert(
+            String::from("profiles"),
+            json!({
+                "type": "object",
+                "description": format!("Named sets of values, selected with --profile <name> or {}.", PROFILE_ENV_VAR),
+                "additionalProperties": {"$ref": "#/$defs/values"}
+            }),
+        );
+        properties.insert(
+            String::from("pipelines"),
+            json!({
+                "type": "object",
+                "description": "Named pipelines, run together with --pipelines <names|all>.",
+                "additionalProperties": {"$ref": "#/$defs/values"}
+            }),
+        );
+        properties.insert(
+            String::from("tenants"),
+            json!({
+                "type": "object",
+                "description": "Tenants with their own data paths, thresholds and reports, selected with \
+                                --tenant <name> or per run in serve mode. Each sets its own data_path, \
+                                checkpoint_path, history_path and audit_log_path.",
+                "additionalProperties": {"$ref": "#/$defs/values"}
+            }),
+        );
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "rust_sample_project configuration",
+            "description": format!("The config file ({} or --config <path>), written in TOML.", CONFIG_FILE_PATH),
+            "type": "object",
+            "properties": properties,
+            "additionalProperties": false,
+            "$defs": {
+                "values": {"type": "object", "properties": keys, "additionalProperties": false}
+            }
+        })
+    }
+
+    /**
+     * @brief Return where the value of a key came from.
+     *
+     * @param key The configuration key.
+     * @return ConfigSource The source; Default for keys never overridden.
+     */
+    pub fn source_of(&self, key: &str) -> ConfigSource {
+        self.sources.get(key).cloned().unwrap_or(ConfigSource::Default)
+    }
+
+    /**
+     * @brief Check all values and collect every problem found.
+     *
+     * Unlike the parsers used during a run, this does not stop at the first
+     * invalid value, so a broken configuration can be fixed in one go.
+     *
+     * @return Result<(), ConfigErrors> Ok, or all problems with their keys and sources.
+     */
+    pub fn validate(&self) -> Result<(), ConfigErrors> {
+        let mut problems = Vec::new();
+        let mut check = |key: &str, result: Result<(), String>| {
+            if let Err(message) = result {
+                let base_key = key.split('[').next().unwrap_or(key);
+                problems.push(ConfigProblem {
+                    key: key.to_string(),
+                    source: self.source_of(base_key),
+                    message,
+                });
+            }
+        };
+
+        check("data_path", if self.data_path.as_os_str().is_empty() {
+            Err(String::from("no data file path is set"))
+        } else {
+            Ok(())
+        });
+        check("staging_dir", if self.staging_dir.as_os_str().is_empty() {
+            Err(String::from("no staging directory is set"))
+        } else {
+            Ok(())
+        });
+        check("snapshot_dir", if self.snapshot_dir.as_os_str().is_empty() {
+            Err(String::from("no snapshot directory is set"))
+        } else {
+            Ok(())
+        });
+        check("threshold", check_threshold(self.threshold));
+        check("rate_limit", if !self.rate_limit.is_finite() || self.rate_limit < 0.0 {
+            Err(format!("must be a non-negative number, got {}", self.rate_limit))
+        } else {
+            Ok(())
+        });
+        check("sample_percent", if !(0.0..=100.0).contains(&self.sample_percent) {
+            Err(format!("must be between 0 and 100, got {}", self.sample_percent))
+        } else if self.sample_size > 0 && self.sample_percent > 0.0 {
+            Err(String::from("cannot be combined with sample_size"))
+        } else {
+            Ok(())
+        });
+        check("anomaly_detection", AnomalyMethod::parse(&self.anomaly_detection).map(drop));
+        check("anomaly_factor", if !self.anomaly_factor.is_finite() || self.anomaly_factor <= 0.0 {
+            Err(format!("must be a positive number, got {}", self.anomaly_factor))
+        } else {
+            Ok(())
+        });
+        check("anomaly_review_path", if self.anomaly_detection == "off" {
+            Ok(())
+        } else if self.anomaly_review_path.as_os_str().is_empty() {
+            Err(String::from("no review sink is set"))
+        } else if storage::canonical_path(&self.anomaly_review_path) == storage::canonical_path(&self.data_path) {
+            Err(String::from("must differ from data_path, or outliers would be processed again on the next run"))
+        } else {
+            Ok(())
+        });
+        for (index, grouping) in self.aggregate_by.iter().enumerate() {
+            check(&format!("aggregate_by[{}]", index), Grouping::parse(grouping).map(drop));
+        }
+        check("aggregate_format", SummaryFormat::parse(&self.aggregate_format, &self.aggregate_path).map(drop));
+        check("aggregate_path", if self.aggregate_by.is_empty() {
+            Ok(())
+        } else if self.aggregate_path.as_os_str().is_empty() {
+            Err(String::from("no summary file is set"))
+        } else if storage::canonical_path(&self.aggregate_path) == storage::canonical_path(&self.data_path) {
+            Err(String::from("must differ from data_path"))
+        } else {
+            Ok(())
+        });
+        for (name, threshold) in &self.category_thresholds {
+            check(&format!("category_thresholds.{}", name), check_threshold(*threshold));
+        }
+        for (name, threshold) in &self.tag_thresholds {
+            check(&format!("tag_thresholds.{}", name), check_threshold(*threshold));
+        }
+        check("value_decimals", if self.value_decimals > MAX_DECIMALS {
+            Err(format!("must be at most {}, got {}", MAX_DECIMALS, self.value_decimals))
+        } else {
+            Ok(())
+        });
+        check("item_id_kind", IdKind::parse(&self.item_id_kind).map(drop));
+        check("locale", Locale::resolve(&self.locale).map(drop));
+        check("log_level", LogLevel::parse(&self.log_level).map(drop));
+        check("log_format", LogFormat::parse(&self.log_format).map(drop));
+        check("log_color", ColorChoice::parse(&self.log_color).map(drop));
+        check("merge_strategy", DedupStrategy::parse(&self.merge_strategy).map(drop));
+        check("dedup_strategy", DedupStrategy::parse(&self.dedup_strategy).map(drop));
+        check("duplicate_id_policy", DuplicatePolicy::parse(&self.duplicate_id_policy).map(drop));
+        check(
+            "enrichment_missing_policy",
+            MissingLookupPolicy::parse(&self.enrichment_missing_policy).map(drop),
+        );
+        check("join_type", JoinType::parse(&self.join_type).map(drop));
+        for (index, field) in self.join_fields.iter().enumerate() {
+            check(&format!("join_fields[{}]", index), JoinField::parse(field).map(drop));
+        }
+        check("join_path", if !self.join_path.as_os_str().is_empty()
+            && storage::canonical_path(&self.join_path) == storage::canonical_path(&self.data_path)
+        {
+            Err(String::from("must differ from data_path"))
+        } else {
+            Ok(())
+        });
+        for (index, url) in self.notify_webhooks.iter().enumerate() {
+            check(&format!("notify_webhooks[{}]", index), notify::check_webhook(url));
+        }
+        if !self.notify_smtp_server.trim().is_empty() {
+            check("notify_email_from", if self.notify_email_from.trim().is_empty() {
+                Err(String::from("must be set to send notification emails"))
+            } else {
+                notify::check_address(&self.notify_email_from)
+            });
+            check("notify_email_to", if self.notify_email_to.is_empty() {
+                Err(String::from("must name at least one recipient to send notification emails"))
+            } else {
+                Ok(())
+            });
+        }
+        let smtp_login = (self.notify_smtp_username.is_empty(), self.notify_smtp_password.is_empty());
+        check("notify_smtp_password", match smtp_login {
+            (false, true) => Err(String::from("must be set to log in as notify_smtp_username")),
+            (true, false) => Err(String::from("is set, but notify_smtp_username is not")),
+            _ => self.notify_smtp_password.resolve().map(drop),
+        });
+        for (index, address) in self.notify_email_to.iter().enumerate() {
+            check(&format!("notify_email_to[{}]", index), notify::check_address(address));
+        }
+        check("notify_on", NotifyOn::parse(&self.notify_on).map(drop));
+        check("otel_endpoint", if self.otel_endpoint.is_empty() {
+            Ok(())
+        } else if !self.otel_endpoint.starts_with("http://") && !self.otel_endpoint.starts_with("https://") {
+            Err(String::from("must be an http(s) URL"))
+        } else if cfg!(feature = "otel") {
+            Ok(())
+        } else {
+            Err(String::from("needs a build with the `otel` feature"))
+        });
+        check("otel_service_name", if self.otel_service_name.trim().is_empty() {
+            Err(String::from("must not be empty"))
+        } else {
+            Ok(())
+        });
+        if !self.schedule.is_empty() {
+            check("schedule", CronSchedule::parse(&self.schedule).map(drop));
+        }
+        check("schedule_missed_runs", MissedRunPolicy::parse(&self.schedule_missed_runs).map(drop));
+        check("output_compression", Compression::parse(&self.output_compression).map(drop));
+        check("data_format", DataFormat::parse(&self.data_format).map(drop));
+        check("input_strictness", Strictness::parse(&self.input_strictness).map(drop));
+        check("xml_item_element", xml_format::check_field_name(&self.xml_item_element, false));
+        check("xml_id_field", xml_format::check_field_name(&self.xml_id_field, true));
+        check("xml_name_field", xml_format::check_field_name(&self.xml_name_field, true));
+        check("xml_value_field", xml_format::check_field_name(&self.xml_value_field, true));
+        check("xml_mode", XmlMode::parse(&self.xml_mode).map(drop));
+        if !self.xlsx_sheet.is_empty() {
+            check("xlsx_sheet", xlsx_format::check_sheet_name(&self.xlsx_sheet));
+        }
+        check("xlsx_id_column", xlsx_format::check_column_name(&self.xlsx_id_column));
+        check("xlsx_name_column", xlsx_format::check_column_name(&self.xlsx_name_column));
+        check("xlsx_value_column", xlsx_format::check_column_name(&self.xlsx_value_column));
+        check("xlsx_results_sheet", xlsx_format::check_sheet_name(&self.xlsx_results_sheet));
+        check("lock_policy", LockPolicy::parse(&self.lock_policy).map(drop));
+        check("priority_policy", PriorityPolicy::parse(&self.priority_policy).map(drop));
+        #[cfg(not(feature = "arena"))]
+        check("stream_arena_batch", if self.stream_arena_batch > 0 {
+            Err(String::from("needs a build with the `arena` feature"))
+        } else {
+            Ok(())
+        });
+        for (index, (sink, condition)) in self.output_routes.iter().enumerate() {
+            let route = [(sink.as_str(), condition.as_str())];
+            check(
+                &format!("output_routes[{}]", index),
+                path_template::check(Path::new(sink))
+                    .and_then(|_| OutputRouter::new(&self.data_path).with_routes(&route).map(drop)),
+            );
+        }
+        check("output_path", path_template::check(&self.output_path));
+        check("anomaly_review_path", path_template::check(&self.anomaly_review_path));
+        check("aggregate_path", path_template::check(&self.aggregate_path));
+        if !self.filter_expression.trim().is_empty() {
+            check("filter_expression", FilterExpr::parse(&self.filter_expression).map(drop));
+        }
+        for (index, rule) in self.transformations.iter().enumerate() {
+            check(&format!("transformations[{}]", index), Transformation::parse(rule).map(drop));
+        }
+        for (index, name) in self.custom_stages.iter().enumerate() {
+            check(&format!("custom_stages[{}]", index), registry::check_stage_name(name));
+        }
+        for (key, value) in [
+            ("worker_threads", self.worker_threads as u64),
+            ("worker_queue_capacity", self.worker_queue_capacity as u64),
+            ("rate_limit_burst", self.rate_limit_burst as u64),
+            ("stream_process_buffer", self.stream_process_buffer as u64),
+            ("stream_save_buffer", self.stream_save_buffer as u64),
+            ("save_batch_size", self.save_batch_size as u64),
+            ("checkpoint_interval", self.checkpoint_interval as u64),
+            ("watch_interval_secs", self.watch_interval_secs),
+        ] {
+            check(key, if value == 0 { Err(String::from("must be at least 1")) } else { Ok(()) });
+        }
+
+        // Values that are fine on their own but contradict each other
+        if !self.encryption_key_env.is_empty() && !self.encryption_key_file.as_os_str().is_empty() {
+            check(
+                "encryption_key_file",
+                Err(String::from("cannot be set together with encryption_key_env")),
+            );
+        }
+        for tag in self.required_tags.iter().filter(|tag| self.excluded_tags.contains(tag)) {
+            check(
+                "excluded_tags",
+                Err(format!("tag '{}' is also required (see required_tags)", tag)),
+            );
+        }
+        if storage::canonical_path(&self.checkpoint_path) == storage::canonical_path(&self.data_path) {
+            check(
+                "checkpoint_path",
+                Err(format!("'{}' is also the data file path", self.checkpoint_path.display())),
+            );
+        }
+        if let Some(tenant) = &self.tenant {
+            for key in TENANT_PATH_KEYS {
+                let value = self.value_of(key).and_then(|value| value.as_str().map(String::from)).unwrap_or_default();
+                if !value.is_empty() && !matches!(self.source_of(key), ConfigSource::Tenant { .. }) {
+                    check(
+                        key,
+                        Err(format!(
+                            "tenant '{}' must set its own {} in [tenants.{}] instead of sharing '{}'",
+                            tenant, key, tenant, value
+                        )),
+                    );
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigErrors { problems })
+        }
+    }
+
+    /**
+     * @brief Apply the config values that are process-wide.
+     *
+     * Sets the kind of item id accepted when loading, the locale of log and report messages, and the rate limit of
+     * the HTTP and Kafka sinks.
+     */
+    pub fn apply_process_settings(&self) {
+        if let Ok(kind) = IdKind::parse(&self.item_id_kind) {
+            item_id::set_expected_kind(kind);
+        }
+        if let Ok(locale) = Locale::resolve(&self.locale) {
+            messages::set_locale(locale);
+        }
+        rate_limit::set_sink_limit(self.rate_limit, self.rate_limit_burst);
+    }
+}
+
+/**
+ * @brief Map a key to its entry in CONFIG_KEYS.
+ *
+ * @param key The key as given in a file, variable name or flag.
+ * @return Result<&'static str, String> The key, or an error message for unknown keys.
+ */
+fn known_key(key: &str) -> Result<&'static str, String> {
+    CONFIG_KEYS
+        .iter()
+        .copied()
+        .find(|known| *known == key)
+        .ok_or_else(|| format!("Unknown configuration key '{}'", key))
+}
+
+/**
+ * @brief Parse a numeric config value.
+ *
+ * @param value The value as text.
+ * @return Result<T, String> The number, or an error message.
+ */
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String>
+where
+    T::Err: fmt::Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid number '{}': {}", value, e))
+}
+
+/**
+ * @brief Parse a flag given as text: "true"/"false", "yes"/"no" or "1"/"0".
+ */
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(format!("Invalid flag '{}' (expected 'true' or 'false')", value)),
+    }
+}
+
+/**
+ * @brief Copy default (name, threshold) pairs into owned config values.
+ */
+fn owned_thresholds(thresholds: &[(&str, f64)]) -> Vec<(String, f64)> {
+    thresholds.iter().map(|(name, threshold)| (name.to_string(), *threshold)).collect()
+}
+
+/**
+ * @brief Parse thresholds written as "name=threshold".
+ *
+ * @param values The entries.
+ * @return Result<Vec<(String, f64)>, String> The (name, threshold) pairs, or
+ *         an error message for the first malformed entry.
+ */
+fn parse_thresholds(values: &[String]) -> Result<Vec<(String, f64)>, String> {
+    values
+        .iter()
+        .map(|entry| {
+            let (name, threshold) = entry
+                .split_once('=')
+                .filter(|(name, _)| !name.trim().is_empty())
+                .ok_or_else(|| format!("Threshold '{}' is not of the form name=threshold", entry))?;
+            Ok((name.trim().to_string(), parse_number(threshold)?))
+        })
+        .collect()
+}
+
+/**
+ * @brief Check that a threshold is a finite, non-negative number.
+ */
+fn check_threshold(threshold: f64) -> Result<(), String> {
+    if !threshold.is_finite() || threshold < 0.0 {
+        Err(format!("must be a non-negative number, got {}", threshold))
+    } else {
+        Ok(())
+    }
 }
 
 // End of rust_sample_project/src/config.rs
\ No newline at end of file
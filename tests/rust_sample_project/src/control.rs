@@ -0,0 +1,69 @@
+// rust_sample_project/src/control.rs
+
+// Pausing processing, for operators quiescing the daemon before maintenance
+// (see the /control endpoints in daemon.rs). While processing is paused, the
+// workers of a pipeline run finish the item they are on and then wait before
+// taking the next one; nothing is lost or saved early. Resuming lets them go
+// on. A shutdown request (SIGINT or SIGTERM) ends the wait, so a paused run
+// still stops and saves its progress as usual.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::shutdown;
+
+/// How often waiting workers check whether processing was resumed.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Set while processing is paused.
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/**
+ * @brief Pause processing before the next item.
+ *
+ * @return bool True if processing was running, false if it was paused already.
+ */
+pub fn pause() -> bool {
+    let paused = !PAUSED.swap(true, Ordering::SeqCst);
+    if paused {
+        log_warning!("control:pause", "Processing paused; items in progress finish, the others wait for resume.");
+    }
+    paused
+}
+
+/**
+ * @brief Resume paused processing.
+ *
+ * @return bool True if processing was paused, false if it was running already.
+ */
+pub fn resume() -> bool {
+    let resumed = PAUSED.swap(false, Ordering::SeqCst);
+    if resumed {
+        log_info!("control:resume", "Processing resumed.");
+    }
+    resumed
+}
+
+/**
+ * @brief Return whether processing is paused.
+ *
+ * @return bool True while paused.
+ */
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
+
+/**
+ * @brief Wait until processing is resumed or a shutdown is requested.
+ *
+ * Returns at once while processing is not paused; called by workers before
+ * they take the next item.
+ */
+pub fn wait_while_paused() {
+    while is_paused() && !shutdown::requested() {
+        thread::sleep(PAUSE_POLL_INTERVAL);
+    }
+}
+
+// End of rust_sample_project/src/control.rs
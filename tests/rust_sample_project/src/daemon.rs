@@ -0,0 +1,412 @@
+// rust_sample_project/src/daemon.rs
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::control;
+use crate::health::Health;
+use crate::metrics::Metrics;
+use crate::report::PipelineReport;
+use crate::shutdown;
+
+/// How often the shutdown watcher checks for a pending SIGINT/SIGTERM.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How long a client may take to send its request or read the response.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connections answered at once; further ones get 503 until one finishes.
+const MAX_CONNECTIONS: usize = 32;
+
+/**
+ * @struct RunOutcome
+ * @brief Result of the most recent pipeline run triggered through the API.
+ */
+#[derive(Debug, Clone, Serialize)]
+struct RunOutcome {
+    /// When the run was triggered.
+    triggered_at: DateTime<Utc>,
+    /// The tenant the run was for, if one was given.
+    tenant: Option<String>,
+    /// Whether the run completed without error.
+    ok: bool,
+    /// The error message of a failed run.
+    error: Option<String>,
+    /// The report of a successful run.
+    report: Option<PipelineReport>,
+}
+
+/**
+ * @struct DaemonState
+ * @brief State shared between the HTTP listener and the run worker thread.
+ */
+struct DaemonState {
+    /// Whether a run is in progress; only one run executes at a time.
+    running: AtomicBool,
+    /// Set by POST /control/drain: the run in progress finishes, no new run starts.
+    draining: AtomicBool,
+    /// Number of runs started since the daemon came up.
+    runs_started: AtomicU64,
+    /// The outcome of the most recent finished run.
+    last_run: Mutex<Option<RunOutcome>>,
+    /// When the daemon came up.
+    started_at: DateTime<Utc>,
+}
+
+/**
+ * @brief Keep the process alive and trigger pipeline runs over HTTP.
+ *
+ * Endpoints:
+ *   POST /run     start a run in the background (202), or 409 if one is in progress;
+ *                 POST /run?tenant=<name> runs with that tenant's config (see config.rs)
+ *   GET  /status  JSON with the current state and the last run's outcome/report
+ *   GET  /health  liveness probe, always 200 while the process is up
+ *   GET  /healthz liveness probe with the last run's outcome, see health.rs
+ *   GET  /readyz  readiness probe: config valid and data source reachable
+ *   GET  /metrics Prometheus metrics accumulated across runs
+ *
+ * Control endpoints, for quiescing the daemon before maintenance:
+ *   POST /control/pause          hold processing before the next item (see control.rs);
+ *                                no new run starts until resumed
+ *   POST /control/resume         continue processing, and accept runs again after a drain
+ *   POST /control/drain          let the run in progress finish (resuming it if paused),
+ *                                start no new ones; /status shows "drained" once it is done
+ *   POST /control/reload-config  re-read the config file now; applies to the next run
+ *
+ * Each connection is answered on its own thread, up to MAX_CONNECTIONS at
+ * once, and a client that does not send its request or read the response
+ * within CONNECTION_TIMEOUT is dropped, so a slow or idle client never holds
+ * up the probes. On SIGINT/SIGTERM a running pipeline stops and saves its
+ * progress, after which the process exits with shutdown::EXIT_INTERRUPTED.
+ *
+ * @param addr The socket address to listen on, e.g. "127.0.0.1:8080".
+ * @param metrics The shared metrics, accumulated across runs.
+ * @param health Answers the probes; run_pipeline records its runs there.
+ * @param run_pipeline Executes one pipeline run.
+ * @param reload_config Re-reads the configuration, returning the changed
+ *        values or why the config file was rejected.
+ * @return Result<(), String> Only returns if the listener cannot be bound, with the error message.
+ */
+pub fn serve<F, R>(
+    addr: &str,
+    metrics: Arc<Metrics>,
+    health: Arc<Health>,
+    run_pipeline: F,
+    reload_config: R,
+) -> Result<(), String>
+where
+    F: Fn(Option<&str>) -> Result<PipelineReport, String> + Send + Sync + 'static,
+    R: Fn() -> Result<Vec<String>, String> + Sync,
+{
+    let listener =
+        TcpListener::bind(addr).map_err(|e| format!("Failed to bind API on {}: {}", addr, e))?;
+    log_info!("daemon:serve", "Serving the pipeline API on http://{}", addr);
+
+    let state = Arc::new(DaemonState {
+        running: AtomicBool::new(false),
+        draining: AtomicBool::new(false),
+        runs_started: AtomicU64::new(0),
+        last_run: Mutex::new(None),
+        started_at: Utc::now(),
+    });
+    let run_pipeline = Arc::new(run_pipeline);
+
+    let watched_state = Arc::clone(&state);
+    thread::spawn(move || loop {
+        if shutdown::requested() && !watched_state.running.load(Ordering::SeqCst) {
+            log_info!("daemon:serve", "Shutdown requested, stopping the daemon.");
+            shutdown::exit(shutdown::EXIT_INTERRUPTED);
+        }
+        thread::sleep(SHUTDOWN_POLL_INTERVAL);
+    });
+
+    let handlers = Handlers {
+        metrics: &metrics,
+        health: &health,
+        run_pipeline: &run_pipeline,
+        reload_config: &reload_config,
+    };
+    let open_connections = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = match stream.and_then(|stream| {
+                stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+                stream.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+                Ok(stream)
+            }) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log_warning!("daemon:serve", "API connection failed: {}", e);
+                    continue;
+                }
+            };
+            if open_connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                open_connections.fetch_sub(1, Ordering::SeqCst);
+                let body = String::from("Too many connections\n");
+                if let Err(e) = write_response(stream, "503 Service Unavailable", "text/plain", &body) {
+                    log_warning!("daemon:serve", "API request failed: {}", e);
+                }
+                continue;
+            }
+            let (state, handlers, open_connections) = (&state, &handlers, &open_connections);
+            scope.spawn(move || {
+                if let Err(e) = handle_connection(stream, state, handlers) {
+                    log_warning!("daemon:serve", "API request failed: {}", e);
+                }
+                open_connections.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+    Ok(())
+}
+
+/**
+ * @struct Handlers
+ * @brief What the API requests are answered with.
+ */
+struct Handlers<'a, F, R> {
+    /// The metrics to render for GET /metrics.
+    metrics: &'a Metrics,
+    /// Answers GET /healthz and /readyz.
+    health: &'a Health,
+    /// Executes one pipeline run for POST /run.
+    run_pipeline: &'a Arc<F>,
+    /// Re-reads the configuration for POST /control/reload-config.
+    reload_config: &'a R,
+}
+
+/**
+ * @brief Start a pipeline run on a worker thread unless one is already running.
+ *
+ * @param state The shared daemon state.
+ * @param run_pipeline Executes one pipeline run.
+ * @param tenant The tenant to run for, or None for the daemon's own configuration.
+ * @return Result<(), &'static str> Ok if a run was started, or why not.
+ */
+fn trigger_run<F>(state: &Arc<DaemonState>, run_pipeline: &Arc<F>, tenant: Option<String>) -> Result<(), &'static str>
+where
+    F: Fn(Option<&str>) -> Result<PipelineReport, String> + Send + Sync + 'static,
+{
+    if shutdown::requested() {
+        return Err("the daemon is shutting down");
+    }
+    if control::is_paused() {
+        return Err("processing is paused");
+    }
+    if state.draining.load(Ordering::SeqCst) {
+        return Err("the daemon is draining");
+    }
+    if state.running.swap(true, Ordering::SeqCst) {
+        return Err("a run is already in progress");
+    }
+    let run_number = state.runs_started.fetch_add(1, Ordering::SeqCst) + 1;
+    match &tenant {
+        Some(tenant) => {
+            log_info!("daemon:trigger_run", "Starting pipeline run #{} for tenant {}.", run_number, tenant)
+        }
+        None => log_info!("daemon:trigger_run", "Starting pipeline run #{}.", run_number),
+    }
+
+    let state = Arc::clone(state);
+    let run_pipeline = Arc::clone(run_pipeline);
+    thread::spawn(move || {
+        let triggered_at = Utc::now();
+        let outcome = match run_pipeline(tenant.as_deref()) {
+            Ok(report) => RunOutcome {
+                triggered_at,
+                tenant,
+                ok: true,
+                error: None,
+                report: Some(report),
+            },
+            Err(e) => {
+                log_error!("daemon:trigger_run", "Pipeline run #{} failed: {}", run_number, e);
+                RunOutcome {
+                    triggered_at,
+                    tenant,
+                    ok: false,
+                    error: Some(e),
+                    report: None,
+                }
+            }
+        };
+        if let Ok(mut last_run) = state.last_run.lock() {
+            *last_run = Some(outcome);
+        }
+        state.running.store(false, Ordering::SeqCst);
+    });
+    Ok(())
+}
+
+/**
+ * @brief Return the daemon's state as shown by /status.
+ *
+ * @param state The shared daemon state.
+ * @return &'static str "paused", "draining", "drained", "running" or "idle".
+ */
+fn state_name(state: &DaemonState) -> &'static str {
+    let running = state.running.load(Ordering::SeqCst);
+    match (control::is_paused(), state.draining.load(Ordering::SeqCst), running) {
+        (true, _, _) => "paused",
+        (false, true, true) => "draining",
+        (false, true, false) => "drained",
+        (false, false, true) => "running",
+        (false, false, false) => "idle",
+    }
+}
+
+/**
+ * @brief Answer a POST /control/<command> request.
+ *
+ * @param command "pause", "resume", "drain" or "reload-config".
+ * @param state The shared daemon state.
+ * @param reload_config Re-reads the configuration.
+ * @return Option<(&'static str, String)> The status line and JSON body, or
+ *         None for an unknown command.
+ */
+fn control_command<R>(command: &str, state: &DaemonState, reload_config: &R) -> Option<(&'static str, String)>
+where
+    R: Fn() -> Result<Vec<String>, String>,
+{
+    let (status, mut body) = match command {
+        "pause" => {
+            let changed = control::pause();
+            ("200 OK", json!({ "changed": changed }))
+        }
+        "resume" => {
+            let changed = control::resume() | state.draining.swap(false, Ordering::SeqCst);
+            ("200 OK", json!({ "changed": changed }))
+        }
+        "drain" => {
+            let changed = !state.draining.swap(true, Ordering::SeqCst);
+            if changed {
+                log_info!("daemon:control_command", "Draining: the run in progress finishes, no new run starts.");
+            }
+            control::resume();
+            ("200 OK", json!({ "changed": changed }))
+        }
+        "reload-config" => match reload_config() {
+            Ok(changes) => ("200 OK", json!({ "changed": !changes.is_empty(), "changes": changes })),
+            Err(e) => ("422 Unprocessable Entity", json!({ "changed": false, "error": e })),
+        },
+        _ => return None,
+    };
+    body["state"] = json!(state_name(state));
+    Some((status, format!("{}\n", body)))
+}
+
+/**
+ * @brief Render the /status document.
+ *
+ * @param state The shared daemon state.
+ * @return String The status as pretty-printed JSON.
+ */
+fn render_status(state: &DaemonState) -> String {
+    let last_run = state.last_run.lock().map(|last| last.clone()).unwrap_or(None);
+    let status = json!({
+        "state": state_name(state),
+        "started_at": state.started_at,
+        "runs_started": state.runs_started.load(Ordering::SeqCst),
+        "last_run": last_run,
+    });
+    let mut body = serde_json::to_string_pretty(&status).unwrap_or_else(|_| String::from("{}"));
+    body.push('\n');
+    body
+}
+
+/**
+ * @brief Return the value of a parameter of a request's query string.
+ *
+ * @param query The query string, without the leading '?'.
+ * @param name The parameter's name.
+ * @return Option<String> The value, or None if the parameter is missing or empty.
+ */
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, value)| *key == name && !value.is_empty())
+        .map(|(_, value)| value.to_string())
+}
+
+/**
+ * @brief Answer a single HTTP request on the API.
+ *
+ * @param stream The accepted client connection.
+ * @param state The shared daemon state.
+ * @param handlers What the requests are answered with.
+ * @return std::io::Result<()> Ok once the response is written.
+ */
+fn handle_connection<F, R>(
+    stream: TcpStream,
+    state: &Arc<DaemonState>,
+    handlers: &Handlers<'_, F, R>,
+) -> std::io::Result<()>
+where
+    F: Fn(Option<&str>) -> Result<PipelineReport, String> + Send + Sync + 'static,
+    R: Fn() -> Result<Vec<String>, String>,
+{
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("");
+    log_debug!("daemon:handle_connection", "{} {}", method, target);
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let control = path.strip_prefix("/control/");
+    let (status, content_type, body) = match (method, path) {
+        (_, "/healthz") | (_, "/readyz") => handlers.health.respond(method, path),
+        ("POST", "/run") => match trigger_run(state, handlers.run_pipeline, query_param(query, "tenant")) {
+            Ok(()) => ("202 Accepted", "application/json", format!("{}\n", json!({ "started": true }))),
+            Err(reason) => {
+                let body = json!({ "started": false, "reason": reason });
+                ("409 Conflict", "application/json", format!("{}\n", body))
+            }
+        },
+        ("GET", "/status") => ("200 OK", "application/json", render_status(state)),
+        ("GET", "/health") => ("200 OK", "text/plain", String::from("OK\n")),
+        ("GET", "/metrics") => ("200 OK", "text/plain; version=0.0.4", handlers.metrics.render_prometheus()),
+        (_, "/run") | (_, "/status") | (_, "/health") | (_, "/metrics") => {
+            ("405 Method Not Allowed", "text/plain", String::from("Method Not Allowed\n"))
+        }
+        ("POST", _) => match control.and_then(|command| control_command(command, state, handlers.reload_config)) {
+            Some((status, body)) => (status, "application/json", body),
+            None => ("404 Not Found", "text/plain", String::from("Not Found\n")),
+        },
+        _ if control.is_some() => ("405 Method Not Allowed", "text/plain", String::from("Method Not Allowed\n")),
+        _ => ("404 Not Found", "text/plain", String::from("Not Found\n")),
+    };
+    write_response(stream, status, content_type, &body)
+}
+
+/**
+ * @brief Write an HTTP response and close the connection.
+ *
+ * @param stream The client connection.
+ * @param status The status line, e.g. "200 OK".
+ * @param content_type The Content-Type of the body.
+ * @param body The response body.
+ * @return std::io::Result<()> Ok once the response is written.
+ */
+fn write_response(mut stream: TcpStream, status: &str, content_type: &str, body: &str) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+// End of rust_sample_project/src/daemon.rs
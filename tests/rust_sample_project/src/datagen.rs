@@ -0,0 +1,378 @@
+// rust_sample_project/src/datagen.rs
+
+// Synthetic item datasets for demos and load tests. Records are generated as
+// JSON values so that a configurable share of them can be deliberately
+// invalid (missing fields, wrong types), exercising the loaders' skip logic.
+
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Exp, Normal};
+use serde_json::{json, Value};
+use uuid::Builder;
+
+use crate::compression::Compression;
+use crate::config::{
+    get_datagen_count, get_datagen_distribution, get_datagen_invalid_ratio, get_datagen_name_pattern,
+};
+use crate::data_handler::{DataFormat, CURRENT_SCHEMA_VERSION};
+use crate::integrity;
+use crate::item::Item;
+use crate::item_id::{self, IdKind, ItemId};
+use crate::storage;
+use crate::xlsx_format::{self, XlsxMapping};
+
+/// Words substituted for `{word}` in name patterns.
+const NAME_WORDS: &[&str] = &[
+    "Gadget", "Widget", "Thingamajig", "Doohickey", "Gizmo", "Contraption", "Sprocket", "Whatsit",
+];
+
+/**
+ * @enum ValueDistribution
+ * @brief How the values of generated items are distributed.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueDistribution {
+    /// Uniform between min and max ("uniform:<min>,<max>").
+    Uniform { min: f64, max: f64 },
+    /// Normal with the given mean and standard deviation ("normal:<mean>,<std_dev>").
+    Normal { mean: f64, std_dev: f64 },
+    /// Exponential with the given mean ("exponential:<mean>").
+    Exponential { mean: f64 },
+}
+
+impl ValueDistribution {
+    /**
+     * @brief Parse a distribution spec as used in config and on the command line.
+     *
+     * @param spec E.g. "uniform:0,200", "normal:100,25" or "exponential:80".
+     * @return Result<ValueDistribution, String> The distribution, or an error message.
+     */
+    pub fn parse(spec: &str) -> Result<ValueDistribution, String> {
+        let (kind, params) = spec.split_once(':').unwrap_or((spec, ""));
+        let numbers = params
+            .split(',')
+            .filter(|p| !p.trim().is_empty())
+            .map(|p| p.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|e| format!("Invalid value distribution '{}': {}", spec, e))?;
+        let distribution = match (kind.trim(), numbers.as_slice()) {
+            ("uniform", [min, max]) if min < max => ValueDistribution::Uniform { min: *min, max: *max },
+            ("normal", [mean, std_dev]) if *std_dev > 0.0 => ValueDistribution::Normal {
+                mean: *mean,
+                std_dev: *std_dev,
+            },
+            ("exponential", [mean]) if *mean > 0.0 => ValueDistribution::Exponential { mean: *mean },
+            _ => {
+                return Err(format!(
+                    "Invalid value distribution '{}' (expected 'uniform:<min>,<max>', 'normal:<mean>,<std_dev>' or 'exponential:<mean>')",
+                    spec
+                ))
+            }
+        };
+        Ok(distribution)
+    }
+
+    /**
+     * @brief Draw a value, rounded to two decimals.
+     *
+     * @param rng The random number generator.
+     * @return f64 The value.
+     */
+    fn sample(&self, rng: &mut StdRng) -> f64 {
+        let value = match *self {
+            ValueDistribution::Uniform { min, max } => rng.gen_range(min..max),
+            ValueDistribution::Normal { mean, std_dev } => Normal::new(mean, std_dev)
+                .map(|normal| normal.sample(rng))
+                .unwrap_or(mean),
+            ValueDistribution::Exponential { mean } => Exp::new(1.0 / mean)
+                .map(|exp| exp.sample(rng))
+                .unwrap_or(mean),
+        };
+        (value * 100.0).round() / 100.0
+    }
+}
+
+/**
+ * @struct DatagenOptions
+ * @brief Parameters of a generated dataset.
+ */
+#[derive(Debug, Clone)]
+pub struct DatagenOptions {
+    /// Number of records to generate (valid and invalid).
+    pub count: usize,
+    /// Seed for reproducible datasets; None draws a random seed.
+    pub seed: Option<u64>,
+    /// Distribution of the item values.
+    pub distribution: ValueDistribution,
+    /// Item name pattern; `{id}` is replaced by the item ID, `{word}` by a random word.
+    pub name_pattern: String,
+    /// Share of records (0.0 to 1.0) that are deliberately invalid.
+    pub invalid_ratio: f64,
+}
+
+impl DatagenOptions {
+    /**
+     * @brief Build the options from the DATAGEN_* config values.
+     *
+     * @return Result<DatagenOptions, String> The options, or an error message
+     *         for an invalid configured distribution or ratio.
+     */
+    pub fn from_config() -> Result<Self, String> {
+        let options = DatagenOptions {
+            count: get_datagen_count(),
+            seed: None,
+            distribution: ValueDistribution::parse(get_datagen_distribution())?,
+            name_pattern: get_datagen_name_pattern().to_string(),
+            invalid_ratio: get_datagen_invalid_ratio(),
+        };
+        options.validate()?;
+        Ok(options)
+    }
+
+    /**
+     * @brief The small, fixed dataset used when the data file does not exist yet.
+     *
+     * @return DatagenOptions Six records, one in three invalid, always the same.
+     */
+    pub fn simulated() -> Self {
+        DatagenOptions {
+            count: 6,
+            seed: Some(7),
+            distribution: ValueDistribution::Uniform { min: 10.0, max: 250.0 },
+            name_pattern: String::from("{word} {id}"),
+            invalid_ratio: 1.0 / 3.0,
+        }
+    }
+
+    /**
+     * @brief Check that the options describe a dataset that can be generated.
+     *
+     * @return Result<(), String> Ok, or an error message naming the bad option.
+     */
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.invalid_ratio) {
+            return Err(format!(
+                "Invalid record ratio must be between 0 and 1, got {}",
+                self.invalid_ratio
+            ));
+        }
+        if self.name_pattern.trim().is_empty() {
+            return Err(String::from("Name pattern must not be empty"));
+        }
+        Ok(())
+    }
+}
+
+/**
+ * @brief Generate the records of a dataset as JSON values.
+ *
+ * Invalid records lack a required field or carry a wrongly typed value, so
+ * they are skipped when loaded.
+ *
+ * @param options The dataset parameters.
+ * @return Vec<Value> The records (invalid ones included), with IDs of the
+ *         expected kind (see item_id::set_expected_kind): 1, 2, ... for
+ *         integers, random UUIDs, or "item-1", "item-2", ... for strings.
+ */
+pub fn generate_records(options: &DatagenOptions) -> Vec<Value> {
+    let mut rng = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    (1..=options.count)
+        .map(|index| {
+            let item_id = match item_id::expected_kind() {
+                IdKind::Int => ItemId::Int(index as i64),
+                IdKind::Uuid => ItemId::Uuid(Builder::from_random_bytes(rng.gen()).into_uuid()),
+                IdKind::Str => ItemId::Str(format!("item-{}", index)),
+            };
+            let word = NAME_WORDS[rng.gen_range(0..NAME_WORDS.len())];
+            let name = options
+                .name_pattern
+                .replace("{id}", &item_id.to_string())
+                .replace("{word}", word);
+            let value = options.distribution.sample(&mut rng);
+            let mut record = json!({ "item_id": item_id, "name": name, "value": value });
+            if rng.gen_bool(options.invalid_ratio) {
+                corrupt_record(&mut record, &mut rng);
+            }
+            record
+        })
+        .collect()
+}
+
+/**
+ * @brief Generate the valid items of a dataset.
+ *
+ * @param options The dataset parameters; invalid_ratio is ignored.
+ * @return Vec<Item> The items.
+ */
+pub fn generate_items(options: &DatagenOptions) -> Vec<Item> {
+    let valid_options = DatagenOptions {
+        invalid_ratio: 0.0,
+        ..options.clone()
+    };
+    generate_records(&valid_options)
+        .into_iter()
+        .filter_map(|record| serde_json::from_value(record).ok())
+        .collect()
+}
+
+/**
+ * @brief Make a record invalid in one of several ways.
+ *
+ * @param record The valid record to corrupt.
+ * @param rng The random number generator.
+ */
+fn corrupt_record(record: &mut Value, rng: &mut StdRng) {
+    let Some(fields) = record.as_object_mut() else { return };
+    match rng.gen_range(0..4) {
+        0 => {
+            fields.remove("item_id");
+        }
+        1 => {
+            fields.remove("name");
+        }
+        2 => {
+            fields.insert(String::from("value"), Value::String(String::from("n/a")));
+        }
+        _ => {
+            fields.insert(String::from("value"), Value::Null);
+        }
+    }
+}
+
+/**
+ * @brief Generate a dataset and write it as a data file.
+ *
+ * The format follows the file extension (JSON, CSV, NDJSON or Parquet, optionally
+ * compressed as .gz/.zst), so any file DataHandler can read can be generated.
+ * Parquet and Excel files hold only the valid records (see render_parquet).
+ * A local file gets a checksum file like a saved one (see integrity.rs).
+ *
+ * @param path Where the dataset is written (path or supported URL).
+ * @param options The dataset parameters.
+ * @return Result<(usize, usize), String> The number of records written and
+ *         how many of them are invalid, or an error message.
+ */
+pub fn write_dataset(path: &Path, options: &DatagenOptions) -> Result<(usize, usize), String> {
+    options.validate()?;
+    let store = storage::open(path)?;
+    let path = store.location();
+    let records = generate_records(options);
+    let invalid = records
+        .iter()
+        .filter(|record| serde_json::from_value::<Item>((*record).clone()).is_err())
+        .count();
+
+    let format = DataFormat::from_path(path)?;
+    let contents = match format {
+        DataFormat::Json => {
+            let document = json!({ "schema_version": CURRENT_SCHEMA_VERSION, "items": records });
+            serde_json::to_vec_pretty(&document)
+                .map_err(|e| format!("Failed to serialize generated items: {}", e))?
+        }
+        DataFormat::Csv => render_csv(&records).into_bytes(),
+        DataFormat::Ndjson => render_ndjson(&records).into_bytes(),
+        DataFormat::Parquet => render_parquet(&records, path)?,
+        DataFormat::Xlsx => xlsx_format::encode_items(&valid_items(&records), &XlsxMapping::default())
+            .map_err(|e| format!("Failed to save data file '{}': {}", path, e))?,
+        DataFormat::Xml => {
+            return Err(format!("Cannot generate '{}': XML is an input-only format", path));
+        }
+    };
+    let encoded = Compression::from_path(path)
+        .encode(&contents)
+        .map_err(|e| format!("Failed to save data file '{}': {}", path, e))?;
+    store.write(&encoded)?;
+    if let Some(local_path) = store.local_path() {
+        integrity::write_sidecar(local_path, &integrity::digest(&encoded))?;
+    }
+
+    if matches!(format, DataFormat::Parquet | DataFormat::Xlsx) {
+        return Ok((records.len() - invalid, 0));
+    }
+    Ok((records.len(), invalid))
+}
+
+/**
+ * @brief Render records as a Parquet file.
+ *
+ * Parquet columns are typed, so invalid records cannot be represented and
+ * are left out; only the valid items are written.
+ *
+ * @param records The generated records.
+ * @param path The output path, used in messages.
+ * @return Result<Vec<u8>, String> The Parquet file, or an error message.
+ */
+fn render_parquet(records: &[Value], path: &str) -> Result<Vec<u8>, String> {
+    let items = valid_items(records);
+    #[cfg(feature = "parquet")]
+    return crate::parquet_format::encode_items(&items)
+        .map_err(|e| format!("Failed to save data file '{}': {}", path, e));
+    #[cfg(not(feature = "parquet"))]
+    {
+        let _ = items;
+        Err(format!(
+            "Cannot save '{}' in Parquet format: this build lacks the 'parquet' feature",
+            path
+        ))
+    }
+}
+
+/**
+ * @brief Return the records that make valid items, as items.
+ *
+ * @param records The generated records.
+ * @return Vec<Item> The valid items, in record order.
+ */
+fn valid_items(records: &[Value]) -> Vec<Item> {
+    records
+        .iter()
+        .filter_map(|record| serde_json::from_value(record.clone()).ok())
+        .collect()
+}
+
+/**
+ * @brief Render records as NDJSON, one record per line.
+ *
+ * @param records The generated records.
+ * @return String The NDJSON document.
+ */
+fn render_ndjson(records: &[Value]) -> String {
+    records.iter().map(|record| format!("{}\n", record)).collect()
+}
+
+/**
+ * @brief Render records as CSV with the columns the CSV loader expects.
+ *
+ * Missing numbers become empty cells, rows without a name end after the ID,
+ * and non-numeric values are written as-is, so invalid records stay invalid.
+ *
+ * @param records The generated records.
+ * @return String The CSV document, including the header row.
+ */
+fn render_csv(records: &[Value]) -> String {
+    let mut csv = String::from("item_id,name,value\n");
+    for record in records {
+        let cell = |field: &str| match record.get(field) {
+            Some(Value::String(text)) => format!("\"{}\"", text.replace('"', "\"\"")),
+            Some(Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        };
+        // An empty cell would load as an empty name, so a missing name ends the row
+        let row = if record.get("name").is_some() {
+            format!("{},{},{}", cell("item_id"), cell("name"), cell("value"))
+        } else {
+            cell("item_id")
+        };
+        csv.push_str(&row);
+        csv.push('\n');
+    }
+    csv
+}
+
+// End of rust_sample_project/src/datagen.rs
@@ -0,0 +1,287 @@
+// rust_sample_project/src/dedup.rs
+
+// Duplicate item ids: deduplicate resolves them right after loading, by the
+// dedup strategy. Steps after that can bring duplicates back, e.g. a join
+// with a secondary dataset listing an id twice, or a composed pipeline
+// merging several sources without deduplication, so check_before_save looks
+// at the output once more, by the duplicate id policy.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::item::Item;
+use crate::item_id::ItemId;
+
+/// Duplicate ids named in messages; the rest are only counted.
+const MAX_LISTED_DUPLICATES: usize = 10;
+
+/**
+ * @enum DedupStrategy
+ * @brief How to resolve several loaded items sharing the same item_id.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupStrategy {
+    /// Keep the first occurrence, drop later ones ("keep-first").
+    KeepFirst,
+    /// Keep the last occurrence, drop earlier ones ("keep-last").
+    KeepLast,
+    /// Keep the occurrence with the highest value ("merge-by-max-value").
+    MergeByMaxValue,
+}
+
+impl DedupStrategy {
+    /**
+     * @brief Parse a strategy name as used in config.
+     *
+     * @param name One of "keep-first", "keep-last" or "merge-by-max-value".
+     * @return Result<DedupStrategy, String> The strategy, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<DedupStrategy, String> {
+        match name {
+            "keep-first" => Ok(DedupStrategy::KeepFirst),
+            "keep-last" => Ok(DedupStrategy::KeepLast),
+            "merge-by-max-value" => Ok(DedupStrategy::MergeByMaxValue),
+            other => Err(format!(
+                "Unknown dedup strategy '{}' (expected 'keep-first', 'keep-last' or 'merge-by-max-value')",
+                other
+            )),
+        }
+    }
+}
+
+/**
+ * @brief Remove items with duplicate ids according to the given strategy.
+ *
+ * The surviving items keep the position of the first occurrence of their id,
+ * so the relative order of distinct ids is the same as in the input.
+ *
+ * @param items The loaded items, possibly containing duplicate ids.
+ * @param strategy Which of the duplicates survives.
+ * @return (Vec<Item>, usize) The deduplicated items and the number of dropped duplicates.
+ */
+pub fn deduplicate(items: Vec<Item>, strategy: DedupStrategy) -> (Vec<Item>, usize) {
+    let input_len = items.len();
+    let mut position_by_id: HashMap<ItemId, usize> = HashMap::with_capacity(input_len);
+    let mut unique: Vec<Item> = Vec::with_capacity(input_len);
+
+    for item in items {
+        match position_by_id.get(&item.item_id) {
+            None => {
+                position_by_id.insert(item.item_id.clone(), unique.len());
+                unique.push(item);
+            }
+            Some(&position) => {
+                log_debug!(
+                    "dedup:deduplicate",
+                    "Duplicate item ID {} found ({:?}).",
+                    item.item_id,
+                    strategy
+                );
+                let replace = match strategy {
+                    DedupStrategy::KeepFirst => false,
+                    DedupStrategy::KeepLast => true,
+                    DedupStrategy::MergeByMaxValue => item.value > unique[position].value,
+                };
+                if replace {
+                    unique[position] = item;
+                }
+            }
+        }
+    }
+
+    let dropped = input_len - unique.len();
+    (unique, dropped)
+}
+
+/**
+ * @enum DuplicatePolicy
+ * @brief What saving does when the output contains duplicate item ids.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Save them without checking ("allow").
+    #[default]
+    Allow,
+    /// Save them, logging a warning and listing them in the report ("warn").
+    Warn,
+    /// Fail the save, writing nothing ("refuse").
+    Refuse,
+}
+
+impl DuplicatePolicy {
+    /**
+     * @brief Parse a policy name as used in config.
+     *
+     * @param name One of "allow", "warn" or "refuse".
+     * @return Result<DuplicatePolicy, String> The policy, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<DuplicatePolicy, String> {
+        match name {
+            "allow" => Ok(DuplicatePolicy::Allow),
+            "warn" => Ok(DuplicatePolicy::Warn),
+            "refuse" => Ok(DuplicatePolicy::Refuse),
+            other => Err(format!(
+                "Unknown duplicate id policy '{}' (expected 'allow', 'warn' or 'refuse')",
+                other
+            )),
+        }
+    }
+}
+
+/**
+ * @struct DuplicateId
+ * @brief An item id occurring more than once among items about to be saved.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicateId {
+    /// The id.
+    pub item_id: ItemId,
+    /// Number of items carrying it.
+    pub count: usize,
+}
+
+/**
+ * @brief Find the ids shared by several items.
+ *
+ * @param items The items.
+ * @return Vec<DuplicateId> The duplicate ids, in the order they first occur.
+ */
+pub fn find_duplicates(items: &[Item]) -> Vec<DuplicateId> {
+    let mut count_by_id: HashMap<&ItemId, usize> = HashMap::with_capacity(items.len());
+    for item in items {
+        *count_by_id.entry(&item.item_id).or_insert(0) += 1;
+    }
+    let mut duplicates = Vec::new();
+    for item in items {
+        if let Some(count) = count_by_id.remove(&item.item_id).filter(|count| *count > 1) {
+            duplicates.push(DuplicateId {
+                item_id: item.item_id.clone(),
+                count,
+            });
+        }
+    }
+    duplicates
+}
+
+/**
+ * @brief Describe duplicate ids for messages, e.g. "3 (2 items), 7 (3 items)".
+ */
+fn describe(duplicates: &[DuplicateId]) -> String {
+    let mut listed: Vec<String> = duplicates
+        .iter()
+        .take(MAX_LISTED_DUPLICATES)
+        .map(|duplicate| format!("{} ({} items)", duplicate.item_id, duplicate.count))
+        .collect();
+    if duplicates.len() > MAX_LISTED_DUPLICATES {
+        listed.push(format!("and {} more", duplicates.len() - MAX_LISTED_DUPLICATES));
+    }
+    listed.join(", ")
+}
+
+/**
+ * @brief Check the items about to be saved for duplicate ids.
+ *
+ * @param items The items about to be saved.
+ * @param policy What to do about duplicates.
+ * @return Result<Vec<DuplicateId>, String> The duplicate ids (none are looked
+ *         for under Allow), or an error message naming them under Refuse.
+ */
+pub fn check_before_save(items: &[Item], policy: DuplicatePolicy) -> Result<Vec<DuplicateId>, String> {
+    if policy == DuplicatePolicy::Allow {
+        return Ok(Vec::new());
+    }
+    let duplicates = find_duplicates(items);
+    if duplicates.is_empty() {
+        return Ok(duplicates);
+    }
+    if policy == DuplicatePolicy::Refuse {
+        return Err(format!(
+            "Refusing to save {} items with {} duplicate ids (duplicate_id_policy is refuse): {}",
+            items.len(),
+            duplicates.len(),
+            describe(&duplicates)
+        ));
+    }
+    log_warning!(
+        "dedup:check_before_save",
+        "Saving {} items with {} duplicate ids: {}",
+        items.len(),
+        duplicates.len(),
+        describe(&duplicates)
+    );
+    Ok(duplicates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Money;
+
+    /// Items with ids 1, 2, 1, 3, 2, named after their position.
+    fn loaded_items() -> Vec<Item> {
+        [(1, "20.00"), (2, "5.00"), (1, "30.00"), (3, "1.00"), (2, "4.00")]
+            .into_iter()
+            .enumerate()
+            .map(|(index, (id, value))| {
+                Item::new(ItemId::from(id), format!("#{}", index), Money::parse(value).unwrap())
+            })
+            .collect()
+    }
+
+    fn names(items: &[Item]) -> Vec<&str> {
+        items.iter().map(|item| item.name.as_ref()).collect()
+    }
+
+    #[test]
+    fn keep_first_keeps_the_earliest_occurrence() {
+        let (items, dropped) = deduplicate(loaded_items(), DedupStrategy::KeepFirst);
+
+        assert_eq!((names(&items), dropped), (vec!["#0", "#1", "#3"], 2));
+    }
+
+    #[test]
+    fn keep_last_keeps_the_latest_occurrence_in_the_first_position() {
+        let (items, dropped) = deduplicate(loaded_items(), DedupStrategy::KeepLast);
+
+        assert_eq!((names(&items), dropped), (vec!["#2", "#4", "#3"], 2));
+    }
+
+    #[test]
+    fn merge_by_max_value_keeps_the_highest_value_in_the_first_position() {
+        let (items, dropped) = deduplicate(loaded_items(), DedupStrategy::MergeByMaxValue);
+
+        assert_eq!((names(&items), dropped), (vec!["#2", "#1", "#3"], 2));
+    }
+
+    #[test]
+    fn duplicates_are_counted_in_order_of_first_occurrence() {
+        let duplicates = find_duplicates(&loaded_items());
+
+        assert_eq!(
+            duplicates,
+            [
+                DuplicateId { item_id: ItemId::from(1), count: 2 },
+                DuplicateId { item_id: ItemId::from(2), count: 2 },
+            ]
+        );
+        assert!(find_duplicates(&deduplicate(loaded_items(), DedupStrategy::KeepFirst).0).is_empty());
+    }
+
+    #[test]
+    fn saving_duplicates_follows_the_policy() {
+        let items = loaded_items();
+
+        assert!(check_before_save(&items, DuplicatePolicy::Allow).unwrap().is_empty());
+        assert_eq!(check_before_save(&items, DuplicatePolicy::Warn).unwrap().len(), 2);
+        let refused = check_before_save(&items, DuplicatePolicy::Refuse);
+        assert!(
+            matches!(&refused, Err(message) if message.ends_with("1 (2 items), 2 (2 items)")),
+            "{:?}",
+            refused
+        );
+        assert!(check_before_save(&items[..2], DuplicatePolicy::Refuse).unwrap().is_empty());
+    }
+}
+
+// End of rust_sample_project/src/dedup.rs
@@ -0,0 +1,269 @@
+// rust_sample_project/src/delivery.rs
+
+// Exactly-once delivery to network sinks (REST endpoints, see http_store.rs,
+// and the Kafka output topic, see kafka_stream.rs). Every delivered item gets
+// an idempotency key: a SHA-256 over the run id, the item id and a hash of the
+// item's contents. The contents hash leaves out when and how the item was
+// processed (processed_at and lineage), so reprocessing an unchanged item
+// yields the same key, while a changed item gets a new one. A batch posted
+// over HTTP carries a key derived from its items' keys in the Idempotency-Key
+// header, the same on every retry of the request; a Kafka record carries its
+// item's key as message key.
+//
+// Once a sink has acknowledged items, their keys are appended to the delivery
+// ledger (see the delivery_ledger_path config key), a JSON Lines file. Items
+// whose key is already in the ledger are not sent again, so an item saved to
+// the same sink twice in a run, or a message the stream reprocesses after a
+// crash, is delivered once. A stream counts as one long run per consumer
+// group, and a run resumed with --resume keeps the id of the run it resumes
+// (see checkpoint.rs). `ledger list` shows the deliveries, `ledger show <key>` one of them.
+
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::diff;
+use crate::integrity;
+use crate::item::Item;
+use crate::item_id::ItemId;
+use crate::storage;
+
+/// Number of key digits shown in the ledger table.
+const SHORT_KEY_DIGITS: usize = 16;
+
+/**
+ * @struct LedgerEntry
+ * @brief One delivered item, as recorded in the ledger.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    /// The item's idempotency key, 64 hex digits.
+    pub key: String,
+    /// The run that delivered the item (the consumer group for a stream).
+    pub run_id: String,
+    /// ID of the delivered item.
+    pub item_id: ItemId,
+    /// The URL or topic the item was delivered to.
+    pub sink: String,
+    /// When the sink acknowledged the item.
+    pub delivered_at: DateTime<Utc>,
+}
+
+impl LedgerEntry {
+    /**
+     * @brief Describe the delivery, one field per line.
+     *
+     * @return String The fields as a two-column table.
+     */
+    pub fn to_text(&self) -> String {
+        let rows = [
+            [String::from("Key:"), self.key.clone()],
+            [String::from("Run ID:"), self.run_id.clone()],
+            [String::from("Item ID:"), self.item_id.to_string()],
+            [String::from("Sink:"), self.sink.clone()],
+            [String::from("Delivered at:"), format_time(self.delivered_at)],
+        ];
+        diff::format_table(&rows)
+    }
+}
+
+/**
+ * @brief Return the idempotency key of an item delivered by a run.
+ *
+ * @param run_id The delivering run.
+ * @param item The item as delivered.
+ * @return Result<String, String> The key as 64 hex digits, or an error
+ *         message if the item cannot be serialized.
+ */
+pub fn idempotency_key(run_id: &str, item: &Item) -> Result<String, String> {
+    let mut contents = item.clone();
+    contents.processed_at = None;
+    contents.lineage = None;
+    let json = serde_json::to_vec(&contents)
+        .map_err(|e| format!("Failed to serialize item {}: {}", item.item_id, e))?;
+    let content_hash = integrity::digest(&json);
+    Ok(integrity::digest(format!("{}\n{}\n{}", run_id, item.item_id, content_hash).as_bytes()))
+}
+
+/**
+ * @brief Return the idempotency key of a batch, derived from its items' keys.
+ *
+ * @param keys The keys of the batch's items, in order.
+ * @return String The key as 64 hex digits.
+ */
+pub fn batch_key(keys: &[String]) -> String {
+    integrity::digest(keys.join("\n").as_bytes())
+}
+
+/**
+ * @struct Delivery
+ * @brief Assigns idempotency keys for a run and tracks which items were delivered.
+ *
+ * The ledger is read on first use; without a ledger path, items get keys but
+ * nothing is recorded.
+ */
+#[derive(Debug)]
+pub struct Delivery {
+    run_id: String,
+    /// The ledger file; empty to keep no ledger.
+    ledger_path: PathBuf,
+    /// Keys in the ledger, read on first use.
+    delivered: Mutex<Option<HashSet<String>>>,
+}
+
+impl Delivery {
+    /**
+     * @brief Constructs the delivery tracking of a run.
+     *
+     * @param run_id The run, see PipelineReport::run_id.
+     * @param ledger_path The ledger file, or an empty path for none.
+     * @return Delivery The tracking, with the ledger not read yet.
+     */
+    pub fn new(run_id: &str, ledger_path: &Path) -> Self {
+        Delivery {
+            run_id: run_id.to_string(),
+            ledger_path: ledger_path.to_path_buf(),
+            delivered: Mutex::new(None),
+        }
+    }
+
+    /**
+     * @brief Return the idempotency key of an item delivered by this run.
+     *
+     * @param item The item.
+     * @return Result<String, String> The key, see idempotency_key.
+     */
+    pub fn key(&self, item: &Item) -> Result<String, String> {
+        idempotency_key(&self.run_id, item)
+    }
+
+    /**
+     * @brief Return whether an item with this key was delivered before.
+     *
+     * @param key The item's idempotency key.
+     * @return Result<bool, String> True if the key is in the ledger, or an
+     *         error message if the ledger cannot be read.
+     */
+    pub fn is_delivered(&self, key: &str) -> Result<bool, String> {
+        if self.ledger_path.as_os_str().is_empty() {
+            return Ok(false);
+        }
+        let mut delivered = self.delivered.lock().unwrap_or_else(|e| e.into_inner());
+        if delivered.is_none() {
+            let keys = read(&self.ledger_path)?.into_iter().map(|entry| entry.key).collect();
+            *delivered = Some(keys);
+        }
+        Ok(delivered.as_ref().is_some_and(|keys| keys.contains(key)))
+    }
+
+    /**
+     * @brief Record items a sink has acknowledged.
+     *
+     * @param sink The URL or topic the items were delivered to.
+     * @param items The items' keys and ids.
+     * @return Result<(), String> Ok, or an error message if the ledger cannot be written.
+     */
+    pub fn record(&self, sink: &str, items: &[(String, ItemId)]) -> Result<(), String> {
+        if self.ledger_path.as_os_str().is_empty() || items.is_empty() {
+            return Ok(());
+        }
+        let delivered_at = Utc::now();
+        let mut lines = String::new();
+        for (key, item_id) in items {
+            let entry = LedgerEntry {
+                key: key.clone(),
+                run_id: self.run_id.clone(),
+                item_id: item_id.clone(),
+                sink: sink.to_string(),
+                delivered_at,
+            };
+            let line = serde_json::to_string(&entry)
+                .map_err(|e| format!("Failed to serialize ledger entry: {}", e))?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+        storage::create_parent_dir(&self.ledger_path)?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.ledger_path)
+            .and_then(|mut file| file.write_all(lines.as_bytes()))
+            .map_err(|e| format!("Failed to write delivery ledger '{}': {}", self.ledger_path.display(), e))?;
+        let mut delivered = self.delivered.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(keys) = delivered.as_mut() {
+            keys.extend(items.iter().map(|(key, _)| key.clone()));
+        }
+        Ok(())
+    }
+}
+
+/**
+ * @brief Format a timestamp for the ledger table.
+ */
+fn format_time(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+/**
+ * @brief Lay out deliveries as a table.
+ *
+ * @param entries The deliveries, oldest first.
+ * @return String The table, one line per delivery, with shortened keys.
+ */
+pub fn to_table(entries: &[LedgerEntry]) -> String {
+    let mut rows: Vec<[String; 5]> = vec![[
+        String::from("KEY"),
+        String::from("RUN ID"),
+        String::from("ITEM ID"),
+        String::from("SINK"),
+        String::from("DELIVERED"),
+    ]];
+    for entry in entries {
+        rows.push([
+            entry.key.chars().take(SHORT_KEY_DIGITS).collect(),
+            entry.run_id.clone(),
+            entry.item_id.to_string(),
+            entry.sink.clone(),
+            format_time(entry.delivered_at),
+        ]);
+    }
+    let mut text = diff::format_table(&rows);
+    text.push_str(&format!("{} deliveries.\n", entries.len()));
+    text
+}
+
+/**
+ * @brief Read the deliveries recorded in a ledger.
+ *
+ * Lines that are not a ledger entry are skipped with a warning.
+ *
+ * @param path Path of the ledger.
+ * @return Result<Vec<LedgerEntry>, String> The deliveries in the order they
+ *         were recorded (none if the file does not exist), or an error message.
+ */
+pub fn read(path: &Path) -> Result<Vec<LedgerEntry>, String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to open delivery ledger '{}': {}", path.display(), e)),
+    };
+    let mut entries = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read delivery ledger '{}': {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<LedgerEntry>(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => log_warning!("delivery:read", "Skipping line {} of {}: {}", index + 1, path.display(), e),
+        }
+    }
+    Ok(entries)
+}
+
+// End of rust_sample_project/src/delivery.rs
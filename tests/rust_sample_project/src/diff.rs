@@ -0,0 +1,311 @@
+// rust_sample_project/src/diff.rs
+
+// Comparison of two item datasets, typically a data file before and after a
+// pipeline run. Items are matched by id; the result lists the items only in
+// the second dataset (added), only in the first (removed), and the items in
+// both whose fields differ, field by field. Used by the diff subcommand, which
+// prints the result as a table or as JSON. Data files in the current schema
+// are compared as borrowed items (see item_ref.rs); only the added and
+// removed items are copied into the result.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::data_handler;
+use crate::encryption::EncryptionKey;
+use crate::item::Item;
+use crate::item_id::ItemId;
+use crate::item_ref::{self, ItemRef};
+
+/**
+ * @struct FieldChange
+ * @brief One field of an item that differs between the two datasets.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// Name of the field, as in data files (e.g. "value", "status").
+    pub field: &'static str,
+    /// The field in the first dataset.
+    pub before: Value,
+    /// The field in the second dataset.
+    pub after: Value,
+}
+
+/**
+ * @struct ChangedItem
+ * @brief An item present in both datasets with different fields.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedItem {
+    /// The item's id.
+    pub item_id: ItemId,
+    /// The fields that differ, in data file order.
+    pub changes: Vec<FieldChange>,
+}
+
+/**
+ * @struct DatasetDiff
+ * @brief The differences between two datasets, each list ordered by item id.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DatasetDiff {
+    /// Items only in the second dataset.
+    pub added: Vec<Item>,
+    /// Items only in the first dataset.
+    pub removed: Vec<Item>,
+    /// Items in both datasets whose fields differ.
+    pub changed: Vec<ChangedItem>,
+    /// Number of items that are the same in both datasets.
+    pub unchanged: usize,
+}
+
+impl DatasetDiff {
+    /**
+     * @brief Compare two datasets.
+     *
+     * Compares name, value, status, tags, category, supplier and tenant;
+     * timestamps and the source are ignored, as they change with every load
+     * or processing run. If an id occurs more than once in a dataset, its
+     * first occurrence is used.
+     *
+     * @param before The first dataset, e.g. the data file before a run.
+     * @param after The second dataset, e.g. the data file after the run.
+     * @return DatasetDiff The differences.
+     */
+    pub fn compare(before: &[Item], after: &[Item]) -> Self {
+        let before: Vec<ItemRef> = before.iter().map(ItemRef::from).collect();
+        let after: Vec<ItemRef> = after.iter().map(ItemRef::from).collect();
+        DatasetDiff::compare_refs(&before, &after)
+    }
+
+    /**
+     * @brief Compare two datasets of borrowed items, as compare does.
+     *
+     * @param before The first dataset.
+     * @param after The second dataset.
+     * @return DatasetDiff The differences.
+     */
+    pub fn compare_refs(before: &[ItemRef], after: &[ItemRef]) -> Self {
+        let before = index_by_id(before);
+        let mut after = index_by_id(after);
+
+        let mut diff = DatasetDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+            unchanged: 0,
+        };
+        for (item_id, old) in before {
+            match after.remove(item_id) {
+                None => diff.removed.push(old.to_owned_item()),
+                Some(new) => {
+                    let changes = field_changes(old, new);
+                    if changes.is_empty() {
+                        diff.unchanged += 1;
+                    } else {
+                        diff.changed.push(ChangedItem {
+                            item_id: item_id.clone(),
+                            changes,
+                        });
+                    }
+                }
+            }
+        }
+        diff.added = after.into_values().map(ItemRef::to_owned_item).collect();
+        diff
+    }
+
+    /**
+     * @brief Compare the items of two data files.
+     *
+     * JSON and NDJSON files in the current schema are parsed as borrowed
+     * items; other files are loaded as owned items.
+     *
+     * @param before Path of the first data file.
+     * @param after Path of the second data file.
+     * @param encryption Key to decrypt encrypted files with.
+     * @param checksums Verify the files against their checksum files, if they have them.
+     * @return Result<DatasetDiff, String> The differences, or an error message
+     *         if a file cannot be loaded.
+     */
+    pub fn compare_files(
+        before: &Path,
+        after: &Path,
+        encryption: Option<&EncryptionKey>,
+        checksums: bool,
+    ) -> Result<Self, String> {
+        let before_text = data_handler::read_text_contents(before, encryption, checksums)?;
+        let after_text = data_handler::read_text_contents(after, encryption, checksums)?;
+        let (before_items, after_items);
+        let before_refs = match borrowed_items(before_text.as_ref(), before) {
+            Some(items) => items,
+            None => {
+                before_items = data_handler::load_items_from_path(before, encryption, checksums)?;
+                before_items.iter().map(ItemRef::from).collect()
+            }
+        };
+        let after_refs = match borrowed_items(after_text.as_ref(), after) {
+            Some(items) => items,
+            None => {
+                after_items = data_handler::load_items_from_path(after, encryption, checksums)?;
+                after_items.iter().map(ItemRef::from).collect()
+            }
+        };
+        Ok(DatasetDiff::compare_refs(&before_refs, &after_refs))
+    }
+
+    /**
+     * @brief Check whether the datasets hold the same items.
+     *
+     * @return bool True if nothing was added, removed or changed.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /**
+     * @brief Render the differences as a table, one row per added or removed
+     *        item and per changed field, followed by a summary line.
+     *
+     * @return String The table, ending with a newline.
+     */
+    pub fn to_table(&self) -> String {
+        let mut rows: Vec<[String; 5]> = vec![[
+            String::from("CHANGE"),
+            String::from("ITEM ID"),
+            String::from("FIELD"),
+            String::from("BEFORE"),
+            String::from("AFTER"),
+        ]];
+        for item in &self.added {
+            rows.push([
+                String::from("added"),
+                item.item_id.to_string(),
+                String::new(),
+                String::new(),
+                format!("{} ({})", item.name, item.value),
+            ]);
+        }
+        for item in &self.removed {
+            rows.push([
+                String::from("removed"),
+                item.item_id.to_string(),
+                String::new(),
+                format!("{} ({})", item.name, item.value),
+                String::new(),
+            ]);
+        }
+        for item in &self.changed {
+            for change in &item.changes {
+                rows.push([
+                    String::from("changed"),
+                    item.item_id.to_string(),
+                    change.field.to_string(),
+                    display_value(&change.before),
+                    display_value(&change.after),
+                ]);
+            }
+        }
+
+        let mut table = format_table(&rows);
+        table.push_str(&format!(
+            "{} added, {} removed, {} changed, {} unchanged.\n",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            self.unchanged
+        ));
+        table
+    }
+}
+
+/**
+ * @brief Parse the contents of a data file as borrowed items.
+ *
+ * @return Option<Vec<ItemRef>> The items, or None if the file has to be
+ *         loaded as owned items (another format, an older schema, or a
+ *         malformed document, whose error the owned load reports).
+ */
+fn borrowed_items<'a>(contents: Option<&'a (data_handler::DataFormat, String)>, path: &Path) -> Option<Vec<ItemRef<'a>>> {
+    let (format, text) = contents?;
+    item_ref::parse_items(text, *format, &path.display().to_string())
+        .map_err(|e| log_debug!("diff:borrowed_items", "{}; loading it as owned items.", e))
+        .ok()
+}
+
+/**
+ * @brief Index items by id, keeping the first occurrence of each id.
+ */
+fn index_by_id<'a, 'b>(items: &'b [ItemRef<'a>]) -> BTreeMap<&'b ItemId, &'b ItemRef<'a>> {
+    let mut index = BTreeMap::new();
+    for item in items {
+        index.entry(&item.item_id).or_insert(item);
+    }
+    index
+}
+
+/**
+ * @brief List the compared fields that differ between two versions of an item.
+ */
+fn field_changes(old: &ItemRef, new: &ItemRef) -> Vec<FieldChange> {
+    let fields = [
+        ("name", json!(old.name), json!(new.name)),
+        ("value", json!(old.value), json!(new.value)),
+        ("status", json!(old.status), json!(new.status)),
+        ("tags", json!(old.tags), json!(new.tags)),
+        ("category", json!(old.category), json!(new.category)),
+        ("supplier", json!(old.supplier), json!(new.supplier)),
+        ("tenant", json!(old.tenant), json!(new.tenant)),
+    ];
+    fields
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(field, before, after)| FieldChange { field, before, after })
+        .collect()
+}
+
+/**
+ * @brief Lay out rows as a table with left-aligned columns.
+ *
+ * @param rows The rows, the header first.
+ * @return String The table, one line per row, each ending with a newline.
+ */
+pub fn format_table<const N: usize>(rows: &[[String; N]]) -> String {
+    let mut widths = [0; N];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut table = String::new();
+    for row in rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        table.push_str(line.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/**
+ * @brief Format a field value for a table: text without quotes, a missing
+ *        value as "-", anything else as JSON.
+ *
+ * @param value The value.
+ * @return String The value as shown in tables.
+ */
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::from("-"),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+// End of rust_sample_project/src/diff.rs
@@ -0,0 +1,277 @@
+// rust_sample_project/src/docs.rs
+
+// Reference documentation for the hidden `gen-docs <dir>` subcommand: a man
+// page (rust_sample_project.1) and a Markdown reference
+// (rust_sample_project.md). Both are generated from what the program itself
+// runs on, so regenerating them after a change keeps them in sync:
+//
+//     synopsis                the usage message of main.rs, checked against
+//                             the parser by its tests
+//     subcommands and flags   the CLI table of main.rs (see completions.rs)
+//     config keys             CONFIG_KEYS, Config::from_defaults and
+//                             config::describe (the doc comments of config.rs)
+//     exit codes              error.rs and shutdown.rs
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::completions::{Arg, Cli, Value};
+use crate::config::{self, Config, CONFIG_ENV_PREFIX, CONFIG_FILE_PATH, CONFIG_KEYS, PROFILE_ENV_VAR};
+use crate::error;
+use crate::shutdown;
+
+/// Version in the headers of the documents.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// One-line description of the program.
+const DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
+
+/// The exit codes and when the program ends with them.
+const EXIT_CODES: &[(i32, &str)] = &[
+    (0, "The run completed and every item was processed."),
+    (error::EXIT_FAILURE, "A failure that fits no more specific class."),
+    (error::EXIT_CONFIG, "Invalid configuration or command-line arguments."),
+    (error::EXIT_LOAD, "The items could not be loaded."),
+    (error::EXIT_SAVE, "The processed items could not be saved."),
+    (error::EXIT_PARTIAL, "The run completed but some items failed to process."),
+    (shutdown::EXIT_INTERRUPTED, "The run was stopped by SIGINT or SIGTERM."),
+];
+
+/**
+ * @struct ConfigKeyDoc
+ * @brief What the reference says about a config key.
+ */
+struct ConfigKeyDoc {
+    /// The key, see CONFIG_KEYS.
+    key: &'static str,
+    /// The default value as written in the config file.
+    default: String,
+    /// The SAMPLE_PROJECT_<KEY> variable overriding it.
+    env_var: String,
+    /// See config::describe.
+    description: &'static str,
+}
+
+/**
+ * @brief Collect the documentation of all config keys, in CONFIG_KEYS order.
+ */
+fn config_keys() -> Vec<ConfigKeyDoc> {
+    let defaults = Config::from_defaults();
+    CONFIG_KEYS
+        .iter()
+        .map(|key| ConfigKeyDoc {
+            key,
+            default: defaults.value_of(key).map(|value| value.to_string()).unwrap_or_default(),
+            env_var: format!("{}{}", CONFIG_ENV_PREFIX, key.to_ascii_uppercase()),
+            description: config::describe(key).unwrap_or(""),
+        })
+        .collect()
+}
+
+/**
+ * @brief Return the environment variables the program reads besides the
+ *        per-key ones, with what they do.
+ */
+fn environment() -> [(String, &'static str); 3] {
+    [
+        (
+            format!("{}<KEY>", CONFIG_ENV_PREFIX),
+            "Overrides the config key <key>, e.g. SAMPLE_PROJECT_THRESHOLD=150.",
+        ),
+        (PROFILE_ENV_VAR.to_string(), "Selects the config profile when --profile is not given."),
+        (
+            String::from("NO_COLOR"),
+            "Turns off colored log records when set to a non-empty value and log_color is auto.",
+        ),
+    ]
+}
+
+/**
+ * @brief Describe what follows a flag or subcommand, e.g. "<auto|always|never>".
+ */
+fn value_hint(value: Value) -> String {
+    match value {
+        Value::None => String::new(),
+        Value::Path => String::from("<path>"),
+        Value::Text => String::from("<value>"),
+        Value::Choices(choices) => format!("<{}>", choices.join("|")),
+    }
+}
+
+/**
+ * @brief Return a subcommand or flag with what follows it, e.g. "--color <auto|always|never>".
+ */
+fn usage(arg: &Arg) -> String {
+    match value_hint(arg.value) {
+        hint if hint.is_empty() => arg.name.to_string(),
+        hint => format!("{} {}", arg.name, hint),
+    }
+}
+
+/**
+ * @brief Return the forms of the command line, one per line of the usage
+ *        message without its "Usage:" prefix.
+ *
+ * @param cli The command line, see the CLI table of main.rs.
+ * @return Vec<String> The forms, e.g. "rust_sample_project config schema".
+ */
+pub fn synopsis(cli: &Cli) -> Vec<String> {
+    cli.usage
+        .lines()
+        .map(|line| line.trim_start_matches("Usage:").trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/**
+ * @brief Escape text for roff; lines must not start with a control character.
+ */
+fn roff(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+/**
+ * @brief Generate the man page (section 1).
+ *
+ * @param cli The command line, see the CLI table of main.rs.
+ * @return String The page in roff (man macros), e.g. for `man -l`.
+ */
+pub fn man_page(cli: &Cli) -> String {
+    let program = roff(cli.program);
+    let mut page = format!(
+        ".TH {} 1 \"\" \"{} {}\" \"User Commands\"\n",
+        cli.program.to_ascii_uppercase(),
+        program,
+        VERSION
+    );
+    let _ = write!(page, ".SH NAME\n{} \\- {}\n", program, roff(DESCRIPTION));
+    page.push_str(".SH SYNOPSIS\n");
+    for form in synopsis(cli) {
+        let _ = write!(page, "{}\n.br\n", roff(&form));
+    }
+    page.push_str(".SH DESCRIPTION\n");
+    let _ = writeln!(
+        page,
+        "{}",
+        roff(&format!(
+            "Without a subcommand the pipeline runs once. Every option can be given as --flag value or \
+             --flag=value. Configuration is read from {} (or --config), then overridden by the \
+             selected profile, the environment and the command line.",
+            CONFIG_FILE_PATH
+        ))
+    );
+    page.push_str(".SH COMMANDS\n");
+    for subcommand in cli.subcommands {
+        let _ = write!(page, ".TP\n.B {}\n{}\n", roff(&usage(subcommand)), roff(subcommand.help));
+    }
+    page.push_str(".SH OPTIONS\n");
+    for flag in cli.flags {
+        let _ = write!(page, ".TP\n.B {}\n{}\n", roff(&usage(flag)), roff(flag.help));
+    }
+    page.push_str(".SH CONFIGURATION\n");
+    for doc in config_keys() {
+        let _ = write!(
+            page,
+            ".TP\n.B {}\n{}\n.br\nDefault: {}; environment: {}.\n",
+            roff(doc.key),
+            roff(doc.description),
+            roff(&doc.default),
+            roff(&doc.env_var)
+        );
+    }
+    page.push_str(".SH ENVIRONMENT\n");
+    for (var, description) in environment() {
+        let _ = write!(page, ".TP\n.B {}\n{}\n", roff(&var), roff(description));
+    }
+    page.push_str(".SH EXIT STATUS\n");
+    for (code, description) in EXIT_CODES {
+        let _ = write!(page, ".TP\n.B {}\n{}\n", code, roff(description));
+    }
+    page
+}
+
+/**
+ * @brief Escape text for a Markdown table cell.
+ */
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+/**
+ * @brief Generate the Markdown reference.
+ *
+ * @param cli The command line, see the CLI table of main.rs.
+ * @return String The reference, e.g. for docs/.
+ */
+pub fn markdown(cli: &Cli) -> String {
+    let mut text = format!("# {} {}\n\n{}\n\n", cli.program, VERSION, DESCRIPTION);
+    let _ = write!(
+        text,
+        "Without a subcommand the pipeline runs once. Every option can be given as `--flag value` or \
+         `--flag=value`. Configuration is read from `{}` (or `--config`), then overridden by the \
+         selected profile, the environment and the command line.\n\n",
+        CONFIG_FILE_PATH
+    );
+    text.push_str("## Synopsis\n\n```text\n");
+    for form in synopsis(cli) {
+        let _ = writeln!(text, "{}", form);
+    }
+    text.push_str("```\n\n## Commands\n\n| Command | Description |\n| --- | --- |\n");
+    for subcommand in cli.subcommands {
+        let _ = writeln!(text, "| `{}` | {} |", cell(&usage(subcommand)), cell(subcommand.help));
+    }
+    text.push_str("\n## Options\n\n| Option | Description |\n| --- | --- |\n");
+    for flag in cli.flags {
+        let _ = writeln!(text, "| `{}` | {} |", cell(&usage(flag)), cell(flag.help));
+    }
+    text.push_str("\n## Configuration\n\n| Key | Default | Environment | Description |\n| --- | --- | --- | --- |\n");
+    for doc in config_keys() {
+        let _ = writeln!(
+            text,
+            "| `{}` | `{}` | `{}` | {} |",
+            doc.key,
+            cell(&doc.default),
+            doc.env_var,
+            cell(doc.description)
+        );
+    }
+    text.push_str("\n## Environment\n\n| Variable | Description |\n| --- | --- |\n");
+    for (var, description) in environment() {
+        let _ = writeln!(text, "| `{}` | {} |", var, cell(description));
+    }
+    text.push_str("\n## Exit status\n\n| Code | Meaning |\n| --- | --- |\n");
+    for (code, description) in EXIT_CODES {
+        let _ = writeln!(text, "| {} | {} |", code, description);
+    }
+    text
+}
+
+/**
+ * @brief Write the man page and the Markdown reference into a directory.
+ *
+ * @param cli The command line, see the CLI table of main.rs.
+ * @param dir The directory, created if missing.
+ * @return Result<Vec<String>, String> The paths written, or an error message.
+ */
+pub fn write(cli: &Cli, dir: &Path) -> Result<Vec<String>, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    let documents = [
+        (format!("{}.1", cli.program), man_page(cli)),
+        (format!("{}.md", cli.program), markdown(cli)),
+    ];
+    let mut written = Vec::new();
+    for (name, content) in documents {
+        let path = dir.join(name);
+        fs::write(&path, content).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+        written.push(path.display().to_string());
+    }
+    Ok(written)
+}
+
+// End of rust_sample_project/src/docs.rs
@@ -0,0 +1,327 @@
+// rust_sample_project/src/encryption.rs
+
+// Encryption at rest of saved data files, as item data can contain sensitive
+// pricing. When a key is configured (in an environment variable or a key
+// file, as 64 hex digits for AES-256), every saved data file is encrypted
+// with AES-256-GCM after compression:
+//
+//     "SPENC\0\0\x01" (8 bytes) | nonce (12 bytes) | ciphertext and tag
+//
+// A fresh random nonce is used for every file. Encrypted files are
+// recognized by the magic header and decrypted transparently on load; GCM's
+// authentication tag makes a wrong key or a modified file fail to decrypt
+// rather than load garbage. A plain file is refused when a key is configured,
+// as it could have been put in place of an encrypted one; with
+// encryption_allow_plaintext it loads instead, so existing data files are
+// encrypted by the next save.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+/// Header identifying an encrypted data file, including the format version.
+const MAGIC: &[u8; 8] = b"SPENC\0\0\x01";
+/// Length in bytes of the AES-GCM nonce stored after the header.
+const NONCE_LEN: usize = 12;
+/// Length in bytes of an AES-256 key.
+const KEY_LEN: usize = 32;
+
+/**
+ * @struct EncryptionKey
+ * @brief An AES-256 key for data files.
+ *
+ * Debug output never shows the key material.
+ */
+#[derive(Clone)]
+pub struct EncryptionKey {
+    cipher: Aes256Gcm,
+    /// Where the key came from, for messages (e.g. "env DATA_KEY").
+    origin: String,
+    /// Whether files that are not encrypted still load.
+    allow_plaintext: bool,
+}
+
+impl EncryptionKey {
+    /**
+     * @brief Read the key from an environment variable.
+     *
+     * @param var Name of the variable holding the key as 64 hex digits.
+     * @return Result<EncryptionKey, String> The key, or an error message if
+     *         the variable is unset or does not hold a valid key.
+     */
+    pub fn from_env(var: &str) -> Result<Self, String> {
+        let text = env::var(var).map_err(|e| format!("Failed to read encryption key from env {}: {}", var, e))?;
+        Self::from_hex(&text, format!("env {}", var))
+    }
+
+    /**
+     * @brief Read the key from a key file.
+     *
+     * @param path File holding the key as 64 hex digits (surrounding
+     *        whitespace is ignored) or as 32 raw bytes.
+     * @return Result<EncryptionKey, String> The key, or an error message.
+     */
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = fs::read(path)
+            .map_err(|e| format!("Failed to read encryption key file '{}': {}", path.display(), e))?;
+        let origin = format!("key file {}", path.display());
+        match std::str::from_utf8(&contents) {
+            Ok(text) if text.trim().len() == KEY_LEN * 2 => Self::from_hex(text, origin),
+            _ if contents.len() == KEY_LEN => Ok(Self::from_bytes(&contents, origin)),
+            _ => Err(format!(
+                "Encryption key file '{}' must hold {} hex digits or {} raw bytes",
+                path.display(),
+                KEY_LEN * 2,
+                KEY_LEN
+            )),
+        }
+    }
+
+    /**
+     * @brief Parse a key given as hex digits.
+     */
+    fn from_hex(text: &str, origin: String) -> Result<Self, String> {
+        let text = text.trim();
+        let invalid = || format!("Encryption key from {} must be {} hex digits", origin, KEY_LEN * 2);
+        if text.len() != KEY_LEN * 2 || !text.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..KEY_LEN)
+            .map(|index| u8::from_str_radix(&text[index * 2..index * 2 + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        Ok(Self::from_bytes(&bytes, origin))
+    }
+
+    /**
+     * @brief Constructs a key from its 32 bytes.
+     */
+    fn from_bytes(bytes: &[u8], origin: String) -> Self {
+        EncryptionKey {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(bytes)),
+            origin,
+            allow_plaintext: false,
+        }
+    }
+
+    /**
+     * @brief Set whether data files that are not encrypted still load.
+     *
+     * @param allow_plaintext True to accept plain files (see
+     *        ENCRYPTION_ALLOW_PLAINTEXT).
+     * @return EncryptionKey The key with the setting applied.
+     */
+    pub fn with_allow_plaintext(mut self, allow_plaintext: bool) -> Self {
+        self.allow_plaintext = allow_plaintext;
+        self
+    }
+
+    /**
+     * @brief Encrypt the contents of a data file.
+     *
+     * @param plaintext The (possibly compressed) file contents.
+     * @return Result<Vec<u8>, String> The encrypted file, or an error message.
+     */
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| String::from("Failed to encrypt data"))?;
+        let mut encrypted = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        encrypted.extend_from_slice(MAGIC);
+        encrypted.extend_from_slice(&nonce);
+        encrypted.extend_from_slice(&ciphertext);
+        Ok(encrypted)
+    }
+
+    /**
+     * @brief Decrypt an encrypted data file.
+     *
+     * @param raw The file contents, starting with the magic header.
+     * @param location Path or URL of the file, used in messages.
+     * @return Result<Vec<u8>, String> The decrypted contents, or an error
+     *         message if the key is wrong or the file was modified.
+     */
+    fn decrypt(&self, raw: &[u8], location: &str) -> Result<Vec<u8>, String> {
+        let body = &raw[MAGIC.len()..];
+        if body.len() < NONCE_LEN {
+            return Err(format!("Encrypted data file '{}' is truncated", location));
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        self.cipher.decrypt(Nonce::from_slice(nonce), ciphertext).map_err(|_| {
+            format!(
+                "Failed to decrypt data file '{}' with the key from {}: wrong key, or the file was modified",
+                location, self.origin
+            )
+        })
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptionKey")
+            .field("origin", &self.origin)
+            .field("allow_plaintext", &self.allow_plaintext)
+            .finish_non_exhaustive()
+    }
+}
+
+/**
+ * @brief Load the configured key, if any.
+ *
+ * @param key_env Name of the environment variable holding the key, or "".
+ * @param key_file Path of the key file, or an empty path.
+ * @param allow_plaintext Whether plain data files still load with the key.
+ * @return Result<Option<EncryptionKey>, String> The key, None when neither is
+ *         configured, or an error message.
+ */
+pub fn load_key(key_env: &str, key_file: &Path, allow_plaintext: bool) -> Result<Option<EncryptionKey>, String> {
+    let key = match (key_env.is_empty(), key_file.as_os_str().is_empty()) {
+        (true, true) => return Ok(None),
+        (false, true) => EncryptionKey::from_env(key_env)?,
+        (true, false) => EncryptionKey::from_file(key_file)?,
+        (false, false) => {
+            return Err(String::from(
+                "Only one of encryption_key_env and encryption_key_file can be set",
+            ))
+        }
+    };
+    Ok(Some(key.with_allow_plaintext(allow_plaintext)))
+}
+
+/**
+ * @brief Check whether file contents are an encrypted data file.
+ *
+ * @param raw The raw file contents.
+ * @return bool True if the contents start with the encryption header.
+ */
+pub fn is_encrypted(raw: &[u8]) -> bool {
+    raw.starts_with(MAGIC)
+}
+
+/**
+ * @brief Decrypt the raw contents of a data file if they are encrypted.
+ *
+ * @param raw The raw file contents.
+ * @param key The configured key, if any.
+ * @param location Path or URL of the file, used in messages.
+ * @return Result<Vec<u8>, String> The plain contents (unchanged if the file
+ *         is not encrypted), or an error message, also for a plain file when
+ *         the key does not allow plain files.
+ */
+pub fn decrypt_if_encrypted(raw: Vec<u8>, key: Option<&EncryptionKey>, location: &str) -> Result<Vec<u8>, String> {
+    if !is_encrypted(&raw) {
+        return match key {
+            Some(key) if !key.allow_plaintext => Err(format!(
+                "Data file '{}' is not encrypted, but a key is configured (from {}); \
+                 set encryption_allow_plaintext = true to load it and encrypt it on the next save",
+                location, key.origin
+            )),
+            _ => Ok(raw),
+        };
+    }
+    match key {
+        Some(key) => {
+            log_debug!("encryption:decrypt_if_encrypted", "Decrypting {} with the key from {}.", location, key.origin);
+            key.decrypt(&raw, location)
+        }
+        None => Err(format!(
+            "Data file '{}' is encrypted, but no encryption key is configured (set encryption_key_env or encryption_key_file)",
+            location
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    const PLAINTEXT: &[u8] = br#"{"schema_version":3,"items":[]}"#;
+
+    fn key(digit: char) -> EncryptionKey {
+        EncryptionKey::from_hex(&digit.to_string().repeat(KEY_LEN * 2), String::from("test")).unwrap()
+    }
+
+    #[test]
+    fn encrypted_files_decrypt_to_their_contents() {
+        let encrypted = key('a').encrypt(PLAINTEXT).unwrap();
+
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(encrypted.len(), MAGIC.len() + NONCE_LEN + PLAINTEXT.len() + 16);
+        assert_ne!(key('a').encrypt(PLAINTEXT).unwrap(), encrypted, "every file gets a fresh nonce");
+        assert_eq!(decrypt_if_encrypted(encrypted, Some(&key('a')), "data.json").unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn a_wrong_key_fails_to_decrypt() {
+        let encrypted = key('a').encrypt(PLAINTEXT).unwrap();
+
+        let result = decrypt_if_encrypted(encrypted, Some(&key('b')), "data.json");
+
+        assert!(matches!(&result, Err(message) if message.contains("wrong key")), "{:?}", result);
+    }
+
+    #[test]
+    fn truncated_and_tampered_files_fail_to_decrypt() {
+        let encrypted = key('a').encrypt(PLAINTEXT).unwrap();
+        let truncated = encrypted[..MAGIC.len() + NONCE_LEN / 2].to_vec();
+        let cut_short = encrypted[..encrypted.len() - 1].to_vec();
+        let mut tampered = encrypted;
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+
+        let result = decrypt_if_encrypted(truncated, Some(&key('a')), "data.json");
+        assert!(matches!(&result, Err(message) if message.contains("truncated")), "{:?}", result);
+        assert!(decrypt_if_encrypted(cut_short, Some(&key('a')), "data.json").is_err());
+        assert!(decrypt_if_encrypted(tampered, Some(&key('a')), "data.json").is_err());
+    }
+
+    #[test]
+    fn encrypted_files_need_a_key() {
+        let encrypted = key('a').encrypt(PLAINTEXT).unwrap();
+
+        let result = decrypt_if_encrypted(encrypted, None, "data.json");
+
+        assert!(matches!(&result, Err(message) if message.contains("no encryption key")), "{:?}", result);
+    }
+
+    #[test]
+    fn plain_files_are_refused_with_a_key_unless_allowed() {
+        let refused = decrypt_if_encrypted(PLAINTEXT.to_vec(), Some(&key('a')), "data.json");
+        let allowed = decrypt_if_encrypted(PLAINTEXT.to_vec(), Some(&key('a').with_allow_plaintext(true)), "data.json");
+
+        assert!(
+            matches!(&refused, Err(message) if message.contains("encryption_allow_plaintext")),
+            "{:?}",
+            refused
+        );
+        assert_eq!(allowed.unwrap(), PLAINTEXT);
+        assert_eq!(decrypt_if_encrypted(PLAINTEXT.to_vec(), None, "data.json").unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn keys_load_from_hex_or_raw_key_files() {
+        let dir = TestDir::new("encryption");
+        let hex_file = dir.join("hex.key");
+        let raw_file = dir.join("raw.key");
+        fs::write(&hex_file, format!("{}\n", "ab".repeat(KEY_LEN))).unwrap();
+        fs::write(&raw_file, [0xab; KEY_LEN]).unwrap();
+
+        let encrypted = load_key("", &hex_file, false).unwrap().unwrap().encrypt(PLAINTEXT).unwrap();
+        let raw_key = load_key("", &raw_file, false).unwrap().unwrap();
+
+        assert_eq!(decrypt_if_encrypted(encrypted, Some(&raw_key), "data.json").unwrap(), PLAINTEXT);
+        assert!(load_key("", Path::new(""), false).unwrap().is_none());
+        assert!(load_key("DATA_KEY", &hex_file, false).is_err());
+        fs::write(&hex_file, "abc").unwrap();
+        assert!(load_key("", &hex_file, false).is_err());
+    }
+}
+
+// End of rust_sample_project/src/encryption.rs
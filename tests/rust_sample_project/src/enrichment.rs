@@ -0,0 +1,231 @@
+// rust_sample_project/src/enrichment.rs
+
+// Joins items against a lookup table (item_id -> category, supplier) before
+// they are processed. The lookup file is CSV with a header row
+// (item_id,category,supplier) or a JSON array of objects with the same
+// fields; like data files it may be compressed and live in any ItemStore.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::audit::{AuditChange, AuditEvent, AuditLog};
+use crate::compression;
+use crate::data_handler::DataFormat;
+use crate::item::Item;
+use crate::item_id::ItemId;
+use crate::storage;
+
+/**
+ * @enum MissingLookupPolicy
+ * @brief What happens to an item whose id is not in the lookup table.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingLookupPolicy {
+    /// Log a warning and process the item without enrichment ("warn").
+    Warn,
+    /// Leave the item unprocessed ("skip").
+    Skip,
+    /// Record the item as failed ("fail").
+    Fail,
+}
+
+impl MissingLookupPolicy {
+    /**
+     * @brief Parse a policy name as used in config.
+     *
+     * @param name One of "warn", "skip" or "fail".
+     * @return Result<MissingLookupPolicy, String> The policy, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<MissingLookupPolicy, String> {
+        match name {
+            "warn" => Ok(MissingLookupPolicy::Warn),
+            "skip" => Ok(MissingLookupPolicy::Skip),
+            "fail" => Ok(MissingLookupPolicy::Fail),
+            other => Err(format!(
+                "Unknown missing lookup policy '{}' (expected 'warn', 'skip' or 'fail')",
+                other
+            )),
+        }
+    }
+}
+
+/**
+ * @struct LookupEntry
+ * @brief Supplemental data for one item id.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LookupEntry {
+    /// The item id the entry belongs to.
+    pub item_id: ItemId,
+    /// The item's category, if known.
+    #[serde(default)]
+    pub category: Option<String>,
+    /// The item's supplier, if known.
+    #[serde(default)]
+    pub supplier: Option<String>,
+}
+
+/**
+ * @enum Enrichment
+ * @brief Result of enriching a single item.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Enrichment {
+    /// The item was found and its fields were filled in.
+    Enriched,
+    /// The item is not in the table, but may be processed as it is.
+    Missing,
+    /// The item is not in the table and must not be processed.
+    Skip,
+    /// The item is not in the table, or its changes could not be recorded
+    /// in the audit log, and it counts as failed.
+    Fail,
+}
+
+/**
+ * @struct Enricher
+ * @brief A loaded lookup table and the policy for items missing from it.
+ */
+#[derive(Debug, Clone)]
+pub struct Enricher {
+    entries: HashMap<ItemId, LookupEntry>,
+    policy: MissingLookupPolicy,
+    /// Describes the lookup table in audit events.
+    rule: String,
+    audit_log: Option<Arc<AuditLog>>,
+}
+
+impl Enricher {
+    /**
+     * @brief Constructs an enricher from lookup entries.
+     *
+     * Later entries for the same id replace earlier ones.
+     *
+     * @param entries The lookup table rows.
+     * @param policy What to do with items missing from the table.
+     * @return Enricher The enricher.
+     */
+    pub fn new(entries: Vec<LookupEntry>, policy: MissingLookupPolicy) -> Self {
+        Enricher {
+            entries: entries.into_iter().map(|entry| (entry.item_id.clone(), entry)).collect(),
+            policy,
+            rule: "enrichment".to_string(),
+            audit_log: None,
+        }
+    }
+
+    /**
+     * @brief Record the fields set on items in an audit log.
+     *
+     * @param audit_log The log, or None to not record changes.
+     * @return Enricher The enricher with the audit log applied.
+     */
+    pub fn with_audit_log(mut self, audit_log: Option<Arc<AuditLog>>) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
+    /**
+     * @brief Load the lookup table from a CSV or JSON file.
+     *
+     * @param path Path or URL of the lookup file.
+     * @param policy What to do with items missing from the table.
+     * @return Result<Enricher, String> The enricher, or an error message if
+     *         the file cannot be read or parsed.
+     */
+    pub fn load(path: &Path, policy: MissingLookupPolicy) -> Result<Self, String> {
+        let store = storage::open(path)?;
+        let path = store.location();
+        let entries: Vec<LookupEntry> = match DataFormat::from_path(path)? {
+            DataFormat::Json => serde_json::from_str(&compression::decode_text(&store.read()?, path)?)
+                .map_err(|e| format!("Failed to parse lookup file '{}': {}", path, e))?,
+            DataFormat::Csv => csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(compression::decode_text(&store.read()?, path)?.as_bytes())
+                .deserialize()
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to parse lookup file '{}': {}", path, e))?,
+            DataFormat::Ndjson => serde_json::Deserializer::from_str(&compression::decode_text(&store.read()?, path)?)
+                .into_iter()
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to parse lookup file '{}': {}", path, e))?,
+            DataFormat::Parquet | DataFormat::Xml | DataFormat::Xlsx => {
+                return Err(format!("Unsupported lookup file '{}' (lookup files must be CSV, JSON or NDJSON)", path));
+            }
+        };
+        log_info!(
+            "enrichment:load",
+            "Loaded {} lookup entries from {} (missing lookups: {:?}).",
+            entries.len(),
+            path,
+            policy
+        );
+        let mut enricher = Enricher::new(entries, policy);
+        enricher.rule = format!("enrichment:{}", path);
+        Ok(enricher)
+    }
+
+    /**
+     * @brief Fill in the category and supplier of an item from the table.
+     *
+     * Values the table does not provide are left as they are.
+     *
+     * @param item The item to enrich.
+     * @return Enrichment Whether the item was found, and if not, what the
+     *         policy says should happen to it.
+     */
+    pub fn enrich(&self, item: &mut Item) -> Enrichment {
+        let Some(entry) = self.entries.get(&item.item_id) else {
+            return match self.policy {
+                MissingLookupPolicy::Warn => {
+                    log_warning!("enrichment:enrich", "No lookup entry for item ID {}.", item.item_id);
+                    Enrichment::Missing
+                }
+                MissingLookupPolicy::Skip => Enrichment::Skip,
+                MissingLookupPolicy::Fail => Enrichment::Fail,
+            };
+        };
+        let mut changes = Vec::new();
+        if entry.category.is_some() && entry.category != item.category {
+            changes.push(AuditEvent::new(
+                &item.item_id,
+                AuditChange::CategorySet,
+                json!(item.category),
+                json!(entry.category),
+                &self.rule,
+            ));
+        }
+        if entry.supplier.is_some() && entry.supplier != item.supplier {
+            changes.push(AuditEvent::new(
+                &item.item_id,
+                AuditChange::SupplierSet,
+                json!(item.supplier),
+                json!(entry.supplier),
+                &self.rule,
+            ));
+        }
+        if let Some(audit_log) = &self.audit_log {
+            if let Err(e) = audit_log.record(&changes) {
+                log_error!("enrichment:enrich", "{}", e);
+                return Enrichment::Fail;
+            }
+        }
+
+        for change in &changes {
+            item.record_rule(&change.rule);
+        }
+        if entry.category.is_some() {
+            item.category = entry.category.clone();
+        }
+        if entry.supplier.is_some() {
+            item.supplier = entry.supplier.clone();
+        }
+        Enrichment::Enriched
+    }
+}
+
+// End of rust_sample_project/src/enrichment.rs
@@ -0,0 +1,98 @@
+// rust_sample_project/src/error.rs
+
+// Process exit codes, so shell scripts can branch on the cause of a failure:
+//
+//     0    success
+//     1    other failure (e.g. a run vetoed by a hook, or an API or stream mode
+//          that stopped on an error)
+//     2    configuration or command-line error
+//     3    items could not be loaded
+//     4    processed items could not be saved
+//     5    the run completed, but some items failed to process
+//     130  stopped by SIGINT/SIGTERM (see shutdown::EXIT_INTERRUPTED)
+
+use std::fmt;
+
+/// Exit code for failures that fit no more specific class.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code for invalid configuration or command-line arguments.
+pub const EXIT_CONFIG: i32 = 2;
+/// Exit code when the items cannot be loaded.
+pub const EXIT_LOAD: i32 = 3;
+/// Exit code when the processed items cannot be saved.
+pub const EXIT_SAVE: i32 = 4;
+/// Exit code when the run completed but some items failed to process.
+pub const EXIT_PARTIAL: i32 = 5;
+
+/**
+ * @enum PipelineError
+ * @brief Why a pipeline run failed, classified by the stage that failed.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum PipelineError {
+    /// Invalid configuration or command-line arguments (filters, rules, strategies, ...).
+    Config(String),
+    /// The items (or the checkpoint to resume from) could not be loaded.
+    Load(String),
+    /// The processed items could not be saved.
+    Save(String),
+    /// A pipeline hook vetoed the run (see hooks::PipelineObserver).
+    Aborted(String),
+    /// The run completed and was saved, but some items failed to process.
+    PartialProcessing {
+        /// Number of items whose processing failed.
+        failed: usize,
+        /// Number of items the processor was run on.
+        attempted: usize,
+    },
+}
+
+impl PipelineError {
+    /**
+     * @brief Return the process exit code for this class of failure.
+     *
+     * @return i32 One of EXIT_FAILURE, EXIT_CONFIG, EXIT_LOAD, EXIT_SAVE or EXIT_PARTIAL.
+     */
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PipelineError::Config(_) => EXIT_CONFIG,
+            PipelineError::Load(_) => EXIT_LOAD,
+            PipelineError::Save(_) => EXIT_SAVE,
+            PipelineError::Aborted(_) => EXIT_FAILURE,
+            PipelineError::PartialProcessing { .. } => EXIT_PARTIAL,
+        }
+    }
+
+    /**
+     * @brief Return a stable name for this class of failure, for machine-readable output.
+     *
+     * @return &'static str One of "config", "load", "save", "aborted" or "partial_processing".
+     */
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PipelineError::Config(_) => "config",
+            PipelineError::Load(_) => "load",
+            PipelineError::Save(_) => "save",
+            PipelineError::Aborted(_) => "aborted",
+            PipelineError::PartialProcessing { .. } => "partial_processing",
+        }
+    }
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::Config(message) => write!(f, "{}", tr!(ErrorConfig, message)),
+            PipelineError::Load(message) => write!(f, "{}", tr!(ErrorLoad, message)),
+            PipelineError::Save(message) => write!(f, "{}", tr!(ErrorSave, message)),
+            PipelineError::Aborted(reason) => write!(f, "{}", tr!(ErrorAborted, reason)),
+            PipelineError::PartialProcessing { failed, attempted } => {
+                write!(f, "{}", tr!(ErrorPartial, failed, attempted))
+            }
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+// End of rust_sample_project/src/error.rs
@@ -0,0 +1,365 @@
+// rust_sample_project/src/ffi.rs
+
+// C API (see the `ffi` feature), so services written in C or C++ can run the
+// pipeline in-process. The declarations are in include/rust_sample_project.h,
+// generated from this file by build.rs; link against the cdylib:
+//
+//     SpPipeline *pipeline = sp_pipeline_new(NULL, NULL);
+//     if (sp_pipeline_configure(pipeline, "{\"data_path\": \"items.json\", \"threshold\": 150}") != SP_STATUS_OK) {
+//         fprintf(stderr, "%s\n", sp_pipeline_last_error(pipeline));
+//     }
+//     char *result = NULL;
+//     SpStatus status = sp_pipeline_run(pipeline, &result);
+//     ...                                  /* result is the run result as JSON */
+//     sp_string_free(result);
+//     sp_pipeline_free(pipeline);
+//
+// A handle holds a configuration, built like the binary's (config file,
+// profile, environment) with the values of sp_pipeline_configure on top. The
+// result of a run is the document of the binary's --output json, and the
+// status codes are the binary's exit codes, plus a few for misuse of the API.
+// Handles are not thread-safe: use one handle from one thread at a time, or
+// one handle per thread. Process-wide settings (locale, item id kind, money
+// decimals) follow the configuration of the last run. Panics are caught at
+// the boundary and reported as SP_STATUS_PANIC; they never unwind into C.
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+
+use crate::config::{Config, ConfigErrors, ConfigProblem, ConfigSource};
+use crate::error::PipelineError;
+use crate::hooks::PipelineHooks;
+use crate::metrics::Metrics;
+use crate::pipeline;
+use crate::report::{ItemResult, RunResult};
+
+/**
+ * @enum SpStatus
+ * @brief Result codes of the C API; 0 to 5 and 130 are the binary's exit codes.
+ */
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpStatus {
+    /// Success.
+    Ok = 0,
+    /// A pipeline hook vetoed the run.
+    Aborted = 1,
+    /// Invalid configuration.
+    ConfigError = 2,
+    /// The items could not be loaded.
+    LoadError = 3,
+    /// The processed items could not be saved.
+    SaveError = 4,
+    /// The run completed, but some items failed to process.
+    PartialProcessing = 5,
+    /// A NULL handle or pointer, invalid UTF-8 or malformed JSON was passed in.
+    InvalidArgument = 64,
+    /// The call panicked; the handle may be left in any state.
+    Panic = 70,
+    /// The run was stopped by SIGINT/SIGTERM; what was done is saved.
+    Interrupted = 130,
+}
+
+impl SpStatus {
+    /**
+     * @brief Return the status for an exit code of a run.
+     */
+    fn from_exit_code(code: i32) -> Self {
+        match code {
+            0 => SpStatus::Ok,
+            1 => SpStatus::Aborted,
+            2 => SpStatus::ConfigError,
+            3 => SpStatus::LoadError,
+            4 => SpStatus::SaveError,
+            5 => SpStatus::PartialProcessing,
+            130 => SpStatus::Interrupted,
+            _ => SpStatus::Aborted,
+        }
+    }
+}
+
+/**
+ * @struct SpPipeline
+ * @brief A pipeline handle, opaque to C.
+ */
+pub struct SpPipeline {
+    /// The configuration runs use.
+    config: Config,
+    /// Problems found while loading the config file, reported by sp_pipeline_run.
+    load_problems: Vec<ConfigProblem>,
+    /// Message of the last failed call, returned by sp_pipeline_last_error.
+    last_error: Option<CString>,
+}
+
+impl SpPipeline {
+    /**
+     * @brief Remember the message of a failed call and return its status.
+     */
+    fn fail(&mut self, status: SpStatus, message: &str) -> SpStatus {
+        self.last_error = Some(to_c_string(message));
+        status
+    }
+}
+
+/**
+ * @brief Create a pipeline handle.
+ *
+ * # Safety
+ *
+ * config_file and profile must each be NULL or a NUL-terminated string.
+ *
+ * @param config_file Config file to read, or NULL for the default lookup
+ *        (SAMPLE_PROJECT_CONFIG, then the default path).
+ * @param profile Config file profile to apply, or NULL for none (or
+ *        SAMPLE_PROJECT_PROFILE).
+ * @return SpPipeline* The handle, to be released with sp_pipeline_free, or
+ *         NULL if an argument is not valid UTF-8. Config file problems are
+ *         reported by sp_pipeline_run.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn sp_pipeline_new(config_file: *const c_char, profile: *const c_char) -> *mut SpPipeline {
+    panic::catch_unwind(|| {
+        let (Ok(config_file), Ok(profile)) = (unsafe { optional_str(config_file) }, unsafe { optional_str(profile) })
+        else {
+            log_error!("ffi:sp_pipeline_new", "Config file and profile must be valid UTF-8.");
+            return ptr::null_mut();
+        };
+        let (config, load_problems) = Config::load(config_file.map(Path::new), profile);
+        Box::into_raw(Box::new(SpPipeline { config, load_problems, last_error: None }))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/**
+ * @brief Set configuration values of a handle.
+ *
+ * Takes a JSON object of config keys to values, e.g.
+ * {"threshold": 150, "custom_stages": ["normalize"]}: strings, numbers and
+ * booleans for single values, arrays for list keys. Either all values are
+ * set or, if any is invalid, none is.
+ *
+ * # Safety
+ *
+ * pipeline must be a handle from sp_pipeline_new or NULL, and json a
+ * NUL-terminated string or NULL.
+ *
+ * @param pipeline The handle.
+ * @param json The values.
+ * @return SpStatus SP_STATUS_OK, SP_STATUS_CONFIG_ERROR for unknown keys or
+ *         invalid values, or SP_STATUS_INVALID_ARGUMENT.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn sp_pipeline_configure(pipeline: *mut SpPipeline, json: *const c_char) -> SpStatus {
+    let Some(pipeline) = (unsafe { pipeline.as_mut() }) else {
+        return SpStatus::InvalidArgument;
+    };
+    guarded(pipeline, |pipeline| {
+        let values = match unsafe { optional_str(json) } {
+            Ok(Some(json)) => serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(json),
+            _ => return pipeline.fail(SpStatus::InvalidArgument, "The values must be a UTF-8 JSON string"),
+        };
+        let values = match values {
+            Ok(values) => values,
+            Err(e) => {
+                let message = format!("The values must be a JSON object: {}", e);
+                return pipeline.fail(SpStatus::InvalidArgument, &message);
+            }
+        };
+
+        let mut config = pipeline.config.clone();
+        let mut problems = Vec::new();
+        for (key, value) in values {
+            let result = match value {
+                serde_json::Value::Array(entries) => {
+                    config.set_list(&key, entries.iter().map(json_text).collect(), ConfigSource::Api)
+                }
+                value => config.set(&key, &json_text(&value), ConfigSource::Api),
+            };
+            if let Err(message) = result {
+                problems.push(ConfigProblem { key, source: ConfigSource::Api, message });
+            }
+        }
+        if !problems.is_empty() {
+            return pipeline.fail(SpStatus::ConfigError, &ConfigErrors { problems }.to_string());
+        }
+        pipeline.config = config;
+        SpStatus::Ok
+    })
+}
+
+/**
+ * @brief Run the pipeline once with the configuration of a handle.
+ *
+ * Blocks until the run is over. The configuration is validated first; an
+ * invalid one fails the run with SP_STATUS_CONFIG_ERROR.
+ *
+ * # Safety
+ *
+ * pipeline must be a handle from sp_pipeline_new or NULL, and result_json a
+ * valid pointer or NULL.
+ *
+ * @param pipeline The handle.
+ * @param result_json If not NULL, receives the run result as JSON (the
+ *        binary's --output json document), to be released with
+ *        sp_string_free; also on failure, except for SP_STATUS_INVALID_ARGUMENT
+ *        and SP_STATUS_PANIC, where it receives NULL.
+ * @return SpStatus How the run ended.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn sp_pipeline_run(pipeline: *mut SpPipeline, result_json: *mut *mut c_char) -> SpStatus {
+    if let Some(result_json) = unsafe { result_json.as_mut() } {
+        *result_json = ptr::null_mut();
+    }
+    let Some(pipeline) = (unsafe { pipeline.as_mut() }) else {
+        return SpStatus::InvalidArgument;
+    };
+    guarded(pipeline, |pipeline| {
+        let run_result = run(pipeline);
+        let status = SpStatus::from_exit_code(run_result.exit_code);
+        match run_result.errors.first() {
+            Some(error) => pipeline.last_error = Some(to_c_string(&error.message)),
+            None => pipeline.last_error = None,
+        }
+        if let Some(result_json) = unsafe { result_json.as_mut() } {
+            match serde_json::to_string(&run_result) {
+                Ok(json) => *result_json = to_c_string(&json).into_raw(),
+                Err(e) => log_error!("ffi:sp_pipeline_run", "Failed to serialize the run result: {}", e),
+            }
+        }
+        status
+    })
+}
+
+/**
+ * @brief Return the message of the last failed call on a handle.
+ *
+ * # Safety
+ *
+ * pipeline must be a handle from sp_pipeline_new or NULL.
+ *
+ * @param pipeline The handle.
+ * @return const char* The message, owned by the handle and valid until the
+ *         next call on it, or NULL if the last call succeeded.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn sp_pipeline_last_error(pipeline: *const SpPipeline) -> *const c_char {
+    match unsafe { pipeline.as_ref() } {
+        Some(pipeline) => pipeline.last_error.as_ref().map_or(ptr::null(), |message| message.as_ptr()),
+        None => ptr::null(),
+    }
+}
+
+/**
+ * @brief Release a pipeline handle.
+ *
+ * # Safety
+ *
+ * pipeline must be a handle from sp_pipeline_new or NULL, and not be used
+ * afterwards.
+ *
+ * @param pipeline The handle; NULL is ignored.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn sp_pipeline_free(pipeline: *mut SpPipeline) {
+    if !pipeline.is_null() {
+        drop(unsafe { Box::from_raw(pipeline) });
+    }
+}
+
+/**
+ * @brief Release a string returned by the API.
+ *
+ * # Safety
+ *
+ * string must be a string from sp_pipeline_run or NULL, and not be used
+ * afterwards.
+ *
+ * @param string The string; NULL is ignored.
+ */
+#[no_mangle]
+pub unsafe extern "C" fn sp_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+/**
+ * @brief Validate the configuration of a handle and run the pipeline with it.
+ */
+fn run(pipeline: &SpPipeline) -> RunResult {
+    let mut problems = pipeline.load_problems.clone();
+    if let Err(errors) = pipeline.config.validate() {
+        problems.extend(errors.problems);
+    }
+    if !problems.is_empty() {
+        let error = PipelineError::Config(ConfigErrors { problems }.to_string());
+        return RunResult::new(Err(error), Vec::new());
+    }
+    pipeline.config.apply_process_settings();
+
+    let outcomes = Arc::new(Mutex::new(Vec::new()));
+    let collected = Arc::clone(&outcomes);
+    let hooks = PipelineHooks::new().after_item(move |item, outcome| {
+        collected.lock().unwrap_or_else(|e| e.into_inner()).push(ItemResult {
+            item_id: item.item_id.clone(),
+            outcome,
+            status: item.status,
+        });
+    });
+    let result = pipeline::run_processing_pipeline(&pipeline.config, false, &Metrics::new(), &hooks);
+    let items = std::mem::take(&mut *outcomes.lock().unwrap_or_else(|e| e.into_inner()));
+    RunResult::new(result, items)
+}
+
+/**
+ * @brief Call an API function body, turning a panic into SP_STATUS_PANIC.
+ */
+fn guarded(pipeline: &mut SpPipeline, body: impl FnOnce(&mut SpPipeline) -> SpStatus) -> SpStatus {
+    pipeline.last_error = None;
+    match panic::catch_unwind(AssertUnwindSafe(|| body(&mut *pipeline))) {
+        Ok(status) => status,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| String::from("unknown panic"));
+            log_critical!("ffi:guarded", "Panic in the C API: {}", message);
+            pipeline.fail(SpStatus::Panic, &format!("Panic: {}", message))
+        }
+    }
+}
+
+/**
+ * @brief Borrow a C string argument.
+ *
+ * @return Result<Option<&str>, ()> None for NULL, or Err for invalid UTF-8.
+ */
+unsafe fn optional_str<'a>(string: *const c_char) -> Result<Option<&'a str>, ()> {
+    if string.is_null() {
+        return Ok(None);
+    }
+    unsafe { CStr::from_ptr(string) }.to_str().map(Some).map_err(drop)
+}
+
+/**
+ * @brief Return a config value given as JSON as the text Config::set takes.
+ */
+fn json_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/**
+ * @brief Convert a message to a C string, dropping any NUL bytes.
+ */
+fn to_c_string(message: &str) -> CString {
+    CString::new(message.replace('\0', "")).unwrap_or_default()
+}
+
+// End of rust_sample_project/src/ffi.rs
@@ -0,0 +1,556 @@
+// rust_sample_project/src/filter.rs
+
+// A small expression language for selecting which items get processed, e.g.
+//
+//     value > 100 && name contains 'Widget'
+//     !(tags contains 'manual_review') || item_id == 7
+//     status == 'failed'
+//
+// UUID and string ids are compared as text, e.g. item_id == 'sku-0042';
+// they never equal a number. processed == true is short for
+// status == 'processed'.
+//
+// Grammar (lowest to highest precedence):
+//
+//     expr       := and_expr ( "||" and_expr )*
+//     and_expr   := unary ( "&&" unary )*
+//     unary      := "!" unary | "(" expr ")" | comparison
+//     comparison := field operator literal
+//     field      := "item_id" | "name" | "value" | "status" | "processed" | "tags"
+//     operator   := "==" | "!=" | ">" | ">=" | "<" | "<=" | "contains"
+//     literal    := number | 'text' | "text" | true | false
+
+use std::fmt;
+
+use crate::item::{Item, ItemStatus};
+
+/**
+ * @enum Field
+ * @brief An Item field that can appear on the left side of a comparison.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Id,
+    Name,
+    Value,
+    Status,
+    Processed,
+    Tags,
+}
+
+/**
+ * @enum CompareOp
+ * @brief A comparison operator.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Contains,
+}
+
+/**
+ * @enum Literal
+ * @brief A constant on the right side of a comparison.
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum Literal {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+/**
+ * @enum Expr
+ * @brief Parsed filter expression tree.
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Compare(Field, CompareOp, Literal),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+/**
+ * @enum TokenKind
+ * @brief Lexical tokens of the filter language.
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Number(f64),
+    Text(String),
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+    End,
+}
+
+/**
+ * @struct Token
+ * @brief A token together with its character offset in the source expression.
+ */
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+/**
+ * @struct FilterExpr
+ * @brief A parsed filter expression that can be evaluated against items.
+ */
+#[derive(Debug, Clone)]
+pub struct FilterExpr {
+    source: String,
+    expr: Expr,
+}
+
+impl FilterExpr {
+    /**
+     * @brief Parse a filter expression.
+     *
+     * @param source The expression text, e.g. "value > 100 && name contains 'Widget'".
+     * @return Result<FilterExpr, String> The parsed filter, or an error message
+     *         pointing at the offending position in the expression.
+     */
+    pub fn parse(source: &str) -> Result<FilterExpr, String> {
+        let to_error = |(position, msg): (usize, String)| {
+            format!("Invalid filter expression {}", format_error(source, position, &msg))
+        };
+        let tokens = tokenize(source).map_err(to_error)?;
+        let mut parser = Parser { tokens, index: 0 };
+        let expr = parser
+            .parse_expr()
+            .and_then(|expr| match parser.peek().kind {
+                TokenKind::End => Ok(expr),
+                _ => Err((parser.peek().position, String::from("unexpected input after expression"))),
+            })
+            .map_err(to_error)?;
+        Ok(FilterExpr {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    /**
+     * @brief Evaluate the filter for one item.
+     *
+     * @param item The item to test.
+     * @return bool True if the item matches the expression.
+     */
+    pub fn matches(&self, item: &Item) -> bool {
+        evaluate(&self.expr, item)
+    }
+}
+
+impl fmt::Display for FilterExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/**
+ * @brief Build an error message that quotes an expression and marks the position.
+ *
+ * Shared by the expression languages of the crate (filters and transformations).
+ *
+ * @param source The full expression text.
+ * @param position Character offset of the problem.
+ * @param message Description of the problem.
+ * @return String A multi-line "at position N: ..." message with a caret under the position.
+ */
+pub fn format_error(source: &str, position: usize, message: &str) -> String {
+    format!(
+        "at position {}: {}\n  {}\n  {}^",
+        position + 1,
+        message,
+        source,
+        " ".repeat(position)
+    )
+}
+
+/**
+ * @brief Split an expression into tokens.
+ *
+ * @param source The expression text.
+ * @return Result<Vec<Token>, (usize, String)> The tokens (ending with End), or
+ *         the position and description of a lexical error.
+ */
+fn tokenize(source: &str) -> Result<Vec<Token>, (usize, String)> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+        let kind = match c {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                TokenKind::LParen
+            }
+            ')' => {
+                i += 1;
+                TokenKind::RParen
+            }
+            _ if two == "&&" || two == "||" || two == "==" || two == "!=" || two == ">=" || two == "<=" => {
+                i += 2;
+                match two.as_str() {
+                    "&&" => TokenKind::And,
+                    "||" => TokenKind::Or,
+                    "==" => TokenKind::Op(CompareOp::Eq),
+                    "!=" => TokenKind::Op(CompareOp::Ne),
+                    ">=" => TokenKind::Op(CompareOp::Ge),
+                    _ => TokenKind::Op(CompareOp::Le),
+                }
+            }
+            '>' => {
+                i += 1;
+                TokenKind::Op(CompareOp::Gt)
+            }
+            '<' => {
+                i += 1;
+                TokenKind::Op(CompareOp::Lt)
+            }
+            '!' => {
+                i += 1;
+                TokenKind::Not
+            }
+            '\'' | '"' => {
+                let quote = c;
+                i += 1;
+                let mut text = String::new();
+                while i < chars.len() && chars[i] != quote {
+                    text.push(chars[i]);
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err((start, String::from("unterminated string literal")));
+                }
+                i += 1; // closing quote
+                TokenKind::Text(text)
+            }
+            _ if c.is_ascii_digit() || c == '-' || c == '.' => {
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text
+                    .parse::<f64>()
+                    .map_err(|_| (start, format!("invalid number '{}'", text)))?;
+                TokenKind::Number(number)
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                if word == "contains" {
+                    TokenKind::Op(CompareOp::Contains)
+                } else {
+                    TokenKind::Ident(word)
+                }
+            }
+            _ => return Err((start, format!("unexpected character '{}'", c))),
+        };
+        tokens.push(Token { kind, position: start });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::End,
+        position: chars.len(),
+    });
+    Ok(tokens)
+}
+
+/**
+ * @struct Parser
+ * @brief Recursive-descent parser over a token list.
+ */
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.index].clone();
+        if self.index < self.tokens.len() - 1 {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, (usize, String)> {
+        let mut left = self.parse_and()?;
+        while self.peek().kind == TokenKind::Or {
+            self.advance();
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, (usize, String)> {
+        let mut left = self.parse_unary()?;
+        while self.peek().kind == TokenKind::And {
+            self.advance();
+            let right = self.parse_unary()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, (usize, String)> {
+        match self.peek().kind {
+            TokenKind::Not => {
+                self.advance();
+                Ok(Expr::Not(Box::new(self.parse_unary()?)))
+            }
+            TokenKind::LParen => {
+                self.advance();
+                let inner = self.parse_expr()?;
+                let closing = self.advance();
+                if closing.kind != TokenKind::RParen {
+                    return Err((closing.position, String::from("expected ')'")));
+                }
+                Ok(inner)
+            }
+            _ => self.parse_comparison(),
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, (usize, String)> {
+        let field_token = self.advance();
+        let field = match &field_token.kind {
+            TokenKind::Ident(name) => match name.as_str() {
+                "item_id" | "id" => Field::Id,
+                "name" => Field::Name,
+                "value" => Field::Value,
+                "status" => Field::Status,
+                "processed" => Field::Processed,
+                "tags" => Field::Tags,
+                other => {
+                    return Err((
+                        field_token.position,
+                        format!(
+                            "unknown field '{}' (expected item_id, name, value, status, processed or tags)",
+                            other
+                        ),
+                    ))
+                }
+            },
+            _ => return Err((field_token.position, String::from("expected a field name"))),
+        };
+
+        let op_token = self.advance();
+        let op = match op_token.kind {
+            TokenKind::Op(op) => op,
+            _ => {
+                return Err((
+                    op_token.position,
+                    String::from("expected a comparison operator (==, !=, >, >=, <, <=, contains)"),
+                ))
+            }
+        };
+
+        let literal_token = self.advance();
+        let literal = match literal_token.kind {
+            TokenKind::Number(n) => Literal::Number(n),
+            TokenKind::Text(text) => Literal::Text(text),
+            TokenKind::Ident(ref word) if word == "true" => Literal::Bool(true),
+            TokenKind::Ident(ref word) if word == "false" => Literal::Bool(false),
+            _ => {
+                return Err((
+                    literal_token.position,
+                    String::from("expected a number, a quoted string, true or false"),
+                ))
+            }
+        };
+
+        check_types(field, op, &literal).map_err(|msg| (op_token.position, msg))?;
+        if let (Field::Status, Literal::Text(name)) = (field, &literal) {
+            ItemStatus::parse(name).map_err(|msg| (literal_token.position, msg))?;
+        }
+        Ok(Expr::Compare(field, op, literal))
+    }
+}
+
+/**
+ * @brief Reject comparisons whose operator or literal does not fit the field.
+ *
+ * @return Result<(), String> Ok if the comparison is well-typed, or a description of the mismatch.
+ */
+fn check_types(field: Field, op: CompareOp, literal: &Literal) -> Result<(), String> {
+    let ok = match (field, literal) {
+        (Field::Id | Field::Value, Literal::Number(_)) => op != CompareOp::Contains,
+        (Field::Id, Literal::Text(_)) => matches!(op, CompareOp::Eq | CompareOp::Ne),
+        (Field::Name, Literal::Text(_)) => matches!(op, CompareOp::Eq | CompareOp::Ne | CompareOp::Contains),
+        (Field::Status, Literal::Text(_)) => matches!(op, CompareOp::Eq | CompareOp::Ne),
+        (Field::Processed, Literal::Bool(_)) => matches!(op, CompareOp::Eq | CompareOp::Ne),
+        (Field::Tags, Literal::Text(_)) => op == CompareOp::Contains,
+        _ => false,
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(format!("operator {:?} cannot compare field {:?} with {:?}", op, field, literal))
+    }
+}
+
+/**
+ * @brief Evaluate an expression tree for one item.
+ */
+fn evaluate(expr: &Expr, item: &Item) -> bool {
+    match expr {
+        Expr::And(left, right) => evaluate(left, item) && evaluate(right, item),
+        Expr::Or(left, right) => evaluate(left, item) || evaluate(right, item),
+        Expr::Not(inner) => !evaluate(inner, item),
+        Expr::Compare(field, op, literal) => match (field, literal) {
+            (Field::Id, Literal::Number(n)) => {
+                compare_numbers(item.item_id.as_number().unwrap_or(f64::NAN), *op, *n)
+            }
+            (Field::Id, Literal::Text(text)) => match op {
+                CompareOp::Ne => item.item_id.to_string() != *text,
+                _ => item.item_id.to_string() == *text,
+            },
+            (Field::Value, Literal::Number(n)) => compare_numbers(item.value.to_f64(), *op, *n),
+            (Field::Name, Literal::Text(text)) => match op {
+                CompareOp::Eq => *item.name == **text,
+                CompareOp::Ne => *item.name != **text,
+                _ => item.name.contains(text.as_str()),
+            },
+            (Field::Status, Literal::Text(text)) => match op {
+                CompareOp::Ne => item.status.name() != *text,
+                _ => item.status.name() == *text,
+            },
+            (Field::Processed, Literal::Bool(b)) => match op {
+                CompareOp::Ne => item.is_processed() != *b,
+                _ => item.is_processed() == *b,
+            },
+            (Field::Tags, Literal::Text(text)) => item.has_tag(text),
+            // Ruled out by check_types at parse time.
+            _ => false,
+        },
+    }
+}
+
+fn compare_numbers(left: f64, op: CompareOp, right: f64) -> bool {
+    match op {
+        CompareOp::Eq => left == right,
+        CompareOp::Ne => left != right,
+        CompareOp::Gt => left > right,
+        CompareOp::Ge => left >= right,
+        CompareOp::Lt => left < right,
+        CompareOp::Le => left <= right,
+        CompareOp::Contains => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item_id::{IdKind, ItemId};
+    use crate::money::Money;
+
+    fn item(id: i64, name: &str, value: i64) -> Item {
+        Item::new(ItemId::from(id), name.to_string(), Money::from_int(value))
+    }
+
+    fn matches(source: &str, item: &Item) -> bool {
+        FilterExpr::parse(source).unwrap_or_else(|e| panic!("{}", e)).matches(item)
+    }
+
+    fn parse_error(source: &str) -> String {
+        FilterExpr::parse(source).expect_err("expression should be rejected")
+    }
+
+    #[test]
+    fn accepts_every_field_and_literal_form() {
+        let mut widget = item(7, "Blue Widget", 150);
+        widget.add_tag("manual_review");
+        assert!(matches("value > 100 && name contains 'Widget'", &widget));
+        assert!(matches("name == \"Blue Widget\"", &widget));
+        assert!(matches("item_id == 7 && id != 8", &widget));
+        assert!(matches("value >= 150 && value <= 150 && value < 150.01", &widget));
+        assert!(matches("tags contains 'manual_review'", &widget));
+        assert!(matches("status == 'new' && processed == false", &widget));
+        assert!(matches("  value>-1&&value!=0  ", &widget));
+        let mut sku = widget.clone();
+        sku.item_id = ItemId::parse("sku-0042", IdKind::Str).unwrap();
+        assert!(matches("item_id == 'sku-0042'", &sku));
+        assert!(!matches("item_id == 42 || item_id > 0", &sku));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let gizmo = item(2, "Gizmo", 50);
+        assert!(matches("value > 10 || value > 100 && name == 'Nope'", &gizmo));
+        assert!(!matches("(value > 10 || value > 100) && name == 'Nope'", &gizmo));
+        assert!(!matches("!value > 10 || name == 'Nope'", &gizmo));
+        assert!(matches("!(value > 100 && name == 'Gizmo')", &gizmo));
+        assert!(matches("!!(name == 'Gizmo')", &gizmo));
+    }
+
+    #[test]
+    fn display_keeps_the_source_text() {
+        let source = "value > 100 && name contains 'Widget'";
+        assert_eq!(FilterExpr::parse(source).unwrap().to_string(), source);
+    }
+
+    #[test]
+    fn empty_expression_is_rejected() {
+        assert_eq!(parse_error(""), "Invalid filter expression at position 1: expected a field name\n  \n  ^");
+        assert!(parse_error("   ").contains("expected a field name"));
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_position() {
+        assert_eq!(
+            parse_error("value > 'x"),
+            "Invalid filter expression at position 9: unterminated string literal\n  value > 'x\n          ^"
+        );
+        assert!(parse_error("price > 1").starts_with(
+            "Invalid filter expression at position 1: unknown field 'price' (expected item_id, name, value, \
+             status, processed or tags)"
+        ));
+        assert!(parse_error("value # 1").contains("at position 7: unexpected character '#'"));
+        assert!(parse_error("value > 1.2.3").contains("at position 9: invalid number '1.2.3'"));
+        assert!(parse_error("value 1").contains("at position 7: expected a comparison operator"));
+        assert!(parse_error("value >").contains("at position 8: expected a number, a quoted string, true or false"));
+        assert!(parse_error("(value > 1").contains("at position 11: expected ')'"));
+        assert!(parse_error("value > 1 value").contains("at position 11: unexpected input after expression"));
+        assert!(parse_error("value > 1 &&").contains("at position 13: expected a field name"));
+    }
+
+    #[test]
+    fn ill_typed_comparisons_are_rejected() {
+        let message = parse_error("value contains 1");
+        assert!(message.contains("operator Contains cannot compare field Value with Number(1.0)"));
+        assert!(parse_error("name > 'a'").contains("cannot compare field Name"));
+        assert!(parse_error("tags == 'a'").contains("cannot compare field Tags"));
+        assert!(parse_error("processed == 1").contains("cannot compare field Processed"));
+        assert!(parse_error("status == 'finished'").contains("at position 11:"));
+    }
+}
+
+// End of rust_sample_project/src/filter.rs
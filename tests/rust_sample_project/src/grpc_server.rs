@@ -0,0 +1,295 @@
+// rust_sample_project/src/grpc_server.rs
+
+// gRPC service (see the `grpc` feature and proto/item_processing.proto), so
+// other services can have items processed without shelling out to the
+// binary. Started with `serve --grpc [--listen <addr>]`:
+//
+//     ProcessItems  a stream of items in, a stream of results out, one per
+//                   item and in order
+//     GetStatus     the configured threshold and counters since startup
+//
+// Items are handled like streamed items (--stream): the configured filter,
+// tag rules and processor, see pipeline::process_streamed_item. Nothing is
+// loaded from or saved to the data file. Each item is processed on tokio's
+// blocking pool, so slow transformations do not hold up other streams. An
+// item that cannot be read (unknown id kind, invalid value or status) gets
+// an "invalid" result and the stream goes on.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::config::Config;
+use crate::filter::FilterExpr;
+use crate::hooks::ItemOutcome;
+use crate::item::{Item, ItemStatus};
+use crate::item_id::{self, ItemId};
+use crate::item_processor::ItemProcessor;
+use crate::metrics::Metrics;
+use crate::money::Money;
+use crate::pipeline;
+use crate::shutdown;
+
+/// The types and service traits generated from proto/item_processing.proto by build.rs.
+pub mod proto {
+    tonic::include_proto!("rust_sample_project.v1");
+}
+
+use proto::item_processing_server::{ItemProcessing, ItemProcessingServer};
+
+/// How often the server checks for a pending SIGINT/SIGTERM.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Results buffered per stream before processing waits for the client to read.
+const RESULT_BUFFER: usize = 64;
+
+/**
+ * @struct ServiceCounters
+ * @brief Counters reported by GetStatus, shared by all streams.
+ */
+#[derive(Default)]
+struct ServiceCounters {
+    active_streams: AtomicU64,
+    items_received: AtomicU64,
+    items_processed: AtomicU64,
+    items_failed: AtomicU64,
+    items_skipped: AtomicU64,
+    items_invalid: AtomicU64,
+}
+
+/**
+ * @struct ItemProcessingService
+ * @brief Implementation of the ItemProcessing service.
+ */
+pub struct ItemProcessingService {
+    /// Processes the selected items.
+    item_processor: Arc<ItemProcessor>,
+    /// Optional filter selecting the items to process.
+    item_filter: Arc<Option<FilterExpr>>,
+    /// The configured threshold, for GetStatus.
+    threshold: f64,
+    /// Collector for the processing counters and timings.
+    metrics: Arc<Metrics>,
+    /// Counters since startup.
+    counters: Arc<ServiceCounters>,
+    /// When the service was created.
+    started_at: DateTime<Utc>,
+}
+
+impl ItemProcessingService {
+    /**
+     * @brief Constructs the service with the processor and filter of a configuration.
+     *
+     * @param config The effective configuration.
+     * @param metrics Collector for the processing counters and timings.
+     * @return Result<ItemProcessingService, String> The service, or an error
+     *         message for invalid processing settings.
+     */
+    pub fn new(config: &Config, metrics: Arc<Metrics>) -> Result<Self, String> {
+        let item_processor = pipeline::build_item_processor(config)?.with_audit_log(pipeline::open_audit_log(config)?);
+        Ok(ItemProcessingService {
+            item_processor: Arc::new(item_processor),
+            item_filter: Arc::new(pipeline::build_item_filter(config)?),
+            threshold: config.threshold,
+            metrics,
+            counters: Arc::new(ServiceCounters::default()),
+            started_at: Utc::now(),
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl ItemProcessing for ItemProcessingService {
+    type ProcessItemsStream = ReceiverStream<Result<proto::ProcessResult, Status>>;
+
+    async fn process_items(
+        &self,
+        request: Request<Streaming<proto::Item>>,
+    ) -> Result<Response<Self::ProcessItemsStream>, Status> {
+        let mut incoming = request.into_inner();
+        let (sender, receiver) = mpsc::channel(RESULT_BUFFER);
+        let item_processor = Arc::clone(&self.item_processor);
+        let item_filter = Arc::clone(&self.item_filter);
+        let metrics = Arc::clone(&self.metrics);
+        let counters = Arc::clone(&self.counters);
+
+        counters.active_streams.fetch_add(1, Ordering::SeqCst);
+        tokio::spawn(async move {
+            while let Some(message) = incoming.next().await {
+                let message = match message {
+                    Ok(message) => message,
+                    Err(status) => {
+                        log_warning!("grpc_server:process_items", "Stream ended with an error: {}", status);
+                        break;
+                    }
+                };
+                counters.items_received.fetch_add(1, Ordering::Relaxed);
+                let result = match item_from_message(&message) {
+                    Ok(mut item) => {
+                        let (item_processor, item_filter, metrics) =
+                            (Arc::clone(&item_processor), Arc::clone(&item_filter), Arc::clone(&metrics));
+                        let handled = tokio::task::spawn_blocking(move || {
+                            let outcome = pipeline::process_streamed_item(
+                                &mut item,
+                                &item_processor,
+                                item_filter.as_ref().as_ref(),
+                                &metrics,
+                            );
+                            (item, outcome)
+                        })
+                        .await;
+                        match handled {
+                            Ok((item, outcome)) => {
+                                counters.count(outcome);
+                                Ok(processed_result(message.item_id, item, outcome))
+                            }
+                            Err(e) => Err(Status::internal(format!("Processing item {} failed: {}", message.item_id, e))),
+                        }
+                    }
+                    Err(error) => {
+                        log_warning!("grpc_server:process_items", "Invalid item {}: {}", message.item_id, error);
+                        counters.items_invalid.fetch_add(1, Ordering::Relaxed);
+                        Ok(proto::ProcessResult {
+                            item_id: message.item_id,
+                            outcome: String::from("invalid"),
+                            item: None,
+                            error,
+                        })
+                    }
+                };
+                if sender.send(result).await.is_err() {
+                    log_debug!("grpc_server:process_items", "Client went away, closing the stream.");
+                    break;
+                }
+            }
+            counters.active_streams.fetch_sub(1, Ordering::SeqCst);
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn get_status(
+        &self,
+        _request: Request<proto::GetStatusRequest>,
+    ) -> Result<Response<proto::ServerStatus>, Status> {
+        let counters = &self.counters;
+        Ok(Response::new(proto::ServerStatus {
+            started_at: self.started_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            threshold: self.threshold,
+            active_streams: counters.active_streams.load(Ordering::SeqCst),
+            items_received: counters.items_received.load(Ordering::Relaxed),
+            items_processed: counters.items_processed.load(Ordering::Relaxed),
+            items_failed: counters.items_failed.load(Ordering::Relaxed),
+            items_skipped: counters.items_skipped.load(Ordering::Relaxed),
+            items_invalid: counters.items_invalid.load(Ordering::Relaxed),
+        }))
+    }
+}
+
+impl ServiceCounters {
+    /**
+     * @brief Count a handled item under its outcome.
+     */
+    fn count(&self, outcome: ItemOutcome) {
+        let counter = match outcome {
+            ItemOutcome::Processed => &self.items_processed,
+            ItemOutcome::Failed | ItemOutcome::TimedOut => &self.items_failed,
+            _ => &self.items_skipped,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/**
+ * @brief Serve the ItemProcessing service until SIGINT/SIGTERM.
+ *
+ * Streams still open at shutdown are cut off; items already handed to the
+ * processor finish first.
+ *
+ * @param config The effective configuration (grpc_addr and the processing settings).
+ * @param metrics Collector for the processing counters and timings.
+ * @return Result<(), String> Ok after a requested shutdown, or an error
+ *         message if the settings are invalid or the address cannot be bound.
+ */
+pub fn serve(config: &Config, metrics: Arc<Metrics>) -> Result<(), String> {
+    let addr: SocketAddr = config
+        .grpc_addr
+        .parse()
+        .map_err(|e| format!("Invalid gRPC address '{}': {}", config.grpc_addr, e))?;
+    let service = ItemProcessingService::new(config, metrics)?;
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start the gRPC runtime: {}", e))?;
+
+    runtime.block_on(async move {
+        log_info!("grpc_server:serve", "gRPC service listening on {}.", addr);
+        Server::builder()
+            .add_service(ItemProcessingServer::new(service))
+            .serve_with_shutdown(addr, async {
+                while !shutdown::requested() {
+                    tokio::time::sleep(SHUTDOWN_POLL_INTERVAL).await;
+                }
+                log_info!("grpc_server:serve", "Shutdown requested, stopping the gRPC service.");
+            })
+            .await
+            .map_err(|e| format!("gRPC service on {} failed: {}", addr, e))
+    })
+}
+
+/**
+ * @brief Read an item from its message.
+ *
+ * @param message The item as received.
+ * @return Result<Item, String> The item, or an error message naming the invalid field.
+ */
+fn item_from_message(message: &proto::Item) -> Result<Item, String> {
+    let item_id = ItemId::parse(&message.item_id, item_id::expected_kind())?;
+    let value = Money::parse(&message.value).map_err(|e| format!("Invalid value '{}': {}", message.value, e))?;
+    let mut item = Item::new(item_id, message.name.clone(), value);
+    if !message.status.is_empty() {
+        item.status = ItemStatus::parse(&message.status)?;
+    }
+    item.tags = message.tags.clone();
+    item.category = message.category.clone();
+    item.supplier = message.supplier.clone();
+    item.invariants()?;
+    Ok(item)
+}
+
+/**
+ * @brief Build the result of a handled item.
+ *
+ * @param item_id The id as it was sent.
+ * @param item The item after processing.
+ * @param outcome What happened to it.
+ * @return proto::ProcessResult The result message.
+ */
+fn processed_result(item_id: String, item: Item, outcome: ItemOutcome) -> proto::ProcessResult {
+    let outcome = serde_json::to_value(outcome)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default();
+    proto::ProcessResult {
+        item_id,
+        outcome,
+        item: Some(proto::Item {
+            item_id: item.item_id.to_string(),
+            name: item.name.to_string(),
+            value: item.value.to_string(),
+            status: item.status.name().to_string(),
+            tags: item.tags,
+            category: item.category,
+            supplier: item.supplier,
+        }),
+        error: String::new(),
+    }
+}
+
+// End of rust_sample_project/src/grpc_server.rs
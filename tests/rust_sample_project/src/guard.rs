@@ -0,0 +1,85 @@
+// rust_sample_project/src/guard.rs
+
+// Protection against destroying data by accident. Runs rewrite their own data
+// source and configured outputs in place, which snapshots (see snapshot.rs)
+// and staged saves (see staging.rs) already protect, so they are not checked.
+// Writing over any other existing non-empty local data file (the output of
+// join, the data file generate would replace) is refused unless it was allowed
+// explicitly, with --allow-overwrite or the allow_overwrite config key.
+// Likewise rollback, which replaces the current data files with a snapshot's
+// copies. The checks run before anything is loaded or written, so a refused
+// operation leaves no trace. Remote data files are not checked; their stores
+// keep versions of their own.
+
+use std::fs;
+use std::path::Path;
+
+use crate::storage;
+
+/// How the refusal messages say the operation can be allowed.
+const HOW_TO_ALLOW: &str = "pass --allow-overwrite or set allow_overwrite = true to allow it";
+
+/**
+ * @brief Return the size of a local data file that holds data.
+ *
+ * @param path Path (or URL) of the data file.
+ * @return Option<u64> The size in bytes, or None if the file is remote,
+ *         missing or empty.
+ */
+fn existing_size(path: &Path) -> Option<u64> {
+    if path.to_str().is_some_and(storage::is_remote) {
+        return None;
+    }
+    fs::metadata(path).ok().filter(|metadata| metadata.is_file() && metadata.len() > 0).map(|metadata| metadata.len())
+}
+
+/**
+ * @brief Refuse to write over an existing non-empty data file unless allowed.
+ *
+ * @param path Path (or URL) of the data file about to be written.
+ * @param operation What is writing it, for the message (e.g. "join").
+ * @param allowed Whether overwriting was allowed (see ALLOW_OVERWRITE).
+ * @return Result<(), String> Ok if the file may be written, or the refusal message.
+ */
+pub fn check_overwrite(path: &Path, operation: &str, allowed: bool) -> Result<(), String> {
+    match existing_size(path) {
+        Some(size) if !allowed => Err(format!(
+            "Refusing to let {} overwrite '{}': it already holds {} bytes of data; {}",
+            operation,
+            path.display(),
+            size,
+            HOW_TO_ALLOW
+        )),
+        _ => Ok(()),
+    }
+}
+
+/**
+ * @brief Refuse to restore a snapshot over non-empty data files unless allowed.
+ *
+ * @param id The snapshot's id.
+ * @param targets The data files the snapshot would restore.
+ * @param allowed Whether overwriting was allowed (see ALLOW_OVERWRITE).
+ * @return Result<(), String> Ok if the snapshot may be restored, or the refusal message.
+ */
+pub fn check_restore(id: &str, targets: &[&Path], allowed: bool) -> Result<(), String> {
+    if allowed {
+        return Ok(());
+    }
+    let replaced: Vec<String> = targets
+        .iter()
+        .filter(|target| existing_size(target).is_some())
+        .map(|target| format!("'{}'", target.display()))
+        .collect();
+    if replaced.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "Refusing to restore snapshot {}: it would replace the current contents of {}; {}",
+        id,
+        replaced.join(", "),
+        HOW_TO_ALLOW
+    ))
+}
+
+// End of rust_sample_project/src/guard.rs
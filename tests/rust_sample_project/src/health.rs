@@ -0,0 +1,179 @@
+// rust_sample_project/src/health.rs
+
+// Health and readiness probes of the long-running modes, served by the daemon
+// (`serve`) and by the metrics endpoint of --watch (--metrics-addr):
+//
+//     GET /healthz  200 while the process is up
+//     GET /readyz   200 if the configuration is valid and the data source can
+//                   be reached, 503 with the failed checks otherwise
+//
+// Both answer with JSON that includes the outcome of the last run and when it
+// finished (see history::RunRecord), so Kubernetes probes and dashboards can
+// monitor the processor without parsing its logs.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::history::RunRecord;
+use crate::storage;
+
+/**
+ * @struct ReadinessCheck
+ * @brief The result of one readiness check.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReadinessCheck {
+    /// What was checked: "config" or "data_source".
+    pub name: &'static str,
+    /// Whether the check passed.
+    pub ok: bool,
+    /// What was found, e.g. the location checked or why it failed.
+    pub detail: String,
+}
+
+/**
+ * @struct Health
+ * @brief What the probes of a long-running process report.
+ */
+pub struct Health {
+    /// When the process came up.
+    started_at: DateTime<Utc>,
+    /// The most recent finished run.
+    last_run: Mutex<Option<RunRecord>>,
+    /// Runs the readiness checks against the configuration in effect.
+    readiness: Box<dyn Fn() -> Vec<ReadinessCheck> + Send + Sync>,
+}
+
+impl Health {
+    /**
+     * @brief Constructs the probe state of a process that just came up.
+     *
+     * @param readiness Runs the readiness checks, see readiness_checks.
+     * @return Health The state, with no run yet.
+     */
+    pub fn new<F>(readiness: F) -> Self
+    where
+        F: Fn() -> Vec<ReadinessCheck> + Send + Sync + 'static,
+    {
+        Health {
+            started_at: Utc::now(),
+            last_run: Mutex::new(None),
+            readiness: Box::new(readiness),
+        }
+    }
+
+    /**
+     * @brief Remember a finished run as the last one.
+     *
+     * @param record The run.
+     */
+    pub fn record_run(&self, record: RunRecord) {
+        *self.last_run.lock().unwrap_or_else(|e| e.into_inner()) = Some(record);
+    }
+
+    /**
+     * @brief Return the last run as JSON, with when it finished (null before the first run).
+     */
+    fn last_run_json(&self) -> Value {
+        let last_run = self.last_run.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        match last_run {
+            Some(record) => {
+                let finished_at = record.started_at + Duration::milliseconds((record.duration_secs * 1000.0) as i64);
+                let mut value = json!(record);
+                value["finished_at"] = json!(finished_at);
+                value
+            }
+            None => Value::Null,
+        }
+    }
+
+    /**
+     * @brief Answer a probe request.
+     *
+     * @param method The HTTP method.
+     * @param target "/healthz" or "/readyz".
+     * @return (&'static str, &'static str, String) The status line, content type and body.
+     */
+    pub fn respond(&self, method: &str, target: &str) -> (&'static str, &'static str, String) {
+        if method != "GET" {
+            return ("405 Method Not Allowed", "text/plain", String::from("Method Not Allowed\n"));
+        }
+        let (status, body) = match target {
+            "/readyz" => {
+                let checks = (self.readiness)();
+                let ready = checks.iter().all(|check| check.ok);
+                (
+                    if ready { "200 OK" } else { "503 Service Unavailable" },
+                    json!({
+                        "ready": ready,
+                        "checks": checks,
+                        "last_run": self.last_run_json(),
+                    }),
+                )
+            }
+            _ => (
+                "200 OK",
+                json!({
+                    "status": "alive",
+                    "started_at": self.started_at,
+                    "last_run": self.last_run_json(),
+                }),
+            ),
+        };
+        let mut body = serde_json::to_string_pretty(&body).unwrap_or_else(|_| String::from("{}"));
+        body.push('\n');
+        (status, "application/json", body)
+    }
+}
+
+/**
+ * @brief Check that a data location can be reached.
+ *
+ * @param location The data path or URL.
+ * @return ReadinessCheck The "data_source" check; a local file that does not
+ *         exist yet passes, as runs fall back to simulated items.
+ */
+pub fn check_data_source(location: &Path) -> ReadinessCheck {
+    let result = storage::open(location).and_then(|store| store.probe());
+    ReadinessCheck {
+        name: "data_source",
+        ok: result.is_ok(),
+        detail: match result {
+            Ok(true) => format!("{} is reachable", location.display()),
+            Ok(false) => format!("{} is reachable, but does not exist yet", location.display()),
+            Err(e) => e,
+        },
+    }
+}
+
+/**
+ * @brief Run the readiness checks against a configuration.
+ *
+ * @param config The configuration in effect.
+ * @param config_error Why the config file was last rejected, if it was
+ *        (the previous configuration stays in effect meanwhile).
+ * @return Vec<ReadinessCheck> The "config" check, then a "data_source" check
+ *         for the data path and each input source.
+ */
+pub fn readiness_checks(config: &Config, config_error: Option<&str>) -> Vec<ReadinessCheck> {
+    let mut checks = vec![ReadinessCheck {
+        name: "config",
+        ok: config_error.is_none(),
+        detail: match config_error {
+            Some(e) => format!("the config file was rejected: {}", e),
+            None => String::from("valid"),
+        },
+    }];
+    checks.push(check_data_source(&config.data_path));
+    for source in &config.input_sources {
+        checks.push(check_data_source(Path::new(source)));
+    }
+    checks
+}
+
+// End of rust_sample_project/src/health.rs
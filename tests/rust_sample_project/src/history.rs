@@ -0,0 +1,336 @@
+// rust_sample_project/src/history.rs
+
+// Run history (see the history_path config key and the `history`
+// subcommand). Every pipeline run, successful or not, appends one record to a
+// JSON Lines file: when it ran and for how long, a hash of the effective
+// configuration, how many items it loaded, processed and failed, and how it
+// ended. `history list` shows the runs with their failure rate and throughput,
+// plus the averages over all of them, so trends stand out; `history show
+// <run_id>` shows one run. Streaming runs and runs of a pipeline definition
+// file are not recorded.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::diff;
+use crate::error::PipelineError;
+use crate::report::{self, PipelineReport};
+use crate::storage;
+
+/**
+ * @enum RunOutcome
+ * @brief How a run ended.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    /// Every processed item succeeded.
+    Succeeded,
+    /// The run completed, but some items failed.
+    PartiallyFailed,
+    /// The run was stopped early by SIGINT/SIGTERM.
+    Interrupted,
+    /// The run failed as a whole (config, load, lock or save error).
+    Failed,
+}
+
+impl RunOutcome {
+    /**
+     * @brief Classify the result of a run.
+     *
+     * @param result The outcome of the run.
+     * @return RunOutcome How the run ended.
+     */
+    pub fn of(result: &Result<PipelineReport, PipelineError>) -> Self {
+        match result {
+            Ok(report) if report.interrupted => RunOutcome::Interrupted,
+            Ok(report) if report.items_failed > 0 => RunOutcome::PartiallyFailed,
+            Ok(_) => RunOutcome::Succeeded,
+            Err(_) => RunOutcome::Failed,
+        }
+    }
+
+    /**
+     * @brief Return the outcome as in the history file, e.g. "partially_failed".
+     *
+     * @return &'static str The name.
+     */
+    pub fn name(&self) -> &'static str {
+        match self {
+            RunOutcome::Succeeded => "succeeded",
+            RunOutcome::PartiallyFailed => "partially_failed",
+            RunOutcome::Interrupted => "interrupted",
+            RunOutcome::Failed => "failed",
+        }
+    }
+}
+
+/**
+ * @struct RunRecord
+ * @brief One run, as recorded in the history file.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Id of the run, see PipelineReport::run_id.
+    pub run_id: String,
+    /// When the run started.
+    pub started_at: DateTime<Utc>,
+    /// How long the run took, in seconds.
+    pub duration_secs: f64,
+    /// Hash of the effective configuration, see Config::fingerprint.
+    pub config_hash: String,
+    /// The config profile, if one was active.
+    pub profile: Option<String>,
+    /// How the run ended.
+    pub outcome: RunOutcome,
+    /// Why the run failed (outcome "failed" only).
+    pub error: Option<String>,
+    /// Number of items loaded.
+    pub items_loaded: usize,
+    /// Number of items processed successfully.
+    pub items_processed: usize,
+    /// Number of items whose processing failed.
+    pub items_failed: usize,
+}
+
+impl RunRecord {
+    /**
+     * @brief Constructs the record of a finished run.
+     *
+     * @param started_at When the run was started, for runs that failed
+     *        before they had a report.
+     * @param config_hash Hash of the effective configuration.
+     * @param profile The config profile, if one is active.
+     * @param result The outcome of the run.
+     * @return RunRecord The record.
+     */
+    pub fn new(
+        started_at: DateTime<Utc>,
+        config_hash: String,
+        profile: Option<String>,
+        result: &Result<PipelineReport, PipelineError>,
+    ) -> Self {
+        let mut record = RunRecord {
+            run_id: report::run_id_at(started_at),
+            started_at,
+            duration_secs: 0.0,
+            config_hash,
+            profile,
+            outcome: RunOutcome::of(result),
+            error: None,
+            items_loaded: 0,
+            items_processed: 0,
+            items_failed: 0,
+        };
+        match result {
+            Ok(report) => {
+                record.run_id = report.run_id.clone();
+                record.started_at = report.started_at;
+                record.items_loaded = report.items_loaded;
+                record.items_processed = report.items_processed;
+                record.items_failed = report.items_failed;
+            }
+            Err(e) => record.error = Some(e.to_string()),
+        }
+        let finished_at = result.as_ref().ok().and_then(|report| report.finished_at).unwrap_or_else(Utc::now);
+        record.duration_secs = (finished_at - record.started_at).num_milliseconds().max(0) as f64 / 1000.0;
+        record
+    }
+
+    /**
+     * @brief Return the share of processed items that failed.
+     *
+     * @return Option<f64> The failure rate between 0 and 1, or None if no item was processed.
+     */
+    pub fn failure_rate(&self) -> Option<f64> {
+        let attempted = self.items_processed + self.items_failed;
+        (attempted > 0).then(|| self.items_failed as f64 / attempted as f64)
+    }
+
+    /**
+     * @brief Return the number of items processed (or failed) per second.
+     *
+     * @return Option<f64> The throughput, or None if the run took no measurable time.
+     */
+    pub fn items_per_sec(&self) -> Option<f64> {
+        (self.duration_secs > 0.0).then(|| (self.items_processed + self.items_failed) as f64 / self.duration_secs)
+    }
+
+    /**
+     * @brief Format the record as text, one field per line.
+     *
+     * @return String The text, ending with a newline.
+     */
+    pub fn to_text(&self) -> String {
+        let rows = [
+            [String::from("Run ID:"), self.run_id.clone()],
+            [String::from("Started at:"), format_time(self.started_at)],
+            [String::from("Duration:"), format!("{:.3} s", self.duration_secs)],
+            [String::from("Config hash:"), self.config_hash.clone()],
+            [String::from("Profile:"), self.profile.clone().unwrap_or_else(|| String::from("-"))],
+            [String::from("Outcome:"), self.outcome.name().to_string()],
+            [String::from("Error:"), self.error.clone().unwrap_or_else(|| String::from("-"))],
+            [String::from("Items loaded:"), self.items_loaded.to_string()],
+            [String::from("Items processed:"), self.items_processed.to_string()],
+            [String::from("Items failed:"), self.items_failed.to_string()],
+            [String::from("Failure rate:"), format_rate(self.failure_rate())],
+            [String::from("Throughput:"), format_throughput(self.items_per_sec())],
+        ];
+        diff::format_table(&rows)
+    }
+}
+
+/**
+ * @struct HistoryTrend
+ * @brief Averages over the recorded runs.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct HistoryTrend {
+    /// Number of runs.
+    pub runs: usize,
+    /// Number of runs that failed as a whole.
+    pub failed_runs: usize,
+    /// Share of processed items that failed, over all runs (None if no item was processed).
+    pub failure_rate: Option<f64>,
+    /// Average throughput of the runs that processed items, in items per second.
+    pub items_per_sec: Option<f64>,
+}
+
+impl HistoryTrend {
+    /**
+     * @brief Compute the averages over runs.
+     *
+     * @param records The runs.
+     * @return HistoryTrend The averages.
+     */
+    pub fn of(records: &[RunRecord]) -> Self {
+        let processed: usize = records.iter().map(|record| record.items_processed).sum();
+        let failed: usize = records.iter().map(|record| record.items_failed).sum();
+        let throughputs: Vec<f64> = records
+            .iter()
+            .filter(|record| record.items_processed + record.items_failed > 0)
+            .filter_map(RunRecord::items_per_sec)
+            .collect();
+        HistoryTrend {
+            runs: records.len(),
+            failed_runs: records.iter().filter(|record| record.outcome == RunOutcome::Failed).count(),
+            failure_rate: (processed + failed > 0).then(|| failed as f64 / (processed + failed) as f64),
+            items_per_sec: (!throughputs.is_empty())
+                .then(|| throughputs.iter().sum::<f64>() / throughputs.len() as f64),
+        }
+    }
+}
+
+/**
+ * @brief Format a timestamp as in the tables.
+ */
+fn format_time(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+/**
+ * @brief Format a failure rate as a percentage, or "-".
+ */
+fn format_rate(rate: Option<f64>) -> String {
+    rate.map_or(String::from("-"), |rate| format!("{:.1}%", rate * 100.0))
+}
+
+/**
+ * @brief Format a throughput in items per second, or "-".
+ */
+fn format_throughput(items_per_sec: Option<f64>) -> String {
+    items_per_sec.map_or(String::from("-"), |items_per_sec| format!("{:.1}/s", items_per_sec))
+}
+
+/**
+ * @brief Lay out runs as a table, followed by the averages over them.
+ *
+ * @param records The runs, oldest first.
+ * @return String The table, one line per run.
+ */
+pub fn to_table(records: &[RunRecord]) -> String {
+    let mut rows: Vec<[String; 7]> = vec![[
+        String::from("RUN ID"),
+        String::from("STARTED"),
+        String::from("DURATION"),
+        String::from("PROCESSED"),
+        String::from("FAILED"),
+        String::from("THROUGHPUT"),
+        String::from("OUTCOME"),
+    ]];
+    for record in records {
+        rows.push([
+            record.run_id.clone(),
+            format_time(record.started_at),
+            format!("{:.1} s", record.duration_secs),
+            record.items_processed.to_string(),
+            format!("{} ({})", record.items_failed, format_rate(record.failure_rate())),
+            format_throughput(record.items_per_sec()),
+            record.outcome.name().to_string(),
+        ]);
+    }
+    let trend = HistoryTrend::of(records);
+    let mut text = diff::format_table(&rows);
+    text.push_str(&format!(
+        "{} runs ({} failed), {} of processed items failed, {} on average.\n",
+        trend.runs,
+        trend.failed_runs,
+        format_rate(trend.failure_rate),
+        format_throughput(trend.items_per_sec)
+    ));
+    text
+}
+
+/**
+ * @brief Append a run to the history file.
+ *
+ * @param path Path of the history file (it and its parent directories are created).
+ * @param record The run.
+ * @return Result<(), String> Ok once the record is written, or an error message.
+ */
+pub fn append(path: &Path, record: &RunRecord) -> Result<(), String> {
+    let mut line = serde_json::to_string(record).map_err(|e| format!("Failed to serialize run record: {}", e))?;
+    line.push('\n');
+    storage::create_parent_dir(path)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Failed to write run history '{}': {}", path.display(), e))
+}
+
+/**
+ * @brief Read the runs recorded in a history file.
+ *
+ * Lines that are not a run record are skipped with a warning.
+ *
+ * @param path Path of the history file.
+ * @return Result<Vec<RunRecord>, String> The runs in the order they were
+ *         recorded (none if the file does not exist), or an error message.
+ */
+pub fn read(path: &Path) -> Result<Vec<RunRecord>, String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to open run history '{}': {}", path.display(), e)),
+    };
+    let mut records = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read run history '{}': {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<RunRecord>(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => log_warning!("history:read", "Skipping line {} of {}: {}", index + 1, path.display(), e),
+        }
+    }
+    Ok(records)
+}
+
+// End of rust_sample_project/src/history.rs
@@ -0,0 +1,306 @@
+// rust_sample_project/src/hooks.rs
+
+// Lifecycle hooks for embedding applications. A PipelineHooks value collects
+// observers (PipelineObserver trait objects, or plain closures registered for
+// a single event) and is passed to pipeline::run_processing_pipeline, which
+// calls them at each step. Observers can watch a run or veto steps of it.
+
+use serde::Serialize;
+
+use crate::error::PipelineError;
+use crate::item::Item;
+use crate::report::PipelineReport;
+
+/**
+ * @enum HookDecision
+ * @brief Whether a step may go ahead, as decided by an observer.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookDecision {
+    /// Let the step go ahead.
+    Continue,
+    /// Stop the step, for the given reason (logged and reported).
+    Veto(String),
+}
+
+/**
+ * @enum ItemOutcome
+ * @brief What happened to a single item during a run.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemOutcome {
+    /// The item was already marked processed (incremental runs).
+    AlreadyProcessed,
+    /// The item does not match the filter expression.
+    FilteredOut,
+    /// The item is not selected by the tag rules.
+    SkippedByTag,
+    /// The item is missing from the enrichment lookup table (policy "skip").
+    SkippedByLookup,
+    /// An observer vetoed processing the item.
+    Vetoed,
+    /// The item was processed successfully.
+    Processed,
+    /// Processing the item failed.
+    Failed,
+    /// Processing the item exceeded the per-item timeout.
+    TimedOut,
+}
+
+/**
+ * @brief Observer of a pipeline run.
+ *
+ * Every method has a default that does nothing (or lets the step go ahead),
+ * so implementations only override the events they care about. Items are
+ * handled on worker threads, hence the Send + Sync bound.
+ */
+pub trait PipelineObserver: Send + Sync {
+    /**
+     * @brief Called once the items are loaded and deduplicated, before any is processed.
+     *
+     * Not called when the data source holds no items.
+     *
+     * @param items The items about to be processed (without those a resumed
+     *              run already completed).
+     * @return HookDecision Veto to abort the run; nothing is processed or saved then.
+     */
+    fn on_load_complete(&self, _items: &[Item]) -> HookDecision {
+        HookDecision::Continue
+    }
+
+    /**
+     * @brief Called on a worker thread before an item selected for processing is processed.
+     *
+     * @param item The item, unchanged so far.
+     * @return HookDecision Veto to skip the item; it is saved unchanged then.
+     */
+    fn before_item(&self, _item: &Item) -> HookDecision {
+        HookDecision::Continue
+    }
+
+    /**
+     * @brief Called for every item once it is handled, in the original item order.
+     *
+     * @param item The item as it will be saved.
+     * @param outcome What happened to the item.
+     */
+    fn after_item(&self, _item: &Item, _outcome: ItemOutcome) {}
+
+    /**
+     * @brief Called once the items are saved.
+     *
+     * @param items The saved items.
+     * @param report The run's finished report.
+     */
+    fn on_save_complete(&self, _items: &[Item], _report: &PipelineReport) {}
+
+    /**
+     * @brief Called when the run fails, just before the error is returned.
+     *
+     * @param error Why the run failed.
+     */
+    fn on_error(&self, _error: &PipelineError) {}
+}
+
+type LoadCompleteFn = Box<dyn Fn(&[Item]) -> HookDecision + Send + Sync>;
+type BeforeItemFn = Box<dyn Fn(&Item) -> HookDecision + Send + Sync>;
+type AfterItemFn = Box<dyn Fn(&Item, ItemOutcome) + Send + Sync>;
+type SaveCompleteFn = Box<dyn Fn(&[Item], &PipelineReport) + Send + Sync>;
+type ErrorFn = Box<dyn Fn(&PipelineError) + Send + Sync>;
+
+/**
+ * @struct ClosureObserver
+ * @brief Adapts a closure registered for a single event to PipelineObserver.
+ */
+#[derive(Default)]
+struct ClosureObserver {
+    on_load_complete: Option<LoadCompleteFn>,
+    before_item: Option<BeforeItemFn>,
+    after_item: Option<AfterItemFn>,
+    on_save_complete: Option<SaveCompleteFn>,
+    on_error: Option<ErrorFn>,
+}
+
+impl PipelineObserver for ClosureObserver {
+    fn on_load_complete(&self, items: &[Item]) -> HookDecision {
+        self.on_load_complete.as_ref().map_or(HookDecision::Continue, |hook| hook(items))
+    }
+
+    fn before_item(&self, item: &Item) -> HookDecision {
+        self.before_item.as_ref().map_or(HookDecision::Continue, |hook| hook(item))
+    }
+
+    fn after_item(&self, item: &Item, outcome: ItemOutcome) {
+        if let Some(hook) = &self.after_item {
+            hook(item, outcome);
+        }
+    }
+
+    fn on_save_complete(&self, items: &[Item], report: &PipelineReport) {
+        if let Some(hook) = &self.on_save_complete {
+            hook(items, report);
+        }
+    }
+
+    fn on_error(&self, error: &PipelineError) {
+        if let Some(hook) = &self.on_error {
+            hook(error);
+        }
+    }
+}
+
+/**
+ * @struct PipelineHooks
+ * @brief The observers of a pipeline run, called in registration order.
+ */
+#[derive(Default)]
+pub struct PipelineHooks {
+    observers: Vec<Box<dyn PipelineObserver>>,
+}
+
+impl PipelineHooks {
+    /**
+     * @brief Constructs an empty set of hooks; a run with it behaves as if there were none.
+     *
+     * @return PipelineHooks Hooks without observers.
+     */
+    pub fn new() -> Self {
+        PipelineHooks::default()
+    }
+
+    /**
+     * @brief Add an observer.
+     *
+     * @param observer The observer, called after those added before it.
+     * @return PipelineHooks The hooks with the observer added.
+     */
+    pub fn with_observer(mut self, observer: Box<dyn PipelineObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /**
+     * @brief Add a closure called when loading completes, see PipelineObserver::on_load_complete.
+     */
+    pub fn on_load_complete<F>(self, hook: F) -> Self
+    where
+        F: Fn(&[Item]) -> HookDecision + Send + Sync + 'static,
+    {
+        self.with_closure(ClosureObserver {
+            on_load_complete: Some(Box::new(hook)),
+            ..ClosureObserver::default()
+        })
+    }
+
+    /**
+     * @brief Add a closure called before each item, see PipelineObserver::before_item.
+     */
+    pub fn before_item<F>(self, hook: F) -> Self
+    where
+        F: Fn(&Item) -> HookDecision + Send + Sync + 'static,
+    {
+        self.with_closure(ClosureObserver {
+            before_item: Some(Box::new(hook)),
+            ..ClosureObserver::default()
+        })
+    }
+
+    /**
+     * @brief Add a closure called after each item, see PipelineObserver::after_item.
+     */
+    pub fn after_item<F>(self, hook: F) -> Self
+    where
+        F: Fn(&Item, ItemOutcome) + Send + Sync + 'static,
+    {
+        self.with_closure(ClosureObserver {
+            after_item: Some(Box::new(hook)),
+            ..ClosureObserver::default()
+        })
+    }
+
+    /**
+     * @brief Add a closure called when saving completes, see PipelineObserver::on_save_complete.
+     */
+    pub fn on_save_complete<F>(self, hook: F) -> Self
+    where
+        F: Fn(&[Item], &PipelineReport) + Send + Sync + 'static,
+    {
+        self.with_closure(ClosureObserver {
+            on_save_complete: Some(Box::new(hook)),
+            ..ClosureObserver::default()
+        })
+    }
+
+    /**
+     * @brief Add a closure called when the run fails, see PipelineObserver::on_error.
+     */
+    pub fn on_error<F>(self, hook: F) -> Self
+    where
+        F: Fn(&PipelineError) + Send + Sync + 'static,
+    {
+        self.with_closure(ClosureObserver {
+            on_error: Some(Box::new(hook)),
+            ..ClosureObserver::default()
+        })
+    }
+
+    fn with_closure(self, observer: ClosureObserver) -> Self {
+        self.with_observer(Box::new(observer))
+    }
+
+    /**
+     * @brief Notify the observers that loading completed.
+     *
+     * @param items The loaded items.
+     * @return HookDecision The first veto, or Continue; later observers are
+     *         not called after a veto.
+     */
+    pub fn notify_load_complete(&self, items: &[Item]) -> HookDecision {
+        self.first_veto(|observer| observer.on_load_complete(items))
+    }
+
+    /**
+     * @brief Ask the observers whether an item may be processed.
+     *
+     * @param item The item about to be processed.
+     * @return HookDecision The first veto, or Continue.
+     */
+    pub fn notify_before_item(&self, item: &Item) -> HookDecision {
+        self.first_veto(|observer| observer.before_item(item))
+    }
+
+    /**
+     * @brief Notify the observers that an item was handled.
+     */
+    pub fn notify_after_item(&self, item: &Item, outcome: ItemOutcome) {
+        self.observers.iter().for_each(|observer| observer.after_item(item, outcome));
+    }
+
+    /**
+     * @brief Notify the observers that the items were saved.
+     */
+    pub fn notify_save_complete(&self, items: &[Item], report: &PipelineReport) {
+        self.observers.iter().for_each(|observer| observer.on_save_complete(items, report));
+    }
+
+    /**
+     * @brief Notify the observers that the run failed.
+     */
+    pub fn notify_error(&self, error: &PipelineError) {
+        self.observers.iter().for_each(|observer| observer.on_error(error));
+    }
+
+    fn first_veto<F>(&self, decide: F) -> HookDecision
+    where
+        F: Fn(&dyn PipelineObserver) -> HookDecision,
+    {
+        self.observers
+            .iter()
+            .map(|observer| decide(observer.as_ref()))
+            .find(|decision| matches!(decision, HookDecision::Veto(_)))
+            .unwrap_or(HookDecision::Continue)
+    }
+}
+
+// End of rust_sample_project/src/hooks.rs
@@ -0,0 +1,204 @@
+// rust_sample_project/src/http_store.rs
+
+use std::io::Read;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::config::{
+    get_http_auth_token_env, get_http_headers, get_http_post_results, get_http_retries,
+    get_http_timeout_secs,
+};
+use crate::batch::SaveBatch;
+use crate::rate_limit::{self, RateLimiter};
+use crate::storage::ItemStore;
+
+/// Delay before the first retry; doubled for every further attempt.
+const RETRY_BASE_DELAY_MS: u64 = 500;
+
+/**
+ * @struct HttpStore
+ * @brief Items data served by a REST endpoint.
+ *
+ * Items are fetched with GET and, if enabled, processed items are sent back
+ * with POST to the same URL. Transport errors and 5xx/429 responses are
+ * retried with exponential backoff. A batch with an idempotency key (see
+ * delivery.rs) is posted with an Idempotency-Key header, the same on every
+ * retry, so the endpoint can drop a batch it already received.
+ */
+pub struct HttpStore {
+    url: String,
+    agent: ureq::Agent,
+    /// Extra request headers, including Authorization when a token is set.
+    headers: Vec<(String, String)>,
+    /// Number of retries after a failed request.
+    retries: u32,
+    /// Whether write() POSTs the processed items back to the URL.
+    post_results: bool,
+    /// Limits the items posted per second (None without a rate limit).
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl HttpStore {
+    /**
+     * @brief Constructs a store for a URL using the HTTP settings from config.
+     *
+     * The bearer token is read from the environment variable named by
+     * HTTP_AUTH_TOKEN_ENV, so it never has to live in the config itself.
+     *
+     * @param url The http(s) URL of the items endpoint.
+     * @return HttpStore The store.
+     */
+    pub fn from_config(url: &str) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(get_http_timeout_secs()))
+            .build();
+        let mut headers: Vec<(String, String)> = get_http_headers()
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        if let Ok(token) = std::env::var(get_http_auth_token_env()) {
+            headers.push((String::from("Authorization"), format!("Bearer {}", token)));
+        }
+        HttpStore {
+            url: url.to_string(),
+            agent,
+            headers,
+            retries: get_http_retries(),
+            post_results: get_http_post_results(),
+            rate_limiter: rate_limit::sink_limiter(),
+        }
+    }
+
+    /**
+     * @brief Send a request, retrying transient failures.
+     *
+     * @param method "GET" or "POST".
+     * @param body The request body for POST.
+     * @param idempotency_key Sent as Idempotency-Key header, if given.
+     * @return Result<Vec<u8>, String> The response body, or an error message
+     *         once all attempts failed.
+     */
+    fn send(
+        &self,
+        method: &str,
+        body: Option<&[u8]>,
+        idempotency_key: Option<&str>,
+    ) -> Result<Vec<u8>, String> {
+        let mut attempt: u32 = 0;
+        loop {
+            let mut request = self.agent.request(method, &self.url);
+            for (name, value) in &self.headers {
+                request = request.set(name, value);
+            }
+            if let Some(key) = idempotency_key {
+                request = request.set("Idempotency-Key", key);
+            }
+            let result = match body {
+                Some(body) => request.set("Content-Type", "application/json").send_bytes(body),
+                None => request.call(),
+            };
+
+            let error = match result {
+                Ok(response) => {
+                    let mut contents = Vec::new();
+                    response.into_reader().read_to_end(&mut contents).map_err(|e| {
+                        format!("Failed to read response from '{}': {}", self.url, e)
+                    })?;
+                    return Ok(contents);
+                }
+                Err(ureq::Error::Status(code, _)) if code != 429 && code < 500 => {
+                    return Err(format!("{} '{}' failed with status {}", method, self.url, code));
+                }
+                Err(e) => e,
+            };
+
+            if attempt >= self.retries {
+                return Err(format!(
+                    "{} '{}' failed after {} attempts: {}",
+                    method,
+                    self.url,
+                    attempt + 1,
+                    error
+                ));
+            }
+            let delay = Duration::from_millis(RETRY_BASE_DELAY_MS << attempt.min(10));
+            log_warning!(
+                "http_store:send",
+                "{} '{}' failed ({}); retrying in {} ms.",
+                method,
+                self.url,
+                error,
+                delay.as_millis()
+            );
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+}
+
+impl ItemStore for HttpStore {
+    fn location(&self) -> &str {
+        &self.url
+    }
+
+    /**
+     * @brief A remote source always counts as existing; an unreachable
+     *        endpoint is reported when reading instead of falling back to
+     *        simulated items.
+     */
+    fn exists(&self) -> Result<bool, String> {
+        Ok(true)
+    }
+
+    fn read(&self) -> Result<Vec<u8>, String> {
+        log_info!("http_store:read", "Fetching items from {}...", self.url);
+        self.send("GET", None, None)
+    }
+
+    fn write(&self, contents: &[u8]) -> Result<(), String> {
+        if !self.post_results {
+            log_info!(
+                "http_store:write",
+                "Not posting processed items back to {} (HTTP_POST_RESULTS is off).",
+                self.url
+            );
+            return Ok(());
+        }
+        log_info!("http_store:write", "Posting processed items to {}...", self.url);
+        self.send("POST", Some(contents), None).map(|_| ())
+    }
+
+    /**
+     * @brief Processed items are posted in batches, one request per batch.
+     */
+    fn accepts_batches(&self) -> bool {
+        self.post_results
+    }
+
+    /**
+     * @brief Send a single HEAD request; any answer short of a server error
+     *        means the endpoint is up.
+     */
+    fn probe(&self) -> Result<bool, String> {
+        let mut request = self.agent.head(&self.url);
+        for (name, value) in &self.headers {
+            request = request.set(name, value);
+        }
+        match request.call() {
+            Ok(_) => Ok(true),
+            Err(ureq::Error::Status(code, _)) if code < 500 => Ok(true),
+            Err(e) => Err(format!("HEAD '{}' failed: {}", self.url, e)),
+        }
+    }
+
+    fn write_batch(&self, batch: &SaveBatch, contents: &[u8]) -> Result<(), String> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire(batch.len as u64);
+        }
+        log_info!("http_store:write_batch", "Posting {} to {}...", batch, self.url);
+        self.send("POST", Some(contents), batch.idempotency_key.as_deref()).map(|_| ())
+    }
+}
+
+// End of rust_sample_project/src/http_store.rs
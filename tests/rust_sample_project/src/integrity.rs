@@ -0,0 +1,193 @@
+// rust_sample_project/src/integrity.rs
+
+// Integrity checks of local data files. Every save writes a checksum file
+// next to the data file ("items.json" -> "items.json.sha256") in the format
+// of sha256sum(1), so it can also be checked by hand:
+//
+//     9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08  items.json
+//
+// The checksum covers the bytes on disk (after compression and encryption).
+// Loading a data file that has a checksum file verifies it first, so a file
+// that was modified or truncated outside the pipeline fails with an
+// IntegrityError instead of being processed. Data files without a checksum
+// file (written before checksums existed, or by other tools) load unchecked.
+// Remote data files are not covered; their stores have checks of their own.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::storage::{self, FileStore, ItemStore};
+
+/// Suffix of the checksum file next to a data file.
+pub const SIDECAR_SUFFIX: &str = ".sha256";
+
+/**
+ * @enum IntegrityError
+ * @brief Why a data file failed its integrity check.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// The data file does not have the checksum its checksum file records.
+    Mismatch {
+        /// Path of the data file.
+        location: String,
+        /// Checksum recorded in the checksum file.
+        expected: String,
+        /// Checksum of the data file as it is now.
+        actual: String,
+    },
+    /// The checksum file cannot be read or holds no SHA-256 checksum.
+    InvalidChecksumFile {
+        /// Path of the checksum file.
+        location: String,
+        /// What is wrong with it.
+        reason: String,
+    },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::Mismatch { location, expected, actual } => write!(
+                f,
+                "Integrity check failed for data file '{}': its SHA-256 is {} but {} was recorded when it \
+                 was saved, so it was modified or truncated outside the pipeline (delete '{}{}' to accept it as is)",
+                location, actual, expected, location, SIDECAR_SUFFIX
+            ),
+            IntegrityError::InvalidChecksumFile { location, reason } => {
+                write!(f, "Integrity check failed: checksum file '{}' {}", location, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {}
+
+/**
+ * @brief Return the path of the checksum file of a data file.
+ *
+ * @param data_path Path of the data file.
+ * @return PathBuf The path with ".sha256" appended.
+ */
+pub fn sidecar_path(data_path: &Path) -> PathBuf {
+    storage::with_suffix(data_path, SIDECAR_SUFFIX)
+}
+
+/**
+ * @brief Return the SHA-256 checksum of file contents as lowercase hex.
+ *
+ * @param contents The file contents.
+ * @return String The 64 hex digit checksum.
+ */
+pub fn digest(contents: &[u8]) -> String {
+    hex(&Sha256::digest(contents))
+}
+
+/**
+ * @brief Return the SHA-256 checksum of a file, reading it in chunks.
+ *
+ * @param path The file.
+ * @return Result<String, String> The 64 hex digit checksum, or an error message.
+ */
+pub fn file_digest(path: &Path) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    File::open(path)
+        .and_then(|mut file| io::copy(&mut file, &mut hasher))
+        .map_err(|e| format!("Failed to read data file '{}': {}", path.display(), e))?;
+    Ok(hex(&hasher.finalize()))
+}
+
+/**
+ * @brief Return the contents of the checksum file for a data file.
+ *
+ * @param data_path Path of the data file; its file name is recorded.
+ * @param digest The data file's checksum, see digest.
+ * @return Vec<u8> A sha256sum(1) line.
+ */
+pub fn sidecar_contents(data_path: &Path, digest: &str) -> Vec<u8> {
+    let name = data_path.file_name().unwrap_or(data_path.as_os_str());
+    format!("{}  {}\n", digest, name.to_string_lossy()).into_bytes()
+}
+
+/**
+ * @brief Write (atomically) the checksum file of a data file.
+ *
+ * @param data_path Path of the data file.
+ * @param digest The data file's checksum, see digest.
+ * @return Result<(), String> Ok, or an error message.
+ */
+pub fn write_sidecar(data_path: &Path, digest: &str) -> Result<(), String> {
+    let sidecar = sidecar_path(data_path);
+    FileStore::new(&sidecar)
+        .write(&sidecar_contents(data_path, digest))
+        .map_err(|e| format!("Failed to write checksum file: {}", e))?;
+    log_debug!("integrity:write_sidecar", "Wrote checksum file {}.", sidecar.display());
+    Ok(())
+}
+
+/**
+ * @brief Verify a data file against its checksum file, if it has one.
+ *
+ * @param data_path Path of the data file.
+ * @param digest Checksum of the data file's contents as read, see digest.
+ * @return Result<bool, IntegrityError> True if the file was verified, false
+ *         if it has no checksum file, or the failed check.
+ */
+pub fn verify(data_path: &Path, digest: &str) -> Result<bool, IntegrityError> {
+    let Some(expected) = recorded_digest(data_path)? else {
+        log_debug!(
+            "integrity:verify",
+            "No checksum file for {}, loading it unverified.",
+            data_path.display()
+        );
+        return Ok(false);
+    };
+    if expected != digest {
+        return Err(IntegrityError::Mismatch {
+            location: data_path.display().to_string(),
+            expected,
+            actual: digest.to_string(),
+        });
+    }
+    log_debug!("integrity:verify", "Verified {} against its checksum file.", data_path.display());
+    Ok(true)
+}
+
+/**
+ * @brief Return the checksum recorded in the checksum file of a data file.
+ *
+ * @param data_path Path of the data file.
+ * @return Result<Option<String>, IntegrityError> The checksum as lowercase
+ *         hex, None if the file has no checksum file, or the checksum file
+ *         that cannot be read or holds no checksum.
+ */
+pub fn recorded_digest(data_path: &Path) -> Result<Option<String>, IntegrityError> {
+    let sidecar = sidecar_path(data_path);
+    let invalid = |reason: String| IntegrityError::InvalidChecksumFile {
+        location: sidecar.display().to_string(),
+        reason,
+    };
+    let text = match fs::read_to_string(&sidecar) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(invalid(format!("cannot be read: {}", e))),
+    };
+    let recorded = text.split_whitespace().next().unwrap_or_default().to_ascii_lowercase();
+    if recorded.len() != 64 || !recorded.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(invalid(String::from("holds no SHA-256 checksum")));
+    }
+    Ok(Some(recorded))
+}
+
+/**
+ * @brief Format bytes as lowercase hex.
+ */
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// End of rust_sample_project/src/integrity.rs
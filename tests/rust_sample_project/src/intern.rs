@@ -0,0 +1,93 @@
+// rust_sample_project/src/intern.rs
+
+// Interning of item names (see the intern_names config key). Datasets often
+// hold a few thousand distinct names across millions of records; with
+// interning, DataHandler swaps each loaded item's name for the pool's copy,
+// so all items with the same name share one allocation and the duplicates
+// are freed right after each file is loaded. The pool counts what it saved,
+// for the run report.
+
+use std::collections::HashSet;
+use std::mem;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::item::Item;
+
+/**
+ * @struct InternStats
+ * @brief What interning saved, for the run report.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct InternStats {
+    /// Number of names looked up in the pool.
+    pub names: usize,
+    /// Number of distinct names kept.
+    pub distinct_names: usize,
+    /// Bytes of name allocations freed because an equal name was already pooled.
+    pub bytes_saved: usize,
+}
+
+/**
+ * @struct NamePool
+ * @brief The distinct item names seen so far.
+ */
+#[derive(Debug, Default)]
+pub struct NamePool {
+    names: HashSet<Arc<str>>,
+    stats: InternStats,
+}
+
+impl NamePool {
+    /**
+     * @brief Constructs an empty pool.
+     *
+     * @return NamePool The pool.
+     */
+    pub fn new() -> Self {
+        NamePool::default()
+    }
+
+    /**
+     * @brief Return the pooled copy of a name, adding the name if it is new.
+     *
+     * @param name The name.
+     * @return Arc<str> The shared name.
+     */
+    pub fn intern(&mut self, name: &Arc<str>) -> Arc<str> {
+        self.stats.names += 1;
+        if let Some(pooled) = self.names.get(name) {
+            if !Arc::ptr_eq(pooled, name) && Arc::strong_count(name) == 1 {
+                // The string plus the two reference counts in front of it
+                self.stats.bytes_saved += name.len() + 2 * mem::size_of::<usize>();
+            }
+            return Arc::clone(pooled);
+        }
+        self.names.insert(Arc::clone(name));
+        self.stats.distinct_names = self.names.len();
+        Arc::clone(name)
+    }
+
+    /**
+     * @brief Replace the names of items with their pooled copies.
+     *
+     * @param items The freshly loaded items.
+     */
+    pub fn intern_items(&mut self, items: &mut [Item]) {
+        for item in items.iter_mut() {
+            item.name = self.intern(&item.name);
+        }
+    }
+
+    /**
+     * @brief Return what the pool has saved so far.
+     *
+     * @return InternStats The counters.
+     */
+    pub fn stats(&self) -> InternStats {
+        self.stats
+    }
+}
+
+// End of rust_sample_project/src/intern.rs
@@ -0,0 +1,335 @@
+// rust_sample_project/src/invalid_records.rs
+
+// What loading does with the records of a data file that cannot be turned
+// into an item, by input_strictness:
+//
+//     lenient   skip them, logging each at DEBUG and how many at INFO
+//     warn      skip them with a warning each (the default)
+//     strict    fail the load with an error listing every invalid record by
+//               its record, line or row number, so the run ends with the
+//               load-failure exit code
+//
+// The readers of all formats report invalid records to an InvalidRecords,
+// which the loader finishes once the whole file is read. Each record comes
+// with its context: the file, its record number (or line, or row), the line
+// it starts on in text formats, and a snippet of its source text. The skipped
+// records end up in the run report (skipped_records), so they are in the
+// machine-readable output as well as in the log.
+//
+// Errors that fail a text data file as a whole (malformed JSON, XML or CSV)
+// name the line and quote it the same way, see source_line and with_source.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Range;
+
+use serde::de::IgnoredAny;
+use serde::Serialize;
+
+/// Characters of source text quoted in messages; longer snippets are cut.
+pub const SNIPPET_CHARS: usize = 60;
+
+/**
+ * @enum Strictness
+ * @brief How loading treats invalid records.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Skip invalid records quietly ("lenient").
+    Lenient,
+    /// Skip invalid records with a warning each ("warn").
+    #[default]
+    Warn,
+    /// Fail the load if any record is invalid ("strict").
+    Strict,
+}
+
+impl Strictness {
+    /**
+     * @brief Parse a strictness name as used in config.
+     *
+     * @param name One of "lenient", "warn" or "strict".
+     * @return Result<Strictness, String> The strictness, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<Strictness, String> {
+        match name {
+            "lenient" => Ok(Strictness::Lenient),
+            "warn" => Ok(Strictness::Warn),
+            "strict" => Ok(Strictness::Strict),
+            other => Err(format!(
+                "Unknown input strictness '{}' (expected 'lenient', 'warn' or 'strict')",
+                other
+            )),
+        }
+    }
+}
+
+/**
+ * @struct InvalidRecord
+ * @brief A record of a data file that is not a valid item, with its context.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InvalidRecord {
+    /// The data file.
+    pub file: String,
+    /// Where the record is, e.g. "record #3", "line 7" or "row 4 of sheet 'Items'".
+    pub record: String,
+    /// The line of the file the record starts on (None in binary formats).
+    pub line: Option<usize>,
+    /// The start of the record's source text (empty if there is none).
+    pub snippet: String,
+    /// Why the record is invalid.
+    pub error: String,
+}
+
+impl fmt::Display for InvalidRecord {
+    // e.g. "record #3 (line 14): Invalid amount 'oops'; near `{"item_id": 3, ...`"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.record)?;
+        if let Some(line) = self.line.filter(|_| !self.record.starts_with("line ")) {
+            write!(f, " (line {})", line)?;
+        }
+        write!(f, ": {}", self.error)?;
+        if !self.snippet.is_empty() {
+            write!(f, "; near `{}`", self.snippet)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * @struct InvalidRecords
+ * @brief Collects the invalid records of one data file.
+ */
+pub struct InvalidRecords<'a> {
+    strictness: Strictness,
+    /// The data file, for messages.
+    location: &'a str,
+    /// The invalid records, in file order.
+    records: Vec<InvalidRecord>,
+}
+
+impl<'a> InvalidRecords<'a> {
+    /**
+     * @brief Constructs an empty collection for a data file.
+     *
+     * @param strictness How invalid records are treated.
+     * @param location The data file, for messages.
+     * @return InvalidRecords The collection.
+     */
+    pub fn new(strictness: Strictness, location: &'a str) -> Self {
+        InvalidRecords {
+            strictness,
+            location,
+            records: Vec::new(),
+        }
+    }
+
+    /**
+     * @brief Report a record that is not a valid item.
+     *
+     * @param record Where the record is, e.g. "record #3" or "line 7".
+     * @param line The line the record starts on, if the format has lines.
+     * @param source The record's source text (cut to a snippet), or "".
+     * @param error Why it is invalid.
+     */
+    pub fn skip(&mut self, record: impl fmt::Display, line: Option<usize>, source: &str, error: impl fmt::Display) {
+        let record = InvalidRecord {
+            file: self.location.to_string(),
+            record: record.to_string(),
+            line,
+            snippet: snippet(source),
+            error: error.to_string(),
+        };
+        match self.strictness {
+            Strictness::Lenient => log_debug!("invalid_records:skip", "{}: skipping invalid {}", self.location, record),
+            Strictness::Warn => log_warning!("invalid_records:skip", "{}: skipping invalid {}", self.location, record),
+            Strictness::Strict => {}
+        }
+        self.records.push(record);
+    }
+
+    /**
+     * @brief Conclude the data file.
+     *
+     * @return Result<Vec<InvalidRecord>, String> The skipped records, or (in
+     *         strict mode, if any record is invalid) an error message listing
+     *         all of them.
+     */
+    pub fn finish(self) -> Result<Vec<InvalidRecord>, String> {
+        let count = self.records.len();
+        match self.strictness {
+            _ if count == 0 => {}
+            Strictness::Strict => {
+                let records: Vec<String> = self.records.iter().map(ToString::to_string).collect();
+                return Err(format!(
+                    "{} invalid records in data file '{}' (input_strictness is strict):\n  {}",
+                    count,
+                    self.location,
+                    records.join("\n  ")
+                ));
+            }
+            Strictness::Lenient => {
+                log_info!("invalid_records:finish", "Skipped {} invalid records in {}.", count, self.location)
+            }
+            Strictness::Warn => {
+                log_warning!("invalid_records:finish", "Skipped {} invalid records in {}.", count, self.location)
+            }
+        }
+        Ok(self.records)
+    }
+}
+
+/**
+ * @struct JsonRecords
+ * @brief Finds the source text of the records of a JSON data file, which a
+ *        parsed serde_json::Value no longer knows.
+ *
+ * The records are the elements of the top-level array (schema version 1) or
+ * of the top-level "items" array. They are only looked up once a record is
+ * invalid, so valid files are not scanned twice.
+ */
+pub struct JsonRecords<'a> {
+    /// The file contents.
+    contents: &'a [u8],
+    /// Byte ranges of the records (None until needed, empty if the layout is unexpected).
+    spans: Option<Vec<Range<usize>>>,
+}
+
+impl<'a> JsonRecords<'a> {
+    /**
+     * @brief Constructs the lookup for a JSON data file.
+     *
+     * @param contents The file contents.
+     * @return JsonRecords The lookup.
+     */
+    pub fn new(contents: &'a [u8]) -> Self {
+        JsonRecords { contents, spans: None }
+    }
+
+    /**
+     * @brief Return where a record is in the file.
+     *
+     * @param index The record's index in the items array, counting from 0.
+     * @return (Option<usize>, Cow<str>) The line it starts on and its source
+     *         text, or (None, "") if it cannot be found.
+     */
+    pub fn locate(&mut self, index: usize) -> (Option<usize>, Cow<'a, str>) {
+        let contents = self.contents;
+        let spans = self.spans.get_or_insert_with(|| record_spans(contents).unwrap_or_default());
+        match spans.get(index) {
+            Some(span) => (Some(line_of(contents, span.start)), String::from_utf8_lossy(&contents[span.clone()])),
+            None => (None, Cow::Borrowed("")),
+        }
+    }
+}
+
+/**
+ * @brief Find the byte ranges of the records of a JSON data file, see JsonRecords.
+ *
+ * @return Option<Vec<Range<usize>>> The ranges, or None if the file is not
+ *         laid out as expected.
+ */
+fn record_spans(contents: &[u8]) -> Option<Vec<Range<usize>>> {
+    let skip_whitespace = |mut at: usize| {
+        while contents.get(at).is_some_and(u8::is_ascii_whitespace) {
+            at += 1;
+        }
+        at
+    };
+    // The end of the JSON value starting at a byte offset
+    let value_end = |at: usize| {
+        let mut values = serde_json::Deserializer::from_slice(contents.get(at..)?).into_iter::<IgnoredAny>();
+        values.next()?.ok()?;
+        Some(at + values.byte_offset())
+    };
+    let mut at = skip_whitespace(0);
+    if contents.get(at) == Some(&b'{') {
+        at = skip_whitespace(at + 1);
+        loop {
+            let key_end = value_end(at)?;
+            let key: String = serde_json::from_slice(&contents[at..key_end]).ok()?;
+            at = skip_whitespace(key_end);
+            if contents.get(at) != Some(&b':') {
+                return None;
+            }
+            at = skip_whitespace(at + 1);
+            if key == "items" {
+                break;
+            }
+            at = skip_whitespace(value_end(at)?);
+            if contents.get(at) != Some(&b',') {
+                return None;
+            }
+            at = skip_whitespace(at + 1);
+        }
+    }
+    if contents.get(at) != Some(&b'[') {
+        return None;
+    }
+    at = skip_whitespace(at + 1);
+    let mut spans = Vec::new();
+    while contents.get(at).is_some_and(|&byte| byte != b']') {
+        let end = value_end(at)?;
+        spans.push(at..end);
+        at = skip_whitespace(end);
+        if contents.get(at) == Some(&b',') {
+            at = skip_whitespace(at + 1);
+        }
+    }
+    Some(spans)
+}
+
+/**
+ * @brief Cut source text to a one-line snippet for messages.
+ *
+ * @param source The text, e.g. a record spanning several lines.
+ * @return String The text with runs of whitespace collapsed, cut to
+ *         SNIPPET_CHARS characters ("..." marks a cut).
+ */
+pub fn snippet(source: &str) -> String {
+    let collapsed = source.split_whitespace().collect::<Vec<_>>().join(" ");
+    match collapsed.char_indices().nth(SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}...", &collapsed[..end]),
+        None => collapsed,
+    }
+}
+
+/**
+ * @brief Return the line a byte offset of a text is on.
+ *
+ * @param contents The text.
+ * @param offset A byte offset into it.
+ * @return usize The line, counting from 1.
+ */
+pub fn line_of(contents: &[u8], offset: usize) -> usize {
+    contents[..offset.min(contents.len())].iter().filter(|&&byte| byte == b'\n').count() + 1
+}
+
+/**
+ * @brief Return a line of a text.
+ *
+ * @param contents The text.
+ * @param line The line, counting from 1.
+ * @return &str The line without its line break ("" past the end).
+ */
+pub fn source_line(contents: &str, line: usize) -> &str {
+    contents.lines().nth(line.saturating_sub(1)).unwrap_or("")
+}
+
+/**
+ * @brief Add the quoted source line to an error at a line of a data file.
+ *
+ * @param message The error message, e.g. "Failed to parse data file 'x': ... at line 3 column 5".
+ * @param contents The file contents.
+ * @param line The line the error is at, counting from 1.
+ * @return String The message followed by "; near `<line 3>`", unless the line is blank.
+ */
+pub fn with_source(message: String, contents: &str, line: usize) -> String {
+    match snippet(source_line(contents, line)) {
+        quoted if quoted.is_empty() => message,
+        quoted => format!("{}; near `{}`", message, quoted),
+    }
+}
+
+// End of rust_sample_project/src/invalid_records.rs
@@ -1,10 +1,292 @@
-// V main.rs
-mod item; // Deklaruje modul item.rs
-use crate::item::Item; // Sprístupní Item štruktúru
-
-fn main() {
-    let mut my_item = Item::new(1, String::from("Test Item"), 123.45);
-    println!("{}", my_item); // Vďaka Display trait
-    my_item.mark_as_processed();
-    println!("{:?}", my_item); // Vďaka Debug trait
-}
\ No newline at end of file
+// rust_sample_project/src/item.rs
+
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::item_id::ItemId;
+use crate::money::Money;
+
+// The status is part of the no_std core, see item_core.rs
+pub use crate::item_core::ItemStatus;
+
+/**
+ * @struct Item
+ * @brief Represents a single data item to be processed.
+ *
+ * This struct defines the structure of data objects used within the application,
+ * similar to a dataclass in Python for simplicity and type safety.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    /// A unique identifier for the item (an integer, UUID or string).
+    pub item_id: ItemId,
+    /// The name of the item, shared by items with the same name when they
+    /// were loaded with name interning (see intern.rs).
+    pub name: Arc<str>,
+    /// A monetary value associated with the item, as an exact decimal amount.
+    pub value: Money,
+    /// Where the item is in its life cycle; changed only through the
+    /// validate and mark_* methods, which enforce the allowed transitions.
+    #[serde(default)]
+    pub status: ItemStatus,
+    /// Free-form labels attached by the data source or by processing rules.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// When the item was created (defaults to load time for records without it).
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    /// When the item was marked as processed, if it has been.
+    #[serde(default)]
+    pub processed_at: Option<DateTime<Utc>>,
+    /// The data source (file path) the item was loaded from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The item's category, filled in from the enrichment lookup table.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// The item's supplier, filled in from the enrichment lookup table.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supplier: Option<String>,
+    /// The tenant the item belongs to (see the tenants of the config file);
+    /// items loaded for a tenant without one are assigned to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// What the last run tracking lineage did to the item (see the lineage config key).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<Box<Lineage>>,
+}
+
+/**
+ * @struct Lineage
+ * @brief Where an item came from and what happened to it in a run.
+ *
+ * Recorded while a run with the lineage config key handles the item; each
+ * such run replaces the lineage of the one before. The history of an item
+ * across runs is in the audit log (see the `lineage` subcommand).
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lineage {
+    /// The data source the item was loaded from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The stages that handled the item, in order.
+    #[serde(default)]
+    pub stages: Vec<String>,
+    /// The rules that changed the item, in order.
+    #[serde(default)]
+    pub rules: Vec<String>,
+}
+
+impl Item {
+    /**
+     * @brief Constructs a new Item object.
+     *
+     * @param item_id A unique identifier for the item.
+     * @param name The name of the item.
+     * @param value A monetary value associated with the item.
+     * @return Item A new Item without tags, created now.
+     */
+    pub fn new(item_id: ItemId, name: String, value: Money) -> Self {
+        Item {
+            item_id,
+            name: Arc::from(name),
+            value,
+            status: ItemStatus::New,
+            tags: Vec::new(),
+            created_at: Utc::now(),
+            processed_at: None,
+            source: None,
+            category: None,
+            supplier: None,
+            tenant: None,
+            lineage: None,
+        }
+    }
+
+    /**
+     * @brief Check whether the item has been processed successfully.
+     *
+     * @return bool True if the status is ItemStatus::Processed.
+     */
+    pub fn is_processed(&self) -> bool {
+        self.status == ItemStatus::Processed
+    }
+
+    /**
+     * @brief Select the item for processing by the current run.
+     *
+     * Allowed from every status; an item processed by an earlier run loses
+     * its processed_at until it is processed again. Does nothing if the item
+     * is already validated.
+     */
+    pub fn validate(&mut self) {
+        if self.status != ItemStatus::Validated {
+            self.status = ItemStatus::Validated;
+            self.processed_at = None;
+        }
+    }
+
+    /**
+     * @brief Mark a validated item as processed and record the time in processed_at.
+     *
+     * @return Result<(), String> Ok, or an error message if the item was not validated.
+     */
+    pub fn mark_as_processed(&mut self) -> Result<(), String> {
+        self.transition(ItemStatus::Processed)?;
+        self.processed_at = Some(Utc::now());
+        Ok(())
+    }
+
+    /**
+     * @brief Mark a new or validated item as failed.
+     *
+     * @return Result<(), String> Ok, or an error message for items in another status.
+     */
+    pub fn mark_failed(&mut self) -> Result<(), String> {
+        self.transition(ItemStatus::Failed)
+    }
+
+    /**
+     * @brief Mark a new or validated item as skipped.
+     *
+     * @return Result<(), String> Ok, or an error message for items in another status.
+     */
+    pub fn mark_skipped(&mut self) -> Result<(), String> {
+        self.transition(ItemStatus::Skipped)
+    }
+
+    /**
+     * @brief Move the item to another status if the transition is allowed.
+     */
+    fn transition(&mut self, next: ItemStatus) -> Result<(), String> {
+        if !self.status.can_become(next) {
+            return Err(format!(
+                "Item ID {}: cannot change status from {} to {}",
+                self.item_id, self.status, next
+            ));
+        }
+        self.status = next;
+        Ok(())
+    }
+
+    /**
+     * @brief Attach a tag to the item, unless it is already present.
+     *
+     * @param tag The tag to add.
+     * @return bool True if the tag was added, false if the item already had it.
+     */
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        if self.has_tag(tag) {
+            return false;
+        }
+        self.tags.push(tag.to_string());
+        true
+    }
+
+    /**
+     * @brief Detach a tag from the item, if it is present.
+     *
+     * @param tag The tag to remove.
+     * @return bool True if the tag was removed, false if the item did not have it.
+     */
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let count = self.tags.len();
+        self.tags.retain(|t| t != tag);
+        self.tags.len() != count
+    }
+
+    /**
+     * @brief Start recording the item's lineage for the current run.
+     *
+     * Replaces the lineage recorded by an earlier run.
+     */
+    pub fn start_lineage(&mut self) {
+        self.lineage = Some(Box::new(Lineage {
+            source: self.source.clone(),
+            ..Lineage::default()
+        }));
+    }
+
+    /**
+     * @brief Record that a stage handled the item, if its lineage is tracked.
+     *
+     * @param stage The name of the stage.
+     */
+    pub fn record_stage(&mut self, stage: &str) {
+        if let Some(lineage) = &mut self.lineage {
+            lineage.stages.push(stage.to_string());
+        }
+    }
+
+    /**
+     * @brief Record that a rule changed the item, if its lineage is tracked.
+     *
+     * @param rule The rule, as configured.
+     */
+    pub fn record_rule(&mut self, rule: &str) {
+        if let Some(lineage) = &mut self.lineage {
+            lineage.rules.push(rule.to_string());
+        }
+    }
+
+    /**
+     * @brief Check whether the item carries the given tag.
+     *
+     * @param tag The tag to look for.
+     * @return bool True if the item has the tag.
+     */
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /**
+     * @brief Check the invariants every item must satisfy, whatever stage of
+     *        the pipeline it is in.
+     *
+     * processed_at is only set on processed items, and tags are non-empty and
+     * unique. (The value is a Money amount, which is always finite.) Custom processors and storage
+     * backends can assert these on their output (see the `test-util` feature
+     * for matching proptest strategies).
+     *
+     * @return Result<(), String> Ok, or a message describing the first violation.
+     */
+    pub fn invariants(&self) -> Result<(), String> {
+        if self.processed_at.is_some() && !self.is_processed() {
+            return Err(format!("Item ID {}: has processed_at but is not processed", self.item_id));
+        }
+        for (index, tag) in self.tags.iter().enumerate() {
+            if tag.is_empty() {
+                return Err(format!("Item ID {}: has an empty tag", self.item_id));
+            }
+            if self.tags[..index].contains(tag) {
+                return Err(format!("Item ID {}: has tag '{}' more than once", self.item_id, tag));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Item {
+    /**
+     * @brief Format a user-friendly representation of the item.
+     *
+     * Produces e.g.
+     * `Item(ID=1, Name='Gadget', Value=150.75, Status=Processed, Tags=[over_threshold])`.
+     */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Item(ID={}, Name='{}', Value={}, Status={:?}, Tags=[{}])",
+            self.item_id,
+            self.name,
+            self.value,
+            self.status,
+            self.tags.join(", ")
+        )
+    }
+}
+
+// End of rust_sample_project/src/item.rs
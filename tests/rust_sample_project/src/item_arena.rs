@@ -0,0 +1,107 @@
+// rust_sample_project/src/item_arena.rs
+
+// Arena-backed reading of a streamed NDJSON data file (see the `arena`
+// feature and the stream_arena_batch config key). Line by line, every line
+// and every parsed item is a handful of separate heap allocations, freed one
+// by one a moment later. In arena mode a batch of lines is read into one
+// bump arena, the items are parsed as ItemRef views into it (see
+// item_ref.rs), and the whole batch is freed at once by resetting the arena,
+// which keeps its memory for the next batch. Only items that go through the
+// processor are copied into owned Items.
+
+use std::io::BufRead;
+
+use bumpalo::collections::Vec as ArenaVec;
+use bumpalo::Bump;
+
+/**
+ * @struct LineArena
+ * @brief Reads lines in batches into a reused bump arena.
+ */
+pub struct LineArena {
+    /// Holds the lines of the current batch.
+    bump: Bump,
+    /// Lines per batch.
+    batch_lines: usize,
+    /// Buffer each line is read into before it is copied into the arena.
+    buffer: String,
+    /// Largest amount of memory the arena held for one batch.
+    peak_bytes: usize,
+}
+
+impl LineArena {
+    /**
+     * @brief Constructs an arena for batches of the given number of lines.
+     *
+     * @param batch_lines Lines per batch (at least one).
+     * @return LineArena The arena, still empty.
+     */
+    pub fn new(batch_lines: usize) -> Self {
+        LineArena {
+            bump: Bump::new(),
+            batch_lines: batch_lines.max(1),
+            buffer: String::new(),
+            peak_bytes: 0,
+        }
+    }
+
+    /**
+     * @brief Hand every line of a reader to a handler, a batch at a time.
+     *
+     * Line endings ("\n" or "\r\n") are stripped, as BufRead::lines does.
+     * The lines of a batch are valid until the handler has seen the whole
+     * batch; then the arena is reset.
+     *
+     * @param reader The input.
+     * @param location Path of the input, used in messages.
+     * @param handle Called with each line and its 1-based line number.
+     * @return Result<(), String> Ok at the end of the input, or the first
+     *         read or handler error.
+     */
+    pub fn for_each_line<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        location: &str,
+        mut handle: impl FnMut(&str, usize) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut line_number = 0;
+        loop {
+            let mut lines = ArenaVec::with_capacity_in(self.batch_lines, &self.bump);
+            let mut at_end = false;
+            while lines.len() < self.batch_lines {
+                self.buffer.clear();
+                let read = reader
+                    .read_line(&mut self.buffer)
+                    .map_err(|e| format!("Failed to read data file '{}': {}", location, e))?;
+                if read == 0 {
+                    at_end = true;
+                    break;
+                }
+                line_number += 1;
+                let line = self.buffer.strip_suffix('\n').unwrap_or(&self.buffer);
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                lines.push((line_number, &*self.bump.alloc_str(line)));
+            }
+            for (number, line) in lines.iter() {
+                handle(line, *number)?;
+            }
+            drop(lines);
+            self.peak_bytes = self.peak_bytes.max(self.bump.allocated_bytes());
+            self.bump.reset();
+            if at_end {
+                return Ok(());
+            }
+        }
+    }
+
+    /**
+     * @brief Return the most memory the arena held for one batch.
+     *
+     * @return usize The size in bytes of the arena's chunks at its largest.
+     */
+    pub fn peak_bytes(&self) -> usize {
+        self.peak_bytes
+    }
+}
+
+// End of rust_sample_project/src/item_arena.rs
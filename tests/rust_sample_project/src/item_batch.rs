@@ -0,0 +1,307 @@
+// rust_sample_project/src/item_batch.rs
+
+// A columnar (struct-of-arrays) copy of a list of items, for analytics over
+// whole datasets (see the analytics config key and ValueStats in report.rs).
+// An Item keeps its value next to its name, tags and timestamps, so a pass
+// over the values of a Vec<Item> touches every field's cache lines; in an
+// ItemBatch each field is its own column, and the values are one contiguous
+// f64 column that aggregations stream through (and the compiler vectorizes).
+// Items convert into a batch and back without loss; the over-threshold flags
+// are derived from the tags and need no converting back. The columns are only
+// filled by push, which keeps them the same length, and are read through
+// accessors.
+
+use std::sync::Arc;
+
+use bitvec::vec::BitVec;
+use chrono::{DateTime, Utc};
+
+use crate::item::{Item, ItemStatus, Lineage};
+use crate::item_core::TAG_OVER_THRESHOLD;
+use crate::item_id::ItemId;
+use crate::money::Money;
+
+/**
+ * @struct ItemBatch
+ * @brief Items stored as one column per field; row i of every column is item i.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemBatch {
+    /// The item ids.
+    ids: Vec<ItemId>,
+    /// The item names (shared with the items, see intern.rs).
+    names: Vec<Arc<str>>,
+    /// The values as f64, for aggregation.
+    values: Vec<f64>,
+    /// The exact values, for converting back to items.
+    amounts: Vec<Money>,
+    /// Set for items tagged TAG_OVER_THRESHOLD.
+    over_threshold: BitVec,
+    /// The item statuses.
+    statuses: Vec<ItemStatus>,
+    /// The tags of each item.
+    tags: Vec<Vec<String>>,
+    /// When each item was created.
+    created_at: Vec<DateTime<Utc>>,
+    /// When each item was processed, if it was.
+    processed_at: Vec<Option<DateTime<Utc>>>,
+    /// The data source of each item.
+    sources: Vec<Option<String>>,
+    /// The category of each item.
+    categories: Vec<Option<String>>,
+    /// The supplier of each item.
+    suppliers: Vec<Option<String>>,
+    /// The tenant of each item.
+    tenants: Vec<Option<String>>,
+    /// The lineage of each item, if tracked.
+    lineages: Vec<Option<Box<Lineage>>>,
+}
+
+impl ItemBatch {
+    /**
+     * @brief Constructs an empty batch with room for the given number of items.
+     *
+     * @param capacity The number of items to reserve room for.
+     * @return ItemBatch The batch.
+     */
+    pub fn with_capacity(capacity: usize) -> Self {
+        ItemBatch {
+            ids: Vec::with_capacity(capacity),
+            names: Vec::with_capacity(capacity),
+            values: Vec::with_capacity(capacity),
+            amounts: Vec::with_capacity(capacity),
+            over_threshold: BitVec::with_capacity(capacity),
+            statuses: Vec::with_capacity(capacity),
+            tags: Vec::with_capacity(capacity),
+            created_at: Vec::with_capacity(capacity),
+            processed_at: Vec::with_capacity(capacity),
+            sources: Vec::with_capacity(capacity),
+            categories: Vec::with_capacity(capacity),
+            suppliers: Vec::with_capacity(capacity),
+            tenants: Vec::with_capacity(capacity),
+            lineages: Vec::with_capacity(capacity),
+        }
+    }
+
+    /**
+     * @brief Append an item as the last row.
+     *
+     * @param item The item, moved into the columns.
+     */
+    pub fn push(&mut self, item: Item) {
+        self.ids.push(item.item_id);
+        self.names.push(item.name);
+        self.values.push(item.value.to_f64());
+        self.amounts.push(item.value);
+        self.over_threshold.push(item.tags.iter().any(|tag| tag == TAG_OVER_THRESHOLD));
+        self.statuses.push(item.status);
+        self.tags.push(item.tags);
+        self.created_at.push(item.created_at);
+        self.processed_at.push(item.processed_at);
+        self.sources.push(item.source);
+        self.categories.push(item.category);
+        self.suppliers.push(item.supplier);
+        self.tenants.push(item.tenant);
+        self.lineages.push(item.lineage);
+    }
+
+    /**
+     * @brief Return the number of items in the batch.
+     *
+     * @return usize The number of rows.
+     */
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /**
+     * @brief Return whether the batch holds no items.
+     *
+     * @return bool True if there are no rows.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /**
+     * @brief Return the item ids, one per row.
+     *
+     * @return &[ItemId] The id column.
+     */
+    pub fn ids(&self) -> &[ItemId] {
+        &self.ids
+    }
+
+    /**
+     * @brief Return the item names, one per row.
+     *
+     * @return &[Arc<str>] The name column.
+     */
+    pub fn names(&self) -> &[Arc<str>] {
+        &self.names
+    }
+
+    /**
+     * @brief Return the values as f64, one per row, for aggregation.
+     *
+     * @return &[f64] The value column.
+     */
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /**
+     * @brief Return the exact values, one per row.
+     *
+     * @return &[Money] The amount column.
+     */
+    pub fn amounts(&self) -> &[Money] {
+        &self.amounts
+    }
+
+    /**
+     * @brief Return which rows are tagged TAG_OVER_THRESHOLD.
+     *
+     * @return &BitVec One bit per row.
+     */
+    pub fn over_threshold(&self) -> &BitVec {
+        &self.over_threshold
+    }
+
+    /**
+     * @brief Return the item statuses, one per row.
+     *
+     * @return &[ItemStatus] The status column.
+     */
+    pub fn statuses(&self) -> &[ItemStatus] {
+        &self.statuses
+    }
+
+    /**
+     * @brief Return the tags of each item, one list per row.
+     *
+     * @return &[Vec<String>] The tag column.
+     */
+    pub fn tags(&self) -> &[Vec<String>] {
+        &self.tags
+    }
+
+    /**
+     * @brief Convert the batch back into items, in row order.
+     *
+     * @return Vec<Item> The items, as they were put into the batch.
+     */
+    pub fn into_items(self) -> Vec<Item> {
+        let mut items = Vec::with_capacity(self.len());
+        let mut names = self.names.into_iter();
+        let mut amounts = self.amounts.into_iter();
+        let mut statuses = self.statuses.into_iter();
+        let mut tags = self.tags.into_iter();
+        let mut created_at = self.created_at.into_iter();
+        let mut processed_at = self.processed_at.into_iter();
+        let mut sources = self.sources.into_iter();
+        let mut categories = self.categories.into_iter();
+        let mut suppliers = self.suppliers.into_iter();
+        let mut tenants = self.tenants.into_iter();
+        let mut lineages = self.lineages.into_iter();
+        for item_id in self.ids {
+            // Every column has one entry per id, as push keeps them in step
+            let column = "the columns of an ItemBatch have the same length";
+            items.push(Item {
+                item_id,
+                name: names.next().expect(column),
+                value: amounts.next().expect(column),
+                status: statuses.next().expect(column),
+                tags: tags.next().expect(column),
+                created_at: created_at.next().expect(column),
+                processed_at: processed_at.next().expect(column),
+                source: sources.next().expect(column),
+                category: categories.next().expect(column),
+                supplier: suppliers.next().expect(column),
+                tenant: tenants.next().expect(column),
+                lineage: lineages.next().expect(column),
+            });
+        }
+        items
+    }
+}
+
+impl From<Vec<Item>> for ItemBatch {
+    /**
+     * @brief Move items into a batch.
+     */
+    fn from(items: Vec<Item>) -> Self {
+        let mut batch = ItemBatch::with_capacity(items.len());
+        for item in items {
+            batch.push(item);
+        }
+        batch
+    }
+}
+
+impl From<&[Item]> for ItemBatch {
+    /**
+     * @brief Copy items into a batch (the names are shared, not copied).
+     */
+    fn from(items: &[Item]) -> Self {
+        let mut batch = ItemBatch::with_capacity(items.len());
+        for item in items {
+            batch.push(item.clone());
+        }
+        batch
+    }
+}
+
+impl From<ItemBatch> for Vec<Item> {
+    /**
+     * @brief Convert a batch back into items, see ItemBatch::into_items.
+     */
+    fn from(batch: ItemBatch) -> Self {
+        batch.into_items()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item_core::TAG_UNDER_THRESHOLD;
+
+    fn items() -> Vec<Item> {
+        let mut items: Vec<Item> = (1..=3)
+            .map(|id| Item::new(ItemId::from(id), format!("Item {}", id), Money::from_minor(id * 7_550, 2)))
+            .collect();
+        items[0].tags.push(String::from(TAG_OVER_THRESHOLD));
+        items[1].tags.push(String::from(TAG_UNDER_THRESHOLD));
+        items[2].tags.extend([String::from("fragile"), String::from(TAG_OVER_THRESHOLD)]);
+        items[2].status = ItemStatus::Processed;
+        items[2].processed_at = Some(items[2].created_at);
+        items[2].category = Some(String::from("tools"));
+        items
+    }
+
+    #[test]
+    fn items_convert_into_a_batch_and_back_unchanged() {
+        let items = items();
+
+        let batch = ItemBatch::from(&items[..]);
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch.ids(), &[ItemId::from(1), ItemId::from(2), ItemId::from(3)]);
+        assert_eq!(batch.values(), &[75.5, 151.0, 226.5]);
+        assert_eq!(batch.amounts()[2], Money::parse("226.50").unwrap());
+
+        assert_eq!(Vec::<Item>::from(batch), items);
+        assert_eq!(ItemBatch::from(items.clone()).into_items(), items);
+        assert!(ItemBatch::from(Vec::new()).into_items().is_empty());
+    }
+
+    #[test]
+    fn over_threshold_marks_the_rows_tagged_over_the_threshold() {
+        let batch = ItemBatch::from(items());
+
+        let marked: Vec<bool> = batch.over_threshold().iter().map(|bit| *bit).collect();
+        assert_eq!(marked, [true, false, true]);
+        assert_eq!(batch.over_threshold().count_ones(), 2);
+    }
+}
+
+// End of rust_sample_project/src/item_batch.rs
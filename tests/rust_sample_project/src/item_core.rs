@@ -0,0 +1,366 @@
+// rust_sample_project/src/item_core.rs
+
+// The item model and processing rules without std (no_std + alloc), so the
+// threshold logic of ItemProcessor can run on an embedded gateway that
+// collects item readings. ItemProcessor uses the same ThresholdRules, so a
+// reading and an item with the same value, category and tags get the same
+// threshold and tag. Build the core alone without default features, e.g.
+//
+//     [dependencies]
+//     rust_sample_project = { path = "...", default-features = false }
+//
+// for a target such as thumbv7em-none-eabihf; the firmware provides the
+// global allocator. Everything else (Item with its timestamps and ids, the
+// processor with transformations, the pipeline) needs the `std` feature.
+//
+//     let rules = ThresholdRules::new(100.0).with_tag_thresholds(vec![(String::from("fragile"), 50.0)]);
+//     let mut reading = ItemReading::new(String::from("sensor-7"), Money::from_minor(12050, 2));
+//     match rules.apply(&mut reading) { ... }
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::money::Money;
+
+/**
+ * @brief Tag added to items whose value exceeds the threshold.
+ */
+pub const TAG_OVER_THRESHOLD: &str = "over_threshold";
+
+/**
+ * @brief Tag added to items whose value is within the threshold.
+ */
+pub const TAG_UNDER_THRESHOLD: &str = "under_threshold";
+
+/**
+ * @enum ItemStatus
+ * @brief Where an item is in its life cycle, written to data files as
+ *        "new", "validated", "processed", "failed" or "skipped".
+ *
+ * Allowed transitions:
+ *
+ * ```text
+ * new -> validated | skipped | failed
+ * validated -> processed | failed | skipped
+ * processed | failed | skipped -> validated   (taken up again by a later run)
+ * ```
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    /// Loaded, not yet looked at by a run.
+    #[default]
+    New,
+    /// Selected for processing by the current run.
+    Validated,
+    /// Processed successfully.
+    Processed,
+    /// Processing failed.
+    Failed,
+    /// Left out of processing (by the filter, tag rules, lookup table or a hook).
+    Skipped,
+}
+
+impl ItemStatus {
+    /// All statuses, in life-cycle order.
+    pub const ALL: [ItemStatus; 5] = [
+        ItemStatus::New,
+        ItemStatus::Validated,
+        ItemStatus::Processed,
+        ItemStatus::Failed,
+        ItemStatus::Skipped,
+    ];
+
+    /**
+     * @brief Parse a status name as written in data files.
+     *
+     * @param name One of "new", "validated", "processed", "failed" or "skipped".
+     * @return Result<ItemStatus, String> The status, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<ItemStatus, String> {
+        ItemStatus::ALL
+            .into_iter()
+            .find(|status| status.name() == name)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown item status '{}' (expected 'new', 'validated', 'processed', 'failed' or 'skipped')",
+                    name
+                )
+            })
+    }
+
+    /**
+     * @brief Return the status name as written in data files.
+     *
+     * @return &'static str The name, e.g. "processed".
+     */
+    pub fn name(self) -> &'static str {
+        match self {
+            ItemStatus::New => "new",
+            ItemStatus::Validated => "validated",
+            ItemStatus::Processed => "processed",
+            ItemStatus::Failed => "failed",
+            ItemStatus::Skipped => "skipped",
+        }
+    }
+
+    /**
+     * @brief Check whether an item may move from this status to another.
+     *
+     * @param next The status to move to.
+     * @return bool True if the transition is allowed.
+     */
+    pub fn can_become(self, next: ItemStatus) -> bool {
+        matches!(
+            (self, next),
+            (ItemStatus::New, ItemStatus::Validated | ItemStatus::Skipped | ItemStatus::Failed)
+                | (ItemStatus::Validated, ItemStatus::Processed | ItemStatus::Failed | ItemStatus::Skipped)
+                | (ItemStatus::Processed | ItemStatus::Failed | ItemStatus::Skipped, ItemStatus::Validated)
+        )
+    }
+}
+
+impl fmt::Display for ItemStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/**
+ * @struct ItemReading
+ * @brief An item as collected by a gateway: the fields the rules look at.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemReading {
+    /// The item's id, as text.
+    pub item_id: String,
+    /// The value read.
+    pub value: Money,
+    #[serde(default)]
+    pub status: ItemStatus,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+impl ItemReading {
+    /**
+     * @brief Constructs a new reading without tags or category.
+     *
+     * @param item_id The item's id.
+     * @param value The value read.
+     * @return ItemReading A reading in status new.
+     */
+    pub fn new(item_id: String, value: Money) -> Self {
+        ItemReading {
+            item_id,
+            value,
+            status: ItemStatus::New,
+            tags: Vec::new(),
+            category: None,
+        }
+    }
+}
+
+/**
+ * @enum RuleOutcome
+ * @brief What ThresholdRules::apply did with a reading.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleOutcome {
+    /// Not selected by the tag rules; marked skipped.
+    SkippedByTag,
+    /// Processed; the value exceeds its threshold.
+    OverThreshold,
+    /// Processed; the value is within its threshold.
+    UnderThreshold,
+}
+
+/**
+ * @struct ThresholdRules
+ * @brief The thresholds and tag rules of ItemProcessor.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThresholdRules {
+    /// The threshold for items matching no category or tag threshold.
+    threshold: f64,
+    /// Thresholds replacing `threshold` for items of these categories.
+    category_thresholds: BTreeMap<String, f64>,
+    /// Thresholds replacing `threshold` for items carrying these tags.
+    tag_thresholds: Vec<(String, f64)>,
+    /// Only items carrying at least one of these tags are selected (empty = all items).
+    required_tags: Vec<String>,
+    /// Items carrying any of these tags are never selected.
+    excluded_tags: Vec<String>,
+}
+
+impl ThresholdRules {
+    /**
+     * @brief Constructs rules with a single threshold that select every item.
+     *
+     * @param threshold The threshold.
+     * @return ThresholdRules The rules.
+     */
+    pub fn new(threshold: f64) -> Self {
+        ThresholdRules {
+            threshold,
+            category_thresholds: BTreeMap::new(),
+            tag_thresholds: Vec::new(),
+            required_tags: Vec::new(),
+            excluded_tags: Vec::new(),
+        }
+    }
+
+    /**
+     * @brief Use different thresholds for some categories.
+     *
+     * @param category_thresholds (category, threshold) pairs.
+     * @return ThresholdRules The rules with the thresholds applied.
+     */
+    pub fn with_category_thresholds(mut self, category_thresholds: Vec<(String, f64)>) -> Self {
+        self.category_thresholds = category_thresholds.into_iter().collect();
+        self
+    }
+
+    /**
+     * @brief Use different thresholds for items carrying some tags.
+     *
+     * @param tag_thresholds (tag, threshold) pairs.
+     * @return ThresholdRules The rules with the thresholds applied.
+     */
+    pub fn with_tag_thresholds(mut self, tag_thresholds: Vec<(String, f64)>) -> Self {
+        self.tag_thresholds = tag_thresholds;
+        self
+    }
+
+    /**
+     * @brief Restrict which items are selected, based on their tags.
+     *
+     * @param required_tags Select only items with at least one of these tags
+     *                      (an empty list selects every item).
+     * @param excluded_tags Never select items with any of these tags.
+     * @return ThresholdRules The rules with the tag rules applied.
+     */
+    pub fn with_tag_rules(mut self, required_tags: Vec<String>, excluded_tags: Vec<String>) -> Self {
+        self.required_tags = required_tags;
+        self.excluded_tags = excluded_tags;
+        self
+    }
+
+    /**
+     * @brief Tell whether every item gets the same threshold.
+     *
+     * @return bool True if there are no category or tag thresholds.
+     */
+    pub fn is_uniform(&self) -> bool {
+        self.category_thresholds.is_empty() && self.tag_thresholds.is_empty()
+    }
+
+    /**
+     * @brief Return the threshold that applies to an item.
+     *
+     * An item's category threshold takes precedence over its tag thresholds;
+     * if it carries several tags with a threshold, the lowest one applies.
+     *
+     * @param category The item's category.
+     * @param tags The item's tags.
+     * @return f64 Its category threshold, else the lowest of its tag
+     *         thresholds, else the default threshold.
+     */
+    pub fn threshold_for(&self, category: Option<&str>, tags: &[String]) -> f64 {
+        if let Some(threshold) = category.and_then(|category| self.category_thresholds.get(category)) {
+            return *threshold;
+        }
+        self.tag_thresholds
+            .iter()
+            .filter(|(tag, _)| tags.contains(tag))
+            .map(|(_, threshold)| *threshold)
+            .reduce(f64::min)
+            .unwrap_or(self.threshold)
+    }
+
+    /**
+     * @brief Decide whether an item is selected by the tag rules.
+     *
+     * @param tags The item's tags.
+     * @return bool True if the item passes the required and excluded tag rules.
+     */
+    pub fn is_selected(&self, tags: &[String]) -> bool {
+        if self.excluded_tags.iter().any(|tag| tags.contains(tag)) {
+            return false;
+        }
+        self.required_tags.is_empty() || self.required_tags.iter().any(|tag| tags.contains(tag))
+    }
+
+    /**
+     * @brief Return the tag for a value checked against a threshold.
+     *
+     * @param value The value.
+     * @param threshold The threshold that applies, as an amount (see
+     *        Money::from_f64_saturating), so the comparison is exact.
+     * @return &'static str TAG_OVER_THRESHOLD if the value exceeds the
+     *         threshold, else TAG_UNDER_THRESHOLD.
+     */
+    pub fn threshold_tag(value: Money, threshold: Money) -> &'static str {
+        if value > threshold {
+            TAG_OVER_THRESHOLD
+        } else {
+            TAG_UNDER_THRESHOLD
+        }
+    }
+
+    /**
+     * @brief Return the other threshold tag, which an item tagged with this one must not carry.
+     *
+     * @param tag TAG_OVER_THRESHOLD or TAG_UNDER_THRESHOLD.
+     * @return &'static str TAG_UNDER_THRESHOLD for TAG_OVER_THRESHOLD, else TAG_OVER_THRESHOLD.
+     */
+    pub fn opposite_tag(tag: &str) -> &'static str {
+        if tag == TAG_OVER_THRESHOLD {
+            TAG_UNDER_THRESHOLD
+        } else {
+            TAG_OVER_THRESHOLD
+        }
+    }
+
+    /**
+     * @brief Apply the rules to a reading: tag it and mark it processed, or
+     *        mark it skipped if the tag rules do not select it.
+     *
+     * Readings that arrived already settled keep their status when skipped. A
+     * threshold tag from an earlier check is replaced by the current one.
+     *
+     * @param reading The reading, updated in place.
+     * @return RuleOutcome What was done.
+     */
+    pub fn apply(&self, reading: &mut ItemReading) -> RuleOutcome {
+        if !self.is_selected(&reading.tags) {
+            if reading.status.can_become(ItemStatus::Skipped) {
+                reading.status = ItemStatus::Skipped;
+            }
+            return RuleOutcome::SkippedByTag;
+        }
+        let threshold = self.threshold_for(reading.category.as_deref(), &reading.tags);
+        let tag = Self::threshold_tag(reading.value, Money::from_f64_saturating(threshold));
+        let stale_tag = Self::opposite_tag(tag);
+        reading.tags.retain(|t| t != stale_tag);
+        if !reading.tags.iter().any(|t| t == tag) {
+            reading.tags.push(tag.to_string());
+        }
+        reading.status = ItemStatus::Processed;
+        if tag == TAG_OVER_THRESHOLD {
+            RuleOutcome::OverThreshold
+        } else {
+            RuleOutcome::UnderThreshold
+        }
+    }
+}
+
+// End of rust_sample_project/src/item_core.rs
@@ -0,0 +1,223 @@
+// rust_sample_project/src/item_id.rs
+
+// Item identifiers. An id is an integer, a UUID or an arbitrary string;
+// which kind the data sources use is set process-wide with
+// set_expected_kind (config key item_id_kind), and records whose id is of
+// another kind are rejected when they are deserialized. Integer ids are
+// written as JSON numbers, the others as strings.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering as AtomicOrdering};
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use uuid::Uuid;
+
+static EXPECTED_KIND: AtomicU8 = AtomicU8::new(IdKind::Int as u8);
+
+/**
+ * @enum IdKind
+ * @brief The kind of item id the data sources use.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IdKind {
+    /// 64-bit integers ("int").
+    Int,
+    /// UUIDs in their usual hyphenated form ("uuid").
+    Uuid,
+    /// Any text; numbers are taken as their decimal text ("string").
+    Str,
+}
+
+impl IdKind {
+    /**
+     * @brief Parse an id kind name as used in config.
+     *
+     * @param name One of "int", "uuid" or "string".
+     * @return Result<IdKind, String> The kind, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<IdKind, String> {
+        match name {
+            "int" => Ok(IdKind::Int),
+            "uuid" => Ok(IdKind::Uuid),
+            "string" => Ok(IdKind::Str),
+            other => Err(format!(
+                "Unknown item id kind '{}' (expected 'int', 'uuid' or 'string')",
+                other
+            )),
+        }
+    }
+}
+
+/**
+ * @brief Set the kind of id accepted when items are deserialized.
+ *
+ * @param kind The expected id kind.
+ */
+pub fn set_expected_kind(kind: IdKind) {
+    EXPECTED_KIND.store(kind as u8, AtomicOrdering::Relaxed);
+}
+
+/**
+ * @brief Return the kind of id accepted when items are deserialized.
+ *
+ * @return IdKind The expected id kind (IdKind::Int unless set).
+ */
+pub fn expected_kind() -> IdKind {
+    match EXPECTED_KIND.load(AtomicOrdering::Relaxed) {
+        kind if kind == IdKind::Uuid as u8 => IdKind::Uuid,
+        kind if kind == IdKind::Str as u8 => IdKind::Str,
+        _ => IdKind::Int,
+    }
+}
+
+/**
+ * @enum ItemId
+ * @brief The identifier of an item.
+ *
+ * Ids of different kinds never compare equal; they order integers first,
+ * then UUIDs, then strings.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ItemId {
+    /// An integer id.
+    Int(i64),
+    /// A UUID.
+    Uuid(Uuid),
+    /// A string id.
+    Str(String),
+}
+
+impl ItemId {
+    /**
+     * @brief Parse an id of the given kind from its text form.
+     *
+     * @param text The id, e.g. "42" or "67e55044-10b1-426f-9247-bb680e5fe0c8".
+     * @param kind The kind the id must be of.
+     * @return Result<ItemId, String> The id, or an error message if the text
+     *         is not an id of that kind.
+     */
+    pub fn parse(text: &str, kind: IdKind) -> Result<ItemId, String> {
+        match kind {
+            IdKind::Int => text
+                .trim()
+                .parse()
+                .map(ItemId::Int)
+                .map_err(|_| format!("Invalid item id '{}' (expected an integer)", text)),
+            IdKind::Uuid => Uuid::parse_str(text.trim())
+                .map(ItemId::Uuid)
+                .map_err(|_| format!("Invalid item id '{}' (expected a UUID)", text)),
+            IdKind::Str if text.is_empty() => Err(String::from("Item id must not be empty")),
+            IdKind::Str => Ok(ItemId::Str(text.to_string())),
+        }
+    }
+
+    /**
+     * @brief Return the kind of the id.
+     *
+     * @return IdKind The kind.
+     */
+    pub fn kind(&self) -> IdKind {
+        match self {
+            ItemId::Int(_) => IdKind::Int,
+            ItemId::Uuid(_) => IdKind::Uuid,
+            ItemId::Str(_) => IdKind::Str,
+        }
+    }
+
+    /**
+     * @brief Return an integer id as a float, e.g. for arithmetic in transformations.
+     *
+     * @return Option<f64> The id, or None for UUID and string ids.
+     */
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            ItemId::Int(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+}
+
+impl From<i64> for ItemId {
+    fn from(id: i64) -> Self {
+        ItemId::Int(id)
+    }
+}
+
+impl From<Uuid> for ItemId {
+    fn from(id: Uuid) -> Self {
+        ItemId::Uuid(id)
+    }
+}
+
+impl fmt::Display for ItemId {
+    /**
+     * @brief Format the id as written in data files, e.g. `42` or a hyphenated UUID.
+     */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemId::Int(n) => write!(f, "{}", n),
+            ItemId::Uuid(uuid) => write!(f, "{}", uuid.hyphenated()),
+            ItemId::Str(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+impl Serialize for ItemId {
+    /**
+     * @brief Serialize integer ids as numbers and all others as strings.
+     */
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ItemId::Int(n) => serializer.serialize_i64(*n),
+            other => serializer.collect_str(other),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ItemId {
+    /**
+     * @brief Deserialize from a number or a string, as an id of the expected kind.
+     */
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ItemIdVisitor(expected_kind()))
+    }
+}
+
+/**
+ * @struct ItemIdVisitor
+ * @brief Accepts the representations of an id of one kind found in data files.
+ */
+struct ItemIdVisitor(IdKind);
+
+impl Visitor<'_> for ItemIdVisitor {
+    type Value = ItemId;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            IdKind::Int => write!(f, "an integer item id"),
+            IdKind::Uuid => write!(f, "a UUID item id"),
+            IdKind::Str => write!(f, "a string item id"),
+        }
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<ItemId, E> {
+        match self.0 {
+            IdKind::Uuid => Err(E::invalid_type(de::Unexpected::Signed(value), &self)),
+            kind => ItemId::parse(&value.to_string(), kind).map_err(E::custom),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<ItemId, E> {
+        match self.0 {
+            IdKind::Uuid => Err(E::invalid_type(de::Unexpected::Unsigned(value), &self)),
+            kind => ItemId::parse(&value.to_string(), kind).map_err(E::custom),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<ItemId, E> {
+        ItemId::parse(value, self.0).map_err(E::custom)
+    }
+}
+
+// End of rust_sample_project/src/item_id.rs
@@ -1,16 +1,78 @@
 // rust_sample_project/src/item_processor.rs
 
-use std::io::{self, Write}; // For println, if not using a logging crate
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "native")]
+use std::sync::{mpsc, Arc};
+#[cfg(feature = "native")]
+use std::thread;
+use std::time::Duration;
 
+use serde_json::json;
+
+#[cfg(feature = "native")]
+use crate::audit::AuditLog;
+use crate::audit::{AuditChange, AuditEvent};
 // Import Item struct from the item module
 use crate::item::Item;
+use crate::classify::{self, Thresholds};
+use crate::item_core::ThresholdRules;
+pub use crate::item_core::{TAG_OVER_THRESHOLD, TAG_UNDER_THRESHOLD};
+use crate::money::{Money, DEFAULT_DECIMALS, MAX_DECIMALS};
+use crate::transform::Transformation;
+
+/**
+ * @enum ProcessError
+ * @brief Why processing an item failed.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessError {
+    /// A transformation failed for the item (details are logged by the processor).
+    Failed,
+    /// Processing did not finish within the per-item timeout.
+    Timeout(Duration),
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessError::Failed => write!(f, "processing failed"),
+            ProcessError::Timeout(limit) => write!(f, "Timeout: processing took longer than {:?}", limit),
+        }
+    }
+}
+
+/**
+ * @struct Simulation
+ * @brief What processing would do to an item, see ItemProcessor::simulate_item.
+ */
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    /// The item with the rules applied, but not marked processed.
+    pub item: Item,
+    /// The changes the rules made (value adjustments and tags added or removed).
+    pub changes: Vec<AuditEvent>,
+    /// Why the rules failed for the item, if they did.
+    pub error: Option<String>,
+}
 
 /**
  * @struct ItemProcessor
  * @brief Processes individual Item objects based on configured rules.
  */
 pub struct ItemProcessor {
-    threshold: i32,
+    /// The thresholds and tag rules, shared with the no_std core (see item_core.rs).
+    rules: ThresholdRules,
+    /// Value adjustments applied, in order, before the threshold check.
+    transformations: Vec<Transformation>,
+    /// Decimal places item values and transformation results are rounded to.
+    value_decimals: u32,
+    /// Longest time a single item may take (None = no limit).
+    #[cfg(feature = "native")]
+    timeout: Option<Duration>,
+    /// Where the changes made to items are recorded (None = not recorded).
+    #[cfg(feature = "native")]
+    audit_log: Option<Arc<AuditLog>>,
     // A proper logger instance would be used in a real application.
 }
 
@@ -24,56 +86,527 @@ impl ItemProcessor {
      *                  threshold might be handled differently.
      * @return ItemProcessor A new ItemProcessor instance.
      */
-    pub fn new(threshold: i32) -> Self {
+    pub fn new(threshold: f64) -> Self {
         // For demonstration, mirroring Python's direct logging.
         // Use the `log` crate for actual logging.
-        println!("INFO: ItemProcessor initialized with threshold: {}", threshold);
-        ItemProcessor { threshold }
+        log_info!("item_processor:new", "ItemProcessor initialized with threshold: {}", threshold);
+        ItemProcessor {
+            rules: ThresholdRules::new(threshold),
+            transformations: Vec::new(),
+            value_decimals: DEFAULT_DECIMALS,
+            #[cfg(feature = "native")]
+            timeout: None,
+            #[cfg(feature = "native")]
+            audit_log: None,
+        }
+    }
+
+    /**
+     * @brief Use different thresholds for some categories and tags.
+     *
+     * An item's category threshold takes precedence over its tag thresholds;
+     * if it carries several tags with a threshold, the lowest one applies.
+     * Items matching neither use the threshold given to new().
+     *
+     * @param category_thresholds (category, threshold) pairs.
+     * @param tag_thresholds (tag, threshold) pairs.
+     * @return ItemProcessor The processor with the thresholds applied.
+     */
+    pub fn with_thresholds(
+        mut self,
+        category_thresholds: Vec<(String, f64)>,
+        tag_thresholds: Vec<(String, f64)>,
+    ) -> Self {
+        if !category_thresholds.is_empty() || !tag_thresholds.is_empty() {
+            log_info!(
+                "item_processor:with_thresholds",
+                "Thresholds - by category: {:?}, by tag: {:?}",
+                category_thresholds,
+                tag_thresholds
+            );
+        }
+        self.rules = self
+            .rules
+            .with_category_thresholds(category_thresholds)
+            .with_tag_thresholds(tag_thresholds);
+        self
+    }
+
+    /**
+     * @brief Return the threshold that applies to an item.
+     *
+     * @param item The item.
+     * @return f64 Its category threshold, else the lowest of its tag
+     *         thresholds, else the default threshold.
+     */
+    pub fn threshold_for(&self, item: &Item) -> f64 {
+        self.rules.threshold_for(item.category.as_deref(), &item.tags)
+    }
+
+    /**
+     * @brief Classify items as over or under their thresholds without processing them.
+     *
+     * A fast path for callers that only need the classification: the values
+     * are compared with their thresholds in one vectorized pass (see
+     * classify.rs). Tag rules, transformations and statuses are not looked
+     * at; an item is over exactly if process_item would tag it
+     * TAG_OVER_THRESHOLD for its current value.
+     *
+     * @param items The items.
+     * @return Vec<bool> For each item, true if its value is over its threshold.
+     */
+    pub fn classify_items(&self, items: &[Item]) -> Vec<bool> {
+        let values: Vec<Money> = items.iter().map(|item| item.value).collect();
+        let over = if self.rules.is_uniform() {
+            let threshold = Money::from_f64_saturating(self.rules.threshold_for(None, &[]));
+            classify::classify_values(&values, Thresholds::Uniform(threshold))
+        } else {
+            let thresholds: Vec<Money> =
+                items.iter().map(|item| Money::from_f64_saturating(self.threshold_for(item))).collect();
+            classify::classify_values(&values, Thresholds::PerValue(&thresholds))
+        };
+        over.expect("the value and threshold columns have one entry per item")
+    }
+
+    /**
+     * @brief Record every change made to an item in an audit log.
+     *
+     * An item whose changes cannot be recorded is left unchanged and counts
+     * as failed.
+     *
+     * @param audit_log The log, or None to not record changes.
+     * @return ItemProcessor The processor with the audit log applied.
+     */
+    #[cfg(feature = "native")]
+    pub fn with_audit_log(mut self, audit_log: Option<Arc<AuditLog>>) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
+    /**
+     * @brief Limit how long processing a single item may take.
+     *
+     * Only applies to process_item_with_timeout.
+     *
+     * @param timeout The limit per item, or None for no limit.
+     * @return ItemProcessor The processor with the timeout applied.
+     */
+    #[cfg(feature = "native")]
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        if let Some(limit) = timeout {
+            log_info!("item_processor:with_timeout", "Per-item timeout: {:?}", limit);
+        }
+        self.timeout = timeout;
+        self
+    }
+
+    /**
+     * @brief Set the value transformations applied to every item processed for the first time.
+     *
+     * @param transformations Parsed rules such as "value = value * 1.2 if value > threshold",
+     *                        applied in the given order before the threshold check.
+     * @return ItemProcessor The processor with the transformations applied.
+     */
+    pub fn with_transformations(mut self, transformations: Vec<Transformation>) -> Self {
+        for transformation in &transformations {
+            log_info!("item_processor:with_transformations", "Transformation: {}", transformation);
+        }
+        self.transformations = transformations;
+        self
+    }
+
+    /**
+     * @brief Set the number of decimal places processed values are kept at.
+     *
+     * Each processed item's value, and the result of every transformation,
+     * is rounded to it (half away from zero). Items that are not processed
+     * keep the decimals they were loaded with.
+     *
+     * @param value_decimals Number of decimal places (at most money::MAX_DECIMALS).
+     * @return ItemProcessor The processor with the precision applied.
+     */
+    pub fn with_value_decimals(mut self, value_decimals: u32) -> Self {
+        self.value_decimals = value_decimals.min(MAX_DECIMALS);
+        self
+    }
+
+    /**
+     * @brief Restrict which items this processor selects, based on their tags.
+     *
+     * @param required_tags Select only items with at least one of these tags
+     *                      (an empty list selects every item).
+     * @param excluded_tags Never select items with any of these tags.
+     * @return ItemProcessor The processor with the tag rules applied.
+     */
+    pub fn with_tag_rules(mut self, required_tags: Vec<String>, excluded_tags: Vec<String>) -> Self {
+        log_info!(
+            "item_processor:with_tag_rules",
+            "Tag rules - required (any of): {:?}, excluded: {:?}",
+            required_tags,
+            excluded_tags
+        );
+        self.rules = self.rules.with_tag_rules(required_tags, excluded_tags);
+        self
+    }
+
+    /**
+     * @brief Decide whether an item should be processed according to the tag rules.
+     *
+     * @param item The item to check.
+     * @return bool True if the item passes the required and excluded tag rules.
+     */
+    pub fn is_selected(&self, item: &Item) -> bool {
+        self.rules.is_selected(&item.tags)
     }
 
     /**
      * @brief Process a single item.
      *
-     * Marks the item as processed and applies logic based on the threshold.
-     * In this example, it simply logs whether the item's value exceeds
-     * the threshold.
+     * Validates the item, applies the configured value transformations
+     * (unless an earlier run already processed the item, so they never
+     * compound), then marks the item as processed and applies logic based on
+     * the threshold. In this example, it logs whether the item's value
+     * exceeds the threshold and tags the item with "over_threshold" or
+     * "under_threshold" accordingly, removing the other of the two if an
+     * earlier run with another value or threshold left it.
      *
      * @param item A mutable reference to the Item object to process.
-     * @return bool True if processing was successful (always true in this simulation).
+     * @return bool True if processing was successful, false if a transformation
+     *              failed (the item is then left as it was, so a retry starts
+     *              from the same value; the caller marks it as failed).
      *              Rust functions typically return Result<T, E> for operations that can fail.
      *              Returning bool here to match Python example's simplicity.
      */
     pub fn process_item(&self, item: &mut Item) -> bool {
+        let mut working_copy = item.clone();
+        let mut changes = Vec::new();
+        if !self.process_item_cancellable(&mut working_copy, &AtomicBool::new(false), &mut changes) {
+            // Transformations applied before the failing step are dropped with the copy
+            return false;
+        }
+        if !self.audit(&changes) {
+            return false;
+        }
+        record_rules(&mut working_copy, &changes);
+        *item = working_copy;
+        true
+    }
+
+    /**
+     * @brief Evaluate the rules on an item without processing it.
+     *
+     * Applies the transformations and the threshold check to a copy of the
+     * item, as process_item would, but the copy is not marked processed and
+     * nothing is written to the audit log; the item itself is not touched.
+     * Tag rules are not looked at, see is_selected.
+     *
+     * @param item The item.
+     * @return Simulation The item as processing would leave it, and the changes.
+     */
+    pub fn simulate_item(&self, item: &Item) -> Simulation {
+        let mut simulated = item.clone();
+        let mut changes = Vec::new();
+        let error = self.apply_rules(&mut simulated, &AtomicBool::new(false), &mut changes).err();
+        Simulation {
+            item: simulated,
+            changes,
+            error,
+        }
+    }
+
+    /**
+     * @brief Process a single item, giving up once the per-item timeout expires.
+     *
+     * With a timeout set, the item is processed on a separate thread. If that
+     * thread does not finish in time, it is told to stop at its next step and
+     * abandoned; the item is left unchanged and the timeout is reported, so
+     * the caller can record the item as failed and carry on.
+     *
+     * @param item The item, modified in place when processed.
+     * @return Result<(), ProcessError> Ok if the item was processed, or why not.
+     */
+    #[cfg(feature = "native")]
+    pub fn process_item_with_timeout(self: &Arc<Self>, item: &mut Item) -> Result<(), ProcessError> {
+        let Some(limit) = self.timeout else {
+            return if self.process_item(item) { Ok(()) } else { Err(ProcessError::Failed) };
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let processor = Arc::clone(self);
+        let worker_cancelled = Arc::clone(&cancelled);
+        let mut working_copy = item.clone();
+        thread::spawn(move || {
+            let mut changes = Vec::new();
+            let processed_ok =
+                processor.process_item_cancellable(&mut working_copy, &worker_cancelled, &mut changes);
+            // The receiver is gone if the item timed out; the result is dropped then
+            let _ = sender.send((processed_ok, working_copy, changes));
+        });
+
+        match receiver.recv_timeout(limit) {
+            Ok((true, mut processed, changes)) => {
+                if !self.audit(&changes) {
+                    return Err(ProcessError::Failed);
+                }
+                record_rules(&mut processed, &changes);
+                *item = processed;
+                Ok(())
+            }
+            Ok((false, _, _)) => Err(ProcessError::Failed),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                cancelled.store(true, Ordering::Relaxed);
+                Err(ProcessError::Timeout(limit))
+            }
+            // The processing thread panicked
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(ProcessError::Failed),
+        }
+    }
+
+    /**
+     * @brief Record changes in the audit log, if there is one.
+     *
+     * @param changes The changes made to one item.
+     * @return bool True if the changes were recorded (or need not be).
+     */
+    #[cfg(feature = "native")]
+    fn audit(&self, changes: &[AuditEvent]) -> bool {
+        let Some(audit_log) = &self.audit_log else {
+            return true;
+        };
+        match audit_log.record(changes) {
+            Ok(()) => true,
+            Err(e) => {
+                log_error!("item_processor:audit", "{}", e);
+                false
+            }
+        }
+    }
+
+    /**
+     * @brief Without the `native` feature there is no audit log; nothing to record.
+     */
+    #[cfg(not(feature = "native"))]
+    fn audit(&self, _changes: &[AuditEvent]) -> bool {
+        true
+    }
+
+    /**
+     * @brief Process a single item unless cancelled.
+     *
+     * The cancellation flag is checked before each step, so an abandoned
+     * item stops at the next transformation instead of running to the end.
+     *
+     * @param item The item to process.
+     * @param cancelled Set by the caller to stop processing.
+     * @param changes Receives an audit event for every change made to the item.
+     * @return bool True if processing was successful, false if a
+     *              transformation failed or processing was cancelled.
+     */
+    fn process_item_cancellable(
+        &self,
+        item: &mut Item,
+        cancelled: &AtomicBool,
+        changes: &mut Vec<AuditEvent>,
+    ) -> bool {
+        let previous_status = item.status;
+        if self.apply_rules(item, cancelled, changes).is_err() || cancelled.load(Ordering::Relaxed) {
+            return false;
+        }
+        // Mark the item as processed using its own method
+        if let Err(e) = item.mark_as_processed() {
+            log_error!("item_processor:process_item", "{}", e);
+            return false;
+        }
+        changes.push(AuditEvent::new(
+            &item.item_id,
+            AuditChange::MarkedProcessed,
+            json!(previous_status),
+            json!(item.status),
+            "item_processor",
+        ));
+
+        // Simulate successful processing
+        true
+    }
+
+    /**
+     * @brief Validate an item, apply the transformations and tag it by its threshold.
+     *
+     * @param item The item.
+     * @param cancelled Set by the caller to stop before the next transformation.
+     * @param changes Receives an audit event for every change made to the item.
+     * @return Result<(), String> Ok if the rules were applied, or why not (a
+     *         failed transformation, or cancellation); the error is logged.
+     */
+    fn apply_rules(
+        &self,
+        item: &mut Item,
+        cancelled: &AtomicBool,
+        changes: &mut Vec<AuditEvent>,
+    ) -> Result<(), String> {
         // Type checking `isinstance(item, Item)` from Python is handled by Rust's
         // static type system at compile time, as `item` is explicitly typed as `&mut Item`.
 
-        // Using format! macro for constructing the debug string, then println!
-        // This is similar to f-strings but separates formatting from printing.
-        let debug_msg = format!(
-            "DEBUG: Processing item ID: {}, Name: '{}', Value: {:.2}",
+        // The log_* macros format their arguments like format!, similar to f-strings.
+        log_trace!(
+            "item_processor:process_item",
+            "Processing item ID: {}, Name: '{}', Value: {}",
             item.item_id, item.name, item.value
         );
-        println!("{}", debug_msg);
+        // An earlier run already transformed the value; applying the rules
+        // again (e.g. "value = value * 1.2") would compound them
+        let processed_before = item.is_processed();
+        item.validate();
+
+        let stored_value = item.value;
+        item.value = stored_value.round_to(self.value_decimals).map_err(|e| {
+            log_error!("item_processor:process_item", "Item ID {}: {}", item.item_id, e);
+            e
+        })?;
+        if item.value != stored_value {
+            changes.push(AuditEvent::new(
+                &item.item_id,
+                AuditChange::ValueAdjusted,
+                json!(stored_value),
+                json!(item.value),
+                &format!("value_decimals = {}", self.value_decimals),
+            ));
+        }
+
+        let threshold = self.threshold_for(item);
+        let transformations = if processed_before {
+            log_debug!(
+                "item_processor:process_item",
+                "Item ID {}: already processed, transformations not applied again.",
+                item.item_id
+            );
+            &[][..]
+        } else {
+            &self.transformations[..]
+        };
+        for transformation in transformations {
+            if cancelled.load(Ordering::Relaxed) {
+                log_warning!("item_processor:process_item", "Item ID {}: processing cancelled.", item.item_id);
+                return Err(String::from("processing cancelled"));
+            }
+            let old_value = item.value;
+            match transformation.apply(item, threshold, self.value_decimals) {
+                Ok(true) => {
+                    log_debug!(
+                        "item_processor:process_item",
+                        "Item ID {}: '{}' changed value {} -> {}.",
+                        item.item_id, transformation, old_value, item.value
+                    );
+                    changes.push(AuditEvent::new(
+                        &item.item_id,
+                        AuditChange::ValueAdjusted,
+                        json!(old_value),
+                        json!(item.value),
+                        &transformation.to_string(),
+                    ));
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    log_error!("item_processor:process_item", "Item ID {}: {}", item.item_id, e);
+                    return Err(e);
+                }
+            }
+        }
 
         // Apply some simple logic based on the threshold
-        if item.value > self.threshold as f64 { // Cast threshold to f64 for comparison
-            println!(
-                "INFO: Item '{}' (ID: {}) value {:.2} exceeds threshold {}.",
-                item.name, item.item_id, item.value, self.threshold
+        let tag = ThresholdRules::threshold_tag(item.value, Money::from_f64_saturating(threshold));
+        let rule = if tag == TAG_OVER_THRESHOLD {
+            log_debug!(
+                "item_processor:process_item",
+                "Item '{}' (ID: {}) value {:.2} exceeds threshold {}.",
+                item.name, item.item_id, item.value, threshold
             );
-            // Potential place for different actions based on threshold
+            format!("value > threshold {}", threshold)
         } else {
-            println!(
-                "INFO: Item '{}' (ID: {}) value {:.2} is within threshold {}.",
-                item.name, item.item_id, item.value, self.threshold
+            log_debug!(
+                "item_processor:process_item",
+                "Item '{}' (ID: {}) value {:.2} is within threshold {}.",
+                item.name, item.item_id, item.value, threshold
             );
+            format!("value <= threshold {}", threshold)
+        };
+        // A tag from an earlier run with another value or threshold no longer applies
+        let stale_tag = ThresholdRules::opposite_tag(tag);
+        if item.remove_tag(stale_tag) {
+            changes.push(AuditEvent::new(
+                &item.item_id,
+                AuditChange::TagRemoved,
+                json!(stale_tag),
+                json!(null),
+                &rule,
+            ));
+        }
+        if item.add_tag(tag) {
+            changes.push(AuditEvent::new(&item.item_id, AuditChange::TagAdded, json!(null), json!(tag), &rule));
         }
 
-        // Mark the item as processed using its own method
-        item.mark_as_processed();
+        Ok(())
+    }
+}
 
-        // Simulate successful processing
-        true
+/**
+ * @brief Record the rules that changed an item in its lineage, if it is tracked.
+ *
+ * @param item The item.
+ * @param changes The changes made to it; marking it processed is not a rule.
+ */
+fn record_rules(item: &mut Item, changes: &[AuditEvent]) {
+    for change in changes.iter().filter(|change| change.change != AuditChange::MarkedProcessed) {
+        item.record_rule(&change.rule);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::ItemStatus;
+    use crate::item_id::ItemId;
+
+    #[test]
+    fn a_failed_item_keeps_its_value_so_retries_do_not_compound() {
+        // The second rule divides by zero for a doubled value of 200, but not for 400
+        let rules = ["value = value * 2", "value = value / (value - 200)"];
+        let processor = ItemProcessor::new(1000.0)
+            .with_transformations(rules.iter().map(|rule| Transformation::parse(rule).unwrap()).collect());
+        let mut item = Item::new(ItemId::from(1), String::from("Gadget"), Money::from_int(100));
+
+        for _ in 0..2 {
+            assert!(!processor.process_item(&mut item));
+            assert_eq!(item.value.to_string(), "100.00");
+            assert_eq!(item.status, ItemStatus::New);
+            assert!(item.tags.is_empty());
+        }
+    }
+
+    #[test]
+    fn each_processor_rounds_to_its_own_precision() {
+        let rules = vec![Transformation::parse("value = value / 3").unwrap()];
+        let coarse = ItemProcessor::new(1000.0).with_transformations(rules.clone()).with_value_decimals(0);
+        let fine = ItemProcessor::new(1000.0).with_transformations(rules).with_value_decimals(4);
+        let item = Item::new(ItemId::from(1), String::from("Gadget"), Money::parse("10.005").unwrap());
+
+        let mut rounded = item.clone();
+        assert!(coarse.process_item(&mut rounded));
+        assert_eq!(rounded.value.to_string(), "3");
+        let mut exact = item;
+        assert!(fine.process_item(&mut exact));
+        assert_eq!(exact.value.to_string(), "3.3350");
+    }
+
+    #[test]
+    fn compares_values_with_the_threshold_exactly() {
+        let processor = ItemProcessor::new(100.1).with_value_decimals(MAX_DECIMALS);
+        for (value, tag) in [("100.10", TAG_UNDER_THRESHOLD), ("100.1000001", TAG_OVER_THRESHOLD)] {
+            let mut item = Item::new(ItemId::from(1), String::from("Gadget"), Money::parse(value).unwrap());
+            assert!(processor.process_item(&mut item));
+            assert!(item.has_tag(tag), "{} should be tagged {}", value, tag);
+        }
     }
 }
 
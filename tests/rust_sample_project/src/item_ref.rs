@@ -0,0 +1,239 @@
+// rust_sample_project/src/item_ref.rs
+
+// A read-only view of an item whose strings are borrowed from the buffer it
+// was parsed from, for the paths that only read and compare items (the diff
+// subcommand). Parsing an owned Item allocates its name, every tag, the
+// source, category, supplier and tenant; an ItemRef borrows them, so a large
+// data file costs about half the allocations. A string with JSON escapes cannot be
+// borrowed and is copied, so the fields are Cow<str>.
+//
+// Only data files in the current schema (JSON and NDJSON) are parsed this
+// way; older JSON documents need a migration and are loaded as owned items,
+// which ItemRef::from views without copying. to_owned_item turns a view into
+// an Item for the paths that change items.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::de::{Deserializer, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+use crate::data_handler::{DataFormat, CURRENT_SCHEMA_VERSION};
+use crate::item::{Item, ItemStatus, Lineage};
+use crate::item_id::ItemId;
+use crate::money::Money;
+
+/**
+ * @struct ItemRef
+ * @brief An item with its strings borrowed from the parsed input.
+ *
+ * The fields are those of Item; see there.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemRef<'a> {
+    pub item_id: ItemId,
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    pub value: Money,
+    #[serde(default)]
+    pub status: ItemStatus,
+    #[serde(default, borrow, deserialize_with = "borrowed_strs")]
+    pub tags: Vec<Cow<'a, str>>,
+    /// None for records without it (Item fills in the load time instead).
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub processed_at: Option<DateTime<Utc>>,
+    #[serde(default, borrow, deserialize_with = "borrowed_opt_str", skip_serializing_if = "Option::is_none")]
+    pub source: Option<Cow<'a, str>>,
+    #[serde(default, borrow, deserialize_with = "borrowed_opt_str", skip_serializing_if = "Option::is_none")]
+    pub category: Option<Cow<'a, str>>,
+    #[serde(default, borrow, deserialize_with = "borrowed_opt_str", skip_serializing_if = "Option::is_none")]
+    pub supplier: Option<Cow<'a, str>>,
+    #[serde(default, borrow, deserialize_with = "borrowed_opt_str", skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<Box<Lineage>>,
+}
+
+impl ItemRef<'_> {
+    /**
+     * @brief Copy the view into an owned Item.
+     *
+     * @return Item The item, created now if the record had no created_at.
+     */
+    pub fn to_owned_item(&self) -> Item {
+        Item {
+            item_id: self.item_id.clone(),
+            name: Arc::from(self.name.as_ref()),
+            value: self.value,
+            status: self.status,
+            tags: self.tags.iter().map(|tag| tag.to_string()).collect(),
+            created_at: self.created_at.unwrap_or_else(Utc::now),
+            processed_at: self.processed_at,
+            source: self.source.as_deref().map(str::to_string),
+            category: self.category.as_deref().map(str::to_string),
+            supplier: self.supplier.as_deref().map(str::to_string),
+            tenant: self.tenant.as_deref().map(str::to_string),
+            lineage: self.lineage.clone(),
+        }
+    }
+}
+
+impl<'a> From<&'a Item> for ItemRef<'a> {
+    /**
+     * @brief View an owned item, borrowing its strings.
+     */
+    fn from(item: &'a Item) -> Self {
+        ItemRef {
+            item_id: item.item_id.clone(),
+            name: Cow::Borrowed(&*item.name),
+            value: item.value,
+            status: item.status,
+            tags: item.tags.iter().map(|tag| Cow::Borrowed(tag.as_str())).collect(),
+            created_at: Some(item.created_at),
+            processed_at: item.processed_at,
+            source: item.source.as_deref().map(Cow::Borrowed),
+            category: item.category.as_deref().map(Cow::Borrowed),
+            supplier: item.supplier.as_deref().map(Cow::Borrowed),
+            tenant: item.tenant.as_deref().map(Cow::Borrowed),
+            lineage: item.lineage.clone(),
+        }
+    }
+}
+
+/**
+ * @brief Parse the items of a JSON or NDJSON data file in the current schema
+ *        as views into its contents.
+ *
+ * Records that are not a valid item are skipped with a warning, as when
+ * loading owned items.
+ *
+ * @param contents The decoded file contents, which the items borrow from.
+ * @param format The format of the file.
+ * @param location Path of the file, used in messages.
+ * @return Result<Vec<ItemRef>, String> The valid items, or an error message
+ *         for other formats, malformed documents and older schema versions.
+ */
+pub fn parse_items<'a>(contents: &'a str, format: DataFormat, location: &str) -> Result<Vec<ItemRef<'a>>, String> {
+    match format {
+        DataFormat::Json => parse_json_items(contents, location),
+        DataFormat::Ndjson => Ok(parse_ndjson_items(contents, location)),
+        other => Err(format!("{:?} data file '{}' cannot be read as borrowed items", other, location)),
+    }
+}
+
+/// The top level of a JSON data file, with its records left unparsed.
+#[derive(Deserialize)]
+struct RawDocument<'a> {
+    schema_version: u64,
+    #[serde(borrow)]
+    items: Vec<&'a RawValue>,
+}
+
+/**
+ * @brief Parse the items of a current-schema JSON data document.
+ */
+fn parse_json_items<'a>(contents: &'a str, location: &str) -> Result<Vec<ItemRef<'a>>, String> {
+    let document: RawDocument = serde_json::from_str(contents)
+        .map_err(|e| format!("Failed to parse data file '{}': {}", location, e))?;
+    if document.schema_version != CURRENT_SCHEMA_VERSION {
+        return Err(format!(
+            "Data file '{}' has schema version {}, not the current version {}",
+            location, document.schema_version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+    let mut items = Vec::with_capacity(document.items.len());
+    for (index, raw_item) in document.items.into_iter().enumerate() {
+        match serde_json::from_str::<ItemRef>(raw_item.get()) {
+            Ok(item) => items.push(item),
+            Err(e) => {
+                log_warning!(
+                    "item_ref:parse_json_items",
+                    "Skipping invalid record #{} in {}: {}",
+                    index + 1,
+                    location,
+                    e
+                );
+            }
+        }
+    }
+    Ok(items)
+}
+
+/**
+ * @brief Parse the items of an NDJSON data file, skipping blank and malformed lines.
+ */
+fn parse_ndjson_items<'a>(contents: &'a str, location: &str) -> Vec<ItemRef<'a>> {
+    let mut items = Vec::new();
+    let mut malformed_lines = 0;
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<ItemRef>(line) {
+            Ok(item) => items.push(item),
+            Err(e) => {
+                log_warning!("item_ref:parse_ndjson_items", "Skipping malformed line {} in {}: {}", index + 1, location, e);
+                malformed_lines += 1;
+            }
+        }
+    }
+    if malformed_lines > 0 {
+        log_warning!("item_ref:parse_ndjson_items", "Skipped {} malformed lines in {}.", malformed_lines, location);
+    }
+    items
+}
+
+/// Deserializes a string, borrowing it from the input when it has no escapes.
+/// (serde borrows only into Cow<str> fields themselves, not into lists or options of them.)
+struct CowStrVisitor;
+
+impl<'de> Visitor<'de> for CowStrVisitor {
+    type Value = Cow<'de, str>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_borrowed_str<E: serde::de::Error>(self, text: &'de str) -> Result<Cow<'de, str>, E> {
+        Ok(Cow::Borrowed(text))
+    }
+
+    fn visit_str<E: serde::de::Error>(self, text: &str) -> Result<Cow<'de, str>, E> {
+        Ok(Cow::Owned(text.to_string()))
+    }
+
+    fn visit_string<E: serde::de::Error>(self, text: String) -> Result<Cow<'de, str>, E> {
+        Ok(Cow::Owned(text))
+    }
+}
+
+/// A string as deserialized by CowStrVisitor.
+struct CowStr<'a>(Cow<'a, str>);
+
+impl<'de> Deserialize<'de> for CowStr<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(CowStrVisitor).map(CowStr)
+    }
+}
+
+/**
+ * @brief Deserialize a list of strings, borrowing them where possible.
+ */
+fn borrowed_strs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Cow<'de, str>>, D::Error> {
+    let strs = Vec::<CowStr>::deserialize(deserializer)?;
+    Ok(strs.into_iter().map(|text| text.0).collect())
+}
+
+/**
+ * @brief Deserialize an optional string, borrowing it where possible.
+ */
+fn borrowed_opt_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Cow<'de, str>>, D::Error> {
+    Ok(Option::<CowStr>::deserialize(deserializer)?.map(|text| text.0))
+}
+
+// End of rust_sample_project/src/item_ref.rs
@@ -0,0 +1,189 @@
+// rust_sample_project/src/join.rs
+
+// Joins a secondary item dataset into the primary one by item_id (see the
+// join_path, join_type and join_fields config keys, and the `join`
+// subcommand), e.g. to merge the categories and suppliers of a second export
+// into the items before they are processed. The secondary file is a data file
+// like any other and is read with the same settings.
+//
+// A left join keeps every primary item, an inner join drops those without a
+// match. Only the fields listed in join_fields are taken over; the ids on
+// either side without a match are reported.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::item::Item;
+use crate::item_id::ItemId;
+
+/**
+ * @enum JoinType
+ * @brief Which primary items a join keeps.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JoinType {
+    /// Every primary item, matched or not ("left").
+    Left,
+    /// Only primary items with a match ("inner").
+    Inner,
+}
+
+impl JoinType {
+    /**
+     * @brief Parse a join type as used in config.
+     *
+     * @param name "left" or "inner".
+     * @return Result<JoinType, String> The join type, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<JoinType, String> {
+        match name {
+            "left" => Ok(JoinType::Left),
+            "inner" => Ok(JoinType::Inner),
+            other => Err(format!("Unknown join type '{}' (expected 'left' or 'inner')", other)),
+        }
+    }
+}
+
+/**
+ * @enum JoinField
+ * @brief A field taken over from the matching secondary item.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinField {
+    /// The name ("name").
+    Name,
+    /// The value ("value").
+    Value,
+    /// The tags, added to those the item has ("tags").
+    Tags,
+    /// The category, if the secondary item has one ("category").
+    Category,
+    /// The supplier, if the secondary item has one ("supplier").
+    Supplier,
+}
+
+impl JoinField {
+    /**
+     * @brief Parse a field name as used in config.
+     *
+     * @param name "name", "value", "tags", "category" or "supplier".
+     * @return Result<JoinField, String> The field, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<JoinField, String> {
+        match name {
+            "name" => Ok(JoinField::Name),
+            "value" => Ok(JoinField::Value),
+            "tags" => Ok(JoinField::Tags),
+            "category" => Ok(JoinField::Category),
+            "supplier" => Ok(JoinField::Supplier),
+            other => Err(format!(
+                "Unknown join field '{}' (expected 'name', 'value', 'tags', 'category' or 'supplier')",
+                other
+            )),
+        }
+    }
+
+    /**
+     * @brief Copy this field from the secondary item onto the primary one.
+     */
+    fn apply(self, item: &mut Item, secondary: &Item) {
+        match self {
+            JoinField::Name => item.name = secondary.name.clone(),
+            JoinField::Value => item.value = secondary.value,
+            JoinField::Tags => {
+                for tag in &secondary.tags {
+                    item.add_tag(tag);
+                }
+            }
+            JoinField::Category => {
+                if secondary.category.is_some() {
+                    item.category = secondary.category.clone();
+                }
+            }
+            JoinField::Supplier => {
+                if secondary.supplier.is_some() {
+                    item.supplier = secondary.supplier.clone();
+                }
+            }
+        }
+    }
+}
+
+/**
+ * @struct JoinStats
+ * @brief Outcome of a join, for the run report.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JoinStats {
+    /// The join type used.
+    pub join_type: JoinType,
+    /// Number of primary items with a match.
+    pub matched: usize,
+    /// Ids of the primary items without a match, in input order.
+    pub unmatched: Vec<ItemId>,
+    /// Ids of the secondary items without a match, in their input order.
+    pub unused: Vec<ItemId>,
+    /// Number of primary items dropped (inner join only).
+    pub dropped: usize,
+}
+
+/**
+ * @brief Join secondary items into primary items by item_id.
+ *
+ * If the secondary items hold an id more than once, the last one wins.
+ *
+ * @param items The primary items, in input order.
+ * @param secondary The secondary items.
+ * @param join_type Which primary items are kept.
+ * @param fields The fields taken over from the matching secondary item.
+ * @return (Vec<Item>, JoinStats) The joined items, in input order, and what matched.
+ */
+pub fn join(
+    items: Vec<Item>,
+    secondary: Vec<Item>,
+    join_type: JoinType,
+    fields: &[JoinField],
+) -> (Vec<Item>, JoinStats) {
+    let secondary_order: Vec<ItemId> = secondary.iter().map(|item| item.item_id.clone()).collect();
+    let secondary: HashMap<ItemId, Item> = secondary.into_iter().map(|item| (item.item_id.clone(), item)).collect();
+    let mut used: HashSet<&ItemId> = HashSet::with_capacity(secondary.len());
+    let mut joined = Vec::with_capacity(items.len());
+    let mut stats = JoinStats {
+        join_type,
+        matched: 0,
+        unmatched: Vec::new(),
+        unused: Vec::new(),
+        dropped: 0,
+    };
+    for mut item in items {
+        match secondary.get_key_value(&item.item_id) {
+            Some((id, other)) => {
+                for field in fields {
+                    field.apply(&mut item, other);
+                }
+                used.insert(id);
+                stats.matched += 1;
+                joined.push(item);
+            }
+            None => {
+                log_debug!("join:join", "No item ID {} in the joined dataset.", item.item_id);
+                stats.unmatched.push(item.item_id.clone());
+                match join_type {
+                    JoinType::Left => joined.push(item),
+                    JoinType::Inner => stats.dropped += 1,
+                }
+            }
+        }
+    }
+    let mut reported: HashSet<&ItemId> = HashSet::new();
+    for id in &secondary_order {
+        if !used.contains(id) && reported.insert(id) {
+            stats.unused.push(id.clone());
+        }
+    }
+    (joined, stats)
+}
+
+// End of rust_sample_project/src/join.rs
@@ -0,0 +1,154 @@
+// rust_sample_project/src/kafka_stream.rs
+
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use kafka::consumer::{Consumer, FetchOffset, GroupOffsetStorage};
+use kafka::producer::{Producer, Record, RequiredAcks};
+
+use crate::config::{
+    get_kafka_brokers, get_kafka_consumer_group, get_kafka_input_topic, get_kafka_output_topic,
+};
+use crate::delivery::Delivery;
+use crate::filter::FilterExpr;
+use crate::item::Item;
+use crate::item_id::ItemId;
+use crate::item_processor::ItemProcessor;
+use crate::metrics::Metrics;
+use crate::pipeline;
+use crate::rate_limit;
+use crate::shutdown;
+
+/// Pause between polls that returned no messages.
+const IDLE_POLL_DELAY: Duration = Duration::from_millis(200);
+/// How long the producer waits for the broker to acknowledge a batch.
+const ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/**
+ * @brief Consume items from the input topic, process them and emit them to the output topic.
+ *
+ * Each message is one Item as JSON. Every item is forwarded, like a
+ * file-based run saves every item: items that do not match the filter or tag
+ * rules, or whose processing failed, are emitted unchanged. Offsets are
+ * committed only after all items of a poll have been acknowledged by the
+ * broker, so a crash leads to reprocessing rather than lost items.
+ * Malformed messages are logged and skipped. Emitting is held back to the
+ * sink rate limit, if one is configured. A shutdown request (SIGINT or
+ * SIGTERM) is honoured between polls, after the last batch was committed.
+ *
+ * Every emitted record has the item's idempotency key as message key, with
+ * the consumer group as run id (see delivery.rs). With a delivery ledger,
+ * items reprocessed after a crash that were already emitted are not emitted
+ * again.
+ *
+ * @param item_processor Processes the selected items.
+ * @param item_filter Optional filter selecting the items to process.
+ * @param metrics Collector for the stream's counters and timings.
+ * @param ledger_path The delivery ledger, or an empty path for none.
+ * @return Result<(), String> Ok after a requested shutdown, or the message of a Kafka error.
+ */
+pub fn run(
+    item_processor: &Arc<ItemProcessor>,
+    item_filter: Option<&FilterExpr>,
+    metrics: &Metrics,
+    ledger_path: &Path,
+) -> Result<(), String> {
+    let brokers = get_kafka_brokers();
+    let input_topic = get_kafka_input_topic();
+    let output_topic = get_kafka_output_topic();
+    let delivery = Delivery::new(get_kafka_consumer_group(), ledger_path);
+
+    let mut consumer = Consumer::from_hosts(brokers.clone())
+        .with_topic(input_topic.to_string())
+        .with_group(get_kafka_consumer_group().to_string())
+        .with_fallback_offset(FetchOffset::Earliest)
+        .with_offset_storage(Some(GroupOffsetStorage::Kafka))
+        .create()
+        .map_err(|e| format!("Failed to connect Kafka consumer to {:?}: {}", brokers, e))?;
+    let rate_limiter = rate_limit::sink_limiter();
+    let mut producer = Producer::from_hosts(brokers.clone())
+        .with_ack_timeout(ACK_TIMEOUT)
+        .with_required_acks(RequiredAcks::All)
+        .create()
+        .map_err(|e| format!("Failed to connect Kafka producer to {:?}: {}", brokers, e))?;
+
+    log_info!(
+        "kafka_stream:run",
+        "Streaming items from topic '{}' to topic '{}' via {:?}.",
+        input_topic,
+        output_topic,
+        brokers
+    );
+
+    while !shutdown::requested() {
+        let message_sets = consumer
+            .poll()
+            .map_err(|e| format!("Failed to poll topic '{}': {}", input_topic, e))?;
+        if message_sets.is_empty() {
+            thread::sleep(IDLE_POLL_DELAY);
+            continue;
+        }
+
+        for message_set in message_sets.iter() {
+            let mut outputs: Vec<(String, ItemId, String)> = Vec::new();
+            for message in message_set.messages() {
+                let mut item: Item = match serde_json::from_slice(message.value) {
+                    Ok(item) => item,
+                    Err(e) => {
+                        log_warning!(
+                            "kafka_stream:run",
+                            "Skipping malformed message at {}:{}/{}: {}",
+                            message_set.topic(),
+                            message_set.partition(),
+                            message.offset,
+                            e
+                        );
+                        continue;
+                    }
+                };
+                metrics.add_items_loaded(1);
+                pipeline::process_streamed_item(&mut item, item_processor, item_filter, metrics);
+                let key = delivery.key(&item)?;
+                if delivery.is_delivered(&key)? {
+                    log_debug!(
+                        "kafka_stream:run",
+                        "Item {} was already emitted to topic '{}', skipping it.",
+                        item.item_id,
+                        output_topic
+                    );
+                    continue;
+                }
+                let json = serde_json::to_string(&item)
+                    .map_err(|e| format!("Failed to serialize item {}: {}", item.item_id, e))?;
+                outputs.push((key, item.item_id.clone(), json));
+            }
+
+            let records: Vec<Record<'_, &[u8], &[u8]>> = outputs
+                .iter()
+                .map(|(key, _, json)| Record::from_key_value(output_topic, key.as_bytes(), json.as_bytes()))
+                .collect();
+            if let Some(rate_limiter) = &rate_limiter {
+                rate_limiter.acquire(records.len() as u64);
+            }
+            producer
+                .send_all(&records)
+                .map_err(|e| format!("Failed to emit items to topic '{}': {}", output_topic, e))?;
+            let delivered: Vec<(String, ItemId)> =
+                outputs.into_iter().map(|(key, item_id, _)| (key, item_id)).collect();
+            delivery.record(output_topic, &delivered)?;
+            consumer
+                .consume_messageset(message_set)
+                .map_err(|e| format!("Failed to mark messages as consumed: {}", e))?;
+        }
+
+        consumer
+            .commit_consumed()
+            .map_err(|e| format!("Failed to commit offsets for topic '{}': {}", input_topic, e))?;
+    }
+    log_info!("kafka_stream:run", "Shutdown requested, stopped streaming.");
+    Ok(())
+}
+
+// End of rust_sample_project/src/kafka_stream.rs
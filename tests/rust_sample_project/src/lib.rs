@@ -0,0 +1,187 @@
+// rust_sample_project/src/lib.rs
+
+// The data processing pipeline as a library. The binary (src/main.rs) wires
+// these modules together; benchmarks and embedding applications use them
+// directly.
+//
+// The item processing core (item, item_id, money, item_processor, classify,
+// transform, filter, audit events, logging and messages) builds on every target,
+// including wasm32-unknown-unknown. Everything needing a filesystem, threads
+// or signals is behind the default `native` feature. Without the `std`
+// feature (implied by `native` and `web`) the crate is no_std + alloc and
+// holds only item_core and money, for embedded targets.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+// The logging and messages modules come first so their log_* and tr! macros
+// are visible in all other modules.
+#[cfg(feature = "std")]
+#[macro_use]
+pub mod logging;
+#[cfg(feature = "std")]
+#[macro_use]
+pub mod messages;
+#[cfg(feature = "native")]
+pub mod aggregate;
+#[cfg(feature = "native")]
+pub mod anomaly;
+#[cfg(feature = "std")]
+pub mod audit;
+#[cfg(feature = "native")]
+pub mod batch;
+#[cfg(feature = "bench")]
+pub mod bench_support;
+#[cfg(feature = "native")]
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub mod classify;
+#[cfg(feature = "native")]
+pub mod completions;
+#[cfg(feature = "native")]
+pub mod composition;
+#[cfg(feature = "native")]
+pub mod compression;
+#[cfg(feature = "native")]
+pub mod config;
+#[cfg(feature = "native")]
+pub mod control;
+#[cfg(feature = "native")]
+pub mod daemon;
+#[cfg(feature = "native")]
+pub mod datagen;
+#[cfg(feature = "native")]
+pub mod data_handler;
+#[cfg(feature = "native")]
+pub mod dedup;
+#[cfg(feature = "native")]
+pub mod delivery;
+#[cfg(feature = "native")]
+pub mod diff;
+#[cfg(feature = "native")]
+pub mod docs;
+#[cfg(feature = "native")]
+pub mod encryption;
+#[cfg(feature = "native")]
+pub mod enrichment;
+#[cfg(feature = "native")]
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod filter;
+#[cfg(feature = "grpc")]
+pub mod grpc_server;
+#[cfg(feature = "native")]
+pub mod guard;
+#[cfg(feature = "native")]
+pub mod health;
+#[cfg(feature = "native")]
+pub mod history;
+#[cfg(feature = "native")]
+pub mod hooks;
+#[cfg(feature = "http")]
+pub mod http_store;
+#[cfg(feature = "native")]
+pub mod integrity;
+#[cfg(feature = "native")]
+pub mod intern;
+#[cfg(feature = "native")]
+pub mod invalid_records;
+#[cfg(feature = "std")]
+pub mod item;
+#[cfg(feature = "arena")]
+pub mod item_arena;
+#[cfg(feature = "native")]
+pub mod item_batch;
+pub mod item_core;
+#[cfg(feature = "std")]
+pub mod item_id;
+#[cfg(feature = "native")]
+pub mod item_ref;
+#[cfg(feature = "std")]
+pub mod item_processor;
+#[cfg(feature = "native")]
+pub mod join;
+#[cfg(feature = "kafka")]
+pub mod kafka_stream;
+#[cfg(feature = "native")]
+pub mod lineage;
+#[cfg(feature = "native")]
+pub mod load_cache;
+#[cfg(feature = "native")]
+pub mod lock;
+#[cfg(feature = "native")]
+pub mod metrics;
+#[cfg(feature = "mmap")]
+pub mod mmap_input;
+pub mod money;
+#[cfg(feature = "native")]
+pub mod ndjson;
+#[cfg(feature = "parquet")]
+pub mod parquet_format;
+#[cfg(feature = "native")]
+pub mod path_template;
+#[cfg(feature = "native")]
+pub mod notify;
+#[cfg(feature = "native")]
+pub mod pipeline;
+#[cfg(feature = "native")]
+pub mod pipeline_file;
+#[cfg(feature = "native")]
+pub mod priority;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "native")]
+pub mod rate_limit;
+#[cfg(feature = "native")]
+pub mod registry;
+#[cfg(feature = "native")]
+pub mod repl;
+#[cfg(feature = "native")]
+pub mod report;
+#[cfg(feature = "native")]
+pub mod router;
+#[cfg(feature = "s3")]
+pub mod s3_store;
+#[cfg(feature = "native")]
+pub mod sampling;
+#[cfg(feature = "native")]
+pub mod schedule;
+#[cfg(feature = "native")]
+pub mod secret;
+#[cfg(feature = "native")]
+pub mod shard;
+#[cfg(feature = "native")]
+pub mod shutdown;
+#[cfg(feature = "native")]
+pub mod snapshot;
+#[cfg(feature = "native")]
+pub mod staging;
+#[cfg(feature = "native")]
+pub mod storage;
+#[cfg(feature = "otel")]
+pub mod telemetry;
+#[cfg(all(test, feature = "native"))]
+mod test_dir;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "std")]
+pub mod transform;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "wasm")]
+pub mod wasm_plugin;
+#[cfg(feature = "web")]
+pub mod web;
+#[cfg(feature = "native")]
+pub mod what_if;
+#[cfg(feature = "native")]
+pub mod worker_pool;
+#[cfg(feature = "native")]
+pub mod xlsx_format;
+#[cfg(feature = "native")]
+pub mod xml_format;
+
+// End of rust_sample_project/src/lib.rs
@@ -0,0 +1,104 @@
+// rust_sample_project/src/lineage.rs
+
+// The history of one item, for the `lineage <id>` subcommand: the lineage the
+// item carries in the data file (what the last run tracking lineage did to
+// it, see the lineage config key and Item::start_lineage), followed by every
+// change the audit log recorded for it, across all runs, oldest first.
+// Printed as a table or as JSON.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::audit::{self, AuditEvent};
+use crate::diff;
+use crate::item::{Item, Lineage};
+use crate::item_id::ItemId;
+
+/**
+ * @struct ItemHistory
+ * @brief What is known about where an item came from and how it changed.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ItemHistory {
+    /// The item.
+    pub item_id: ItemId,
+    /// Whether the item is in the data file.
+    pub in_data_file: bool,
+    /// The lineage recorded by the last run tracking it (None if none did).
+    pub lineage: Option<Lineage>,
+    /// The changes recorded in the audit log, oldest first.
+    pub events: Vec<AuditEvent>,
+}
+
+impl ItemHistory {
+    /**
+     * @brief Collect the history of an item.
+     *
+     * @param item_id The item.
+     * @param items The items of the data file.
+     * @param audit_log_path Path of the audit log.
+     * @return Result<ItemHistory, String> The history, or an error message if
+     *         the audit log cannot be read.
+     */
+    pub fn collect(item_id: &ItemId, items: &[Item], audit_log_path: &Path) -> Result<Self, String> {
+        let item = items.iter().find(|item| item.item_id == *item_id);
+        Ok(ItemHistory {
+            item_id: item_id.clone(),
+            in_data_file: item.is_some(),
+            lineage: item.and_then(|item| item.lineage.as_deref().cloned()),
+            events: audit::read_item_events(audit_log_path, item_id)?,
+        })
+    }
+
+    /**
+     * @brief Return whether nothing at all is known about the item.
+     *
+     * @return bool True if the item is neither in the data file nor in the audit log.
+     */
+    pub fn is_empty(&self) -> bool {
+        !self.in_data_file && self.events.is_empty()
+    }
+
+    /**
+     * @brief Render the history: the recorded lineage, then a table with one
+     *        row per audit event, followed by a summary line.
+     *
+     * @return String The history, ending with a newline.
+     */
+    pub fn to_table(&self) -> String {
+        let mut text = String::new();
+        if let Some(lineage) = &self.lineage {
+            let list = |entries: &[String]| if entries.is_empty() { String::from("-") } else { entries.join(", ") };
+            text.push_str(&format!("Source: {}\n", lineage.source.as_deref().unwrap_or("-")));
+            text.push_str(&format!("Stages: {}\n", list(&lineage.stages)));
+            text.push_str(&format!("Rules:  {}\n\n", list(&lineage.rules)));
+        }
+        let mut rows: Vec<[String; 5]> = vec![[
+            String::from("TIME"),
+            String::from("CHANGE"),
+            String::from("BEFORE"),
+            String::from("AFTER"),
+            String::from("RULE"),
+        ]];
+        for event in &self.events {
+            rows.push([
+                event.timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                event.change.to_string(),
+                diff::display_value(&event.old_value),
+                diff::display_value(&event.new_value),
+                event.rule.clone(),
+            ]);
+        }
+        text.push_str(&diff::format_table(&rows));
+        text.push_str(&format!(
+            "{} changes recorded for item {}{}.\n",
+            self.events.len(),
+            self.item_id,
+            if self.in_data_file { "" } else { " (not in the data file)" }
+        ));
+        text
+    }
+}
+
+// End of rust_sample_project/src/lineage.rs
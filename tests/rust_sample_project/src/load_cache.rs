@@ -0,0 +1,183 @@
+// rust_sample_project/src/load_cache.rs
+
+// Cache of the items parsed from local data files, for the long-running
+// modes (--watch and serve), which load the same input files run after run.
+// A file whose modification time, size and SHA-256 digest of its contents
+// (see integrity.rs) are unchanged since it was parsed is not parsed again;
+// its items are copied out of the cache. Hashing still reads the file, but
+// catches a rewrite that kept the size within the timestamp granularity.
+// Remote stores are not cached, and neither are the simulated items of a
+// missing data file.
+//
+// The cache is process-wide and off until a long-running mode enables it
+// (unless --no-cache was given). Parsing also depends on the format mappings
+// and the encryption key, so the config reloader invalidates everything when
+// the configuration changes; invalidate drops a single file.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::data_handler::DataFormat;
+use crate::integrity;
+use crate::invalid_records::{InvalidRecord, Strictness};
+use crate::item::Item;
+
+/// Set while repeated loads are served from the cache.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The parsed items of each cached file, with what identified the file's version.
+static ENTRIES: Mutex<Option<HashMap<PathBuf, CacheEntry>>> = Mutex::new(None);
+
+/**
+ * @struct CacheKey
+ * @brief What identifies the version of a data file that items were parsed from.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    /// The format the file was parsed as.
+    pub format: DataFormat,
+    /// How invalid records were treated; a strict load that failed is not cached.
+    pub strictness: Strictness,
+    /// Modification time of the file.
+    pub modified: SystemTime,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// SHA-256 digest of the file's contents.
+    pub digest: String,
+}
+
+impl CacheKey {
+    /**
+     * @brief Identify the current version of a local data file.
+     *
+     * @param path Path of the data file.
+     * @param format The format the file is parsed as.
+     * @param strictness How invalid records are treated.
+     * @return Option<CacheKey> The key, or None if the file cannot be read
+     *         (the load then reports why).
+     */
+    pub fn of(path: &Path, format: DataFormat, strictness: Strictness) -> Option<CacheKey> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(CacheKey {
+            format,
+            strictness,
+            modified: metadata.modified().ok()?,
+            size: metadata.len(),
+            digest: integrity::file_digest(path).ok()?,
+        })
+    }
+}
+
+/// A cached file.
+struct CacheEntry {
+    key: CacheKey,
+    items: Vec<Item>,
+    /// The records skipped when the file was parsed, reported again on every load.
+    skipped: Vec<InvalidRecord>,
+}
+
+/**
+ * @brief Turn the cache on or off; turning it off drops all cached items.
+ *
+ * @param enabled Serve repeated loads of unchanged files from the cache.
+ */
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+    if !enabled {
+        invalidate_all();
+    }
+}
+
+/**
+ * @brief Return whether repeated loads are served from the cache.
+ *
+ * @return bool True if the cache is on.
+ */
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/**
+ * @brief Return the items parsed from a file, if it is unchanged since.
+ *
+ * @param path Path of the data file.
+ * @param key The file's current version, see CacheKey::of.
+ * @return Option<(Vec<Item>, Vec<InvalidRecord>)> A copy of the cached items
+ *         and skipped records, or None if the file is not cached, has
+ *         changed, or the cache is off.
+ */
+pub fn lookup(path: &Path, key: &CacheKey) -> Option<(Vec<Item>, Vec<InvalidRecord>)> {
+    if !is_enabled() {
+        return None;
+    }
+    let entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = entries.as_ref()?.get(path)?;
+    (entry.key == *key).then(|| (entry.items.clone(), entry.skipped.clone()))
+}
+
+/**
+ * @brief Remember the items parsed from a file, replacing an older version.
+ *
+ * @param path Path of the data file.
+ * @param key The version of the file the items were parsed from.
+ * @param items The items, as parsed.
+ * @param skipped The records skipped as invalid.
+ */
+pub fn store(path: &Path, key: CacheKey, items: &[Item], skipped: &[InvalidRecord]) {
+    if !is_enabled() {
+        return;
+    }
+    let mut entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    entries.get_or_insert_with(HashMap::new).insert(
+        path.to_path_buf(),
+        CacheEntry {
+            key,
+            items: items.to_vec(),
+            skipped: skipped.to_vec(),
+        },
+    );
+}
+
+/**
+ * @brief Drop the cached items of a file, so its next load reads it again.
+ *
+ * @param path Path of the data file.
+ * @return bool True if the file was cached.
+ */
+pub fn invalidate(path: &Path) -> bool {
+    let mut entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    entries.as_mut().is_some_and(|entries| entries.remove(path).is_some())
+}
+
+/**
+ * @brief Drop all cached items.
+ */
+pub fn invalidate_all() {
+    let mut entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    *entries = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn test_key_changes_with_contents_of_same_size_and_time() {
+        let dir = TestDir::new("load-cache");
+        let path = dir.join("items.json");
+        fs::write(&path, "[1]").unwrap();
+        let before = CacheKey::of(&path, DataFormat::Json, Strictness::Lenient).unwrap();
+        fs::write(&path, "[2]").unwrap();
+        fs::File::options().write(true).open(&path).unwrap().set_modified(before.modified).unwrap();
+        let after = CacheKey::of(&path, DataFormat::Json, Strictness::Lenient).unwrap();
+        assert_eq!((after.modified, after.size), (before.modified, before.size));
+        assert_ne!(after, before);
+    }
+}
+
+// End of rust_sample_project/src/load_cache.rs
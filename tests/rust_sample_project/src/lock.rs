@@ -0,0 +1,160 @@
+// rust_sample_project/src/lock.rs
+
+// Advisory locking of the data file, so two pipeline runs on the same file do
+// not overwrite each other's results. A run locks a lock file next to the
+// data file (items.json.lock) from before loading until after saving, and
+// writes its process id into it so a second run can say who holds the lock.
+// The lock is an OS file lock (flock on Unix), so it is released when the
+// holding process exits, even if it crashes; the lock file itself stays.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::shutdown;
+use crate::storage;
+
+/// Interval at which a waiting run retries to take the lock.
+const RETRY_INTERVAL: Duration = Duration::from_millis(200);
+
+/**
+ * @enum LockPolicy
+ * @brief What a run does when another run holds the data file's lock.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockPolicy {
+    /// Wait until the lock is released ("wait").
+    Wait,
+    /// Fail at once ("fail").
+    Fail,
+}
+
+impl LockPolicy {
+    /**
+     * @brief Parse a policy name as used in config.
+     *
+     * @param name One of "wait" or "fail".
+     * @return Result<LockPolicy, String> The policy, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<LockPolicy, String> {
+        match name {
+            "wait" => Ok(LockPolicy::Wait),
+            "fail" => Ok(LockPolicy::Fail),
+            other => Err(format!("Unknown lock policy '{}' (expected 'wait' or 'fail')", other)),
+        }
+    }
+}
+
+/**
+ * @struct DataLock
+ * @brief The lock on a data file, held until dropped.
+ */
+#[derive(Debug)]
+pub struct DataLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl DataLock {
+    /**
+     * @brief Lock a data file.
+     *
+     * @param data_path The data file to lock.
+     * @param policy What to do if another run holds the lock.
+     * @param timeout With LockPolicy::Wait, how long to wait at most (None = no limit).
+     * @return Result<Option<DataLock>, String> The lock, None for remote
+     *         locations (which cannot be locked), or an error message naming
+     *         the process holding the lock.
+     */
+    pub fn acquire(data_path: &Path, policy: LockPolicy, timeout: Option<Duration>) -> Result<Option<Self>, String> {
+        if data_path.to_str().is_some_and(storage::is_remote) {
+            return Ok(None);
+        }
+        let path = storage::with_suffix(&storage::canonical_path(data_path), ".lock");
+        storage::create_parent_dir(&path)?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| format!("Failed to open lock file '{}': {}", path.display(), e))?;
+
+        let started = Instant::now();
+        let mut waiting = false;
+        loop {
+            match file.try_lock() {
+                Ok(()) => break,
+                Err(fs::TryLockError::WouldBlock) => {}
+                Err(fs::TryLockError::Error(e)) => {
+                    return Err(format!("Failed to lock '{}': {}", path.display(), e));
+                }
+            }
+            let held_by = holder_description(&path);
+            if policy == LockPolicy::Fail {
+                return Err(format!(
+                    "Data file '{}' is in use by another run ({}, lock file '{}')",
+                    data_path.display(),
+                    held_by,
+                    path.display()
+                ));
+            }
+            if timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+                return Err(format!(
+                    "Timed out after {}s waiting for data file '{}', still in use by another run ({}, lock file '{}')",
+                    started.elapsed().as_secs(),
+                    data_path.display(),
+                    held_by,
+                    path.display()
+                ));
+            }
+            if shutdown::requested() {
+                return Err(format!("Interrupted while waiting for data file '{}'", data_path.display()));
+            }
+            if !waiting {
+                log_info!(
+                    "lock:acquire",
+                    "Data file {} is in use by another run ({}), waiting...",
+                    data_path.display(),
+                    held_by
+                );
+                waiting = true;
+            }
+            thread::sleep(RETRY_INTERVAL);
+        }
+
+        file.set_len(0)
+            .and_then(|()| writeln!(file, "{}", process::id()))
+            .and_then(|()| file.flush())
+            .map_err(|e| format!("Failed to write lock file '{}': {}", path.display(), e))?;
+        log_debug!("lock:acquire", "Locked {}.", path.display());
+        Ok(Some(DataLock { file, path }))
+    }
+}
+
+impl Drop for DataLock {
+    /**
+     * @brief Clear the process id from the lock file and release the lock.
+     */
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+        if let Err(e) = self.file.unlock() {
+            log_warning!("lock:release", "Failed to unlock '{}': {}", self.path.display(), e);
+        }
+    }
+}
+
+/**
+ * @brief Describe the holder of a lock by the process id in the lock file.
+ */
+fn holder_description(path: &Path) -> String {
+    match fs::read_to_string(path).ok().and_then(|text| text.trim().parse::<u32>().ok()) {
+        Some(pid) => format!("PID {}", pid),
+        None => String::from("PID unknown"),
+    }
+}
+
+// End of rust_sample_project/src/lock.rs
@@ -0,0 +1,552 @@
+// rust_sample_project/src/logging.rs
+
+// A deliberately small logging facility. Every log call site goes through the
+// `log_*!` macros defined here, so the output format can be switched between
+// the human-readable text lines and one JSON object per record. Records
+// carry the context they were logged in: the pipeline (when several run at
+// once), the stage of the run and the item being handled (see LogContext).
+// Call sites do not repeat it in their messages; the pipeline sets it as it
+// moves through a run:
+//
+//     INFO:     (pipeline=orders stage=process item=A-17) [pipeline:handle_item] ...
+//     {"level":"INFO","module":"...","pipeline":"orders","stage":"process","item_id":"A-17",...}
+//
+// Text records align after the level, which is colored for warnings and
+// errors when the stream is a terminal and NO_COLOR is not set (see
+// ColorChoice). Warnings and errors go to stderr, everything else to stdout.
+
+use std::cell::RefCell;
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::RwLock;
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+
+/**
+ * @enum LogLevel
+ * @brief Severity of a log record.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// Per-item detail, only wanted when following single records (-vv).
+    Trace,
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
+
+impl LogLevel {
+    /**
+     * @brief Return the upper-case name used in log output.
+     *
+     * @return &'static str The level name, e.g. "INFO".
+     */
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warning => "WARNING",
+            LogLevel::Error => "ERROR",
+            LogLevel::Critical => "CRITICAL",
+        }
+    }
+
+    /**
+     * @brief Return the ANSI color of the level in colored text output.
+     *
+     * @return Option<&'static str> The SGR parameters, or None for levels shown uncolored.
+     */
+    fn color(self) -> Option<&'static str> {
+        match self {
+            LogLevel::Warning => Some("33"),
+            LogLevel::Error => Some("31"),
+            LogLevel::Critical => Some("1;31"),
+            _ => None,
+        }
+    }
+
+    /**
+     * @brief Parse a log level name ("trace", "debug", "info", "warning",
+     *        "error" or "critical", case-insensitive; "warn" is accepted as well).
+     *
+     * @param name The level name from config or the command line.
+     * @return Result<LogLevel, String> The level, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<LogLevel, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "trace" => Ok(LogLevel::Trace),
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warning" | "warn" => Ok(LogLevel::Warning),
+            "error" => Ok(LogLevel::Error),
+            "critical" => Ok(LogLevel::Critical),
+            other => Err(format!(
+                "Unknown log level '{}' (expected 'trace', 'debug', 'info', 'warning', 'error' or 'critical')",
+                other
+            )),
+        }
+    }
+}
+
+/**
+ * @enum LogFormat
+ * @brief Output format of log records.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `LEVEL: [module] message` lines, as printed so far.
+    Text,
+    /// One JSON object per line, for log aggregators.
+    Json,
+}
+
+impl LogFormat {
+    /**
+     * @brief Parse a log format name ("text" or "json", case-insensitive).
+     *
+     * @param name The format name from config or the command line.
+     * @return Result<LogFormat, String> The format, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<LogFormat, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!(
+                "Unknown log format '{}' (expected 'text' or 'json')",
+                other
+            )),
+        }
+    }
+}
+
+/**
+ * @enum ColorChoice
+ * @brief When text log records are colored.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// When the stream is a terminal and the NO_COLOR environment variable is not set.
+    Auto,
+    /// Always, e.g. when piping into `less -R`.
+    Always,
+    /// Never.
+    Never,
+}
+
+impl ColorChoice {
+    /**
+     * @brief Parse a color choice ("auto", "always" or "never", case-insensitive).
+     *
+     * @param name The choice from config or the command line.
+     * @return Result<ColorChoice, String> The choice, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<ColorChoice, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            other => Err(format!(
+                "Unknown color choice '{}' (expected 'auto', 'always' or 'never')",
+                other
+            )),
+        }
+    }
+}
+
+/**
+ * @struct LogContext
+ * @brief Where in a run a record was logged.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LogContext {
+    /// The pipeline of the config file being run, see config::Config::pipeline.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pipeline: Option<String>,
+    /// The stage of the run ("load", "process", "save") or, while an item is
+    /// handled, the step it is in ("enrichment", "filter", "processor", ...).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage: Option<String>,
+    /// The id of the item being handled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_id: Option<String>,
+}
+
+impl LogContext {
+    /**
+     * @brief Constructs the context of a pipeline's run.
+     *
+     * @param name The pipeline's name.
+     * @return LogContext The context, with no stage or item yet.
+     */
+    pub fn pipeline(name: &str) -> Self {
+        LogContext {
+            pipeline: Some(name.to_string()),
+            ..LogContext::default()
+        }
+    }
+
+    /**
+     * @brief Check whether the context names nothing.
+     *
+     * @return bool True if no field is set.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.pipeline.is_none() && self.stage.is_none() && self.item_id.is_none()
+    }
+}
+
+impl fmt::Display for LogContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = [("pipeline", &self.pipeline), ("stage", &self.stage), ("item", &self.item_id)];
+        let mut separator = "";
+        for (name, value) in fields {
+            if let Some(value) = value {
+                write!(f, "{}{}={}", separator, name, value)?;
+                separator = " ";
+            }
+        }
+        Ok(())
+    }
+}
+
+/**
+ * @struct ContextGuard
+ * @brief Restores the previous context of the thread when dropped, see enter_stage and enter_item.
+ */
+#[must_use = "the context is restored as soon as the guard is dropped"]
+pub struct ContextGuard {
+    previous: LogContext,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        let previous = std::mem::take(&mut self.previous);
+        CONTEXT.with(|current| current.replace(previous));
+    }
+}
+
+/**
+ * @struct LogRecord
+ * @brief Shape of a record in JSON log output.
+ */
+#[derive(Serialize)]
+struct LogRecord<'a> {
+    timestamp: String,
+    level: &'static str,
+    module: &'a str,
+    #[serde(flatten)]
+    context: &'a LogContext,
+    message: &'a str,
+}
+
+static LOG_FORMAT: AtomicU8 = AtomicU8::new(0); // 0 = Text, 1 = Json
+static MIN_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Trace as u8);
+static STDERR_ONLY: AtomicBool = AtomicBool::new(false);
+/// Whether text records written to stdout and to stderr are colored, see set_color.
+static COLOR_STDOUT: AtomicBool = AtomicBool::new(false);
+static COLOR_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Width of the level column of text records, "CRITICAL:" being the longest.
+const LEVEL_WIDTH: usize = 9;
+
+/// Receives the formatted records instead of stdout/stderr while set, see set_sink.
+type Sink = Box<dyn Fn(LogLevel, &str) + Send + Sync>;
+static SINK: RwLock<Option<Sink>> = RwLock::new(None);
+
+thread_local! {
+    /// The context of the records logged on this thread, see with_context.
+    static CONTEXT: RefCell<LogContext> = const {
+        RefCell::new(LogContext { pipeline: None, stage: None, item_id: None })
+    };
+}
+
+/**
+ * @brief Write all log records to stderr, keeping stdout free for
+ *        machine-readable output.
+ *
+ * @param stderr_only True to send every record to stderr.
+ */
+pub fn set_stderr_only(stderr_only: bool) {
+    STDERR_ONLY.store(stderr_only, Ordering::Relaxed);
+}
+
+/**
+ * @brief Select when text log records are colored.
+ *
+ * Records are not colored until this is called. With ColorChoice::Auto
+ * each stream is colored if it is a terminal, unless NO_COLOR is set to a
+ * non-empty value (see https://no-color.org).
+ *
+ * @param choice When to color.
+ */
+pub fn set_color(choice: ColorChoice) {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let colored = |terminal: bool| match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => terminal && !no_color,
+    };
+    COLOR_STDOUT.store(colored(io::stdout().is_terminal()), Ordering::Relaxed);
+    COLOR_STDERR.store(colored(io::stderr().is_terminal()), Ordering::Relaxed);
+}
+
+/**
+ * @brief Hand all log records to a function instead of writing them to
+ *        stdout/stderr, e.g. while the terminal dashboard owns the screen.
+ *
+ * @param sink Receives the level and the formatted record, or None to write
+ *        records to stdout/stderr again.
+ */
+pub fn set_sink(sink: Option<Sink>) {
+    *SINK.write().unwrap_or_else(|e| e.into_inner()) = sink;
+}
+
+/**
+ * @brief Suppress all log records below the given level.
+ *
+ * Everything is logged by default.
+ *
+ * @param level The lowest level that is still logged.
+ */
+pub fn set_min_level(level: LogLevel) {
+    MIN_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/**
+ * @brief Check whether records of a level are currently logged.
+ *
+ * The log_* macros check this before formatting their message.
+ *
+ * @param level The level to check.
+ * @return bool True if records of this level are emitted.
+ */
+pub fn enabled(level: LogLevel) -> bool {
+    level as u8 >= MIN_LEVEL.load(Ordering::Relaxed)
+}
+
+/**
+ * @brief Select the output format for all subsequent log records.
+ *
+ * @param format The log format to use.
+ */
+pub fn set_format(format: LogFormat) {
+    let raw = match format {
+        LogFormat::Text => 0,
+        LogFormat::Json => 1,
+    };
+    LOG_FORMAT.store(raw, Ordering::Relaxed);
+}
+
+/**
+ * @brief Return the currently active log format.
+ *
+ * @return LogFormat The active log format.
+ */
+pub fn format() -> LogFormat {
+    match LOG_FORMAT.load(Ordering::Relaxed) {
+        1 => LogFormat::Json,
+        _ => LogFormat::Text,
+    }
+}
+
+/**
+ * @brief Run a function with a context attached to the records it logs on
+ *        this thread.
+ *
+ * Threads started for the work (e.g. the worker pool) take over the context
+ * of the thread that starts them this way.
+ *
+ * @param context The context, replacing the current one.
+ * @param f The function to run.
+ * @return R What the function returned; the previous context is restored.
+ */
+pub fn with_context<R>(context: LogContext, f: impl FnOnce() -> R) -> R {
+    let _guard = enter(context);
+    f()
+}
+
+/**
+ * @brief Return the context of the records logged on this thread.
+ *
+ * @return LogContext The context, see with_context.
+ */
+pub fn context() -> LogContext {
+    CONTEXT.with(|current| current.borrow().clone())
+}
+
+/**
+ * @brief Replace the context of this thread until the returned guard is dropped.
+ */
+fn enter(context: LogContext) -> ContextGuard {
+    ContextGuard {
+        previous: CONTEXT.with(|current| current.replace(context)),
+    }
+}
+
+/**
+ * @brief Log the records of this thread as part of a stage of the run, until
+ *        the returned guard is dropped.
+ *
+ * @param stage The stage, e.g. "load".
+ * @return ContextGuard Restores the previous context (and stage) when dropped.
+ */
+pub fn enter_stage(stage: &str) -> ContextGuard {
+    let mut context = context();
+    context.stage = Some(stage.to_string());
+    enter(context)
+}
+
+/**
+ * @brief Log the records of this thread as about an item, until the returned
+ *        guard is dropped.
+ *
+ * @param item_id The item's id.
+ * @return ContextGuard Restores the previous context when dropped, which also
+ *         undoes set_stage calls made for the item.
+ */
+pub fn enter_item(item_id: &str) -> ContextGuard {
+    let mut context = context();
+    context.item_id = Some(item_id.to_string());
+    enter(context)
+}
+
+/**
+ * @brief Move the records of this thread on to another stage, e.g. the next
+ *        step an item goes through; the enclosing guard restores the stage.
+ *
+ * @param stage The stage.
+ */
+pub fn set_stage(stage: &str) {
+    CONTEXT.with(|current| current.borrow_mut().stage = Some(stage.to_string()));
+}
+
+/**
+ * @brief Format a record as a text line, the level padded to LEVEL_WIDTH.
+ *
+ * @param colored Whether to color the level, see LogLevel::color.
+ */
+fn render_text(level: LogLevel, context: &LogContext, module: &str, message: &str, colored: bool) -> String {
+    let label = format!("{}:", level.as_str());
+    let padding = " ".repeat(LEVEL_WIDTH + 1 - label.len());
+    let label = match level.color() {
+        Some(color) if colored => format!("\x1b[{}m{}\x1b[0m", color, label),
+        _ => label,
+    };
+    if context.is_empty() {
+        format!("{}{}[{}] {}", label, padding, module, message)
+    } else {
+        format!("{}{}({}) [{}] {}", label, padding, context, module, message)
+    }
+}
+
+/**
+ * @brief Emit a single log record.
+ *
+ * Records of level WARNING and above go to stderr, all others to stdout
+ * (unless set_stderr_only is on), or all to the sink set with set_sink.
+ * Prefer the `log_*!` macros over calling this directly.
+ *
+ * @param level Severity of the record.
+ * @param module Where the record originates, e.g. "main:run_processing_pipeline".
+ * @param message The formatted log message.
+ */
+pub fn log(level: LogLevel, module: &str, message: &str) {
+    let context = context();
+    let sink = SINK.read().unwrap_or_else(|e| e.into_inner());
+    let to_stderr = level >= LogLevel::Warning || STDERR_ONLY.load(Ordering::Relaxed);
+    let colored = sink.is_none() && if to_stderr { &COLOR_STDERR } else { &COLOR_STDOUT }.load(Ordering::Relaxed);
+    let line = match format() {
+        LogFormat::Text => render_text(level, &context, module, message, colored),
+        LogFormat::Json => {
+            let record = LogRecord {
+                timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+                level: level.as_str(),
+                module,
+                context: &context,
+                message,
+            };
+            serde_json::to_string(&record).unwrap_or_else(|e| {
+                format!(
+                    "{{\"level\":\"ERROR\",\"module\":\"logging\",\"message\":\"Failed to serialize log record: {}\"}}",
+                    e
+                )
+            })
+        }
+    };
+
+    if let Some(sink) = sink.as_ref() {
+        sink(level, &line);
+    } else if to_stderr {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Log a TRACE record: `log_trace!("module:function", "format {}", args)`.
+#[macro_export]
+macro_rules! log_trace {
+    ($module:expr, $($arg:tt)*) => {
+        if $crate::logging::enabled($crate::logging::LogLevel::Trace) {
+            $crate::logging::log($crate::logging::LogLevel::Trace, $module, &format!($($arg)*))
+        }
+    };
+}
+
+/// Log a DEBUG record: `log_debug!("module:function", "format {}", args)`.
+#[macro_export]
+macro_rules! log_debug {
+    ($module:expr, $($arg:tt)*) => {
+        if $crate::logging::enabled($crate::logging::LogLevel::Debug) {
+            $crate::logging::log($crate::logging::LogLevel::Debug, $module, &format!($($arg)*))
+        }
+    };
+}
+
+/// Log an INFO record: `log_info!("module:function", "format {}", args)`.
+#[macro_export]
+macro_rules! log_info {
+    ($module:expr, $($arg:tt)*) => {
+        if $crate::logging::enabled($crate::logging::LogLevel::Info) {
+            $crate::logging::log($crate::logging::LogLevel::Info, $module, &format!($($arg)*))
+        }
+    };
+}
+
+/// Log a WARNING record: `log_warning!("module:function", "format {}", args)`.
+#[macro_export]
+macro_rules! log_warning {
+    ($module:expr, $($arg:tt)*) => {
+        if $crate::logging::enabled($crate::logging::LogLevel::Warning) {
+            $crate::logging::log($crate::logging::LogLevel::Warning, $module, &format!($($arg)*))
+        }
+    };
+}
+
+/// Log an ERROR record: `log_error!("module:function", "format {}", args)`.
+#[macro_export]
+macro_rules! log_error {
+    ($module:expr, $($arg:tt)*) => {
+        if $crate::logging::enabled($crate::logging::LogLevel::Error) {
+            $crate::logging::log($crate::logging::LogLevel::Error, $module, &format!($($arg)*))
+        }
+    };
+}
+
+/// Log a CRITICAL record: `log_critical!("module:function", "format {}", args)`.
+#[macro_export]
+macro_rules! log_critical {
+    ($module:expr, $($arg:tt)*) => {
+        if $crate::logging::enabled($crate::logging::LogLevel::Critical) {
+            $crate::logging::log($crate::logging::LogLevel::Critical, $module, &format!($($arg)*))
+        }
+    };
+}
+
+// End of rust_sample_project/src/logging.rs
@@ -1,142 +1,2519 @@
 // rust_sample_project/src/main.rs
 
-// Declare modules that are part of this crate (files in the same directory or subdirectories)
-mod config;
-mod data_handler;
-mod item;
-mod item_processor;
+// The pipeline's modules live in the library crate (src/lib.rs), so they can
+// also be used by benchmarks and embedding applications.
+use rust_sample_project::{
+    daemon, encryption, error, load_cache, logging, metrics, pipeline, repl, shutdown, snapshot, staging,
+};
+use rust_sample_project::{log_critical, log_error, log_info, log_warning, tr};
+#[cfg(feature = "grpc")]
+use rust_sample_project::grpc_server;
+#[cfg(feature = "kafka")]
+use rust_sample_project::kafka_stream;
+#[cfg(feature = "otel")]
+use rust_sample_project::telemetry;
+#[cfg(feature = "tui")]
+use rust_sample_project::tui;
 
 // Bring specific items into scope for easier use
-use crate::config::{get_data_path, get_threshold, get_log_level}; // Corrected: use config::get_log_level
-use crate::data_handler::DataHandler;
-use crate::item::Item;
-use crate::item_processor::ItemProcessor;
+use rust_sample_project::completions::{self, Arg, Cli, Shell, Value};
+use rust_sample_project::config::{
+    Config, ConfigErrors, ConfigProblem, ConfigSource, CONFIG_FILE_PATH, CONFIG_RELOAD_POLL_SECS,
+};
+use rust_sample_project::data_handler::DataFormat;
+use rust_sample_project::datagen::{self, DatagenOptions, ValueDistribution};
+use rust_sample_project::delivery;
+use rust_sample_project::diff::DatasetDiff;
+use rust_sample_project::docs;
+use rust_sample_project::enrichment::Enrichment;
+use rust_sample_project::error::PipelineError;
+use rust_sample_project::guard;
+use rust_sample_project::health::{self, Health};
+use rust_sample_project::history::{self, HistoryTrend, RunRecord};
+use rust_sample_project::hooks::PipelineHooks;
+use rust_sample_project::item::Item;
+use rust_sample_project::item_id::{self, ItemId};
+use rust_sample_project::join::{self, JoinType};
+use rust_sample_project::lineage::ItemHistory;
+use rust_sample_project::lock::{DataLock, LockPolicy};
+use rust_sample_project::logging::{ColorChoice, LogContext, LogFormat, LogLevel};
+use rust_sample_project::metrics::Metrics;
+use rust_sample_project::pipeline_file::PipelineDefinition;
+use rust_sample_project::report::{ItemResult, MultiRunResult, PipelineReport, PipelineRunResult, RunResult};
+use rust_sample_project::schedule::{CronSchedule, MissedRunPolicy};
+use rust_sample_project::storage;
+use rust_sample_project::what_if::{RuleComparison, RuleSet, WhatIfReport};
 
+use chrono::{DateTime, Utc};
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::process::exit; // For program termination with a code
+use std::fs;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/**
+ * @brief How often waiting loops check whether a shutdown was requested.
+ */
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(200);
 
 // For simplistic logging similar to Python's basicConfig,
-// we'll just use println! and eprintln!
+// all modules log through the log_* macros of the logging module.
 // A more robust solution would use the `log` crate and an implementation like `env_logger`.
 
 /**
  * @brief Set up basic logging for the main application execution.
  *
- * In this simple version, it primarily prints an informational message.
- * It retrieves the log level from config but doesn't fully implement
- * log level filtering as `basicConfig` would.
+ * Selects the log output format (text or JSON), when text records are
+ * colored and the lowest logged level from the effective config, which
+ * includes --log-format, --color, -q and -v.
+ *
+ * @param config The effective configuration.
+ * @return Result<(), String> Ok once logging is set up, or an error message
+ *         for an unknown log format or level.
  */
-fn setup_main_logging() {
-    // Retrieve log level from config
-    let log_level_str = get_log_level(); // Use the imported function
+fn setup_main_logging(config: &Config) -> Result<(), String> {
+    // Retrieve log level and format from config
+    let log_level_str = config.log_level.as_str();
+    let log_format_str = config.log_format.as_str();
+    logging::set_format(LogFormat::parse(log_format_str)?);
+    logging::set_color(ColorChoice::parse(&config.log_color)?);
+    logging::set_min_level(LogLevel::parse(log_level_str)?);
 
-    // In a real app, initialize a logger here, e.g., env_logger::init();
-    // or another logger based on log_level_str.
-    // For now, just print the intended level.
-    println!(
-        "INFO: [main:setup_main_logging] Main logging setup. Effective level from config: {}",
-        log_level_str
+    log_info!(
+        "main:setup_main_logging",
+        "Main logging setup. Effective level from config: {}, format: {}",
+        log_level_str,
+        log_format_str
     );
-    // This is a placeholder. Actual log level filtering would require a logging crate.
+    Ok(())
+}
+
+/**
+ * @struct CliOptions
+ * @brief Command-line options accepted by the application.
+ */
+#[derive(Clone)]
+struct CliOptions {
+    /// Run as a daemon that triggers pipeline runs over HTTP (`serve` subcommand).
+    serve: bool,
+    /// Serve the gRPC service instead of the HTTP API (`serve --grpc`).
+    grpc: bool,
+    /// Address the daemon API, or with --grpc the gRPC service, listens on (`--listen <addr>`).
+    listen: Option<String>,
+    /// Process all items, also those already processed, in incremental mode (`--force`).
+    force: bool,
+    /// Let join, generate and rollback replace existing data files (`--allow-overwrite`).
+    allow_overwrite: bool,
+    /// Resume an interrupted run from the checkpoint file (`--resume`).
+    resume: bool,
+    /// Keep running and re-run the pipeline periodically (`--watch`).
+    watch: bool,
+    /// Keep running and run the pipeline at the times of the schedule config key (`schedule` subcommand).
+    schedule: bool,
+    /// Stream an NDJSON data file line by line, or else items from Kafka (`--stream`).
+    stream: bool,
+    /// Re-read unchanged data files on every run in watch, schedule and daemon modes (`--no-cache`).
+    no_cache: bool,
+    /// Write Prometheus metrics to this textfile after each run (`--metrics-file <path>`).
+    metrics_file: Option<PathBuf>,
+    /// Serve Prometheus metrics on this address in watch and schedule modes (`--metrics-addr <addr>`).
+    metrics_addr: Option<String>,
+    /// Log output format, "text" or "json" (`--log-format <format>`).
+    log_format: Option<String>,
+    /// When text log records are colored, "auto", "always" or "never" (`--color <when>`).
+    color: Option<String>,
+    /// Only log errors and the final summary (`-q` / `--quiet`).
+    quiet: bool,
+    /// Log more detail: 1 for DEBUG, 2 for TRACE (`-v`, `-vv`, `--verbose`).
+    verbosity: u8,
+    /// Expression selecting the items to process (`--filter <expr>`).
+    filter: Option<String>,
+    /// Longest time in seconds spent processing (`--max-duration <secs>`).
+    max_duration: Option<String>,
+    /// Number of items to process in a sample run (`--sample <n>`).
+    sample: Option<String>,
+    /// Percentage of items to process in a sample run (`--sample-percent <p>`).
+    sample_percent: Option<String>,
+    /// Which items a join keeps (`--join-type <left|inner>`).
+    join_type: Option<String>,
+    /// Registered stages to apply, added to the custom_stages config key (`--stage <name>`, repeatable).
+    stages: Vec<String>,
+    /// Write a synthetic dataset instead of running the pipeline (`generate` subcommand).
+    generate: bool,
+    /// Number of records to generate (`--count <n>`).
+    count: Option<String>,
+    /// Format of the result, "text" or "json" (`--output <format>`).
+    output: Option<String>,
+    /// File the generated dataset is written to (`generate --output-file <path>`).
+    output_file: Option<PathBuf>,
+    /// Seed for a reproducible dataset (`--seed <n>`).
+    seed: Option<String>,
+    /// Value distribution of generated items (`--distribution <spec>`).
+    distribution: Option<String>,
+    /// Name pattern of generated items (`--name-pattern <pattern>`).
+    name_pattern: Option<String>,
+    /// Share of deliberately invalid generated records (`--invalid-ratio <ratio>`).
+    invalid_ratio: Option<String>,
+    /// Config file to read instead of the default one (`--config <path>`).
+    config_file: Option<PathBuf>,
+    /// Config file profile to apply, instead of APP_PROFILE (`--profile <name>`).
+    profile: Option<String>,
+    /// Tenant of the config file to run for (`--tenant <name>`).
+    tenant: Option<String>,
+    /// Print the effective configuration and exit (`config show` subcommand).
+    config_show: bool,
+    /// Clean up after runs that died while saving, then exit (`recover` subcommand).
+    recover: bool,
+    /// Restore the data files from a snapshot, or list the snapshots, then exit (`rollback` subcommand).
+    rollback: bool,
+    /// Snapshot to restore, or "latest" (`rollback --snapshot <id>`).
+    snapshot: Option<String>,
+    /// Print the history of an item and exit (`lineage <id>` subcommand).
+    lineage: Option<String>,
+    /// Print the recorded runs, or with a run id one of them, and exit
+    /// (`history list` / `history show <run_id>` subcommand).
+    history: Option<Option<String>>,
+    /// Print the items delivered to network sinks, or with a key one delivery,
+    /// and exit (`ledger list` / `ledger show <key>` subcommand).
+    ledger: Option<Option<String>>,
+    /// Join a secondary data file into a primary one and exit (`join <primary> <secondary> <output>` subcommand).
+    join: Option<(PathBuf, PathBuf, PathBuf)>,
+    /// Compare two data files and exit (`diff <before> <after>` subcommand).
+    diff: Option<(PathBuf, PathBuf)>,
+    /// Explore the items of the data file in a REPL (`interactive` subcommand).
+    interactive: bool,
+    /// Report what processing would change, without changing it (`what-if` subcommand).
+    what_if: bool,
+    /// Threshold to preview with what-if, instead of the configured one (`--threshold <value>`).
+    threshold: Option<String>,
+    /// Compare the rules of two config files on the same items (`compare` subcommand).
+    compare: bool,
+    /// Config file with the rules in use (`compare --baseline <path>`).
+    baseline: Option<PathBuf>,
+    /// Config file with the rules to compare them with (`compare --candidate <path>`).
+    candidate: Option<PathBuf>,
+    /// Show the terminal dashboard instead of log lines during --watch and serve runs (`--tui`).
+    tui: bool,
+    /// Run the pipeline described in a definition file (`run --pipeline <path>`).
+    pipeline_file: Option<PathBuf>,
+    /// Run several pipelines of the config file at once (`--pipelines <name,...|all>`).
+    pipelines: Option<String>,
+    /// Print the JSON Schema of the config file and exit (`config schema` subcommand).
+    config_schema: bool,
+    /// Print the completion script for a shell and exit (`completions <shell>` subcommand).
+    completions: Option<Shell>,
+    /// Write the man page and Markdown reference into this directory and exit
+    /// (`gen-docs <dir>`, hidden: for keeping the published docs in sync).
+    gen_docs: Option<PathBuf>,
+}
+
+/// The command line as parse_cli_options accepts it, for `completions <shell>` and `gen-docs`.
+/// parse_cli_options rejects flags missing from it, and the tests check every entry against
+/// the parser and USAGE.
+const CLI: Cli = Cli {
+    program: "rust_sample_project",
+    subcommands: &[
+        Arg { name: "run", help: "Run the pipeline once (the default)", value: Value::None },
+        Arg { name: "serve", help: "Run as a daemon triggering runs over HTTP or gRPC", value: Value::None },
+        Arg { name: "schedule", help: "Run the pipeline on the configured cron schedule", value: Value::None },
+        Arg {
+            name: "config",
+            help: "Print the effective configuration, or the JSON Schema of the config file",
+            value: Value::Choices(&["show", "schema"]),
+        },
+        Arg { name: "recover", help: "Clean up after runs that died while saving", value: Value::None },
+        Arg { name: "rollback", help: "Restore the data files from a snapshot", value: Value::None },
+        Arg { name: "lineage", help: "Print the history of an item", value: Value::Text },
+        Arg { name: "history", help: "Print the recorded runs", value: Value::Choices(&["list", "show"]) },
+        Arg {
+            name: "ledger",
+            help: "Print the items delivered to network sinks",
+            value: Value::Choices(&["list", "show"]),
+        },
+        Arg { name: "join", help: "Join a secondary data file into a primary one", value: Value::Path },
+        Arg { name: "diff", help: "Compare two data files", value: Value::Path },
+        Arg { name: "interactive", help: "Explore the items of the data file in a REPL", value: Value::None },
+        Arg { name: "what-if", help: "Report what processing would change", value: Value::None },
+        Arg { name: "compare", help: "Compare the rules of two config files", value: Value::None },
+        Arg { name: "generate", help: "Generate a synthetic data file", value: Value::None },
+        Arg { name: "completions", help: "Print a shell completion script", value: Value::Choices(Shell::NAMES) },
+    ],
+    flags: &[
+        Arg { name: "--grpc", help: "Serve the gRPC service instead of the HTTP API", value: Value::None },
+        Arg { name: "--listen", help: "Address the daemon listens on", value: Value::Text },
+        Arg { name: "--resume", help: "Resume an interrupted run from the checkpoint", value: Value::None },
+        Arg { name: "--force", help: "Process all items, also those already processed", value: Value::None },
+        Arg {
+            name: "--allow-overwrite",
+            help: "Let generate, join and rollback replace existing data files",
+            value: Value::None,
+        },
+        Arg { name: "--watch", help: "Keep running and re-run the pipeline periodically", value: Value::None },
+        Arg {
+            name: "--stream",
+            help: "Stream an NDJSON data file line by line, or else items from Kafka",
+            value: Value::None,
+        },
+        Arg { name: "--no-cache", help: "Re-read unchanged data files on every run", value: Value::None },
+        Arg { name: "--tui", help: "Show the terminal dashboard", value: Value::None },
+        Arg { name: "--metrics-file", help: "Write Prometheus metrics to this file", value: Value::Path },
+        Arg { name: "--metrics-addr", help: "Serve Prometheus metrics on this address", value: Value::Text },
+        Arg { name: "--log-format", help: "Log output format", value: Value::Choices(&["text", "json"]) },
+        Arg { name: "--color", help: "When to color log records", value: Value::Choices(&["auto", "always", "never"]) },
+        Arg { name: "-q", help: "Only log errors and the final summary", value: Value::None },
+        Arg { name: "--quiet", help: "Only log errors and the final summary", value: Value::None },
+        Arg { name: "-v", help: "Log more detail", value: Value::None },
+        Arg { name: "--verbose", help: "Log more detail", value: Value::None },
+        Arg { name: "-vv", help: "Log even more detail, down to every item", value: Value::None },
+        Arg { name: "--output", help: "Result format", value: Value::Choices(&["text", "json"]) },
+        Arg { name: "--output-file", help: "File the generated dataset is written to", value: Value::Path },
+        Arg { name: "--filter", help: "Only process items matching this expression", value: Value::Text },
+        Arg { name: "--max-duration", help: "Stop the run after this many seconds", value: Value::Text },
+        Arg { name: "--sample", help: "Process a random sample of this many items", value: Value::Text },
+        Arg { name: "--sample-percent", help: "Process a random sample of this percentage", value: Value::Text },
+        Arg {
+            name: "--join-type",
+            help: "How join handles unmatched items",
+            value: Value::Choices(&["left", "inner"]),
+        },
+        Arg { name: "--stage", help: "Run this custom stage as well", value: Value::Text },
+        Arg { name: "--snapshot", help: "Snapshot to restore, or latest", value: Value::Text },
+        Arg { name: "--threshold", help: "Threshold to preview with what-if", value: Value::Text },
+        Arg { name: "--baseline", help: "Config file with the rules in use", value: Value::Path },
+        Arg { name: "--candidate", help: "Config file with the rules to compare", value: Value::Path },
+        Arg { name: "--pipeline", help: "Run the pipeline of this definition file", value: Value::Path },
+        Arg { name: "--pipelines", help: "Run these pipelines of the config file, or all", value: Value::Text },
+        Arg { name: "--config", help: "Read the configuration from this file", value: Value::Path },
+        Arg { name: "--profile", help: "Use this profile of the config file", value: Value::Text },
+        Arg { name: "--tenant", help: "Run for this tenant of the config file", value: Value::Text },
+        Arg { name: "--count", help: "Number of items to generate", value: Value::Text },
+        Arg { name: "--seed", help: "Seed of the generated data", value: Value::Text },
+        Arg { name: "--distribution", help: "Distribution of the generated values", value: Value::Text },
+        Arg { name: "--name-pattern", help: "Pattern of the generated names", value: Value::Text },
+        Arg { name: "--invalid-ratio", help: "Share of invalid generated items", value: Value::Text },
+    ],
+    usage: USAGE,
+};
+
+/// The usage message printed on command-line errors; names every entry of CLI.
+const USAGE: &str = "Usage: rust_sample_project [serve [--grpc] [--listen <addr>]] [--resume] [--force] [--watch] [--stream] [--no-cache] [--tui] [--metrics-file <path>] [--metrics-addr <addr>] [--log-format <text|json>] [--color <auto|always|never>] [-q | --quiet | -v | --verbose | -vv] [--output <text|json>] [--filter <expr>] [--max-duration <secs>] [--sample <n> | --sample-percent <p>] [--stage <name>]... [--config <path>] [--profile <name>] [--tenant <name>]\n       rust_sample_project schedule [--resume] [--force] [--no-cache] [--metrics-file <path>] [--metrics-addr <addr>] [--config <path>] [--profile <name>]\n       rust_sample_project run --pipeline <path> [--metrics-file <path>] [--config <path>] [--profile <name>]\n       rust_sample_project run --pipelines <name,...|all> [--resume] [--force] [--output <text|json>] [--metrics-file <path>] [--config <path>] [--profile <name>]\n       rust_sample_project config show [--config <path>] [--profile <name>]\n       rust_sample_project config schema\n       rust_sample_project recover [--config <path>] [--profile <name>]\n       rust_sample_project rollback [--snapshot <id|latest>] [--allow-overwrite] [--config <path>] [--profile <name>]\n       rust_sample_project lineage <id> [--output <text|json>] [--config <path>] [--profile <name>]\n       rust_sample_project history list|show <run_id> [--output <text|json>] [--config <path>] [--profile <name>]\n       rust_sample_project ledger list|show <key> [--output <text|json>] [--config <path>] [--profile <name>]\n       rust_sample_project join <primary> <secondary> <output> [--join-type <left|inner>] [--allow-overwrite] [--output <text|json>] [--config <path>] [--profile <name>]\n       rust_sample_project diff <before> <after> [--output <text|json>]\n       rust_sample_project interactive [--filter <expr>] [--config <path>] [--profile <name>]\n       rust_sample_project what-if [--threshold <value>] [--filter <expr>] [--output <text|json>] [--config <path>] [--profile <name>] [--tenant <name>]\n       rust_sample_project compare --baseline <path> --candidate <path> [--output <text|json>] [--config <path>] [--profile <name>]\n       rust_sample_project generate [--count <n>] [--output-file <path>] [--seed <n>] [--distribution <spec>] [--name-pattern <pattern>] [--invalid-ratio <ratio>] [--allow-overwrite]\n       rust_sample_project completions bash|zsh|fish|powershell";
+
+/**
+ * @brief Parse the command-line arguments into CliOptions.
+ *
+ * @param args The arguments, without the program name.
+ * @return Result<CliOptions, String> The parsed options, or an error message
+ *         naming the unrecognized argument.
+ */
+fn parse_cli_options(args: impl IntoIterator<Item = OsString>) -> Result<CliOptions, String> {
+    let mut options = CliOptions {
+        serve: false,
+        grpc: false,
+        listen: None,
+        force: false,
+        allow_overwrite: false,
+        resume: false,
+        watch: false,
+        schedule: false,
+        stream: false,
+        no_cache: false,
+        metrics_file: None,
+        metrics_addr: None,
+        log_format: None,
+        color: None,
+        quiet: false,
+        verbosity: 0,
+        filter: None,
+        max_duration: None,
+        sample: None,
+        sample_percent: None,
+        join_type: None,
+        stages: Vec::new(),
+        generate: false,
+        count: None,
+        output: None,
+        output_file: None,
+        seed: None,
+        distribution: None,
+        name_pattern: None,
+        invalid_ratio: None,
+        config_file: None,
+        profile: None,
+        tenant: None,
+        config_show: false,
+        recover: false,
+        rollback: false,
+        snapshot: None,
+        lineage: None,
+        history: None,
+        ledger: None,
+        join: None,
+        diff: None,
+        interactive: false,
+        what_if: false,
+        threshold: None,
+        compare: false,
+        baseline: None,
+        candidate: None,
+        tui: false,
+        pipeline_file: None,
+        pipelines: None,
+        config_schema: false,
+        completions: None,
+        gen_docs: None,
+    };
+    let mut args = args.into_iter();
+    while let Some(raw_arg) = args.next() {
+        // Flags are text; only the values following them (paths) may be other than UTF-8
+        let raw_arg = raw_arg
+            .into_string()
+            .map_err(|arg| format!("Unrecognized argument: {}", arg.to_string_lossy()))?;
+        // Accept both "--flag value" and "--flag=value"
+        let (arg, mut inline_value) = match raw_arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (raw_arg, None),
+        };
+        // Only the flags of CLI exist, so completions and the docs cannot miss one
+        if arg.starts_with('-') && !CLI.flags.iter().any(|flag| flag.name == arg) {
+            return Err(format!("Unrecognized argument: {}", arg));
+        }
+        match arg.as_str() {
+            "run" => {} // The default mode; only needed before --pipeline(s) for readability
+            "serve" => options.serve = true,
+            "--grpc" => options.grpc = true,
+            "--listen" => options.listen = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--resume" => options.resume = true,
+            "--force" => options.force = true,
+            "--allow-overwrite" => options.allow_overwrite = true,
+            "--watch" => options.watch = true,
+            "schedule" => options.schedule = true,
+            "--stream" => options.stream = true,
+            "--no-cache" => options.no_cache = true,
+            "--tui" => options.tui = true,
+            "--metrics-file" => {
+                options.metrics_file = Some(PathBuf::from(next_value(&mut args, &mut inline_value, &arg)?))
+            }
+            "--metrics-addr" => options.metrics_addr = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--log-format" => options.log_format = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--color" => options.color = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "-q" | "--quiet" => options.quiet = true,
+            "-v" | "--verbose" => options.verbosity = options.verbosity.saturating_add(1),
+            "-vv" => options.verbosity = options.verbosity.saturating_add(2),
+            "--filter" => options.filter = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--max-duration" => options.max_duration = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--sample" => options.sample = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--sample-percent" => options.sample_percent = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--join-type" => options.join_type = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--stage" => options.stages.push(next_text(&mut args, &mut inline_value, &arg)?),
+            "generate" => options.generate = true,
+            "config" => match args.next().as_ref().and_then(|arg| arg.to_str()) {
+                Some("show") => options.config_show = true,
+                Some("schema") => options.config_schema = true,
+                _ => return Err(String::from("Expected 'config show' or 'config schema'")),
+            },
+            "recover" => options.recover = true,
+            "rollback" => options.rollback = true,
+            "--snapshot" => options.snapshot = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--threshold" => options.threshold = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--baseline" => {
+                options.baseline = Some(PathBuf::from(next_value(&mut args, &mut inline_value, &arg)?))
+            }
+            "--candidate" => {
+                options.candidate = Some(PathBuf::from(next_value(&mut args, &mut inline_value, &arg)?))
+            }
+            "lineage" => match args.next().map(OsString::into_string) {
+                Some(Ok(id)) => options.lineage = Some(id),
+                _ => return Err(String::from("Expected 'lineage <id>'")),
+            },
+            "history" => match args.next().as_ref().and_then(|arg| arg.to_str()) {
+                Some("list") => options.history = Some(None),
+                Some("show") => match args.next().map(OsString::into_string) {
+                    Some(Ok(run_id)) => options.history = Some(Some(run_id)),
+                    _ => return Err(String::from("Expected 'history show <run_id>'")),
+                },
+                _ => return Err(String::from("Expected 'history list' or 'history show <run_id>'")),
+            },
+            "ledger" => match args.next().as_ref().and_then(|arg| arg.to_str()) {
+                Some("list") => options.ledger = Some(None),
+                Some("show") => match args.next().map(OsString::into_string) {
+                    Some(Ok(key)) => options.ledger = Some(Some(key)),
+                    _ => return Err(String::from("Expected 'ledger show <key>'")),
+                },
+                _ => return Err(String::from("Expected 'ledger list' or 'ledger show <key>'")),
+            },
+            "join" => match (args.next(), args.next(), args.next()) {
+                (Some(primary), Some(secondary), Some(output)) => {
+                    options.join = Some((PathBuf::from(primary), PathBuf::from(secondary), PathBuf::from(output)))
+                }
+                _ => return Err(String::from("Expected 'join <primary> <secondary> <output>'")),
+            },
+            "diff" => match (args.next(), args.next()) {
+                (Some(before), Some(after)) => options.diff = Some((PathBuf::from(before), PathBuf::from(after))),
+                _ => return Err(String::from("Expected 'diff <before> <after>'")),
+            },
+            "interactive" => options.interactive = true,
+            "what-if" => options.what_if = true,
+            "compare" => options.compare = true,
+            "completions" => match args.next().as_ref().and_then(|arg| arg.to_str()) {
+                Some(shell) => options.completions = Some(Shell::parse(shell)?),
+                None => return Err(String::from("Expected 'completions <shell>'")),
+            },
+            "gen-docs" => match args.next() {
+                Some(dir) => options.gen_docs = Some(PathBuf::from(dir)),
+                None => return Err(String::from("Expected 'gen-docs <dir>'")),
+            },
+            "--pipeline" => {
+                options.pipeline_file = Some(PathBuf::from(next_value(&mut args, &mut inline_value, &arg)?))
+            }
+            "--pipelines" => options.pipelines = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--config" => {
+                options.config_file = Some(PathBuf::from(next_value(&mut args, &mut inline_value, &arg)?))
+            }
+            "--profile" => options.profile = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--tenant" => options.tenant = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--count" => options.count = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--output" => options.output = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--output-file" => {
+                options.output_file = Some(PathBuf::from(next_value(&mut args, &mut inline_value, &arg)?))
+            }
+            "--seed" => options.seed = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--distribution" => options.distribution = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--name-pattern" => options.name_pattern = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            "--invalid-ratio" => options.invalid_ratio = Some(next_text(&mut args, &mut inline_value, &arg)?),
+            other => return Err(format!("Unrecognized argument: {}", other)),
+        }
+        if inline_value.is_some() {
+            return Err(format!("Argument {} does not take a value", arg));
+        }
+    }
+    Ok(options)
 }
 
 /**
- * @brief Execute the main data processing pipeline.
+ * @brief Take the value of a command-line flag.
  *
- * Orchestrates the loading, processing, and saving of data items using
- * configuration settings and dedicated handler/processor classes.
+ * Uses the inline value of "--flag=value" if present, otherwise the next argument.
  *
- * @return Result<(), String> Ok(()) on success, or an Err(String) on failure.
+ * @param args The remaining command-line arguments.
+ * @param inline_value The value given after '=', consumed by this call.
+ * @param flag The flag whose value is expected, used in the error message.
+ * @return Result<OsString, String> The flag's value, which need not be valid
+ *         UTF-8, or an error message if it is missing.
  */
-fn run_processing_pipeline() -> Result<(), String> {
-    println!("INFO: [main:run_processing_pipeline] Starting Sample Project Rust processing pipeline...");
+fn next_value(
+    args: &mut impl Iterator<Item = OsString>,
+    inline_value: &mut Option<String>,
+    flag: &str,
+) -> Result<OsString, String> {
+    inline_value
+        .take()
+        .map(OsString::from)
+        .or_else(|| args.next())
+        .ok_or_else(|| format!("Missing value for argument: {}", flag))
+}
 
-    // 1. Initialize components using configuration
-    let data_path: String = get_data_path().to_string(); // Get path and convert to owned String
-    let threshold: i32 = get_threshold();
+/**
+ * @brief Take the value of a command-line flag that must be text.
+ *
+ * @param args The remaining command-line arguments.
+ * @param inline_value The value given after '=', consumed by this call.
+ * @param flag The flag whose value is expected, used in the error message.
+ * @return Result<String, String> The flag's value, or an error message if it
+ *         is missing or not valid UTF-8.
+ */
+fn next_text(
+    args: &mut impl Iterator<Item = OsString>,
+    inline_value: &mut Option<String>,
+    flag: &str,
+) -> Result<String, String> {
+    next_value(args, inline_value, flag)?
+        .into_string()
+        .map_err(|_| format!("Value of argument {} is not valid UTF-8", flag))
+}
 
-    println!(
-        "INFO: [main:run_processing_pipeline] Config - Data Path: {}, Threshold: {}",
-        data_path, threshold
-    );
+/**
+ * @brief Build the effective configuration: defaults, config file, profile,
+ *        pipeline or tenant, environment and finally the command line.
+ *
+ * @param options The parsed command-line options.
+ * @param pipeline The pipeline of the config file to run, if any.
+ * @param tenant The tenant of the config file to run for, if any.
+ * @return (Config, Vec<ConfigProblem>) The configuration and the problems
+ *         found while resolving it, to be reported with the validation results.
+ */
+fn build_config(
+    options: &CliOptions,
+    pipeline: Option<&str>,
+    tenant: Option<&str>,
+) -> (Config, Vec<ConfigProblem>) {
+    let (config_file, profile) = (options.config_file.as_deref(), options.profile.as_deref());
+    let (mut config, mut problems) = match (pipeline, tenant) {
+        (Some(name), _) => Config::load_pipeline(config_file, profile, name),
+        (None, Some(name)) => Config::load_tenant(config_file, profile, name),
+        (None, None) => Config::load(config_file, profile),
+    };
+    let log_level = match (options.quiet, options.verbosity) {
+        (true, _) => Some(String::from("error")),
+        (false, 0) => None,
+        (false, 1) => Some(String::from("debug")),
+        (false, _) => Some(String::from("trace")),
+    };
+    let incremental = options.force.then(|| String::from("false"));
+    let allow_overwrite = options.allow_overwrite.then(|| String::from("true"));
+    let listen_key = if options.grpc { "grpc_addr" } else { "daemon_addr" };
+    let overrides = [
+        ("log_format", &options.log_format),
+        ("log_color", &options.color),
+        ("log_level", &log_level),
+        ("filter_expression", &options.filter),
+        ("max_duration_secs", &options.max_duration),
+        ("sample_size", &options.sample),
+        ("sample_percent", &options.sample_percent),
+        ("join_type", &options.join_type),
+        ("threshold", &options.threshold),
+        (listen_key, &options.listen),
+        ("incremental", &incremental),
+        ("allow_overwrite", &allow_overwrite),
+    ];
+    for (key, value) in overrides {
+        if let Some(value) = value {
+            if let Err(message) = config.set(key, value, ConfigSource::Cli) {
+                problems.push(ConfigProblem {
+                    key: key.to_string(),
+                    source: ConfigSource::Cli,
+                    message,
+                });
+            }
+        }
+    }
+    // --stage adds to the configured stages rather than replacing them
+    if !options.stages.is_empty() {
+        let stages = config.custom_stages.iter().chain(&options.stages).cloned().collect();
+        if let Err(message) = config.set_list("custom_stages", stages, ConfigSource::Cli) {
+            problems.push(ConfigProblem {
+                key: String::from("custom_stages"),
+                source: ConfigSource::Cli,
+                message,
+            });
+        }
+    }
+    (config, problems)
+}
 
-    let data_handler = DataHandler::new(data_path); // data_path is moved here
-    let item_processor = ItemProcessor::new(threshold);
+/**
+ * @struct ConfigReloader
+ * @brief Keeps the configuration of a long-running process in sync with its
+ *        config file.
+ */
+struct ConfigReloader {
+    /// The command-line options, re-applied on top of every reloaded file.
+    options: CliOptions,
+    /// The config file watched for changes.
+    path: PathBuf,
+    /// Modification time of the file when it was last read (None if missing).
+    modified: Option<SystemTime>,
+    /// The configuration currently in effect.
+    current: Config,
+    /// Why the config file was rejected when last read (None if it was valid).
+    rejected: Option<String>,
+}
 
-    // 2. Load data
-    let mut items_to_process: Vec<Item> = match data_handler.load_items() {
-        Ok(items) => items,
+impl ConfigReloader {
+    /**
+     * @brief Constructs a reloader for the config file used at startup.
+     *
+     * @param options The parsed command-line options.
+     * @param config The validated configuration in effect.
+     * @return ConfigReloader The reloader.
+     */
+    fn new(options: &CliOptions, config: &Config) -> Self {
+        let path = options.config_file.clone().unwrap_or_else(|| PathBuf::from(CONFIG_FILE_PATH));
+        ConfigReloader {
+            modified: modified_time(&path),
+            options: options.clone(),
+            path,
+            current: config.clone(),
+            rejected: None,
+        }
+    }
+
+    /**
+     * @brief Re-read the configuration if the config file changed, see reload.
+     */
+    fn reload_if_changed(&mut self) {
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        let _ = self.reload();
+    }
+
+    /**
+     * @brief Re-read the configuration from the config file.
+     *
+     * The new configuration is validated first; an invalid one is rejected
+     * with a log entry and the current configuration stays in effect.
+     *
+     * @return Result<Vec<String>, String> The changed values (none if no
+     *         value differs), or why the config file was rejected.
+     */
+    fn reload(&mut self) -> Result<Vec<String>, String> {
+        let (config, load_problems) = build_config(&self.options, None, None);
+        if let Err(e) = validate_config(&self.options, &config, load_problems) {
+            log_error!(
+                "main:ConfigReloader",
+                "Rejected config file {}, keeping the current configuration. {}",
+                self.path.display(),
+                e
+            );
+            let rejected = e.problems.iter().map(|problem| problem.to_string()).collect::<Vec<_>>().join("; ");
+            self.rejected = Some(rejected.clone());
+            return Err(rejected);
+        }
+        self.rejected = None;
+        let changes = self.current.changes(&config);
+        if changes.is_empty() {
+            log_info!("main:ConfigReloader", "Re-read config file {}, no values differ.", self.path.display());
+            return Ok(changes);
+        }
+        log_info!(
+            "main:ConfigReloader",
+            "Reloaded configuration from {}: {}",
+            self.path.display(),
+            changes.join(", ")
+        );
+        if config.daemon_addr != self.current.daemon_addr {
+            log_warning!(
+                "main:ConfigReloader",
+                "daemon_addr only takes effect after a restart."
+            );
+        }
+        if let Err(e) = setup_main_logging(&config) {
+            log_warning!("main:ConfigReloader", "{}", e);
+        }
+        config.apply_process_settings();
+        // Cached items may have been parsed with mappings or a key that changed
+        load_cache::invalidate_all();
+        self.current = config;
+        Ok(changes)
+    }
+
+    /**
+     * @brief Resolve the configuration of a tenant of the config file.
+     *
+     * The config file is read afresh, so the tenant's values are as current
+     * as the ones reload picks up.
+     *
+     * @param tenant The name of the tenant ([tenants.<name>]).
+     * @return Result<Config, String> The tenant's configuration, or why it is
+     *         not valid (an unknown tenant among them).
+     */
+    fn tenant_config(&self, tenant: &str) -> Result<Config, String> {
+        let (config, load_problems) = build_config(&self.options, None, Some(tenant));
+        validate_config(&self.options, &config, load_problems).map_err(|e| e.to_string())?;
+        Ok(config)
+    }
+}
+
+/**
+ * @brief Return the modification time of a file.
+ *
+ * @param path The file.
+ * @return Option<SystemTime> The time, or None if the file does not exist.
+ */
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/**
+ * @brief Print the effective configuration with the source of each value.
+ *
+ * The output is valid TOML, so it can be used as a starting point for a
+ * config file.
+ *
+ * @param config The effective configuration.
+ */
+fn show_config(config: &Config) {
+    if let Some(profile) = &config.profile {
+        println!("# profile: {}", profile);
+    }
+    for (key, source) in config.sources() {
+        if let Some(value) = config.value_of(key) {
+            println!("{} = {}  # {}", key, value, source);
+        }
+    }
+}
+
+/**
+ * @brief Check the effective configuration and the chosen mode together.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration.
+ * @param load_problems Problems already found while resolving the configuration.
+ * @return Result<(), ConfigErrors> Ok, or every problem found, including
+ *         command-line flags that cannot be combined.
+ */
+fn validate_config(
+    options: &CliOptions,
+    config: &Config,
+    load_problems: Vec<ConfigProblem>,
+) -> Result<(), ConfigErrors> {
+    let mut problems = load_problems;
+    let modes = [
+        ("serve", options.serve),
+        ("generate", options.generate),
+        ("config show", options.config_show),
+        ("recover", options.recover),
+        ("rollback", options.rollback),
+        ("lineage", options.lineage.is_some()),
+        ("history", options.history.is_some()),
+        ("ledger", options.ledger.is_some()),
+        ("join", options.join.is_some()),
+        ("diff", options.diff.is_some()),
+        ("interactive", options.interactive),
+        ("what-if", options.what_if),
+        ("compare", options.compare),
+        ("--pipeline", options.pipeline_file.is_some()),
+        ("--pipelines", options.pipelines.is_some()),
+        ("--watch", options.watch),
+        ("schedule", options.schedule),
+        ("--stream", options.stream),
+    ];
+    let selected: Vec<&str> = modes.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect();
+    if selected.len() > 1 {
+        problems.push(ConfigProblem {
+            key: selected.join(", "),
+            source: ConfigSource::Cli,
+            message: String::from(
+                "only one of serve, generate, config show, recover, rollback, lineage, history, ledger, join, \
+                 diff, interactive, what-if, compare, --pipeline, --pipelines, --watch, schedule and --stream \
+                 can be used",
+            ),
+        });
+    }
+    if options.resume
+        && (options.stream
+            || options.generate
+            || options.recover
+            || options.rollback
+            || options.lineage.is_some()
+            || options.history.is_some()
+            || options.ledger.is_some()
+            || options.join.is_some()
+            || options.diff.is_some()
+            || options.interactive
+            || options.what_if
+            || options.compare
+            || options.pipeline_file.is_some())
+    {
+        problems.push(ConfigProblem {
+            key: String::from("--resume"),
+            source: ConfigSource::Cli,
+            message: String::from(
+                "has no effect with --stream, generate, recover, rollback, lineage, history, ledger, join, diff, \
+                 interactive, what-if, compare or --pipeline",
+            ),
+        });
+    }
+    let long_running = options.serve || options.schedule || options.watch || options.stream;
+    if options.tenant.is_some() && (long_running || options.pipeline_file.is_some() || options.pipelines.is_some()) {
+        problems.push(ConfigProblem {
+            key: String::from("--tenant"),
+            source: ConfigSource::Cli,
+            message: String::from(
+                "is for single runs; serve takes the tenant per run (POST /run?tenant=<name>), and serve, schedule, \
+                 --watch, --stream, --pipeline and --pipelines otherwise run without one",
+            ),
+        });
+    }
+    let inspecting = options.recover
+        || options.rollback
+        || options.lineage.is_some()
+        || options.history.is_some()
+        || options.ledger.is_some()
+        || options.join.is_some()
+        || options.diff.is_some()
+        || options.interactive
+        || options.what_if
+        || options.compare;
+    if options.force && (options.generate || inspecting) {
+        problems.push(ConfigProblem {
+            key: String::from("--force"),
+            source: ConfigSource::Cli,
+            message: String::from(
+                "has no effect with generate, recover, rollback, lineage, history, ledger, join, diff, interactive, \
+                 what-if or compare",
+            ),
+        });
+    }
+    if options.lineage.is_some() && config.audit_log_path.as_os_str().is_empty() {
+        problems.push(ConfigProblem {
+            key: String::from("audit_log_path"),
+            source: config.source_of("audit_log_path"),
+            message: String::from("must be set for lineage, which reads the item's history from the audit log"),
+        });
+    }
+    if options.history.is_some() && config.history_path.as_os_str().is_empty() {
+        problems.push(ConfigProblem {
+            key: String::from("history_path"),
+            source: config.source_of("history_path"),
+            message: String::from("must be set for history, which reads the recorded runs from it"),
+        });
+    }
+    if options.ledger.is_some() && config.delivery_ledger_path.as_os_str().is_empty() {
+        problems.push(ConfigProblem {
+            key: String::from("delivery_ledger_path"),
+            source: config.source_of("delivery_ledger_path"),
+            message: String::from("must be set for ledger, which reads the delivered items from it"),
+        });
+    }
+    if options.tui && !(options.watch || (options.serve && !options.grpc)) {
+        problems.push(ConfigProblem {
+            key: String::from("--tui"),
+            source: ConfigSource::Cli,
+            message: String::from("needs --watch or serve, which it shows the runs of"),
+        });
+    } else if options.tui && !cfg!(feature = "tui") {
+        problems.push(ConfigProblem {
+            key: String::from("--tui"),
+            source: ConfigSource::Cli,
+            message: String::from("needs a build with the `tui` feature"),
+        });
+    }
+    if options.schedule && config.schedule.is_empty() {
+        problems.push(ConfigProblem {
+            key: String::from("schedule"),
+            source: config.source_of("schedule"),
+            message: String::from("must be set for the schedule subcommand, e.g. \"0 */6 * * *\""),
+        });
+    }
+    if let Some(selection) = &options.pipelines {
+        if let Err(message) = selected_pipelines(selection, config) {
+            problems.push(ConfigProblem {
+                key: String::from("--pipelines"),
+                source: ConfigSource::Cli,
+                message,
+            });
+        }
+    }
+    if options.snapshot.is_some() && !options.rollback {
+        problems.push(ConfigProblem {
+            key: String::from("--snapshot"),
+            source: ConfigSource::Cli,
+            message: String::from("only applies to rollback"),
+        });
+    }
+    if options.allow_overwrite && !options.generate && !options.rollback && options.join.is_none() {
+        problems.push(ConfigProblem {
+            key: String::from("--allow-overwrite"),
+            source: ConfigSource::Cli,
+            message: String::from("only applies to generate, join and rollback"),
+        });
+    }
+    if options.threshold.is_some() && !options.what_if {
+        problems.push(ConfigProblem {
+            key: String::from("--threshold"),
+            source: ConfigSource::Cli,
+            message: String::from("only applies to what-if; set the threshold config key to process with it"),
+        });
+    }
+    for (flag, path) in [("--baseline", &options.baseline), ("--candidate", &options.candidate)] {
+        let message = match (options.compare, path) {
+            (true, None) => "is required for compare",
+            (false, Some(_)) => "only applies to compare",
+            _ => continue,
+        };
+        problems.push(ConfigProblem {
+            key: String::from(flag),
+            source: ConfigSource::Cli,
+            message: String::from(message),
+        });
+    }
+    if options.output_file.is_some() && !options.generate {
+        problems.push(ConfigProblem {
+            key: String::from("--output-file"),
+            source: ConfigSource::Cli,
+            message: String::from("only applies to generate"),
+        });
+    }
+    if let Some(format) = options.output.as_deref() {
+        if format != "text" && format != "json" {
+            problems.push(ConfigProblem {
+                key: String::from("--output"),
+                source: ConfigSource::Cli,
+                message: format!("expected 'text' or 'json', got '{}'", format),
+            });
+        } else if json_output(options)
+            && selected
+                .iter()
+                .any(|mode| !["--pipelines", "lineage", "history", "ledger", "join", "diff", "what-if", "compare"]
+                    .contains(mode))
+        {
+            problems.push(ConfigProblem {
+                key: String::from("--output"),
+                source: ConfigSource::Cli,
+                message: String::from(
+                    "json output is only available for a single pipeline run, --pipelines, lineage, history, \
+                     ledger, join, diff, what-if and compare",
+                ),
+            });
+        }
+    }
+    if options.grpc && !options.serve {
+        problems.push(ConfigProblem {
+            key: String::from("--grpc"),
+            source: ConfigSource::Cli,
+            message: String::from("only applies to serve"),
+        });
+    }
+    if options.grpc && (options.resume || options.force) {
+        problems.push(ConfigProblem {
+            key: String::from(if options.resume { "--resume" } else { "--force" }),
+            source: ConfigSource::Cli,
+            message: String::from("has no effect with serve --grpc"),
+        });
+    }
+    if options.grpc && !config.custom_stages.is_empty() {
+        problems.push(ConfigProblem {
+            key: String::from("custom_stages"),
+            source: config.source_of("custom_stages"),
+            message: String::from("custom stages are not applied by the gRPC service; run without --grpc"),
+        });
+    }
+    if options.pipeline_file.is_some() && options.filter.is_some() {
+        problems.push(ConfigProblem {
+            key: String::from("--filter"),
+            source: ConfigSource::Cli,
+            message: String::from("has no effect with --pipeline; add a filter stage to the pipeline file"),
+        });
+    }
+    if options.pipeline_file.is_some() && !options.stages.is_empty() {
+        problems.push(ConfigProblem {
+            key: String::from("--stage"),
+            source: ConfigSource::Cli,
+            message: String::from("has no effect with --pipeline; add the stage to the pipeline file"),
+        });
+    }
+    if options.max_duration.is_some() && (options.stream || options.pipeline_file.is_some()) {
+        problems.push(ConfigProblem {
+            key: String::from("--max-duration"),
+            source: ConfigSource::Cli,
+            message: String::from("has no effect with --stream or --pipeline"),
+        });
+    }
+    let sampling = options.sample.is_some() || options.sample_percent.is_some();
+    if sampling && (options.stream || options.pipeline_file.is_some()) {
+        problems.push(ConfigProblem {
+            key: String::from(if options.sample.is_some() { "--sample" } else { "--sample-percent" }),
+            source: ConfigSource::Cli,
+            message: String::from("has no effect with --stream or --pipeline"),
+        });
+    }
+    if options.stream && !config.custom_stages.is_empty() {
+        problems.push(ConfigProblem {
+            key: String::from("custom_stages"),
+            source: config.source_of("custom_stages"),
+            message: String::from("custom stages are not applied when streaming; run without --stream"),
+        });
+    }
+    if (options.stream || options.pipeline_file.is_some()) && !config.join_path.as_os_str().is_empty() {
+        problems.push(ConfigProblem {
+            key: String::from("join_path"),
+            source: config.source_of("join_path"),
+            message: String::from("has no effect with --stream or --pipeline; use the join subcommand first"),
+        });
+    }
+    if (options.stream || options.pipeline_file.is_some()) && config.anomaly_detection != "off" {
+        problems.push(ConfigProblem {
+            key: String::from("anomaly_detection"),
+            source: config.source_of("anomaly_detection"),
+            message: String::from("needs all values at once and has no effect with --stream or --pipeline"),
+        });
+    }
+    if options.quiet && options.verbosity > 0 {
+        problems.push(ConfigProblem {
+            key: String::from("--quiet"),
+            source: ConfigSource::Cli,
+            message: String::from("cannot be combined with -v"),
+        });
+    }
+    if let Err(errors) = config.validate() {
+        problems.extend(errors.problems);
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigErrors { problems })
+    }
+}
+
+/**
+ * @brief Main entry point for the application.
+ */
+fn main() {
+    // Until the config is known, e.g. for command-line errors
+    logging::set_color(ColorChoice::Auto);
+    let options = match parse_cli_options(env::args_os().skip(1)) {
+        Ok(options) => options,
         Err(e) => {
-            // Using eprintln! for errors, similar to Python's logger.error or logger.critical
-            eprintln!("CRITICAL: [main:run_processing_pipeline] Failed to load items: {}", e);
-            return Err(format!("Data loading failed: {}", e));
+            log_critical!("main:main", "{}", e);
+            eprintln!("{}", CLI.usage);
+            exit(error::EXIT_CONFIG);
         }
     };
+    if let Some(shell) = options.completions {
+        print!("{}", completions::script(&CLI, shell));
+        exit(0);
+    }
+    if options.config_schema {
+        match serde_json::to_string_pretty(&Config::json_schema()) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                log_critical!("main:main", "Failed to serialize the config schema: {}", e);
+                exit(error::EXIT_FAILURE);
+            }
+        }
+        exit(0);
+    }
+    if let Some(dir) = &options.gen_docs {
+        match docs::write(&CLI, dir) {
+            Ok(written) => log_info!("main:main", "Wrote {}.", written.join(" and ")),
+            Err(e) => {
+                log_critical!("main:main", "{}", e);
+                exit(error::EXIT_FAILURE);
+            }
+        }
+        exit(0);
+    }
 
-    if items_to_process.is_empty() {
-        println!("WARNING: [main:run_processing_pipeline] No items loaded from data source. Exiting pipeline.");
-        println!("INFO: [main:run_processing_pipeline] Sample Project Rust processing pipeline finished.");
-        return Ok(());
+    // The result document or the diff is the only thing written to stdout then
+    if json_output(&options) || options.diff.is_some() {
+        logging::set_stderr_only(true);
     }
 
-    println!(
-        "INFO: [main:run_processing_pipeline] Successfully loaded {} items.",
-        items_to_process.len()
-    );
+    let (config, load_problems) = build_config(&options, None, options.tenant.as_deref());
+    // Already for the validation problems; an invalid choice is one of them
+    if let Ok(choice) = ColorChoice::parse(&config.log_color) {
+        logging::set_color(choice);
+    }
+    if options.config_show {
+        show_config(&config);
+    }
+    if let Err(e) = validate_config(&options, &config, load_problems) {
+        log_critical!("main:main", "{}", e);
+        if json_output(&options) {
+            print_run_result(&RunResult::new(Err(PipelineError::Config(e.to_string())), Vec::new()));
+        }
+        exit(error::EXIT_CONFIG);
+    }
+    if options.config_show {
+        exit(0);
+    }
 
-    // 3. Process data items
-    let mut successful_processing_count = 0;
-    let mut failed_processing_count = 0;
-
-    for item_ref_mut in items_to_process.iter_mut() { // Iterate with mutable references
-        // Log the item before processing
-        // println!("DEBUG: [main:run_processing_pipeline] Passing item to processor: {}", item_ref_mut);
-        
-        if item_processor.process_item(item_ref_mut) {
-            successful_processing_count += 1;
-        } else {
-            // This path is not taken in the current ItemProcessor::process_item logic,
-            // but kept for structural similarity.
-            eprintln!(
-                "ERROR: [main:run_processing_pipeline] Failed to process item: {}",
-                item_ref_mut
-            );
-            failed_processing_count += 1;
+    config.apply_process_settings();
+    if let Err(e) = setup_main_logging(&config) {
+        log_critical!("main:main", "{}", e);
+        exit(error::EXIT_CONFIG);
+    }
+    match &config.profile {
+        Some(profile) => log_info!("main:main", "{}", tr!(ConfigProfile, profile)),
+        None => log_info!("main:main", "{}", tr!(ConfigNoProfile)),
+    }
+    #[cfg(feature = "otel")]
+    if !config.otel_endpoint.is_empty() {
+        if let Err(e) = telemetry::install(&config.otel_endpoint, &config.otel_service_name) {
+            log_warning!("main:main", "{}", e);
         }
     }
 
-    println!(
-        "INFO: [main:run_processing_pipeline] Processed {} items successfully, {} failed.",
-        successful_processing_count,
-        failed_processing_count
-    );
+    if let Err(e) = shutdown::install_handlers() {
+        log_warning!("main:main", "{}", e);
+    }
+
+    if options.generate {
+        run_generate_mode(&options, &config);
+    }
+    if options.recover {
+        run_recover_mode(&config);
+    }
+    if options.rollback {
+        run_rollback_mode(&options, &config);
+    }
+    if let Some(id) = &options.lineage {
+        run_lineage_mode(&options, &config, id);
+    }
+    if let Some(run_id) = &options.history {
+        run_history_mode(&options, &config, run_id.as_deref());
+    }
+    if let Some(key) = &options.ledger {
+        run_ledger_mode(&options, &config, key.as_deref());
+    }
+    if let Some((primary, secondary, output)) = &options.join {
+        run_join_mode(&options, &config, primary, secondary, output);
+    }
+    if let Some((before, after)) = &options.diff {
+        run_diff_mode(&options, &config, before, after);
+    }
+    if options.interactive {
+        run_interactive_mode(&config);
+    }
+    if options.what_if {
+        run_what_if_mode(&options, &config);
+    }
+    if options.compare {
+        run_compare_mode(&options, &config);
+    }
 
-    // 4. Save processed data
-    // The `items_to_process` vector now contains the (potentially) modified items.
-    match data_handler.save_items(&items_to_process) {
-        Ok(_) => {
-            println!("INFO: [main:run_processing_pipeline] Processed items saved successfully.");
+    let metrics = Arc::new(Metrics::new());
+
+    if let Some(path) = &options.pipeline_file {
+        run_pipeline_file_mode(&options, &config, path, &metrics);
+    }
+    if options.serve && options.grpc {
+        run_grpc_mode(&options, &config, &metrics);
+    }
+    if options.serve {
+        run_daemon_mode(&options, &config, &metrics);
+    }
+    if options.stream {
+        run_stream_mode(&options, &config, &metrics);
+    }
+    if options.watch {
+        run_watch_mode(&options, &config, &metrics);
+    }
+    if options.schedule {
+        run_schedule_mode(&options, &config, &metrics);
+    }
+    if let Some(selection) = &options.pipelines {
+        run_pipelines_mode(&options, &config, selection, &metrics);
+    }
+
+    if json_output(&options) {
+        run_with_json_output(&options, &config, &metrics);
+    }
+
+    let result = pipeline::run_processing_pipeline(&config, options.resume, &metrics, &PipelineHooks::new());
+    export_metrics_file(&options, &metrics);
+    if matches!(&result, Ok(report) if report.interrupted) {
+        exit(shutdown::EXIT_INTERRUPTED);
+    }
+    if let Err(e) = result.and_then(|report| report.outcome()) {
+        log_critical!("main:main", "{}", tr!(PipelineFailed, e));
+        exit(e.exit_code()); // The exit code tells scripts which stage failed
+    }
+    // Implicitly returns 0 (success) if every item was processed and saved
+}
+
+/**
+ * @brief Check whether the result of the run is to be printed as JSON (`--output json`).
+ *
+ * @param options The parsed command-line options.
+ * @return bool True for a pipeline run with --output json.
+ */
+fn json_output(options: &CliOptions) -> bool {
+    options.output.as_deref() == Some("json")
+}
+
+/**
+ * @brief Run the pipeline once and print its result as a JSON document on stdout.
+ *
+ * The document holds the report, the outcome of every item and the errors;
+ * log records go to stderr. The exit code is the same as without
+ * --output json. This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration.
+ * @param metrics The shared metrics.
+ */
+fn run_with_json_output(options: &CliOptions, config: &Config, metrics: &Arc<Metrics>) -> ! {
+    let outcomes = Arc::new(Mutex::new(Vec::new()));
+    let collected = Arc::clone(&outcomes);
+    let hooks = PipelineHooks::new().after_item(move |item, outcome| {
+        collected.lock().unwrap_or_else(|e| e.into_inner()).push(ItemResult {
+            item_id: item.item_id.clone(),
+            outcome,
+            status: item.status,
+        });
+    });
+    let result = pipeline::run_processing_pipeline(config, options.resume, metrics, &hooks);
+    export_metrics_file(options, metrics);
+    if let Err(e) = &result {
+        log_critical!("main:main", "{}", tr!(PipelineFailed, e));
+    }
+
+    let items = std::mem::take(&mut *outcomes.lock().unwrap_or_else(|e| e.into_inner()));
+    let run_result = RunResult::new(result, items);
+    print_run_result(&run_result);
+    exit(run_result.exit_code);
+}
+
+/**
+ * @brief Resolve the pipelines selected with --pipelines.
+ *
+ * @param selection "all", or the names of pipelines of the config file, separated by ','.
+ * @param config The effective configuration, knowing the pipelines of the config file.
+ * @return Result<Vec<String>, String> The names, or an error message for
+ *         unknown names or a config file without pipelines.
+ */
+fn selected_pipelines(selection: &str, config: &Config) -> Result<Vec<String>, String> {
+    if config.pipelines.is_empty() {
+        return Err(String::from("the config file defines no pipelines ([pipelines.<name>] tables)"));
+    }
+    if selection == "all" {
+        return Ok(config.pipelines.clone());
+    }
+    let mut names: Vec<String> = Vec::new();
+    for name in selection.split(',').map(str::trim) {
+        if !config.pipelines.iter().any(|known| known == name) {
+            return Err(format!("unknown pipeline '{}' (pipelines: {})", name, config.pipelines.join(", ")));
+        }
+        if !names.iter().any(|selected| selected == name) {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+/**
+ * @brief Run several pipelines of the config file at once, each on its own
+ *        thread (`--pipelines <name,...|all>`).
+ *
+ * Every pipeline gets its own configuration, resolved like the main one with
+ * its [pipelines.<name>] table applied on top of the profile; all of them are
+ * validated before any starts. Their log records carry the pipeline's name.
+ * Afterwards a table of the results is printed, or with --output json a
+ * document with the result of each pipeline. The exit code is that of
+ * report::MultiRunResult. This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration, without a pipeline applied.
+ * @param selection The value of --pipelines.
+ * @param metrics The shared metrics, accumulated over all pipelines.
+ */
+fn run_pipelines_mode(options: &CliOptions, config: &Config, selection: &str, metrics: &Arc<Metrics>) -> ! {
+    let names = selected_pipelines(selection, config).unwrap_or_default();
+    let mut configs = Vec::new();
+    for name in &names {
+        let (pipeline_config, load_problems) = build_config(options, Some(name), None);
+        match validate_config(options, &pipeline_config, load_problems) {
+            Ok(()) => configs.push((name.clone(), pipeline_config)),
+            Err(e) => log_critical!("main:run_pipelines_mode", "Pipeline '{}': {}", name, e),
+        }
+    }
+    if configs.len() < names.len() {
+        exit(error::EXIT_CONFIG);
+    }
+
+    log_info!("main:run_pipelines_mode", "{}", tr!(PipelinesStarting, names.len(), names.join(", ")));
+    let json = json_output(options);
+    let results: Vec<PipelineRunResult> = thread::scope(|scope| {
+        let runs: Vec<_> = configs
+            .iter()
+            .map(|(name, pipeline_config)| {
+                let handle = scope.spawn(move || {
+                    logging::with_context(LogContext::pipeline(name), || {
+                        let outcomes = Arc::new(Mutex::new(Vec::new()));
+                        let collected = Arc::clone(&outcomes);
+                        let hooks = if json {
+                            PipelineHooks::new().after_item(move |item, outcome| {
+                                collected.lock().unwrap_or_else(|e| e.into_inner()).push(ItemResult {
+                                    item_id: item.item_id.clone(),
+                                    outcome,
+                                    status: item.status,
+                                });
+                            })
+                        } else {
+                            PipelineHooks::new()
+                        };
+                        let result = pipeline::run_processing_pipeline(pipeline_config, options.resume, metrics, &hooks);
+                        if let Err(e) = &result {
+                            log_error!("main:run_pipelines_mode", "{}", tr!(PipelineFailed, e));
+                        }
+                        let items = std::mem::take(&mut *outcomes.lock().unwrap_or_else(|e| e.into_inner()));
+                        RunResult::new(result, items)
+                    })
+                });
+                (name, handle)
+            })
+            .collect();
+        runs.into_iter()
+            .map(|(name, handle)| PipelineRunResult {
+                name: name.clone(),
+                result: handle.join().unwrap_or_else(|_| {
+                    let e = PipelineError::Aborted(String::from("the pipeline's thread panicked"));
+                    log_error!("main:run_pipelines_mode", "Pipeline '{}': {}", name, e);
+                    RunResult::new(Err(e), Vec::new())
+                }),
+            })
+            .collect()
+    });
+    export_metrics_file(options, metrics);
+
+    let multi_result = MultiRunResult::new(results);
+    if json {
+        match serde_json::to_string_pretty(&multi_result) {
+            Ok(json) => println!("{}", json),
+            Err(e) => log_error!("main:run_pipelines_mode", "Failed to serialize the run results: {}", e),
+        }
+    } else {
+        print!("{}", multi_result.to_table());
+    }
+    exit(multi_result.exit_code);
+}
+
+/**
+ * @brief Print a run result as a JSON document on stdout.
+ *
+ * @param run_result The result to print.
+ */
+fn print_run_result(run_result: &RunResult) {
+    match serde_json::to_string_pretty(run_result) {
+        Ok(json) => println!("{}", json),
+        Err(e) => log_error!("main:print_run_result", "Failed to serialize the run result: {}", e),
+    }
+}
+
+/**
+ * @brief Write a synthetic dataset and exit.
+ *
+ * Starts from the DATAGEN_* config values, overridden by the command-line
+ * flags. The dataset is written to --output-file, or to the configured data path.
+ * This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration.
+ */
+fn run_generate_mode(options: &CliOptions, config: &Config) -> ! {
+    let datagen_options = match build_datagen_options(options) {
+        Ok(datagen_options) => datagen_options,
+        Err(e) => {
+            log_critical!("main:run_generate_mode", "{}", e);
+            exit(error::EXIT_CONFIG);
+        }
+    };
+    let output = options.output_file.as_deref().unwrap_or(config.data_path.as_path());
+    if let Err(e) = guard::check_overwrite(output, "generate", config.allow_overwrite) {
+        log_critical!("main:run_generate_mode", "{}", tr!(GenerateFailed, e));
+        exit(error::EXIT_SAVE);
+    }
+    match datagen::write_dataset(output, &datagen_options) {
+        Ok((records, invalid)) => {
+            log_info!("main:run_generate_mode", "{}", tr!(GenerateWrote, records, invalid, output.display()));
+            exit(0);
         }
         Err(e) => {
-            eprintln!("ERROR: [main:run_processing_pipeline] Failed to save processed items: {}", e);
-            // Decide if this should be a critical error for the pipeline
+            log_critical!("main:run_generate_mode", "{}", tr!(GenerateFailed, e));
+            exit(error::EXIT_SAVE);
         }
     }
+}
 
-    println!("INFO: [main:run_processing_pipeline] Sample Project Rust processing pipeline finished.");
-    Ok(())
+/**
+ * @brief Clean up after runs that died while saving, and exit.
+ *
+ * Unfinished saves found in the staging directory are rolled back, so the
+ * data files are as they were before the failed run (saves that completed
+ * are only tidied up). Must not be run while a pipeline run is saving. This
+ * function never returns.
+ *
+ * @param config The effective configuration.
+ */
+fn run_recover_mode(config: &Config) -> ! {
+    match staging::recover(&config.staging_dir) {
+        Ok(0) => {
+            log_info!("main:run_recover_mode", "{}", tr!(RecoverNothing, config.staging_dir.display()));
+            exit(0);
+        }
+        Ok(count) => {
+            log_info!("main:run_recover_mode", "{}", tr!(RecoverDone, count));
+            exit(0);
+        }
+        Err(e) => {
+            log_critical!("main:run_recover_mode", "{}", tr!(RecoverFailed, e));
+            exit(error::EXIT_FAILURE);
+        }
+    }
 }
 
 /**
- * @brief Main entry point for the application.
+ * @brief Restore the data files from a snapshot, or list the snapshots, and exit.
+ *
+ * Without --snapshot, prints the snapshots in the snapshot directory, oldest
+ * first, with the data files each holds. With --snapshot, restores the data
+ * files of that snapshot ("latest" for the newest one), holding the data
+ * file's lock so no run saves meanwhile. Restoring over data files that hold
+ * data needs --allow-overwrite (see guard.rs). This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration.
  */
-fn main() {
-    setup_main_logging();
+fn run_rollback_mode(options: &CliOptions, config: &Config) -> ! {
+    let Some(id) = &options.snapshot else {
+        match snapshot::list(&config.snapshot_dir) {
+            Ok(snapshots) if snapshots.is_empty() => {
+                log_info!("main:run_rollback_mode", "{}", tr!(RollbackNothing, config.snapshot_dir.display()));
+            }
+            Ok(snapshots) => {
+                for snapshot in &snapshots {
+                    let files: Vec<String> =
+                        snapshot.files.iter().map(|file| file.target.display().to_string()).collect();
+                    println!("{}  {}", snapshot.id, files.join(", "));
+                }
+            }
+            Err(e) => {
+                log_critical!("main:run_rollback_mode", "{}", tr!(RollbackFailed, e));
+                exit(error::EXIT_FAILURE);
+            }
+        }
+        exit(0);
+    };
+    let lock_policy = LockPolicy::parse(&config.lock_policy).expect("lock_policy was validated with the config");
+    let lock_timeout = (config.lock_timeout_secs > 0).then(|| Duration::from_secs(config.lock_timeout_secs));
+    let restored = DataLock::acquire(&config.data_path, lock_policy, lock_timeout).and_then(|_data_lock| {
+        let snapshot = snapshot::find(&config.snapshot_dir, id)?;
+        let targets: Vec<&Path> = snapshot.files.iter().map(|file| file.target.as_path()).collect();
+        guard::check_restore(&snapshot.id, &targets, config.allow_overwrite)?;
+        snapshot.restore(&config.staging_dir).map(|count| (snapshot.id, count))
+    });
+    match restored {
+        Ok((id, count)) => {
+            log_info!("main:run_rollback_mode", "{}", tr!(RollbackDone, count, id));
+            exit(0);
+        }
+        Err(e) => {
+            log_critical!("main:run_rollback_mode", "{}", tr!(RollbackFailed, e));
+            exit(error::EXIT_FAILURE);
+        }
+    }
+}
+
+/**
+ * @brief Print the history of an item and exit.
+ *
+ * Prints the lineage the item carries in the data file and the changes the
+ * audit log recorded for it, as a table or with --output json as a JSON
+ * document. Exits with 0 if anything is known about the item and 1 if it is
+ * neither in the data file nor in the audit log. This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration.
+ * @param id The item id, as given on the command line.
+ */
+fn run_lineage_mode(options: &CliOptions, config: &Config, id: &str) -> ! {
+    let item_id = ItemId::parse(id, item_id::expected_kind()).unwrap_or_else(|e| {
+        log_critical!("main:run_lineage_mode", "{}", e);
+        exit(error::EXIT_CONFIG);
+    });
+    let items = pipeline::build_data_handler(config, &config.data_path)
+        .and_then(|data_handler| data_handler.load_items())
+        .unwrap_or_else(|e| {
+            log_critical!("main:run_lineage_mode", "{}", e);
+            exit(error::EXIT_LOAD);
+        });
+    let history = ItemHistory::collect(&item_id, &items, &config.audit_log_path).unwrap_or_else(|e| {
+        log_critical!("main:run_lineage_mode", "{}", e);
+        exit(error::EXIT_LOAD);
+    });
+    if json_output(options) {
+        match serde_json::to_string_pretty(&history) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                log_critical!("main:run_lineage_mode", "Failed to serialize the history: {}", e);
+                exit(error::EXIT_FAILURE);
+            }
+        }
+    } else {
+        print!("{}", history.to_table());
+    }
+    exit(if history.is_empty() { error::EXIT_FAILURE } else { 0 });
+}
+
+/**
+ * @brief Print the recorded runs, or one of them, and exit.
+ *
+ * Without a run id, prints every run in the run history, oldest first, with
+ * its failure rate and throughput and the averages over all runs; with one,
+ * prints that run. As a table or with --output json as a JSON document.
+ * Exits with 1 if the run id is not in the history. This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration.
+ * @param run_id The run to show (`history show <run_id>`), or None for all (`history list`).
+ */
+fn run_history_mode(options: &CliOptions, config: &Config, run_id: Option<&str>) -> ! {
+    let records = history::read(&config.history_path).unwrap_or_else(|e| {
+        log_critical!("main:run_history_mode", "{}", e);
+        exit(error::EXIT_LOAD);
+    });
+    let json = match run_id {
+        None if json_output(options) => serde_json::to_string_pretty(&serde_json::json!({
+            "runs": records,
+            "trend": HistoryTrend::of(&records),
+        })),
+        None => {
+            print!("{}", history::to_table(&records));
+            exit(0);
+        }
+        Some(run_id) => {
+            let Some(record) = records.iter().find(|record| record.run_id == run_id) else {
+                log_critical!(
+                    "main:run_history_mode",
+                    "No run '{}' in {}.",
+                    run_id,
+                    config.history_path.display()
+                );
+                exit(error::EXIT_FAILURE);
+            };
+            if !json_output(options) {
+                print!("{}", record.to_text());
+                exit(0);
+            }
+            serde_json::to_string_pretty(record)
+        }
+    };
+    match json {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            log_critical!("main:run_history_mode", "Failed to serialize the run history: {}", e);
+            exit(error::EXIT_FAILURE);
+        }
+    }
+    exit(0);
+}
+
+/**
+ * @brief Print the items delivered to network sinks, or one delivery, and exit.
+ *
+ * Without a key, prints every delivery in the delivery ledger, oldest first;
+ * with one, prints the delivery with that idempotency key, which may be
+ * shortened to a prefix as shown by `ledger list`. As a table or with
+ * --output json as a JSON document. Exits with 1 if no delivery has the key.
+ * This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration.
+ * @param key The delivery to show (`ledger show <key>`), or None for all (`ledger list`).
+ */
+fn run_ledger_mode(options: &CliOptions, config: &Config, key: Option<&str>) -> ! {
+    let entries = delivery::read(&config.delivery_ledger_path).unwrap_or_else(|e| {
+        log_critical!("main:run_ledger_mode", "{}", e);
+        exit(error::EXIT_LOAD);
+    });
+    let json = match key {
+        None if json_output(options) => serde_json::to_string_pretty(&serde_json::json!({ "deliveries": entries })),
+        None => {
+            print!("{}", delivery::to_table(&entries));
+            exit(0);
+        }
+        Some(key) => {
+            let matching: Vec<&delivery::LedgerEntry> =
+                entries.iter().filter(|entry| entry.key.starts_with(key)).collect();
+            let entry = match matching.as_slice() {
+                [entry] => *entry,
+                [] => {
+                    log_critical!(
+                        "main:run_ledger_mode",
+                        "No delivery with key '{}' in {}.",
+                        key,
+                        config.delivery_ledger_path.display()
+                    );
+                    exit(error::EXIT_FAILURE);
+                }
+                _ => {
+                    log_critical!(
+                        "main:run_ledger_mode",
+                        "Key '{}' matches {} deliveries in {}; give more digits.",
+                        key,
+                        matching.len(),
+                        config.delivery_ledger_path.display()
+                    );
+                    exit(error::EXIT_FAILURE);
+                }
+            };
+            if !json_output(options) {
+                print!("{}", entry.to_text());
+                exit(0);
+            }
+            serde_json::to_string_pretty(entry)
+        }
+    };
+    match json {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            log_critical!("main:run_ledger_mode", "Failed to serialize the delivery ledger: {}", e);
+            exit(error::EXIT_FAILURE);
+        }
+    }
+    exit(0);
+}
+
+/**
+ * @brief Join a secondary data file into a primary one, save the result and exit.
+ *
+ * Uses the join_type (or --join-type) and join_fields config keys. Prints
+ * what matched and the ids without a match, as text or with --output json as
+ * a JSON document. Replacing an existing data file other than the primary
+ * needs --allow-overwrite (see guard.rs); that is checked before anything is
+ * loaded. This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration, for the storage settings of the
+ *        data files.
+ * @param primary Path of the primary data file.
+ * @param secondary Path of the data file joined into it.
+ * @param output Path of the data file the joined items are saved to.
+ */
+fn run_join_mode(options: &CliOptions, config: &Config, primary: &Path, secondary: &Path, output: &Path) -> ! {
+    let join_type = JoinType::parse(&config.join_type).expect("join_type was validated with the config");
+    let join_fields = pipeline::parse_join_fields(config).expect("join_fields were validated with the config");
+    // The output may replace the primary data file, which the joined items
+    // include, but no other existing one unless overwriting is allowed
+    if storage::canonical_path(output) != storage::canonical_path(primary) {
+        if let Err(e) = guard::check_overwrite(output, "join", config.allow_overwrite) {
+            log_critical!("main:run_join_mode", "{}", tr!(JoinFailed, e));
+            exit(error::EXIT_SAVE);
+        }
+    }
+    let load = |path: &Path| {
+        pipeline::build_data_handler(config, path).and_then(|data_handler| data_handler.load_items())
+    };
+    let (items, secondary_items) = match (load(primary), load(secondary)) {
+        (Ok(items), Ok(secondary_items)) => (items, secondary_items),
+        (Err(e), _) | (_, Err(e)) => {
+            log_critical!("main:run_join_mode", "{}", tr!(JoinFailed, e));
+            exit(error::EXIT_LOAD);
+        }
+    };
+    let (items, stats) = join::join(items, secondary_items, join_type, &join_fields);
+    let saved = pipeline::build_data_handler(config, primary)
+        .and_then(|data_handler| data_handler.save_items_to(output, &items));
+    if let Err(e) = saved {
+        log_critical!("main:run_join_mode", "{}", tr!(JoinFailed, e));
+        exit(error::EXIT_SAVE);
+    }
+    log_info!("main:run_join_mode", "{}", tr!(JoinSaved, items.len(), output.display()));
+    if json_output(options) {
+        match serde_json::to_string_pretty(&stats) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                log_critical!("main:run_join_mode", "Failed to serialize the join result: {}", e);
+                exit(error::EXIT_FAILURE);
+            }
+        }
+    } else {
+        let ids = |ids: &[ItemId]| ids.iter().map(ItemId::to_string).collect::<Vec<String>>().join(", ");
+        println!("{}", tr!(JoinValue, stats.matched, stats.unmatched.len(), stats.dropped, stats.unused.len()));
+        if !stats.unmatched.is_empty() {
+            println!("Unmatched: {}", ids(&stats.unmatched));
+        }
+        if !stats.unused.is_empty() {
+            println!("Unused:    {}", ids(&stats.unused));
+        }
+    }
+    exit(0);
+}
+
+/**
+ * @brief Load the items of the data file and explore them in a REPL on
+ *        stdin/stdout until quit, then exit (see repl.rs).
+ *
+ * Exits with 0 when the session ends, or with error::EXIT_LOAD if the items
+ * cannot be loaded. This function never returns.
+ *
+ * @param config The effective configuration: the data file, the processor's
+ *        thresholds and rules and the filter expression.
+ */
+fn run_interactive_mode(config: &Config) -> ! {
+    let session = pipeline::build_data_handler(config, &config.data_path).and_then(|data_handler| {
+        let items = data_handler.load_items()?;
+        let processor = pipeline::build_item_processor(config)?;
+        let item_filter = pipeline::build_item_filter(config)?;
+        Ok(repl::Session::new(items, processor, item_filter, data_handler))
+    });
+    let mut session = session.unwrap_or_else(|e| {
+        log_critical!("main:run_interactive_mode", "{}", e);
+        exit(error::EXIT_LOAD);
+    });
+    if let Err(e) = session.run(io::stdin().lock(), &mut io::stdout()) {
+        log_critical!("main:run_interactive_mode", "The interactive session failed: {}", e);
+        exit(error::EXIT_FAILURE);
+    }
+    exit(0);
+}
+
+/**
+ * @brief Report what processing the loaded items would change, and exit.
+ *
+ * Evaluates the rules for the items the tag rules and the filter select,
+ * after the lookup table enriched them as in a run, without processing them
+ * (see what_if.rs): nothing is marked processed, audited or saved. Exits
+ * with EXIT_CONFIG for invalid rules and EXIT_LOAD for unreadable data files
+ * or lookup tables. Prints the items whose value or threshold tag would
+ * change as a table, or with --output json as a JSON document. This function
+ * never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration (its threshold includes --threshold).
+ */
+fn run_what_if_mode(options: &CliOptions, config: &Config) -> ! {
+    let rules = pipeline::build_item_processor(config)
+        .and_then(|processor| Ok((processor, pipeline::build_item_filter(config)?)))
+        .map_err(PipelineError::Config);
+    let ((processor, item_filter), items) = rules
+        .and_then(|rules| Ok((rules, load_preview_items(config)?)))
+        .unwrap_or_else(|e| {
+            log_critical!("main:run_what_if_mode", "{}", e);
+            exit(e.exit_code());
+        });
+    let report = WhatIfReport::build(&processor, item_filter.as_ref(), config.threshold, &items).unwrap_or_else(|e| {
+        log_critical!("main:run_what_if_mode", "{}", e);
+        exit(error::EXIT_FAILURE);
+    });
+    if json_output(options) {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                log_critical!("main:run_what_if_mode", "Failed to serialize the what-if report: {}", e);
+                exit(error::EXIT_FAILURE);
+            }
+        }
+    } else {
+        print!("{}", report.to_table());
+    }
+    exit(0);
+}
+
+/**
+ * @brief Compare how the rules of two config files would process the items, and exit.
+ *
+ * Loads the items once, with the effective configuration and its lookup
+ * table (see load_preview_items), and evaluates them with the thresholds, tag rules, transformations and filter of the
+ * --baseline and the --candidate config file (each resolved like --config,
+ * with the same profile and environment) without processing them (see
+ * what_if.rs). Prints the items the two treat differently as a table, or with
+ * --output json as a JSON document. This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration, for the data file and the lookup table.
+ */
+fn run_compare_mode(options: &CliOptions, config: &Config) -> ! {
+    let baseline_file = options.baseline.as_deref().expect("--baseline was validated with the config");
+    let candidate_file = options.candidate.as_deref().expect("--candidate was validated with the config");
+    let load_rules = |config_file: &Path| {
+        rule_config(config_file, options.profile.as_deref()).and_then(|rule_config| {
+            let processor = pipeline::build_item_processor(&rule_config)?;
+            let item_filter = pipeline::build_item_filter(&rule_config)?;
+            Ok((processor, item_filter))
+        })
+    };
+    let (baseline, candidate) = match (load_rules(baseline_file), load_rules(candidate_file)) {
+        (Ok(baseline), Ok(candidate)) => (baseline, candidate),
+        (Err(e), _) | (_, Err(e)) => {
+            log_critical!("main:run_compare_mode", "{}", e);
+            exit(error::EXIT_CONFIG);
+        }
+    };
+    let items = load_preview_items(config).unwrap_or_else(|e| {
+        log_critical!("main:run_compare_mode", "{}", e);
+        exit(e.exit_code());
+    });
+    let comparison = RuleComparison::build(
+        &RuleSet {
+            config_file: baseline_file.to_path_buf(),
+            processor: &baseline.0,
+            filter: baseline.1.as_ref(),
+        },
+        &RuleSet {
+            config_file: candidate_file.to_path_buf(),
+            processor: &candidate.0,
+            filter: candidate.1.as_ref(),
+        },
+        &items,
+    )
+    .unwrap_or_else(|e| {
+        log_critical!("main:run_compare_mode", "{}", e);
+        exit(error::EXIT_FAILURE);
+    });
+    if json_output(options) {
+        match serde_json::to_string_pretty(&comparison) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                log_critical!("main:run_compare_mode", "Failed to serialize the comparison: {}", e);
+                exit(error::EXIT_FAILURE);
+            }
+        }
+    } else {
+        print!("{}", comparison.to_table());
+    }
+    exit(0);
+}
+
+/**
+ * @brief Load the items what-if and compare evaluate, enriched as a run would.
+ *
+ * With a lookup table configured (enrichment_lookup_path), every item is
+ * enriched first, so category thresholds apply to the categories a run would
+ * see. Items a run would skip or fail for a missing lookup entry are left out.
+ *
+ * @param config The effective configuration.
+ * @return Result<Vec<Item>, PipelineError> The items, or the classified failure.
+ */
+fn load_preview_items(config: &Config) -> Result<Vec<Item>, PipelineError> {
+    let items = pipeline::build_data_handler(config, &config.data_path)
+        .map_err(PipelineError::Config)?
+        .load_items()
+        .map_err(PipelineError::Load)?;
+    let Some(enricher) = pipeline::build_enricher(config)? else {
+        return Ok(items);
+    };
+    let loaded = items.len();
+    let items: Vec<Item> = items
+        .into_iter()
+        .filter_map(|mut item| match enricher.enrich(&mut item) {
+            Enrichment::Skip | Enrichment::Fail => None,
+            Enrichment::Enriched | Enrichment::Missing => Some(item),
+        })
+        .collect();
+    if items.len() < loaded {
+        log_info!(
+            "main:load_preview_items",
+            "Leaving out {} items without a lookup entry, which a run would skip or fail.",
+            loaded - items.len()
+        );
+    }
+    Ok(items)
+}
+
+/**
+ * @brief Resolve and validate the configuration of a config file compared by compare.
+ *
+ * @param config_file The config file.
+ * @param profile The profile to apply (--profile), as for Config::load.
+ * @return Result<Config, String> The configuration, or its problems.
+ */
+fn rule_config(config_file: &Path, profile: Option<&str>) -> Result<Config, String> {
+    let (rule_config, mut problems) = Config::load(Some(config_file), profile);
+    if let Err(errors) = rule_config.validate() {
+        problems.extend(errors.problems);
+    }
+    if problems.is_empty() {
+        Ok(rule_config)
+    } else {
+        Err(format!("{}: {}", config_file.display(), ConfigErrors { problems }))
+    }
+}
+
+/**
+ * @brief Compare two data files, print the differences and exit.
+ *
+ * Prints the added, removed and changed items as a table, or with
+ * --output json as a JSON document. Like diff(1), exits with 0 if the files
+ * hold the same items and 1 if they differ. This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration; its encryption key decrypts
+ *        encrypted files.
+ * @param before Path of the first data file, e.g. a copy taken before a run.
+ * @param after Path of the second data file.
+ */
+fn run_diff_mode(options: &CliOptions, config: &Config, before: &Path, after: &Path) -> ! {
+    let encryption_key = encryption::load_key(
+        &config.encryption_key_env,
+        &config.encryption_key_file,
+        config.encryption_allow_plaintext,
+    )
+    .unwrap_or_else(|e| {
+        log_critical!("main:run_diff_mode", "{}", e);
+        exit(error::EXIT_CONFIG);
+    });
+    let diff = DatasetDiff::compare_files(before, after, encryption_key.as_ref(), config.data_checksums)
+        .unwrap_or_else(|e| {
+            log_critical!("main:run_diff_mode", "{}", e);
+            exit(error::EXIT_LOAD);
+        });
+    if json_output(options) {
+        match serde_json::to_string_pretty(&diff) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                log_critical!("main:run_diff_mode", "Failed to serialize the differences: {}", e);
+                exit(error::EXIT_FAILURE);
+            }
+        }
+    } else {
+        print!("{}", diff.to_table());
+    }
+    exit(if diff.is_empty() { 0 } else { error::EXIT_FAILURE });
+}
+
+/**
+ * @brief Run the pipeline described in a pipeline definition file and exit.
+ *
+ * The whole definition, including its stages' parameters and lookup tables,
+ * is checked before any data file is read. The exit codes are those of a
+ * normal run. This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration, for the storage settings of the
+ *        data files.
+ * @param path Path of the definition file.
+ * @param metrics The shared metrics.
+ */
+fn run_pipeline_file_mode(options: &CliOptions, config: &Config, path: &Path, metrics: &Arc<Metrics>) -> ! {
+    let pipeline = PipelineDefinition::load(path)
+        .and_then(|definition| definition.build(config))
+        .unwrap_or_else(|e| {
+            log_critical!("main:run_pipeline_file_mode", "{}", e);
+            exit(error::EXIT_CONFIG);
+        });
+    let result = pipeline.run(metrics);
+    export_metrics_file(options, metrics);
+    if matches!(&result, Ok(report) if report.interrupted) {
+        exit(shutdown::EXIT_INTERRUPTED);
+    }
+    if let Err(e) = result.and_then(|report| report.outcome()) {
+        log_critical!("main:run_pipeline_file_mode", "{}", tr!(PipelineFailed, e));
+        exit(e.exit_code());
+    }
+    exit(0);
+}
+
+/**
+ * @brief Build the dataset parameters for the generate subcommand.
+ *
+ * @param options The parsed command-line options.
+ * @return Result<DatagenOptions, String> The parameters, or an error message
+ *         for an invalid flag or config value.
+ */
+fn build_datagen_options(options: &CliOptions) -> Result<DatagenOptions, String> {
+    let mut datagen_options = DatagenOptions::from_config()?;
+    if let Some(count) = &options.count {
+        datagen_options.count = count
+            .parse()
+            .map_err(|e| format!("Invalid value for --count '{}': {}", count, e))?;
+    }
+    if let Some(seed) = &options.seed {
+        datagen_options.seed = Some(
+            seed.parse()
+                .map_err(|e| format!("Invalid value for --seed '{}': {}", seed, e))?,
+        );
+    }
+    if let Some(distribution) = &options.distribution {
+        datagen_options.distribution = ValueDistribution::parse(distribution)?;
+    }
+    if let Some(name_pattern) = &options.name_pattern {
+        datagen_options.name_pattern = name_pattern.clone();
+    }
+    if let Some(ratio) = &options.invalid_ratio {
+        datagen_options.invalid_ratio = ratio
+            .parse()
+            .map_err(|e| format!("Invalid value for --invalid-ratio '{}': {}", ratio, e))?;
+    }
+    datagen_options.validate()?;
+    Ok(datagen_options)
+}
+
+/**
+ * @brief Set up the /healthz and /readyz probes of a long-running mode.
+ *
+ * Readiness is checked against the configuration in effect when a probe
+ * arrives; a rejected config file makes the process unready until it is fixed.
+ *
+ * @param reloader Keeps the configuration in sync with the config file.
+ * @return Arc<Health> The probe state, without a run yet.
+ */
+fn probe_state(reloader: &Arc<Mutex<ConfigReloader>>) -> Arc<Health> {
+    let reloader = Arc::clone(reloader);
+    Arc::new(Health::new(move || {
+        // Checking the data source may take a while; don't hold up reloads meanwhile
+        let (config, rejected) = {
+            let reloader = reloader.lock().unwrap_or_else(|e| e.into_inner());
+            (reloader.current.clone(), reloader.rejected.clone())
+        };
+        health::readiness_checks(&config, rejected.as_deref())
+    }))
+}
+
+/**
+ * @brief Run the pipeline once in a long-running mode and report the run to the probes.
+ *
+ * @param config The configuration for the run.
+ * @param resume Continue from the checkpoint of an interrupted run.
+ * @param metrics The shared metrics, accumulated across runs.
+ * @param health Remembers the run for /healthz and /readyz.
+ * @return Result<PipelineReport, PipelineError> As for pipeline::run_processing_pipeline.
+ */
+fn run_and_record(
+    config: &Config,
+    resume: bool,
+    metrics: &Metrics,
+    health: &Health,
+) -> Result<PipelineReport, PipelineError> {
+    let started_at = Utc::now();
+    let result = pipeline::run_processing_pipeline(config, resume, metrics, &PipelineHooks::new());
+    health.record_run(RunRecord::new(started_at, config.fingerprint(), config.profile.clone(), &result));
+    result
+}
+
+/**
+ * @brief Keep the process alive and re-run the pipeline periodically.
+ *
+ * A failed run is logged and retried at the next interval. When a metrics
+ * address is configured, the /metrics endpoint and the /healthz and /readyz
+ * probes are served for the whole lifetime of the process. Changes to the config file are picked up while
+ * waiting and apply from the next run on. Unchanged data files are not
+ * parsed again (see load_cache.rs) unless --no-cache is given. This
+ * function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration.
+ * @param metrics The shared metrics, accumulated across runs.
+ */
+fn run_watch_mode(options: &CliOptions, config: &Config, metrics: &Arc<Metrics>) -> ! {
+    let reloader = Arc::new(Mutex::new(ConfigReloader::new(options, config)));
+    let health = probe_state(&reloader);
+    if let Some(addr) = &options.metrics_addr {
+        if let Err(e) = metrics::serve(addr, Arc::clone(metrics), Arc::clone(&health)) {
+            log_critical!("main:run_watch_mode", "{}", e);
+            exit(error::EXIT_FAILURE);
+        }
+    }
+
+    load_cache::set_enabled(!options.no_cache);
+    start_dashboard(options, metrics, "watch");
+    log_info!("main:run_watch_mode", "{}", tr!(WatchEnabled, config.watch_interval_secs.max(1)));
+    loop {
+        let run_config = reloader.lock().unwrap_or_else(|e| e.into_inner()).current.clone();
+        if let Err(e) = run_and_record(&run_config, options.resume, metrics, &health) {
+            log_error!("main:run_watch_mode", "{}", tr!(PipelineFailed, e));
+        }
+        export_metrics_file(options, metrics);
+
+        // Sleep in short steps so a shutdown request is noticed promptly; a
+        // reloaded watch_interval_secs already applies to the current wait
+        let waiting_since = Instant::now();
+        let mut next_reload_check = waiting_since + Duration::from_secs(CONFIG_RELOAD_POLL_SECS);
+        loop {
+            let watch_interval_secs = reloader.lock().unwrap_or_else(|e| e.into_inner()).current.watch_interval_secs;
+            let interval = Duration::from_secs(watch_interval_secs.max(1));
+            let now = Instant::now();
+            if now >= waiting_since + interval || shutdown::requested() {
+                break;
+            }
+            thread::sleep(SHUTDOWN_POLL_INTERVAL.min(waiting_since + interval - now));
+            if Instant::now() >= next_reload_check {
+                reloader.lock().unwrap_or_else(|e| e.into_inner()).reload_if_changed();
+                next_reload_check = Instant::now() + Duration::from_secs(CONFIG_RELOAD_POLL_SECS);
+            }
+        }
+        if shutdown::requested() {
+            log_info!("main:run_watch_mode", "{}", tr!(WatchLeaving));
+            shutdown::exit(shutdown::EXIT_INTERRUPTED);
+        }
+    }
+}
 
-    if let Err(e) = run_processing_pipeline() {
-        eprintln!("CRITICAL: [main:main] Pipeline execution failed: {}", e);
-        exit(1); // Exit with a non-zero code to indicate failure
+/**
+ * @brief Return when the last recorded run started.
+ *
+ * @param config The effective configuration.
+ * @return Option<DateTime<Utc>> The start of the last run in the history
+ *         file, or None without a history or runs.
+ */
+fn last_recorded_run(config: &Config) -> Option<DateTime<Utc>> {
+    if config.history_path.as_os_str().is_empty() {
+        return None;
+    }
+    match history::read(&config.history_path) {
+        Ok(records) => records.last().map(|record| record.started_at),
+        Err(e) => {
+            log_warning!("main:last_recorded_run", "{}", e);
+            None
+        }
+    }
+}
+
+/**
+ * @brief Keep the process alive and run the pipeline at the times the
+ *        schedule config key names (`schedule` subcommand).
+ *
+ * See schedule.rs for the expressions. Runs never overlap; scheduled times
+ * that pass while a run is in progress, or passed while the process was down
+ * (known from the run history, if history_path is set), are handled as
+ * schedule_missed_runs says. The next scheduled time is logged before each
+ * wait. Changes to the config file, including the schedule, are picked up
+ * while waiting, and --metrics-addr serves /metrics and the probes, as in
+ * watch mode. This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration.
+ * @param metrics The shared metrics, accumulated across runs.
+ */
+fn run_schedule_mode(options: &CliOptions, config: &Config, metrics: &Arc<Metrics>) -> ! {
+    let reloader = Arc::new(Mutex::new(ConfigReloader::new(options, config)));
+    let health = probe_state(&reloader);
+    if let Some(addr) = &options.metrics_addr {
+        if let Err(e) = metrics::serve(addr, Arc::clone(metrics), Arc::clone(&health)) {
+            log_critical!("main:run_schedule_mode", "{}", e);
+            exit(error::EXIT_FAILURE);
+        }
+    }
+    let run = |run_config: &Config| {
+        if let Err(e) = run_and_record(run_config, options.resume, metrics, &health) {
+            log_error!("main:run_schedule_mode", "{}", tr!(PipelineFailed, e));
+        }
+        export_metrics_file(options, metrics);
+    };
+
+    load_cache::set_enabled(!options.no_cache);
+    log_info!("main:run_schedule_mode", "{}", tr!(ScheduleEnabled, config.schedule));
+    // Scheduled times up to this one have been run or handled as missed
+    let mut handled_until = last_recorded_run(config).unwrap_or_else(Utc::now);
+    loop {
+        let run_config = reloader.lock().unwrap_or_else(|e| e.into_inner()).current.clone();
+        // Both were validated along with the rest of the configuration
+        let schedule = match CronSchedule::parse(&run_config.schedule) {
+            Ok(schedule) => schedule,
+            Err(e) => {
+                log_critical!("main:run_schedule_mode", "{}", e);
+                exit(error::EXIT_CONFIG);
+            }
+        };
+        let policy = MissedRunPolicy::parse(&run_config.schedule_missed_runs).unwrap_or(MissedRunPolicy::Skip);
+
+        let now = Utc::now();
+        if let (missed, Some(last_missed)) = schedule.times_between(handled_until, now) {
+            handled_until = now;
+            let last_missed = last_missed.format("%Y-%m-%dT%H:%M:%SZ");
+            match policy {
+                MissedRunPolicy::Skip => {
+                    log_warning!("main:run_schedule_mode", "{}", tr!(ScheduleMissedSkip, missed, last_missed));
+                }
+                MissedRunPolicy::RunOnce => {
+                    log_warning!("main:run_schedule_mode", "{}", tr!(ScheduleMissedRunOnce, missed, last_missed));
+                    run(&run_config);
+                }
+            }
+            continue;
+        }
+        let Some(next) = schedule.next_after(now) else {
+            log_critical!("main:run_schedule_mode", "The schedule '{}' has no further run times.", schedule);
+            exit(error::EXIT_CONFIG);
+        };
+        log_info!(
+            "main:run_schedule_mode",
+            "{}",
+            tr!(ScheduleNextRun, next.format("%Y-%m-%dT%H:%M:%SZ"), (next - now).num_seconds())
+        );
+
+        // Sleep in short steps so a shutdown request and config changes are noticed promptly
+        let mut next_reload_check = Instant::now() + Duration::from_secs(CONFIG_RELOAD_POLL_SECS);
+        let mut rescheduled = false;
+        loop {
+            let now = Utc::now();
+            if now >= next || shutdown::requested() {
+                break;
+            }
+            thread::sleep(SHUTDOWN_POLL_INTERVAL.min((next - now).to_std().unwrap_or_default()));
+            if Instant::now() >= next_reload_check {
+                let mut reloader = reloader.lock().unwrap_or_else(|e| e.into_inner());
+                reloader.reload_if_changed();
+                if reloader.current.schedule != run_config.schedule {
+                    rescheduled = true;
+                    break;
+                }
+                next_reload_check = Instant::now() + Duration::from_secs(CONFIG_RELOAD_POLL_SECS);
+            }
+        }
+        if shutdown::requested() {
+            log_info!("main:run_schedule_mode", "{}", tr!(ScheduleLeaving));
+            exit(shutdown::EXIT_INTERRUPTED);
+        }
+        if rescheduled {
+            // Times of the new schedule before now were never due
+            handled_until = Utc::now();
+            let schedule = reloader.lock().unwrap_or_else(|e| e.into_inner()).current.schedule.clone();
+            log_info!("main:run_schedule_mode", "{}", tr!(ScheduleEnabled, schedule));
+            continue;
+        }
+        handled_until = next;
+        let run_config = reloader.lock().unwrap_or_else(|e| e.into_inner()).current.clone();
+        run(&run_config);
+    }
+}
+
+/**
+ * @brief Keep the process alive and run the pipeline when asked to over HTTP.
+ *
+ * See daemon::serve for the endpoints. The daemon also serves /metrics, so
+ * --metrics-addr is not needed in this mode. Changes to the config file are
+ * picked up in the background, or at once on POST /control/reload-config,
+ * and apply to the next run. Unchanged data
+ * files are served from the load cache, as in watch mode. This function never
+ * returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration (its daemon_addr includes --listen).
+ * @param metrics The shared metrics, accumulated across runs.
+ */
+fn run_daemon_mode(options: &CliOptions, config: &Config, metrics: &Arc<Metrics>) -> ! {
+    let addr = config.daemon_addr.clone();
+    load_cache::set_enabled(!options.no_cache);
+    let reloader = Arc::new(Mutex::new(ConfigReloader::new(options, config)));
+    let watched = Arc::clone(&reloader);
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(CONFIG_RELOAD_POLL_SECS));
+        watched.lock().unwrap_or_else(|e| e.into_inner()).reload_if_changed();
+    });
+
+    let health = probe_state(&reloader);
+    let run_health = Arc::clone(&health);
+    start_dashboard(options, metrics, "serve");
+    let run_options = options.clone();
+    let run_metrics = Arc::clone(metrics);
+    let run_reloader = Arc::clone(&reloader);
+    let run_pipeline = move |tenant: Option<&str>| {
+        // Each run uses the configuration in effect when it starts
+        let run_config = {
+            let reloader = run_reloader.lock().unwrap_or_else(|e| e.into_inner());
+            match tenant {
+                Some(tenant) => reloader.tenant_config(tenant)?,
+                None => reloader.current.clone(),
+            }
+        };
+        let result = run_and_record(&run_config, run_options.resume, &run_metrics, &run_health);
+        export_metrics_file(&run_options, &run_metrics);
+        result.map_err(|e| e.to_string())
+    };
+    let reload_config = move || reloader.lock().unwrap_or_else(|e| e.into_inner()).reload();
+    let result = daemon::serve(&addr, Arc::clone(metrics), health, run_pipeline, reload_config);
+    if let Err(e) = result {
+        log_critical!("main:run_daemon_mode", "{}", e);
+    }
+    shutdown::exit(error::EXIT_FAILURE);
+}
+
+/**
+ * @brief Show the terminal dashboard if --tui is given (see tui.rs).
+ *
+ * Falls back to log lines, with a warning, if the dashboard cannot be shown.
+ *
+ * @param options The parsed command-line options.
+ * @param metrics The shared metrics the dashboard shows.
+ * @param mode The mode, for the dashboard's title.
+ */
+fn start_dashboard(options: &CliOptions, metrics: &Arc<Metrics>, mode: &str) {
+    #[cfg(feature = "tui")]
+    if options.tui {
+        if let Err(e) = tui::start(mode, Arc::clone(metrics)) {
+            log_warning!("main:start_dashboard", "{}", e);
+        }
+    }
+    #[cfg(not(feature = "tui"))]
+    let _ = (options, metrics, mode);
+}
+
+/**
+ * @brief Serve the gRPC service until SIGINT/SIGTERM (`serve --grpc`).
+ *
+ * See grpc_server.rs for the service; it requires the `grpc` feature. Items
+ * are processed with the configuration at startup. This function never returns.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration (its grpc_addr includes --listen).
+ * @param metrics The shared metrics, accumulated across streams.
+ */
+fn run_grpc_mode(options: &CliOptions, config: &Config, metrics: &Arc<Metrics>) -> ! {
+    #[cfg(feature = "grpc")]
+    {
+        let result = grpc_server::serve(config, Arc::clone(metrics));
+        export_metrics_file(options, metrics);
+        match result {
+            Ok(()) => exit(shutdown::EXIT_INTERRUPTED),
+            Err(e) => {
+                log_critical!("main:run_grpc_mode", "{}", e);
+                exit(error::EXIT_FAILURE);
+            }
+        }
+    }
+    #[cfg(not(feature = "grpc"))]
+    {
+        let _ = (options, config, metrics);
+        log_critical!("main:run_grpc_mode", "serve --grpc requires a build with the 'grpc' feature.");
+        exit(error::EXIT_CONFIG);
+    }
+}
+
+/**
+ * @brief Stream items through the processor: an NDJSON data file line by
+ *        line, or otherwise items from Kafka until an error occurs.
+ *
+ * Uses the same processor and filter as a file-based run. Streaming from
+ * Kafka requires the `kafka` feature; the process exits when streaming stops.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration.
+ * @param metrics The shared metrics, accumulated across the stream.
+ */
+fn run_stream_mode(options: &CliOptions, config: &Config, metrics: &Arc<Metrics>) -> ! {
+    let data_format = match DataFormat::parse(&config.data_format) {
+        Ok(Some(format)) => Some(format),
+        _ => DataFormat::from_path(&config.data_path.to_string_lossy()).ok(),
+    };
+    if data_format == Some(DataFormat::Ndjson) {
+        run_ndjson_stream_mode(options, config, metrics);
+    }
+
+    #[cfg(feature = "kafka")]
+    {
+        let result = pipeline::build_item_processor(config).and_then(|item_processor| {
+            let item_processor = Arc::new(item_processor.with_audit_log(pipeline::open_audit_log(config)?));
+            let item_filter = pipeline::build_item_filter(config)?;
+            kafka_stream::run(&item_processor, item_filter.as_ref(), metrics, &config.delivery_ledger_path)
+        });
+        export_metrics_file(options, metrics);
+        match result {
+            Ok(()) => exit(shutdown::EXIT_INTERRUPTED),
+            Err(e) => {
+                log_critical!("main:run_stream_mode", "{}", tr!(StreamFailed, e));
+                exit(error::EXIT_FAILURE);
+            }
+        }
+    }
+    #[cfg(not(feature = "kafka"))]
+    {
+        let _ = (options, config, metrics);
+        log_critical!(
+            "main:run_stream_mode",
+            "--stream requires an NDJSON data file or a build with the 'kafka' feature."
+        );
+        exit(error::EXIT_CONFIG);
+    }
+}
+
+/**
+ * @brief Stream the NDJSON data file through the processor once, then exit.
+ *
+ * @param options The parsed command-line options.
+ * @param config The effective configuration.
+ * @param metrics The metrics collected while streaming.
+ */
+fn run_ndjson_stream_mode(options: &CliOptions, config: &Config, metrics: &Arc<Metrics>) -> ! {
+    let result = pipeline::run_ndjson_stream(config, metrics);
+    export_metrics_file(options, metrics);
+    match result {
+        Ok(summary) => {
+            log_info!(
+                "main:run_ndjson_stream_mode",
+                "{}",
+                tr!(StreamFinished, summary.items, config.data_path.display(), summary.malformed_lines)
+            );
+            if summary.already_processed > 0 {
+                log_info!(
+                    "main:run_ndjson_stream_mode",
+                    "{} {}",
+                    tr!(ReportAlreadyProcessed),
+                    summary.already_processed
+                );
+            }
+            exit(if summary.interrupted { shutdown::EXIT_INTERRUPTED } else { 0 });
+        }
+        Err(e) => {
+            log_critical!("main:run_ndjson_stream_mode", "{}", tr!(StreamFailed, e));
+            exit(e.exit_code());
+        }
+    }
+}
+
+/**
+ * @brief Write the metrics textfile if one was requested with --metrics-file.
+ *
+ * @param options The parsed command-line options.
+ * @param metrics The metrics to export.
+ */
+fn export_metrics_file(options: &CliOptions, metrics: &Metrics) {
+    if let Some(path) = &options.metrics_file {
+        if let Err(e) = metrics.write_textfile(path) {
+            log_warning!("main:export_metrics_file", "{}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliOptions, String> {
+        parse_cli_options(args.iter().map(OsString::from))
+    }
+
+    /// A value the parser accepts for an entry taking one.
+    fn sample_value(arg: &Arg) -> Option<&'static str> {
+        match arg.value {
+            Value::None => None,
+            Value::Path | Value::Text => Some("x"),
+            Value::Choices(choices) => choices.first().copied(),
+        }
+    }
+
+    #[test]
+    fn test_parser_accepts_every_entry_of_cli() {
+        let unrecognized = |name: &str| Err(format!("Unrecognized argument: {}", name));
+        for arg in CLI.subcommands.iter().chain(CLI.flags) {
+            let mut args = vec![arg.name];
+            args.extend(sample_value(arg));
+            assert_ne!(parse(&args).map(|_| ()), unrecognized(arg.name), "{} is not parsed", arg.name);
+        }
+        for flag in CLI.flags {
+            match sample_value(flag) {
+                Some(value) => {
+                    assert!(parse(&[flag.name, value]).is_ok(), "{} {} is rejected", flag.name, value);
+                    assert!(parse(&[flag.name]).is_err(), "{} takes no value in the parser", flag.name);
+                }
+                None => assert!(parse(&[flag.name]).is_ok(), "{} takes a value in the parser", flag.name),
+            }
+        }
+    }
+
+    #[test]
+    fn test_output_takes_a_format_and_generate_a_file() {
+        let options = parse(&["generate", "--output-file", "items.json"]).unwrap();
+        assert_eq!(options.output_file, Some(PathBuf::from("items.json")));
+        assert!(!json_output(&options));
+        assert!(json_output(&parse(&["--output", "json"]).unwrap()));
+        let output = CLI.flags.iter().find(|flag| flag.name == "--output").unwrap();
+        assert!(matches!(output.value, Value::Choices(&["text", "json"])));
+    }
+
+    #[test]
+    fn test_parser_rejects_flags_missing_from_cli() {
+        assert!(parse(&["--no-such-flag"]).is_err());
+    }
+
+    #[test]
+    fn test_usage_names_exactly_the_entries_of_cli() {
+        let words: Vec<&str> = USAGE.split(|c: char| !(c.is_alphanumeric() || c == '-')).collect();
+        for arg in CLI.subcommands.iter().chain(CLI.flags) {
+            assert!(words.contains(&arg.name), "{} is missing from the usage", arg.name);
+        }
+        for word in words.iter().filter(|word| word.starts_with('-')) {
+            assert!(CLI.flags.iter().any(|flag| flag.name == *word), "{} of the usage is not in CLI", word);
+        }
+    }
+
+    #[test]
+    fn test_docs_show_the_usage_as_synopsis() {
+        let markdown = docs::markdown(&CLI);
+        for line in docs::synopsis(&CLI) {
+            assert!(markdown.contains(&line), "{} is missing from the reference", line);
+        }
+        assert_eq!(docs::synopsis(&CLI).len(), USAGE.lines().count());
     }
-    // Implicitly returns 0 (success) if run_processing_pipeline is Ok
 }
 
 // End of rust_sample_project/src/main.rs
\ No newline at end of file
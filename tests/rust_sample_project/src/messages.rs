@@ -0,0 +1,585 @@
+// rust_sample_project/src/messages.rs
+
+// Catalog of the user-facing messages (run progress, the final report,
+// pipeline errors) in every supported locale: English, German and Slovak.
+// The locale is set process-wide with set_locale, from the config key
+// locale or, when that is empty, from LC_ALL / LC_MESSAGES / LANG.
+// Templates refer to their arguments by position ("{0}", "{1}", ...), so a
+// translation may reorder them. Debug output and messages that only wrap
+// another error stay in English.
+
+use std::env;
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicU8, Ordering as AtomicOrdering};
+
+static LOCALE: AtomicU8 = AtomicU8::new(Locale::En as u8);
+
+/**
+ * @enum Locale
+ * @brief A language messages can be emitted in.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    /// English ("en"), also used for unsupported languages.
+    En,
+    /// German ("de").
+    De,
+    /// Slovak ("sk").
+    Sk,
+}
+
+impl Locale {
+    /**
+     * @brief Parse a language code as used in config.
+     *
+     * @param name One of "en", "de" or "sk".
+     * @return Result<Locale, String> The locale, or an error message for unsupported codes.
+     */
+    pub fn parse(name: &str) -> Result<Locale, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "en" => Ok(Locale::En),
+            "de" => Ok(Locale::De),
+            "sk" => Ok(Locale::Sk),
+            other => Err(format!("Unsupported locale '{}' (expected 'en', 'de' or 'sk')", other)),
+        }
+    }
+
+    /**
+     * @brief Determine the locale from the environment.
+     *
+     * The first non-empty one of LC_ALL, LC_MESSAGES and LANG is used, e.g.
+     * "de_DE.UTF-8" selects German.
+     *
+     * @return Locale The locale, English if none is set or supported.
+     */
+    pub fn from_env() -> Locale {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| {
+                let language = value.split(['_', '.', '@']).next().unwrap_or_default();
+                Locale::parse(language).ok()
+            })
+            .unwrap_or(Locale::En)
+    }
+
+    /**
+     * @brief Resolve the configured locale.
+     *
+     * @param name The config value; empty selects the locale from the environment.
+     * @return Result<Locale, String> The locale, or an error message for unsupported codes.
+     */
+    pub fn resolve(name: &str) -> Result<Locale, String> {
+        if name.trim().is_empty() {
+            Ok(Locale::from_env())
+        } else {
+            Locale::parse(name.trim())
+        }
+    }
+}
+
+/**
+ * @brief Set the locale messages are emitted in.
+ *
+ * @param locale The locale.
+ */
+pub fn set_locale(locale: Locale) {
+    LOCALE.store(locale as u8, AtomicOrdering::Relaxed);
+}
+
+/**
+ * @brief Return the locale messages are emitted in.
+ *
+ * @return Locale The current locale (English unless set).
+ */
+pub fn locale() -> Locale {
+    match LOCALE.load(AtomicOrdering::Relaxed) {
+        value if value == Locale::De as u8 => Locale::De,
+        value if value == Locale::Sk as u8 => Locale::Sk,
+        _ => Locale::En,
+    }
+}
+
+/**
+ * @enum Msg
+ * @brief Key of a message in the catalog.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    PipelineStarting,
+    PipelineConfig,
+    UnfinishedSaves,
+    LoadFailed,
+    NoItemsLoaded,
+    ItemsLoaded,
+    DedupResult,
+    JoinResult,
+    AnomaliesFound,
+    SamplingItems,
+    NoCheckpoint,
+    RunVetoed,
+    ShutdownStopping,
+    DeadlineStopping,
+    ProcessedSummary,
+    ItemsSaved,
+    SummarySaved,
+    SaveFailed,
+    PipelineFinished,
+    ReportRunId,
+    ReportTenant,
+    ReportStartedAt,
+    ReportFinishedAt,
+    ReportItemsLoaded,
+    ReportRecordsSkipped,
+    ReportDuplicatesDropped,
+    ReportDuplicateIdsSaved,
+    ReportJoin,
+    JoinValue,
+    ReportAnomalies,
+    AnomaliesValue,
+    ReportSample,
+    SampleValue,
+    ReportNamesInterned,
+    NamesInternedValue,
+    ReportItemsProcessed,
+    ReportItemsFailed,
+    ReportTimedOut,
+    ReportSkippedByTag,
+    ReportFilteredOut,
+    ReportWithoutLookup,
+    ReportVetoed,
+    ReportAlreadyProcessed,
+    ReportFirstProcessed,
+    ReportLastProcessed,
+    ReportSavedTo,
+    ReportByStatus,
+    ReportRateLimit,
+    RateLimitValue,
+    ReportValues,
+    ValueStatsValue,
+    ReportOverThreshold,
+    ReportInterrupted,
+    ReportDeadlineReached,
+    ErrorConfig,
+    ErrorLoad,
+    ErrorSave,
+    ErrorAborted,
+    ErrorPartial,
+    PipelineFailed,
+    PipelinesStarting,
+    ConfigProfile,
+    ConfigNoProfile,
+    GenerateWrote,
+    GenerateFailed,
+    RecoverNothing,
+    RecoverDone,
+    RecoverFailed,
+    RollbackNothing,
+    RollbackDone,
+    RollbackFailed,
+    JoinSaved,
+    JoinFailed,
+    WatchEnabled,
+    WatchLeaving,
+    ScheduleEnabled,
+    ScheduleNextRun,
+    ScheduleMissedSkip,
+    ScheduleMissedRunOnce,
+    ScheduleLeaving,
+    StreamFailed,
+    StreamFinished,
+}
+
+/**
+ * @brief Look up the translations of a message.
+ *
+ * @param msg The message key.
+ * @return [&'static str; 3] The English, German and Slovak templates.
+ */
+fn catalog(msg: Msg) -> [&'static str; 3] {
+    match msg {
+        Msg::PipelineStarting => [
+            "Starting Sample Project Rust processing pipeline...",
+            "Starte die Verarbeitungspipeline von Sample Project Rust...",
+            "Spúšťam spracovateľskú pipeline Sample Project Rust...",
+        ],
+        Msg::PipelineConfig => [
+            "Config - Data Path: {0}, Threshold: {1}",
+            "Konfiguration - Datenpfad: {0}, Schwellenwert: {1}",
+            "Konfigurácia - cesta k dátam: {0}, prah: {1}",
+        ],
+        Msg::UnfinishedSaves => [
+            "Found {0} unfinished saves in {1}; run the recover subcommand to restore the data files.",
+            "{0} unvollständige Speichervorgänge in {1} gefunden; führen Sie den Unterbefehl recover aus, um die Datendateien wiederherzustellen.",
+            "V {1} sa našlo nedokončených ukladaní: {0}; spustite podpríkaz recover na obnovenie dátových súborov.",
+        ],
+        Msg::LoadFailed => [
+            "Failed to load items: {0}",
+            "Laden der Elemente fehlgeschlagen: {0}",
+            "Načítanie položiek zlyhalo: {0}",
+        ],
+        Msg::NoItemsLoaded => [
+            "No items loaded from data source. Exiting pipeline.",
+            "Keine Elemente aus der Datenquelle geladen. Pipeline wird beendet.",
+            "Zo zdroja dát sa nenačítali žiadne položky. Pipeline končí.",
+        ],
+        Msg::ItemsLoaded => [
+            "Successfully loaded {0} items.",
+            "{0} Elemente erfolgreich geladen.",
+            "Úspešne načítané položky: {0}.",
+        ],
+        Msg::DedupResult => [
+            "Deduplication ({0}) dropped {1} duplicate items, {2} items remain.",
+            "Deduplizierung ({0}) hat {1} doppelte Elemente verworfen, {2} Elemente verbleiben.",
+            "Deduplikácia ({0}) vyradila duplicitné položky: {1}, zostávajúce položky: {2}.",
+        ],
+        Msg::JoinResult => [
+            "Join ({0}) with {1}: {2} items matched, {3} without a match ({4} dropped), {5} ids only in {1}.",
+            "Join ({0}) mit {1}: {2} Elemente zugeordnet, {3} ohne Treffer ({4} verworfen), {5} IDs nur in {1}.",
+            "Spojenie ({0}) s {1}: priradené položky: {2}, bez zhody: {3} (vyradené: {4}), ID iba v {1}: {5}.",
+        ],
+        Msg::AnomaliesFound => [
+            "Anomaly detection: {0} items outside {1}..{2}, sending them to review at {3}.",
+            "Anomalieerkennung: {0} Elemente außerhalb von {1}..{2}, sende sie zur Prüfung nach {3}.",
+            "Detekcia anomálií: položky mimo {1}..{2}: {0}, posielam ich na kontrolu do {3}.",
+        ],
+        Msg::SamplingItems => [
+            "Sample run: processing {0} of {1} items (seed {2}).",
+            "Stichprobenlauf: verarbeite {0} von {1} Elementen (Seed {2}).",
+            "Beh so vzorkou: spracúvam {0} z {1} položiek (seed {2}).",
+        ],
+        Msg::NoCheckpoint => [
+            "--resume given but no checkpoint found at {0}. Starting from the beginning.",
+            "--resume angegeben, aber kein Checkpoint unter {0} gefunden. Beginne von vorn.",
+            "Zadané --resume, ale v {0} sa nenašiel žiadny checkpoint. Začínam od začiatku.",
+        ],
+        Msg::RunVetoed => [
+            "A hook vetoed the run: {0}",
+            "Ein Hook hat den Lauf abgelehnt: {0}",
+            "Hook zamietol beh: {0}",
+        ],
+        Msg::ShutdownStopping => [
+            "Shutdown requested, stopping after {0} of {1} items.",
+            "Beenden angefordert, stoppe nach {0} von {1} Elementen.",
+            "Požiadavka na ukončenie, zastavujem po {0} z {1} položiek.",
+        ],
+        Msg::DeadlineStopping => [
+            "Max duration of {0} s reached, stopping after {1} of {2} items.",
+            "Maximale Dauer von {0} s erreicht, stoppe nach {1} von {2} Elementen.",
+            "Maximálne trvanie {0} s dosiahnuté, zastavujem po {1} z {2} položiek.",
+        ],
+        Msg::ProcessedSummary => [
+            "Processed {0} items successfully, {1} failed, {2} skipped by tag rules, {3} filtered out.",
+            "{0} Elemente erfolgreich verarbeitet, {1} fehlgeschlagen, {2} durch Tag-Regeln übersprungen, {3} herausgefiltert.",
+            "Úspešne spracované položky: {0}, zlyhané: {1}, preskočené podľa tagov: {2}, odfiltrované: {3}.",
+        ],
+        Msg::ItemsSaved => [
+            "Processed items saved successfully.",
+            "Verarbeitete Elemente erfolgreich gespeichert.",
+            "Spracované položky boli úspešne uložené.",
+        ],
+        Msg::SummarySaved => [
+            "Saved the summary of {0} groups to {1}.",
+            "Zusammenfassung von {0} Gruppen nach {1} gespeichert.",
+            "Súhrn skupín ({0}) bol uložený do {1}.",
+        ],
+        Msg::SaveFailed => [
+            "Failed to save processed items: {0}",
+            "Speichern der verarbeiteten Elemente fehlgeschlagen: {0}",
+            "Uloženie spracovaných položiek zlyhalo: {0}",
+        ],
+        Msg::PipelineFinished => [
+            "Sample Project Rust processing pipeline finished.",
+            "Verarbeitungspipeline von Sample Project Rust beendet.",
+            "Spracovateľská pipeline Sample Project Rust skončila.",
+        ],
+        Msg::ReportRunId => ["Run ID:", "Lauf-ID:", "ID behu:"],
+        Msg::ReportTenant => ["Tenant:", "Mandant:", "Nájomca:"],
+        Msg::ReportStartedAt => ["Run started at:", "Lauf gestartet:", "Beh začal:"],
+        Msg::ReportFinishedAt => ["Run finished at:", "Lauf beendet:", "Beh skončil:"],
+        Msg::ReportItemsLoaded => ["Items loaded:", "Geladene Elemente:", "Načítané položky:"],
+        Msg::ReportRecordsSkipped => [
+            "Invalid records skipped:",
+            "Übersprungene ungültige Datensätze:",
+            "Preskočené neplatné záznamy:",
+        ],
+        Msg::ReportDuplicatesDropped => ["Duplicates dropped:", "Verworfene Duplikate:", "Vyradené duplikáty:"],
+        Msg::ReportDuplicateIdsSaved => [
+            "Duplicate ids saved:",
+            "Gespeicherte doppelte IDs:",
+            "Uložené duplicitné ID:",
+        ],
+        Msg::ReportJoin => ["Joined:", "Verknüpft:", "Spojené:"],
+        Msg::JoinValue => [
+            "{0} matched, {1} unmatched ({2} dropped), {3} unused",
+            "{0} zugeordnet, {1} ohne Treffer ({2} verworfen), {3} ungenutzt",
+            "{0} priradených, {1} bez zhody ({2} vyradených), {3} nepoužitých",
+        ],
+        Msg::ReportAnomalies => ["Sent to review:", "Zur Prüfung gesendet:", "Poslané na kontrolu:"],
+        Msg::AnomaliesValue => [
+            "{0} outside {1}..{2}",
+            "{0} außerhalb von {1}..{2}",
+            "{0} mimo {1}..{2}",
+        ],
+        Msg::ReportSample => ["Sample:", "Stichprobe:", "Vzorka:"],
+        Msg::SampleValue => [
+            "{0} of {1} items (seed {2})",
+            "{0} von {1} Elementen (Seed {2})",
+            "{0} z {1} položiek (seed {2})",
+        ],
+        Msg::ReportNamesInterned => ["Names interned:", "Internierte Namen:", "Internované názvy:"],
+        Msg::NamesInternedValue => [
+            "{0} distinct of {1} ({2} saved)",
+            "{0} verschiedene von {1} ({2} gespart)",
+            "{0} rôznych z {1} (ušetrené {2})",
+        ],
+        Msg::ReportItemsProcessed => ["Items processed:", "Verarbeitete Elemente:", "Spracované položky:"],
+        Msg::ReportItemsFailed => ["Items failed:", "Fehlgeschlagene Elemente:", "Zlyhané položky:"],
+        Msg::ReportTimedOut => ["  of which timed out:", "  davon Zeitüberschreitung:", "  z toho vypršal čas:"],
+        Msg::ReportSkippedByTag => ["Items skipped by tag:", "Per Tag übersprungen:", "Preskočené podľa tagu:"],
+        Msg::ReportFilteredOut => ["Items filtered out:", "Herausgefiltert:", "Odfiltrované položky:"],
+        Msg::ReportWithoutLookup => ["Items without lookup:", "Ohne Lookup-Eintrag:", "Bez záznamu v lookup tabuľke:"],
+        Msg::ReportVetoed => ["Items vetoed by hooks:", "Von Hooks abgelehnt:", "Zamietnuté hookmi:"],
+        Msg::ReportAlreadyProcessed => [
+            "Already processed:",
+            "Bereits verarbeitet:",
+            "Už spracované:",
+        ],
+        Msg::ReportFirstProcessed => [
+            "First item processed:",
+            "Erstes Element verarbeitet:",
+            "Prvá položka spracovaná:",
+        ],
+        Msg::ReportLastProcessed => [
+            "Last item processed:",
+            "Letztes Element verarbeitet:",
+            "Posledná položka spracovaná:",
+        ],
+        Msg::ReportSavedTo => ["Saved to:", "Gespeichert in:", "Uložené do:"],
+        Msg::ReportByStatus => ["Items by status:", "Elemente nach Status:", "Položky podľa stavu:"],
+        Msg::ReportRateLimit => ["Rate limit:", "Ratenbegrenzung:", "Obmedzenie rýchlosti:"],
+        Msg::RateLimitValue => [
+            "{0}/s, effective {1}/s, {2} s throttled",
+            "{0}/s, tatsächlich {1}/s, {2} s gedrosselt",
+            "{0}/s, skutočne {1}/s, {2} s pribrzdené",
+        ],
+        Msg::ReportValues => ["Values:", "Werte:", "Hodnoty:"],
+        Msg::ValueStatsValue => [
+            "total {0}, mean {1}, min {2}, max {3}",
+            "Summe {0}, Mittel {1}, Min. {2}, Max. {3}",
+            "súčet {0}, priemer {1}, min. {2}, max. {3}",
+        ],
+        Msg::ReportOverThreshold => ["Over threshold:", "Über dem Schwellenwert:", "Nad prahom:"],
+        Msg::ReportInterrupted => [
+            "Run was interrupted; resume with --resume.",
+            "Der Lauf wurde unterbrochen; mit --resume fortsetzen.",
+            "Beh bol prerušený; pokračujte pomocou --resume.",
+        ],
+        Msg::ReportDeadlineReached => [
+            "Run stopped at its max duration; the remaining items are processed by the next run or with --resume.",
+            "Der Lauf wurde nach maximaler Dauer beendet; die übrigen Elemente verarbeitet der nächste Lauf oder --resume.",
+            "Beh sa zastavil po maximálnom trvaní; zvyšné položky spracuje ďalší beh alebo --resume.",
+        ],
+        Msg::ErrorConfig => [
+            "Configuration error: {0}",
+            "Konfigurationsfehler: {0}",
+            "Chyba konfigurácie: {0}",
+        ],
+        Msg::ErrorLoad => [
+            "Data loading failed: {0}",
+            "Laden der Daten fehlgeschlagen: {0}",
+            "Načítanie dát zlyhalo: {0}",
+        ],
+        Msg::ErrorSave => [
+            "Saving processed items failed: {0}",
+            "Speichern der verarbeiteten Elemente fehlgeschlagen: {0}",
+            "Uloženie spracovaných položiek zlyhalo: {0}",
+        ],
+        Msg::ErrorAborted => [
+            "Run aborted by a pipeline hook: {0}",
+            "Lauf durch einen Pipeline-Hook abgebrochen: {0}",
+            "Beh prerušil pipeline hook: {0}",
+        ],
+        Msg::ErrorPartial => [
+            "{0} of {1} items failed to process",
+            "{0} von {1} Elementen konnten nicht verarbeitet werden",
+            "Spracovanie zlyhalo pre {0} z {1} položiek",
+        ],
+        Msg::PipelineFailed => [
+            "Pipeline execution failed: {0}",
+            "Ausführung der Pipeline fehlgeschlagen: {0}",
+            "Vykonanie pipeline zlyhalo: {0}",
+        ],
+        Msg::PipelinesStarting => [
+            "Running {0} pipelines concurrently: {1}",
+            "{0} Pipelines werden gleichzeitig ausgeführt: {1}",
+            "Súbežne sa spúšťajú pipeline ({0}): {1}",
+        ],
+        Msg::ConfigProfile => [
+            "Using config profile '{0}'.",
+            "Verwende das Konfigurationsprofil '{0}'.",
+            "Používa sa konfiguračný profil '{0}'.",
+        ],
+        Msg::ConfigNoProfile => [
+            "No config profile selected.",
+            "Kein Konfigurationsprofil ausgewählt.",
+            "Nie je vybraný žiadny konfiguračný profil.",
+        ],
+        Msg::GenerateWrote => [
+            "Wrote {0} synthetic records ({1} invalid) to {2}.",
+            "{0} synthetische Datensätze ({1} ungültig) nach {2} geschrieben.",
+            "Do {2} sa zapísali syntetické záznamy: {0} (neplatné: {1}).",
+        ],
+        Msg::GenerateFailed => [
+            "Failed to generate data: {0}",
+            "Erzeugen der Daten fehlgeschlagen: {0}",
+            "Generovanie dát zlyhalo: {0}",
+        ],
+        Msg::RecoverNothing => [
+            "Nothing to recover in {0}.",
+            "In {0} gibt es nichts wiederherzustellen.",
+            "V {0} nie je čo obnoviť.",
+        ],
+        Msg::RecoverDone => [
+            "Recovered {0} unfinished saves.",
+            "{0} unvollständige Speichervorgänge wiederhergestellt.",
+            "Obnovené nedokončené ukladania: {0}.",
+        ],
+        Msg::RecoverFailed => [
+            "Recovery failed: {0}",
+            "Wiederherstellung fehlgeschlagen: {0}",
+            "Obnovenie zlyhalo: {0}",
+        ],
+        Msg::RollbackNothing => [
+            "No snapshots in {0}.",
+            "Keine Snapshots in {0}.",
+            "V {0} nie sú žiadne snímky.",
+        ],
+        Msg::RollbackDone => [
+            "Restored {0} data files from snapshot {1}.",
+            "{0} Datendateien aus Snapshot {1} wiederhergestellt.",
+            "Obnovené dátové súbory zo snímky {1}: {0}.",
+        ],
+        Msg::RollbackFailed => [
+            "Rollback failed: {0}",
+            "Zurücksetzen fehlgeschlagen: {0}",
+            "Návrat zlyhal: {0}",
+        ],
+        Msg::JoinSaved => [
+            "Saved {0} joined items to {1}.",
+            "{0} verknüpfte Elemente nach {1} gespeichert.",
+            "Spojené položky ({0}) boli uložené do {1}.",
+        ],
+        Msg::JoinFailed => [
+            "Join failed: {0}",
+            "Join fehlgeschlagen: {0}",
+            "Spojenie zlyhalo: {0}",
+        ],
+        Msg::WatchEnabled => [
+            "Watch mode enabled. Re-running the pipeline every {0} seconds.",
+            "Überwachungsmodus aktiv. Die Pipeline wird alle {0} Sekunden erneut ausgeführt.",
+            "Režim sledovania je zapnutý. Pipeline sa spúšťa každých {0} s.",
+        ],
+        Msg::WatchLeaving => [
+            "Shutdown requested, leaving watch mode.",
+            "Beenden angefordert, verlasse den Überwachungsmodus.",
+            "Požiadavka na ukončenie, opúšťam režim sledovania.",
+        ],
+        Msg::ScheduleEnabled => [
+            "Schedule mode enabled. Running the pipeline on the schedule '{0}' (UTC).",
+            "Zeitplanmodus aktiv. Die Pipeline wird nach dem Zeitplan '{0}' (UTC) ausgeführt.",
+            "Režim plánovania je zapnutý. Pipeline sa spúšťa podľa plánu '{0}' (UTC).",
+        ],
+        Msg::ScheduleNextRun => [
+            "Next scheduled run at {0} (in {1} seconds).",
+            "Nächster geplanter Lauf um {0} (in {1} Sekunden).",
+            "Ďalšie plánované spustenie o {0} (o {1} s).",
+        ],
+        Msg::ScheduleMissedSkip => [
+            "Missed {0} scheduled runs (the last at {1}); skipping to the next scheduled time.",
+            "{0} geplante Läufe verpasst (zuletzt um {1}); es wird auf den nächsten geplanten Zeitpunkt gewartet.",
+            "Vynechané plánované spustenia: {0} (posledné o {1}); čaká sa na ďalší plánovaný čas.",
+        ],
+        Msg::ScheduleMissedRunOnce => [
+            "Missed {0} scheduled runs (the last at {1}); running once now to catch up.",
+            "{0} geplante Läufe verpasst (zuletzt um {1}); die Pipeline wird jetzt einmal nachgeholt.",
+            "Vynechané plánované spustenia: {0} (posledné o {1}); pipeline sa teraz raz dobehne.",
+        ],
+        Msg::ScheduleLeaving => [
+            "Shutdown requested, leaving schedule mode.",
+            "Beenden angefordert, verlasse den Zeitplanmodus.",
+            "Požiadavka na ukončenie, opúšťam režim plánovania.",
+        ],
+        Msg::StreamFailed => [
+            "Streaming failed: {0}",
+            "Streaming fehlgeschlagen: {0}",
+            "Streamovanie zlyhalo: {0}",
+        ],
+        Msg::StreamFinished => [
+            "Streamed {0} items through {1} ({2} malformed lines skipped).",
+            "{0} Elemente durch {1} gestreamt ({2} fehlerhafte Zeilen übersprungen).",
+            "Streamovaných {0} položiek cez {1} ({2} chybných riadkov preskočených).",
+        ],
+    }
+}
+
+/**
+ * @brief Return the template of a message in the current locale.
+ *
+ * @param msg The message key.
+ * @return &'static str The template, with "{0}", "{1}", ... for its arguments.
+ */
+pub fn text(msg: Msg) -> &'static str {
+    catalog(msg)[locale() as usize]
+}
+
+/**
+ * @brief Format a message in the current locale.
+ *
+ * Each "{n}" in the template is replaced by the n-th argument; placeholders
+ * without a matching argument are kept as they are.
+ *
+ * @param msg The message key.
+ * @param args The arguments.
+ * @return String The formatted message.
+ */
+pub fn format(msg: Msg, args: &[&dyn fmt::Display]) -> String {
+    let mut out = String::new();
+    let mut rest = text(msg);
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let placeholder = after
+            .find('}')
+            .and_then(|end| Some((end, args.get(after[..end].parse::<usize>().ok()?)?)));
+        match placeholder {
+            Some((end, arg)) => {
+                let _ = write!(out, "{}", arg);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Format a catalog message in the current locale: `tr!(ItemsLoaded, count)`.
+#[macro_export]
+macro_rules! tr {
+    ($msg:ident) => {
+        $crate::messages::text($crate::messages::Msg::$msg).to_string()
+    };
+    ($msg:ident, $($arg:expr),+ $(,)?) => {
+        $crate::messages::format(
+            $crate::messages::Msg::$msg,
+            &[$(&$arg as &dyn ::std::fmt::Display),+],
+        )
+    };
+}
+
+// End of rust_sample_project/src/messages.rs
@@ -0,0 +1,349 @@
+// rust_sample_project/src/metrics.rs
+
+use std::fmt::Write as FmtWrite; // For write! into a String
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::health::Health;
+use crate::storage;
+
+/**
+ * @brief Upper bounds (in seconds) of the processing duration histogram buckets.
+ */
+const DURATION_BUCKETS: [f64; 9] = [0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0];
+
+/**
+ * @struct Histogram
+ * @brief A cumulative histogram with fixed bucket boundaries.
+ */
+struct Histogram {
+    bucket_counts: [u64; DURATION_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+/**
+ * @enum StreamQueue
+ * @brief The bounded queues of a streamed pass (see ndjson::stream_file),
+ *        named after the stage that takes items from them.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamQueue {
+    /// Parsed items waiting to be processed.
+    Process,
+    /// Processed items waiting to be written.
+    Save,
+}
+
+impl StreamQueue {
+    /**
+     * @brief Return the queue's label value in the exported metrics.
+     */
+    fn label(self) -> &'static str {
+        match self {
+            StreamQueue::Process => "process",
+            StreamQueue::Save => "save",
+        }
+    }
+}
+
+/**
+ * @struct Metrics
+ * @brief Collects pipeline counters and timings for export in Prometheus text format.
+ *
+ * The struct is safe to share between threads (wrap it in an `Arc`), so the
+ * same instance can be updated by the pipeline and read by the /metrics endpoint.
+ */
+pub struct Metrics {
+    items_loaded: AtomicU64,
+    items_processed: AtomicU64,
+    items_failed: AtomicU64,
+    processing_duration: Mutex<Histogram>,
+    /// Items currently in each streaming queue, indexed by StreamQueue.
+    queue_depths: [AtomicU64; 2],
+    /// Most items that were in each streaming queue at once.
+    queue_peaks: [AtomicU64; 2],
+    /// The id of the item handled last while a run processes items.
+    current_item: Mutex<Option<String>>,
+}
+
+impl Metrics {
+    /**
+     * @brief Constructs a new Metrics object with all values at zero.
+     *
+     * @return Metrics A new Metrics instance.
+     */
+    pub fn new() -> Self {
+        Metrics {
+            items_loaded: AtomicU64::new(0),
+            items_processed: AtomicU64::new(0),
+            items_failed: AtomicU64::new(0),
+            processing_duration: Mutex::new(Histogram {
+                bucket_counts: [0; DURATION_BUCKETS.len()],
+                sum: 0.0,
+                count: 0,
+            }),
+            queue_depths: [AtomicU64::new(0), AtomicU64::new(0)],
+            queue_peaks: [AtomicU64::new(0), AtomicU64::new(0)],
+            current_item: Mutex::new(None),
+        }
+    }
+
+    /**
+     * @brief Add to the number of items loaded from the data source.
+     *
+     * @param count Number of newly loaded items.
+     */
+    pub fn add_items_loaded(&self, count: u64) {
+        self.items_loaded.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /**
+     * @brief Count one successfully processed item.
+     */
+    pub fn inc_items_processed(&self) {
+        self.items_processed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /**
+     * @brief Count one item whose processing failed.
+     */
+    pub fn inc_items_failed(&self) {
+        self.items_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /**
+     * @brief Return the number of items loaded so far.
+     *
+     * @return u64 The count.
+     */
+    pub fn items_loaded(&self) -> u64 {
+        self.items_loaded.load(Ordering::Relaxed)
+    }
+
+    /**
+     * @brief Return the number of items processed successfully so far.
+     *
+     * @return u64 The count.
+     */
+    pub fn items_processed(&self) -> u64 {
+        self.items_processed.load(Ordering::Relaxed)
+    }
+
+    /**
+     * @brief Return the number of items whose processing failed so far.
+     *
+     * @return u64 The count.
+     */
+    pub fn items_failed(&self) -> u64 {
+        self.items_failed.load(Ordering::Relaxed)
+    }
+
+    /**
+     * @brief Remember the item a run is handling, for the terminal dashboard.
+     *
+     * @param item_id The item's id, or None once the run's items are done.
+     */
+    pub fn set_current_item(&self, item_id: Option<String>) {
+        *self.current_item.lock().unwrap_or_else(|e| e.into_inner()) = item_id;
+    }
+
+    /**
+     * @brief Return the item a run is handling.
+     *
+     * @return Option<String> The id of the item handled last, or None between runs.
+     */
+    pub fn current_item(&self) -> Option<String> {
+        self.current_item.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /**
+     * @brief Record how long processing a single item took.
+     *
+     * @param duration The measured processing duration.
+     */
+    pub fn observe_processing_duration(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let mut histogram = self
+            .processing_duration
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for (bound, bucket_count) in DURATION_BUCKETS.iter().zip(histogram.bucket_counts.iter_mut()) {
+            if seconds <= *bound {
+                *bucket_count += 1;
+            }
+        }
+        histogram.sum += seconds;
+        histogram.count += 1;
+    }
+
+    /**
+     * @brief Count one item put into a streaming queue.
+     *
+     * @param queue The queue.
+     */
+    pub fn inc_queue_depth(&self, queue: StreamQueue) {
+        let depth = self.queue_depths[queue as usize].fetch_add(1, Ordering::Relaxed) + 1;
+        self.queue_peaks[queue as usize].fetch_max(depth, Ordering::Relaxed);
+    }
+
+    /**
+     * @brief Count one item taken out of a streaming queue.
+     *
+     * @param queue The queue.
+     */
+    pub fn dec_queue_depth(&self, queue: StreamQueue) {
+        self.queue_depths[queue as usize].fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /**
+     * @brief Render all metrics in the Prometheus text exposition format.
+     *
+     * @return String The metrics document (format version 0.0.4).
+     */
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            ("items_loaded_total", "Number of items loaded from the data source.", &self.items_loaded),
+            ("items_processed_total", "Number of items processed successfully.", &self.items_processed),
+            ("items_failed_total", "Number of items whose processing failed.", &self.items_failed),
+        ];
+        for (name, help, value) in counters {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, value.load(Ordering::Relaxed));
+        }
+
+        let histogram = self
+            .processing_duration
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let name = "item_processing_duration_seconds";
+        let _ = writeln!(out, "# HELP {} Time spent processing a single item.", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (bound, bucket_count) in DURATION_BUCKETS.iter().zip(histogram.bucket_counts.iter()) {
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, bucket_count);
+        }
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, histogram.count);
+        let _ = writeln!(out, "{}_sum {}", name, histogram.sum);
+        let _ = writeln!(out, "{}_count {}", name, histogram.count);
+
+        let gauges = [
+            (
+                "stream_queue_depth",
+                "Items waiting for a stage of a stream, queued or blocked on its full queue.",
+                &self.queue_depths,
+            ),
+            ("stream_queue_depth_max", "Most items that waited in a streaming queue at once.", &self.queue_peaks),
+        ];
+        for (name, help, values) in gauges {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            for queue in [StreamQueue::Process, StreamQueue::Save] {
+                let value = values[queue as usize].load(Ordering::Relaxed);
+                let _ = writeln!(out, "{}{{stage=\"{}\"}} {}", name, queue.label(), value);
+            }
+        }
+        out
+    }
+
+    /**
+     * @brief Write the metrics to a textfile (e.g. for node_exporter's textfile collector).
+     *
+     * The file is written to a temporary path and renamed, so a scraper never
+     * reads a half-written document. Missing parent directories are created.
+     *
+     * @param path Destination path of the metrics file.
+     * @return Result<(), String> Ok on success, or an error message.
+     */
+    pub fn write_textfile(&self, path: &Path) -> Result<(), String> {
+        storage::create_parent_dir(path)?;
+        let tmp_path = storage::with_suffix(path, ".tmp");
+        fs::write(&tmp_path, self.render_prometheus())
+            .map_err(|e| format!("Failed to write metrics file '{}': {}", tmp_path.display(), e))?;
+        fs::rename(&tmp_path, path).map_err(|e| {
+            format!("Failed to move metrics file into place at '{}': {}", path.display(), e)
+        })?;
+        log_info!("metrics:write_textfile", "Metrics written to {}.", path.display());
+        Ok(())
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+ * @brief Serve the metrics on a /metrics HTTP endpoint in a background thread.
+ *
+ * Intended for long-running modes (e.g. `--watch`), where a Prometheus server
+ * scrapes the process periodically. The endpoint also answers the /healthz
+ * and /readyz probes (see health.rs).
+ *
+ * @param addr The socket address to listen on, e.g. "127.0.0.1:9898".
+ * @param metrics The shared metrics to expose.
+ * @param health Answers the probes.
+ * @return Result<(), String> Ok once the listener is bound, or an error message.
+ */
+pub fn serve(addr: &str, metrics: Arc<Metrics>, health: Arc<Health>) -> Result<(), String> {
+    let listener = TcpListener::bind(addr)
+        .map_err(|e| format!("Failed to bind metrics endpoint on {}: {}", addr, e))?;
+    log_info!("metrics:serve", "Serving metrics on http://{}/metrics", addr);
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = handle_connection(stream, &metrics, &health) {
+                        log_warning!("metrics:serve", "Metrics endpoint request failed: {}", e);
+                    }
+                }
+                Err(e) => log_warning!("metrics:serve", "Metrics endpoint connection failed: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+/**
+ * @brief Answer a single HTTP request on the metrics endpoint.
+ *
+ * @param stream The accepted client connection.
+ * @param metrics The metrics to render for GET /metrics.
+ * @param health Answers GET /healthz and /readyz.
+ * @return std::io::Result<()> Ok once the response is written.
+ */
+fn handle_connection(mut stream: TcpStream, metrics: &Metrics, health: &Health) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("");
+
+    let (status, content_type, body) = if method == "GET" && target == "/metrics" {
+        ("200 OK", "text/plain; version=0.0.4", metrics.render_prometheus())
+    } else if target == "/healthz" || target == "/readyz" {
+        health.respond(method, target)
+    } else {
+        ("404 Not Found", "text/plain", String::from("Not Found\n"))
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+// End of rust_sample_project/src/metrics.rs
@@ -0,0 +1,222 @@
+// rust_sample_project/src/mmap_input.rs
+
+// Memory-mapped loading of large local data files (see the `mmap` feature).
+// Reading a multi-GB JSON file into a buffer and parsing it into one
+// serde_json::Value holds the whole file and the tree of the whole document
+// before the first item exists. Here the file is mapped instead, so the
+// kernel pages it in as the parser goes (and may drop those pages again), and
+// the items of a current-schema JSON document are deserialized one at a time
+// straight into the item list. CSV and NDJSON files are parsed from the
+// mapping as they would be from a string, without the copy.
+//
+// Only plain local files of at least MIN_MAPPED_SIZE bytes are mapped;
+// smaller, compressed or encrypted files, remote stores and the other formats
+// are read as usual, and JSON documents of an older schema version are
+// migrated as a whole document. Saves replace the data file by renaming a new
+// file over it, so they never change a mapped file; another program
+// truncating the data file while it is loaded would fail the load with
+// SIGBUS, as with any memory-mapped reader.
+
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::Mmap;
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Value};
+
+use crate::compression;
+use crate::data_handler::CURRENT_SCHEMA_VERSION;
+use crate::encryption;
+use crate::invalid_records::{self, InvalidRecords, JsonRecords};
+use crate::item::Item;
+
+/// Data files smaller than this (16 MiB) are read into memory as usual.
+pub const MIN_MAPPED_SIZE: u64 = 16 * 1024 * 1024;
+
+/**
+ * @enum JsonDocument
+ * @brief A JSON data file as parsed from its mapping.
+ */
+#[derive(Debug)]
+pub enum JsonDocument {
+    /// The valid items of a document in the current schema.
+    Items(Vec<Item>),
+    /// A document in an older schema, still to be migrated.
+    Document(Value),
+}
+
+/**
+ * @brief Map a local data file for reading, if it is worth mapping.
+ *
+ * @param path Path of the data file.
+ * @param location The path as text, used in messages.
+ * @return Result<Option<Mmap>, String> The mapping, None if the file is
+ *         smaller than MIN_MAPPED_SIZE, compressed or encrypted, or an error
+ *         message if it cannot be opened or mapped.
+ */
+pub fn map(path: &Path, location: &str) -> Result<Option<Mmap>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to read data file '{}': {}", location, e))?;
+    let size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read data file '{}': {}", location, e))?
+        .len();
+    if size < MIN_MAPPED_SIZE {
+        return Ok(None);
+    }
+    // SAFETY: the mapping is read-only and dropped once the items are parsed;
+    // see the module comment for files truncated while they are mapped.
+    let map = unsafe { Mmap::map(&file) }.map_err(|e| format!("Failed to map data file '{}': {}", location, e))?;
+    if compression::is_compressed(&map) || encryption::is_encrypted(&map) {
+        return Ok(None);
+    }
+    #[cfg(unix)]
+    if let Err(e) = map.advise(memmap2::Advice::Sequential) {
+        log_debug!("mmap_input:map", "Could not advise sequential reads of {}: {}", location, e);
+    }
+    log_debug!("mmap_input:map", "Mapped {} ({} bytes).", location, size);
+    Ok(Some(map))
+}
+
+/**
+ * @brief View the mapped contents of a text data file as UTF-8.
+ *
+ * @param contents The mapped file contents.
+ * @param location Path of the file, used in messages.
+ * @return Result<&str, String> The contents, or an error message if they are not UTF-8.
+ */
+pub fn as_text<'a>(contents: &'a [u8], location: &str) -> Result<&'a str, String> {
+    std::str::from_utf8(contents).map_err(|e| format!("Failed to read data file '{}': {}", location, e))
+}
+
+/**
+ * @brief Parse a JSON data file, deserializing the items of a current-schema
+ *        document one at a time.
+ *
+ * Records that are not a valid item are reported as invalid records, as on
+ * the usual path.
+ *
+ * @param contents The mapped file contents.
+ * @param location Path of the file, used in messages.
+ * @param invalid Where invalid records are reported.
+ * @return Result<JsonDocument, String> The items, the document to migrate,
+ *         or an error message for malformed JSON or a newer schema version.
+ */
+pub fn parse_json(contents: &[u8], location: &str, invalid: &mut InvalidRecords) -> Result<JsonDocument, String> {
+    let parse_error = |e: serde_json::Error| {
+        let message = format!("Failed to parse data file '{}': {}", location, e);
+        invalid_records::with_source(message, std::str::from_utf8(contents).unwrap_or_default(), e.line())
+    };
+    let mut deserializer = serde_json::Deserializer::from_slice(contents);
+    let document = DocumentSeed { invalid, contents }
+        .deserialize(&mut deserializer)
+        .map_err(parse_error)?;
+    deserializer.end().map_err(parse_error)?;
+    Ok(document)
+}
+
+/// Deserializes the top level of a data file.
+struct DocumentSeed<'s, 'a, 'c> {
+    /// Where the invalid records of streamed items go.
+    invalid: &'s mut InvalidRecords<'a>,
+    /// The file contents, to locate invalid records.
+    contents: &'c [u8],
+}
+
+impl<'de> DeserializeSeed<'de> for DocumentSeed<'_, '_, '_> {
+    type Value = JsonDocument;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<JsonDocument, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DocumentSeed<'_, '_, '_> {
+    type Value = JsonDocument;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON object or array at the top level")
+    }
+
+    // Version 1 files were a bare array of items; they are migrated whole.
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonDocument, A::Error> {
+        let mut raw_items = Vec::new();
+        while let Some(raw_item) = seq.next_element::<Value>()? {
+            raw_items.push(raw_item);
+        }
+        Ok(JsonDocument::Document(Value::Array(raw_items)))
+    }
+
+    // The items are streamed only if schema_version comes first and is the
+    // current one, as in files this build saves; otherwise the document is
+    // kept for migration.
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonDocument, A::Error> {
+        let mut version = None;
+        let mut items = None;
+        let mut fields = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "items" && items.is_none() && version == Some(CURRENT_SCHEMA_VERSION) {
+                items = Some(map.next_value_seed(ItemsSeed { invalid: &mut *self.invalid, contents: self.contents })?);
+                continue;
+            }
+            let value: Value = map.next_value()?;
+            if key == "schema_version" {
+                version = value.as_u64();
+                if version.is_some_and(|version| version > CURRENT_SCHEMA_VERSION) {
+                    return Err(de::Error::custom(format!(
+                        "data file has schema version {}, but this build supports at most version {}",
+                        value, CURRENT_SCHEMA_VERSION
+                    )));
+                }
+            }
+            fields.insert(key, value);
+        }
+        Ok(match items {
+            Some(items) => JsonDocument::Items(items),
+            None => JsonDocument::Document(Value::Object(fields)),
+        })
+    }
+}
+
+/// Deserializes the items array of a current-schema document.
+struct ItemsSeed<'s, 'a, 'c> {
+    invalid: &'s mut InvalidRecords<'a>,
+    contents: &'c [u8],
+}
+
+impl<'de> DeserializeSeed<'de> for ItemsSeed<'_, '_, '_> {
+    type Value = Vec<Item>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Vec<Item>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ItemsSeed<'_, '_, '_> {
+    type Value = Vec<Item>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an 'items' array")
+    }
+
+    // Each record is read as a Value first, so an invalid record is skipped
+    // without losing the parser's place in the file.
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<Item>, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        let mut records = JsonRecords::new(self.contents);
+        let mut index = 0;
+        while let Some(raw_item) = seq.next_element::<Value>()? {
+            index += 1;
+            match serde_json::from_value::<Item>(raw_item) {
+                Ok(item) => items.push(item),
+                Err(e) => {
+                    let (line, source) = records.locate(index - 1);
+                    self.invalid.skip(format_args!("record #{}", index), line, &source, e);
+                }
+            }
+        }
+        Ok(items)
+    }
+}
+
+// End of rust_sample_project/src/mmap_input.rs
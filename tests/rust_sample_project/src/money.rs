@@ -0,0 +1,540 @@
+// rust_sample_project/src/money.rs
+
+// Fixed-point decimal amounts for item values. A Money value is an integer
+// number of minor units plus its number of decimal places, so values are
+// compared, serialized and displayed exactly instead of as binary floats.
+// Parsed amounts keep the decimal places they were written with (at least
+// DEFAULT_DECIMALS); callers that want a fixed precision, such as the item
+// processor with the value_decimals config key, round explicitly (half away
+// from zero), so pipelines with different precisions can run side by side.
+// Part of the no_std core (see item_core.rs).
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::cmp::Ordering;
+use core::fmt;
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Largest supported number of decimal places.
+pub const MAX_DECIMALS: u32 = 9;
+
+/// Fewest decimal places of a parsed amount, and those of whole amounts.
+pub const DEFAULT_DECIMALS: u32 = 2;
+
+/**
+ * @struct Money
+ * @brief An exact decimal amount with a fixed number of decimal places.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Money {
+    /// The amount in minor units, e.g. 15075 for 150.75 at two decimals.
+    minor: i64,
+    /// Number of decimal places of `minor`.
+    scale: u32,
+}
+
+impl Money {
+    /**
+     * @brief Constructs an amount from minor units.
+     *
+     * @param minor The amount in minor units.
+     * @param scale Number of decimal places (at most MAX_DECIMALS).
+     * @return Money The amount, e.g. from_minor(15075, 2) is 150.75.
+     */
+    pub fn from_minor(minor: i64, scale: u32) -> Self {
+        Money {
+            minor,
+            scale: scale.min(MAX_DECIMALS),
+        }
+    }
+
+    /**
+     * @brief Constructs a whole amount with DEFAULT_DECIMALS decimal places.
+     *
+     * @param units The whole amount.
+     * @return Money The amount (saturating at the representable range).
+     */
+    pub fn from_int(units: i64) -> Self {
+        let scale = DEFAULT_DECIMALS;
+        Money {
+            minor: units.saturating_mul(10i64.pow(scale)),
+            scale,
+        }
+    }
+
+    /**
+     * @brief Constructs an amount from a float, see parse.
+     *
+     * The float is taken at its shortest decimal representation, so 1.005
+     * is 1.005 rather than the binary float's 1.00499999999999989...
+     *
+     * @param value The amount.
+     * @return Result<Money, String> The amount, or an error message for
+     *         non-finite or out-of-range values.
+     */
+    pub fn from_f64(value: f64) -> Result<Self, String> {
+        if !value.is_finite() {
+            return Err(format!("value {} is not a finite number", value));
+        }
+        Money::parse(&value.to_string())
+    }
+
+    /**
+     * @brief Constructs an amount from a float, rounded to a number of decimal places.
+     *
+     * Like from_f64, the float is taken at its shortest decimal
+     * representation, so 1.005 rounds to 1.01 at two decimals.
+     *
+     * @param value The amount.
+     * @param scale Number of decimal places (at most MAX_DECIMALS).
+     * @return Result<Money, String> The amount, or an error message for
+     *         non-finite or out-of-range values.
+     */
+    pub fn from_f64_rounded(value: f64, scale: u32) -> Result<Self, String> {
+        if !value.is_finite() {
+            return Err(format!("value {} is not a finite number", value));
+        }
+        Money::parse_rounded(&value.to_string(), scale)
+    }
+
+    /**
+     * @brief Constructs the amount nearest to a float, for limits such as thresholds.
+     *
+     * @param value The amount.
+     * @return Money The amount; values beyond the representable range, and
+     *         infinities, saturate at its ends, and NaN becomes the largest
+     *         amount, so no value exceeds it.
+     */
+    pub fn from_f64_saturating(value: f64) -> Self {
+        let limit = Money {
+            minor: if value < 0.0 { -i64::MAX } else { i64::MAX },
+            scale: 0,
+        };
+        Money::from_f64(value).unwrap_or(limit)
+    }
+
+    /**
+     * @brief Parse a decimal amount such as "150.75" or "-3".
+     *
+     * The amount keeps the decimal places it was written with, but at least
+     * DEFAULT_DECIMALS; digits beyond MAX_DECIMALS are rounded.
+     *
+     * @param text The amount.
+     * @return Result<Money, String> The amount, or an error message.
+     */
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let written = text.trim().split_once('.').map_or(0, |(_, fraction)| fraction.len());
+        let scale = u32::try_from(written).unwrap_or(MAX_DECIMALS).clamp(DEFAULT_DECIMALS, MAX_DECIMALS);
+        Money::parse_rounded(text, scale)
+    }
+
+    /**
+     * @brief Parse a decimal amount, rounded to a number of decimal places.
+     *
+     * @param text The amount.
+     * @param scale Number of decimal places (at most MAX_DECIMALS).
+     * @return Result<Money, String> The amount, or an error message.
+     */
+    pub fn parse_rounded(text: &str, scale: u32) -> Result<Self, String> {
+        let invalid = || format!("Invalid amount '{}'", text);
+        let trimmed = text.trim();
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !all_digits(whole) || !all_digits(fraction) {
+            return Err(invalid());
+        }
+
+        let scale = scale.min(MAX_DECIMALS);
+        let kept = &fraction[..fraction.len().min(scale as usize)];
+        let mut minor: i64 = 0;
+        for digit in whole.bytes().chain(kept.bytes()) {
+            minor = minor
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(i64::from(digit - b'0')))
+                .ok_or_else(|| format!("Amount '{}' is out of range", text))?;
+        }
+        // Pad missing decimals, then round half away from zero on the first dropped digit
+        minor = minor
+            .checked_mul(10i64.pow(scale - kept.len() as u32))
+            .ok_or_else(|| format!("Amount '{}' is out of range", text))?;
+        if fraction.as_bytes().get(scale as usize).is_some_and(|digit| *digit >= b'5') {
+            minor = minor.checked_add(1).ok_or_else(|| format!("Amount '{}' is out of range", text))?;
+        }
+        Ok(Money {
+            minor: if negative { -minor } else { minor },
+            scale,
+        })
+    }
+
+    /**
+     * @brief Return the amount as a float, e.g. for arithmetic in transformations.
+     *
+     * @return f64 The nearest float.
+     */
+    pub fn to_f64(self) -> f64 {
+        // 10^scale is exact as a float for every supported scale
+        self.minor as f64 / 10u64.pow(self.scale) as f64
+    }
+
+    /**
+     * @brief Return the amount in minor units.
+     *
+     * @return i64 The minor units, see scale().
+     */
+    pub fn minor_units(self) -> i64 {
+        self.minor
+    }
+
+    /**
+     * @brief Return the number of decimal places of the amount.
+     *
+     * @return u32 The scale.
+     */
+    pub fn scale(self) -> u32 {
+        self.scale
+    }
+
+    /**
+     * @brief Return the amount at another number of decimal places.
+     *
+     * @param scale Number of decimal places (at most MAX_DECIMALS); dropped
+     *        digits are rounded half away from zero.
+     * @return Result<Money, String> The amount, or an error message if it is
+     *         out of range at the larger scale.
+     */
+    pub fn round_to(self, scale: u32) -> Result<Self, String> {
+        let scale = scale.min(MAX_DECIMALS);
+        if scale >= self.scale {
+            let minor = self
+                .minor
+                .checked_mul(10i64.pow(scale - self.scale))
+                .ok_or_else(|| format!("Amount {} is out of range at {} decimals", self, scale))?;
+            return Ok(Money { minor, scale });
+        }
+        let factor = 10i64.pow(self.scale - scale);
+        let mut minor = self.minor / factor;
+        if (self.minor % factor).abs() * 2 >= factor {
+            minor += self.minor.signum();
+        }
+        Ok(Money { minor, scale })
+    }
+
+    /**
+     * @brief Add two amounts exactly, at the larger of their scales.
+     *
+     * @param other The amount to add.
+     * @return Option<Money> The sum, or None if it is out of range.
+     */
+    pub fn checked_add(self, other: Money) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let minor = self.minor_at(scale)?.checked_add(other.minor_at(scale)?)?;
+        Some(Money { minor, scale })
+    }
+
+    /**
+     * @brief Subtract an amount exactly, at the larger of the two scales.
+     *
+     * @param other The amount to subtract.
+     * @return Option<Money> The difference, or None if it is out of range.
+     */
+    pub fn checked_sub(self, other: Money) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let minor = self.minor_at(scale)?.checked_sub(other.minor_at(scale)?)?;
+        Some(Money { minor, scale })
+    }
+
+    /**
+     * @brief Divide the amount by a count, e.g. for an average.
+     *
+     * @param divisor The count to divide by.
+     * @param scale Decimal places of the result (at most MAX_DECIMALS);
+     *        dropped digits are rounded half away from zero.
+     * @return Option<Money> The quotient, or None for a zero divisor or a
+     *         result out of range.
+     */
+    pub fn checked_div(self, divisor: i64, scale: u32) -> Option<Self> {
+        let scale = scale.min(MAX_DECIMALS);
+        let common = scale.max(self.scale);
+        let numerator = self.rescaled(common);
+        let denominator = i128::from(divisor).checked_mul(10i128.pow(common - scale))?;
+        let mut quotient = numerator.checked_div(denominator)?;
+        if (numerator % denominator).abs() * 2 >= denominator.abs() {
+            quotient += numerator.signum() * denominator.signum();
+        }
+        Some(Money { minor: i64::try_from(quotient).ok()?, scale })
+    }
+
+    /**
+     * @brief Return the minor units at a larger number of decimal places, if they fit.
+     */
+    fn minor_at(self, scale: u32) -> Option<i64> {
+        self.minor.checked_mul(10i64.pow(scale - self.scale))
+    }
+
+    /**
+     * @brief Return the minor units rescaled to a larger number of decimal places.
+     *
+     * @param scale The target scale (not smaller than self.scale).
+     * @return i128 The rescaled minor units.
+     */
+    fn rescaled(self, scale: u32) -> i128 {
+        i128::from(self.minor) * 10i128.pow(scale - self.scale)
+    }
+}
+
+impl PartialEq for Money {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Money {}
+
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Money {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        self.rescaled(scale).cmp(&other.rescaled(scale))
+    }
+}
+
+impl fmt::Display for Money {
+    /**
+     * @brief Format the amount with all of its decimal places, e.g. `85.00`.
+     */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let magnitude = self.minor.unsigned_abs();
+        if self.scale == 0 {
+            return write!(f, "{}{}", sign, magnitude);
+        }
+        let factor = 10u64.pow(self.scale);
+        write!(
+            f,
+            "{}{}.{:0width$}",
+            sign,
+            magnitude / factor,
+            magnitude % factor,
+            width = self.scale as usize
+        )
+    }
+}
+
+impl Serialize for Money {
+    /**
+     * @brief Serialize as a decimal string such as "150.75", which keeps the
+     *        exact amount and its decimal places (a float would do neither).
+     */
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    /**
+     * @brief Deserialize from a decimal string or a number, see parse.
+     */
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+/**
+ * @struct MoneyVisitor
+ * @brief Accepts the representations of an amount found in data files.
+ */
+struct MoneyVisitor;
+
+impl Visitor<'_> for MoneyVisitor {
+    type Value = Money;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a decimal amount")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Money, E> {
+        Money::parse(&value.to_string()).map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Money, E> {
+        Money::parse(&value.to_string()).map_err(E::custom)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Money, E> {
+        Money::from_f64(value).map_err(E::custom)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Money, E> {
+        Money::parse(value).map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(text: &str) -> String {
+        Money::parse(text).unwrap_or_else(|e| panic!("{}", e)).to_string()
+    }
+
+    fn rounded(text: &str, scale: u32) -> String {
+        Money::parse_rounded(text, scale).unwrap_or_else(|e| panic!("{}", e)).to_string()
+    }
+
+    #[test]
+    fn accepts_whole_decimal_and_signed_amounts() {
+        assert_eq!(parsed("150.75"), "150.75");
+        assert_eq!(parsed("150"), "150.00");
+        assert_eq!(parsed("150."), "150.00");
+        assert_eq!(parsed(".5"), "0.50");
+        assert_eq!(parsed("+3"), "3.00");
+        assert_eq!(parsed(" 7.1 "), "7.10");
+        assert_eq!(parsed("007.00"), "7.00");
+        assert_eq!(Money::parse("150.75").unwrap().minor_units(), 15075);
+        assert_eq!(Money::parse("150.75").unwrap().scale(), 2);
+    }
+
+    #[test]
+    fn keeps_the_decimal_places_given() {
+        assert_eq!(parsed("1.005"), "1.005");
+        assert_eq!(parsed("-0.004"), "-0.004");
+        assert_eq!(parsed("2.50000"), "2.50000");
+        assert_eq!(parsed("0.1234567891"), "0.123456789");
+        assert_eq!(parsed("0.1234567895"), "0.123456790");
+        assert_eq!(Money::from_f64(1.005).unwrap().to_string(), "1.005");
+        assert_eq!(Money::from_f64(2.0 / 3.0).unwrap().to_string(), "0.666666667");
+    }
+
+    #[test]
+    fn rounds_half_away_from_zero() {
+        assert_eq!(rounded("1.004", 2), "1.00");
+        assert_eq!(rounded("1.005", 2), "1.01");
+        assert_eq!(rounded("1.00499999", 2), "1.00");
+        assert_eq!(rounded("0.995", 2), "1.00");
+        assert_eq!(rounded("-1.005", 2), "-1.01");
+        assert_eq!(rounded("-1.004", 2), "-1.00");
+        assert_eq!(rounded("7.5", 0), "8");
+        // Shortest decimal form of the float, not its binary value (1.00499999999999989...)
+        assert_eq!(Money::from_f64_rounded(1.005, 2).unwrap().to_string(), "1.01");
+        assert_eq!(Money::from_f64_rounded(2.0 / 3.0, 2).unwrap().to_string(), "0.67");
+    }
+
+    #[test]
+    fn divides_by_a_count_with_rounding() {
+        let divide = |text: &str, divisor: i64, scale: u32| {
+            Money::parse(text).unwrap().checked_div(divisor, scale).map(|money| money.to_string())
+        };
+        assert_eq!(divide("10.00", 3, 2).as_deref(), Some("3.33"));
+        assert_eq!(divide("20.00", 3, 2).as_deref(), Some("6.67"));
+        assert_eq!(divide("-20.00", 3, 2).as_deref(), Some("-6.67"));
+        assert_eq!(divide("0.05", 2, 2).as_deref(), Some("0.03"));
+        assert_eq!(divide("1.005", 1, 2).as_deref(), Some("1.01"));
+        assert_eq!(divide("7", 2, 0).as_deref(), Some("4"));
+        assert_eq!(divide("7", 0, 2), None);
+    }
+
+    #[test]
+    fn rounds_to_another_scale() {
+        let round = |text: &str, scale: u32| Money::parse(text).unwrap().round_to(scale).unwrap().to_string();
+        assert_eq!(round("1.005", 2), "1.01");
+        assert_eq!(round("-1.005", 2), "-1.01");
+        assert_eq!(round("1.004", 2), "1.00");
+        assert_eq!(round("-0.004", 2), "0.00");
+        assert_eq!(round("2.5", 0), "3");
+        assert_eq!(round("150.75", 4), "150.7500");
+        assert_eq!(Money::from_int(1).round_to(MAX_DECIMALS + 1).unwrap().scale(), MAX_DECIMALS);
+        assert_eq!(
+            Money::from_minor(i64::MAX, 2).round_to(3),
+            Err(String::from("Amount 92233720368547758.07 is out of range at 3 decimals"))
+        );
+    }
+
+    #[test]
+    fn adds_and_subtracts_exactly_at_the_larger_scale() {
+        let amount = |text: &str| Money::parse(text).unwrap();
+        assert_eq!(amount("150.75").checked_add(amount("0.125")).unwrap().to_string(), "150.875");
+        assert_eq!(amount("0.1").checked_add(amount("0.2")).unwrap().to_string(), "0.30");
+        assert_eq!(amount("10").checked_sub(amount("12.5")).unwrap().to_string(), "-2.50");
+        assert_eq!(amount("-1.005").checked_sub(amount("-1.005")).unwrap(), Money::from_int(0));
+        assert_eq!(Money::from_minor(i64::MAX, 2).checked_add(Money::from_minor(1, 2)), None);
+        assert_eq!(Money::from_minor(i64::MIN, 2).checked_sub(Money::from_minor(1, 2)), None);
+        assert_eq!(Money::from_minor(i64::MAX, 2).checked_sub(Money::from_minor(0, 3)), None, "out of range at 3");
+    }
+
+    #[test]
+    fn handles_negative_amounts() {
+        assert_eq!(parsed("-3"), "-3.00");
+        assert_eq!(parsed("-0.5"), "-0.50");
+        assert_eq!(rounded("-0.004", 2), "0.00");
+        assert_eq!(Money::parse_rounded("-0.004", 2).unwrap(), Money::from_int(0));
+        assert_eq!(Money::from_minor(-5, 3).to_string(), "-0.005");
+        assert!(Money::parse("-1").unwrap() < Money::parse("-0.99").unwrap());
+        assert_eq!(Money::from_f64(-12.5).unwrap().minor_units(), -1250);
+    }
+
+    #[test]
+    fn compares_amounts_of_different_scales_exactly() {
+        assert_eq!(Money::from_minor(150, 1), Money::from_minor(1500, 2));
+        assert!(Money::from_minor(1001, 3) > Money::from_minor(100, 2));
+        assert_eq!(Money::from_minor(5, 0).to_string(), "5");
+        assert_eq!(Money::from_minor(1, MAX_DECIMALS + 3).scale(), MAX_DECIMALS);
+    }
+
+    #[test]
+    fn rejects_malformed_amounts() {
+        for text in ["", " ", "-", "+", ".", "-.", "abc", "1e5", "1.2.3", "1,5", "--1", "+-1", "1 000", "0x10"] {
+            assert_eq!(Money::parse(text), Err(format!("Invalid amount '{}'", text)));
+        }
+        assert_eq!(
+            Money::from_f64(f64::NAN),
+            Err(String::from("value NaN is not a finite number"))
+        );
+        assert_eq!(
+            Money::from_f64(f64::INFINITY),
+            Err(String::from("value inf is not a finite number"))
+        );
+    }
+
+    #[test]
+    fn rejects_amounts_that_overflow_the_minor_units() {
+        assert_eq!(Money::parse("92233720368547758.07").unwrap().minor_units(), i64::MAX);
+        assert_eq!(Money::parse("-92233720368547758.07").unwrap().minor_units(), -i64::MAX);
+        for text in ["92233720368547758.08", "92233720368547758.075", "92233720368547759", "-99999999999999999999"] {
+            assert_eq!(Money::parse(text), Err(format!("Amount '{}' is out of range", text)));
+        }
+        assert!(Money::from_f64(1e300).unwrap_err().ends_with("is out of range"));
+        assert_eq!(Money::from_int(i64::MAX).minor_units(), i64::MAX);
+    }
+
+    #[test]
+    fn saturates_thresholds_beyond_the_range() {
+        assert_eq!(Money::from_f64_saturating(100.5), Money::from_minor(1005, 1));
+        assert_eq!(Money::from_f64_saturating(1e300).minor_units(), i64::MAX);
+        assert_eq!(Money::from_f64_saturating(f64::NEG_INFINITY).minor_units(), -i64::MAX);
+        assert!(Money::from_f64_saturating(f64::NAN) > Money::from_int(i64::MAX / 1000));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn serializes_as_an_exact_decimal_string() {
+        let amount = Money::parse("0.10").unwrap();
+        assert_eq!(serde_json::to_string(&amount).unwrap(), "\"0.10\"");
+        let parsed: Money = serde_json::from_str("\"1.005\"").unwrap();
+        assert_eq!(parsed.to_string(), "1.005");
+        let number: Money = serde_json::from_str("150.75").unwrap();
+        assert_eq!(number, Money::from_minor(15075, 2));
+    }
+}
+
+// End of rust_sample_project/src/money.rs
@@ -0,0 +1,520 @@
+// rust_sample_project/src/ndjson.rs
+
+// Items data files in newline-delimited JSON (NDJSON / JSON Lines, ".ndjson"
+// or ".jsonl"): one Item object per line, without the schema_version
+// document around them. Besides whole-file loading and saving, an NDJSON
+// data file can be streamed (--stream): items are read, processed and
+// written one line at a time by three stages connected through bounded
+// queues, so files of any size run in constant memory.
+// Malformed lines are skipped and counted in both cases; a streamed pass
+// copies them to the rewritten file unchanged, so they are not lost. In
+// strict mode (see invalid_records.rs) loading fails on them instead, and a
+// streamed pass stops at the first one and leaves the file as it was.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::panic;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread;
+
+use crate::compression::Compression;
+use crate::encryption;
+use crate::integrity;
+use crate::filter::FilterExpr;
+use crate::invalid_records::{InvalidRecords, Strictness};
+use crate::item::{Item, ItemStatus};
+#[cfg(feature = "arena")]
+use crate::item_arena::LineArena;
+use crate::item_ref::ItemRef;
+use crate::item_processor::ItemProcessor;
+use crate::metrics::{Metrics, StreamQueue};
+use crate::pipeline;
+use crate::shutdown;
+use crate::storage;
+
+/**
+ * @struct StreamSummary
+ * @brief What a streamed pass over an NDJSON data file did.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamSummary {
+    /// Items read and written back (processed or not).
+    pub items: usize,
+    /// Items passed through unchanged because they were already processed (incremental mode).
+    pub already_processed: usize,
+    /// Lines that were not a valid item; they are copied to the output unchanged.
+    pub malformed_lines: usize,
+    /// True if a shutdown request stopped processing; the remaining lines were copied unchanged.
+    pub interrupted: bool,
+}
+
+/**
+ * @brief Parse the items of an NDJSON data file.
+ *
+ * Blank lines are ignored; lines that are not a valid item are reported as
+ * invalid records.
+ *
+ * @param contents The file contents.
+ * @param invalid Where invalid lines are reported (it knows the file).
+ * @return Result<Vec<Item>, String> The valid items.
+ */
+pub fn parse_items(contents: &str, invalid: &mut InvalidRecords) -> Result<Vec<Item>, String> {
+    let mut items = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Item>(line) {
+            Ok(item) => items.push(item),
+            Err(e) => invalid.skip(format_args!("line {}", index + 1), Some(index + 1), line, e),
+        }
+    }
+    Ok(items)
+}
+
+/**
+ * @brief Serialize items as NDJSON, one item per line.
+ *
+ * @param items The items to write.
+ * @return Result<Vec<u8>, String> The file contents, or an error message.
+ */
+pub fn encode_items(items: &[Item]) -> Result<Vec<u8>, String> {
+    let mut contents = Vec::new();
+    for item in items {
+        serde_json::to_writer(&mut contents, item)
+            .map_err(|e| format!("Failed to serialize item {}: {}", item.item_id, e))?;
+        contents.push(b'\n');
+    }
+    Ok(contents)
+}
+
+/**
+ * @struct StreamOptions
+ * @brief How a streamed pass over an NDJSON data file runs.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamOptions {
+    /// Pass items that are already processed through unchanged.
+    pub incremental: bool,
+    /// Verify and rewrite the file's checksum file (see integrity.rs).
+    pub checksums: bool,
+    /// How malformed lines are treated; strict stops the pass at the first one.
+    pub strictness: Strictness,
+    /// Lines per arena batch, 0 to read line by line (see item_arena.rs).
+    pub arena_batch: usize,
+    /// Items the load stage may read ahead of the process stage.
+    pub process_buffer: usize,
+    /// Items the process stage may get ahead of the save stage.
+    pub save_buffer: usize,
+}
+
+/**
+ * @brief Process a local NDJSON data file line by line, rewriting it in place.
+ *
+ * The pass runs as three stages connected by bounded queues: the load stage
+ * reads and parses lines, the process stage runs each item through the filter,
+ * tag rules and processor like a streamed Kafka message (see
+ * pipeline::process_streamed_item), and the save stage writes the items to a
+ * temporary file that replaces the data file at the end (or is removed if the
+ * pass fails), so an interrupted run never truncates it. A full queue blocks
+ * the stage feeding it, so a slow disk holds back processing and reading
+ * instead of filling memory; the queue depths are exported as metrics. After a
+ * shutdown request the remaining lines are copied unchanged. Malformed lines
+ * are skipped, unless the strictness is strict: then the pass fails and the
+ * file is not replaced. Compressed and encrypted files cannot be streamed.
+ * With checksums, the file is verified against its checksum file before the
+ * pass and gets a new checksum file after it. With an arena batch size (and
+ * the `arena` feature), lines are read into an arena that is freed a batch at
+ * a time (see item_arena.rs).
+ *
+ * @param path The NDJSON data file.
+ * @param item_processor Processes the selected items.
+ * @param item_filter Optional filter selecting the items to process.
+ * @param options Incremental mode, checksums, strictness, arena batch and queue sizes.
+ * @param metrics Collector for the stream's counters, timings and queue depths.
+ * @return Result<StreamSummary, String> What the pass did, or an error message
+ *         if the file cannot be read or written.
+ */
+pub fn stream_file(
+    path: &Path,
+    item_processor: &Arc<ItemProcessor>,
+    item_filter: Option<&FilterExpr>,
+    options: &StreamOptions,
+    metrics: &Metrics,
+) -> Result<StreamSummary, String> {
+    let location = path.display().to_string();
+    if storage::is_remote(&location) {
+        return Err(format!("Only local NDJSON data files can be streamed, not '{}'", location));
+    }
+    if Compression::from_path(&location) != Compression::None {
+        return Err(format!(
+            "Compressed data file '{}' cannot be streamed; decompress it or run without --stream",
+            location
+        ));
+    }
+
+    #[cfg(not(feature = "arena"))]
+    if options.arena_batch > 0 {
+        return Err(String::from("stream_arena_batch needs a build with the `arena` feature"));
+    }
+    if options.checksums {
+        integrity::verify(path, &integrity::file_digest(path)?).map_err(|e| e.to_string())?;
+    }
+    let input = File::open(path).map_err(|e| format!("Failed to read data file '{}': {}", location, e))?;
+    let mut reader = BufReader::new(input);
+    let head = reader
+        .fill_buf()
+        .map_err(|e| format!("Failed to read data file '{}': {}", location, e))?;
+    if encryption::is_encrypted(head) {
+        return Err(format!(
+            "Encrypted data file '{}' cannot be streamed; run without --stream",
+            location
+        ));
+    }
+    let tmp_path = storage::with_suffix(path, ".tmp");
+    let output = File::create(&tmp_path)
+        .map_err(|e| format!("Failed to write data file '{}': {}", tmp_path.display(), e))?;
+
+    log_info!("ndjson:stream_file", "Streaming items from {}...", location);
+    let (to_process, process_queue) = mpsc::sync_channel(options.process_buffer.max(1));
+    let (to_save, save_queue) = mpsc::sync_channel(options.save_buffer.max(1));
+    let (loaded, processed, saved) = thread::scope(|scope| {
+        let process_stage = scope.spawn(|| {
+            let mut stage = ProcessStage {
+                item_processor,
+                item_filter,
+                incremental: options.incremental,
+                metrics,
+                already_processed: 0,
+            };
+            stage.run(
+                StageReceiver { receiver: process_queue, queue: StreamQueue::Process, metrics },
+                StageSender { sender: to_save, queue: StreamQueue::Save, metrics },
+            )
+        });
+        let save_stage = scope.spawn(|| {
+            save_records(StageReceiver { receiver: save_queue, queue: StreamQueue::Save, metrics }, output, &tmp_path)
+        });
+        let mut load_stage = LoadStage {
+            queue: StageSender { sender: to_process, queue: StreamQueue::Process, metrics },
+            location: &location,
+            incremental: options.incremental,
+            strictness: options.strictness,
+            metrics,
+            malformed_lines: 0,
+            interrupted: false,
+        };
+        #[cfg(feature = "arena")]
+        let loaded = if options.arena_batch > 0 {
+            load_in_arena(&mut reader, options.arena_batch, &mut load_stage)
+        } else {
+            load_lines(&mut reader, &mut load_stage)
+        };
+        #[cfg(not(feature = "arena"))]
+        let loaded = load_lines(&mut reader, &mut load_stage);
+        // Closing the queue lets the other stages finish
+        let loaded = loaded.map(|_| (load_stage.malformed_lines, load_stage.interrupted));
+        drop(load_stage);
+        let join = |stage: thread::ScopedJoinHandle<'_, _>| stage.join().unwrap_or_else(|panic| panic::resume_unwind(panic));
+        (loaded, join(process_stage), join(save_stage))
+    });
+    // A stage stops when the next one has stopped, so the last failing stage has the cause
+    let finished = saved
+        .and_then(|items| Ok((items, processed?, loaded?)))
+        .and_then(|finished| {
+            fs::rename(&tmp_path, path)
+                .map(|_| finished)
+                .map_err(|e| format!("Failed to move data file into place at '{}': {}", location, e))
+        });
+    let (items, already_processed, (malformed_lines, interrupted)) = match finished {
+        Ok(finished) => finished,
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+    };
+    if options.checksums {
+        integrity::write_sidecar(path, &integrity::file_digest(path)?)?;
+    }
+    Ok(StreamSummary {
+        items,
+        already_processed,
+        malformed_lines,
+        interrupted,
+    })
+}
+
+/**
+ * @enum Record
+ * @brief A line of a streamed file on its way through the stages.
+ */
+enum Record {
+    /// An item to run through the processor (or pass through, in incremental mode).
+    Item(Item),
+    /// An item already processed, serialized again (read as a view in arena mode).
+    Unchanged(String),
+    /// A line copied as it is: malformed, or read after a shutdown request.
+    Copied(String),
+}
+
+/**
+ * @struct StageSender
+ * @brief The sending end of a queue between two stages, counting its depth.
+ */
+struct StageSender<'a> {
+    sender: SyncSender<Record>,
+    queue: StreamQueue,
+    metrics: &'a Metrics,
+}
+
+impl StageSender<'_> {
+    /**
+     * @brief Queue a record, waiting while the queue is full.
+     *
+     * @return bool False if the receiving stage has stopped.
+     */
+    fn send(&self, record: Record) -> bool {
+        self.metrics.inc_queue_depth(self.queue);
+        let sent = self.sender.send(record).is_ok();
+        if !sent {
+            self.metrics.dec_queue_depth(self.queue);
+        }
+        sent
+    }
+}
+
+/**
+ * @struct StageReceiver
+ * @brief The receiving end of a queue between two stages, counting its depth.
+ */
+struct StageReceiver<'a> {
+    receiver: Receiver<Record>,
+    queue: StreamQueue,
+    metrics: &'a Metrics,
+}
+
+impl Iterator for StageReceiver<'_> {
+    type Item = Record;
+
+    /**
+     * @brief Take the next record, waiting while the queue is empty.
+     *
+     * Ends once the sending stage has finished and the queue is empty.
+     */
+    fn next(&mut self) -> Option<Record> {
+        let record = self.receiver.recv().ok()?;
+        self.metrics.dec_queue_depth(self.queue);
+        Some(record)
+    }
+}
+
+/**
+ * @struct LoadStage
+ * @brief Reads and parses the lines of a streamed file.
+ */
+struct LoadStage<'a> {
+    /// The queue to the process stage.
+    queue: StageSender<'a>,
+    /// Path of the data file, used in messages.
+    location: &'a str,
+    /// Pass items that are already processed through unchanged.
+    incremental: bool,
+    /// How malformed lines are treated.
+    strictness: Strictness,
+    metrics: &'a Metrics,
+    /// Lines that were not a valid item.
+    malformed_lines: usize,
+    /// True once a shutdown request was seen.
+    interrupted: bool,
+}
+
+impl LoadStage<'_> {
+    /**
+     * @brief Handle one line: parse its item and queue it, queue it for
+     *        copying after a shutdown request, or skip it if it is malformed
+     *        (and the strictness allows it).
+     *
+     * @param line The line, without its line ending.
+     * @param line_number 1-based line number, used in messages.
+     * @param borrowed Parse the item as an ItemRef and copy it into an owned
+     *        Item only if it gets processed.
+     * @return Result<(), String> Ok, or an error message if the line is
+     *         malformed in strict mode or the process stage has stopped.
+     */
+    fn line(&mut self, line: &str, line_number: usize, borrowed: bool) -> Result<(), String> {
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        if !self.interrupted && shutdown::requested() {
+            log_info!(
+                "ndjson:stream_file",
+                "Shutdown requested, copying the remaining lines of {} unprocessed.",
+                self.location
+            );
+            self.interrupted = true;
+        }
+        let record = if self.interrupted {
+            Record::Copied(line.to_string())
+        } else {
+            match self.parse(line, borrowed) {
+                Ok(record) => {
+                    self.metrics.add_items_loaded(1);
+                    record
+                }
+                Err(e) if self.strictness == Strictness::Strict => {
+                    return Err(format!(
+                        "Malformed line {} in data file '{}' (input_strictness is strict): {}",
+                        line_number, self.location, e
+                    ));
+                }
+                Err(e) => {
+                    if self.strictness == Strictness::Warn {
+                        log_warning!(
+                            "ndjson:stream_file",
+                            "Copying malformed line {} of {} unchanged: {}",
+                            line_number,
+                            self.location,
+                            e
+                        );
+                    }
+                    self.malformed_lines += 1;
+                    Record::Copied(line.to_string())
+                }
+            }
+        };
+        if self.queue.send(record) {
+            Ok(())
+        } else {
+            Err(String::from("the process stage stopped"))
+        }
+    }
+
+    /**
+     * @brief Parse the item of a line.
+     */
+    fn parse(&self, line: &str, borrowed: bool) -> Result<Record, serde_json::Error> {
+        if !borrowed {
+            return serde_json::from_str::<Item>(line).map(Record::Item);
+        }
+        let item = serde_json::from_str::<ItemRef>(line)?;
+        if self.incremental && item.status == ItemStatus::Processed {
+            serde_json::to_string(&item).map(Record::Unchanged)
+        } else {
+            Ok(Record::Item(item.to_owned_item()))
+        }
+    }
+}
+
+/**
+ * @struct ProcessStage
+ * @brief Runs the items of a streamed file through the processor.
+ */
+struct ProcessStage<'a> {
+    item_processor: &'a Arc<ItemProcessor>,
+    item_filter: Option<&'a FilterExpr>,
+    /// Pass items that are already processed through unchanged.
+    incremental: bool,
+    metrics: &'a Metrics,
+    /// Items passed through because they were already processed.
+    already_processed: usize,
+}
+
+impl ProcessStage<'_> {
+    /**
+     * @brief Process the queued items and hand them on, until the load stage is done.
+     *
+     * @param records The queue from the load stage.
+     * @param queue The queue to the save stage.
+     * @return Result<usize, String> The number of items passed through
+     *         because they were already processed, or an error message if the
+     *         save stage has stopped.
+     */
+    fn run(&mut self, records: StageReceiver, queue: StageSender) -> Result<usize, String> {
+        for record in records {
+            let record = match record {
+                Record::Item(mut item) => {
+                    if self.incremental && item.is_processed() {
+                        self.already_processed += 1;
+                    } else {
+                        pipeline::process_streamed_item(&mut item, self.item_processor, self.item_filter, self.metrics);
+                    }
+                    Record::Item(item)
+                }
+                Record::Unchanged(line) => {
+                    self.already_processed += 1;
+                    Record::Unchanged(line)
+                }
+                copied => copied,
+            };
+            if !queue.send(record) {
+                return Err(String::from("the save stage stopped"));
+            }
+        }
+        Ok(self.already_processed)
+    }
+}
+
+/**
+ * @brief Write the queued records to the temporary file, until the process stage is done.
+ *
+ * @param records The queue from the process stage.
+ * @param output The temporary file.
+ * @param tmp_path Path of the temporary file, used in messages.
+ * @return Result<usize, String> The number of items written (copied lines
+ *         not included), or an error message.
+ */
+fn save_records(records: StageReceiver, output: File, tmp_path: &Path) -> Result<usize, String> {
+    let write_error = |e: std::io::Error| format!("Failed to write data file '{}': {}", tmp_path.display(), e);
+    let mut writer = BufWriter::new(output);
+    let mut items = 0;
+    for record in records {
+        match record {
+            Record::Item(item) => {
+                serde_json::to_writer(&mut writer, &item)
+                    .map_err(|e| format!("Failed to serialize item {}: {}", item.item_id, e))?;
+                writeln!(writer).map_err(write_error)?;
+                items += 1;
+            }
+            Record::Unchanged(line) => {
+                writeln!(writer, "{}", line).map_err(write_error)?;
+                items += 1;
+            }
+            Record::Copied(line) => writeln!(writer, "{}", line).map_err(write_error)?,
+        }
+    }
+    writer.flush().map_err(write_error)?;
+    Ok(items)
+}
+
+/**
+ * @brief Feed the lines of a streamed file to the load stage in arena batches.
+ */
+#[cfg(feature = "arena")]
+fn load_in_arena(reader: &mut BufReader<File>, arena_batch: usize, stage: &mut LoadStage) -> Result<(), String> {
+    let mut arena = LineArena::new(arena_batch);
+    let location = stage.location;
+    arena.for_each_line(reader, location, |line, line_number| stage.line(line, line_number, true))?;
+    log_debug!(
+        "ndjson:stream_file",
+        "Streamed {} in arena batches of {} lines (at most {} bytes per batch).",
+        location,
+        arena_batch,
+        arena.peak_bytes()
+    );
+    Ok(())
+}
+
+/**
+ * @brief Feed the lines of a streamed file to the load stage one at a time.
+ */
+fn load_lines(reader: &mut BufReader<File>, stage: &mut LoadStage) -> Result<(), String> {
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read data file '{}': {}", stage.location, e))?;
+        stage.line(&line, index + 1, false)?;
+    }
+    Ok(())
+}
+
+// End of rust_sample_project/src/ndjson.rs
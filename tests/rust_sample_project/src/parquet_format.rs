@@ -0,0 +1,305 @@
+// rust_sample_project/src/parquet_format.rs
+
+// Items data files in Apache Parquet format (the `parquet` feature), so
+// pipeline outputs can be read directly by DataFusion, pandas and other
+// analytical tools. Every item is one row:
+//
+//     item_id       int64 (integer ids) or string (UUID and string ids)
+//     name          string
+//     value         decimal128(38, <decimals>)
+//     status        string, e.g. "processed"
+//     tags          list<string>
+//     created_at    timestamp (UTC, microseconds)
+//     processed_at  timestamp (UTC, microseconds), null unless processed
+//     source, category, supplier, tenant    string, null if unset
+//
+// The schema version of the items data file is kept in the file's key-value
+// metadata. Files written by other tools need only item_id, name and value
+// (which may also be a float, integer or string column); rows that do not
+// make a valid item are skipped with a warning, as in JSON files.
+
+use std::sync::Arc;
+
+use arrow_array::builder::{Decimal128Builder, Int64Builder, ListBuilder, StringBuilder, TimestampMicrosecondBuilder};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Decimal128Type, Float32Type, Float64Type, Int32Type, Int64Type, TimestampMicrosecondType,
+    TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType,
+};
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression as ParquetCompression;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+
+use crate::data_handler::CURRENT_SCHEMA_VERSION;
+use crate::invalid_records::InvalidRecords;
+use crate::item::{Item, ItemStatus};
+use crate::item_id::{self, ItemId};
+use crate::money::{self, Money};
+
+/// Key-value metadata entry holding the items data file schema version.
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/**
+ * @brief Serialize items as a Parquet file.
+ *
+ * @param items The items to write.
+ * @return Result<Vec<u8>, String> The file contents, or an error message.
+ */
+pub fn encode_items(items: &[Item]) -> Result<Vec<u8>, String> {
+    let batch = to_record_batch(items).map_err(|e| format!("Failed to build Parquet columns: {}", e))?;
+    let properties = WriterProperties::builder()
+        .set_compression(ParquetCompression::SNAPPY)
+        .set_key_value_metadata(Some(vec![KeyValue::new(
+            SCHEMA_VERSION_KEY.to_string(),
+            CURRENT_SCHEMA_VERSION.to_string(),
+        )]))
+        .build();
+
+    let mut contents = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut contents, batch.schema(), Some(properties))
+        .map_err(|e| format!("Failed to write Parquet data: {}", e))?;
+    writer
+        .write(&batch)
+        .and_then(|()| writer.close().map(drop))
+        .map_err(|e| format!("Failed to write Parquet data: {}", e))?;
+    Ok(contents)
+}
+
+/**
+ * @brief Parse the items of a Parquet file.
+ *
+ * @param contents The file contents.
+ * @param path The file path, used in messages.
+ * @param invalid Where invalid records are reported.
+ * @return Result<Vec<Item>, String> The valid items, or an error message if
+ *         the file is not a readable Parquet file or lacks a required column.
+ */
+pub fn parse_items(contents: &[u8], path: &str, invalid: &mut InvalidRecords) -> Result<Vec<Item>, String> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::copy_from_slice(contents))
+        .map_err(|e| format!("Failed to read Parquet data file '{}': {}", path, e))?;
+    let schema_version = builder
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .and_then(|entries| entries.iter().find(|entry| entry.key == SCHEMA_VERSION_KEY))
+        .and_then(|entry| entry.value.as_deref())
+        .and_then(|version| version.parse::<u64>().ok());
+    if let Some(version) = schema_version.filter(|version| *version > CURRENT_SCHEMA_VERSION) {
+        return Err(format!(
+            "Data file '{}' has schema version {}, but this build supports at most version {}. Please upgrade the application.",
+            path, version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+    let reader = builder
+        .build()
+        .map_err(|e| format!("Failed to read Parquet data file '{}': {}", path, e))?;
+
+    let mut items = Vec::new();
+    let mut record = 0;
+    for batch in reader {
+        let batch = batch.map_err(|e| format!("Failed to read Parquet data file '{}': {}", path, e))?;
+        for required in ["item_id", "name", "value"] {
+            if batch.column_by_name(required).is_none() {
+                return Err(format!("Parquet data file '{}' has no '{}' column", path, required));
+            }
+        }
+        for row in 0..batch.num_rows() {
+            record += 1;
+            match item_from_row(&batch, row) {
+                Ok(item) => items.push(item),
+                // A binary format has no lines or source text to quote
+                Err(e) => invalid.skip(format_args!("record #{}", record), None, "", e),
+            }
+        }
+    }
+    Ok(items)
+}
+
+/**
+ * @brief Build the columns of the items as one record batch.
+ */
+fn to_record_batch(items: &[Item]) -> Result<RecordBatch, arrow_schema::ArrowError> {
+    // Integer ids stay numbers unless the items mix id kinds
+    let item_ids: ArrayRef = if items.iter().all(|item| matches!(item.item_id, ItemId::Int(_))) {
+        let mut builder = Int64Builder::with_capacity(items.len());
+        for item in items {
+            if let ItemId::Int(id) = item.item_id {
+                builder.append_value(id);
+            }
+        }
+        Arc::new(builder.finish())
+    } else {
+        let mut builder = StringBuilder::new();
+        for item in items {
+            builder.append_value(item.item_id.to_string());
+        }
+        Arc::new(builder.finish())
+    };
+
+    // One scale for the whole column, fine enough for every item's value
+    let scale = items.iter().map(|item| item.value.scale()).fold(0, u32::max);
+    let mut values = Decimal128Builder::with_capacity(items.len()).with_precision_and_scale(38, scale as i8)?;
+    let mut names = StringBuilder::new();
+    let mut statuses = StringBuilder::new();
+    let mut tags = ListBuilder::new(StringBuilder::new());
+    let mut created_at = TimestampMicrosecondBuilder::with_capacity(items.len()).with_timezone("UTC");
+    let mut processed_at = TimestampMicrosecondBuilder::with_capacity(items.len()).with_timezone("UTC");
+    let mut sources = StringBuilder::new();
+    let mut categories = StringBuilder::new();
+    let mut suppliers = StringBuilder::new();
+    let mut tenants = StringBuilder::new();
+    for item in items {
+        names.append_value(&item.name);
+        values.append_value(i128::from(item.value.minor_units()) * 10i128.pow(scale - item.value.scale()));
+        statuses.append_value(item.status.name());
+        for tag in &item.tags {
+            tags.values().append_value(tag);
+        }
+        tags.append(true);
+        created_at.append_value(item.created_at.timestamp_micros());
+        processed_at.append_option(item.processed_at.map(|at| at.timestamp_micros()));
+        sources.append_option(item.source.as_deref());
+        categories.append_option(item.category.as_deref());
+        suppliers.append_option(item.supplier.as_deref());
+        tenants.append_option(item.tenant.as_deref());
+    }
+
+    let columns: Vec<(&str, ArrayRef, bool)> = vec![
+        ("item_id", item_ids, false),
+        ("name", Arc::new(names.finish()), false),
+        ("value", Arc::new(values.finish()), false),
+        ("status", Arc::new(statuses.finish()), false),
+        ("tags", Arc::new(tags.finish()), false),
+        ("created_at", Arc::new(created_at.finish()), false),
+        ("processed_at", Arc::new(processed_at.finish()), true),
+        ("source", Arc::new(sources.finish()), true),
+        ("category", Arc::new(categories.finish()), true),
+        ("supplier", Arc::new(suppliers.finish()), true),
+        ("tenant", Arc::new(tenants.finish()), true),
+    ];
+    let schema = Schema::new(
+        columns
+            .iter()
+            .map(|(name, column, nullable)| Field::new(*name, column.data_type().clone(), *nullable))
+            .collect::<Vec<Field>>(),
+    );
+    RecordBatch::try_new(Arc::new(schema), columns.into_iter().map(|(_, column, _)| column).collect())
+}
+
+/**
+ * @brief Turn one row of a record batch into an item.
+ */
+fn item_from_row(batch: &RecordBatch, row: usize) -> Result<Item, String> {
+    let column = |name: &str| batch.column_by_name(name).filter(|column| column.is_valid(row));
+    let kind = item_id::expected_kind();
+    let item_id = match column("item_id") {
+        Some(id) => match integer_at(id, row) {
+            Some(number) => ItemId::parse(&number.to_string(), kind)?,
+            None => ItemId::parse(&text_at(id, row, "item_id")?, kind)?,
+        },
+        None => return Err(String::from("item_id is missing")),
+    };
+    let name = match column("name") {
+        Some(name) => text_at(name, row, "name")?,
+        None => return Err(String::from("name is missing")),
+    };
+    let value = match column("value") {
+        Some(value) => money_at(value, row)?,
+        None => return Err(String::from("value is missing")),
+    };
+
+    let mut item = Item::new(item_id, name, value);
+    if let Some(status) = column("status") {
+        item.status = ItemStatus::parse(&text_at(status, row, "status")?)?;
+    }
+    if let Some(tags) = column("tags") {
+        let list = tags.as_list_opt::<i32>().ok_or("tags must be a list of strings")?.value(row);
+        for index in (0..list.len()).filter(|index| list.is_valid(*index)) {
+            item.add_tag(&text_at(&list, index, "tags")?);
+        }
+    }
+    if let Some(created_at) = column("created_at") {
+        item.created_at = timestamp_at(created_at, row, "created_at")?;
+    }
+    if let Some(processed_at) = column("processed_at") {
+        item.processed_at = Some(timestamp_at(processed_at, row, "processed_at")?);
+    }
+    item.source = column("source").map(|source| text_at(source, row, "source")).transpose()?;
+    item.category = column("category").map(|category| text_at(category, row, "category")).transpose()?;
+    item.supplier = column("supplier").map(|supplier| text_at(supplier, row, "supplier")).transpose()?;
+    item.tenant = column("tenant").map(|tenant| text_at(tenant, row, "tenant")).transpose()?;
+    item.invariants()?;
+    Ok(item)
+}
+
+/**
+ * @brief Read an integer from a 32- or 64-bit integer column.
+ */
+fn integer_at(column: &ArrayRef, row: usize) -> Option<i64> {
+    match column.data_type() {
+        DataType::Int64 => column.as_primitive_opt::<Int64Type>().map(|array| array.value(row)),
+        DataType::Int32 => column.as_primitive_opt::<Int32Type>().map(|array| i64::from(array.value(row))),
+        _ => None,
+    }
+}
+
+/**
+ * @brief Read a string from a string or large string column.
+ */
+fn text_at(column: &dyn Array, row: usize, field: &str) -> Result<String, String> {
+    match column.data_type() {
+        DataType::Utf8 => Ok(column.as_string::<i32>().value(row).to_string()),
+        DataType::LargeUtf8 => Ok(column.as_string::<i64>().value(row).to_string()),
+        other => Err(format!("{} must be a string column, not {}", field, other)),
+    }
+}
+
+/**
+ * @brief Read an amount from a decimal, float, integer or string column.
+ */
+fn money_at(column: &ArrayRef, row: usize) -> Result<Money, String> {
+    match column.data_type() {
+        DataType::Decimal128(_, scale) if (0..=money::MAX_DECIMALS as i8).contains(scale) => {
+            let minor = column.as_primitive::<Decimal128Type>().value(row);
+            let minor = i64::try_from(minor).map_err(|_| format!("value {} is out of range", minor))?;
+            Ok(Money::from_minor(minor, *scale as u32))
+        }
+        DataType::Float64 => Money::from_f64(column.as_primitive::<Float64Type>().value(row)),
+        DataType::Float32 => Money::from_f64(f64::from(column.as_primitive::<Float32Type>().value(row))),
+        DataType::Utf8 | DataType::LargeUtf8 => Money::parse(&text_at(column, row, "value")?),
+        _ => match integer_at(column, row) {
+            Some(units) => Ok(Money::from_int(units)),
+            None => Err(format!("value cannot be read from a {} column", column.data_type())),
+        },
+    }
+}
+
+/**
+ * @brief Read a point in time from a timestamp column of any unit.
+ */
+fn timestamp_at(column: &ArrayRef, row: usize, field: &str) -> Result<DateTime<Utc>, String> {
+    let timestamp = match column.data_type() {
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            DateTime::from_timestamp(column.as_primitive::<TimestampSecondType>().value(row), 0)
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            DateTime::from_timestamp_millis(column.as_primitive::<TimestampMillisecondType>().value(row))
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            DateTime::from_timestamp_micros(column.as_primitive::<TimestampMicrosecondType>().value(row))
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            Some(DateTime::from_timestamp_nanos(column.as_primitive::<TimestampNanosecondType>().value(row)))
+        }
+        other => return Err(format!("{} must be a timestamp column, not {}", field, other)),
+    };
+    timestamp.ok_or_else(|| format!("{} is out of range", field))
+}
+
+// End of rust_sample_project/src/parquet_format.rs
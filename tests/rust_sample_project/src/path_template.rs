@@ -0,0 +1,114 @@
+// rust_sample_project/src/path_template.rs
+
+// Output paths with placeholders, resolved when a run saves (see the
+// output_path config key), so repeated runs write new files instead of
+// overwriting each other's:
+//
+//     out/items-{date}-{run_id}.json  ->  out/items-2026-10-15-20261015T140309123456Z.json
+//
+// Placeholders are {date} (YYYY-MM-DD), {time} (HHMMSS), {run_id} (the run's
+// id, see PipelineReport::run_id), {profile} (the config profile, or
+// "default") and {tenant} (the tenant, or "default"); dates and times are UTC,
+// taken when the run started. Output routes, the review sink and the summary
+// file take the same placeholders.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+/// The placeholders a template may use.
+const PLACEHOLDERS: &[&str] = &["date", "time", "run_id", "profile", "tenant"];
+
+/**
+ * @struct RunValues
+ * @brief What the placeholders of a run resolve to.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunValues {
+    /// When the run started.
+    pub started_at: DateTime<Utc>,
+    /// The run's id.
+    pub run_id: String,
+    /// The config profile, if one is active.
+    pub profile: Option<String>,
+    /// The tenant the run is for, if any.
+    pub tenant: Option<String>,
+}
+
+impl RunValues {
+    /**
+     * @brief Return the value of a placeholder.
+     */
+    fn value(&self, placeholder: &str) -> Option<String> {
+        match placeholder {
+            "date" => Some(self.started_at.format("%Y-%m-%d").to_string()),
+            "time" => Some(self.started_at.format("%H%M%S").to_string()),
+            "run_id" => Some(self.run_id.clone()),
+            "profile" => Some(self.profile.clone().unwrap_or_else(|| String::from("default"))),
+            "tenant" => Some(self.tenant.clone().unwrap_or_else(|| String::from("default"))),
+            _ => None,
+        }
+    }
+
+    /**
+     * @brief Resolve the placeholders of a path template.
+     *
+     * @param template The path, possibly with placeholders.
+     * @return PathBuf The path with every placeholder replaced; paths that
+     *         are not valid UTF-8 cannot hold placeholders and are kept as is.
+     */
+    pub fn resolve(&self, template: &Path) -> PathBuf {
+        let Some(text) = template.to_str() else {
+            return template.to_path_buf();
+        };
+        let mut resolved = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(open) = rest.find('{') {
+            resolved.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            match after.find('}').and_then(|close| Some((close, self.value(&after[..close])?))) {
+                Some((close, value)) => {
+                    resolved.push_str(&value);
+                    rest = &after[close + 1..];
+                }
+                None => {
+                    resolved.push('{');
+                    rest = after;
+                }
+            }
+        }
+        resolved.push_str(rest);
+        PathBuf::from(resolved)
+    }
+}
+
+/**
+ * @brief Check that a path template uses only known placeholders.
+ *
+ * @param template The path template.
+ * @return Result<(), String> Ok, or an error message naming the first
+ *         unknown or unclosed placeholder.
+ */
+pub fn check(template: &Path) -> Result<(), String> {
+    let Some(mut rest) = template.to_str() else {
+        return Ok(());
+    };
+    while let Some(open) = rest.find('{') {
+        let after = &rest[open + 1..];
+        let Some(close) = after.find('}') else {
+            return Err(format!("unclosed placeholder in '{}'", template.display()));
+        };
+        if !PLACEHOLDERS.contains(&&after[..close]) {
+            return Err(format!(
+                "unknown placeholder '{{{}}}' in '{}' (expected {{date}}, {{time}}, {{run_id}}, {{profile}} or \
+                 {{tenant}})",
+                &after[..close],
+                template.display()
+            ));
+        }
+        rest = &after[close + 1..];
+    }
+    Ok(())
+}
+
+// End of rust_sample_project/src/path_template.rs
@@ -0,0 +1,1368 @@
+// rust_sample_project/src/pipeline.rs
+
+// The load -> deduplicate -> process -> save run, shared by the binary's
+// modes and embedding applications. Embedding applications observe or veto
+// its steps through hooks::PipelineHooks, or assemble flows of their own
+// with composition::Pipeline.
+//
+// Each run is traced with `tracing` spans: "pipeline.run" around the whole
+// run, with "pipeline.load", one "pipeline.item" per processed item (on the
+// worker threads) and "pipeline.save" inside it. An embedding service's
+// subscriber sees them as part of its own traces; the binary exports them
+// with the `otel` feature (see telemetry.rs). Log records name the stage
+// ("load", "process", "save") and, on the worker threads, the item and the
+// step it is in (see logging::LogContext).
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use tracing::field::Empty;
+use tracing::{info_span, Span};
+
+use crate::aggregate::{self, Grouping, SummaryFormat};
+use crate::anomaly::{self, AnomalyMethod, ANOMALY_TAG};
+use crate::audit::AuditLog;
+use crate::batch::BatchPolicy;
+use crate::checkpoint::Checkpoint;
+use crate::composition::{Stage, StageResult};
+use crate::compression::Compression;
+use crate::config::Config;
+use crate::control;
+use crate::data_handler::{DataFormat, DataHandler};
+use crate::dedup::{self, deduplicate, DedupStrategy, DuplicatePolicy};
+use crate::delivery::Delivery;
+use crate::encryption;
+use crate::enrichment::{Enricher, Enrichment, MissingLookupPolicy};
+use crate::error::PipelineError;
+use crate::filter::FilterExpr;
+use crate::history::{self, RunOutcome, RunRecord};
+use crate::hooks::{HookDecision, ItemOutcome, PipelineHooks};
+use crate::invalid_records::Strictness;
+use crate::item::{Item, ItemStatus};
+use crate::item_batch::ItemBatch;
+use crate::item_processor::{ItemProcessor, ProcessError};
+use crate::join::{self, JoinField, JoinType};
+use crate::lock::{DataLock, LockPolicy};
+use crate::logging;
+use crate::metrics::Metrics;
+use crate::notify::{EmailSettings, Notifier, NotifyOn};
+use crate::path_template::RunValues;
+use crate::registry::{self, StageParams};
+use crate::ndjson::{self, StreamOptions, StreamSummary};
+use crate::priority::{self, PriorityPolicy};
+use crate::rate_limit::RateLimiter;
+use crate::report::{PipelineReport, ValueStats};
+use crate::router::OutputRouter;
+use crate::sampling;
+use crate::shard::ShardPolicy;
+use crate::shutdown;
+use crate::staging;
+use crate::storage;
+use crate::transform::Transformation;
+use crate::worker_pool::WorkerPool;
+use crate::xlsx_format::XlsxMapping;
+use crate::xml_format::XmlMapping;
+
+/**
+ * @brief Build a DataHandler for a data file with the configured storage settings.
+ *
+ * Applies the output compression, data format, XML and Excel mappings,
+ * encryption key, checksums, batching and staging directory of the config;
+ * input sources are left to the caller.
+ *
+ * @param config The effective configuration.
+ * @param data_path Path (or URL) of the data file.
+ * @return Result<DataHandler, String> The handler, or an error message for
+ *         invalid settings or an unreadable encryption key.
+ */
+pub fn build_data_handler(config: &Config, data_path: &Path) -> Result<DataHandler, String> {
+    let output_compression = Compression::parse(&config.output_compression)?;
+    let data_format = DataFormat::parse(&config.data_format)?;
+    let xml_mapping = XmlMapping::new(
+        &config.xml_item_element,
+        &config.xml_id_field,
+        &config.xml_name_field,
+        &config.xml_value_field,
+        &config.xml_mode,
+    )?;
+    let xlsx_mapping = XlsxMapping::new(
+        &config.xlsx_sheet,
+        &config.xlsx_id_column,
+        &config.xlsx_name_column,
+        &config.xlsx_value_column,
+        &config.xlsx_results_sheet,
+    )?;
+    let encryption_key = encryption::load_key(
+        &config.encryption_key_env,
+        &config.encryption_key_file,
+        config.encryption_allow_plaintext,
+    )?;
+    Ok(DataHandler::new(data_path)
+        .with_output_compression(output_compression)
+        .with_data_format(data_format)
+        .with_xml_mapping(xml_mapping)
+        .with_xlsx_mapping(xlsx_mapping)
+        .with_strictness(Strictness::parse(&config.input_strictness)?)
+        .with_encryption(encryption_key)
+        .with_checksums(config.data_checksums)
+        .with_name_interning(config.intern_names)
+        .with_batch_policy(BatchPolicy::new(config.save_batch_size, config.save_flush_interval_ms))
+        .with_staging_dir(config.staging_dir.clone())
+        .with_snapshots(config.snapshot_dir.clone(), config.snapshot_retention)
+        .with_sharding(ShardPolicy::new(config.shard_max_items, config.shard_max_bytes)))
+}
+
+/**
+ * @brief Build the ItemProcessor from the configured threshold, tag rules and transformations.
+ *
+ * @param config The effective configuration.
+ * @return Result<ItemProcessor, String> The processor, or an error message for
+ *         an invalid transformation.
+ */
+pub fn build_item_processor(config: &Config) -> Result<ItemProcessor, String> {
+    let transformations = config
+        .transformations
+        .iter()
+        .map(|rule| Transformation::parse(rule))
+        .collect::<Result<Vec<Transformation>, String>>()?;
+    let timeout = (config.item_timeout_ms > 0).then(|| Duration::from_millis(config.item_timeout_ms));
+    Ok(ItemProcessor::new(config.threshold)
+        .with_thresholds(config.category_thresholds.clone(), config.tag_thresholds.clone())
+        .with_tag_rules(config.required_tags.clone(), config.excluded_tags.clone())
+        .with_transformations(transformations)
+        .with_value_decimals(config.value_decimals)
+        .with_timeout(timeout))
+}
+
+/**
+ * @brief Build the item filter from the effective config (including --filter).
+ *
+ * @param config The effective configuration.
+ * @return Result<Option<FilterExpr>, String> The filter (None when no
+ *         expression is set), or an error message for an invalid expression.
+ */
+pub fn build_item_filter(config: &Config) -> Result<Option<FilterExpr>, String> {
+    let filter_source = config.filter_expression.as_str();
+    if filter_source.trim().is_empty() {
+        return Ok(None);
+    }
+    let parsed = FilterExpr::parse(filter_source)?;
+    log_info!("pipeline:build_item_filter", "Filter expression: {}", parsed);
+    Ok(Some(parsed))
+}
+
+/**
+ * @brief Open the audit log configured in audit_log_path.
+ *
+ * @param config The effective configuration.
+ * @return Result<Option<Arc<AuditLog>>, String> The log (None when no path is
+ *         set), or an error message if it cannot be opened.
+ */
+pub fn open_audit_log(config: &Config) -> Result<Option<Arc<AuditLog>>, String> {
+    if config.audit_log_path.as_os_str().is_empty() {
+        return Ok(None);
+    }
+    AuditLog::open(&config.audit_log_path).map(|audit_log| Some(Arc::new(audit_log)))
+}
+
+/**
+ * @brief Load the enrichment lookup table configured in enrichment_lookup_path.
+ *
+ * @param config The effective configuration.
+ * @return Result<Option<Enricher>, PipelineError> The enricher (None when no
+ *         lookup file is set), or the classified failure.
+ */
+pub fn build_enricher(config: &Config) -> Result<Option<Enricher>, PipelineError> {
+    if config.enrichment_lookup_path.as_os_str().is_empty() {
+        return Ok(None);
+    }
+    let policy =
+        MissingLookupPolicy::parse(&config.enrichment_missing_policy).map_err(PipelineError::Config)?;
+    Enricher::load(&config.enrichment_lookup_path, policy).map(Some).map_err(PipelineError::Load)
+}
+
+/**
+ * @brief Build the registered stages named in the config (including --stage).
+ *
+ * @param config The effective configuration.
+ * @return Result<Vec<Box<dyn Stage>>, String> The stages in order (empty when
+ *         none are configured), or an error message naming the failing stage.
+ */
+pub fn build_custom_stages(config: &Config) -> Result<Vec<Box<dyn Stage>>, String> {
+    config
+        .custom_stages
+        .iter()
+        .map(|name| {
+            log_info!("pipeline:build_custom_stages", "Custom stage: {}", name);
+            registry::build_stage(name, &StageParams::new()).map_err(|e| format!("Stage '{}': {}", name, e))
+        })
+        .collect()
+}
+
+/**
+ * @struct ItemSteps
+ * @brief What a run does to each item, built once from the config.
+ */
+struct ItemSteps {
+    /// Processes the selected items.
+    item_processor: Arc<ItemProcessor>,
+    /// Optional filter selecting the items to process.
+    item_filter: Option<FilterExpr>,
+    /// Optional lookup table joined against the item first.
+    enricher: Option<Enricher>,
+    /// Stages applied after the filter: those of custom_stages, then the caller's.
+    custom_stages: Vec<Box<dyn Stage>>,
+    /// Names of the custom stages, as recorded in item lineage.
+    custom_stage_names: Vec<String>,
+    /// Record the lineage of each item handled (see Item::start_lineage).
+    lineage: bool,
+}
+
+/**
+ * @brief Enrich, filter, select and process a single item.
+ *
+ * Safe to call from several worker threads at once.
+ *
+ * @param item The item, modified in place when enriched or processed.
+ * @param incremental Leave items already marked processed as they are.
+ * @param steps The enricher, filter, custom stages and processor of the run.
+ * @param metrics Collector for the run's counters and timings.
+ * @param hooks Observers that may veto processing the item.
+ * @return ItemOutcome What happened to the item.
+ */
+fn handle_item(
+    item: &mut Item,
+    incremental: bool,
+    steps: &ItemSteps,
+    metrics: &Metrics,
+    hooks: &PipelineHooks,
+) -> ItemOutcome {
+    if incremental && item.is_processed() {
+        log_debug!("pipeline:handle_item", "Skipping item processed by an earlier run: {}", item);
+        return ItemOutcome::AlreadyProcessed;
+    }
+    if steps.lineage {
+        item.start_lineage();
+    }
+    if steps.enricher.is_some() {
+        item.record_stage("enrichment");
+        logging::set_stage("enrichment");
+    }
+    match steps.enricher.as_ref().map(|enricher| enricher.enrich(item)) {
+        Some(Enrichment::Skip) => {
+            log_debug!("pipeline:handle_item", "Skipping item without a lookup entry: {}", item);
+            return ItemOutcome::SkippedByLookup;
+        }
+        Some(Enrichment::Fail) => {
+            log_error!("pipeline:handle_item", "No lookup entry for item: {}", item);
+            metrics.inc_items_failed();
+            return ItemOutcome::Failed;
+        }
+        _ => {}
+    }
+    if steps.item_filter.is_some() {
+        item.record_stage("filter");
+        logging::set_stage("filter");
+    }
+    if steps.item_filter.as_ref().is_some_and(|f| !f.matches(item)) {
+        log_debug!("pipeline:handle_item", "Skipping item not matching the filter: {}", item);
+        return ItemOutcome::FilteredOut;
+    }
+    for (stage, name) in steps.custom_stages.iter().zip(&steps.custom_stage_names) {
+        item.record_stage(name);
+        logging::set_stage(name);
+        if let StageResult::Finish(outcome) = stage.apply(item) {
+            log_debug!("pipeline:handle_item", "Item finished by a custom stage ({:?}): {}", outcome, item);
+            match outcome {
+                ItemOutcome::Processed => metrics.inc_items_processed(),
+                ItemOutcome::Failed | ItemOutcome::TimedOut => metrics.inc_items_failed(),
+                _ => {}
+            }
+            return outcome;
+        }
+    }
+    if !steps.item_processor.is_selected(item) {
+        log_debug!("pipeline:handle_item", "Skipping item not selected by tag rules: {}", item);
+        return ItemOutcome::SkippedByTag;
+    }
+    if let HookDecision::Veto(reason) = hooks.notify_before_item(item) {
+        log_debug!("pipeline:handle_item", "Skipping item vetoed by a hook ({}): {}", reason, item);
+        return ItemOutcome::Vetoed;
+    }
+
+    item.record_stage("processor");
+    logging::set_stage("processor");
+    let started = Instant::now();
+    let result = steps.item_processor.process_item_with_timeout(item);
+    metrics.observe_processing_duration(started.elapsed());
+    match result {
+        Ok(()) => {
+            metrics.inc_items_processed();
+            ItemOutcome::Processed
+        }
+        Err(ProcessError::Failed) => {
+            // Taken when one of the configured transformations fails for this item.
+            log_error!("pipeline:handle_item", "Failed to process item: {}", item);
+            metrics.inc_items_failed();
+            ItemOutcome::Failed
+        }
+        Err(e @ ProcessError::Timeout(_)) => {
+            log_error!("pipeline:handle_item", "Failed to process item {}: {}", item, e);
+            metrics.inc_items_failed();
+            ItemOutcome::TimedOut
+        }
+    }
+}
+
+/**
+ * @brief Set an item's status from what happened to it in this run.
+ *
+ * Failed items become failed. Skipped items become skipped, unless an
+ * earlier run settled them (processed, failed or skipped): those keep their
+ * status, so e.g. a filter excluding processed items does not undo their
+ * processing. Processed items were already marked by the processor.
+ *
+ * @param item The item.
+ * @param outcome What happened to it.
+ */
+pub fn record_status(item: &mut Item, outcome: ItemOutcome) {
+    let result = match outcome {
+        ItemOutcome::FilteredOut | ItemOutcome::SkippedByTag | ItemOutcome::SkippedByLookup | ItemOutcome::Vetoed => {
+            if item.status.can_become(ItemStatus::Skipped) {
+                item.mark_skipped()
+            } else {
+                Ok(())
+            }
+        }
+        ItemOutcome::Failed | ItemOutcome::TimedOut => {
+            item.validate();
+            item.mark_failed()
+        }
+        ItemOutcome::Processed | ItemOutcome::AlreadyProcessed => Ok(()),
+    };
+    if let Err(e) = result {
+        log_error!("pipeline:record_status", "{}", e);
+    }
+}
+
+/**
+ * @brief Apply the filter, tag rules and processor to one streamed item.
+ *
+ * Used by the streaming modes (Kafka and NDJSON files), which handle items
+ * one at a time rather than as a loaded list.
+ *
+ * @param item The item, modified in place when processed.
+ * @param item_processor Processes the selected items.
+ * @param item_filter Optional filter selecting the items to process.
+ * @param metrics Collector for the stream's counters and timings.
+ * @return ItemOutcome What happened to the item.
+ */
+pub fn process_streamed_item(
+    item: &mut Item,
+    item_processor: &Arc<ItemProcessor>,
+    item_filter: Option<&FilterExpr>,
+    metrics: &Metrics,
+) -> ItemOutcome {
+    if item_filter.is_some_and(|f| !f.matches(item)) {
+        log_debug!("pipeline:process_streamed_item", "Skipping item not matching the filter: {}", item);
+        mark_streamed_skipped(item);
+        return ItemOutcome::FilteredOut;
+    }
+    if !item_processor.is_selected(item) {
+        log_debug!("pipeline:process_streamed_item", "Skipping item not selected by tag rules: {}", item);
+        mark_streamed_skipped(item);
+        return ItemOutcome::SkippedByTag;
+    }
+
+    let started = Instant::now();
+    let result = item_processor.process_item_with_timeout(item);
+    metrics.observe_processing_duration(started.elapsed());
+    match result {
+        Ok(()) => {
+            metrics.inc_items_processed();
+            ItemOutcome::Processed
+        }
+        Err(e) => {
+            log_error!("pipeline:process_streamed_item", "Failed to process item {}: {}", item, e);
+            metrics.inc_items_failed();
+            item.validate();
+            if let Err(e) = item.mark_failed() {
+                log_error!("pipeline:process_streamed_item", "{}", e);
+            }
+            match e {
+                ProcessError::Failed => ItemOutcome::Failed,
+                ProcessError::Timeout(_) => ItemOutcome::TimedOut,
+            }
+        }
+    }
+}
+
+/**
+ * @brief Mark a streamed item left out of processing as skipped, unless it
+ *        arrived already settled (processed, failed or skipped).
+ */
+fn mark_streamed_skipped(item: &mut Item) {
+    if item.status.can_become(ItemStatus::Skipped) {
+        if let Err(e) = item.mark_skipped() {
+            log_error!("pipeline:mark_streamed_skipped", "{}", e);
+        }
+    }
+}
+
+/**
+ * @brief Assign the loaded items without a tenant to the run's tenant.
+ *
+ * Items that name another tenant keep it, with a warning, as a tenant's data
+ * path should hold only its own items.
+ *
+ * @param items The loaded items.
+ * @param tenant The tenant the run is for.
+ */
+fn assign_tenant(items: &mut [Item], tenant: &str) {
+    let mut foreign = 0;
+    for item in items.iter_mut() {
+        match &item.tenant {
+            None => item.tenant = Some(tenant.to_string()),
+            Some(other) if other != tenant => foreign += 1,
+            Some(_) => {}
+        }
+    }
+    if foreign > 0 {
+        log_warning!(
+            "pipeline:assign_tenant",
+            "{} loaded items belong to another tenant than '{}'; they keep their tenant.",
+            foreign,
+            tenant
+        );
+    }
+}
+
+/**
+ * @brief Write a checkpoint covering the given completed items.
+ *
+ * Failures are only logged; a missing checkpoint just means a resumed run
+ * redoes more work.
+ *
+ * @param checkpoint_path Where the checkpoint is written.
+ * @param data_source_path The data source the items were loaded from.
+ * @param run_id The run the items were handled by.
+ * @param completed The items handled so far.
+ */
+fn save_checkpoint(checkpoint_path: &Path, data_source_path: &Path, run_id: &str, completed: &[Item]) {
+    let checkpoint = Checkpoint::new(
+        data_source_path.to_path_buf(),
+        run_id,
+        completed.iter().map(|item| item.item_id.clone()).collect(),
+    );
+    if let Err(e) = checkpoint.save(checkpoint_path) {
+        log_warning!("pipeline:save_checkpoint", "Failed to write checkpoint: {}", e);
+    }
+}
+
+/**
+ * @brief Set aside the items a resumed run does not process again.
+ *
+ * @param items The items, in processing order.
+ * @param resumed_mask For each item, whether it is done (see
+ *        DataHandler::apply_checkpoint); missing entries count as not done.
+ * @return (Vec<Item>, Vec<Item>) The done items and the items to process,
+ *         each in processing order.
+ */
+fn split_resumed(items: Vec<Item>, resumed_mask: &[bool]) -> (Vec<Item>, Vec<Item>) {
+    let mut resumed = Vec::new();
+    let mut pending = Vec::new();
+    for (index, item) in items.into_iter().enumerate() {
+        if resumed_mask.get(index).copied().unwrap_or(false) {
+            resumed.push(item);
+        } else {
+            pending.push(item);
+        }
+    }
+    (resumed, pending)
+}
+
+/**
+ * @brief Put the items split_resumed set aside back among the others.
+ *
+ * @param resumed The done items, in processing order.
+ * @param handled The other items, in processing order.
+ * @param resumed_mask The mask the items were split by.
+ * @return Vec<Item> All items in processing order.
+ */
+fn merge_resumed(resumed: Vec<Item>, handled: Vec<Item>, resumed_mask: &[bool]) -> Vec<Item> {
+    let total = resumed.len() + handled.len();
+    let mut resumed = resumed.into_iter();
+    let mut handled = handled.into_iter();
+    (0..total)
+        .filter_map(|index| match resumed_mask.get(index) {
+            Some(true) => resumed.next(),
+            _ => handled.next(),
+        })
+        .collect()
+}
+
+/**
+ * @brief Stream the configured NDJSON data file through the processor (--stream).
+ *
+ * Unlike run_processing_pipeline, items are never all in memory: the data
+ * file is read, processed and rewritten line by line, by stages connected
+ * through queues of stream_process_buffer and stream_save_buffer items (see
+ * ndjson::stream_file).
+ * The data file is locked for the duration like for a regular run.
+ *
+ * @param config The effective configuration; data_path must be an NDJSON file.
+ * @param metrics Collector for the stream's counters and timings.
+ * @return Result<StreamSummary, PipelineError> What the pass did, or the classified failure.
+ */
+pub fn run_ndjson_stream(config: &Config, metrics: &Metrics) -> Result<StreamSummary, PipelineError> {
+    let item_processor = build_item_processor(config).map_err(PipelineError::Config)?;
+    let item_processor = Arc::new(item_processor.with_audit_log(open_audit_log(config).map_err(PipelineError::Config)?));
+    let item_filter = build_item_filter(config).map_err(PipelineError::Config)?;
+    if encryption::load_key(
+        &config.encryption_key_env,
+        &config.encryption_key_file,
+        config.encryption_allow_plaintext,
+    )
+    .map_err(PipelineError::Config)?
+    .is_some()
+    {
+        return Err(PipelineError::Config(String::from(
+            "Encrypted data files cannot be streamed; run without --stream or without an encryption key",
+        )));
+    }
+    let lock_policy = LockPolicy::parse(&config.lock_policy).map_err(PipelineError::Config)?;
+    let lock_timeout = (config.lock_timeout_secs > 0).then(|| Duration::from_secs(config.lock_timeout_secs));
+    let _data_lock = DataLock::acquire(&config.data_path, lock_policy, lock_timeout).map_err(PipelineError::Load)?;
+
+    let options = StreamOptions {
+        incremental: config.incremental,
+        checksums: config.data_checksums,
+        strictness: Strictness::parse(&config.input_strictness).map_err(PipelineError::Config)?,
+        arena_batch: config.stream_arena_batch,
+        process_buffer: config.stream_process_buffer,
+        save_buffer: config.stream_save_buffer,
+    };
+    ndjson::stream_file(
+        &config.data_path,
+        &item_processor,
+        item_filter.as_ref(),
+        &options,
+        metrics,
+    )
+    .map_err(PipelineError::Load)
+}
+
+/**
+ * @brief Build the notifier for the notify_* config keys.
+ *
+ * @param config The effective configuration.
+ * @return Result<Notifier, String> The notifier (without targets if none are
+ *         configured), or an error message for an unknown notify_on or an
+ *         SMTP password that cannot be read.
+ */
+pub fn build_notifier(config: &Config) -> Result<Notifier, String> {
+    let email = match config.notify_smtp_server.trim() {
+        "" => None,
+        server => Some(EmailSettings {
+            server: server.to_string(),
+            from: config.notify_email_from.clone(),
+            to: config.notify_email_to.clone(),
+            username: config.notify_smtp_username.clone(),
+            password: config
+                .notify_smtp_password
+                .resolve()
+                .map_err(|e| format!("notify_smtp_password: {}", e))?,
+        }),
+    };
+    Ok(Notifier::new(NotifyOn::parse(&config.notify_on)?)
+        .with_webhooks(config.notify_webhooks.clone())
+        .with_email(email))
+}
+
+/**
+ * @brief Parse the join_fields config key.
+ *
+ * @param config The effective configuration.
+ * @return Result<Vec<JoinField>, String> The fields, or an error message for
+ *         the first unknown one.
+ */
+pub fn parse_join_fields(config: &Config) -> Result<Vec<JoinField>, String> {
+    config.join_fields.iter().map(|field| JoinField::parse(field)).collect()
+}
+
+/**
+ * @brief Execute the main data processing pipeline.
+ *
+ * Orchestrates the loading, processing, and saving of data items using
+ * configuration settings and dedicated handler/processor classes. The run,
+ * also a failed one, is recorded in the run history if history_path is set,
+ * and the configured notification targets are told how it ended.
+ *
+ * @param config The effective configuration.
+ * @param resume Continue from the checkpoint of an interrupted run (--resume).
+ * @param metrics Collector for the run's counters and timings.
+ * @param hooks Observers called at each step (PipelineHooks::new() for none).
+ * @return Result<PipelineReport, PipelineError> The run's report on success
+ *         (also when individual items failed, see PipelineReport::outcome),
+ *         or the classified failure.
+ */
+pub fn run_processing_pipeline(
+    config: &Config,
+    resume: bool,
+    metrics: &Metrics,
+    hooks: &PipelineHooks,
+) -> Result<PipelineReport, PipelineError> {
+    run_processing_pipeline_with_stages(config, resume, metrics, hooks, Vec::new())
+}
+
+/**
+ * @brief Execute the main data processing pipeline with additional stages.
+ *
+ * Like run_processing_pipeline, for embedding applications (e.g. the Python
+ * bindings) that bring stages of their own rather than registered ones.
+ *
+ * @param config The effective configuration.
+ * @param resume Continue from the checkpoint of an interrupted run.
+ * @param metrics Collector for the run's counters and timings.
+ * @param hooks Observers called at each step (PipelineHooks::new() for none).
+ * @param stages Applied to each item after those of custom_stages, before
+ *        the processor.
+ * @return Result<PipelineReport, PipelineError> As for run_processing_pipeline.
+ */
+pub fn run_processing_pipeline_with_stages(
+    config: &Config,
+    resume: bool,
+    metrics: &Metrics,
+    hooks: &PipelineHooks,
+    stages: Vec<Box<dyn Stage>>,
+) -> Result<PipelineReport, PipelineError> {
+    let started_at = Utc::now();
+    let run_span = info_span!(
+        "pipeline.run",
+        run_id = Empty,
+        items_loaded = Empty,
+        items_processed = Empty,
+        items_failed = Empty,
+        outcome = Empty,
+        error = Empty
+    );
+    let result = run_span.in_scope(|| run_stages(config, resume, metrics, hooks, stages));
+    match &result {
+        Ok(report) => {
+            run_span.record("items_loaded", report.items_loaded as u64);
+            run_span.record("items_processed", report.items_processed as u64);
+            run_span.record("items_failed", report.items_failed as u64);
+        }
+        Err(e) => {
+            run_span.record("error", e.to_string());
+            hooks.notify_error(e);
+        }
+    }
+    run_span.record("outcome", RunOutcome::of(&result).name());
+    if !config.history_path.as_os_str().is_empty() {
+        let record = RunRecord::new(started_at, config.fingerprint(), config.profile.clone(), &result);
+        if let Err(e) = history::append(&config.history_path, &record) {
+            log_warning!("pipeline:run_processing_pipeline", "{}", e);
+        }
+    }
+    match build_notifier(config) {
+        Ok(notifier) if notifier.is_enabled() => {
+            notifier.notify(&result);
+        }
+        Ok(_) => {}
+        Err(e) => log_warning!("pipeline:run_processing_pipeline", "{}", e),
+    }
+    result
+}
+
+/**
+ * @brief Run the pipeline's stages, see run_processing_pipeline.
+ */
+fn run_stages(
+    config: &Config,
+    resume: bool,
+    metrics: &Metrics,
+    hooks: &PipelineHooks,
+    extra_stages: Vec<Box<dyn Stage>>,
+) -> Result<PipelineReport, PipelineError> {
+    log_info!("pipeline:run_processing_pipeline", "{}", tr!(PipelineStarting));
+    let mut report = PipelineReport::new();
+    report.tenant = config.tenant.clone();
+
+    // 1. Initialize components using configuration
+    let data_path: &Path = &config.data_path;
+    let threshold: f64 = config.threshold;
+
+    log_info!("pipeline:run_processing_pipeline", "{}", tr!(PipelineConfig, data_path.display(), threshold));
+
+    let checkpoint_path: &Path = &config.checkpoint_path;
+    // A resumed run continues the interrupted one, so redelivered items keep their idempotency keys
+    let checkpoint = if resume { Checkpoint::load(checkpoint_path).map_err(PipelineError::Load)? } else { None };
+    if let Some(checkpoint) = checkpoint.as_ref().filter(|checkpoint| !checkpoint.run_id.is_empty()) {
+        report.run_id = checkpoint.run_id.clone();
+    }
+    Span::current().record("run_id", report.run_id.as_str());
+    let checkpoint_interval: usize = config.checkpoint_interval.max(1);
+
+    let merge_strategy = DedupStrategy::parse(&config.merge_strategy).map_err(PipelineError::Config)?;
+    let dedup_strategy = DedupStrategy::parse(&config.dedup_strategy).map_err(PipelineError::Config)?;
+    let duplicate_policy = DuplicatePolicy::parse(&config.duplicate_id_policy).map_err(PipelineError::Config)?;
+    let lock_policy = LockPolicy::parse(&config.lock_policy).map_err(PipelineError::Config)?;
+    let priority_policy = PriorityPolicy::parse(&config.priority_policy).map_err(PipelineError::Config)?;
+    let anomaly_method = AnomalyMethod::parse(&config.anomaly_detection).map_err(PipelineError::Config)?;
+    let groupings = config
+        .aggregate_by
+        .iter()
+        .map(|grouping| Grouping::parse(grouping))
+        .collect::<Result<Vec<Grouping>, String>>()
+        .map_err(PipelineError::Config)?;
+    let summary_format =
+        SummaryFormat::parse(&config.aggregate_format, &config.aggregate_path).map_err(PipelineError::Config)?;
+    let join_type = JoinType::parse(&config.join_type).map_err(PipelineError::Config)?;
+    let join_fields = parse_join_fields(config).map_err(PipelineError::Config)?;
+    let data_handler = build_data_handler(config, data_path)
+        .map_err(PipelineError::Config)?
+        .with_input_sources(config.input_sources.clone(), merge_strategy)
+        .with_delivery(Delivery::new(&report.run_id, &config.delivery_ledger_path));
+    // Output paths may name the run, see path_template.rs
+    let run_values = RunValues {
+        started_at: report.started_at,
+        run_id: report.run_id.clone(),
+        profile: config.profile.clone(),
+        tenant: config.tenant.clone(),
+    };
+    let output_path = if config.output_path.as_os_str().is_empty() {
+        data_handler.data_source_path().to_path_buf()
+    } else {
+        run_values.resolve(&config.output_path)
+    };
+    let review_path = run_values.resolve(&config.anomaly_review_path);
+    let aggregate_path = run_values.resolve(&config.aggregate_path);
+    let route_sinks: Vec<String> = config
+        .output_routes
+        .iter()
+        .map(|(sink, _)| run_values.resolve(Path::new(sink)).to_string_lossy().into_owned())
+        .collect();
+    let output_routes: Vec<(&str, &str)> = route_sinks
+        .iter()
+        .zip(&config.output_routes)
+        .map(|(sink, (_, condition))| (sink.as_str(), condition.as_str()))
+        .collect();
+    let mut output_router = OutputRouter::new(&output_path)
+        .with_routes(&output_routes)
+        .map_err(PipelineError::Config)?;
+    if anomaly_method != AnomalyMethod::Off {
+        let condition = FilterExpr::parse(&format!("tags contains '{}'", ANOMALY_TAG)).map_err(PipelineError::Config)?;
+        output_router = output_router.with_leading_route(&review_path, condition);
+    }
+    let audit_log = open_audit_log(config).map_err(PipelineError::Config)?;
+    let mut custom_stages = build_custom_stages(config).map_err(PipelineError::Config)?;
+    custom_stages.extend(extra_stages);
+    let mut custom_stage_names = config.custom_stages.clone();
+    custom_stage_names.resize(custom_stages.len(), String::from("custom"));
+    let steps = ItemSteps {
+        item_processor: Arc::new(
+            build_item_processor(config)
+                .map_err(PipelineError::Config)?
+                .with_audit_log(audit_log.clone()),
+        ),
+        item_filter: build_item_filter(config).map_err(PipelineError::Config)?,
+        enricher: build_enricher(config)?.map(|enricher| enricher.with_audit_log(audit_log)),
+        custom_stages,
+        custom_stage_names,
+        lineage: config.lineage,
+    };
+
+    // A run that died while saving may have left data files half-replaced
+    match staging::pending_transactions(&config.staging_dir) {
+        Ok(pending) if !pending.is_empty() => log_warning!(
+            "pipeline:run_processing_pipeline",
+            "{}",
+            tr!(UnfinishedSaves, pending.len(), config.staging_dir.display())
+        ),
+        Ok(_) => {}
+        Err(e) => log_warning!("pipeline:run_processing_pipeline", "{}", e),
+    }
+
+    // Keep other runs off the data file until the results are saved
+    let lock_timeout = (config.lock_timeout_secs > 0).then(|| Duration::from_secs(config.lock_timeout_secs));
+    let _data_lock = DataLock::acquire(data_path, lock_policy, lock_timeout).map_err(|e| {
+        log_critical!("pipeline:run_processing_pipeline", "{}", tr!(LoadFailed, e));
+        PipelineError::Load(e)
+    })?;
+
+    // 2. Load data
+    let load_stage = logging::enter_stage("load");
+    let load_span = info_span!("pipeline.load", path = %data_path.display(), items = Empty, error = Empty).entered();
+    let mut items_to_process: Vec<Item> = match data_handler.load_items() {
+        Ok(items) => items,
+        Err(e) => {
+            // Logging at CRITICAL level, similar to Python's logger.critical
+            log_critical!("pipeline:run_processing_pipeline", "{}", tr!(LoadFailed, e));
+            load_span.record("error", e.as_str());
+            return Err(PipelineError::Load(e));
+        }
+    };
+    load_span.record("items", items_to_process.len() as u64);
+    drop(load_span);
+    report.skipped_records = data_handler.skipped_records();
+    // Saving over the data file would drop the records that were skipped
+    let source = data_handler.data_source_path();
+    let saves_over_source = output_router.sinks().iter().any(|sink| storage::canonical_path(sink) == source);
+    if !report.skipped_records.is_empty()
+        && !config.drop_invalid_records
+        && config.input_sources.is_empty()
+        && saves_over_source
+    {
+        let count = report.skipped_records.len();
+        let plural = if count == 1 { "" } else { "s" };
+        let e = format!(
+            "{} invalid record{} in {} would be dropped by saving over it; fix them, set drop_invalid_records \
+             or use strict input_strictness",
+            count,
+            plural,
+            source.display()
+        );
+        log_critical!("pipeline:run_processing_pipeline", "{}", tr!(LoadFailed, e));
+        return Err(PipelineError::Load(e));
+    }
+    if let Some(tenant) = &config.tenant {
+        assign_tenant(&mut items_to_process, tenant);
+    }
+
+    if items_to_process.is_empty() {
+        log_warning!("pipeline:run_processing_pipeline", "{}", tr!(NoItemsLoaded));
+        report.finish(&items_to_process);
+        log_info!("pipeline:run_processing_pipeline", "{}", tr!(PipelineFinished));
+        return Ok(report);
+    }
+
+    log_info!("pipeline:run_processing_pipeline", "{}", tr!(ItemsLoaded, items_to_process.len()));
+    metrics.add_items_loaded(items_to_process.len() as u64);
+    report.items_loaded = items_to_process.len();
+    report.name_interning = data_handler.name_interning_stats();
+
+    // Drop records with duplicate ids before anything else looks at the list
+    let (unique_items, duplicates_dropped) = deduplicate(items_to_process, dedup_strategy);
+    items_to_process = unique_items;
+    report.duplicates_dropped = duplicates_dropped;
+    log_info!(
+        "pipeline:run_processing_pipeline",
+        "{}",
+        tr!(DedupResult, format!("{:?}", dedup_strategy), duplicates_dropped, items_to_process.len())
+    );
+
+    // Take over fields of the secondary dataset before anything looks at the items
+    if !config.join_path.as_os_str().is_empty() {
+        let secondary = build_data_handler(config, &config.join_path)
+            .and_then(|data_handler| data_handler.load_items())
+            .map_err(|e| {
+                log_critical!("pipeline:run_processing_pipeline", "{}", tr!(LoadFailed, e));
+                PipelineError::Load(e)
+            })?;
+        let (joined, stats) = join::join(std::mem::take(&mut items_to_process), secondary, join_type, &join_fields);
+        items_to_process = joined;
+        log_info!(
+            "pipeline:run_processing_pipeline",
+            "{}",
+            tr!(
+                JoinResult,
+                config.join_type,
+                config.join_path.display(),
+                stats.matched,
+                stats.unmatched.len(),
+                stats.dropped,
+                stats.unused.len()
+            )
+        );
+        report.join = Some(stats);
+    }
+
+    // Outliers are not processed; they are saved unchanged to the review sink
+    let (normal_items, anomalies) =
+        anomaly::detect(std::mem::take(&mut items_to_process), anomaly_method, config.anomaly_factor);
+    items_to_process = normal_items;
+    report.anomalies = anomalies.as_ref().map(|anomalies| anomalies.stats);
+    if let Some(stats) = &report.anomalies {
+        log_info!(
+            "pipeline:run_processing_pipeline",
+            "{}",
+            tr!(
+                AnomaliesFound,
+                stats.items,
+                format!("{:.2}", stats.lower),
+                format!("{:.2}", stats.upper),
+                review_path.display()
+            )
+        );
+    }
+
+    // A sample run handles only part of the items; the rest are saved unchanged
+    let sample = sampling::sample_size(items_to_process.len(), config.sample_size, config.sample_percent)
+        .map(|size| {
+            let (sampled, sample) = sampling::draw(std::mem::take(&mut items_to_process), size, config.sample_seed);
+            items_to_process = sampled;
+            sample
+        });
+    report.sample = sample.as_ref().map(|sample| sample.stats);
+    if let Some(stats) = &report.sample {
+        log_info!("pipeline:run_processing_pipeline", "{}", tr!(SamplingItems, stats.items, stats.of_items, stats.seed));
+    }
+
+    // Put the most important items first; they are saved in input order again
+    let priority_order = priority_policy.order(&items_to_process);
+    items_to_process = priority::reorder(items_to_process, &priority_order);
+
+    // Restore progress of an interrupted run, if requested
+    let mut resumed_mask: Vec<bool> = Vec::new();
+    if resume {
+        match &checkpoint {
+            Some(checkpoint) => {
+                resumed_mask = data_handler
+                    .apply_checkpoint(&items_to_process, checkpoint)
+                    .map_err(PipelineError::Load)?;
+            }
+            None => {
+                log_warning!("pipeline:run_processing_pipeline", "{}", tr!(NoCheckpoint, checkpoint_path.display()));
+            }
+        }
+    }
+
+    let total_items = items_to_process.len();
+    let (mut items_to_process, pending_items) = split_resumed(items_to_process, &resumed_mask);
+    let resumed_count = items_to_process.len();
+
+    if let HookDecision::Veto(reason) = hooks.notify_load_complete(&pending_items) {
+        log_warning!("pipeline:run_processing_pipeline", "{}", tr!(RunVetoed, reason));
+        return Err(PipelineError::Aborted(reason));
+    }
+
+    // 3. Process data items
+    // Items are handed to the worker pool and come back in their original
+    // order; checkpoints name the finished items by ID, so a resumed run
+    // finds them even when its priority order differs.
+    drop(load_stage);
+    let process_stage = logging::enter_stage("process");
+    let worker_pool = WorkerPool::new(config.worker_threads.max(1), config.worker_queue_capacity.max(1));
+    let rate_limiter = RateLimiter::configured(config.rate_limit, config.rate_limit_burst);
+    let deadline = (config.max_duration_secs > 0)
+        .then(|| Instant::now() + Duration::from_secs(config.max_duration_secs));
+    let deadline_passed = || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+    // Items already queued for the workers when the max duration passes come
+    // back unprocessed; they, and any item after them, join the items not started
+    let mut cut_off: Vec<Item> = Vec::new();
+    // Worker threads do not inherit the current span, so item spans name their parent
+    let run_span = Span::current();
+    let not_started = worker_pool.map_ordered(
+        pending_items,
+        || shutdown::requested() || deadline_passed(),
+        |mut item: Item| {
+            // Held here while processing is paused, see control.rs
+            control::wait_while_paused();
+            if let Some(rate_limiter) = &rate_limiter {
+                rate_limiter.acquire(1);
+            }
+            if deadline_passed() {
+                return (item, None);
+            }
+            let item_id = item.item_id.to_string();
+            let _item_context = logging::enter_item(&item_id);
+            metrics.set_current_item(Some(item_id));
+            let item_span = info_span!(parent: &run_span, "pipeline.item", item_id = %item.item_id, outcome = Empty);
+            let outcome =
+                item_span.in_scope(|| handle_item(&mut item, config.incremental, &steps, metrics, hooks));
+            item_span.record("outcome", format!("{:?}", outcome));
+            record_status(&mut item, outcome);
+            (item, Some(outcome))
+        },
+        |(item, outcome)| {
+            if let Some(outcome) = outcome {
+                report.record_outcome(outcome);
+                hooks.notify_after_item(&item, outcome);
+            }
+            if outcome.is_none() || !cut_off.is_empty() {
+                cut_off.push(item);
+                return;
+            }
+            items_to_process.push(item);
+
+            // Persist progress periodically so an interrupted run can be resumed
+            let completed = items_to_process.len();
+            if completed.is_multiple_of(checkpoint_interval) || completed == total_items {
+                save_checkpoint(checkpoint_path, data_handler.data_source_path(), &report.run_id, &items_to_process);
+            }
+        },
+    );
+    cut_off.extend(not_started);
+    let not_started = cut_off;
+    metrics.set_current_item(None);
+
+    report.rate_limit = rate_limiter.map(|rate_limiter| rate_limiter.stats());
+
+    // Stopped early on SIGINT/SIGTERM or at the max duration; what is done
+    // so far is saved below, and the checkpoint lets the next run resume
+    if !not_started.is_empty() {
+        if shutdown::requested() {
+            log_warning!("pipeline:run_processing_pipeline", "{}", tr!(ShutdownStopping, items_to_process.len(), total_items));
+            report.interrupted = true;
+        } else {
+            log_warning!(
+                "pipeline:run_processing_pipeline",
+                "{}",
+                tr!(DeadlineStopping, config.max_duration_secs, items_to_process.len(), total_items)
+            );
+            report.deadline_reached = true;
+        }
+        save_checkpoint(checkpoint_path, data_handler.data_source_path(), &report.run_id, &items_to_process);
+        items_to_process.extend(not_started);
+    }
+    let handled = items_to_process.split_off(resumed_count);
+    let items_to_process = merge_resumed(items_to_process, handled, &resumed_mask);
+    let items_to_process = priority::restore_order(items_to_process, &priority_order);
+    let items_to_process = match sample {
+        Some(sample) => sample.merge(items_to_process),
+        None => items_to_process,
+    };
+    let items_to_process = match anomalies {
+        Some(anomalies) => anomalies.merge(items_to_process),
+        None => items_to_process,
+    };
+
+    log_info!(
+        "pipeline:run_processing_pipeline",
+        "{}",
+        tr!(
+            ProcessedSummary,
+            report.items_processed,
+            report.items_failed,
+            report.items_skipped_by_tag,
+            report.items_skipped_by_filter
+        )
+    );
+
+    // 4. Save processed data
+    // The `items_to_process` vector now contains the (potentially) modified items.
+    drop(process_stage);
+    let save_stage = logging::enter_stage("save");
+    let save_span = info_span!(
+        "pipeline.save",
+        items = items_to_process.len() as u64,
+        sinks = output_router.sinks().len() as u64,
+        error = Empty
+    )
+    .entered();
+    // Deduplication ran on the loaded items; a join may have brought duplicates back
+    report.duplicate_ids_saved = dedup::check_before_save(&items_to_process, duplicate_policy).map_err(|e| {
+        log_error!("pipeline:run_processing_pipeline", "{}", tr!(SaveFailed, e));
+        save_span.record("error", e.as_str());
+        PipelineError::Save(e)
+    })?;
+    match data_handler.save_routed_items(&items_to_process, &output_router) {
+        Ok(_) => {
+            log_info!("pipeline:run_processing_pipeline", "{}", tr!(ItemsSaved));
+            report.output_paths = output_router.sinks().into_iter().map(Path::to_path_buf).collect();
+            // The run is complete; a later --resume must start from scratch
+            if !report.interrupted && !report.deadline_reached {
+                if let Err(e) = Checkpoint::clear(checkpoint_path) {
+                    log_warning!("pipeline:run_processing_pipeline", "{}", e);
+                }
+            }
+        }
+        Err(e) => {
+            log_error!("pipeline:run_processing_pipeline", "{}", tr!(SaveFailed, e));
+            save_span.record("error", e.as_str());
+            // The checkpoint is kept, so the run can be resumed once saving works again
+            return Err(PipelineError::Save(e));
+        }
+    }
+
+    // Grouped summaries go next to the processed items
+    if !groupings.is_empty() {
+        let saved = aggregate::summarize(&items_to_process, &groupings, config.value_decimals).and_then(|summaries| {
+            aggregate::save(&aggregate_path, &summaries, summary_format).map(|_| summaries.len())
+        });
+        let groups = saved.map_err(|e| {
+            log_error!("pipeline:run_processing_pipeline", "{}", tr!(SaveFailed, e));
+            PipelineError::Save(e)
+        })?;
+        log_info!("pipeline:run_processing_pipeline", "{}", tr!(SummarySaved, groups, aggregate_path.display()));
+        report.output_paths.push(aggregate_path);
+    }
+    drop(save_span);
+    drop(save_stage);
+
+    report.finish(&items_to_process);
+    if config.analytics {
+        report.value_stats = ValueStats::of(&ItemBatch::from(&items_to_process[..]), config.value_decimals);
+    }
+    hooks.notify_save_complete(&items_to_process, &report);
+    report.log_summary();
+    log_info!("pipeline:run_processing_pipeline", "{}", tr!(PipelineFinished));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use super::*;
+    use crate::config::ConfigSource;
+    use crate::item_id::ItemId;
+    use crate::item_processor::TAG_UNDER_THRESHOLD;
+    use crate::money::Money;
+    use crate::test_dir::TestDir;
+
+    /// The default configuration with all files of a run in `dir`.
+    fn test_config(dir: &Path) -> Config {
+        let mut config = Config::from_defaults();
+        for (key, file) in [
+            ("data_path", "items.json"),
+            ("checkpoint_path", "items.checkpoint.json"),
+            ("staging_dir", "staging"),
+            ("snapshot_dir", "snapshots"),
+        ] {
+            config.set_path(key, dir.join(file), ConfigSource::Cli).unwrap();
+        }
+        config.set("threshold", "1000", ConfigSource::Cli).unwrap();
+        config.set("transformations", "value = value * 2", ConfigSource::Cli).unwrap();
+        config
+    }
+
+    fn item(id: i64, value: i64, status: ItemStatus) -> Item {
+        let mut item = Item::new(ItemId::from(id), format!("Item {}", id), Money::from_int(value));
+        item.status = status;
+        item
+    }
+
+    #[test]
+    fn resume_after_a_crash_handles_the_unsaved_items_again() {
+        let dir = TestDir::new("resume-after-crash");
+        let config = test_config(&dir);
+        let data_handler = build_data_handler(&config, &config.data_path).unwrap();
+        // Item 3 was processed by an earlier run that saved; item 2 failed in it
+        let stored = [
+            item(1, 50, ItemStatus::New),
+            item(2, 150, ItemStatus::Failed),
+            item(3, 10, ItemStatus::Processed),
+            item(4, 200, ItemStatus::New),
+        ];
+        data_handler.save_items_to(&config.data_path, &stored).unwrap();
+        // The next run got through items 1 to 3, then crashed before saving
+        save_checkpoint(&config.checkpoint_path, data_handler.data_source_path(), "", &stored[..3]);
+
+        let report = run_processing_pipeline(&config, true, &Metrics::new(), &PipelineHooks::new()).unwrap();
+
+        assert_eq!(report.items_processed, 3);
+        let items = data_handler.load_items().unwrap();
+        let results: Vec<(String, ItemStatus, bool)> = items
+            .iter()
+            .map(|item| (item.value.to_string(), item.status, item.has_tag(TAG_UNDER_THRESHOLD)))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                (String::from("100.00"), ItemStatus::Processed, true),
+                (String::from("300.00"), ItemStatus::Processed, true),
+                (String::from("10.00"), ItemStatus::Processed, false),
+                (String::from("400.00"), ItemStatus::Processed, true),
+            ]
+        );
+        assert!(!config.checkpoint_path.exists());
+    }
+
+    #[test]
+    fn resume_finds_the_finished_items_when_the_priority_order_changed() {
+        let dir = TestDir::new("resume-after-reorder");
+        let mut config = test_config(&dir);
+        config.set("priority_policy", "highest-value", ConfigSource::Cli).unwrap();
+        config.set("transformations", "value = value / 10", ConfigSource::Cli).unwrap();
+        let data_handler = build_data_handler(&config, &config.data_path).unwrap();
+        // A time-boxed run processed item 2 (then worth 300) first and saved
+        // it, so it now ranks last instead of first
+        let stored = [
+            item(1, 100, ItemStatus::New),
+            item(2, 30, ItemStatus::Processed),
+            item(3, 200, ItemStatus::New),
+        ];
+        data_handler.save_items_to(&config.data_path, &stored).unwrap();
+        save_checkpoint(&config.checkpoint_path, data_handler.data_source_path(), "", &stored[1..2]);
+
+        let report = run_processing_pipeline(&config, true, &Metrics::new(), &PipelineHooks::new()).unwrap();
+
+        assert_eq!(report.items_processed, 2);
+        let items = data_handler.load_items().unwrap();
+        let values: Vec<String> = items.iter().map(|item| item.value.to_string()).collect();
+        assert_eq!(values, ["10.00", "30.00", "20.00"]);
+    }
+
+    #[test]
+    fn runs_rewrite_their_data_file_without_allow_overwrite() {
+        let dir = TestDir::new("overwrite-guard");
+        let config = test_config(&dir);
+        assert!(!config.allow_overwrite);
+        let data_handler = build_data_handler(&config, &config.data_path).unwrap();
+        data_handler.save_items_to(&config.data_path, &[item(1, 50, ItemStatus::New)]).unwrap();
+
+        for _ in 0..2 {
+            run_processing_pipeline(&config, false, &Metrics::new(), &PipelineHooks::new()).unwrap();
+        }
+
+        let items = data_handler.load_items().unwrap();
+        assert_eq!((items[0].value.to_string(), items[0].status), (String::from("100.00"), ItemStatus::Processed));
+        assert!(!config.checkpoint_path.exists());
+    }
+
+    #[test]
+    fn runs_keep_skipped_records_in_their_data_file_unless_told_to_drop_them() {
+        let dir = TestDir::new("keep-skipped");
+        let mut config = test_config(&dir);
+        let line = serde_json::to_string(&item(1, 50, ItemStatus::New)).unwrap();
+        let stored = format!("[{}, \"not an item\"]", line);
+        fs::write(&config.data_path, &stored).unwrap();
+
+        let result = run_processing_pipeline(&config, false, &Metrics::new(), &PipelineHooks::new());
+
+        assert!(matches!(result, Err(PipelineError::Load(message)) if message.contains("1 invalid record ")));
+        assert_eq!(fs::read_to_string(&config.data_path).unwrap(), stored);
+
+        config.set("drop_invalid_records", "true", ConfigSource::Cli).unwrap();
+        let report = run_processing_pipeline(&config, false, &Metrics::new(), &PipelineHooks::new()).unwrap();
+        assert_eq!(report.skipped_records.len(), 1);
+        let data_handler = build_data_handler(&config, &config.data_path).unwrap();
+        assert_eq!(data_handler.load_items().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn warn_stream_copies_malformed_lines_to_the_rewritten_file() {
+        let dir = TestDir::new("warn-stream");
+        let mut config = test_config(&dir);
+        config.set_path("data_path", dir.join("items.ndjson"), ConfigSource::Cli).unwrap();
+        let line = serde_json::to_string(&item(1, 50, ItemStatus::New)).unwrap();
+        fs::write(&config.data_path, format!("{}\nnot an item\n", line)).unwrap();
+
+        let summary = run_ndjson_stream(&config, &Metrics::new()).unwrap();
+
+        assert_eq!(summary.malformed_lines, 1);
+        let rewritten = fs::read_to_string(&config.data_path).unwrap();
+        assert_eq!(rewritten.lines().nth(1), Some("not an item"));
+    }
+
+    #[test]
+    fn an_audit_log_that_cannot_be_opened_is_a_config_error() {
+        let dir = TestDir::new("audit-log-config");
+        let mut config = test_config(&dir);
+        fs::write(dir.join("not-a-dir"), "").unwrap();
+        config.set_path("audit_log_path", dir.join("not-a-dir").join("audit.log"), ConfigSource::Cli).unwrap();
+
+        let result = run_processing_pipeline(&config, false, &Metrics::new(), &PipelineHooks::new());
+
+        assert!(matches!(result, Err(PipelineError::Config(_))));
+        assert!(!config.data_path.exists());
+    }
+
+    #[test]
+    fn strict_stream_stops_at_a_malformed_line_and_keeps_the_file() {
+        let dir = TestDir::new("strict-stream");
+        let mut config = test_config(&dir);
+        config.set_path("data_path", dir.join("items.ndjson"), ConfigSource::Cli).unwrap();
+        config.set("input_strictness", "strict", ConfigSource::Cli).unwrap();
+        let line = serde_json::to_string(&item(1, 50, ItemStatus::New)).unwrap();
+        let stored = format!("{}\nnot an item\n{}\n", line, line);
+        fs::write(&config.data_path, &stored).unwrap();
+
+        let result = run_ndjson_stream(&config, &Metrics::new());
+
+        assert!(matches!(result, Err(PipelineError::Load(message)) if message.contains("line 2")));
+        assert_eq!(fs::read_to_string(&config.data_path).unwrap(), stored);
+        assert!(!dir.join("items.ndjson.tmp").exists());
+    }
+
+    #[test]
+    fn resume_rejects_a_checkpoint_naming_an_item_that_was_not_loaded() {
+        let dir = TestDir::new("resume-changed-source");
+        let config = test_config(&dir);
+        let data_handler = build_data_handler(&config, &config.data_path).unwrap();
+        data_handler.save_items_to(&config.data_path, &[item(1, 50, ItemStatus::New)]).unwrap();
+        let unknown = [item(9, 50, ItemStatus::Processed)];
+        save_checkpoint(&config.checkpoint_path, data_handler.data_source_path(), "", &unknown);
+
+        let result = run_processing_pipeline(&config, true, &Metrics::new(), &PipelineHooks::new());
+
+        assert!(matches!(result, Err(PipelineError::Load(message)) if message.contains("data source has changed")));
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn runs_load_and_save_through_a_mock_store() {
+        use crate::test_util::{fixture_items, MockItemStore, StoreCall};
+
+        let dir = TestDir::new("mock-store");
+        let mut config = test_config(&dir);
+        let store = MockItemStore::new("mock://mock-store.json").with_items(&fixture_items(5));
+        store.register().unwrap();
+        config.set("data_path", "mock://mock-store.json", ConfigSource::Cli).unwrap();
+
+        let report = run_processing_pipeline(&config, false, &Metrics::new(), &PipelineHooks::new()).unwrap();
+
+        assert_eq!((report.items_loaded, report.items_processed), (5, 5));
+        let saved = store.items().unwrap();
+        assert_eq!(saved.len(), 5);
+        assert!(saved.iter().all(|item| item.status == ItemStatus::Processed));
+        assert!(matches!(store.calls().last(), Some(StoreCall::Write(_))));
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn a_store_failing_while_saving_fails_the_run_and_keeps_the_stored_items() {
+        use crate::test_util::{fixture_items, MockItemStore};
+
+        let dir = TestDir::new("mock-store-failure");
+        let mut config = test_config(&dir);
+        let items = fixture_items(5);
+        let store = MockItemStore::new("mock://mock-store-failure.json").with_items(&items);
+        store.register().unwrap();
+        config.set("data_path", "mock://mock-store-failure.json", ConfigSource::Cli).unwrap();
+        run_processing_pipeline(&config, false, &Metrics::new(), &PipelineHooks::new()).unwrap();
+        let calls_per_run = store.calls().len();
+
+        // The next run fails on the same call as the first run's save
+        let store = MockItemStore::new("mock://mock-store-failure.json")
+            .with_items(&items)
+            .fail_on_call(calls_per_run);
+        store.register().unwrap();
+        let result = run_processing_pipeline(&config, false, &Metrics::new(), &PipelineHooks::new());
+
+        assert!(matches!(result, Err(PipelineError::Save(message)) if message.contains("Injected failure")));
+        assert_eq!(store.items().unwrap(), items);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn resumed_saves_do_not_redeliver_the_items_the_interrupted_run_delivered() {
+        use crate::test_util::{MockItemStore, StoreCall};
+
+        let dir = TestDir::new("resume-delivery");
+        let mut config = test_config(&dir);
+        let sink = MockItemStore::new("mock://resume-delivery.json").with_batches().fail_on_call(3);
+        sink.register().unwrap();
+        config.set("output_path", "mock://resume-delivery.json", ConfigSource::Cli).unwrap();
+        config.set_path("delivery_ledger_path", dir.join("ledger.jsonl"), ConfigSource::Cli).unwrap();
+        config.set("save_batch_size", "1", ConfigSource::Cli).unwrap();
+        config.set("checkpoint_interval", "1", ConfigSource::Cli).unwrap();
+        let data_handler = build_data_handler(&config, &config.data_path).unwrap();
+        let stored: Vec<Item> = (1..=4).map(|id| item(id, 50, ItemStatus::New)).collect();
+        data_handler.save_items_to(&config.data_path, &stored).unwrap();
+
+        let result = run_processing_pipeline(&config, false, &Metrics::new(), &PipelineHooks::new());
+        assert!(matches!(result, Err(PipelineError::Save(_))));
+        run_processing_pipeline(&config, true, &Metrics::new(), &PipelineHooks::new()).unwrap();
+
+        let batches = sink.calls().into_iter().filter(|call| matches!(call, StoreCall::WriteBatch(..))).count();
+        assert_eq!(batches, 5);
+        let ids: Vec<ItemId> = sink.items().unwrap().into_iter().map(|item| item.item_id).collect();
+        assert_eq!(ids, (1..=4).map(ItemId::from).collect::<Vec<_>>());
+    }
+}
+
+// End of rust_sample_project/src/pipeline.rs
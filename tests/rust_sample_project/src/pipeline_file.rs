@@ -0,0 +1,215 @@
+// rust_sample_project/src/pipeline_file.rs
+
+// Pipeline definition files: a whole composed pipeline (see composition.rs)
+// described in YAML and run with `run --pipeline pipeline.yaml`, so the
+// binary can serve as a small generic ETL runner:
+//
+//     name: nightly-prices
+//     sources:
+//       - path: data/incoming/prices.csv
+//       - path: data/incoming/legacy.xml
+//         format: xml
+//     dedup: keep-last              # optional, as the dedup_strategy config key
+//     duplicate_ids: refuse         # optional, as the duplicate_id_policy config key
+//     stages:
+//       - stage: enrich
+//         lookup: data/categories.csv
+//         missing: skip
+//       - stage: filter
+//         expression: "value > 10"
+//       - stage: process
+//         threshold: 150
+//         transformations: ["value = value * 1.2 if value > threshold"]
+//     sinks:
+//       - path: data/out/prices.json.gz
+//     on_error: abort               # or continue (the default)
+//     workers: 4
+//
+// Stage names resolve to the registered stages (see registry.rs): the
+// built-in enrich, filter and process, which take the parameters of the
+// matching config keys, and any the embedding binary registered. Unknown
+// names, fields and values are rejected before anything runs. Paths are
+// relative to the working directory, like in the config file, and data files
+// are read and written with the storage settings of the effective config
+// (encryption, checksums, XML/Excel mappings), unless a source or sink sets
+// its own format.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::composition::{ErrorPolicy, Pipeline};
+use crate::compression::Compression;
+use crate::config::Config;
+use crate::data_handler::{DataFormat, DataHandler};
+use crate::dedup::{DedupStrategy, DuplicatePolicy};
+use crate::pipeline;
+use crate::registry::{self, StageParams};
+
+/**
+ * @struct PipelineDefinition
+ * @brief The contents of a pipeline definition file.
+ */
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineDefinition {
+    /// Name of the pipeline, for log messages.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Data files to load, concatenated in order.
+    pub sources: Vec<DataFileDefinition>,
+    /// Dedup strategy applied after loading (see DedupStrategy::parse); none by default.
+    #[serde(default)]
+    pub dedup: Option<String>,
+    /// What saving does about duplicate ids (see DuplicatePolicy::parse); the
+    /// duplicate_id_policy config key by default.
+    #[serde(default)]
+    pub duplicate_ids: Option<String>,
+    /// Stages applied to each item, in order.
+    #[serde(default)]
+    pub stages: Vec<StageDefinition>,
+    /// Data files the items are written to; each gets all items.
+    pub sinks: Vec<DataFileDefinition>,
+    /// "continue" or "abort" (see ErrorPolicy::parse).
+    #[serde(default = "default_error_policy")]
+    pub on_error: String,
+    /// Number of worker threads the stages run on.
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+}
+
+/**
+ * @struct DataFileDefinition
+ * @brief A source or sink data file.
+ */
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DataFileDefinition {
+    /// Path (or URL) of the data file.
+    pub path: PathBuf,
+    /// Format of the file, overriding the data_format config key.
+    #[serde(default)]
+    pub format: Option<String>,
+    /// Compression of a sink, overriding the output_compression config key.
+    #[serde(default)]
+    pub compression: Option<String>,
+}
+
+/**
+ * @struct StageDefinition
+ * @brief One stage: its name and its parameters.
+ */
+#[derive(Debug, Clone, Deserialize)]
+pub struct StageDefinition {
+    /// Name of a registered stage (see registry.rs).
+    pub stage: String,
+    /// The remaining fields, checked by the stage.
+    #[serde(flatten)]
+    pub params: StageParams,
+}
+
+fn default_error_policy() -> String {
+    String::from("continue")
+}
+
+fn default_workers() -> usize {
+    1
+}
+
+impl PipelineDefinition {
+    /**
+     * @brief Read a pipeline definition file.
+     *
+     * @param path Path of the YAML file.
+     * @return Result<PipelineDefinition, String> The definition, or an error
+     *         message if the file cannot be read or is not a valid definition.
+     */
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read pipeline file '{}': {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("Invalid pipeline file '{}': {}", path.display(), e))
+    }
+
+    /**
+     * @brief Parse a pipeline definition from YAML text.
+     *
+     * @param text The YAML document.
+     * @return Result<PipelineDefinition, String> The definition, or an error message.
+     */
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let definition: PipelineDefinition = serde_yaml::from_str(text).map_err(|e| e.to_string())?;
+        if definition.sources.is_empty() {
+            return Err(String::from("sources: at least one source is required"));
+        }
+        if definition.sinks.is_empty() {
+            return Err(String::from("sinks: at least one sink is required"));
+        }
+        if definition.workers == 0 {
+            return Err(String::from("workers: must be at least 1"));
+        }
+        Ok(definition)
+    }
+
+    /**
+     * @brief Assemble the pipeline the definition describes.
+     *
+     * Lookup tables are loaded here, so a missing one fails before any data
+     * file is touched.
+     *
+     * @param config The effective configuration, for the storage settings of
+     *        the data files.
+     * @return Result<Pipeline, String> The pipeline, or an error message
+     *         naming the invalid part of the definition.
+     */
+    pub fn build(&self, config: &Config) -> Result<Pipeline, String> {
+        let error_policy = ErrorPolicy::parse(&self.on_error).map_err(|e| format!("on_error: {}", e))?;
+        let mut pipeline = Pipeline::new()
+            .with_error_policy(error_policy)
+            .with_workers(self.workers);
+        if let Some(dedup) = &self.dedup {
+            pipeline = pipeline.with_dedup(DedupStrategy::parse(dedup).map_err(|e| format!("dedup: {}", e))?);
+        }
+        let duplicate_ids = self.duplicate_ids.as_deref().unwrap_or(&config.duplicate_id_policy);
+        pipeline = pipeline
+            .with_duplicate_policy(DuplicatePolicy::parse(duplicate_ids).map_err(|e| format!("duplicate_ids: {}", e))?);
+        for (index, source) in self.sources.iter().enumerate() {
+            let handler = data_handler(config, source).map_err(|e| format!("sources[{}]: {}", index, e))?;
+            pipeline = pipeline.source(handler);
+        }
+        for (index, stage) in self.stages.iter().enumerate() {
+            let built = registry::build_stage(&stage.stage, &stage.params)
+                .map_err(|e| format!("stages[{}] ({}): {}", index, stage.stage, e))?;
+            pipeline = pipeline.stage(built);
+        }
+        for (index, sink) in self.sinks.iter().enumerate() {
+            let handler = data_handler(config, sink).map_err(|e| format!("sinks[{}]: {}", index, e))?;
+            pipeline = pipeline.sink(handler);
+        }
+        log_info!(
+            "pipeline_file:build",
+            "Assembled pipeline '{}': {} sources, {} stages, {} sinks.",
+            self.name.as_deref().unwrap_or("unnamed"),
+            self.sources.len(),
+            self.stages.len(),
+            self.sinks.len()
+        );
+        Ok(pipeline)
+    }
+}
+
+/**
+ * @brief Build the DataHandler of a source or sink.
+ */
+fn data_handler(config: &Config, definition: &DataFileDefinition) -> Result<DataHandler, String> {
+    let mut handler = pipeline::build_data_handler(config, &definition.path)?;
+    if let Some(format) = &definition.format {
+        handler = handler.with_data_format(DataFormat::parse(format)?);
+    }
+    if let Some(compression) = &definition.compression {
+        handler = handler.with_output_compression(Compression::parse(compression)?);
+    }
+    Ok(handler)
+}
+
+// End of rust_sample_project/src/pipeline_file.rs
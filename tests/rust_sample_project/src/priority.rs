@@ -0,0 +1,113 @@
+// rust_sample_project/src/priority.rs
+
+// The order items are processed in (see the priority_policy config key).
+// Items are processed in input order by default; when a run is time-boxed
+// with --max-duration, a priority policy puts the most important items first,
+// so they are done before the deadline cuts the run short. The saved data
+// file keeps the input order either way: the pipeline puts the items back in
+// their original positions before saving.
+//
+// The order is a stable sort of the loaded items. A resumed run (--resume)
+// may compute a different order, e.g. when a policy ranks by values the
+// interrupted run already transformed and saved; that is fine, since the
+// checkpoint names the finished items by ID rather than by position.
+
+use std::cmp::Reverse;
+
+use crate::filter::FilterExpr;
+use crate::item::Item;
+
+/**
+ * @enum PriorityPolicy
+ * @brief Which items are processed first.
+ */
+#[derive(Debug, Clone)]
+pub enum PriorityPolicy {
+    /// The order of the data file.
+    InputOrder,
+    /// The items with the highest value first.
+    HighestValue,
+    /// The items with the lowest value first.
+    LowestValue,
+    /// The items matching a filter expression first, each group in input order.
+    Matching(FilterExpr),
+}
+
+impl PriorityPolicy {
+    /**
+     * @brief Parse a priority policy as used in config.
+     *
+     * @param spec One of "input-order", "highest-value", "lowest-value" or
+     *        "matching:<filter expression>".
+     * @return Result<PriorityPolicy, String> The policy, or an error message
+     *         for unknown policies and invalid filter expressions.
+     */
+    pub fn parse(spec: &str) -> Result<PriorityPolicy, String> {
+        match spec {
+            "input-order" => Ok(PriorityPolicy::InputOrder),
+            "highest-value" => Ok(PriorityPolicy::HighestValue),
+            "lowest-value" => Ok(PriorityPolicy::LowestValue),
+            other => match other.strip_prefix("matching:") {
+                Some(expression) => FilterExpr::parse(expression.trim()).map(PriorityPolicy::Matching),
+                None => Err(format!(
+                    "Unknown priority policy '{}' (expected 'input-order', 'highest-value', 'lowest-value' or 'matching:<filter expression>')",
+                    other
+                )),
+            },
+        }
+    }
+
+    /**
+     * @brief Return the positions of items in the order they are to be processed.
+     *
+     * Items the policy ranks equally keep their input order.
+     *
+     * @param items The items, in input order.
+     * @return Vec<usize> Every index of items once, highest priority first.
+     */
+    pub fn order(&self, items: &[Item]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        match self {
+            PriorityPolicy::InputOrder => {}
+            PriorityPolicy::HighestValue => order.sort_by_key(|&index| Reverse(items[index].value)),
+            PriorityPolicy::LowestValue => order.sort_by_key(|&index| items[index].value),
+            PriorityPolicy::Matching(filter) => order.sort_by_key(|&index| !filter.matches(&items[index])),
+        }
+        order
+    }
+}
+
+/**
+ * @brief Put items into processing order.
+ *
+ * @param items The items, in input order.
+ * @param order Their processing order, see PriorityPolicy::order.
+ * @return Vec<Item> The items in processing order.
+ */
+pub fn reorder(items: Vec<Item>, order: &[usize]) -> Vec<Item> {
+    let mut slots: Vec<Option<Item>> = items.into_iter().map(Some).collect();
+    order
+        .iter()
+        .map(|&index| slots[index].take().expect("a processing order holds every index once"))
+        .collect()
+}
+
+/**
+ * @brief Put items in processing order back into input order.
+ *
+ * @param items The items, in processing order.
+ * @param order The processing order they were put in by reorder.
+ * @return Vec<Item> The items in input order.
+ */
+pub fn restore_order(items: Vec<Item>, order: &[usize]) -> Vec<Item> {
+    let mut slots: Vec<Option<Item>> = (0..items.len()).map(|_| None).collect();
+    for (item, &index) in items.into_iter().zip(order) {
+        slots[index] = Some(item);
+    }
+    slots
+        .into_iter()
+        .map(|slot| slot.expect("a processing order holds every index once"))
+        .collect()
+}
+
+// End of rust_sample_project/src/priority.rs
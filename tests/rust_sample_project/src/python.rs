@@ -0,0 +1,350 @@
+// rust_sample_project/src/python.rs
+
+// Python bindings (see the `python` feature), so data teams can drive the
+// pipeline from notebooks. Built as an extension module with maturin
+// (`maturin develop --features python`, see pyproject.toml):
+//
+//     import pandas as pd
+//     import rust_sample_project as sp
+//
+//     def tag_expensive(item):
+//         if item.value > 100:
+//             item.tags = item.tags + ["expensive"]
+//
+//     result = sp.run_pipeline(
+//         config={"data_path": "data/items.json", "threshold": 150},
+//         stages=[tag_expensive, sp.ItemProcessor(150, transformations=["value = value * 2"])],
+//     )
+//     df = pd.DataFrame(result["items"])
+//
+// run_pipeline runs the regular pipeline (load, deduplicate, process, save)
+// with the given config values on top of the config file and environment,
+// and returns the result as a dict shaped like the binary's --output json,
+// except that "items" holds the full items (one flat dict per item, plus its
+// "outcome"), ready for pandas. Python stages run after the registered ones
+// named in custom_stages: a callable gets an Item it may change in place,
+// and returns None or True to go on, False to leave the item out, or raises
+// to fail it. Worker threads take the GIL only to call Python stages.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+
+use crate::composition::{Stage, StageResult};
+use crate::config::{Config, ConfigErrors, ConfigProblem, ConfigSource};
+use crate::hooks::{ItemOutcome, PipelineHooks};
+use crate::item::Item;
+use crate::item_id::{self, ItemId};
+use crate::item_processor::ItemProcessor;
+use crate::metrics::Metrics;
+use crate::money::Money;
+use crate::pipeline;
+use crate::report::RunResult;
+use crate::transform::Transformation;
+
+/**
+ * @struct PyItem
+ * @brief An Item as seen from Python.
+ */
+#[pyclass(name = "Item", module = "rust_sample_project")]
+pub struct PyItem {
+    inner: Item,
+}
+
+#[pymethods]
+impl PyItem {
+    /**
+     * @brief Constructs a new item, like Item::new.
+     *
+     * @param item_id The id, an int or a str of the configured item id kind.
+     */
+    #[new]
+    #[pyo3(signature = (item_id, name, value, tags = None))]
+    fn new(item_id: &Bound<'_, PyAny>, name: String, value: f64, tags: Option<Vec<String>>) -> PyResult<Self> {
+        let item_id = ItemId::parse(&item_id.str()?.to_string(), item_id::expected_kind()).map_err(PyValueError::new_err)?;
+        let mut inner = Item::new(item_id, name, Money::from_f64(value).map_err(PyValueError::new_err)?);
+        inner.tags = tags.unwrap_or_default();
+        Ok(PyItem { inner })
+    }
+
+    #[getter]
+    fn item_id(&self) -> String {
+        self.inner.item_id.to_string()
+    }
+
+    #[getter]
+    fn name(&self) -> String {
+        self.inner.name.to_string()
+    }
+
+    #[setter]
+    fn set_name(&mut self, name: String) {
+        self.inner.name = name.into();
+    }
+
+    #[getter]
+    fn value(&self) -> f64 {
+        self.inner.value.to_f64()
+    }
+
+    #[setter]
+    fn set_value(&mut self, value: f64) -> PyResult<()> {
+        self.inner.value = Money::from_f64(value).map_err(PyValueError::new_err)?;
+        Ok(())
+    }
+
+    /// The status name, e.g. "processed"; changed only by the pipeline.
+    #[getter]
+    fn status(&self) -> &'static str {
+        self.inner.status.name()
+    }
+
+    #[getter]
+    fn tags(&self) -> Vec<String> {
+        self.inner.tags.clone()
+    }
+
+    #[setter]
+    fn set_tags(&mut self, tags: Vec<String>) {
+        self.inner.tags = tags;
+    }
+
+    #[getter]
+    fn category(&self) -> Option<String> {
+        self.inner.category.clone()
+    }
+
+    #[setter]
+    fn set_category(&mut self, category: Option<String>) {
+        self.inner.category = category;
+    }
+
+    #[getter]
+    fn supplier(&self) -> Option<String> {
+        self.inner.supplier.clone()
+    }
+
+    #[setter]
+    fn set_supplier(&mut self, supplier: Option<String>) {
+        self.inner.supplier = supplier;
+    }
+
+    #[getter]
+    fn tenant(&self) -> Option<String> {
+        self.inner.tenant.clone()
+    }
+
+    #[setter]
+    fn set_tenant(&mut self, tenant: Option<String>) {
+        self.inner.tenant = tenant;
+    }
+
+    /**
+     * @brief Return the item as a dict, with the fields of the data files.
+     */
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_python(py, &serde_json::to_value(&self.inner).map_err(|e| PyRuntimeError::new_err(e.to_string()))?)
+    }
+
+    fn __repr__(&self) -> String {
+        self.inner.to_string()
+    }
+}
+
+/**
+ * @struct PyItemProcessor
+ * @brief An ItemProcessor as seen from Python; also usable as a stage.
+ */
+#[pyclass(name = "ItemProcessor", module = "rust_sample_project")]
+pub struct PyItemProcessor {
+    inner: Arc<ItemProcessor>,
+}
+
+#[pymethods]
+impl PyItemProcessor {
+    /**
+     * @brief Constructs a processor; the arguments mean what the config keys of their names do.
+     */
+    #[new]
+    #[pyo3(signature = (threshold, transformations = None, required_tags = None, excluded_tags = None, category_thresholds = None, tag_thresholds = None))]
+    fn new(
+        threshold: f64,
+        transformations: Option<Vec<String>>,
+        required_tags: Option<Vec<String>>,
+        excluded_tags: Option<Vec<String>>,
+        category_thresholds: Option<Vec<(String, f64)>>,
+        tag_thresholds: Option<Vec<(String, f64)>>,
+    ) -> PyResult<Self> {
+        let transformations = transformations
+            .unwrap_or_default()
+            .iter()
+            .map(|rule| Transformation::parse(rule))
+            .collect::<Result<Vec<Transformation>, String>>()
+            .map_err(PyValueError::new_err)?;
+        let processor = ItemProcessor::new(threshold)
+            .with_thresholds(category_thresholds.unwrap_or_default(), tag_thresholds.unwrap_or_default())
+            .with_tag_rules(required_tags.unwrap_or_default(), excluded_tags.unwrap_or_default())
+            .with_transformations(transformations);
+        Ok(PyItemProcessor { inner: Arc::new(processor) })
+    }
+
+    /**
+     * @brief Check whether the tag rules select an item.
+     */
+    fn is_selected(&self, item: PyRef<'_, PyItem>) -> bool {
+        self.inner.is_selected(&item.inner)
+    }
+
+    /**
+     * @brief Process an item in place, see ItemProcessor::process_item.
+     *
+     * @return bool True if the item was processed, False if it failed.
+     */
+    fn process(&self, mut item: PyRefMut<'_, PyItem>) -> bool {
+        self.inner.process_item(&mut item.inner)
+    }
+}
+
+/**
+ * @struct PythonStage
+ * @brief A Python callable used as a stage.
+ */
+struct PythonStage {
+    callable: Py<PyAny>,
+}
+
+impl Stage for PythonStage {
+    fn apply(&self, item: &mut Item) -> StageResult {
+        Python::attach(|py| {
+            let argument = Bound::new(py, PyItem { inner: item.clone() })?;
+            let returned = self.callable.bind(py).call1((argument.clone(),))?;
+            if !returned.is_none() && !returned.extract::<bool>()? {
+                return Ok(StageResult::Finish(ItemOutcome::FilteredOut));
+            }
+            *item = argument.borrow().inner.clone();
+            Ok(StageResult::Continue)
+        })
+        .unwrap_or_else(|e: PyErr| {
+            log_error!("python:apply", "Python stage failed item {}: {}", item, e);
+            StageResult::Finish(ItemOutcome::Failed)
+        })
+    }
+}
+
+/**
+ * @brief Run the pipeline and return its result as a dict.
+ *
+ * @param config Config values by key, over the config file and environment; lists for list keys.
+ * @param stages Python callables and ItemProcessor objects applied to each item.
+ * @param config_file Config file to read instead of the default one.
+ * @param profile Config file profile to apply.
+ * @param resume Continue from the checkpoint of an interrupted run.
+ * @return dict The run result; raises ValueError for an invalid configuration.
+ */
+#[pyfunction]
+#[pyo3(signature = (config = None, stages = None, config_file = None, profile = None, resume = false))]
+fn run_pipeline<'py>(
+    py: Python<'py>,
+    config: Option<&Bound<'py, PyDict>>,
+    stages: Option<&Bound<'py, PyList>>,
+    config_file: Option<PathBuf>,
+    profile: Option<String>,
+    resume: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    let config = build_config(config, config_file.as_deref(), profile.as_deref())?;
+    let mut extra_stages: Vec<Box<dyn Stage>> = Vec::new();
+    for stage in stages.into_iter().flatten() {
+        if let Ok(processor) = stage.extract::<PyRef<'_, PyItemProcessor>>() {
+            extra_stages.push(Box::new(Arc::clone(&processor.inner)));
+        } else if stage.is_callable() {
+            extra_stages.push(Box::new(PythonStage { callable: stage.unbind() }));
+        } else {
+            return Err(PyValueError::new_err(format!(
+                "stages must be callables or ItemProcessor objects, got {}",
+                stage.get_type().name()?
+            )));
+        }
+    }
+
+    let items = Arc::new(Mutex::new(Vec::new()));
+    let collected = Arc::clone(&items);
+    let hooks = PipelineHooks::new().after_item(move |item, outcome| {
+        collected.lock().unwrap_or_else(|e| e.into_inner()).push((item.clone(), outcome));
+    });
+    // Release the GIL, so the worker threads can call Python stages
+    let result = py.detach(|| {
+        pipeline::run_processing_pipeline_with_stages(&config, resume, &Metrics::new(), &hooks, extra_stages)
+    });
+
+    let to_error = |e: serde_json::Error| PyRuntimeError::new_err(e.to_string());
+    let mut document = serde_json::to_value(RunResult::new(result, Vec::new())).map_err(to_error)?;
+    let items = std::mem::take(&mut *items.lock().unwrap_or_else(|e| e.into_inner()));
+    document["items"] = items
+        .iter()
+        .map(|(item, outcome)| {
+            let mut row = serde_json::to_value(item)?;
+            row["outcome"] = serde_json::to_value(outcome)?;
+            Ok(row)
+        })
+        .collect::<Result<serde_json::Value, serde_json::Error>>()
+        .map_err(to_error)?;
+    to_python(py, &document)
+}
+
+/**
+ * @brief Resolve and validate the configuration of a run_pipeline call, and
+ *        apply its process-wide values.
+ */
+fn build_config(
+    values: Option<&Bound<'_, PyDict>>,
+    config_file: Option<&Path>,
+    profile: Option<&str>,
+) -> PyResult<Config> {
+    let (mut config, mut problems) = Config::load(config_file, profile);
+    for (key, value) in values.into_iter().flat_map(|values| values.iter()) {
+        let key: String = key.extract()?;
+        let result = if let Ok(list) = value.cast::<PyList>() {
+            let entries = list.iter().map(|entry| Ok(entry.str()?.to_string())).collect::<PyResult<Vec<String>>>()?;
+            config.set_list(&key, entries, ConfigSource::Api)
+        } else if let Ok(flag) = value.extract::<bool>() {
+            config.set(&key, &flag.to_string(), ConfigSource::Api)
+        } else {
+            config.set(&key, &value.str()?.to_string(), ConfigSource::Api)
+        };
+        if let Err(message) = result {
+            problems.push(ConfigProblem { key, source: ConfigSource::Api, message });
+        }
+    }
+    if let Err(errors) = config.validate() {
+        problems.extend(errors.problems);
+    }
+    if !problems.is_empty() {
+        return Err(PyValueError::new_err(ConfigErrors { problems }.to_string()));
+    }
+    config.apply_process_settings();
+    Ok(config)
+}
+
+/**
+ * @brief Convert a JSON value to the matching Python object (dicts, lists, ...).
+ */
+fn to_python<'py>(py: Python<'py>, value: &serde_json::Value) -> PyResult<Bound<'py, PyAny>> {
+    py.import("json")?.call_method1("loads", (value.to_string(),))
+}
+
+/**
+ * @brief The extension module, imported as rust_sample_project.
+ */
+#[pymodule]
+#[pyo3(name = "rust_sample_project")]
+fn python_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyItem>()?;
+    module.add_class::<PyItemProcessor>()?;
+    module.add_function(wrap_pyfunction!(run_pipeline, module)?)?;
+    Ok(())
+}
+
+// End of rust_sample_project/src/python.rs
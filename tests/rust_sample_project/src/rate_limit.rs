@@ -0,0 +1,167 @@
+// rust_sample_project/src/rate_limit.rs
+
+// Rate limiting of item throughput (see the rate_limit and rate_limit_burst
+// config keys), so a run does not overwhelm the systems downstream of it.
+// A RateLimiter is a token bucket: it holds up to `burst` items' worth of
+// tokens, refilled at `rate` per second, and a caller that takes more tokens
+// than the bucket holds sleeps until they have been refilled. Callers take
+// their tokens before waiting, so concurrent callers (worker threads) queue
+// up behind each other instead of all waking at once.
+//
+// The processing loop limits the items it hands to the workers; the HTTP and
+// Kafka sinks share one process-wide limiter (see sink_limiter) for the items
+// they send, set from the configuration by Config::apply_process_settings.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::shutdown;
+
+/// Longest single sleep while waiting for tokens, so a shutdown request is noticed promptly.
+const MAX_SLEEP: Duration = Duration::from_millis(100);
+
+/// The limiter of the HTTP and Kafka sinks (None without a rate limit).
+static SINK_LIMITER: Mutex<Option<Arc<RateLimiter>>> = Mutex::new(None);
+
+/**
+ * @struct RateLimitStats
+ * @brief What a rate limiter let through, for the run report.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RateLimitStats {
+    /// The configured limit in items per second.
+    pub limit_per_sec: f64,
+    /// Number of items let through.
+    pub items: u64,
+    /// Time callers spent waiting for tokens, in seconds.
+    pub throttled_secs: f64,
+    /// Items let through per second since the limiter was created.
+    pub effective_per_sec: f64,
+}
+
+/**
+ * @struct RateLimiter
+ * @brief A token bucket limiting items per second.
+ */
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Tokens refilled per second.
+    rate: f64,
+    /// Most tokens the bucket holds.
+    burst: f64,
+    /// Tokens left (negative while callers wait for refills) and when they were counted.
+    bucket: Mutex<(f64, Instant)>,
+    /// When the limiter was created.
+    started: Instant,
+    /// Number of items let through.
+    items: AtomicU64,
+    /// Total time callers waited, in microseconds.
+    throttled_micros: AtomicU64,
+}
+
+impl RateLimiter {
+    /**
+     * @brief Constructs a limiter with a full bucket.
+     *
+     * @param items_per_sec The sustained rate (greater than 0).
+     * @param burst Most items let through at once after an idle period (at least 1).
+     * @return RateLimiter The limiter.
+     */
+    pub fn new(items_per_sec: f64, burst: usize) -> Self {
+        let burst = burst.max(1) as f64;
+        let now = Instant::now();
+        RateLimiter {
+            rate: items_per_sec,
+            burst,
+            bucket: Mutex::new((burst, now)),
+            started: now,
+            items: AtomicU64::new(0),
+            throttled_micros: AtomicU64::new(0),
+        }
+    }
+
+    /**
+     * @brief Constructs a limiter for a configured rate, if there is one.
+     *
+     * @param items_per_sec The configured rate; 0 means no limit.
+     * @param burst The configured burst size.
+     * @return Option<RateLimiter> The limiter, or None without a limit.
+     */
+    pub fn configured(items_per_sec: f64, burst: usize) -> Option<RateLimiter> {
+        (items_per_sec > 0.0).then(|| RateLimiter::new(items_per_sec, burst))
+    }
+
+    /**
+     * @brief Wait until a number of items may pass.
+     *
+     * Returns early, without the remaining wait, when a shutdown is requested.
+     *
+     * @param items The number of items about to be processed or sent.
+     */
+    pub fn acquire(&self, items: u64) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let (tokens, counted_at) = *bucket;
+            let refilled = (tokens + now.duration_since(counted_at).as_secs_f64() * self.rate).min(self.burst);
+            let left = refilled - items as f64;
+            *bucket = (left, now);
+            Duration::from_secs_f64((-left).max(0.0) / self.rate)
+        };
+        self.items.fetch_add(items, Ordering::Relaxed);
+        if wait.is_zero() {
+            return;
+        }
+        self.throttled_micros.fetch_add(wait.as_micros() as u64, Ordering::Relaxed);
+        let until = Instant::now() + wait;
+        while !shutdown::requested() {
+            let now = Instant::now();
+            if now >= until {
+                break;
+            }
+            thread::sleep(MAX_SLEEP.min(until - now));
+        }
+    }
+
+    /**
+     * @brief Return what the limiter has let through so far.
+     *
+     * @return RateLimitStats The counters.
+     */
+    pub fn stats(&self) -> RateLimitStats {
+        let items = self.items.load(Ordering::Relaxed);
+        let elapsed = self.started.elapsed().as_secs_f64();
+        RateLimitStats {
+            limit_per_sec: self.rate,
+            items,
+            throttled_secs: self.throttled_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+            effective_per_sec: if elapsed > 0.0 { items as f64 / elapsed } else { 0.0 },
+        }
+    }
+}
+
+/**
+ * @brief Set the rate limit of the HTTP and Kafka sinks.
+ *
+ * @param items_per_sec Items per second; 0 removes the limit.
+ * @param burst Most items sent at once after an idle period.
+ */
+pub fn set_sink_limit(items_per_sec: f64, burst: usize) {
+    let limiter = RateLimiter::configured(items_per_sec, burst).map(Arc::new);
+    *SINK_LIMITER.lock().unwrap_or_else(|e| e.into_inner()) = limiter;
+}
+
+/**
+ * @brief Return the limiter of the HTTP and Kafka sinks.
+ *
+ * @return Option<Arc<RateLimiter>> The limiter, or None without a rate limit.
+ */
+pub fn sink_limiter() -> Option<Arc<RateLimiter>> {
+    SINK_LIMITER.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+// End of rust_sample_project/src/rate_limit.rs
@@ -0,0 +1,246 @@
+// rust_sample_project/src/registry.rs
+
+// Registry of named stages, so pipeline definition files (see
+// pipeline_file.rs), the custom_stages config key and --stage can refer to
+// stages by name. The built-in stages (enrich, filter, process, and wasm with
+// the `wasm` feature) are always registered; downstream binaries add their
+// own before running a pipeline:
+//
+//     registry::register_stage("min_name_length", |params| {
+//         let params: MinNameLength = registry::parse_params(params)?;
+//         Ok(Box::new(move |item: &mut Item| if item.name.len() >= params.length {
+//             StageResult::Continue
+//         } else {
+//             StageResult::Finish(ItemOutcome::FilteredOut)
+//         }))
+//     })?;
+//
+// A stage is registered as a factory that builds it from its parameters (the
+// fields of its entry in a pipeline file; none when it is named in the
+// config or on the command line), so invalid parameters are reported before
+// anything runs. Names are unique: registering a taken name is an error
+// rather than silently replacing a stage.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::composition::Stage;
+use crate::config::{ENRICHMENT_MISSING_POLICY, PROCESSING_THRESHOLD};
+use crate::enrichment::{Enricher, MissingLookupPolicy};
+use crate::filter::FilterExpr;
+use crate::item_processor::ItemProcessor;
+use crate::transform::Transformation;
+
+/// Parameters of a stage, by field name.
+pub type StageParams = BTreeMap<String, serde_yaml::Value>;
+
+/// Builds a stage from its parameters, or returns an error message for invalid ones.
+pub type StageFactory = Arc<dyn Fn(&StageParams) -> Result<Box<dyn Stage>, String> + Send + Sync>;
+
+/// The registered stages by name, set up with the built-in ones on first use.
+static REGISTRY: OnceLock<RwLock<BTreeMap<String, StageFactory>>> = OnceLock::new();
+
+/**
+ * @struct FilterParams
+ * @brief Parameters of the "filter" stage.
+ */
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FilterParams {
+    /// Filter expression, as the filter_expression config key.
+    expression: String,
+}
+
+/**
+ * @struct EnrichParams
+ * @brief Parameters of the "enrich" stage.
+ */
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EnrichParams {
+    /// Lookup table, as the enrichment_lookup_path config key.
+    lookup: PathBuf,
+    /// Policy for items missing from the table, as enrichment_missing_policy.
+    #[serde(default = "default_missing_policy")]
+    missing: String,
+}
+
+/**
+ * @struct ProcessParams
+ * @brief Parameters of the "process" stage.
+ */
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProcessParams {
+    #[serde(default = "default_threshold")]
+    threshold: f64,
+    #[serde(default)]
+    category_thresholds: BTreeMap<String, f64>,
+    #[serde(default)]
+    tag_thresholds: BTreeMap<String, f64>,
+    #[serde(default)]
+    required_tags: Vec<String>,
+    #[serde(default)]
+    excluded_tags: Vec<String>,
+    #[serde(default)]
+    transformations: Vec<String>,
+    /// Per-item timeout in milliseconds; 0 for none.
+    #[serde(default)]
+    timeout_ms: u64,
+}
+
+fn default_missing_policy() -> String {
+    ENRICHMENT_MISSING_POLICY.to_string()
+}
+
+fn default_threshold() -> f64 {
+    PROCESSING_THRESHOLD
+}
+
+/**
+ * @brief Register a stage under a name.
+ *
+ * @param name Name the stage is referred to by, e.g. in pipeline files.
+ * @param factory Builds the stage from its parameters; see parse_params.
+ * @return Result<(), String> Ok, or an error message if the name is empty
+ *         or already taken.
+ */
+pub fn register_stage<F>(name: &str, factory: F) -> Result<(), String>
+where
+    F: Fn(&StageParams) -> Result<Box<dyn Stage>, String> + Send + Sync + 'static,
+{
+    if name.trim().is_empty() {
+        return Err(String::from("Stage names cannot be empty"));
+    }
+    let mut stages = registry().write().unwrap_or_else(|e| e.into_inner());
+    if stages.contains_key(name) {
+        return Err(format!("A stage named '{}' is already registered", name));
+    }
+    stages.insert(name.to_string(), Arc::new(factory));
+    log_debug!("registry:register_stage", "Registered stage '{}'.", name);
+    Ok(())
+}
+
+/**
+ * @brief Build a registered stage.
+ *
+ * @param name Name of the stage.
+ * @param params Its parameters.
+ * @return Result<Box<dyn Stage>, String> The stage, or an error message for
+ *         an unknown name or invalid parameters.
+ */
+pub fn build_stage(name: &str, params: &StageParams) -> Result<Box<dyn Stage>, String> {
+    let factory = registry().read().unwrap_or_else(|e| e.into_inner()).get(name).cloned();
+    // Called without the lock held, so a factory may itself register or build stages
+    let factory = factory.ok_or_else(|| unknown_stage(name))?;
+    factory(params)
+}
+
+/**
+ * @brief Check that a stage is registered.
+ *
+ * @param name Name of the stage.
+ * @return Result<(), String> Ok, or an error message listing the registered names.
+ */
+pub fn check_stage_name(name: &str) -> Result<(), String> {
+    let registered = registry().read().unwrap_or_else(|e| e.into_inner()).contains_key(name);
+    if registered {
+        Ok(())
+    } else {
+        Err(unknown_stage(name))
+    }
+}
+
+/**
+ * @brief Return the names of the registered stages.
+ *
+ * @return Vec<String> The names, sorted.
+ */
+pub fn stage_names() -> Vec<String> {
+    registry().read().unwrap_or_else(|e| e.into_inner()).keys().cloned().collect()
+}
+
+/**
+ * @brief Deserialize the parameters of a stage into its parameter struct.
+ *
+ * Use #[serde(deny_unknown_fields)] on the struct to reject misspelled fields.
+ *
+ * @param params The parameters the factory was given.
+ * @return Result<P, String> The parameters, or an error message naming the
+ *         missing, unknown or invalid field.
+ */
+pub fn parse_params<P: DeserializeOwned>(params: &StageParams) -> Result<P, String> {
+    let mapping: serde_yaml::Mapping = params
+        .iter()
+        .map(|(key, value)| (serde_yaml::Value::String(key.clone()), value.clone()))
+        .collect();
+    serde_yaml::from_value(serde_yaml::Value::Mapping(mapping)).map_err(|e| e.to_string())
+}
+
+/**
+ * @brief Return the registry, registering the built-in stages on first use.
+ */
+fn registry() -> &'static RwLock<BTreeMap<String, StageFactory>> {
+    REGISTRY.get_or_init(|| {
+        let mut stages: BTreeMap<String, StageFactory> = BTreeMap::new();
+        stages.insert(String::from("filter"), Arc::new(build_filter));
+        stages.insert(String::from("enrich"), Arc::new(build_enrich));
+        stages.insert(String::from("process"), Arc::new(build_process));
+        #[cfg(feature = "wasm")]
+        stages.insert(String::from("wasm"), Arc::new(crate::wasm_plugin::build_stage));
+        RwLock::new(stages)
+    })
+}
+
+/**
+ * @brief Return the error message for a stage name that is not registered.
+ */
+fn unknown_stage(name: &str) -> String {
+    format!("unknown stage '{}' (expected one of {})", name, stage_names().join(", "))
+}
+
+/**
+ * @brief Build the "filter" stage.
+ */
+fn build_filter(params: &StageParams) -> Result<Box<dyn Stage>, String> {
+    let params: FilterParams = parse_params(params)?;
+    Ok(Box::new(FilterExpr::parse(&params.expression)?))
+}
+
+/**
+ * @brief Build the "enrich" stage; loads the lookup table.
+ */
+fn build_enrich(params: &StageParams) -> Result<Box<dyn Stage>, String> {
+    let params: EnrichParams = parse_params(params)?;
+    let policy = MissingLookupPolicy::parse(&params.missing)?;
+    Ok(Box::new(Enricher::load(&params.lookup, policy)?))
+}
+
+/**
+ * @brief Build the "process" stage.
+ */
+fn build_process(params: &StageParams) -> Result<Box<dyn Stage>, String> {
+    let params: ProcessParams = parse_params(params)?;
+    let transformations = params
+        .transformations
+        .iter()
+        .map(|rule| Transformation::parse(rule))
+        .collect::<Result<Vec<Transformation>, String>>()?;
+    let timeout = (params.timeout_ms > 0).then(|| Duration::from_millis(params.timeout_ms));
+    let processor = ItemProcessor::new(params.threshold)
+        .with_thresholds(
+            params.category_thresholds.into_iter().collect(),
+            params.tag_thresholds.into_iter().collect(),
+        )
+        .with_tag_rules(params.required_tags, params.excluded_tags)
+        .with_transformations(transformations)
+        .with_timeout(timeout);
+    Ok(Box::new(Arc::new(processor)))
+}
+
+// End of rust_sample_project/src/registry.rs
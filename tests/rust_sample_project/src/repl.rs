@@ -0,0 +1,265 @@
+// rust_sample_project/src/repl.rs
+
+// The `interactive` subcommand: loads the items of the data file and reads
+// commands from stdin, to look into why a record is classified the way it is
+// without running the whole pipeline:
+//
+//     list             the items, with status and tags
+//     show <id>        one item, with its threshold, tag rules and filter verdict
+//     filter <expr>    the items matching a filter expression (see filter.rs)
+//     process <id>     process one item as a run would and show the result
+//     save             write the items back to the data file
+//     help, quit
+//
+// Processing happens in memory only, until `save`.
+
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+use crate::data_handler::DataHandler;
+use crate::diff;
+use crate::filter::FilterExpr;
+use crate::hooks::ItemOutcome;
+use crate::item::Item;
+use crate::item_core::ThresholdRules;
+use crate::item_processor::{ItemProcessor, ProcessError, TAG_OVER_THRESHOLD};
+use crate::money::Money;
+use crate::pipeline;
+
+/// Printed before each command is read.
+const PROMPT: &str = "> ";
+
+/// Answer to `help` and to unknown commands.
+const HELP: &str = "Commands:
+  list             list the items
+  show <id>        show an item and how the processor sees it
+  filter <expr>    list the items matching a filter expression, e.g. value > 100
+  process <id>     process an item as a run would
+  save             write the items back to the data file
+  help             show this help
+  quit             leave (exit also works)
+";
+
+/**
+ * @struct Session
+ * @brief The items and processing settings of an interactive session.
+ */
+pub struct Session {
+    /// The items, as loaded and processed so far.
+    items: Vec<Item>,
+    /// Processes items for `process`.
+    processor: Arc<ItemProcessor>,
+    /// The filter_expression of the config, if set.
+    item_filter: Option<FilterExpr>,
+    /// Saves the items for `save`.
+    data_handler: DataHandler,
+    /// Whether items were processed since they were loaded or last saved.
+    unsaved: bool,
+    /// Whether quit was refused once because of unsaved changes.
+    quit_warned: bool,
+}
+
+impl Session {
+    /**
+     * @brief Constructs a session.
+     *
+     * @param items The loaded items.
+     * @param processor The processor, see pipeline::build_item_processor.
+     * @param item_filter The configured filter, see pipeline::build_item_filter.
+     * @param data_handler The data handler the items were loaded with.
+     * @return Session The session.
+     */
+    pub fn new(
+        items: Vec<Item>,
+        processor: ItemProcessor,
+        item_filter: Option<FilterExpr>,
+        data_handler: DataHandler,
+    ) -> Self {
+        Session {
+            items,
+            processor: Arc::new(processor),
+            item_filter,
+            data_handler,
+            unsaved: false,
+            quit_warned: false,
+        }
+    }
+
+    /**
+     * @brief Read and answer commands until quit or the end of the input.
+     *
+     * @param input Where commands are read from, one per line.
+     * @param output Where the prompt and answers are written.
+     * @return io::Result<()> Ok once the session ends, or the read or write error.
+     */
+    pub fn run(&mut self, input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        writeln!(output, "{} items loaded from {}. Type help for the commands.", self.items.len(), self.location())?;
+        let mut lines = input.lines();
+        loop {
+            write!(output, "{}", PROMPT)?;
+            output.flush()?;
+            let Some(line) = lines.next().transpose()? else {
+                writeln!(output)?;
+                return Ok(());
+            };
+            match self.execute(&line) {
+                Some(answer) => write!(output, "{}", answer)?,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /**
+     * @brief Answer one command.
+     *
+     * @param line The command line.
+     * @return Option<String> The answer, or None to end the session.
+     */
+    pub fn execute(&mut self, line: &str) -> Option<String> {
+        let line = line.trim();
+        let (command, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let argument = argument.trim();
+        if command != "quit" && command != "exit" {
+            self.quit_warned = false;
+        }
+        let answer = match (command, argument) {
+            ("", _) => String::new(),
+            ("list", "") => self.list(|_| true),
+            ("show", id) if !id.is_empty() => self.show(id),
+            ("filter", expression) if !expression.is_empty() => match FilterExpr::parse(expression) {
+                Ok(filter) => self.list(|item| filter.matches(item)),
+                Err(e) => format!("Invalid filter: {}\n", e),
+            },
+            ("process", id) if !id.is_empty() => self.process(id),
+            ("save", "") => self.save(),
+            ("quit" | "exit", "") if self.unsaved && !self.quit_warned => {
+                self.quit_warned = true;
+                String::from("There are processed items that are not saved; save them, or quit again to discard them.\n")
+            }
+            ("quit" | "exit", "") => return None,
+            ("help", "") => String::from(HELP),
+            _ => format!("Unknown command: {}\n{}", line, HELP),
+        };
+        Some(answer)
+    }
+
+    /**
+     * @brief Return the data file, for messages.
+     */
+    fn location(&self) -> String {
+        self.data_handler.data_source_path().display().to_string()
+    }
+
+    /**
+     * @brief Find an item by its id as written in the data file.
+     */
+    fn position(&self, id: &str) -> Option<usize> {
+        self.items.iter().position(|item| item.item_id.to_string() == id)
+    }
+
+    /**
+     * @brief Tabulate the items a predicate accepts.
+     */
+    fn list(&self, accept: impl Fn(&Item) -> bool) -> String {
+        let mut rows: Vec<[String; 5]> = vec![[
+            String::from("ID"),
+            String::from("NAME"),
+            String::from("VALUE"),
+            String::from("STATUS"),
+            String::from("TAGS"),
+        ]];
+        for item in self.items.iter().filter(|item| accept(item)) {
+            rows.push([
+                item.item_id.to_string(),
+                item.name.to_string(),
+                item.value.to_string(),
+                format!("{:?}", item.status),
+                item.tags.join(", "),
+            ]);
+        }
+        let mut text = diff::format_table(&rows);
+        text.push_str(&format!("{} of {} items.\n", rows.len() - 1, self.items.len()));
+        text
+    }
+
+    /**
+     * @brief Describe an item and what the processor would make of it.
+     */
+    fn show(&self, id: &str) -> String {
+        let Some(index) = self.position(id) else {
+            return format!("No item '{}' in {}.\n", id, self.location());
+        };
+        let item = &self.items[index];
+        let filter_verdict = match &self.item_filter {
+            Some(filter) if filter.matches(item) => "matches filter_expression",
+            Some(_) => "does not match filter_expression, so runs skip it",
+            None => "no filter_expression is set",
+        };
+        let threshold = self.processor.threshold_for(item);
+        let tag = ThresholdRules::threshold_tag(item.value, Money::from_f64_saturating(threshold));
+        let comparison = if tag == TAG_OVER_THRESHOLD { "over" } else { "within" };
+        let mut text = format!("{}\n", item);
+        text.push_str(&format!("  category:     {}\n", item.category.as_deref().unwrap_or("-")));
+        text.push_str(&format!("  created at:   {}\n", item.created_at.to_rfc3339()));
+        if let Some(processed_at) = item.processed_at {
+            text.push_str(&format!("  processed at: {}\n", processed_at.to_rfc3339()));
+        }
+        text.push_str(&format!(
+            "  threshold:    {} (the value {} is {} it, before transformations)\n",
+            threshold, item.value, comparison
+        ));
+        text.push_str(&format!(
+            "  tag rules:    {}\n",
+            if self.processor.is_selected(item) { "selected" } else { "not selected, so runs skip it" }
+        ));
+        text.push_str(&format!("  filter:       {}\n", filter_verdict));
+        if let Some(lineage) = &item.lineage {
+            text.push_str(&format!("  last stages:  {}\n", lineage.stages.join(" -> ")));
+            if !lineage.rules.is_empty() {
+                text.push_str(&format!("  last rules:   {}\n", lineage.rules.join("; ")));
+            }
+        }
+        text
+    }
+
+    /**
+     * @brief Process an item like a run would: filter, tag rules, then the processor.
+     */
+    fn process(&mut self, id: &str) -> String {
+        let Some(index) = self.position(id) else {
+            return format!("No item '{}' in {}.\n", id, self.location());
+        };
+        let item = &mut self.items[index];
+        let (outcome, detail) = if self.item_filter.as_ref().is_some_and(|filter| !filter.matches(item)) {
+            (ItemOutcome::FilteredOut, String::from("not processed: it does not match filter_expression"))
+        } else if !self.processor.is_selected(item) {
+            (ItemOutcome::SkippedByTag, String::from("not processed: it is not selected by the tag rules"))
+        } else {
+            match self.processor.process_item_with_timeout(item) {
+                Ok(()) => (ItemOutcome::Processed, String::from("processed")),
+                Err(ProcessError::Failed) => (ItemOutcome::Failed, String::from("failed: a transformation failed")),
+                Err(e @ ProcessError::Timeout(_)) => (ItemOutcome::TimedOut, format!("failed: {}", e)),
+            }
+        };
+        pipeline::record_status(item, outcome);
+        let text = format!("{}\n{}\n", detail, item);
+        self.unsaved = true;
+        text
+    }
+
+    /**
+     * @brief Write the items back to the data file.
+     */
+    fn save(&mut self) -> String {
+        let path = self.data_handler.data_source_path().to_path_buf();
+        match self.data_handler.save_items_to(&path, &self.items) {
+            Ok(()) => {
+                self.unsaved = false;
+                format!("Saved {} items to {}.\n", self.items.len(), path.display())
+            }
+            Err(e) => format!("{}\n", e),
+        }
+    }
+}
+
+// End of rust_sample_project/src/repl.rs
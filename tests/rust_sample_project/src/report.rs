@@ -0,0 +1,639 @@
+// rust_sample_project/src/report.rs
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+
+use crate::anomaly::AnomalyStats;
+use crate::dedup::DuplicateId;
+use crate::diff;
+use crate::error::PipelineError;
+use crate::hooks::ItemOutcome;
+use crate::intern::InternStats;
+use crate::invalid_records::InvalidRecord;
+use crate::item::{Item, ItemStatus};
+use crate::item_batch::ItemBatch;
+use crate::item_id::ItemId;
+use crate::join::JoinStats;
+use crate::logging::{self, LogLevel};
+use crate::messages::{self, Msg};
+use crate::rate_limit::RateLimitStats;
+use crate::sampling::SampleStats;
+use crate::shutdown;
+
+/**
+ * @struct PipelineReport
+ * @brief Summary of a single pipeline run, logged when the run finishes.
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineReport {
+    /// Id of the run, unique per run (its start time to the microsecond,
+    /// e.g. "20261015T140309123456Z"); see path_template.rs.
+    pub run_id: String,
+    /// The tenant the run was for (see the tenants of the config file), if any.
+    pub tenant: Option<String>,
+    /// When the run started.
+    pub started_at: DateTime<Utc>,
+    /// When the run finished (None while it is still in progress).
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Number of valid items read from the data source.
+    pub items_loaded: usize,
+    /// The records of the data source that were skipped as invalid, with
+    /// their file, position and source snippet.
+    pub skipped_records: Vec<InvalidRecord>,
+    /// Number of items dropped because their id was already seen.
+    pub duplicates_dropped: usize,
+    /// Ids occurring more than once in the saved items (checked unless
+    /// duplicate_id_policy is allow; refuse fails the save instead).
+    pub duplicate_ids_saved: Vec<DuplicateId>,
+    /// What interning the loaded item names saved (None when intern_names is off).
+    pub name_interning: Option<InternStats>,
+    /// What the join with the secondary dataset matched (None without a join).
+    pub join: Option<JoinStats>,
+    /// The outliers sent to the review sink (None without anomaly detection).
+    pub anomalies: Option<AnomalyStats>,
+    /// The sample of the loaded items this run handled (None for a full run).
+    pub sample: Option<SampleStats>,
+    /// Number of items processed successfully in this run.
+    pub items_processed: usize,
+    /// Number of items whose processing failed (including timeouts).
+    pub items_failed: usize,
+    /// Number of items that failed because processing exceeded the per-item timeout.
+    pub items_timed_out: usize,
+    /// Number of items left as they were because they were already processed (incremental runs).
+    pub items_already_processed: usize,
+    /// Number of items not selected by the tag rules.
+    pub items_skipped_by_tag: usize,
+    /// Number of items not matching the filter expression.
+    pub items_skipped_by_filter: usize,
+    /// Number of items skipped because they are missing from the enrichment lookup table.
+    pub items_skipped_by_lookup: usize,
+    /// Number of items a pipeline hook vetoed processing of.
+    pub items_vetoed: usize,
+    /// Throughput of the processing loop under the rate limit (None without one).
+    pub rate_limit: Option<RateLimitStats>,
+    /// Number of saved items in each status (statuses without items are left out).
+    pub items_by_status: BTreeMap<ItemStatus, usize>,
+    /// Earliest processed_at among the saved items.
+    pub first_processed_at: Option<DateTime<Utc>>,
+    /// Latest processed_at among the saved items.
+    pub last_processed_at: Option<DateTime<Utc>>,
+    /// Statistics of the saved items' values (None unless analytics is on).
+    pub value_stats: Option<ValueStats>,
+    /// The files the items (and summaries) were saved to, with placeholders resolved.
+    pub output_paths: Vec<PathBuf>,
+    /// Whether the run was stopped early by SIGINT/SIGTERM.
+    pub interrupted: bool,
+    /// Whether processing stopped at the configured max duration.
+    pub deadline_reached: bool,
+}
+
+impl PipelineReport {
+    /**
+     * @brief Constructs a new, empty report for a run starting now.
+     *
+     * @return PipelineReport A report with all counters at zero.
+     */
+    pub fn new() -> Self {
+        let started_at = Utc::now();
+        PipelineReport {
+            run_id: run_id_at(started_at),
+            tenant: None,
+            started_at,
+            finished_at: None,
+            items_loaded: 0,
+            skipped_records: Vec::new(),
+            duplicates_dropped: 0,
+            duplicate_ids_saved: Vec::new(),
+            name_interning: None,
+            join: None,
+            anomalies: None,
+            sample: None,
+            items_processed: 0,
+            items_failed: 0,
+            items_timed_out: 0,
+            items_already_processed: 0,
+            items_skipped_by_tag: 0,
+            items_skipped_by_filter: 0,
+            items_skipped_by_lookup: 0,
+            items_vetoed: 0,
+            rate_limit: None,
+            items_by_status: BTreeMap::new(),
+            first_processed_at: None,
+            last_processed_at: None,
+            value_stats: None,
+            output_paths: Vec::new(),
+            interrupted: false,
+            deadline_reached: false,
+        }
+    }
+
+    /**
+     * @brief Count one handled item under its outcome.
+     *
+     * @param outcome What happened to the item.
+     */
+    pub fn record_outcome(&mut self, outcome: ItemOutcome) {
+        match outcome {
+            ItemOutcome::AlreadyProcessed => self.items_already_processed += 1,
+            ItemOutcome::FilteredOut => self.items_skipped_by_filter += 1,
+            ItemOutcome::SkippedByTag => self.items_skipped_by_tag += 1,
+            ItemOutcome::SkippedByLookup => self.items_skipped_by_lookup += 1,
+            ItemOutcome::Vetoed => self.items_vetoed += 1,
+            ItemOutcome::Processed => self.items_processed += 1,
+            ItemOutcome::Failed => self.items_failed += 1,
+            ItemOutcome::TimedOut => {
+                self.items_failed += 1;
+                self.items_timed_out += 1;
+            }
+        }
+    }
+
+    /**
+     * @brief Complete the report once the run is over.
+     *
+     * Records the finish time, the number of items in each status and the
+     * range of processing timestamps of the given items, so the report shows
+     * where the records stand and when they went through the pipeline.
+     *
+     * @param items The items as they are about to be saved.
+     */
+    pub fn finish(&mut self, items: &[Item]) {
+        self.items_by_status.clear();
+        for item in items {
+            *self.items_by_status.entry(item.status).or_insert(0) += 1;
+        }
+        self.first_processed_at = items.iter().filter_map(|item| item.processed_at).min();
+        self.last_processed_at = items.iter().filter_map(|item| item.processed_at).max();
+        self.finished_at = Some(Utc::now());
+    }
+
+    /**
+     * @brief Classify a completed run by whether all processed items succeeded.
+     *
+     * @return Result<(), PipelineError> Ok if no item failed, or
+     *         PipelineError::PartialProcessing with the failure counts.
+     */
+    pub fn outcome(&self) -> Result<(), PipelineError> {
+        if self.items_failed == 0 {
+            return Ok(());
+        }
+        Err(PipelineError::PartialProcessing {
+            failed: self.items_failed,
+            attempted: self.items_processed + self.items_failed,
+        })
+    }
+
+    /**
+     * @brief Check that the counters and timestamps of the report fit together.
+     *
+     * Every item handled in the run was loaded and not dropped as a duplicate
+     * (a resumed run handles fewer), and the timestamps are in order.
+     *
+     * @return bool True if the report is internally consistent.
+     */
+    pub fn is_consistent(&self) -> bool {
+        let handled = self.items_processed
+            + self.items_failed
+            + self.items_already_processed
+            + self.items_skipped_by_tag
+            + self.items_skipped_by_filter
+            + self.items_skipped_by_lookup
+            + self.items_vetoed;
+        let counts_ok = self.items_timed_out <= self.items_failed
+            && self.duplicates_dropped <= self.items_loaded
+            && handled <= self.items_loaded - self.duplicates_dropped;
+        let finish_ok = self.finished_at.is_none_or(|finished| finished >= self.started_at);
+        let range_ok = match (self.first_processed_at, self.last_processed_at) {
+            (Some(first), Some(last)) => first <= last,
+            (None, None) => true,
+            _ => false,
+        };
+        counts_ok && finish_ok && range_ok
+    }
+
+    /**
+     * @brief Log the report as a short multi-line summary, in the current locale.
+     *
+     * The summary is logged whatever the minimum log level, so quiet runs
+     * still end with it.
+     */
+    pub fn log_summary(&self) {
+        let module = "report:log_summary";
+        for line in self.summary_lines() {
+            logging::log(LogLevel::Info, module, &line);
+        }
+        if self.interrupted {
+            logging::log(LogLevel::Warning, module, &tr!(ReportInterrupted));
+        }
+        if self.deadline_reached {
+            logging::log(LogLevel::Warning, module, &tr!(ReportDeadlineReached));
+        }
+    }
+
+    /**
+     * @brief Return the summary lines of the report, in the current locale.
+     *
+     * @return Vec<String> One "label value" line per figure, with the values
+     *         lined up, as logged by log_summary.
+     */
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines: Vec<(Msg, String)> = vec![
+            (Msg::ReportRunId, self.run_id.clone()),
+            (Msg::ReportStartedAt, format_timestamp(Some(self.started_at))),
+            (Msg::ReportFinishedAt, format_timestamp(self.finished_at)),
+            (Msg::ReportItemsLoaded, self.items_loaded.to_string()),
+            (Msg::ReportDuplicatesDropped, self.duplicates_dropped.to_string()),
+        ];
+        if !self.skipped_records.is_empty() {
+            lines.insert(4, (Msg::ReportRecordsSkipped, self.skipped_records.len().to_string()));
+        }
+        if let Some(tenant) = &self.tenant {
+            lines.insert(1, (Msg::ReportTenant, tenant.clone()));
+        }
+        if !self.duplicate_ids_saved.is_empty() {
+            lines.push((Msg::ReportDuplicateIdsSaved, self.duplicate_ids_saved.len().to_string()));
+        }
+        if let Some(stats) = &self.join {
+            lines.push((
+                Msg::ReportJoin,
+                tr!(JoinValue, stats.matched, stats.unmatched.len(), stats.dropped, stats.unused.len()),
+            ));
+        }
+        if let Some(stats) = &self.anomalies {
+            lines.push((
+                Msg::ReportAnomalies,
+                tr!(AnomaliesValue, stats.items, format!("{:.2}", stats.lower), format!("{:.2}", stats.upper)),
+            ));
+        }
+        if let Some(stats) = &self.sample {
+            lines.push((Msg::ReportSample, tr!(SampleValue, stats.items, stats.of_items, stats.seed)));
+        }
+        if let Some(stats) = &self.name_interning {
+            lines.push((
+                Msg::ReportNamesInterned,
+                tr!(NamesInternedValue, stats.distinct_names, stats.names, format_bytes(stats.bytes_saved)),
+            ));
+        }
+        lines.push((Msg::ReportItemsProcessed, self.items_processed.to_string()));
+        lines.push((Msg::ReportItemsFailed, self.items_failed.to_string()));
+        if self.items_timed_out > 0 {
+            lines.push((Msg::ReportTimedOut, self.items_timed_out.to_string()));
+        }
+        if self.items_already_processed > 0 {
+            lines.push((Msg::ReportAlreadyProcessed, self.items_already_processed.to_string()));
+        }
+        lines.push((Msg::ReportSkippedByTag, self.items_skipped_by_tag.to_string()));
+        lines.push((Msg::ReportFilteredOut, self.items_skipped_by_filter.to_string()));
+        if self.items_skipped_by_lookup > 0 {
+            lines.push((Msg::ReportWithoutLookup, self.items_skipped_by_lookup.to_string()));
+        }
+        if self.items_vetoed > 0 {
+            lines.push((Msg::ReportVetoed, self.items_vetoed.to_string()));
+        }
+        if let Some(stats) = &self.rate_limit {
+            lines.push((
+                Msg::ReportRateLimit,
+                tr!(
+                    RateLimitValue,
+                    format!("{:.1}", stats.limit_per_sec),
+                    format!("{:.1}", stats.effective_per_sec),
+                    format!("{:.1}", stats.throttled_secs)
+                ),
+            ));
+        }
+        let by_status: Vec<String> = self
+            .items_by_status
+            .iter()
+            .map(|(status, count)| format!("{} {}", status, count))
+            .collect();
+        let by_status = if by_status.is_empty() { String::from("-") } else { by_status.join(", ") };
+        lines.push((Msg::ReportByStatus, by_status));
+        if let Some(stats) = &self.value_stats {
+            let decimals = stats.decimals as usize;
+            lines.push((
+                Msg::ReportValues,
+                tr!(
+                    ValueStatsValue,
+                    format!("{:.*}", decimals, stats.total),
+                    format!("{:.*}", decimals, stats.mean),
+                    format!("{:.*}", decimals, stats.min),
+                    format!("{:.*}", decimals, stats.max)
+                ),
+            ));
+            lines.push((Msg::ReportOverThreshold, stats.over_threshold.to_string()));
+        }
+        lines.push((Msg::ReportFirstProcessed, format_timestamp(self.first_processed_at)));
+        lines.push((Msg::ReportLastProcessed, format_timestamp(self.last_processed_at)));
+        if !self.output_paths.is_empty() {
+            let paths: Vec<String> = self.output_paths.iter().map(|path| path.display().to_string()).collect();
+            lines.push((Msg::ReportSavedTo, paths.join(", ")));
+        }
+
+        // Values line up after the longest label of the locale
+        let width = lines.iter().map(|(label, _)| messages::text(*label).chars().count()).max().unwrap_or(0);
+        lines
+            .iter()
+            .map(|(label, value)| {
+                let label = messages::text(*label);
+                let padding = width - label.chars().count();
+                format!("{}{} {}", label, " ".repeat(padding), value)
+            })
+            .collect()
+    }
+}
+
+impl Default for PipelineReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+ * @enum RunStatus
+ * @brief How a run ended, as given in RunResult.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    /// Every item was processed and saved.
+    Succeeded,
+    /// The run failed, or some items failed to process.
+    Failed,
+    /// The run was stopped by SIGINT/SIGTERM; what was done is saved.
+    Interrupted,
+}
+
+/**
+ * @struct ValueStats
+ * @brief Aggregates of the item values, for analytics mode.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ValueStats {
+    /// Number of items.
+    pub count: usize,
+    /// Sum of the values.
+    pub total: f64,
+    /// Mean of the values.
+    pub mean: f64,
+    /// Smallest value.
+    pub min: f64,
+    /// Largest value.
+    pub max: f64,
+    /// Number of items tagged over their threshold.
+    pub over_threshold: usize,
+    /// Decimal places the figures are shown with (value_decimals).
+    #[serde(skip)]
+    pub decimals: u32,
+}
+
+impl ValueStats {
+    /**
+     * @brief Aggregate the value column of a batch of items.
+     *
+     * The values are folded in LANES independent lanes combined at the end,
+     * which the compiler turns into SIMD adds and compares; the total may
+     * therefore differ from a left-to-right sum in the last bits.
+     *
+     * @param batch The items, in columns.
+     * @param decimals Decimal places the figures are shown with.
+     * @return Option<ValueStats> The statistics, or None for an empty batch.
+     */
+    pub fn of(batch: &ItemBatch, decimals: u32) -> Option<ValueStats> {
+        const LANES: usize = 8;
+        if batch.is_empty() {
+            return None;
+        }
+        let mut sums = [0.0; LANES];
+        let mut mins = [f64::INFINITY; LANES];
+        let mut maxs = [f64::NEG_INFINITY; LANES];
+        let chunks = batch.values().chunks_exact(LANES);
+        let remainder = chunks.remainder();
+        for chunk in chunks {
+            for lane in 0..LANES {
+                sums[lane] += chunk[lane];
+                mins[lane] = mins[lane].min(chunk[lane]);
+                maxs[lane] = maxs[lane].max(chunk[lane]);
+            }
+        }
+        for (lane, value) in remainder.iter().enumerate() {
+            sums[lane] += value;
+            mins[lane] = mins[lane].min(*value);
+            maxs[lane] = maxs[lane].max(*value);
+        }
+        let total: f64 = sums.iter().sum();
+        Some(ValueStats {
+            count: batch.len(),
+            total,
+            mean: total / batch.len() as f64,
+            min: mins.iter().copied().fold(f64::INFINITY, f64::min),
+            max: maxs.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            over_threshold: batch.over_threshold().count_ones(),
+            decimals,
+        })
+    }
+}
+
+/**
+ * @struct ItemResult
+ * @brief The outcome of one item, as given in RunResult.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ItemResult {
+    /// The item.
+    pub item_id: ItemId,
+    /// What happened to it.
+    pub outcome: ItemOutcome,
+    /// Its status after the run.
+    pub status: ItemStatus,
+}
+
+/**
+ * @struct RunError
+ * @brief A failure of the run, as given in RunResult.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunError {
+    /// The class of failure, see PipelineError::kind.
+    pub kind: &'static str,
+    /// The error message.
+    pub message: String,
+}
+
+/**
+ * @struct RunResult
+ * @brief The final result of a run as one machine-readable document
+ *        (the binary's --output json).
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct RunResult {
+    /// How the run ended.
+    pub status: RunStatus,
+    /// The process exit code belonging to the result.
+    pub exit_code: i32,
+    /// The run's report (None if the run failed before completing).
+    pub report: Option<PipelineReport>,
+    /// The outcome of every item handled, in item order.
+    pub items: Vec<ItemResult>,
+    /// Why the run failed (empty if it succeeded or was interrupted).
+    pub errors: Vec<RunError>,
+}
+
+impl RunResult {
+    /**
+     * @brief Constructs the result of a completed or failed run.
+     *
+     * @param result What run_processing_pipeline returned.
+     * @param items The outcomes of the items handled.
+     * @return RunResult The result, classified like the binary's exit code.
+     */
+    pub fn new(result: Result<PipelineReport, PipelineError>, items: Vec<ItemResult>) -> Self {
+        let (status, exit_code, report, error) = match result {
+            Ok(report) if report.interrupted => {
+                (RunStatus::Interrupted, shutdown::EXIT_INTERRUPTED, Some(report), None)
+            }
+            Ok(report) => match report.outcome() {
+                Ok(()) => (RunStatus::Succeeded, 0, Some(report), None),
+                Err(e) => (RunStatus::Failed, e.exit_code(), Some(report), Some(e)),
+            },
+            Err(e) => (RunStatus::Failed, e.exit_code(), None, Some(e)),
+        };
+        RunResult {
+            status,
+            exit_code,
+            report,
+            items,
+            errors: error
+                .map(|e| RunError {
+                    kind: e.kind(),
+                    message: e.to_string(),
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+/**
+ * @struct PipelineRunResult
+ * @brief The result of one of several pipelines run together (--pipelines).
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineRunResult {
+    /// The pipeline's name in the config file.
+    pub name: String,
+    /// How its run ended.
+    #[serde(flatten)]
+    pub result: RunResult,
+}
+
+/**
+ * @struct MultiRunResult
+ * @brief The results of several pipelines run together, as one document.
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct MultiRunResult {
+    /// The process exit code for all of them, see MultiRunResult::new.
+    pub exit_code: i32,
+    /// The result of every pipeline, in the order they were selected.
+    pub pipelines: Vec<PipelineRunResult>,
+}
+
+impl MultiRunResult {
+    /**
+     * @brief Constructs the combined result of several pipelines.
+     *
+     * @param pipelines The result of every pipeline.
+     * @return MultiRunResult The result; its exit code is 130 if any pipeline
+     *         was interrupted, otherwise that of the first pipeline that did
+     *         not succeed, or 0 if all succeeded.
+     */
+    pub fn new(pipelines: Vec<PipelineRunResult>) -> Self {
+        let exit_code = if pipelines.iter().any(|pipeline| pipeline.result.status == RunStatus::Interrupted) {
+            shutdown::EXIT_INTERRUPTED
+        } else {
+            pipelines
+                .iter()
+                .map(|pipeline| pipeline.result.exit_code)
+                .find(|exit_code| *exit_code != 0)
+                .unwrap_or(0)
+        };
+        MultiRunResult { exit_code, pipelines }
+    }
+
+    /**
+     * @brief Lay out the results as a table, one line per pipeline.
+     *
+     * @return String The table.
+     */
+    pub fn to_table(&self) -> String {
+        let mut rows: Vec<[String; 6]> = vec![[
+            String::from("PIPELINE"),
+            String::from("STATUS"),
+            String::from("LOADED"),
+            String::from("PROCESSED"),
+            String::from("FAILED"),
+            String::from("EXIT CODE"),
+        ]];
+        for pipeline in &self.pipelines {
+            let report = pipeline.result.report.as_ref();
+            let count = |count: fn(&PipelineReport) -> usize| report.map_or(String::from("-"), |r| count(r).to_string());
+            rows.push([
+                pipeline.name.clone(),
+                format!("{:?}", pipeline.result.status).to_lowercase(),
+                count(|report| report.items_loaded),
+                count(|report| report.items_processed),
+                count(|report| report.items_failed),
+                pipeline.result.exit_code.to_string(),
+            ]);
+        }
+        diff::format_table(&rows)
+    }
+}
+
+/**
+ * @brief Return the id of a run started at the given time.
+ *
+ * @param started_at When the run started.
+ * @return String The start time to the microsecond, e.g. "20261015T140309123456Z".
+ */
+pub fn run_id_at(started_at: DateTime<Utc>) -> String {
+    started_at.format("%Y%m%dT%H%M%S%6fZ").to_string()
+}
+
+/**
+ * @brief Format an optional timestamp for the summary, "-" when absent.
+ *
+ * @param timestamp The timestamp to format.
+ * @return String The RFC 3339 timestamp or "-".
+ */
+fn format_timestamp(timestamp: Option<DateTime<Utc>>) -> String {
+    match timestamp {
+        Some(ts) => ts.to_rfc3339_opts(SecondsFormat::Secs, true),
+        None => String::from("-"),
+    }
+}
+
+/**
+ * @brief Format a byte count for the summary, e.g. "512 B", "3.4 KiB" or "1.2 MiB".
+ *
+ * @param bytes The number of bytes.
+ * @return String The count in the largest unit it reaches.
+ */
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut amount = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while amount >= 1024.0 && unit < UNITS.len() - 1 {
+        amount /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", amount, UNITS[unit])
+}
+
+// End of rust_sample_project/src/report.rs
@@ -0,0 +1,147 @@
+// rust_sample_project/src/router.rs
+
+use std::path::{Path, PathBuf};
+
+use crate::filter::FilterExpr;
+use crate::item::Item;
+
+/**
+ * @struct Route
+ * @brief Directs the items matching a condition to a sink file.
+ */
+#[derive(Debug, Clone)]
+pub struct Route {
+    /// Path (or URL) of the JSON file the matching items are written to.
+    pub sink: PathBuf,
+    /// Which items belong to this sink (filter expression syntax).
+    pub condition: FilterExpr,
+}
+
+/**
+ * @struct OutputRouter
+ * @brief Splits processed items across output sinks.
+ *
+ * Routes are checked in order and the first matching one wins; items that
+ * match no route go to the default sink. Processing decisions are visible to
+ * the routes through the tags ItemProcessor attaches, e.g.
+ * `tags contains 'over_threshold'`.
+ */
+#[derive(Debug, Clone)]
+pub struct OutputRouter {
+    routes: Vec<Route>,
+    default_sink: PathBuf,
+}
+
+impl OutputRouter {
+    /**
+     * @brief Constructs a router sending every item to a single sink.
+     *
+     * @param default_sink Path receiving the items no route matches.
+     * @return OutputRouter A router without routes.
+     */
+    pub fn new(default_sink: &Path) -> Self {
+        OutputRouter {
+            routes: Vec::new(),
+            default_sink: default_sink.to_path_buf(),
+        }
+    }
+
+    /**
+     * @brief Add routes given as (sink path, condition) pairs, as in config.
+     *
+     * @param rules The route definitions, checked in the given order.
+     * @return Result<OutputRouter, String> The router, or an error message if a
+     *         condition is not a valid filter expression.
+     */
+    pub fn with_routes(mut self, rules: &[(&str, &str)]) -> Result<Self, String> {
+        for (sink, condition) in rules {
+            let condition = FilterExpr::parse(condition)
+                .map_err(|e| format!("Invalid output route for '{}': {}", sink, e))?;
+            log_info!("router:with_routes", "Routing items matching {} to {}.", condition, sink);
+            self.routes.push(Route {
+                sink: PathBuf::from(sink),
+                condition,
+            });
+        }
+        Ok(self)
+    }
+
+    /**
+     * @brief Add a route checked before all others.
+     *
+     * @param sink Path (or URL) of the file the matching items are written to.
+     * @param condition Which items belong to this sink.
+     * @return OutputRouter The router with the route in front.
+     */
+    pub fn with_leading_route(mut self, sink: &Path, condition: FilterExpr) -> Self {
+        log_info!("router:with_leading_route", "Routing items matching {} to {}.", condition, sink.display());
+        self.routes.insert(
+            0,
+            Route {
+                sink: sink.to_path_buf(),
+                condition,
+            },
+        );
+        self
+    }
+
+    /**
+     * @brief Return the sink an item is written to.
+     *
+     * @param item The item to route.
+     * @return &Path The path of the first matching route's sink, or the default sink.
+     */
+    pub fn sink_for(&self, item: &Item) -> &Path {
+        self.routes
+            .iter()
+            .find(|route| route.condition.matches(item))
+            .map_or(self.default_sink.as_path(), |route| route.sink.as_path())
+    }
+
+    /**
+     * @brief Return the configured sinks.
+     *
+     * @return Vec<&Path> The sinks in route order followed by the default
+     *         sink, each once.
+     */
+    pub fn sinks(&self) -> Vec<&Path> {
+        let mut sinks: Vec<&Path> = Vec::new();
+        let configured = self
+            .routes
+            .iter()
+            .map(|route| route.sink.as_path())
+            .chain(std::iter::once(self.default_sink.as_path()));
+        for sink in configured {
+            if !sinks.contains(&sink) {
+                sinks.push(sink);
+            }
+        }
+        sinks
+    }
+
+    /**
+     * @brief Group items by the sink they are routed to.
+     *
+     * Every configured sink is present in the result, even if no item is
+     * routed to it, so that stale contents from an earlier run are replaced.
+     * Sinks appear in route order followed by the default sink; items keep
+     * their relative order within a sink.
+     *
+     * @param items The processed items.
+     * @return Vec<(PathBuf, Vec<Item>)> The sink paths with their items.
+     */
+    pub fn route(&self, items: &[Item]) -> Vec<(PathBuf, Vec<Item>)> {
+        let mut batches: Vec<(PathBuf, Vec<Item>)> =
+            self.sinks().into_iter().map(|sink| (sink.to_path_buf(), Vec::new())).collect();
+
+        for item in items {
+            let sink = self.sink_for(item);
+            if let Some((_, batch)) = batches.iter_mut().find(|(existing, _)| existing == sink) {
+                batch.push(item.clone());
+            }
+        }
+        batches
+    }
+}
+
+// End of rust_sample_project/src/router.rs
@@ -0,0 +1,126 @@
+// rust_sample_project/src/s3_store.rs
+
+// Items data files in S3 or an S3-compatible object store, addressed as
+// s3://bucket/key. Everything comes from the standard environment variables:
+//
+//     AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY, AWS_SESSION_TOKEN  credentials
+//     AWS_REGION (or AWS_DEFAULT_REGION)                           region, default us-east-1
+//     AWS_ENDPOINT_URL                                             endpoint of an S3-compatible
+//                                                                  store (path-style addressing)
+
+use std::env;
+
+use s3::creds::Credentials;
+use s3::{Bucket, Region};
+
+use crate::storage::ItemStore;
+
+/// Region used when neither AWS_REGION nor AWS_DEFAULT_REGION is set.
+const DEFAULT_REGION: &str = "us-east-1";
+
+/**
+ * @struct S3Store
+ * @brief An items data file stored as an object in an S3 bucket.
+ */
+pub struct S3Store {
+    location: String,
+    bucket: Box<Bucket>,
+    key: String,
+}
+
+impl S3Store {
+    /**
+     * @brief Constructs a store for an s3://bucket/key location.
+     *
+     * @param location The object location, e.g. "s3://my-bucket/data/items.json".
+     * @return Result<S3Store, String> The store, or an error message if the
+     *         location is malformed or the credentials/region are unusable.
+     */
+    pub fn from_location(location: &str) -> Result<Self, String> {
+        let (bucket_name, key) = location
+            .strip_prefix("s3://")
+            .and_then(|rest| rest.split_once('/'))
+            .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+            .ok_or_else(|| format!("Invalid S3 location '{}' (expected s3://bucket/key)", location))?;
+
+        let credentials = Credentials::from_env()
+            .map_err(|e| format!("Failed to read S3 credentials from the environment: {}", e))?;
+        let region_name = env::var("AWS_REGION")
+            .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|_| DEFAULT_REGION.to_string());
+        let custom_endpoint = env::var("AWS_ENDPOINT_URL").ok();
+        let region = match &custom_endpoint {
+            Some(endpoint) => Region::Custom {
+                region: region_name,
+                endpoint: endpoint.clone(),
+            },
+            None => region_name
+                .parse()
+                .map_err(|e| format!("Invalid S3 region '{}': {}", region_name, e))?,
+        };
+
+        let mut bucket = Bucket::new(bucket_name, region, credentials)
+            .map_err(|e| format!("Failed to open S3 bucket '{}': {}", bucket_name, e))?;
+        if custom_endpoint.is_some() {
+            bucket = bucket.with_path_style();
+        }
+
+        Ok(S3Store {
+            location: location.to_string(),
+            bucket,
+            key: key.to_string(),
+        })
+    }
+}
+
+impl ItemStore for S3Store {
+    fn location(&self) -> &str {
+        &self.location
+    }
+
+    fn exists(&self) -> Result<bool, String> {
+        let (_, status) = self
+            .bucket
+            .head_object(&self.key)
+            .map_err(|e| format!("Failed to look up '{}': {}", self.location, e))?;
+        match status {
+            200..=299 => Ok(true),
+            404 => Ok(false),
+            other => Err(format!("Failed to look up '{}': status {}", self.location, other)),
+        }
+    }
+
+    fn read(&self) -> Result<Vec<u8>, String> {
+        log_info!("s3_store:read", "Downloading {}...", self.location);
+        let response = self
+            .bucket
+            .get_object(&self.key)
+            .map_err(|e| format!("Failed to read data file '{}': {}", self.location, e))?;
+        if response.status_code() != 200 {
+            return Err(format!(
+                "Failed to read data file '{}': status {}",
+                self.location,
+                response.status_code()
+            ));
+        }
+        Ok(response.as_slice().to_vec())
+    }
+
+    fn write(&self, contents: &[u8]) -> Result<(), String> {
+        log_info!("s3_store:write", "Uploading {} bytes to {}...", contents.len(), self.location);
+        let response = self
+            .bucket
+            .put_object(&self.key, contents)
+            .map_err(|e| format!("Failed to write data file '{}': {}", self.location, e))?;
+        if !(200..300).contains(&response.status_code()) {
+            return Err(format!(
+                "Failed to write data file '{}': status {}",
+                self.location,
+                response.status_code()
+            ));
+        }
+        Ok(())
+    }
+}
+
+// End of rust_sample_project/src/s3_store.rs
@@ -0,0 +1,117 @@
+// rust_sample_project/src/sampling.rs
+
+// Sample runs (see the sample_size, sample_percent and sample_seed config
+// keys, and --sample / --sample-percent), for smoke-testing rule changes on
+// big datasets without waiting for a full run. A sample run processes a
+// random subset of the loaded items; the other items are saved unchanged.
+//
+// The subset is drawn from a generator seeded with sample_seed, so runs with
+// the same seed over the same data pick the same items, and a sample run
+// can be resumed (--resume) like any other.
+
+use rand::rngs::StdRng;
+use rand::seq::index;
+use rand::SeedableRng;
+use serde::Serialize;
+
+use crate::item::Item;
+
+/**
+ * @struct SampleStats
+ * @brief Which part of the loaded items a sample run processed, for the run report.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SampleStats {
+    /// Number of items in the sample.
+    pub items: usize,
+    /// Number of items the sample was drawn from.
+    pub of_items: usize,
+    /// Seed the sample was drawn with.
+    pub seed: u64,
+}
+
+/**
+ * @struct Sample
+ * @brief The items a sample run sets aside, to be saved unchanged.
+ */
+#[derive(Debug)]
+pub struct Sample {
+    /// The items not drawn, with their positions in the input.
+    set_aside: Vec<(usize, Item)>,
+    /// What the sample covers.
+    pub stats: SampleStats,
+}
+
+/**
+ * @brief Return how many items a sample of the configured size holds.
+ *
+ * @param total Number of items to sample from.
+ * @param size The configured number of items (0 = not set).
+ * @param percent The configured percentage of the items (0 = not set).
+ * @return Option<usize> The sample size, or None for a full run.
+ */
+pub fn sample_size(total: usize, size: usize, percent: f64) -> Option<usize> {
+    if size > 0 {
+        Some(size.min(total))
+    } else if percent > 0.0 {
+        Some(((total as f64 * percent / 100.0).ceil() as usize).min(total))
+    } else {
+        None
+    }
+}
+
+/**
+ * @brief Draw a sample of items.
+ *
+ * @param items The loaded items, in input order.
+ * @param size Number of items to draw, see sample_size.
+ * @param seed Seed of the random generator.
+ * @return (Vec<Item>, Sample) The drawn items, in input order, and the
+ *         items set aside.
+ */
+pub fn draw(items: Vec<Item>, size: usize, seed: u64) -> (Vec<Item>, Sample) {
+    let total = items.len();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut selected = vec![false; total];
+    for position in index::sample(&mut rng, total, size.min(total)) {
+        selected[position] = true;
+    }
+    let mut sampled = Vec::with_capacity(size);
+    let mut set_aside = Vec::with_capacity(total - size.min(total));
+    for (position, item) in items.into_iter().enumerate() {
+        if selected[position] {
+            sampled.push(item);
+        } else {
+            set_aside.push((position, item));
+        }
+    }
+    let stats = SampleStats {
+        items: sampled.len(),
+        of_items: total,
+        seed,
+    };
+    (sampled, Sample { set_aside, stats })
+}
+
+impl Sample {
+    /**
+     * @brief Put the sampled items back among the items set aside.
+     *
+     * @param sampled The sampled items after processing, in input order.
+     * @return Vec<Item> All items, in input order.
+     */
+    pub fn merge(self, sampled: Vec<Item>) -> Vec<Item> {
+        let mut merged = Vec::with_capacity(sampled.len() + self.set_aside.len());
+        let mut sampled = sampled.into_iter();
+        for (position, item) in self.set_aside {
+            while merged.len() < position {
+                merged.push(sampled.next().expect("a sample holds the items not set aside"));
+            }
+            merged.push(item);
+        }
+        merged.extend(sampled);
+        merged
+    }
+}
+
+// End of rust_sample_project/src/sampling.rs
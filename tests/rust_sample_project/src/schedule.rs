@@ -0,0 +1,392 @@
+// rust_sample_project/src/schedule.rs
+
+// Cron schedules for the `schedule` mode (see the schedule and
+// schedule_missed_runs config keys), which runs the pipeline at the times a
+// cron expression names instead of needing an external cron wrapper:
+//
+//     schedule = "0 */6 * * *"    # at minute 0 of every sixth hour
+//
+// Expressions have the five usual fields, minute, hour, day of month, month
+// and day of week, each "*", a number, a range "a-b", a list "a,b" or a step
+// "*/n" / "a-b/n"; months and days of week may be given by their English
+// abbreviations (JAN, MON, ...) and Sunday is 0 or 7. As in cron, a time
+// matches if its day of month or its day of week matches when both fields are
+// restricted. @hourly, @daily (@midnight), @weekly, @monthly and @yearly
+// (@annually) are accepted as well. Times are UTC.
+//
+// Runs never overlap: a scheduled time that passes while a run is still in
+// progress, or while the process is not running, is a missed run, handled
+// by the missed run policy.
+
+use std::fmt;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+
+/// Number of steps after which a search for the next matching time gives up
+/// (enough to reach Feb 29 from anywhere).
+const MAX_SEARCH_STEPS: usize = 100_000;
+
+/// Missed runs are counted up to this many.
+const MAX_COUNTED_MISSED_RUNS: usize = 10_000;
+
+/// Month abbreviations, JAN = 1.
+const MONTH_NAMES: [&str; 12] = ["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+
+/// Day of week abbreviations, SUN = 0.
+const WEEKDAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/**
+ * @enum MissedRunPolicy
+ * @brief What to do about scheduled runs that were missed.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissedRunPolicy {
+    /// Log the missed runs and wait for the next scheduled time ("skip").
+    Skip,
+    /// Make up for the missed runs with a single run right away ("run_once").
+    RunOnce,
+}
+
+impl MissedRunPolicy {
+    /**
+     * @brief Parse a policy name as used in config.
+     *
+     * @param name "skip" or "run_once".
+     * @return Result<MissedRunPolicy, String> The policy, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<MissedRunPolicy, String> {
+        match name {
+            "skip" => Ok(MissedRunPolicy::Skip),
+            "run_once" => Ok(MissedRunPolicy::RunOnce),
+            other => Err(format!("Unknown missed run policy '{}' (expected 'skip' or 'run_once')", other)),
+        }
+    }
+}
+
+/**
+ * @struct CronField
+ * @brief The values one field of a cron expression matches, as a bit set.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CronField {
+    bits: u64,
+    /// Whether the field starts with "*"; as in cron, this decides how the
+    /// day of month and day of week fields combine.
+    any: bool,
+}
+
+impl CronField {
+    fn matches(&self, value: u32) -> bool {
+        self.bits & (1 << value) != 0
+    }
+
+    /**
+     * @brief Parse one field.
+     *
+     * @param text The field.
+     * @param name The field's name, for error messages.
+     * @param min The lowest value.
+     * @param max The highest value.
+     * @param names Names of the values from min on, if the field takes names.
+     * @return Result<CronField, String> The field, or an error message.
+     */
+    fn parse(text: &str, name: &str, min: u32, max: u32, names: &[&str]) -> Result<CronField, String> {
+        let value = |part: &str| -> Result<u32, String> {
+            let named = names.iter().position(|candidate| candidate.eq_ignore_ascii_case(part));
+            match named {
+                Some(index) => Ok(min + index as u32),
+                None => part
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|value| (min..=max).contains(value))
+                    .ok_or_else(|| format!("invalid {} '{}' (expected {} to {})", name, part, min, max)),
+            }
+        };
+        let mut field = CronField { bits: 0, any: text.starts_with('*') };
+        for item in text.split(',') {
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => match step.parse::<u32>() {
+                    Ok(step) if step > 0 => (range, step),
+                    _ => return Err(format!("invalid step '{}' in {} '{}'", step, name, text)),
+                },
+                None => (item, 1),
+            };
+            let (first, last) = match range {
+                "*" => (min, max),
+                _ => match range.split_once('-') {
+                    Some((first, last)) => (value(first)?, value(last)?),
+                    // "a/n" runs from a to the end of the range
+                    None if item.contains('/') => (value(range)?, max),
+                    None => (value(range)?, value(range)?),
+                },
+            };
+            if first > last {
+                return Err(format!("invalid range '{}' in {} '{}'", range, name, text));
+            }
+            for value in (first..=last).step_by(step as usize) {
+                field.bits |= 1 << value;
+            }
+        }
+        Ok(field)
+    }
+}
+
+/**
+ * @struct CronSchedule
+ * @brief The times a cron expression names.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    /// The expression as configured.
+    expression: String,
+    minutes: CronField,
+    hours: CronField,
+    days_of_month: CronField,
+    months: CronField,
+    /// Days of week, Sunday = 0.
+    days_of_week: CronField,
+}
+
+impl CronSchedule {
+    /**
+     * @brief Parse a cron expression.
+     *
+     * @param expression Five fields, or one of the @ shorthands.
+     * @return Result<CronSchedule, String> The schedule, or an error message
+     *         naming the invalid field; an expression that never matches
+     *         (e.g. "0 0 31 2 *") is an error too.
+     */
+    pub fn parse(expression: &str) -> Result<CronSchedule, String> {
+        let fields = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let parts: Vec<&str> = fields.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = parts[..] else {
+            return Err(format!(
+                "invalid cron expression '{}' (expected 5 fields: minute hour day-of-month month day-of-week)",
+                expression
+            ));
+        };
+        let mut days_of_week = CronField::parse(days_of_week, "day of week", 0, 7, &WEEKDAY_NAMES)?;
+        // Sunday is both 0 and 7
+        if days_of_week.matches(7) {
+            days_of_week.bits |= 1;
+        }
+        let schedule = CronSchedule {
+            expression: expression.trim().to_string(),
+            minutes: CronField::parse(minutes, "minute", 0, 59, &[])?,
+            hours: CronField::parse(hours, "hour", 0, 23, &[])?,
+            days_of_month: CronField::parse(days_of_month, "day of month", 1, 31, &[])?,
+            months: CronField::parse(months, "month", 1, 12, &MONTH_NAMES)?,
+            days_of_week,
+        };
+        let epoch = Utc.timestamp_opt(0, 0).single().unwrap_or_default();
+        if schedule.next_after(epoch).is_none() {
+            return Err(format!("cron expression '{}' never matches", expression));
+        }
+        Ok(schedule)
+    }
+
+    /**
+     * @brief Return the expression as configured.
+     *
+     * @return &str The expression.
+     */
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /**
+     * @brief Check whether a day matches the day of month and day of week fields.
+     */
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day_of_month = self.days_of_month.matches(date.day());
+        let day_of_week = self.days_of_week.matches(date.weekday().num_days_from_sunday());
+        match (self.days_of_month.any, self.days_of_week.any) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+
+    /**
+     * @brief Return the first scheduled time after a given time.
+     *
+     * @param after The time to search from (exclusive).
+     * @return Option<DateTime<Utc>> The next scheduled time, or None if the
+     *         expression never matches.
+     */
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.naive_utc().with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        for _ in 0..MAX_SEARCH_STEPS {
+            let date = time.date();
+            if !self.months.matches(date.month()) {
+                let (year, month) = match date.month() {
+                    12 => (date.year() + 1, 1),
+                    month => (date.year(), month + 1),
+                };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_time(NaiveTime::MIN);
+            } else if !self.matches_day(date) {
+                time = date.succ_opt()?.and_time(NaiveTime::MIN);
+            } else if !self.hours.matches(time.hour()) {
+                time = date.and_hms_opt(time.hour(), 0, 0)? + Duration::hours(1);
+            } else if !self.minutes.matches(time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(Utc.from_utc_datetime(&time));
+            }
+        }
+        None
+    }
+
+    /**
+     * @brief Count the scheduled times in a period.
+     *
+     * @param after Start of the period (exclusive).
+     * @param until End of the period (inclusive).
+     * @return (usize, Option<DateTime<Utc>>) The number of scheduled times
+     *         (counted up to 10000) and the last of them, if any.
+     */
+    pub fn times_between(&self, after: DateTime<Utc>, until: DateTime<Utc>) -> (usize, Option<DateTime<Utc>>) {
+        let mut count = 0;
+        let mut last = None;
+        let mut time = after;
+        while count < MAX_COUNTED_MISSED_RUNS {
+            match self.next_after(time) {
+                Some(next) if next <= until => {
+                    count += 1;
+                    last = Some(next);
+                    time = next;
+                }
+                _ => break,
+            }
+        }
+        (count, last)
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+
+    fn next(expression: &str, after: DateTime<Utc>) -> DateTime<Utc> {
+        CronSchedule::parse(expression)
+            .unwrap_or_else(|e| panic!("{}", e))
+            .next_after(after)
+            .expect("schedule should match")
+    }
+
+    fn parse_error(expression: &str) -> String {
+        CronSchedule::parse(expression).expect_err("expression should be rejected")
+    }
+
+    #[test]
+    fn accepts_numbers_ranges_lists_and_steps() {
+        // 2026-10-15 is a Thursday
+        let thursday = at(2026, 10, 15, 7, 30);
+        assert_eq!(next("0 */6 * * *", thursday), at(2026, 10, 15, 12, 0));
+        assert_eq!(next("45 7 * * *", thursday), at(2026, 10, 15, 7, 45));
+        assert_eq!(next("30 7 * * *", thursday), at(2026, 10, 16, 7, 30));
+        assert_eq!(next("5,10-20/5 * * * *", at(2026, 10, 15, 0, 20)), at(2026, 10, 15, 1, 5));
+        assert_eq!(next("30/10 * * * *", at(2026, 10, 15, 0, 50)), at(2026, 10, 15, 1, 30));
+        assert_eq!(next("*/15 9-17 * * MON-FRI", at(2026, 10, 16, 17, 50)), at(2026, 10, 19, 9, 0));
+        assert_eq!(next("0 0 1 jan,Jul *", thursday), at(2027, 1, 1, 0, 0));
+        assert_eq!(next("0 0 29 2 *", thursday), at(2028, 2, 29, 0, 0));
+    }
+
+    #[test]
+    fn sunday_is_both_zero_and_seven() {
+        let thursday = at(2026, 10, 15, 7, 30);
+        assert_eq!(next("0 0 * * 7", thursday), at(2026, 10, 18, 0, 0));
+        assert_eq!(next("0 0 * * 0", thursday), next("0 0 * * SUN", thursday));
+        assert_eq!(next("0 0 * * 5-7", at(2026, 10, 18, 0, 0)), at(2026, 10, 23, 0, 0));
+    }
+
+    #[test]
+    fn restricted_day_fields_combine_with_or() {
+        let thursday = at(2026, 10, 15, 7, 30);
+        assert_eq!(next("0 0 13 * *", thursday), at(2026, 11, 13, 0, 0));
+        assert_eq!(next("0 0 13 * MON", thursday), at(2026, 10, 19, 0, 0));
+        assert_eq!(next("0 0 */1 * MON", thursday), at(2026, 10, 19, 0, 0));
+    }
+
+    #[test]
+    fn accepts_the_shorthands() {
+        let thursday = at(2026, 10, 15, 7, 30);
+        assert_eq!(next("@hourly", thursday), at(2026, 10, 15, 8, 0));
+        assert_eq!(next("@daily", thursday), next("@midnight", thursday));
+        assert_eq!(next("@weekly", thursday), at(2026, 10, 18, 0, 0));
+        assert_eq!(next("@monthly", thursday), at(2026, 11, 1, 0, 0));
+        assert_eq!(next("@yearly", thursday), next("@annually", thursday));
+        assert_eq!(CronSchedule::parse("  @daily ").unwrap().to_string(), "@daily");
+    }
+
+    #[test]
+    fn counts_the_times_in_a_period() {
+        let schedule = CronSchedule::parse("@hourly").unwrap();
+        let start = at(2026, 10, 15, 0, 0);
+        assert_eq!(schedule.times_between(start, at(2026, 10, 15, 5, 0)), (5, Some(at(2026, 10, 15, 5, 0))));
+        assert_eq!(schedule.times_between(start, at(2026, 10, 15, 0, 59)), (0, None));
+    }
+
+    #[test]
+    fn empty_expression_is_rejected() {
+        let expected = "(expected 5 fields: minute hour day-of-month month day-of-week)";
+        assert_eq!(parse_error(""), format!("invalid cron expression '' {}", expected));
+        assert_eq!(parse_error("   "), format!("invalid cron expression '   ' {}", expected));
+        assert_eq!(parse_error("* * * *"), format!("invalid cron expression '* * * *' {}", expected));
+        assert!(parse_error("@often").starts_with("invalid cron expression '@often'"));
+    }
+
+    #[test]
+    fn zero_and_malformed_steps_are_rejected() {
+        assert_eq!(parse_error("*/0 * * * *"), "invalid step '0' in minute '*/0'");
+        assert_eq!(parse_error("0 1-5/0 * * *"), "invalid step '0' in hour '1-5/0'");
+        assert_eq!(parse_error("*/x * * * *"), "invalid step 'x' in minute '*/x'");
+        assert_eq!(parse_error("*/ * * * *"), "invalid step '' in minute '*/'");
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        assert_eq!(parse_error("60 * * * *"), "invalid minute '60' (expected 0 to 59)");
+        assert_eq!(parse_error("* 24 * * *"), "invalid hour '24' (expected 0 to 23)");
+        assert_eq!(parse_error("* * 0 * *"), "invalid day of month '0' (expected 1 to 31)");
+        assert_eq!(parse_error("* * * 13 *"), "invalid month '13' (expected 1 to 12)");
+        assert_eq!(parse_error("* * * FOO *"), "invalid month 'FOO' (expected 1 to 12)");
+        assert_eq!(parse_error("* * * * 8"), "invalid day of week '8' (expected 0 to 7)");
+        assert_eq!(parse_error("-1 * * * *"), "invalid minute '' (expected 0 to 59)");
+        assert_eq!(parse_error("10-5 * * * *"), "invalid range '10-5' in minute '10-5'");
+    }
+
+    #[test]
+    fn expressions_that_never_match_are_rejected() {
+        assert_eq!(parse_error("0 0 31 2 *"), "cron expression '0 0 31 2 *' never matches");
+        assert_eq!(parse_error("0 0 30 FEB *"), "cron expression '0 0 30 FEB *' never matches");
+    }
+
+    #[test]
+    fn parses_missed_run_policies() {
+        assert_eq!(MissedRunPolicy::parse("skip"), Ok(MissedRunPolicy::Skip));
+        assert_eq!(MissedRunPolicy::parse("run_once"), Ok(MissedRunPolicy::RunOnce));
+        assert_eq!(
+            MissedRunPolicy::parse("catch_up"),
+            Err(String::from("Unknown missed run policy 'catch_up' (expected 'skip' or 'run_once')"))
+        );
+    }
+}
+
+// End of rust_sample_project/src/schedule.rs
@@ -0,0 +1,128 @@
+// rust_sample_project/src/secret.rs
+
+// Secret config values such as the SMTP password (notify_smtp_password).
+// Secret wraps them so they never show up in Debug or Display output, and
+// with that in log records, `config show` or the change list of a reloaded
+// config file. Instead of the secret itself, config may name where to read
+// it from when it is needed, so it does not have to live in the config file:
+//
+//     notify_smtp_password = "env:SMTP_PASSWORD"       # an environment variable
+//     notify_smtp_password = "file:/run/secrets/smtp"  # a file, e.g. a mounted secret
+//
+// Such references are not secret and are shown as they are.
+//
+// Webhook URLs (notify_webhooks) stay plain strings, as they are posted to;
+// they are shown cut down to their host instead, see notify::redact.
+
+use std::env;
+use std::fmt;
+use std::fs;
+
+/// Shown in place of a secret value.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Prefix of a secret read from an environment variable.
+const ENV_PREFIX: &str = "env:";
+
+/// Prefix of a secret read from a file.
+const FILE_PREFIX: &str = "file:";
+
+/**
+ * @struct Secret
+ * @brief A value that is printed as [REDACTED]; use expose to get at it.
+ */
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    /**
+     * @brief Constructs a secret.
+     *
+     * @param value The value to keep out of output.
+     * @return Secret<T> The secret.
+     */
+    pub fn new(value: T) -> Self {
+        Secret(value)
+    }
+
+    /**
+     * @brief Return the value, for the one place that needs it.
+     *
+     * @return &T The value.
+     */
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl Secret<String> {
+    /**
+     * @brief Check whether no value is set.
+     *
+     * @return bool True for an empty value.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /**
+     * @brief Return where the secret is read from, if the value is a reference.
+     *
+     * @return Option<&str> The value, if it is "env:NAME" or "file:PATH".
+     */
+    pub fn reference(&self) -> Option<&str> {
+        (self.0.starts_with(ENV_PREFIX) || self.0.starts_with(FILE_PREFIX)).then_some(self.0.as_str())
+    }
+
+    /**
+     * @brief Return a configured value for output: "" if empty, a reference
+     *        as it is, anything else as REDACTED.
+     *
+     * @return String The printable value.
+     */
+    pub fn redacted(&self) -> String {
+        match self.reference() {
+            _ if self.0.is_empty() => String::new(),
+            Some(reference) => reference.to_string(),
+            None => String::from(REDACTED),
+        }
+    }
+
+    /**
+     * @brief Read the secret a configured value refers to.
+     *
+     * "env:NAME" reads the environment variable NAME, "file:PATH" the file
+     * PATH (without a trailing line break); any other value is the secret
+     * itself.
+     *
+     * @return Result<Secret<String>, String> The secret, or an error message
+     *         naming the variable or file (never the secret).
+     */
+    pub fn resolve(&self) -> Result<Secret<String>, String> {
+        if let Some(name) = self.0.strip_prefix(ENV_PREFIX) {
+            return env::var(name)
+                .map(Secret)
+                .map_err(|e| format!("cannot read the environment variable {}: {}", name, e));
+        }
+        if let Some(path) = self.0.strip_prefix(FILE_PREFIX) {
+            return fs::read_to_string(path)
+                .map(|text| Secret(text.trim_end_matches(['\r', '\n']).to_string()))
+                .map_err(|e| format!("cannot read the secret file '{}': {}", path, e));
+        }
+        Ok(self.clone())
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+// End of rust_sample_project/src/secret.rs
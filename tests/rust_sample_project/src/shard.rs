@@ -0,0 +1,285 @@
+// rust_sample_project/src/shard.rs
+
+// Sharded data files (see the shard_max_items and shard_max_bytes config
+// keys). With sharding on, a local data file is saved as numbered shards
+// next to it, each capped by item count and/or size, plus a manifest listing
+// them in order:
+//
+//     data/items-0001.json
+//     data/items-0002.json
+//     data/items.manifest.json
+//
+// The manifest is written last, in the same transaction as the shards, and
+// is what loaders go by: the data file is reassembled by reading the listed
+// shards in order. Shards beyond the listed ones, left from an earlier save
+// with more shards, are removed once the new shards are in place.
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::integrity;
+use crate::item::Item;
+
+/// Version of the manifest layout.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/**
+ * @struct ShardPolicy
+ * @brief How big the shards of a data file may get.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ShardPolicy {
+    /// Largest number of items per shard (0 = no limit).
+    pub max_items: usize,
+    /// Largest size of the items of a shard, as uncompressed JSON (0 = no limit).
+    pub max_bytes: u64,
+}
+
+impl ShardPolicy {
+    /**
+     * @brief Constructs a policy from the config values.
+     *
+     * @param max_items Largest number of items per shard (0 = no limit).
+     * @param max_bytes Largest size of a shard's items in bytes (0 = no limit).
+     * @return ShardPolicy The policy; with both limits at 0, sharding is off.
+     */
+    pub fn new(max_items: usize, max_bytes: u64) -> Self {
+        ShardPolicy { max_items, max_bytes }
+    }
+
+    /**
+     * @brief Return whether data files are sharded.
+     *
+     * @return bool True if either limit is set.
+     */
+    pub fn is_enabled(&self) -> bool {
+        self.max_items > 0 || self.max_bytes > 0
+    }
+
+    /**
+     * @brief Split items into shards.
+     *
+     * A shard ends before the item that would take it over a limit; an item
+     * bigger than max_bytes gets a shard of its own.
+     *
+     * @param items The items of the data file, in order.
+     * @return Vec<&[Item]> The shards, in order (one empty shard for no items).
+     */
+    pub fn split<'a>(&self, items: &'a [Item]) -> Vec<&'a [Item]> {
+        let mut shards = Vec::new();
+        let (mut start, mut bytes) = (0, 0u64);
+        for (index, item) in items.iter().enumerate() {
+            let size = if self.max_bytes > 0 {
+                serde_json::to_vec(item).map_or(0, |json| json.len() as u64)
+            } else {
+                0
+            };
+            let full_by_count = self.max_items > 0 && index - start >= self.max_items;
+            let full_by_size = self.max_bytes > 0 && index > start && bytes + size > self.max_bytes;
+            if full_by_count || full_by_size {
+                shards.push(&items[start..index]);
+                start = index;
+                bytes = 0;
+            }
+            bytes += size;
+        }
+        shards.push(&items[start..]);
+        shards
+    }
+}
+
+/**
+ * @struct ShardEntry
+ * @brief One shard listed in a manifest.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardEntry {
+    /// File name of the shard, in the directory of the manifest.
+    pub file: String,
+    /// Number of items in the shard.
+    pub items: usize,
+    /// Size of the shard file in bytes.
+    pub bytes: usize,
+}
+
+/**
+ * @struct ShardManifest
+ * @brief The shards a data file was saved as, in order.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardManifest {
+    /// Layout version, see MANIFEST_VERSION.
+    pub manifest_version: u32,
+    /// Number of items in all shards.
+    pub total_items: usize,
+    /// The shards.
+    pub shards: Vec<ShardEntry>,
+}
+
+impl ShardManifest {
+    /**
+     * @brief Return the paths of the listed shards.
+     *
+     * @param manifest_path Path of the manifest; shards are next to it.
+     * @return Vec<PathBuf> The shard paths, in order.
+     */
+    pub fn shard_paths(&self, manifest_path: &Path) -> Vec<PathBuf> {
+        let dir = manifest_path.parent().unwrap_or(Path::new(""));
+        self.shards.iter().map(|shard| dir.join(&shard.file)).collect()
+    }
+}
+
+/**
+ * @brief Split a file name at its first dot, e.g. "items.json.gz" into
+ *        ("items", ".json.gz").
+ */
+fn split_name(path: &Path) -> (OsString, OsString) {
+    let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+    match name.find('.').filter(|&dot| dot > 0) {
+        Some(dot) => (OsString::from(&name[..dot]), OsString::from(&name[dot..])),
+        None => (OsString::from(name.as_ref()), OsString::new()),
+    }
+}
+
+/**
+ * @brief Return the path of a shard of a data file.
+ *
+ * @param path The data file, e.g. "data/items.json".
+ * @param number Number of the shard, starting at 1.
+ * @return PathBuf The shard, e.g. "data/items-0001.json".
+ */
+pub fn shard_path(path: &Path, number: usize) -> PathBuf {
+    let (stem, extension) = split_name(path);
+    let mut name = stem;
+    name.push(format!("-{:04}", number));
+    name.push(extension);
+    path.with_file_name(name)
+}
+
+/**
+ * @brief Return the path of the shard manifest of a data file.
+ *
+ * @param path The data file, e.g. "data/items.json".
+ * @return PathBuf The manifest, e.g. "data/items.manifest.json".
+ */
+pub fn manifest_path(path: &Path) -> PathBuf {
+    let (mut name, _) = split_name(path);
+    name.push(".manifest.json");
+    path.with_file_name(name)
+}
+
+/**
+ * @brief Return the shards of a data file beyond those a save writes.
+ *
+ * @param path The data file.
+ * @param count Number of shards the save writes.
+ * @return Vec<PathBuf> The existing shards numbered above count, in order.
+ */
+pub fn stale_shards(path: &Path, count: usize) -> Vec<PathBuf> {
+    (count + 1..).map(|number| shard_path(path, number)).take_while(|shard| shard.is_file()).collect()
+}
+
+/**
+ * @brief Remove stale shards and their checksum files.
+ * Failures are only logged; the manifest no longer lists the shards.
+ *
+ * @param shards The shards to remove, see stale_shards.
+ */
+pub fn remove_stale_shards(shards: &[PathBuf]) {
+    for shard in shards {
+        for path in [shard.clone(), integrity::sidecar_path(shard)] {
+            match fs::remove_file(&path) {
+                Ok(()) => log_debug!("shard:remove_stale_shards", "Removed stale shard file {}.", path.display()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => log_warning!("shard:remove_stale_shards", "Failed to remove '{}': {}", path.display(), e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item_id::ItemId;
+    use crate::money::Money;
+    use crate::test_dir::TestDir;
+
+    fn items(count: i64) -> Vec<Item> {
+        (1..=count).map(|id| Item::new(ItemId::from(id), format!("Item {}", id), Money::from_int(id))).collect()
+    }
+
+    fn lengths(shards: &[&[Item]]) -> Vec<usize> {
+        shards.iter().map(|shard| shard.len()).collect()
+    }
+
+    #[test]
+    fn items_are_split_by_count() {
+        let items = items(5);
+
+        assert_eq!(lengths(&ShardPolicy::new(2, 0).split(&items)), [2, 2, 1]);
+        assert_eq!(lengths(&ShardPolicy::new(5, 0).split(&items)), [5]);
+        assert_eq!(lengths(&ShardPolicy::new(2, 0).split(&[])), [0]);
+    }
+
+    #[test]
+    fn items_are_split_by_size() {
+        let items = items(4);
+        let size = serde_json::to_vec(&items[0]).unwrap().len() as u64;
+
+        assert_eq!(lengths(&ShardPolicy::new(0, size * 2 + 1).split(&items)), [2, 2]);
+        assert_eq!(lengths(&ShardPolicy::new(0, 1).split(&items)), [1, 1, 1, 1], "an oversized item fills a shard");
+        assert_eq!(lengths(&ShardPolicy::new(1, size * 10).split(&items)), [1, 1, 1, 1], "both limits apply");
+    }
+
+    #[test]
+    fn shards_and_manifest_are_named_after_the_data_file() {
+        let path = Path::new("data/items.json.gz");
+
+        assert_eq!(shard_path(path, 12), Path::new("data/items-0012.json.gz"));
+        assert_eq!(manifest_path(path), Path::new("data/items.manifest.json"));
+    }
+
+    #[test]
+    fn manifests_round_trip_and_list_their_shards_in_order() {
+        let manifest = ShardManifest {
+            manifest_version: MANIFEST_VERSION,
+            total_items: 3,
+            shards: vec![
+                ShardEntry { file: String::from("items-0001.json"), items: 2, bytes: 120 },
+                ShardEntry { file: String::from("items-0002.json"), items: 1, bytes: 60 },
+            ],
+        };
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        let loaded: ShardManifest = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded, manifest);
+        assert_eq!(
+            loaded.shard_paths(Path::new("data/items.manifest.json")),
+            [Path::new("data/items-0001.json"), Path::new("data/items-0002.json")]
+        );
+    }
+
+    #[test]
+    fn shards_beyond_a_save_are_stale() {
+        let dir = TestDir::new("stale-shards");
+        let path = dir.join("items.json");
+        for number in 1..=3 {
+            fs::write(shard_path(&path, number), "[]").unwrap();
+        }
+        fs::write(integrity::sidecar_path(&shard_path(&path, 3)), "checksum").unwrap();
+
+        let stale = stale_shards(&path, 1);
+        remove_stale_shards(&stale);
+
+        assert_eq!(stale, [shard_path(&path, 2), shard_path(&path, 3)]);
+        let left: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(left, [shard_path(&path, 1).file_name().unwrap()]);
+        assert!(stale_shards(&path, 3).is_empty());
+    }
+}
+
+// End of rust_sample_project/src/shard.rs
@@ -0,0 +1,94 @@
+// rust_sample_project/src/shutdown.rs
+
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/**
+ * @brief Exit code used when the process stops because of SIGINT/SIGTERM
+ *        (128 + SIGINT, as shells report an interrupted command).
+ */
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// Set by the signal handler once SIGINT or SIGTERM was received.
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Run by exit before the process ends, see before_exit.
+static CLEANUP: Mutex<Option<fn()>> = Mutex::new(None);
+
+/**
+ * @brief Install handlers for SIGINT (Ctrl-C) and SIGTERM.
+ *
+ * The first signal only requests a shutdown, which the running mode honours
+ * at the next safe point (e.g. between two items). A second signal exits
+ * immediately, for when the graceful path is stuck.
+ *
+ * @return Result<(), String> Ok once the handlers are installed, or an error message.
+ */
+pub fn install_handlers() -> Result<(), String> {
+    ctrlc::set_handler(|| {
+        if SHUTDOWN_REQUESTED.swap(true, Ordering::SeqCst) {
+            log_critical!("shutdown:handler", "Second interrupt received, exiting immediately.");
+            exit(EXIT_INTERRUPTED);
+        }
+        log_requested();
+    })
+    .map_err(|e| format!("Failed to install signal handlers: {}", e))
+}
+
+/**
+ * @brief Request a shutdown as SIGINT would, e.g. on a key press in the
+ *        terminal dashboard (which keeps Ctrl-C from raising SIGINT).
+ */
+pub fn request() {
+    if !SHUTDOWN_REQUESTED.swap(true, Ordering::SeqCst) {
+        log_requested();
+    }
+}
+
+/**
+ * @brief Log that a shutdown was requested.
+ */
+fn log_requested() {
+    log_warning!(
+        "shutdown:handler",
+        "Interrupt received, finishing the current step and saving progress (interrupt again to force exit)."
+    );
+}
+
+/**
+ * @brief Register a function exit runs before the process ends, e.g. to
+ *        restore the terminal; replaces the one registered before.
+ *
+ * @param cleanup The function.
+ */
+pub fn before_exit(cleanup: fn()) {
+    *CLEANUP.lock().unwrap_or_else(|e| e.into_inner()) = Some(cleanup);
+}
+
+/**
+ * @brief End the process like std::process::exit, after running the
+ *        function registered with before_exit.
+ *
+ * Used where long-running modes end, as std::process::exit runs no destructors.
+ *
+ * @param code The exit code.
+ */
+pub fn exit(code: i32) -> ! {
+    let cleanup = CLEANUP.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(cleanup) = cleanup {
+        cleanup();
+    }
+    process::exit(code)
+}
+
+/**
+ * @brief Check whether a shutdown was requested by a signal.
+ *
+ * @return bool True once SIGINT or SIGTERM has been received.
+ */
+pub fn requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
+
+// End of rust_sample_project/src/shutdown.rs
@@ -0,0 +1,380 @@
+// rust_sample_project/src/snapshot.rs
+
+// Snapshots of the local data files, taken before every save replaces them
+// (see the snapshot_dir and snapshot_retention config keys), so the results
+// of a run with bad rules can be undone with the `rollback` subcommand. Each
+// snapshot is a directory in the snapshot directory, named after the time it
+// was taken, holding copies of the data files a save replaced and their
+// checksum files, and a manifest recording where they came from:
+//
+//     data/snapshots/20261015T140309.123456Z/snapshot.json
+//     data/snapshots/20261015T140309.123456Z/0-items.json
+//     data/snapshots/20261015T140309.123456Z/0-items.json.sha256
+//
+// Only the newest snapshot_retention snapshots are kept; older ones are pruned
+// once the save has succeeded, and the new one is discarded if it failed.
+// Restoring puts all files back in one staging transaction (see staging.rs).
+// Remote data files are not covered; their stores keep versions of their own.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::integrity;
+use crate::staging::Transaction;
+use crate::storage::{self, FileStore, ItemStore};
+
+/// Name of the manifest in each snapshot directory.
+const MANIFEST_NAME: &str = "snapshot.json";
+
+/**
+ * @struct SnapshotFile
+ * @brief A data file copied into a snapshot.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    /// Where the data file was, and is restored to.
+    pub target: PathBuf,
+    /// Name of the copy in the snapshot directory.
+    pub file_name: String,
+    /// Whether the data file had a checksum file, copied next to the copy.
+    pub checksum_file: bool,
+}
+
+/**
+ * @struct Snapshot
+ * @brief The data files as they were before a save.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Unique id, also the name of the snapshot directory.
+    pub id: String,
+    /// When the snapshot was taken.
+    pub created_at: DateTime<Utc>,
+    /// The data files in the snapshot.
+    pub files: Vec<SnapshotFile>,
+    /// The snapshot directory.
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl Snapshot {
+    /**
+     * @brief Put the data files of the snapshot back in place.
+     *
+     * All files are restored in one transaction, so either every data file
+     * is put back or none is. A data file that had no checksum file but has
+     * one now gets one matching the restored contents, since the current
+     * one would not match.
+     *
+     * @param staging_dir The staging directory for the transaction.
+     * @return Result<usize, String> The number of files restored, or an error
+     *         message if none was.
+     */
+    pub fn restore(&self, staging_dir: &Path) -> Result<usize, String> {
+        let mut restored = Vec::new();
+        for file in &self.files {
+            let copy = self.dir.join(&file.file_name);
+            let contents = read_copy(&copy)?;
+            let sidecar = integrity::sidecar_path(&file.target);
+            if file.checksum_file {
+                restored.push((sidecar, read_copy(&integrity::sidecar_path(&copy))?));
+            } else if sidecar.exists() {
+                restored.push((sidecar, integrity::sidecar_contents(&file.target, &integrity::digest(&contents))));
+            }
+            restored.push((file.target.clone(), contents));
+        }
+        let mut transaction = Transaction::begin(staging_dir)?;
+        for (target, contents) in &restored {
+            transaction = transaction.stage(target, contents)?;
+        }
+        transaction
+            .commit()
+            .map_err(|e| format!("Failed to restore snapshot {}: {}", self.id, e))?;
+        log_debug!("snapshot:restore", "Restored snapshot {} from {}.", self.id, self.dir.display());
+        Ok(self.files.len())
+    }
+
+    /**
+     * @brief Remove the snapshot, e.g. because the save it was taken for failed.
+     *
+     * @return Result<(), String> Ok, or an error message.
+     */
+    pub fn discard(self) -> Result<(), String> {
+        fs::remove_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to remove snapshot '{}': {}", self.dir.display(), e))
+    }
+}
+
+/**
+ * @brief Read a file copied into a snapshot.
+ */
+fn read_copy(copy: &Path) -> Result<Vec<u8>, String> {
+    fs::read(copy).map_err(|e| format!("Failed to read snapshot copy '{}': {}", copy.display(), e))
+}
+
+/**
+ * @brief Snapshot data files before a save replaces them.
+ *
+ * Targets that do not exist yet are left out; if none exists, no snapshot is
+ * taken. Old snapshots are left in place; call prune() once the save has
+ * succeeded.
+ *
+ * @param snapshot_dir The snapshot directory (created if missing).
+ * @param targets The local data files about to be replaced.
+ * @param retention Number of snapshots to keep; 0 takes none.
+ * @return Result<Option<Snapshot>, String> The snapshot, None if none was
+ *         taken, or an error message.
+ */
+pub fn take(snapshot_dir: &Path, targets: &[&Path], retention: usize) -> Result<Option<Snapshot>, String> {
+    let existing: Vec<&Path> = targets.iter().copied().filter(|target| target.is_file()).collect();
+    if retention == 0 || existing.is_empty() {
+        return Ok(None);
+    }
+    let created_at = Utc::now();
+    let id = created_at.format("%Y%m%dT%H%M%S%.6fZ").to_string();
+    let dir = snapshot_dir.join(&id);
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create snapshot directory '{}': {}", dir.display(), e))?;
+    let mut files = Vec::new();
+    for (index, target) in existing.into_iter().enumerate() {
+        let name = target.file_name().unwrap_or(target.as_os_str()).to_string_lossy();
+        let file_name = format!("{}-{}", index, name);
+        let copy = dir.join(&file_name);
+        fs::copy(target, &copy).map_err(|e| {
+            format!("Failed to copy '{}' into snapshot '{}': {}", target.display(), dir.display(), e)
+        })?;
+        let sidecar = integrity::sidecar_path(target);
+        let checksum_file = sidecar.is_file();
+        if checksum_file {
+            fs::copy(&sidecar, integrity::sidecar_path(&copy)).map_err(|e| {
+                format!("Failed to copy '{}' into snapshot '{}': {}", sidecar.display(), dir.display(), e)
+            })?;
+        }
+        files.push(SnapshotFile {
+            target: storage::canonical_path(target),
+            file_name,
+            checksum_file,
+        });
+    }
+    let snapshot = Snapshot {
+        id,
+        created_at,
+        files,
+        dir,
+    };
+    let json = serde_json::to_string_pretty(&snapshot)
+        .map_err(|e| format!("Failed to serialize snapshot manifest: {}", e))?;
+    FileStore::new(&snapshot.dir.join(MANIFEST_NAME))
+        .write(json.as_bytes())
+        .map_err(|e| format!("Failed to write snapshot manifest: {}", e))?;
+    let plural = if snapshot.files.len() == 1 { "" } else { "s" };
+    log_info!(
+        "snapshot:take",
+        "Snapshot {} holds {} data file{} from before the save.",
+        snapshot.id,
+        snapshot.files.len(),
+        plural
+    );
+    Ok(Some(snapshot))
+}
+
+/**
+ * @brief Load the snapshots in a snapshot directory.
+ *
+ * Directories without a readable manifest are skipped.
+ *
+ * @param snapshot_dir The snapshot directory.
+ * @return Result<Vec<Snapshot>, String> The snapshots, oldest first (none if
+ *         the directory does not exist), or an error message.
+ */
+pub fn list(snapshot_dir: &Path) -> Result<Vec<Snapshot>, String> {
+    let entries = match fs::read_dir(snapshot_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read snapshot directory '{}': {}", snapshot_dir.display(), e)),
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        let manifest = dir.join(MANIFEST_NAME);
+        let parsed = fs::read_to_string(&manifest)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<Snapshot>(&json).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(mut snapshot) => {
+                snapshot.dir = dir;
+                snapshots.push(snapshot);
+            }
+            Err(e) => log_debug!("snapshot:list", "Skipping {}: {}", dir.display(), e),
+        }
+    }
+    snapshots.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(snapshots)
+}
+
+/**
+ * @brief Find a snapshot by id.
+ *
+ * @param snapshot_dir The snapshot directory.
+ * @param id The snapshot id, or "latest" for the newest snapshot.
+ * @return Result<Snapshot, String> The snapshot, or an error message if there
+ *         is no such snapshot.
+ */
+pub fn find(snapshot_dir: &Path, id: &str) -> Result<Snapshot, String> {
+    let snapshots = list(snapshot_dir)?;
+    let found = if id == "latest" {
+        snapshots.into_iter().next_back()
+    } else {
+        snapshots.into_iter().find(|snapshot| snapshot.id == id)
+    };
+    found.ok_or_else(|| format!("No snapshot '{}' in '{}'", id, snapshot_dir.display()))
+}
+
+/**
+ * @brief Remove the oldest snapshots beyond the retention.
+ * Failures are only logged; the new snapshot is already in place.
+ *
+ * @param snapshot_dir The snapshot directory.
+ * @param retention Number of snapshots to keep.
+ */
+pub fn prune(snapshot_dir: &Path, retention: usize) {
+    let snapshots = match list(snapshot_dir) {
+        Ok(snapshots) => snapshots,
+        Err(e) => {
+            log_warning!("snapshot:prune", "{}", e);
+            return;
+        }
+    };
+    let excess = snapshots.len().saturating_sub(retention);
+    for snapshot in snapshots.into_iter().take(excess) {
+        match fs::remove_dir_all(&snapshot.dir) {
+            Ok(()) => log_debug!("snapshot:prune", "Removed snapshot {}.", snapshot.id),
+            Err(e) => log_warning!(
+                "snapshot:prune",
+                "Failed to remove snapshot '{}': {}",
+                snapshot.dir.display(),
+                e
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    /// Write a data file and its checksum file.
+    fn write_with_checksum(path: &Path, contents: &str) {
+        fs::write(path, contents).unwrap();
+        let digest = integrity::digest(contents.as_bytes());
+        fs::write(integrity::sidecar_path(path), integrity::sidecar_contents(path, &digest)).unwrap();
+    }
+
+    #[test]
+    fn snapshots_copy_the_existing_targets_and_their_checksum_files() {
+        let dir = TestDir::new("snapshot-take");
+        let items = dir.join("items.json");
+        let archive = dir.join("archive.json");
+        write_with_checksum(&items, "items");
+        fs::write(&archive, "archive").unwrap();
+        let missing = dir.join("missing.json");
+
+        let snapshot = take(&dir.join("snapshots"), &[&items, &archive, &missing], 3).unwrap().unwrap();
+
+        let files: Vec<(&str, bool)> =
+            snapshot.files.iter().map(|file| (file.file_name.as_str(), file.checksum_file)).collect();
+        assert_eq!(files, [("0-items.json", true), ("1-archive.json", false)]);
+        assert_eq!(fs::read_to_string(snapshot.dir.join("0-items.json")).unwrap(), "items");
+        assert!(integrity::sidecar_path(&snapshot.dir.join("0-items.json")).is_file());
+        let listed = find(&dir.join("snapshots"), "latest").unwrap();
+        assert_eq!((listed.id, listed.files.len()), (snapshot.id, 2));
+    }
+
+    #[test]
+    fn no_snapshot_is_taken_without_retention_or_existing_targets() {
+        let dir = TestDir::new("snapshot-none");
+        let items = dir.join("items.json");
+
+        assert!(take(&dir.join("snapshots"), &[&items], 3).unwrap().is_none());
+        fs::write(&items, "items").unwrap();
+        assert!(take(&dir.join("snapshots"), &[&items], 0).unwrap().is_none());
+        assert!(list(&dir.join("snapshots")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn pruning_keeps_the_newest_snapshots() {
+        let dir = TestDir::new("snapshot-prune");
+        let snapshot_dir = dir.join("snapshots");
+        let items = dir.join("items.json");
+        let mut ids = Vec::new();
+        for round in 0..4 {
+            fs::write(&items, format!("round {}", round)).unwrap();
+            ids.push(take(&snapshot_dir, &[&items], 2).unwrap().unwrap().id);
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        assert_eq!(list(&snapshot_dir).unwrap().len(), 4, "taking a snapshot prunes nothing");
+
+        prune(&snapshot_dir, 2);
+
+        let kept: Vec<String> = list(&snapshot_dir).unwrap().into_iter().map(|snapshot| snapshot.id).collect();
+        assert_eq!(kept, ids[2..]);
+    }
+
+    #[test]
+    fn restoring_puts_all_files_and_checksums_back() {
+        let dir = TestDir::new("snapshot-restore");
+        let items = dir.join("items.json");
+        let archive = dir.join("archive.json");
+        write_with_checksum(&items, "old items");
+        fs::write(&archive, "old archive").unwrap();
+        let snapshot = take(&dir.join("snapshots"), &[&items, &archive], 3).unwrap().unwrap();
+        write_with_checksum(&items, "new items");
+        write_with_checksum(&archive, "new archive");
+
+        assert_eq!(snapshot.restore(&dir.join("staging")).unwrap(), 2);
+
+        assert_eq!(fs::read_to_string(&items).unwrap(), "old items");
+        assert_eq!(fs::read_to_string(&archive).unwrap(), "old archive");
+        for (path, contents) in [(&items, "old items"), (&archive, "old archive")] {
+            let digest = integrity::digest(contents.as_bytes());
+            assert_eq!(fs::read(integrity::sidecar_path(path)).unwrap(), integrity::sidecar_contents(path, &digest));
+        }
+        assert_eq!(fs::read_dir(dir.join("staging")).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn a_restore_that_fails_changes_no_file() {
+        let dir = TestDir::new("snapshot-restore-fails");
+        let items = dir.join("items.json");
+        let archive = dir.join("archive.json");
+        fs::write(&items, "old items").unwrap();
+        fs::write(&archive, "old archive").unwrap();
+        let snapshot = take(&dir.join("snapshots"), &[&items, &archive], 3).unwrap().unwrap();
+        fs::write(&items, "new items").unwrap();
+        fs::write(&archive, "new archive").unwrap();
+        fs::remove_file(snapshot.dir.join(&snapshot.files[1].file_name)).unwrap();
+
+        assert!(snapshot.restore(&dir.join("staging")).is_err());
+
+        assert_eq!(fs::read_to_string(&items).unwrap(), "new items");
+        assert_eq!(fs::read_to_string(&archive).unwrap(), "new archive");
+    }
+
+    #[test]
+    fn discarded_snapshots_are_gone() {
+        let dir = TestDir::new("snapshot-discard");
+        let items = dir.join("items.json");
+        fs::write(&items, "items").unwrap();
+        let snapshot = take(&dir.join("snapshots"), &[&items], 3).unwrap().unwrap();
+
+        snapshot.discard().unwrap();
+
+        assert!(list(&dir.join("snapshots")).unwrap().is_empty());
+    }
+}
+
+// End of rust_sample_project/src/snapshot.rs
@@ -0,0 +1,442 @@
+// rust_sample_project/src/staging.rs
+
+// Two-phase saving of several local data files. In the prepare phase every
+// file is written next to its target as "<target>.staged-<id>", and a
+// manifest in the staging directory lists the targets. The commit phase moves
+// each existing target aside to "<target>.backup-<id>" and renames the staged
+// file into place. If any step fails, everything done so far is undone, so
+// either all targets are replaced or none is. A process that dies halfway
+// leaves its manifest behind; recover() uses it to roll the transaction back
+// (or, if it was fully committed, to finish cleaning up).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+/**
+ * @enum TransactionState
+ * @brief How far a save transaction got, as recorded in its manifest.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionState {
+    /// Staged files are being written; no target has been touched.
+    Prepared,
+    /// Staged files are being renamed into place.
+    Committing,
+    /// All targets are replaced; only backups remain to be removed.
+    Committed,
+}
+
+/**
+ * @struct Transaction
+ * @brief A two-phase save of one or more local data files.
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Transaction {
+    /// Unique id, also used in the names of the staged and backup files.
+    pub id: String,
+    /// When the transaction began.
+    pub created_at: DateTime<Utc>,
+    /// Process id of the run that began it.
+    pub pid: u32,
+    /// How far the transaction got.
+    pub state: TransactionState,
+    /// The data files being replaced.
+    pub targets: Vec<PathBuf>,
+    /// Where the manifest is kept.
+    #[serde(skip)]
+    manifest_path: PathBuf,
+}
+
+impl Transaction {
+    /**
+     * @brief Begin a transaction, writing its manifest to the staging directory.
+     *
+     * @param staging_dir The staging directory (created if missing).
+     * @return Result<Transaction, String> The transaction, or an error message.
+     */
+    pub fn begin(staging_dir: &Path) -> Result<Self, String> {
+        fs::create_dir_all(staging_dir).map_err(|e| {
+            format!("Failed to create staging directory '{}': {}", staging_dir.display(), e)
+        })?;
+        let created_at = Utc::now();
+        let id = format!("{}-{}", created_at.format("%Y%m%dT%H%M%S%.6f"), std::process::id());
+        let transaction = Transaction {
+            manifest_path: staging_dir.join(format!("{}.json", id)),
+            id,
+            created_at,
+            pid: std::process::id(),
+            state: TransactionState::Prepared,
+            targets: Vec::new(),
+        };
+        transaction.save_manifest()?;
+        Ok(transaction)
+    }
+
+    /**
+     * @brief Write the new contents of a data file next to it (prepare phase).
+     *
+     * On failure the transaction is rolled back.
+     *
+     * @param target Path of the data file to replace (missing parent
+     *        directories are created).
+     * @param contents Its new contents.
+     * @return Result<Transaction, String> The transaction once the contents
+     *         are staged, or an error message.
+     */
+    pub fn stage(mut self, target: &Path, contents: &[u8]) -> Result<Self, String> {
+        self.targets.push(target.to_path_buf());
+        let result = self.save_manifest().and_then(|()| {
+            storage::create_parent_dir(target)?;
+            let staged = self.staged_path(target);
+            fs::write(&staged, contents)
+                .map_err(|e| format!("Failed to write staged file '{}': {}", staged.display(), e))
+        });
+        match result {
+            Ok(()) => Ok(self),
+            Err(e) => Err(self.abort(e)),
+        }
+    }
+
+    /**
+     * @brief Move all staged files into place (commit phase).
+     *
+     * On failure every target is restored and the staged files are removed.
+     *
+     * @return Result<(), String> Ok once all targets are replaced, or an error message.
+     */
+    pub fn commit(mut self) -> Result<(), String> {
+        self.state = TransactionState::Committing;
+        if let Err(e) = self.save_manifest() {
+            return Err(self.abort(e));
+        }
+        if let Some(e) = self.targets.iter().find_map(|target| self.promote(target).err()) {
+            return Err(self.abort(e));
+        }
+
+        self.state = TransactionState::Committed;
+        if let Err(e) = self.save_manifest() {
+            return Err(self.abort(e));
+        }
+        self.finish();
+        log_debug!("staging:commit", "Committed transaction {} ({} files).", self.id, self.targets.len());
+        Ok(())
+    }
+
+    /**
+     * @brief Undo the transaction: remove staged files and restore replaced targets.
+     *
+     * @return Result<(), String> Ok, or an error message naming the files
+     *         that could not be restored.
+     */
+    pub fn rollback(self) -> Result<(), String> {
+        let mut problems = Vec::new();
+        for target in &self.targets {
+            if let Err(e) = self.restore(target) {
+                problems.push(e);
+            }
+        }
+        if !problems.is_empty() {
+            return Err(format!(
+                "Rolling back transaction {} failed, keeping its manifest {}: {}",
+                self.id,
+                self.manifest_path.display(),
+                problems.join("; ")
+            ));
+        }
+        remove_if_exists(&self.manifest_path)
+    }
+
+    /**
+     * @brief Roll back after a failure and describe both.
+     *
+     * @param error The failure that stopped the transaction.
+     * @return String The error message, including a failed rollback.
+     */
+    fn abort(self, error: String) -> String {
+        let id = self.id.clone();
+        match self.rollback() {
+            Ok(()) => format!("{} (transaction {} rolled back)", error, id),
+            Err(rollback_error) => format!("{}; {}", error, rollback_error),
+        }
+    }
+
+    /**
+     * @brief Replace one target by its staged file, keeping the old one as backup.
+     */
+    fn promote(&self, target: &Path) -> Result<(), String> {
+        if target.exists() {
+            let backup = self.backup_path(target);
+            fs::rename(target, &backup).map_err(|e| {
+                format!("Failed to move '{}' aside to '{}': {}", target.display(), backup.display(), e)
+            })?;
+        }
+        let staged = self.staged_path(target);
+        fs::rename(&staged, target).map_err(|e| {
+            format!("Failed to move data file into place at '{}': {}", target.display(), e)
+        })
+    }
+
+    /**
+     * @brief Undo whatever happened to one target.
+     *
+     * Before the commit phase only the staged file exists. During it, a
+     * backup means the target was moved aside (and possibly replaced), and a
+     * missing staged file without backup means a new file was put in place.
+     */
+    fn restore(&self, target: &Path) -> Result<(), String> {
+        let staged = self.staged_path(target);
+        let backup = self.backup_path(target);
+        if self.state != TransactionState::Prepared {
+            if backup.exists() {
+                fs::rename(&backup, target).map_err(|e| {
+                    format!("Failed to restore '{}' from '{}': {}", target.display(), backup.display(), e)
+                })?;
+            } else if !staged.exists() {
+                remove_if_exists(target)?;
+            }
+        }
+        remove_if_exists(&staged)
+    }
+
+    /**
+     * @brief Remove the backups and the manifest of a committed transaction.
+     *
+     * Failures are only logged; the data files are already in place.
+     */
+    fn finish(&self) {
+        let leftovers = self
+            .targets
+            .iter()
+            .map(|target| self.backup_path(target))
+            .chain(std::iter::once(self.manifest_path.clone()));
+        for path in leftovers {
+            if let Err(e) = remove_if_exists(&path) {
+                log_warning!("staging:finish", "{}", e);
+            }
+        }
+    }
+
+    /**
+     * @brief Write the manifest atomically.
+     */
+    fn save_manifest(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize transaction manifest: {}", e))?;
+        let tmp_path = self.manifest_path.with_extension("json.tmp");
+        fs::write(&tmp_path, json)
+            .and_then(|()| fs::rename(&tmp_path, &self.manifest_path))
+            .map_err(|e| {
+                format!("Failed to write transaction manifest '{}': {}", self.manifest_path.display(), e)
+            })
+    }
+
+    fn staged_path(&self, target: &Path) -> PathBuf {
+        storage::with_suffix(target, &format!(".staged-{}", self.id))
+    }
+
+    fn backup_path(&self, target: &Path) -> PathBuf {
+        storage::with_suffix(target, &format!(".backup-{}", self.id))
+    }
+}
+
+/**
+ * @brief Load the manifests left behind in a staging directory.
+ *
+ * @param staging_dir The staging directory.
+ * @return Result<Vec<Transaction>, String> The unfinished transactions, oldest
+ *         first (none if the directory does not exist), or an error message.
+ */
+pub fn pending_transactions(staging_dir: &Path) -> Result<Vec<Transaction>, String> {
+    let entries = match fs::read_dir(staging_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(format!("Failed to read staging directory '{}': {}", staging_dir.display(), e))
+        }
+    };
+    let mut transactions = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read transaction manifest '{}': {}", path.display(), e))?;
+        let mut transaction: Transaction = serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid transaction manifest '{}': {}", path.display(), e))?;
+        transaction.manifest_path = path;
+        transactions.push(transaction);
+    }
+    transactions.sort_by_key(|transaction| transaction.created_at);
+    Ok(transactions)
+}
+
+/**
+ * @brief Clean up after runs that died while saving.
+ *
+ * Transactions that were fully committed are finished; all others are
+ * rolled back, restoring the previous data files. Must not run while a
+ * pipeline run is saving.
+ *
+ * @param staging_dir The staging directory.
+ * @return Result<usize, String> The number of transactions cleaned up, or an
+ *         error message for the first one that could not be.
+ */
+pub fn recover(staging_dir: &Path) -> Result<usize, String> {
+    let transactions = pending_transactions(staging_dir)?;
+    let count = transactions.len();
+    for transaction in transactions {
+        log_info!(
+            "staging:recover",
+            "Transaction {} (pid {}, started {}) was left {:?}; {}.",
+            transaction.id,
+            transaction.pid,
+            transaction.created_at,
+            transaction.state,
+            if transaction.state == TransactionState::Committed { "finishing it" } else { "rolling it back" }
+        );
+        if transaction.state == TransactionState::Committed {
+            transaction.finish();
+        } else {
+            transaction.rollback()?;
+        }
+    }
+    Ok(count)
+}
+
+/**
+ * @brief Remove a file, treating a missing file as success.
+ */
+fn remove_if_exists(path: &Path) -> Result<(), String> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove '{}': {}", path.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    /// The names of the files left in `dir` (not in its subdirectories).
+    fn files_in(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Stage new contents for an existing file `a` and a new file `b`.
+    fn staged(dir: &Path) -> (Transaction, PathBuf, PathBuf) {
+        let (a, b) = (dir.join("a.json"), dir.join("b.json"));
+        fs::write(&a, "old a").unwrap();
+        let transaction = Transaction::begin(&dir.join("staging")).unwrap();
+        let transaction = transaction.stage(&a, b"new a").unwrap().stage(&b, b"new b").unwrap();
+        (transaction, a, b)
+    }
+
+    #[test]
+    fn commit_replaces_every_target_and_cleans_up() {
+        let dir = TestDir::new("staging-commit");
+        let (transaction, a, b) = staged(&dir);
+
+        transaction.commit().unwrap();
+
+        assert_eq!(fs::read_to_string(&a).unwrap(), "new a");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "new b");
+        assert_eq!(files_in(&dir), ["a.json", "b.json"]);
+        assert!(files_in(&dir.join("staging")).is_empty());
+    }
+
+    #[test]
+    fn rollback_leaves_the_targets_as_they_were() {
+        let dir = TestDir::new("staging-rollback");
+        let (transaction, a, _) = staged(&dir);
+
+        transaction.rollback().unwrap();
+
+        assert_eq!(fs::read_to_string(&a).unwrap(), "old a");
+        assert_eq!(files_in(&dir), ["a.json"]);
+        assert!(files_in(&dir.join("staging")).is_empty());
+    }
+
+    #[test]
+    fn promote_failing_partway_restores_the_targets_already_replaced() {
+        let dir = TestDir::new("staging-failed-promote");
+        let (transaction, a, b) = staged(&dir);
+        fs::write(&b, "old b").unwrap();
+        // b's staged file is lost, so its promotion fails after a's succeeded
+        fs::remove_file(transaction.staged_path(&b)).unwrap();
+
+        let result = transaction.commit();
+
+        assert!(matches!(&result, Err(message) if message.contains("rolled back")), "{:?}", result);
+        assert_eq!(fs::read_to_string(&a).unwrap(), "old a");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "old b");
+        assert_eq!(files_in(&dir), ["a.json", "b.json"]);
+        assert!(files_in(&dir.join("staging")).is_empty());
+    }
+
+    #[test]
+    fn recover_rolls_back_a_prepared_transaction() {
+        let dir = TestDir::new("staging-recover-prepared");
+        // The run died after staging, before committing
+        let (_, a, _) = staged(&dir);
+
+        assert_eq!(recover(&dir.join("staging")).unwrap(), 1);
+
+        assert_eq!(fs::read_to_string(&a).unwrap(), "old a");
+        assert_eq!(files_in(&dir), ["a.json"]);
+        assert!(files_in(&dir.join("staging")).is_empty());
+    }
+
+    #[test]
+    fn recover_rolls_back_a_transaction_that_died_while_committing() {
+        let dir = TestDir::new("staging-recover-committing");
+        let (mut transaction, a, b) = staged(&dir);
+        // The run died after promoting both files, before recording the commit
+        transaction.state = TransactionState::Committing;
+        transaction.save_manifest().unwrap();
+        transaction.promote(&a).unwrap();
+        transaction.promote(&b).unwrap();
+
+        assert_eq!(recover(&dir.join("staging")).unwrap(), 1);
+
+        assert_eq!(fs::read_to_string(&a).unwrap(), "old a");
+        assert_eq!(files_in(&dir), ["a.json"]);
+        assert!(files_in(&dir.join("staging")).is_empty());
+    }
+
+    #[test]
+    fn recover_finishes_a_committed_transaction() {
+        let dir = TestDir::new("staging-recover-committed");
+        let (mut transaction, a, b) = staged(&dir);
+        // The run died after recording the commit, before removing the backups
+        transaction.state = TransactionState::Committing;
+        transaction.promote(&a).unwrap();
+        transaction.promote(&b).unwrap();
+        transaction.state = TransactionState::Committed;
+        transaction.save_manifest().unwrap();
+
+        assert_eq!(recover(&dir.join("staging")).unwrap(), 1);
+
+        assert_eq!(fs::read_to_string(&a).unwrap(), "new a");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "new b");
+        assert_eq!(files_in(&dir), ["a.json", "b.json"]);
+        assert!(files_in(&dir.join("staging")).is_empty());
+    }
+}
+
+// End of rust_sample_project/src/staging.rs
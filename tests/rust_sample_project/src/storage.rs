@@ -0,0 +1,278 @@
+// rust_sample_project/src/storage.rs
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::batch::SaveBatch;
+
+#[cfg(feature = "http")]
+use crate::http_store::HttpStore;
+#[cfg(feature = "s3")]
+use crate::s3_store::S3Store;
+
+/**
+ * @brief Storage for the raw contents of an items data file.
+ *
+ * DataHandler takes care of formats and compression; an ItemStore only moves
+ * the bytes of one data file to and from where it lives (local disk, an HTTP
+ * endpoint, an S3 bucket, ...).
+ */
+pub trait ItemStore {
+    /**
+     * @brief Return the location of the data file, as configured.
+     *
+     * @return &str The path or URL, used for format detection and in messages.
+     */
+    fn location(&self) -> &str;
+
+    /**
+     * @brief Check whether the data file exists yet.
+     *
+     * @return Result<bool, String> True if the file can be read, or an error message.
+     */
+    fn exists(&self) -> Result<bool, String>;
+
+    /**
+     * @brief Check that the data file's location can be reached, without
+     *        reading it (see the /readyz endpoint of the long-running modes).
+     *
+     * @return Result<bool, String> Whether the file exists, or an error message
+     *         if the location cannot be reached.
+     */
+    fn probe(&self) -> Result<bool, String> {
+        self.exists()
+    }
+
+    /**
+     * @brief Read the whole data file.
+     *
+     * @return Result<Vec<u8>, String> The raw file contents, or an error message.
+     */
+    fn read(&self) -> Result<Vec<u8>, String>;
+
+    /**
+     * @brief Replace the data file with new contents.
+     *
+     * @param contents The raw file contents to store.
+     * @return Result<(), String> Ok if the contents were stored, or an error message.
+     */
+    fn write(&self, contents: &[u8]) -> Result<(), String>;
+
+    /**
+     * @brief Return the path of the data file if it is on the local file system.
+     *
+     * @return Option<&Path> The local path, or None for remote stores.
+     */
+    fn local_path(&self) -> Option<&Path> {
+        None
+    }
+
+    /**
+     * @brief Check whether the store takes a save as several batch writes.
+     *
+     * Record-oriented sinks (REST endpoints, databases) accept batches; a data
+     * file has to be replaced as a whole.
+     *
+     * @return bool True if write_batch stores a part of a save.
+     */
+    fn accepts_batches(&self) -> bool {
+        false
+    }
+
+    /**
+     * @brief Store one batch of a save (see batch::BatchWriter).
+     *
+     * Only called when accepts_batches returns true; by default a batch
+     * replaces the stored contents like write.
+     *
+     * @param batch Which part of the save the contents hold.
+     * @param contents A data file document holding the batch's items.
+     * @return Result<(), String> Ok if the batch was stored, or an error message.
+     */
+    fn write_batch(&self, batch: &SaveBatch, contents: &[u8]) -> Result<(), String> {
+        let _ = batch;
+        self.write(contents)
+    }
+}
+
+/**
+ * @struct FileStore
+ * @brief An items data file on the local file system.
+ */
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    path: PathBuf,
+    /// The path as text, for messages and format detection.
+    location: String,
+}
+
+impl FileStore {
+    /**
+     * @brief Constructs a store for a local file.
+     *
+     * @param path Path of the data file (need not be valid UTF-8).
+     * @return FileStore The store.
+     */
+    pub fn new(path: &Path) -> Self {
+        FileStore {
+            path: path.to_path_buf(),
+            location: path.display().to_string(),
+        }
+    }
+}
+
+impl ItemStore for FileStore {
+    fn location(&self) -> &str {
+        &self.location
+    }
+
+    fn local_path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    fn exists(&self) -> Result<bool, String> {
+        Ok(self.path.exists())
+    }
+
+    fn read(&self) -> Result<Vec<u8>, String> {
+        fs::read(&self.path).map_err(|e| format!("Failed to read data file '{}': {}", self.location, e))
+    }
+
+    /**
+     * @brief Write the file atomically.
+     *
+     * Missing parent directories are created. The contents go to a temporary
+     * file first, which is then renamed, so an interrupted save never
+     * truncates the data file.
+     */
+    fn write(&self, contents: &[u8]) -> Result<(), String> {
+        create_parent_dir(&self.path)?;
+        let tmp_path = with_suffix(&self.path, ".tmp");
+        fs::write(&tmp_path, contents)
+            .map_err(|e| format!("Failed to write data file '{}': {}", tmp_path.display(), e))?;
+        fs::rename(&tmp_path, &self.path).map_err(|e| {
+            format!("Failed to move data file into place at '{}': {}", self.location, e)
+        })
+    }
+}
+
+/**
+ * @brief Append a suffix to a path's file name, e.g. "items.json" + ".tmp".
+ *
+ * @param path The path.
+ * @param suffix The text to append.
+ * @return PathBuf The path with the suffix, kept as is if not valid UTF-8.
+ */
+pub fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/**
+ * @brief Create the missing parent directories of a file about to be written.
+ *
+ * @param path The file.
+ * @return Result<(), String> Ok once the parent directory exists, or an error message.
+ */
+pub fn create_parent_dir(path: &Path) -> Result<(), String> {
+    match path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        Some(parent) => fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory '{}': {}", parent.display(), e)),
+        None => Ok(()),
+    }
+}
+
+/**
+ * @brief Make a local path absolute, resolving symbolic links and "..".
+ *
+ * The file itself need not exist yet: its nearest existing ancestor is
+ * canonicalized and the rest of the path is appended. URLs, and paths whose
+ * ancestors cannot be resolved, are returned unchanged.
+ *
+ * @param location The configured path or URL.
+ * @return PathBuf The canonical path, or the location as given.
+ */
+pub fn canonical_path(location: &Path) -> PathBuf {
+    if location.to_str().is_some_and(is_remote) || location.as_os_str().is_empty() {
+        return location.to_path_buf();
+    }
+    let mut missing = Vec::new();
+    let mut existing = location;
+    loop {
+        let ancestor = if existing.as_os_str().is_empty() { Path::new(".") } else { existing };
+        if let Ok(canonical) = fs::canonicalize(ancestor) {
+            return missing.iter().rev().fold(canonical, |path, name| path.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return location.to_path_buf(),
+        }
+    }
+}
+
+/**
+ * @brief Check whether a data location is a URL rather than a local path.
+ *
+ * @param location The configured path or URL.
+ * @return bool True for locations with a scheme, e.g. "https://...".
+ */
+pub fn is_remote(location: &str) -> bool {
+    location.contains("://")
+}
+
+/**
+ * @brief Open the store for a data location.
+ *
+ * @param location A local path (need not be valid UTF-8), an http(s) URL (requires the `http` feature),
+ *        an s3://bucket/key location (requires the `s3` feature) or a
+ *        mock://name location of a registered MockItemStore (requires the
+ *        `test-util` feature).
+ * @return Result<Box<dyn ItemStore>, String> The store, or an error message
+ *         for unsupported locations.
+ */
+pub fn open(location: &Path) -> Result<Box<dyn ItemStore>, String> {
+    match location.to_str() {
+        Some(text) => open_location(text),
+        // Only local paths can be other than UTF-8
+        None => Ok(Box::new(FileStore::new(location))),
+    }
+}
+
+/**
+ * @brief Open the store for a data location given as text.
+ */
+fn open_location(location: &str) -> Result<Box<dyn ItemStore>, String> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        #[cfg(feature = "http")]
+        return Ok(Box::new(HttpStore::from_config(location)));
+        #[cfg(not(feature = "http"))]
+        return Err(format!(
+            "Data source '{}' is an HTTP URL, but this build lacks the 'http' feature",
+            location
+        ));
+    }
+    if location.starts_with("s3://") {
+        #[cfg(feature = "s3")]
+        return Ok(Box::new(S3Store::from_location(location)?));
+        #[cfg(not(feature = "s3"))]
+        return Err(format!(
+            "Data source '{}' is an S3 location, but this build lacks the 's3' feature",
+            location
+        ));
+    }
+    #[cfg(feature = "test-util")]
+    if location.starts_with(crate::test_util::MOCK_SCHEME) {
+        return Ok(Box::new(crate::test_util::open_mock(location)?));
+    }
+    if is_remote(location) {
+        return Err(format!("Unsupported data source scheme in '{}'", location));
+    }
+    Ok(Box::new(FileStore::new(Path::new(location))))
+}
+
+// End of rust_sample_project/src/storage.rs
@@ -0,0 +1,368 @@
+// rust_sample_project/src/telemetry.rs
+
+// Export of the spans of pipeline runs (see pipeline.rs) to an OpenTelemetry
+// collector, for the binary; embedding services see the spans through their
+// own tracing subscriber instead. OtlpSubscriber records the spans and, when
+// a run's root span closes (or many spans are waiting), sends them to
+// otel_endpoint as OTLP/HTTP JSON:
+//
+//     POST http://localhost:4318/v1/traces
+//     {"resourceSpans": [{"resource": {...}, "scopeSpans": [{"spans": [...]}]}]}
+//
+// If the TRACEPARENT environment variable holds a W3C trace context (as set
+// by a scheduler or a parent service), runs become children of that span, so
+// they show up in the existing distributed trace. Spans that cannot be
+// delivered are logged and dropped.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use tracing_core::span::Current;
+
+use crate::config::get_http_timeout_secs;
+
+/// Environment variable holding the W3C trace context runs join.
+pub const TRACEPARENT_ENV: &str = "TRACEPARENT";
+
+/// Number of finished spans after which they are sent without waiting for
+/// the run to end, so long runs do not pile up item spans.
+const MAX_BUFFERED_SPANS: usize = 512;
+
+/// OTLP span kind "internal".
+const SPAN_KIND_INTERNAL: u8 = 1;
+
+/// OTLP status codes.
+const STATUS_UNSET: u8 = 0;
+const STATUS_ERROR: u8 = 2;
+
+thread_local! {
+    /// The spans entered on this thread, innermost last.
+    static ENTERED: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+/**
+ * @brief A span that has not closed yet.
+ */
+struct OpenSpan {
+    metadata: &'static Metadata<'static>,
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_span_id: Option<[u8; 8]>,
+    start: SystemTime,
+    /// Fields recorded so far, as OTLP attribute values.
+    attributes: Vec<(&'static str, Value)>,
+    /// Number of handles to the span.
+    references: usize,
+}
+
+/**
+ * @brief Collects field values as OTLP attribute values.
+ */
+struct AttributeVisitor<'a>(&'a mut Vec<(&'static str, Value)>);
+
+impl AttributeVisitor<'_> {
+    fn set(&mut self, field: &Field, value: Value) {
+        match self.0.iter_mut().find(|(name, _)| *name == field.name()) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((field.name(), value)),
+        }
+    }
+}
+
+impl Visit for AttributeVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.set(field, json!({ "stringValue": format!("{:?}", value) }));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, json!({ "stringValue": value }));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field, json!({ "intValue": value.to_string() }));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field, json!({ "intValue": value.to_string() }));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.set(field, json!({ "doubleValue": value }));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field, json!({ "boolValue": value }));
+    }
+}
+
+/**
+ * @struct OtlpSubscriber
+ * @brief A tracing subscriber sending spans to an OTLP/HTTP endpoint.
+ *
+ * Only spans are recorded; events are ignored (the pipeline logs through
+ * the logging module).
+ */
+pub struct OtlpSubscriber {
+    endpoint: String,
+    service_name: String,
+    agent: ureq::Agent,
+    /// Trace and span id of the remote parent of root spans (TRACEPARENT).
+    remote_parent: Option<([u8; 16], [u8; 8])>,
+    next_id: AtomicU64,
+    open: Mutex<HashMap<u64, OpenSpan>>,
+    /// Finished spans not sent yet, encoded as OTLP JSON.
+    finished: Mutex<Vec<Value>>,
+}
+
+impl OtlpSubscriber {
+    /**
+     * @brief Constructs a subscriber for an endpoint.
+     *
+     * @param endpoint The OTLP/HTTP traces URL.
+     * @param service_name The service name of the spans.
+     * @param traceparent A W3C trace context root spans become children of, if any.
+     * @return Result<OtlpSubscriber, String> The subscriber, or an error
+     *         message for a malformed trace context.
+     */
+    pub fn new(endpoint: &str, service_name: &str, traceparent: Option<&str>) -> Result<Self, String> {
+        let remote_parent = traceparent.map(parse_traceparent).transpose()?;
+        Ok(OtlpSubscriber {
+            endpoint: endpoint.to_string(),
+            service_name: service_name.to_string(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(get_http_timeout_secs()))
+                .build(),
+            remote_parent,
+            next_id: AtomicU64::new(1),
+            open: Mutex::new(HashMap::new()),
+            finished: Mutex::new(Vec::new()),
+        })
+    }
+
+    /**
+     * @brief Send the finished spans to the endpoint.
+     *
+     * @return Result<(), String> Ok once sent (or if there were none), or an error message.
+     */
+    pub fn flush(&self) -> Result<(), String> {
+        let spans = std::mem::take(&mut *self.finished.lock().unwrap_or_else(|e| e.into_inner()));
+        if spans.is_empty() {
+            return Ok(());
+        }
+        let count = spans.len();
+        let body = json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [{ "key": "service.name", "value": { "stringValue": self.service_name } }],
+                },
+                "scopeSpans": [{
+                    "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans,
+                }],
+            }],
+        });
+        self.agent
+            .post(&self.endpoint)
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())
+            .map_err(|e| format!("Failed to export {} spans to '{}': {}", count, self.endpoint, e))?;
+        log_debug!("telemetry:flush", "Exported {} spans to {}.", count, self.endpoint);
+        Ok(())
+    }
+
+    /**
+     * @brief Encode a closed span and queue it, sending the queue when the
+     *        run ends or it is full.
+     */
+    fn finish(&self, span: OpenSpan) {
+        let is_root = span.parent_span_id.is_none()
+            || self.remote_parent.is_some_and(|(_, parent)| span.parent_span_id == Some(parent));
+        let failed = span.attributes.iter().any(|(name, _)| *name == "error");
+        let encoded = json!({
+            "traceId": hex(&span.trace_id),
+            "spanId": hex(&span.span_id),
+            "parentSpanId": span.parent_span_id.map(|id| hex(&id)).unwrap_or_default(),
+            "name": span.metadata.name(),
+            "kind": SPAN_KIND_INTERNAL,
+            "startTimeUnixNano": unix_nanos(span.start),
+            "endTimeUnixNano": unix_nanos(SystemTime::now()),
+            "attributes": span
+                .attributes
+                .iter()
+                .map(|(name, value)| json!({ "key": name, "value": value }))
+                .collect::<Vec<Value>>(),
+            "status": { "code": if failed { STATUS_ERROR } else { STATUS_UNSET } },
+        });
+        let queued = {
+            let mut finished = self.finished.lock().unwrap_or_else(|e| e.into_inner());
+            finished.push(encoded);
+            finished.len()
+        };
+        if is_root || queued >= MAX_BUFFERED_SPANS {
+            if let Err(e) = self.flush() {
+                log_warning!("telemetry:finish", "{}", e);
+            }
+        }
+    }
+}
+
+impl Subscriber for OtlpSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.is_span()
+    }
+
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+        let parent = if attributes.is_contextual() {
+            ENTERED.with(|entered| entered.borrow().last().copied())
+        } else {
+            attributes.parent().map(Id::into_u64)
+        };
+        let mut open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+        let (trace_id, parent_span_id) = match parent.and_then(|parent| open.get(&parent)) {
+            Some(parent) => (parent.trace_id, Some(parent.span_id)),
+            None => match self.remote_parent {
+                Some((trace_id, span_id)) => (trace_id, Some(span_id)),
+                None => (rand::random::<u128>().to_be_bytes(), None),
+            },
+        };
+        let mut span = OpenSpan {
+            metadata: attributes.metadata(),
+            trace_id,
+            span_id: rand::random::<u64>().max(1).to_be_bytes(),
+            parent_span_id,
+            start: SystemTime::now(),
+            attributes: Vec::new(),
+            references: 1,
+        };
+        attributes.record(&mut AttributeVisitor(&mut span.attributes));
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        open.insert(id, span);
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        if let Some(span) = self.open.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&span.into_u64()) {
+            values.record(&mut AttributeVisitor(&mut span.attributes));
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, span: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        ENTERED.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            if let Some(position) = entered.iter().rposition(|id| *id == span.into_u64()) {
+                entered.remove(position);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        if let Some(span) = self.open.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&span.into_u64()) {
+            span.references += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let closed = {
+            let mut open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+            match open.get_mut(&span.into_u64()) {
+                Some(open_span) if open_span.references > 1 => {
+                    open_span.references -= 1;
+                    None
+                }
+                Some(_) => open.remove(&span.into_u64()),
+                None => None,
+            }
+        };
+        match closed {
+            Some(closed) => {
+                self.finish(closed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn current_span(&self) -> Current {
+        let Some(id) = ENTERED.with(|entered| entered.borrow().last().copied()) else {
+            return Current::none();
+        };
+        match self.open.lock().unwrap_or_else(|e| e.into_inner()).get(&id) {
+            Some(span) => Current::new(Id::from_u64(id), span.metadata),
+            None => Current::none(),
+        }
+    }
+}
+
+/**
+ * @brief Parse a W3C trace context, "00-<trace id>-<parent id>-<flags>".
+ *
+ * @param traceparent The trace context.
+ * @return Result<([u8; 16], [u8; 8]), String> The trace id and parent span
+ *         id, or an error message.
+ */
+pub fn parse_traceparent(traceparent: &str) -> Result<([u8; 16], [u8; 8]), String> {
+    let invalid = || format!("Invalid trace context '{}' (expected 00-<trace id>-<span id>-<flags>)", traceparent);
+    let parts: Vec<&str> = traceparent.trim().split('-').collect();
+    let [_version, trace_id, span_id, _flags] = parts[..] else {
+        return Err(invalid());
+    };
+    let trace_id = u128::from_str_radix(trace_id, 16).ok().filter(|id| *id != 0 && trace_id.len() == 32);
+    let span_id = u64::from_str_radix(span_id, 16).ok().filter(|id| *id != 0 && span_id.len() == 16);
+    match (trace_id, span_id) {
+        (Some(trace_id), Some(span_id)) => Ok((trace_id.to_be_bytes(), span_id.to_be_bytes())),
+        _ => Err(invalid()),
+    }
+}
+
+/**
+ * @brief Format bytes as lowercase hex.
+ */
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/**
+ * @brief Return a time as nanoseconds since the Unix epoch, as OTLP JSON writes them.
+ */
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_string()
+}
+
+/**
+ * @brief Export the spans of all pipeline runs of this process.
+ *
+ * Installs an OtlpSubscriber as the global tracing subscriber; runs join
+ * the trace context in TRACEPARENT, if set.
+ *
+ * @param endpoint The OTLP/HTTP traces URL (otel_endpoint).
+ * @param service_name The service name of the spans (otel_service_name).
+ * @return Result<(), String> Ok, or an error message if the trace context is
+ *         malformed or another subscriber is installed.
+ */
+pub fn install(endpoint: &str, service_name: &str) -> Result<(), String> {
+    let traceparent = std::env::var(TRACEPARENT_ENV).ok().filter(|value| !value.trim().is_empty());
+    let subscriber = OtlpSubscriber::new(endpoint, service_name, traceparent.as_deref())?;
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| format!("Failed to install the span exporter: {}", e))?;
+    log_info!("telemetry:install", "Exporting the spans of pipeline runs to {}.", endpoint);
+    Ok(())
+}
+
+// End of rust_sample_project/src/telemetry.rs
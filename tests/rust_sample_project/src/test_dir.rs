@@ -0,0 +1,59 @@
+// rust_sample_project/src/test_dir.rs
+
+// Scratch directories for the unit tests that work with files. Each test gets
+// an empty directory of its own under the system temp dir, named after the
+// test and the process, and the directory is removed again when the test ends,
+// whether it passed or failed.
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/**
+ * @struct TestDir
+ * @brief An empty directory for the files of one test, removed on drop.
+ *
+ * Derefs to its Path, so `dir.join("items.json")` and `&dir` work as with a
+ * plain path.
+ */
+#[derive(Debug)]
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /**
+     * @brief Create the directory, replacing any left over from an earlier run.
+     *
+     * @param name Names the directory; unique per test.
+     * @return TestDir The directory.
+     */
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("rust_sample_project-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TestDir { path }
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TestDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+// End of rust_sample_project/src/test_dir.rs
@@ -0,0 +1,445 @@
+// rust_sample_project/src/test_util.rs
+
+// Helpers for testing code built on this crate (enabled by the `test-util`
+// feature):
+// - proptest strategies producing items and item lists that satisfy
+//   Item::invariants, for property-testing custom processors and storage
+//   backends against the same invariants as the pipeline itself;
+// - MockItemStore, an in-memory ItemStore that records its calls and can be
+//   told to fail. Registered mocks are returned by storage::open for
+//   "mock://<name>" locations, so a DataHandler (and with it the whole
+//   pipeline) can run without touching the file system.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use chrono::{DateTime, Duration, Utc};
+use proptest::collection::{btree_set, vec};
+use proptest::prelude::*;
+use serde_json::json;
+
+use crate::batch::SaveBatch;
+use crate::data_handler::CURRENT_SCHEMA_VERSION;
+use crate::datagen::{self, DatagenOptions, ValueDistribution};
+use crate::item::{Item, ItemStatus};
+use crate::item_id::ItemId;
+use crate::money::Money;
+use crate::storage::ItemStore;
+
+/// Location scheme served by registered mock stores.
+pub const MOCK_SCHEME: &str = "mock://";
+
+/// Range of created_at timestamps (seconds since the epoch, 2000 to 2100).
+const TIMESTAMP_RANGE_SECS: std::ops::Range<i64> = 946_684_800..4_102_444_800;
+
+/**
+ * @brief Strategy for item tags: short, non-empty, lowercase labels.
+ *
+ * @return impl Strategy<Value = String> The tag strategy.
+ */
+pub fn arb_tag() -> impl Strategy<Value = String> {
+    "[a-z][a-z_]{0,15}"
+}
+
+/**
+ * @brief Strategy for item values at two decimals, including negatives and
+ *        values around the default threshold.
+ *
+ * @return impl Strategy<Value = Money> The value strategy.
+ */
+pub fn arb_value() -> impl Strategy<Value = Money> {
+    prop_oneof![-100_000..100_000i64, 9_900..10_100i64, Just(0), Just(10_000)]
+        .prop_map(|minor| Money::from_minor(minor, 2))
+}
+
+/**
+ * @brief Strategy for a timestamp between the years 2000 and 2100.
+ *
+ * @return impl Strategy<Value = DateTime<Utc>> The timestamp strategy.
+ */
+pub fn arb_timestamp() -> impl Strategy<Value = DateTime<Utc>> {
+    TIMESTAMP_RANGE_SECS.prop_map(|secs| DateTime::from_timestamp(secs, 0).unwrap_or_default())
+}
+
+/**
+ * @brief Strategy for a single item, processed or not.
+ *
+ * Every generated item satisfies Item::invariants; processed items have a
+ * processed_at no earlier than their created_at.
+ *
+ * @param item_id The ID given to the item.
+ * @return impl Strategy<Value = Item> The item strategy.
+ */
+pub fn arb_item_with_id(item_id: ItemId) -> impl Strategy<Value = Item> {
+    (
+        "[A-Za-z][A-Za-z0-9 ]{0,23}",
+        arb_value(),
+        btree_set(arb_tag(), 0..4),
+        arb_timestamp(),
+        proptest::option::of(0..86_400i64),
+    )
+        .prop_map(move |(name, value, tags, created_at, processed_after)| {
+            let mut item = Item::new(item_id.clone(), name, value);
+            item.tags = tags.into_iter().collect();
+            item.created_at = created_at;
+            if let Some(secs) = processed_after {
+                item.status = ItemStatus::Processed;
+                item.processed_at = Some(created_at + Duration::seconds(secs));
+            }
+            item
+        })
+}
+
+/**
+ * @brief Strategy for a single item with an arbitrary positive ID.
+ *
+ * @return impl Strategy<Value = Item> The item strategy.
+ */
+pub fn arb_item() -> impl Strategy<Value = Item> {
+    (1..i64::MAX).prop_flat_map(|id| arb_item_with_id(ItemId::Int(id)))
+}
+
+/**
+ * @brief Strategy for a list of items with unique IDs 1..=len, as loaded
+ *        from a data file.
+ *
+ * @param max_len The largest list generated.
+ * @return impl Strategy<Value = Vec<Item>> The item list strategy.
+ */
+pub fn arb_items(max_len: usize) -> impl Strategy<Value = Vec<Item>> {
+    (0..=max_len).prop_flat_map(|len| {
+        (1..=len as i64)
+            .map(|id| arb_item_with_id(ItemId::Int(id)))
+            .collect::<Vec<_>>()
+    })
+}
+
+/**
+ * @brief Strategy for a list of items that may contain duplicate IDs, for
+ *        exercising deduplication and merge logic.
+ *
+ * @param max_len The largest list generated.
+ * @return impl Strategy<Value = Vec<Item>> The item list strategy.
+ */
+pub fn arb_items_with_duplicates(max_len: usize) -> impl Strategy<Value = Vec<Item>> {
+    vec(1..=(max_len.max(1) as i64), 0..=max_len).prop_flat_map(|ids| {
+        ids.into_iter().map(|id| arb_item_with_id(ItemId::Int(id))).collect::<Vec<_>>()
+    })
+}
+
+/**
+ * @enum StoreCall
+ * @brief A call made to a MockItemStore, as recorded by it.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreCall {
+    /// ItemStore::exists was called.
+    Exists,
+    /// ItemStore::read was called.
+    Read,
+    /// ItemStore::write was called with this many bytes.
+    Write(usize),
+    /// ItemStore::write_batch was called for this batch number with this many bytes.
+    WriteBatch(usize, usize),
+}
+
+/**
+ * @struct MockState
+ * @brief The shared state behind a MockItemStore and its clones.
+ */
+#[derive(Debug, Default)]
+struct MockState {
+    contents: Option<Vec<u8>>,
+    calls: Vec<StoreCall>,
+    fail_on_call: Option<usize>,
+    accepts_batches: bool,
+}
+
+/**
+ * @struct MockItemStore
+ * @brief An in-memory ItemStore for tests.
+ *
+ * Clones share their state, so a test can keep a handle to a store it has
+ * registered and inspect the calls and contents after the code under test
+ * ran.
+ */
+#[derive(Debug, Clone)]
+pub struct MockItemStore {
+    location: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockItemStore {
+    /**
+     * @brief Constructs an empty store (the data file does not exist yet).
+     *
+     * @param location The location reported by the store, e.g. "mock://items.json";
+     *        its extension selects the data format as for real files.
+     * @return MockItemStore The store.
+     */
+    pub fn new(location: &str) -> Self {
+        MockItemStore {
+            location: location.to_string(),
+            state: Arc::new(Mutex::new(MockState::default())),
+        }
+    }
+
+    /**
+     * @brief Seed the store with raw data file contents.
+     *
+     * @param contents The bytes returned by read.
+     * @return MockItemStore The store, for chaining.
+     */
+    pub fn with_contents(self, contents: &[u8]) -> Self {
+        self.lock().contents = Some(contents.to_vec());
+        self
+    }
+
+    /**
+     * @brief Seed the store with items, stored as a current-schema JSON data file.
+     *
+     * @param items The items returned when the store is loaded.
+     * @return MockItemStore The store, for chaining.
+     */
+    pub fn with_items(self, items: &[Item]) -> Self {
+        let document = json!({ "schema_version": CURRENT_SCHEMA_VERSION, "items": items });
+        self.with_contents(document.to_string().as_bytes())
+    }
+
+    /**
+     * @brief Make the store accept batched saves, like a database table.
+     *
+     * Each batch appends its items to the stored items document, so items()
+     * returns everything stored so far.
+     *
+     * @return MockItemStore The store, for chaining.
+     */
+    pub fn with_batches(self) -> Self {
+        self.lock().accepts_batches = true;
+        self
+    }
+
+    /**
+     * @brief Make the Nth call to the store fail (1-based, counting all calls).
+     *
+     * @param call The number of the call that fails.
+     * @return MockItemStore The store, for chaining.
+     */
+    pub fn fail_on_call(self, call: usize) -> Self {
+        self.lock().fail_on_call = Some(call);
+        self
+    }
+
+    /**
+     * @brief Make storage::open return this store (or a clone of it) for its location.
+     *
+     * @return Result<(), String> Ok, or an error message if the location does
+     *         not use the mock:// scheme.
+     */
+    pub fn register(&self) -> Result<(), String> {
+        if !self.location.starts_with(MOCK_SCHEME) {
+            return Err(format!(
+                "Mock store location '{}' must start with {}",
+                self.location, MOCK_SCHEME
+            ));
+        }
+        registry()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(self.location.clone(), self.clone());
+        Ok(())
+    }
+
+    /**
+     * @brief Return the calls made to the store so far, in order.
+     *
+     * @return Vec<StoreCall> The recorded calls.
+     */
+    pub fn calls(&self) -> Vec<StoreCall> {
+        self.lock().calls.clone()
+    }
+
+    /**
+     * @brief Return the current contents of the store.
+     *
+     * @return Option<Vec<u8>> The stored bytes, or None if nothing was stored.
+     */
+    pub fn contents(&self) -> Option<Vec<u8>> {
+        self.lock().contents.clone()
+    }
+
+    /**
+     * @brief Parse the current contents as a JSON data file.
+     *
+     * @return Result<Vec<Item>, String> The stored items, or an error message
+     *         if the store is empty or holds no valid items document.
+     */
+    pub fn items(&self) -> Result<Vec<Item>, String> {
+        let contents = self
+            .contents()
+            .ok_or_else(|| format!("Mock store '{}' is empty", self.location))?;
+        let document: serde_json::Value = serde_json::from_slice(&contents)
+            .map_err(|e| format!("Mock store '{}' holds invalid JSON: {}", self.location, e))?;
+        serde_json::from_value(document["items"].clone())
+            .map_err(|e| format!("Mock store '{}' holds invalid items: {}", self.location, e))
+    }
+
+    /**
+     * @brief Lock the shared state, ignoring poisoning by a panicked test.
+     */
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /**
+     * @brief Record a call and fail it if it is the programmed one.
+     *
+     * @param call The call being made.
+     * @return Result<MutexGuard<MockState>, String> The locked state, or the
+     *         injected error.
+     */
+    fn record(&self, call: StoreCall) -> Result<std::sync::MutexGuard<'_, MockState>, String> {
+        let mut state = self.lock();
+        state.calls.push(call);
+        if state.fail_on_call == Some(state.calls.len()) {
+            return Err(format!(
+                "Injected failure on call #{} to mock store '{}'",
+                state.calls.len(),
+                self.location
+            ));
+        }
+        Ok(state)
+    }
+}
+
+impl ItemStore for MockItemStore {
+    fn location(&self) -> &str {
+        &self.location
+    }
+
+    fn exists(&self) -> Result<bool, String> {
+        Ok(self.record(StoreCall::Exists)?.contents.is_some())
+    }
+
+    fn read(&self) -> Result<Vec<u8>, String> {
+        self.record(StoreCall::Read)?
+            .contents
+            .clone()
+            .ok_or_else(|| format!("Failed to read data file '{}': not found", self.location))
+    }
+
+    fn write(&self, contents: &[u8]) -> Result<(), String> {
+        self.record(StoreCall::Write(contents.len()))?.contents = Some(contents.to_vec());
+        Ok(())
+    }
+
+    fn accepts_batches(&self) -> bool {
+        self.lock().accepts_batches
+    }
+
+    fn write_batch(&self, batch: &SaveBatch, contents: &[u8]) -> Result<(), String> {
+        let invalid = |e: serde_json::Error| format!("Invalid batch for mock store '{}': {}", self.location, e);
+        let document: serde_json::Value = serde_json::from_slice(contents).map_err(invalid)?;
+        let batch_items: Vec<Item> = serde_json::from_value(document["items"].clone()).map_err(invalid)?;
+        let mut stored = if self.lock().contents.is_some() { self.items()? } else { Vec::new() };
+        stored.extend(batch_items);
+
+        let merged = json!({ "schema_version": CURRENT_SCHEMA_VERSION, "items": stored });
+        self.record(StoreCall::WriteBatch(batch.number, contents.len()))?.contents =
+            Some(merged.to_string().into_bytes());
+        Ok(())
+    }
+}
+
+/**
+ * @brief The registered mock stores, by location.
+ */
+fn registry() -> &'static Mutex<HashMap<String, MockItemStore>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, MockItemStore>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/**
+ * @brief Look up the registered mock store for a mock:// location.
+ *
+ * Used by storage::open.
+ *
+ * @param location The mock:// location.
+ * @return Result<MockItemStore, String> A handle to the store, or an error
+ *         message if no store is registered there.
+ */
+pub fn open_mock(location: &str) -> Result<MockItemStore, String> {
+    registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(location)
+        .cloned()
+        .ok_or_else(|| format!("No mock store registered for '{}'", location))
+}
+
+/**
+ * @brief A small, fixed list of valid items for tests.
+ *
+ * @param count Number of items; their IDs are 1..=count.
+ * @return Vec<Item> The same items for the same count on every call.
+ */
+pub fn fixture_items(count: usize) -> Vec<Item> {
+    datagen::generate_items(&DatagenOptions {
+        count,
+        seed: Some(42),
+        distribution: ValueDistribution::Uniform { min: 0.0, max: 200.0 },
+        name_pattern: String::from("{word} {id}"),
+        invalid_ratio: 0.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, ConfigSource};
+    use crate::hooks::PipelineHooks;
+    use crate::metrics::Metrics;
+    use crate::pipeline;
+    use crate::test_dir::TestDir;
+
+    proptest! {
+        #[test]
+        fn generated_items_satisfy_the_invariants(
+            item in arb_item(),
+            items in arb_items(20),
+            duplicates in arb_items_with_duplicates(20),
+        ) {
+            for item in std::iter::once(&item).chain(&items).chain(&duplicates) {
+                prop_assert_eq!(item.invariants(), Ok(()));
+            }
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn pipeline_reports_are_consistent(items in arb_items_with_duplicates(20), threshold in 0..2_000u32) {
+            let dir = TestDir::new("proptest-report");
+            let store = MockItemStore::new("mock://proptest-report.json").with_items(&items);
+            store.register().unwrap();
+            let mut config = Config::from_defaults();
+            config.set("data_path", "mock://proptest-report.json", ConfigSource::Cli).unwrap();
+            for (key, file) in [("checkpoint_path", "items.checkpoint.json"), ("staging_dir", "staging")] {
+                config.set_path(key, dir.join(file), ConfigSource::Cli).unwrap();
+            }
+            config.set("threshold", &threshold.to_string(), ConfigSource::Cli).unwrap();
+
+            let report = pipeline::run_processing_pipeline(&config, false, &Metrics::new(), &PipelineHooks::new());
+
+            let report = report.unwrap();
+            prop_assert!(report.is_consistent(), "inconsistent report: {:?}", report);
+            prop_assert_eq!(report.items_loaded, items.len());
+            for item in store.items().unwrap() {
+                prop_assert_eq!(item.invariants(), Ok(()));
+            }
+        }
+    }
+}
+
+// End of rust_sample_project/src/test_util.rs
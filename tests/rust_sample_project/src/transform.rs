@@ -0,0 +1,598 @@
+// rust_sample_project/src/transform.rs
+
+// A minimal DSL for adjusting item values, applied by ItemProcessor, e.g.
+//
+//     value = value * 1.2 if value > threshold
+//     value = (value - 5) / 2 if item_id == 3 || value < 0
+//
+// Grammar:
+//
+//     transformation := "value" "=" arith ( "if" condition )?
+//     condition      := cond_and ( "||" cond_and )*
+//     cond_and       := cond_unary ( "&&" cond_unary )*
+//     cond_unary     := "!" cond_unary | "(" condition ")" | arith compare_op arith
+//     arith          := term ( ( "+" | "-" ) term )*
+//     term           := factor ( ( "*" | "/" ) factor )*
+//     factor         := number | variable | "-" factor | "(" arith ")"
+//     variable       := "value" | "threshold" | "item_id"
+//     compare_op     := "==" | "!=" | ">" | ">=" | "<" | "<="
+
+use std::fmt;
+
+use crate::filter::format_error;
+use crate::item::Item;
+use crate::money::Money;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Variable {
+    Value,
+    Threshold,
+    ItemId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/**
+ * @enum Arith
+ * @brief Arithmetic expression tree.
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum Arith {
+    Number(f64),
+    Var(Variable),
+    Neg(Box<Arith>),
+    Binary(Box<Arith>, ArithOp, Box<Arith>),
+}
+
+/**
+ * @enum Condition
+ * @brief Boolean condition tree guarding a transformation.
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Compare(Arith, CompareOp, Arith),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number(f64),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    LParen,
+    RParen,
+    Assign,
+    Compare(CompareOp),
+    And,
+    Or,
+    Not,
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+/**
+ * @struct Transformation
+ * @brief A parsed value adjustment rule such as "value = value * 1.2 if value > threshold".
+ */
+#[derive(Debug, Clone)]
+pub struct Transformation {
+    source: String,
+    expr: Arith,
+    condition: Option<Condition>,
+}
+
+impl Transformation {
+    /**
+     * @brief Parse a transformation rule.
+     *
+     * @param source The rule text.
+     * @return Result<Transformation, String> The parsed rule, or an error message
+     *         quoting the rule and pointing at the offending position.
+     */
+    pub fn parse(source: &str) -> Result<Transformation, String> {
+        let to_error = |(position, msg): (usize, String)| {
+            format!("Invalid transformation {}", format_error(source, position, &msg))
+        };
+        let tokens = tokenize(source).map_err(to_error)?;
+        let mut parser = Parser { tokens, index: 0 };
+        let (expr, condition) = parser.parse_transformation().map_err(to_error)?;
+        Ok(Transformation {
+            source: source.to_string(),
+            expr,
+            condition,
+        })
+    }
+
+    /**
+     * @brief Apply the rule to an item if its condition holds.
+     *
+     * @param item The item whose value may be adjusted.
+     * @param threshold The processing threshold, available as `threshold` in the rule.
+     * @param decimals Number of decimal places the new value is rounded to.
+     * @return Result<bool, String> True if the value was changed, false if the
+     *         condition did not hold, or an error message (e.g. division by zero)
+     *         naming the rule.
+     */
+    pub fn apply(&self, item: &mut Item, threshold: f64, decimals: u32) -> Result<bool, String> {
+        let context = Context { item, threshold };
+        let holds = match &self.condition {
+            Some(condition) => eval_condition(condition, &context).map_err(|e| self.runtime_error(&e))?,
+            None => true,
+        };
+        if !holds {
+            return Ok(false);
+        }
+        let new_value = eval_arith(&self.expr, &context).map_err(|e| self.runtime_error(&e))?;
+        // Arithmetic runs on floats; the result is rounded back to an exact amount
+        item.value = Money::from_f64_rounded(new_value, decimals).map_err(|e| self.runtime_error(&e))?;
+        Ok(true)
+    }
+
+    fn runtime_error(&self, message: &str) -> String {
+        format!("Transformation '{}' failed: {}", self.source, message)
+    }
+}
+
+impl fmt::Display for Transformation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/**
+ * @struct Context
+ * @brief Values visible to a transformation while it is evaluated.
+ */
+struct Context<'a> {
+    item: &'a Item,
+    threshold: f64,
+}
+
+fn eval_arith(expr: &Arith, context: &Context) -> Result<f64, String> {
+    Ok(match expr {
+        Arith::Number(n) => *n,
+        Arith::Var(Variable::Value) => context.item.value.to_f64(),
+        Arith::Var(Variable::Threshold) => context.threshold,
+        Arith::Var(Variable::ItemId) => context
+            .item
+            .item_id
+            .as_number()
+            .ok_or_else(|| format!("item_id {} is not a number", context.item.item_id))?,
+        Arith::Neg(inner) => -eval_arith(inner, context)?,
+        Arith::Binary(left, op, right) => {
+            let l = eval_arith(left, context)?;
+            let r = eval_arith(right, context)?;
+            match op {
+                ArithOp::Add => l + r,
+                ArithOp::Sub => l - r,
+                ArithOp::Mul => l * r,
+                ArithOp::Div => {
+                    if r == 0.0 {
+                        return Err(String::from("division by zero"));
+                    }
+                    l / r
+                }
+            }
+        }
+    })
+}
+
+fn eval_condition(condition: &Condition, context: &Context) -> Result<bool, String> {
+    Ok(match condition {
+        Condition::And(left, right) => eval_condition(left, context)? && eval_condition(right, context)?,
+        Condition::Or(left, right) => eval_condition(left, context)? || eval_condition(right, context)?,
+        Condition::Not(inner) => !eval_condition(inner, context)?,
+        Condition::Compare(left, op, right) => {
+            let l = eval_arith(left, context)?;
+            let r = eval_arith(right, context)?;
+            match op {
+                CompareOp::Eq => l == r,
+                CompareOp::Ne => l != r,
+                CompareOp::Gt => l > r,
+                CompareOp::Ge => l >= r,
+                CompareOp::Lt => l < r,
+                CompareOp::Le => l <= r,
+            }
+        }
+    })
+}
+
+/**
+ * @brief Split a transformation rule into tokens.
+ *
+ * @return Result<Vec<Token>, (usize, String)> The tokens (ending with End), or
+ *         the position and description of a lexical error.
+ */
+fn tokenize(source: &str) -> Result<Vec<Token>, (usize, String)> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let next = chars.get(i + 1).copied();
+        let kind = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('&', Some('&')) => {
+                i += 2;
+                TokenKind::And
+            }
+            ('|', Some('|')) => {
+                i += 2;
+                TokenKind::Or
+            }
+            ('=', Some('=')) => {
+                i += 2;
+                TokenKind::Compare(CompareOp::Eq)
+            }
+            ('!', Some('=')) => {
+                i += 2;
+                TokenKind::Compare(CompareOp::Ne)
+            }
+            ('>', Some('=')) => {
+                i += 2;
+                TokenKind::Compare(CompareOp::Ge)
+            }
+            ('<', Some('=')) => {
+                i += 2;
+                TokenKind::Compare(CompareOp::Le)
+            }
+            ('>', _) | ('<', _) | ('=', _) | ('!', _) | ('+', _) | ('-', _) | ('*', _) | ('/', _)
+            | ('(', _) | (')', _) => {
+                i += 1;
+                match c {
+                    '>' => TokenKind::Compare(CompareOp::Gt),
+                    '<' => TokenKind::Compare(CompareOp::Lt),
+                    '=' => TokenKind::Assign,
+                    '!' => TokenKind::Not,
+                    '+' => TokenKind::Plus,
+                    '-' => TokenKind::Minus,
+                    '*' => TokenKind::Star,
+                    '/' => TokenKind::Slash,
+                    '(' => TokenKind::LParen,
+                    _ => TokenKind::RParen,
+                }
+            }
+            (c, _) if c.is_ascii_digit() || c == '.' => {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text
+                    .parse::<f64>()
+                    .map_err(|_| (start, format!("invalid number '{}'", text)))?;
+                TokenKind::Number(number)
+            }
+            (c, _) if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                TokenKind::Ident(chars[start..i].iter().collect())
+            }
+            (c, _) => return Err((start, format!("unexpected character '{}'", c))),
+        };
+        tokens.push(Token { kind, position: start });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::End,
+        position: chars.len(),
+    });
+    Ok(tokens)
+}
+
+/**
+ * @struct Parser
+ * @brief Recursive-descent parser over a token list.
+ */
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+type ParseResult<T> = Result<T, (usize, String)>;
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.index].clone();
+        if self.index < self.tokens.len() - 1 {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn expect(&mut self, kind: TokenKind, what: &str) -> ParseResult<()> {
+        let token = self.advance();
+        if token.kind == kind {
+            Ok(())
+        } else {
+            Err((token.position, format!("expected {}", what)))
+        }
+    }
+
+    fn parse_transformation(&mut self) -> ParseResult<(Arith, Option<Condition>)> {
+        let target = self.advance();
+        match &target.kind {
+            TokenKind::Ident(name) if name == "value" => {}
+            _ => {
+                return Err((
+                    target.position,
+                    String::from("a transformation must start with 'value =' (only value can be assigned)"),
+                ))
+            }
+        }
+        self.expect(TokenKind::Assign, "'=' after 'value'")?;
+        let expr = self.parse_arith()?;
+
+        let condition = match &self.peek().kind {
+            TokenKind::Ident(word) if word == "if" => {
+                self.advance();
+                Some(self.parse_condition()?)
+            }
+            _ => None,
+        };
+
+        let end = self.peek();
+        if end.kind != TokenKind::End {
+            return Err((end.position, String::from("unexpected input; expected 'if' or the end of the rule")));
+        }
+        Ok((expr, condition))
+    }
+
+    fn parse_condition(&mut self) -> ParseResult<Condition> {
+        let mut left = self.parse_cond_and()?;
+        while self.peek().kind == TokenKind::Or {
+            self.advance();
+            let right = self.parse_cond_and()?;
+            left = Condition::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_cond_and(&mut self) -> ParseResult<Condition> {
+        let mut left = self.parse_cond_unary()?;
+        while self.peek().kind == TokenKind::And {
+            self.advance();
+            let right = self.parse_cond_unary()?;
+            left = Condition::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_cond_unary(&mut self) -> ParseResult<Condition> {
+        match self.peek().kind {
+            TokenKind::Not => {
+                self.advance();
+                Ok(Condition::Not(Box::new(self.parse_cond_unary()?)))
+            }
+            TokenKind::LParen => {
+                // "(" may open a grouped condition or an arithmetic operand such as
+                // "(value + 1) > 3"; try the condition first and backtrack if needed.
+                let saved = self.index;
+                self.advance();
+                if let Ok(inner) = self.parse_condition() {
+                    if self.peek().kind == TokenKind::RParen {
+                        self.advance();
+                        return Ok(inner);
+                    }
+                }
+                self.index = saved;
+                self.parse_comparison()
+            }
+            _ => self.parse_comparison(),
+        }
+    }
+
+    fn parse_comparison(&mut self) -> ParseResult<Condition> {
+        let left = self.parse_arith()?;
+        let op_token = self.advance();
+        let op = match op_token.kind {
+            TokenKind::Compare(op) => op,
+            _ => {
+                return Err((
+                    op_token.position,
+                    String::from("expected a comparison operator (==, !=, >, >=, <, <=)"),
+                ))
+            }
+        };
+        let right = self.parse_arith()?;
+        Ok(Condition::Compare(left, op, right))
+    }
+
+    fn parse_arith(&mut self) -> ParseResult<Arith> {
+        let mut left = self.parse_term()?;
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::Plus => ArithOp::Add,
+                TokenKind::Minus => ArithOp::Sub,
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = self.parse_term()?;
+            left = Arith::Binary(Box::new(left), op, Box::new(right));
+        }
+    }
+
+    fn parse_term(&mut self) -> ParseResult<Arith> {
+        let mut left = self.parse_factor()?;
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::Star => ArithOp::Mul,
+                TokenKind::Slash => ArithOp::Div,
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = self.parse_factor()?;
+            left = Arith::Binary(Box::new(left), op, Box::new(right));
+        }
+    }
+
+    fn parse_factor(&mut self) -> ParseResult<Arith> {
+        let token = self.advance();
+        match token.kind {
+            TokenKind::Number(n) => Ok(Arith::Number(n)),
+            TokenKind::Minus => Ok(Arith::Neg(Box::new(self.parse_factor()?))),
+            TokenKind::LParen => {
+                let inner = self.parse_arith()?;
+                self.expect(TokenKind::RParen, "')'")?;
+                Ok(inner)
+            }
+            TokenKind::Ident(name) => match name.as_str() {
+                "value" => Ok(Arith::Var(Variable::Value)),
+                "threshold" => Ok(Arith::Var(Variable::Threshold)),
+                "item_id" | "id" => Ok(Arith::Var(Variable::ItemId)),
+                other => Err((
+                    token.position,
+                    format!("unknown variable '{}' (expected value, threshold or item_id)", other),
+                )),
+            },
+            _ => Err((
+                token.position,
+                String::from("expected a number, a variable (value, threshold, item_id) or '('"),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item_id::{IdKind, ItemId};
+    use crate::money::DEFAULT_DECIMALS;
+
+    /// Apply a rule to an item with the given value; returns whether it
+    /// changed the value and the value afterwards.
+    fn apply(rule: &str, value: &str, threshold: f64) -> Result<(bool, String), String> {
+        let transformation = Transformation::parse(rule).unwrap_or_else(|e| panic!("{}", e));
+        let mut item = Item::new(ItemId::from(3), String::from("Gizmo"), Money::parse(value).unwrap());
+        let changed = transformation.apply(&mut item, threshold, DEFAULT_DECIMALS)?;
+        Ok((changed, item.value.to_string()))
+    }
+
+    fn value_after(rule: &str, value: &str) -> String {
+        apply(rule, value, 100.0).unwrap().1
+    }
+
+    fn parse_error(rule: &str) -> String {
+        Transformation::parse(rule).expect_err("rule should be rejected")
+    }
+
+    #[test]
+    fn applies_only_when_the_condition_holds() {
+        let rule = "value = value * 1.2 if value > threshold";
+        assert_eq!(apply(rule, "150", 100.0), Ok((true, String::from("180.00"))));
+        assert_eq!(apply(rule, "50", 100.0), Ok((false, String::from("50.00"))));
+        assert_eq!(apply(rule, "150", 200.0), Ok((false, String::from("150.00"))));
+        assert_eq!(value_after("value = (value - 5) / 2 if item_id == 3 || value < 0", "25"), "10.00");
+        assert_eq!(value_after("value = threshold + id", "1"), "103.00");
+        assert_eq!(Transformation::parse(rule).unwrap().to_string(), rule);
+    }
+
+    #[test]
+    fn arithmetic_follows_the_usual_precedence() {
+        assert_eq!(value_after("value = 2 + 3 * 4", "0"), "14.00");
+        assert_eq!(value_after("value = (2 + 3) * 4", "0"), "20.00");
+        assert_eq!(value_after("value = 10 - 4 - 3", "0"), "3.00");
+        assert_eq!(value_after("value = 100 / 10 / 2", "0"), "5.00");
+        assert_eq!(value_after("value = -value + 1", "5"), "-4.00");
+        assert_eq!(value_after("value = --value", "5"), "5.00");
+    }
+
+    #[test]
+    fn conditions_follow_the_usual_precedence() {
+        // && binds tighter than ||, so this holds for any value over 100
+        assert_eq!(value_after("value = 1 if value > 100 || value < 10 && item_id == 99", "150"), "1.00");
+        assert_eq!(value_after("value = 1 if (value > 100 || value < 10) && item_id == 99", "150"), "150.00");
+        assert_eq!(value_after("value = 1 if !(value > 100) && !!(value >= 0)", "50"), "1.00");
+        // "(" may also open an arithmetic operand of a comparison
+        assert_eq!(value_after("value = 1 if (value + 1) * 2 > 3", "1"), "1.00");
+    }
+
+    #[test]
+    fn results_are_rounded_half_away_from_zero() {
+        assert_eq!(value_after("value = value / 3", "10"), "3.33");
+        assert_eq!(value_after("value = 2 / 3", "0"), "0.67");
+        assert_eq!(value_after("value = 1.005", "0"), "1.01");
+        assert_eq!(value_after("value = -1.005", "0"), "-1.01");
+        assert_eq!(value_after("value = value - 0.004", "0"), "0.00");
+    }
+
+    #[test]
+    fn runtime_errors_name_the_rule_and_leave_the_value() {
+        assert_eq!(
+            apply("value = value / (threshold - 100)", "10", 100.0),
+            Err(String::from("Transformation 'value = value / (threshold - 100)' failed: division by zero"))
+        );
+        let error = apply("value = value * 100000000000000000000", "10", 100.0).unwrap_err();
+        assert!(error.starts_with("Transformation 'value = value * 100000000000000000000' failed: Amount '"));
+        assert!(error.ends_with("' is out of range"));
+
+        let mut item = Item::new(ItemId::parse("sku-1", IdKind::Str).unwrap(), String::new(), Money::from_int(1));
+        let transformation = Transformation::parse("value = item_id").unwrap();
+        let error = transformation.apply(&mut item, 0.0, DEFAULT_DECIMALS).unwrap_err();
+        assert_eq!(error, "Transformation 'value = item_id' failed: item_id sku-1 is not a number");
+        assert_eq!(item.value, Money::from_int(1));
+    }
+
+    #[test]
+    fn empty_rule_is_rejected() {
+        assert_eq!(
+            parse_error(""),
+            "Invalid transformation at position 1: a transformation must start with 'value =' (only value can be \
+             assigned)\n  \n  ^"
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_position() {
+        assert!(parse_error("threshold = 1").contains("at position 1: a transformation must start with 'value ='"));
+        assert!(parse_error("value 1").contains("at position 7: expected '=' after 'value'"));
+        assert!(parse_error("value =")
+            .contains("at position 8: expected a number, a variable (value, threshold, item_id) or '('"));
+        assert!(parse_error("value = price")
+            .contains("at position 9: unknown variable 'price' (expected value, threshold or item_id)"));
+        assert!(parse_error("value = (1 + 2").contains("at position 15: expected ')'"));
+        assert!(parse_error("value = 1 2")
+            .contains("at position 11: unexpected input; expected 'if' or the end of the rule"));
+        assert!(parse_error("value = 1 if").contains("at position 13: expected a number"));
+        assert!(parse_error("value = 1 if value").contains("at position 19: expected a comparison operator"));
+        assert!(parse_error("value = 1.2.3").contains("at position 9: invalid number '1.2.3'"));
+        assert!(parse_error("value = value % 2").contains("at position 15: unexpected character '%'"));
+    }
+}
+
+// End of rust_sample_project/src/transform.rs
@@ -0,0 +1,192 @@
+// rust_sample_project/src/tui.rs
+
+// Terminal dashboard of --watch and serve runs (`--tui`, needs the `tui`
+// feature). It takes over the terminal and shows, refreshed four times a
+// second:
+//
+//     the counters of metrics.rs and the item being handled,
+//     the throughput of the last two minutes as a graph,
+//     recent warnings and errors, and the latest log records.
+//
+// Log records go to the dashboard instead of stdout/stderr meanwhile. q, Esc
+// or Ctrl-C request a shutdown as SIGINT would, a second press exits at once;
+// the terminal is restored when the process exits through shutdown::exit.
+
+use std::collections::VecDeque;
+use std::io::{self, IsTerminal};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, Paragraph, Sparkline};
+use ratatui::Frame;
+
+use crate::logging::{self, LogLevel};
+use crate::metrics::Metrics;
+use crate::shutdown;
+
+/// How often the dashboard is redrawn and checks for key presses.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Interval of the throughput samples.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of throughput samples kept (two minutes).
+const MAX_SAMPLES: usize = 120;
+
+/// Number of log records and of warnings and errors kept.
+const MAX_RECORDS: usize = 200;
+
+/**
+ * @struct Recent
+ * @brief What the dashboard keeps of the log and the throughput.
+ */
+#[derive(Default)]
+struct Recent {
+    /// Records of level WARNING and above, oldest first.
+    errors: VecDeque<String>,
+    /// All records, oldest first.
+    records: VecDeque<String>,
+    /// Items handled in each SAMPLE_INTERVAL, oldest first.
+    throughput: VecDeque<u64>,
+}
+
+/**
+ * @brief Append to a bounded queue, dropping the oldest entry when full.
+ */
+fn push_bounded<T>(queue: &mut VecDeque<T>, value: T, capacity: usize) {
+    if queue.len() == capacity {
+        queue.pop_front();
+    }
+    queue.push_back(value);
+}
+
+/**
+ * @brief Restore the terminal and write log records to stdout/stderr again.
+ */
+fn restore_terminal() {
+    logging::set_sink(None);
+    ratatui::restore();
+}
+
+/**
+ * @brief Take over the terminal and show the dashboard until the process exits.
+ *
+ * @param mode What the process is doing, for the title, e.g. "watch".
+ * @param metrics The shared metrics the runs update.
+ * @return Result<(), String> Ok once the dashboard is shown, or an error
+ *         message if stdout is not a terminal.
+ */
+pub fn start(mode: &str, metrics: Arc<Metrics>) -> Result<(), String> {
+    if !io::stdout().is_terminal() {
+        return Err(String::from("--tui needs a terminal, but stdout is not one; logging as usual"));
+    }
+    let recent = Arc::new(Mutex::new(Recent::default()));
+    let logged = Arc::clone(&recent);
+    logging::set_sink(Some(Box::new(move |level, line| {
+        let mut recent = logged.lock().unwrap_or_else(|e| e.into_inner());
+        if level >= LogLevel::Warning {
+            push_bounded(&mut recent.errors, line.to_string(), MAX_RECORDS);
+        }
+        push_bounded(&mut recent.records, line.to_string(), MAX_RECORDS);
+    })));
+    let mut terminal = ratatui::init();
+    shutdown::before_exit(restore_terminal);
+
+    let title = format!(" rust_sample_project: {} ", mode);
+    thread::spawn(move || {
+        let mut last_sample = Instant::now();
+        let mut last_handled = metrics.items_processed() + metrics.items_failed();
+        loop {
+            if last_sample.elapsed() >= SAMPLE_INTERVAL {
+                let handled = metrics.items_processed() + metrics.items_failed();
+                let mut recent = recent.lock().unwrap_or_else(|e| e.into_inner());
+                push_bounded(&mut recent.throughput, handled.saturating_sub(last_handled), MAX_SAMPLES);
+                last_handled = handled;
+                last_sample = Instant::now();
+            }
+            let drawn = terminal.draw(|frame| {
+                draw(frame, &title, &metrics, &recent.lock().unwrap_or_else(|e| e.into_inner()));
+            });
+            if let Err(e) = drawn {
+                restore_terminal();
+                log_warning!("tui:start", "The dashboard stopped: {}", e);
+                return;
+            }
+            if let Ok(true) = event::poll(REFRESH_INTERVAL) {
+                if let Ok(Event::Key(key)) = event::read() {
+                    let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                    let quit = matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) || ctrl_c;
+                    if key.kind == KeyEventKind::Press && quit {
+                        if shutdown::requested() {
+                            shutdown::exit(shutdown::EXIT_INTERRUPTED);
+                        }
+                        shutdown::request();
+                    }
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/**
+ * @brief Return the newest records first, as many as fit in a pane.
+ */
+fn newest(records: &VecDeque<String>, height: u16) -> Vec<ListItem<'_>> {
+    records.iter().rev().take(height as usize).map(|record| ListItem::new(record.as_str())).collect()
+}
+
+/**
+ * @brief Draw the dashboard.
+ */
+fn draw(frame: &mut Frame, title: &str, metrics: &Metrics, recent: &Recent) {
+    let [counters_area, throughput_area, log_area] =
+        Layout::vertical([Constraint::Length(4), Constraint::Length(8), Constraint::Min(6)]).areas(frame.area());
+    let [errors_area, records_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(log_area);
+
+    let failed = metrics.items_failed();
+    let current_rate = recent.throughput.back().copied().unwrap_or(0);
+    let status = if shutdown::requested() {
+        "shutting down (press q again to exit now)"
+    } else {
+        "q: quit"
+    };
+    let counters = vec![
+        Line::from(vec![
+            Span::raw(format!("Loaded {}   Processed {}   ", metrics.items_loaded(), metrics.items_processed())),
+            Span::styled(
+                format!("Failed {}", failed),
+                if failed > 0 { Style::default().fg(Color::Red) } else { Style::default() },
+            ),
+            Span::raw(format!("   Throughput {} items/s", current_rate)),
+        ]),
+        Line::from(format!(
+            "Current item: {}   ({})",
+            metrics.current_item().as_deref().unwrap_or("-"),
+            status
+        )),
+    ];
+    frame.render_widget(Paragraph::new(counters).block(Block::bordered().title(title)), counters_area);
+
+    let samples: Vec<u64> = recent.throughput.iter().copied().collect();
+    let graph = Sparkline::default()
+        .block(Block::bordered().title(" Throughput (items/s, last 2 minutes) "))
+        .style(Style::default().fg(Color::Green))
+        .data(&samples);
+    frame.render_widget(graph, throughput_area);
+
+    let errors = List::new(newest(&recent.errors, errors_area.height))
+        .style(Style::default().fg(Color::Yellow))
+        .block(Block::bordered().title(" Recent warnings and errors "));
+    frame.render_widget(errors, errors_area);
+    let records = List::new(newest(&recent.records, records_area.height)).block(Block::bordered().title(" Log "));
+    frame.render_widget(records, records_area);
+}
+
+// End of rust_sample_project/src/tui.rs
@@ -0,0 +1,321 @@
+// rust_sample_project/src/wasm_plugin.rs
+
+// Item transforms shipped as WebAssembly modules, run sandboxed with
+// wasmtime (see the `wasm` feature), so users can add transforms without
+// recompiling the pipeline. A plugin is used like any registered stage (see
+// registry.rs), e.g. in a pipeline definition file:
+//
+//     stages:
+//       - stage: wasm
+//         module: plugins/normalize_names.wasm
+//         fuel: 10000000        # optional, instructions per item
+//         max_memory_mb: 16     # optional
+//         config: { suffix: " (EU)" }   # optional, handed to the plugin
+//
+// ABI version 1. A plugin module imports nothing and exports:
+//
+//     memory                                    its linear memory
+//     sp_abi_version() -> i32                   must return 1
+//     sp_alloc(len: i32) -> i32                 a buffer of len bytes for the request
+//     sp_transform(ptr: i32, len: i32) -> i64   handles the request, returns
+//                                               (response_ptr << 32) | response_len
+//
+// Request and response are UTF-8 JSON. The request is
+//
+//     {"abi_version": 1, "config": <config or null>,
+//      "item": {"item_id": "7", "name": "Widget", "value": "12.50",
+//               "status": "new", "tags": ["a"], "category": null, "supplier": null}}
+//
+// with item_id and value as strings, so ids and amounts round-trip exactly.
+// The response is one of
+//
+//     {"item": {...}}        the transformed item; name, value, tags, category
+//                            and supplier are taken over, item_id and status
+//                            are the host's and ignored
+//     {"skip": "reason"}     leave the item out, like the filter
+//     {"error": "message"}   fail the item
+//
+// Every item runs in a fresh instance with its own fuel and memory limits,
+// so a plugin cannot keep state between items, and a plugin that traps, runs
+// out of fuel or exceeds its memory fails only the item at hand.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use wasmtime::{Engine, InstancePre, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+
+use crate::composition::{Stage, StageResult};
+use crate::hooks::ItemOutcome;
+use crate::item::{Item, ItemStatus};
+use crate::money::Money;
+use crate::registry::{self, StageParams};
+
+/// Version of the plugin ABI described above.
+pub const ABI_VERSION: i32 = 1;
+/// Fuel (roughly, wasm instructions) a plugin may use per item by default.
+pub const DEFAULT_FUEL: u64 = 10_000_000;
+/// Linear memory a plugin may grow to by default, in MiB.
+pub const DEFAULT_MAX_MEMORY_MB: usize = 16;
+
+/**
+ * @struct PluginItem
+ * @brief An item as plugins see it (ABI version 1).
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginItem {
+    /// The item's id as text; read-only.
+    pub item_id: String,
+    pub name: String,
+    /// The item's value as a decimal string, e.g. "12.50".
+    pub value: String,
+    /// The item's status; read-only.
+    pub status: ItemStatus,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub supplier: Option<String>,
+}
+
+/**
+ * @struct PluginRequest
+ * @brief What the host hands to sp_transform.
+ */
+#[derive(Debug, Serialize)]
+struct PluginRequest<'a> {
+    abi_version: i32,
+    config: &'a serde_json::Value,
+    item: PluginItem,
+}
+
+/**
+ * @enum PluginResponse
+ * @brief What sp_transform returns.
+ */
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PluginResponse {
+    Item(PluginItem),
+    Skip(String),
+    Error(String),
+}
+
+/**
+ * @struct WasmParams
+ * @brief Parameters of the "wasm" stage.
+ */
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WasmParams {
+    /// Path of the .wasm module.
+    module: PathBuf,
+    #[serde(default = "default_fuel")]
+    fuel: u64,
+    #[serde(default = "default_max_memory_mb")]
+    max_memory_mb: usize,
+    /// Plugin-specific settings, passed through as JSON.
+    #[serde(default)]
+    config: serde_yaml::Value,
+}
+
+fn default_fuel() -> u64 {
+    DEFAULT_FUEL
+}
+
+fn default_max_memory_mb() -> usize {
+    DEFAULT_MAX_MEMORY_MB
+}
+
+/**
+ * @struct WasmPlugin
+ * @brief A loaded plugin module, usable as a stage.
+ */
+pub struct WasmPlugin {
+    /// Path of the module, for messages.
+    location: String,
+    /// The compiled module, ready to be instantiated for each item.
+    instance_pre: InstancePre<StoreLimits>,
+    /// Fuel per item.
+    fuel: u64,
+    /// Memory limit per item, in bytes.
+    max_memory: usize,
+    /// Plugin-specific settings handed over with every item.
+    config: serde_json::Value,
+}
+
+impl WasmPlugin {
+    /**
+     * @brief Compile a plugin module and check that it implements the ABI.
+     *
+     * @param path Path of the .wasm module.
+     * @param fuel Fuel the plugin may use per item.
+     * @param max_memory_mb Memory the plugin may grow to per item, in MiB.
+     * @param config Plugin-specific settings handed over with every item.
+     * @return Result<WasmPlugin, String> The plugin, or an error message if the
+     *         module cannot be read or compiled, imports anything, or does not
+     *         export the ABI version 1 functions.
+     */
+    pub fn load(path: &Path, fuel: u64, max_memory_mb: usize, config: serde_json::Value) -> Result<Self, String> {
+        let location = path.display().to_string();
+        let invalid = |reason: String| format!("Invalid WASM plugin '{}': {}", location, reason);
+        let mut engine_config = wasmtime::Config::new();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config).map_err(|e| invalid(e.to_string()))?;
+        let module = Module::from_file(&engine, path).map_err(|e| invalid(e.to_string()))?;
+        if let Some(import) = module.imports().next() {
+            return Err(invalid(format!(
+                "imports {}::{}, but plugins cannot import anything",
+                import.module(),
+                import.name()
+            )));
+        }
+        let instance_pre = Linker::new(&engine)
+            .instantiate_pre(&module)
+            .map_err(|e| invalid(e.to_string()))?;
+        let plugin = WasmPlugin {
+            location: location.clone(),
+            instance_pre,
+            fuel,
+            max_memory: max_memory_mb.saturating_mul(1024 * 1024),
+            config,
+        };
+
+        let (mut store, instance) = plugin.instantiate().map_err(invalid)?;
+        let version = instance
+            .get_typed_func::<(), i32>(&mut store, "sp_abi_version")
+            .and_then(|func| func.call(&mut store, ()))
+            .map_err(|e| invalid(format!("sp_abi_version: {}", e)))?;
+        if version != ABI_VERSION {
+            return Err(invalid(format!(
+                "implements ABI version {}, expected {}",
+                version, ABI_VERSION
+            )));
+        }
+        for (name, check) in [
+            ("sp_alloc", instance.get_typed_func::<i32, i32>(&mut store, "sp_alloc").map(drop)),
+            ("sp_transform", instance.get_typed_func::<(i32, i32), i64>(&mut store, "sp_transform").map(drop)),
+        ] {
+            check.map_err(|e| invalid(format!("{}: {}", name, e)))?;
+        }
+        if instance.get_memory(&mut store, "memory").is_none() {
+            return Err(invalid(String::from("does not export its memory as 'memory'")));
+        }
+        log_info!(
+            "wasm_plugin:load",
+            "Loaded WASM plugin {} (fuel {} per item, memory up to {} MiB).",
+            location,
+            fuel,
+            max_memory_mb
+        );
+        Ok(plugin)
+    }
+
+    /**
+     * @brief Run the plugin on one item.
+     *
+     * @param item The item, updated from the plugin's response.
+     * @return Result<StageResult, String> Whether the item goes on, or an
+     *         error message if the plugin failed it or misbehaved.
+     */
+    pub fn transform(&self, item: &mut Item) -> Result<StageResult, String> {
+        let request = serde_json::to_vec(&PluginRequest {
+            abi_version: ABI_VERSION,
+            config: &self.config,
+            item: PluginItem {
+                item_id: item.item_id.to_string(),
+                name: item.name.to_string(),
+                value: item.value.to_string(),
+                status: item.status,
+                tags: item.tags.clone(),
+                category: item.category.clone(),
+                supplier: item.supplier.clone(),
+            },
+        })
+        .map_err(|e| format!("Failed to serialize item for WASM plugin: {}", e))?;
+
+        let (mut store, instance) = self.instantiate()?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| String::from("plugin has no memory export"))?;
+        let call_error = |function: &str, e: wasmtime::Error| match e.downcast_ref::<Trap>() {
+            Some(Trap::OutOfFuel) => format!("{} ran out of fuel (limit {})", function, self.fuel),
+            _ => format!("{} failed: {}", function, e),
+        };
+        let length = i32::try_from(request.len()).map_err(|_| String::from("item too large for the plugin"))?;
+        let request_ptr = instance
+            .get_typed_func::<i32, i32>(&mut store, "sp_alloc")
+            .and_then(|func| func.call(&mut store, length))
+            .map_err(|e| call_error("sp_alloc", e))?;
+        memory
+            .write(&mut store, request_ptr as u32 as usize, &request)
+            .map_err(|e| format!("sp_alloc returned an invalid buffer: {}", e))?;
+        let packed = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, "sp_transform")
+            .and_then(|func| func.call(&mut store, (request_ptr, length)))
+            .map_err(|e| call_error("sp_transform", e))?;
+
+        let (response_ptr, response_len) = ((packed as u64 >> 32) as usize, (packed as u64 & 0xffff_ffff) as usize);
+        let mut response = vec![0; response_len];
+        memory
+            .read(&store, response_ptr, &mut response)
+            .map_err(|e| format!("sp_transform returned an invalid buffer: {}", e))?;
+        match serde_json::from_slice(&response).map_err(|e| format!("Invalid response from plugin: {}", e))? {
+            PluginResponse::Item(transformed) => {
+                item.value = Money::parse(&transformed.value)
+                    .map_err(|e| format!("Invalid value '{}' from plugin: {}", transformed.value, e))?;
+                item.name = transformed.name.into();
+                item.tags = transformed.tags;
+                item.category = transformed.category;
+                item.supplier = transformed.supplier;
+                Ok(StageResult::Continue)
+            }
+            PluginResponse::Skip(reason) => {
+                log_debug!("wasm_plugin:transform", "Plugin {} skipped item {}: {}", self.location, item, reason);
+                Ok(StageResult::Finish(ItemOutcome::FilteredOut))
+            }
+            PluginResponse::Error(message) => Err(message),
+        }
+    }
+
+    /**
+     * @brief Create a fresh instance with the plugin's limits.
+     */
+    fn instantiate(&self) -> Result<(Store<StoreLimits>, wasmtime::Instance), String> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.max_memory)
+            .instances(1)
+            .build();
+        let mut store = Store::new(self.instance_pre.module().engine(), limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.fuel).map_err(|e| e.to_string())?;
+        let instance = self
+            .instance_pre
+            .instantiate(&mut store)
+            .map_err(|e| format!("Failed to instantiate: {}", e))?;
+        Ok((store, instance))
+    }
+}
+
+impl Stage for WasmPlugin {
+    fn apply(&self, item: &mut Item) -> StageResult {
+        self.transform(item).unwrap_or_else(|e| {
+            log_error!("wasm_plugin:apply", "WASM plugin {} failed item {}: {}", self.location, item, e);
+            StageResult::Finish(ItemOutcome::Failed)
+        })
+    }
+}
+
+/**
+ * @brief Build the "wasm" stage (registered in registry.rs).
+ */
+pub fn build_stage(params: &StageParams) -> Result<Box<dyn Stage>, String> {
+    let params: WasmParams = registry::parse_params(params)?;
+    let config = match params.config {
+        serde_yaml::Value::Null => serde_json::Value::Null,
+        config => serde_json::to_value(&config).map_err(|e| format!("config: {}", e))?,
+    };
+    Ok(Box::new(WasmPlugin::load(&params.module, params.fuel, params.max_memory_mb, config)?))
+}
+
+// End of rust_sample_project/src/wasm_plugin.rs
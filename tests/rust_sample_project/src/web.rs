@@ -0,0 +1,182 @@
+// rust_sample_project/src/web.rs
+
+// Browser entry point (see the `web` feature), so the processing logic can
+// run in a playground page. Build without the `native` feature:
+//
+//     cargo build --lib --no-default-features --features web --target wasm32-unknown-unknown
+//     wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/debug/rust_sample_project.wasm
+//
+// and call it from JavaScript:
+//
+//     import init, { process_items_json } from "./pkg/rust_sample_project.js";
+//     await init();
+//     const result = JSON.parse(process_items_json(JSON.stringify({
+//         threshold: 150,
+//         transformations: ["value = value * 2 if value < 10"],
+//         items: [{ item_id: 1, name: "Widget", value: 12.5 }],
+//     })));
+//
+// The input is either a list of items, processed with the default threshold,
+// or an object with the items and the processing settings below (named like
+// the config keys). Items have the fields of the data files. Each item is
+// filtered, checked against the tag rules and processed like a streamed item
+// (--stream); the result lists the items as they came out, each with its
+// "outcome", and the counts per outcome. Invalid input or settings throw an
+// Error with the message.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::filter::FilterExpr;
+use crate::item::{Item, ItemStatus};
+use crate::item_processor::ItemProcessor;
+use crate::transform::Transformation;
+
+/// Threshold used when the input does not give one, as the binary's default.
+const DEFAULT_THRESHOLD: f64 = 100.0;
+
+/**
+ * @struct PlaygroundRequest
+ * @brief Items with processing settings.
+ */
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlaygroundRequest {
+    items: Vec<Item>,
+    #[serde(default = "default_threshold")]
+    threshold: f64,
+    #[serde(default)]
+    category_thresholds: BTreeMap<String, f64>,
+    #[serde(default)]
+    tag_thresholds: BTreeMap<String, f64>,
+    #[serde(default)]
+    required_tags: Vec<String>,
+    #[serde(default)]
+    excluded_tags: Vec<String>,
+    #[serde(default)]
+    transformations: Vec<String>,
+    /// Filter expression, as the filter_expression config key.
+    #[serde(default)]
+    filter_expression: Option<String>,
+}
+
+fn default_threshold() -> f64 {
+    DEFAULT_THRESHOLD
+}
+
+/**
+ * @struct PlaygroundItem
+ * @brief An item of the result, with what happened to it.
+ */
+#[derive(Debug, Serialize)]
+struct PlaygroundItem {
+    #[serde(flatten)]
+    item: Item,
+    /// "processed", "failed", "filtered_out" or "skipped_by_tag", as in
+    /// the binary's --output json.
+    outcome: &'static str,
+}
+
+/**
+ * @struct PlaygroundResult
+ * @brief What process_items_json returns.
+ */
+#[derive(Debug, Default, Serialize)]
+struct PlaygroundResult {
+    items: Vec<PlaygroundItem>,
+    processed: usize,
+    failed: usize,
+    filtered_out: usize,
+    skipped_by_tag: usize,
+}
+
+/**
+ * @brief Process items given as JSON and return the result as JSON.
+ *
+ * @param input A JSON list of items, or an object with "items" and settings.
+ * @return Result<String, JsError> The result document, or an Error for bad input or settings.
+ */
+#[wasm_bindgen]
+pub fn process_items_json(input: &str) -> Result<String, JsError> {
+    let invalid = |e: serde_json::Error| JsError::new(&format!("Invalid input: {}", e));
+    let request = match serde_json::from_str(input).map_err(invalid)? {
+        serde_json::Value::Array(items) => PlaygroundRequest {
+            items: serde_json::from_value(serde_json::Value::Array(items)).map_err(invalid)?,
+            threshold: DEFAULT_THRESHOLD,
+            category_thresholds: BTreeMap::new(),
+            tag_thresholds: BTreeMap::new(),
+            required_tags: Vec::new(),
+            excluded_tags: Vec::new(),
+            transformations: Vec::new(),
+            filter_expression: None,
+        },
+        request => serde_json::from_value(request).map_err(invalid)?,
+    };
+    let result = process_request(request).map_err(|e| JsError::new(&e))?;
+    serde_json::to_string(&result).map_err(|e| JsError::new(&format!("Failed to serialize the result: {}", e)))
+}
+
+/**
+ * @brief Build the processor and filter of a request and run its items through them.
+ */
+fn process_request(request: PlaygroundRequest) -> Result<PlaygroundResult, String> {
+    let transformations = request
+        .transformations
+        .iter()
+        .map(|rule| Transformation::parse(rule))
+        .collect::<Result<Vec<Transformation>, String>>()?;
+    let item_filter = request
+        .filter_expression
+        .as_deref()
+        .filter(|expression| !expression.trim().is_empty())
+        .map(FilterExpr::parse)
+        .transpose()?;
+    let item_processor = ItemProcessor::new(request.threshold)
+        .with_thresholds(
+            request.category_thresholds.into_iter().collect(),
+            request.tag_thresholds.into_iter().collect(),
+        )
+        .with_tag_rules(request.required_tags, request.excluded_tags)
+        .with_transformations(transformations);
+
+    let mut result = PlaygroundResult::default();
+    for mut item in request.items {
+        let outcome = if item_filter.as_ref().is_some_and(|f| !f.matches(&item)) {
+            mark_skipped(&mut item);
+            result.filtered_out += 1;
+            "filtered_out"
+        } else if !item_processor.is_selected(&item) {
+            mark_skipped(&mut item);
+            result.skipped_by_tag += 1;
+            "skipped_by_tag"
+        } else if item_processor.process_item(&mut item) {
+            result.processed += 1;
+            "processed"
+        } else {
+            item.validate();
+            if let Err(e) = item.mark_failed() {
+                log_error!("web:process_request", "{}", e);
+            }
+            result.failed += 1;
+            "failed"
+        };
+        result.items.push(PlaygroundItem { item, outcome });
+    }
+    Ok(result)
+}
+
+/**
+ * @brief Mark an item left out of processing as skipped, unless it arrived
+ *        already settled (processed, failed or skipped).
+ */
+fn mark_skipped(item: &mut Item) {
+    if item.status.can_become(ItemStatus::Skipped) {
+        if let Err(e) = item.mark_skipped() {
+            log_error!("web:mark_skipped", "{}", e);
+        }
+    }
+}
+
+// End of rust_sample_project/src/web.rs
@@ -0,0 +1,427 @@
+// rust_sample_project/src/what_if.rs
+
+// What-if reports: what processing the loaded items with the current rules
+// would change, without changing anything (the `what-if` subcommand). Every
+// item the tag rules and the filter select is run through the transformations
+// and the threshold check on a copy (see ItemProcessor::simulate_item); no
+// item is marked processed, nothing is audited and no file is written. The
+// report lists the items whose value would change or whose threshold tag
+// would differ from the one they carry, so the effect of a new threshold
+// (`what-if --threshold <value>`) or of new transformations can be previewed
+// before a run applies it. Already processed items are evaluated too; as in
+// a run, only their threshold tag is, since their values are not transformed
+// again.
+//
+// A rule comparison (`compare --baseline <config> --candidate <config>`)
+// evaluates the same loaded items with the rules of two config files and
+// lists the items the two would treat differently: selected by one only,
+// classified over by one and under by the other, or left with another value.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::audit::AuditChange;
+use crate::diff;
+use crate::filter::FilterExpr;
+use crate::item::Item;
+use crate::item_id::ItemId;
+use crate::item_processor::{ItemProcessor, TAG_OVER_THRESHOLD, TAG_UNDER_THRESHOLD};
+use crate::money::Money;
+
+/**
+ * @struct ItemImpact
+ * @brief What processing would do to one item.
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemImpact {
+    /// ID of the item.
+    pub item_id: ItemId,
+    /// Name of the item.
+    pub name: String,
+    /// The threshold that applies to the item.
+    pub threshold: f64,
+    /// The item's value now.
+    pub value_before: Money,
+    /// The value the transformations would give it.
+    pub value_after: Money,
+    /// value_after - value_before.
+    pub delta: Money,
+    /// The threshold tag the item carries now, if any.
+    pub tag_before: Option<String>,
+    /// The threshold tag processing would give it (None if the rules failed).
+    pub tag_after: Option<String>,
+    /// The transformations that would change the value, in order.
+    pub rules: Vec<String>,
+    /// Why the rules would fail for the item, if they would.
+    pub error: Option<String>,
+}
+
+impl ItemImpact {
+    /**
+     * @brief Evaluate the rules for an item.
+     *
+     * @param processor The processor with the rules to preview.
+     * @param item The item, left unchanged.
+     * @return Result<ItemImpact, String> What processing would do to the item,
+     *         or an error message if the value change is out of range.
+     */
+    pub fn evaluate(processor: &ItemProcessor, item: &Item) -> Result<Self, String> {
+        let threshold = processor.threshold_for(item);
+        let simulation = processor.simulate_item(item);
+        let value_after = simulation.item.value;
+        let tag_after = simulation.error.is_none().then(|| threshold_tag(&simulation.item)).flatten();
+        let rules = simulation
+            .changes
+            .iter()
+            .filter(|change| change.change == AuditChange::ValueAdjusted)
+            .map(|change| change.rule.clone())
+            .collect();
+        let delta = value_after.checked_sub(item.value).ok_or_else(|| {
+            format!("Item {}: the change from {} to {} is out of range", item.item_id, item.value, value_after)
+        })?;
+        Ok(ItemImpact {
+            item_id: item.item_id.clone(),
+            name: item.name.to_string(),
+            threshold,
+            value_before: item.value,
+            value_after,
+            delta,
+            tag_before: threshold_tag(item),
+            tag_after,
+            rules,
+            error: simulation.error,
+        })
+    }
+
+    /**
+     * @brief Return whether processing would change the item.
+     *
+     * @return bool True if its value or threshold tag would change, or the rules would fail.
+     */
+    pub fn is_affected(&self) -> bool {
+        self.value_after != self.value_before || self.tag_after != self.tag_before || self.error.is_some()
+    }
+}
+
+/**
+ * @brief Return the threshold tag an item carries, if any.
+ */
+fn threshold_tag(item: &Item) -> Option<String> {
+    item.tags.iter().find(|tag| *tag == TAG_OVER_THRESHOLD || *tag == TAG_UNDER_THRESHOLD).cloned()
+}
+
+/**
+ * @struct WhatIfReport
+ * @brief What processing the loaded items would change.
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct WhatIfReport {
+    /// The default threshold the report was made with.
+    pub threshold: f64,
+    /// Number of items the rules were evaluated for.
+    pub items_evaluated: usize,
+    /// Number of items the tag rules or the filter leave out.
+    pub items_skipped: usize,
+    /// Number of evaluated items processing would change.
+    pub items_affected: usize,
+    /// Number of items the rules would fail for.
+    pub items_failed: usize,
+    /// Number of items that would become over their threshold.
+    pub newly_over: usize,
+    /// Number of items that would become under their threshold.
+    pub newly_under: usize,
+    /// Sum of the value changes.
+    pub total_delta: Money,
+    /// The affected items, in load order.
+    pub impacts: Vec<ItemImpact>,
+}
+
+impl WhatIfReport {
+    /**
+     * @brief Evaluate the rules for the loaded items.
+     *
+     * @param processor The processor with the rules to preview.
+     * @param filter The item filter (--filter), if any.
+     * @param threshold The default threshold, for the report.
+     * @param items The loaded items, left unchanged.
+     * @return Result<WhatIfReport, String> The report, or an error message if
+     *         a value change or their total is out of range.
+     */
+    pub fn build(
+        processor: &ItemProcessor,
+        filter: Option<&FilterExpr>,
+        threshold: f64,
+        items: &[Item],
+    ) -> Result<Self, String> {
+        let mut report = WhatIfReport {
+            threshold,
+            items_evaluated: 0,
+            items_skipped: 0,
+            items_affected: 0,
+            items_failed: 0,
+            newly_over: 0,
+            newly_under: 0,
+            total_delta: Money::from_int(0),
+            impacts: Vec::new(),
+        };
+        for item in items {
+            if !processor.is_selected(item) || filter.is_some_and(|filter| !filter.matches(item)) {
+                report.items_skipped += 1;
+                continue;
+            }
+            report.items_evaluated += 1;
+            let impact = ItemImpact::evaluate(processor, item)?;
+            if !impact.is_affected() {
+                continue;
+            }
+            report.items_affected += 1;
+            report.total_delta = report
+                .total_delta
+                .checked_add(impact.delta)
+                .ok_or_else(|| String::from("The total value change is out of range"))?;
+            if impact.error.is_some() {
+                report.items_failed += 1;
+            } else if impact.tag_after != impact.tag_before {
+                match impact.tag_after.as_deref() {
+                    Some(TAG_OVER_THRESHOLD) => report.newly_over += 1,
+                    _ => report.newly_under += 1,
+                }
+            }
+            report.impacts.push(impact);
+        }
+        Ok(report)
+    }
+
+    /**
+     * @brief Lay out the report as a table of the affected items and a summary.
+     *
+     * @return String The report as text.
+     */
+    pub fn to_table(&self) -> String {
+        let mut rows: Vec<[String; 7]> = vec![[
+            String::from("ID"),
+            String::from("NAME"),
+            String::from("VALUE"),
+            String::from("NEW VALUE"),
+            String::from("DELTA"),
+            String::from("TAG"),
+            String::from("NEW TAG"),
+        ]];
+        for impact in &self.impacts {
+            let tag_after = match (&impact.tag_after, &impact.error) {
+                (Some(tag), _) => tag.clone(),
+                (None, Some(error)) => format!("failed: {}", error),
+                (None, None) => String::from("-"),
+            };
+            rows.push([
+                impact.item_id.to_string(),
+                impact.name.clone(),
+                impact.value_before.to_string(),
+                impact.value_after.to_string(),
+                impact.delta.to_string(),
+                impact.tag_before.clone().unwrap_or_else(|| String::from("-")),
+                tag_after,
+            ]);
+        }
+        let mut text = diff::format_table(&rows);
+        text.push_str(&format!(
+            "{} of {} items would change ({} skipped by the tag rules or the filter): {} over and {} under \
+             their threshold anew, {} failing; values change by {} in total.\n",
+            self.items_affected,
+            self.items_evaluated,
+            self.items_skipped,
+            self.newly_over,
+            self.newly_under,
+            self.items_failed,
+            self.total_delta
+        ));
+        text
+    }
+}
+
+/**
+ * @struct ItemComparison
+ * @brief How the baseline and the candidate rules would process one item.
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemComparison {
+    /// ID of the item.
+    pub item_id: ItemId,
+    /// Name of the item.
+    pub name: String,
+    /// What the baseline rules would do, or None if they do not select the item.
+    pub baseline: Option<ItemImpact>,
+    /// What the candidate rules would do, or None if they do not select the item.
+    pub candidate: Option<ItemImpact>,
+}
+
+impl ItemComparison {
+    /**
+     * @brief Return whether the two would classify the item differently.
+     *
+     * @return bool True if the threshold tags differ, or only one selects the item.
+     */
+    pub fn classification_differs(&self) -> bool {
+        self.baseline.as_ref().map(|impact| &impact.tag_after)
+            != self.candidate.as_ref().map(|impact| &impact.tag_after)
+    }
+
+    /**
+     * @brief Return whether the two would leave the item with different values.
+     *
+     * @return bool True if the values after the transformations differ.
+     */
+    pub fn value_differs(&self) -> bool {
+        self.baseline.as_ref().map(|impact| impact.value_after)
+            != self.candidate.as_ref().map(|impact| impact.value_after)
+    }
+}
+
+/**
+ * @struct RuleComparison
+ * @brief How the rules of two configurations would process the same items.
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleComparison {
+    /// The config file with the baseline rules.
+    pub baseline: PathBuf,
+    /// The config file with the candidate rules.
+    pub candidate: PathBuf,
+    /// Number of items compared.
+    pub items_compared: usize,
+    /// Number of items classified differently (or selected by one only).
+    pub classification_changes: usize,
+    /// Number of items left with different values.
+    pub value_changes: usize,
+    /// Number of items the baseline would classify over their threshold.
+    pub baseline_over: usize,
+    /// Number of items the candidate would classify over their threshold.
+    pub candidate_over: usize,
+    /// The items the two would treat differently, in load order.
+    pub differences: Vec<ItemComparison>,
+}
+
+/**
+ * @struct RuleSet
+ * @brief The rules of one side of a comparison.
+ */
+pub struct RuleSet<'a> {
+    /// The config file the rules come from.
+    pub config_file: PathBuf,
+    /// The processor with the thresholds, tag rules and transformations.
+    pub processor: &'a ItemProcessor,
+    /// The item filter, if any.
+    pub filter: Option<&'a FilterExpr>,
+}
+
+impl RuleSet<'_> {
+    /**
+     * @brief Evaluate the rules for an item, if they select it.
+     *
+     * @param item The item, left unchanged.
+     * @return Result<Option<ItemImpact>, String> What processing would do, or
+     *         None if the tag rules or the filter leave the item out.
+     */
+    fn evaluate(&self, item: &Item) -> Result<Option<ItemImpact>, String> {
+        let selected = self.processor.is_selected(item) && self.filter.is_none_or(|filter| filter.matches(item));
+        selected.then(|| ItemImpact::evaluate(self.processor, item)).transpose()
+    }
+}
+
+impl RuleComparison {
+    /**
+     * @brief Evaluate the same items with two sets of rules and compare the outcomes.
+     *
+     * @param baseline The rules in use.
+     * @param candidate The rules to compare them with.
+     * @param items The loaded items, left unchanged.
+     * @return Result<RuleComparison, String> The comparison, or an error
+     *         message if a value change is out of range.
+     */
+    pub fn build(baseline: &RuleSet<'_>, candidate: &RuleSet<'_>, items: &[Item]) -> Result<Self, String> {
+        let mut comparison = RuleComparison {
+            baseline: baseline.config_file.clone(),
+            candidate: candidate.config_file.clone(),
+            items_compared: items.len(),
+            classification_changes: 0,
+            value_changes: 0,
+            baseline_over: 0,
+            candidate_over: 0,
+            differences: Vec::new(),
+        };
+        let is_over = |impact: &Option<ItemImpact>| {
+            impact.as_ref().is_some_and(|impact| impact.tag_after.as_deref() == Some(TAG_OVER_THRESHOLD))
+        };
+        for item in items {
+            let item_comparison = ItemComparison {
+                item_id: item.item_id.clone(),
+                name: item.name.to_string(),
+                baseline: baseline.evaluate(item)?,
+                candidate: candidate.evaluate(item)?,
+            };
+            comparison.baseline_over += usize::from(is_over(&item_comparison.baseline));
+            comparison.candidate_over += usize::from(is_over(&item_comparison.candidate));
+            let classification_differs = item_comparison.classification_differs();
+            let value_differs = item_comparison.value_differs();
+            comparison.classification_changes += usize::from(classification_differs);
+            comparison.value_changes += usize::from(value_differs);
+            if classification_differs || value_differs {
+                comparison.differences.push(item_comparison);
+            }
+        }
+        Ok(comparison)
+    }
+
+    /**
+     * @brief Lay out the comparison as a table of the differing items and a summary.
+     *
+     * @return String The comparison as text.
+     */
+    pub fn to_table(&self) -> String {
+        let mut rows: Vec<[String; 6]> = vec![[
+            String::from("ID"),
+            String::from("NAME"),
+            String::from("BASELINE VALUE"),
+            String::from("CANDIDATE VALUE"),
+            String::from("BASELINE TAG"),
+            String::from("CANDIDATE TAG"),
+        ]];
+        let value = |impact: &Option<ItemImpact>| match impact {
+            Some(impact) => impact.value_after.to_string(),
+            None => String::from("-"),
+        };
+        let outcome = |impact: &Option<ItemImpact>| match impact {
+            Some(ItemImpact { tag_after: Some(tag), .. }) => tag.clone(),
+            Some(ItemImpact { error: Some(error), .. }) => format!("failed: {}", error),
+            Some(_) => String::from("-"),
+            None => String::from("not selected"),
+        };
+        for difference in &self.differences {
+            rows.push([
+                difference.item_id.to_string(),
+                difference.name.clone(),
+                value(&difference.baseline),
+                value(&difference.candidate),
+                outcome(&difference.baseline),
+                outcome(&difference.candidate),
+            ]);
+        }
+        let mut text = diff::format_table(&rows);
+        text.push_str(&format!(
+            "{} of {} items differ between {} and {}: {} classified differently, {} with another value; \
+             over their threshold: {} with the baseline, {} with the candidate.\n",
+            self.differences.len(),
+            self.items_compared,
+            self.baseline.display(),
+            self.candidate.display(),
+            self.classification_changes,
+            self.value_changes,
+            self.baseline_over,
+            self.candidate_over
+        ));
+        text
+    }
+}
+
+// End of rust_sample_project/src/what_if.rs
@@ -0,0 +1,237 @@
+// rust_sample_project/src/worker_pool.rs
+
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+
+use crate::logging;
+
+/**
+ * @struct WorkerPool
+ * @brief A fixed number of worker threads fed through bounded channels.
+ *
+ * Work items are handed out to the workers as they become free, and results
+ * are passed back in input order regardless of which worker finishes first.
+ * The bounded job and result queues keep the feeder from running arbitrarily
+ * far ahead of the workers, and at most workers × queue capacity inputs are
+ * in flight at once, so a slow input holds back only that many finished
+ * results waiting for it.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct WorkerPool {
+    workers: usize,
+    queue_capacity: usize,
+}
+
+impl WorkerPool {
+    /**
+     * @brief Constructs a worker pool.
+     *
+     * @param workers Number of worker threads (at least 1).
+     * @param queue_capacity Capacity of the job and result queues (at least 1).
+     * @return WorkerPool The pool; threads are only started by map_ordered.
+     */
+    pub fn new(workers: usize, queue_capacity: usize) -> Self {
+        let pool = WorkerPool {
+            workers: workers.max(1),
+            queue_capacity: queue_capacity.max(1),
+        };
+        if pool.workers > 1 {
+            log_info!(
+                "worker_pool:new",
+                "Processing with {} worker threads (queue capacity {}).",
+                pool.workers,
+                pool.queue_capacity
+            );
+        }
+        pool
+    }
+
+    /**
+     * @brief Apply `work` to every input on the worker threads, in input order.
+     *
+     * `on_output` runs on the calling thread and sees the results in the same
+     * order as the inputs. Before each input is handed out, `should_stop` is
+     * checked; once it returns true no further inputs are started, the results
+     * of the inputs already started are still delivered, and the inputs that
+     * were never started are returned. An input is only handed out while
+     * fewer than workers × queue capacity earlier inputs are undelivered.
+     * If `work` panics, no further inputs are started or delivered, and once
+     * the inputs in flight are done the panic is resumed on the calling
+     * thread.
+     *
+     * @param inputs The work items, in order.
+     * @param should_stop Checked before handing out each input.
+     * @param work Computes the result for one input; runs on a worker thread.
+     * @param on_output Receives the results in input order.
+     * @return Vec<T> The inputs that were not started because of should_stop (empty otherwise).
+     */
+    pub fn map_ordered<T, R, S, W, O>(&self, inputs: Vec<T>, should_stop: S, work: W, mut on_output: O) -> Vec<T>
+    where
+        T: Send,
+        R: Send,
+        S: Fn() -> bool + Send,
+        W: Fn(T) -> R + Sync,
+        O: FnMut(R),
+    {
+        let (job_tx, job_rx) = mpsc::sync_channel::<(usize, T)>(self.queue_capacity);
+        let (result_tx, result_rx) = mpsc::sync_channel::<(usize, thread::Result<R>)>(self.queue_capacity);
+        // One slot per input in flight; delivering a result frees its slot
+        let window = self.workers.saturating_mul(self.queue_capacity);
+        let (slot_tx, slot_rx) = mpsc::sync_channel::<()>(window);
+        for _ in 0..window {
+            let _ = slot_tx.send(());
+        }
+        let job_rx = Mutex::new(job_rx);
+        let work = &work;
+        let panicked = AtomicBool::new(false);
+        let panicked = &panicked;
+        // Workers log with the context of the thread that hands out the work
+        let context = logging::context();
+        let context = &context;
+
+        thread::scope(|scope| {
+            let feeder = scope.spawn(move || {
+                let mut remaining = inputs.into_iter();
+                let mut index = 0;
+                while let Some(input) = remaining.next() {
+                    if slot_rx.recv().is_err() || panicked.load(Ordering::SeqCst) || should_stop() {
+                        return std::iter::once(input).chain(remaining).collect();
+                    }
+                    if job_tx.send((index, input)).is_err() {
+                        break;
+                    }
+                    index += 1;
+                }
+                Vec::new()
+            });
+
+            for _ in 0..self.workers {
+                let job_rx = &job_rx;
+                let result_tx = result_tx.clone();
+                scope.spawn(move || {
+                    logging::with_context(context.clone(), || loop {
+                        // Only one idle worker waits on the queue at a time
+                        let job = match job_rx.lock() {
+                            Ok(receiver) => receiver.recv(),
+                            Err(_) => break,
+                        };
+                        let Ok((index, input)) = job else { break };
+                        // Inputs handed out before a panic was noticed are dropped
+                        if panicked.load(Ordering::SeqCst) {
+                            continue;
+                        }
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| work(input)));
+                        if result_tx.send((index, result)).is_err() {
+                            break;
+                        }
+                    })
+                });
+            }
+            drop(result_tx);
+
+            // Hold back results that finished early until their predecessors arrive
+            let mut early: BTreeMap<usize, R> = BTreeMap::new();
+            let mut next_index = 0;
+            let mut slot_tx = Some(slot_tx);
+            // The first input that panicked; inputs from it on are not delivered
+            let mut panic_at: Option<(usize, Box<dyn std::any::Any + Send>)> = None;
+            for (index, result) in result_rx {
+                match result {
+                    Ok(result) => {
+                        early.insert(index, result);
+                    }
+                    Err(payload) => {
+                        log_error!("worker_pool:map_ordered", "Input {} panicked; no more inputs are started.", index);
+                        panicked.store(true, Ordering::SeqCst);
+                        // Wakes the feeder if it waits for a slot, so it stops
+                        slot_tx = None;
+                        if panic_at.as_ref().is_none_or(|(first, _)| index < *first) {
+                            panic_at = Some((index, payload));
+                        }
+                    }
+                }
+                let limit = panic_at.as_ref().map_or(usize::MAX, |(first, _)| *first);
+                while next_index < limit {
+                    let Some(result) = early.remove(&next_index) else { break };
+                    on_output(result);
+                    next_index += 1;
+                    // The feeder is gone once every input was handed out
+                    if let Some(slot_tx) = &slot_tx {
+                        let _ = slot_tx.send(());
+                    }
+                }
+            }
+
+            let not_started = feeder.join().unwrap_or_default();
+            if let Some((_, payload)) = panic_at {
+                panic::resume_unwind(payload);
+            }
+            not_started
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    #[test]
+    fn a_slow_input_holds_back_at_most_one_window_of_results() {
+        let pool = WorkerPool::new(2, 1);
+        let delivered = AtomicUsize::new(0);
+        let furthest_ahead = AtomicUsize::new(0);
+
+        let mut outputs = Vec::new();
+        let not_started = pool.map_ordered(
+            (0..50).collect(),
+            || false,
+            |index: usize| {
+                furthest_ahead.fetch_max(index - delivered.load(Ordering::SeqCst), Ordering::SeqCst);
+                if index == 0 {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                index
+            },
+            |index| {
+                outputs.push(index);
+                delivered.fetch_add(1, Ordering::SeqCst);
+            },
+        );
+
+        assert!(not_started.is_empty());
+        assert_eq!(outputs, (0..50).collect::<Vec<usize>>());
+        assert!(furthest_ahead.load(Ordering::SeqCst) < 2);
+    }
+
+    #[test]
+    fn a_panicking_input_fails_the_call_instead_of_hanging() {
+        let pool = WorkerPool::new(2, 1);
+        let mut outputs = Vec::new();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            pool.map_ordered(
+                (0..20).collect(),
+                || false,
+                |index: usize| {
+                    if index == 3 {
+                        panic!("input {} is bad", index);
+                    }
+                    index
+                },
+                |index| outputs.push(index),
+            )
+        }));
+
+        let payload = result.expect_err("the panic reaches the caller");
+        assert_eq!(payload.downcast_ref::<String>().map(String::as_str), Some("input 3 is bad"));
+        assert_eq!(outputs, [0, 1, 2]);
+    }
+}
+
+// End of rust_sample_project/src/worker_pool.rs
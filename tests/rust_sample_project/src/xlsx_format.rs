@@ -0,0 +1,320 @@
+// rust_sample_project/src/xlsx_format.rs
+
+// Items data files as Excel workbooks (".xlsx", the `xlsx` feature), for the
+// spreadsheets analysts hand over. Items are read from one sheet (the first,
+// unless a sheet name is configured) whose first row holds the column
+// headers; an XlsxMapping names the id, name and value columns. Optional
+// "status" and "tags" (';'-separated) columns are read as in CSV files, so
+// a results workbook can be loaded again. Rows that do not make a valid item
+// are skipped with a warning.
+//
+// Saved workbooks hold one results sheet with the mapped columns, the
+// status, a "processed" column (highlighted for processed items), the tags
+// and the processing time.
+
+use crate::config::{XLSX_ID_COLUMN, XLSX_NAME_COLUMN, XLSX_RESULTS_SHEET, XLSX_SHEET, XLSX_VALUE_COLUMN};
+use crate::invalid_records::InvalidRecords;
+use crate::item::Item;
+
+/// Characters Excel does not allow in sheet names.
+const INVALID_SHEET_CHARS: &[char] = &['[', ']', ':', '*', '?', '/', '\\'];
+/// Longest sheet name Excel accepts.
+const MAX_SHEET_NAME_LEN: usize = 31;
+
+/**
+ * @struct XlsxMapping
+ * @brief Which sheet and columns of a workbook hold the items.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XlsxMapping {
+    /// Sheet to read items from; empty for the first sheet.
+    pub sheet: String,
+    /// Header of the column holding the item id.
+    pub id_column: String,
+    /// Header of the column holding the item name.
+    pub name_column: String,
+    /// Header of the column holding the item value.
+    pub value_column: String,
+    /// Name of the sheet saved workbooks hold the results in.
+    pub results_sheet: String,
+}
+
+impl XlsxMapping {
+    /**
+     * @brief Constructs a mapping from its config values.
+     *
+     * @param sheet Sheet to read items from ("" for the first sheet).
+     * @param id_column Header of the id column.
+     * @param name_column Header of the name column.
+     * @param value_column Header of the value column.
+     * @param results_sheet Name of the sheet written on save.
+     * @return Result<XlsxMapping, String> The mapping, or an error message for
+     *         an empty column header or an invalid sheet name.
+     */
+    pub fn new(
+        sheet: &str,
+        id_column: &str,
+        name_column: &str,
+        value_column: &str,
+        results_sheet: &str,
+    ) -> Result<Self, String> {
+        for column in [id_column, name_column, value_column] {
+            check_column_name(column)?;
+        }
+        if !sheet.is_empty() {
+            check_sheet_name(sheet)?;
+        }
+        check_sheet_name(results_sheet)?;
+        Ok(XlsxMapping {
+            sheet: sheet.to_string(),
+            id_column: id_column.to_string(),
+            name_column: name_column.to_string(),
+            value_column: value_column.to_string(),
+            results_sheet: results_sheet.to_string(),
+        })
+    }
+}
+
+impl Default for XlsxMapping {
+    /**
+     * @brief The mapping configured by the XLSX_* defaults in config.rs.
+     */
+    fn default() -> Self {
+        XlsxMapping::new(XLSX_SHEET, XLSX_ID_COLUMN, XLSX_NAME_COLUMN, XLSX_VALUE_COLUMN, XLSX_RESULTS_SHEET)
+            .expect("the default XLSX mapping is valid")
+    }
+}
+
+/**
+ * @brief Check a column header of the mapping.
+ *
+ * @param name The header.
+ * @return Result<(), String> Ok unless the header is blank.
+ */
+pub fn check_column_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err(String::from("the column header must not be empty"));
+    }
+    Ok(())
+}
+
+/**
+ * @brief Check that a sheet name is one Excel accepts.
+ *
+ * @param name The sheet name.
+ * @return Result<(), String> Ok for a valid name, or an error message.
+ */
+pub fn check_sheet_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() || name.chars().count() > MAX_SHEET_NAME_LEN {
+        return Err(format!("sheet name '{}' must be 1 to {} characters long", name, MAX_SHEET_NAME_LEN));
+    }
+    if name.contains(INVALID_SHEET_CHARS) {
+        return Err(format!("sheet name '{}' must not contain any of [ ] : * ? / \\", name));
+    }
+    Ok(())
+}
+
+/**
+ * @brief Parse the items of an Excel workbook.
+ *
+ * @param contents The file contents.
+ * @param path The file path, used in messages.
+ * @param mapping Which sheet and columns hold the items.
+ * @param invalid Where invalid rows are reported.
+ * @return Result<Vec<Item>, String> The valid items, or an error message if
+ *         the workbook or sheet cannot be read or a mapped column is missing.
+ */
+#[cfg(feature = "xlsx")]
+pub fn parse_items(
+    contents: &[u8],
+    path: &str,
+    mapping: &XlsxMapping,
+    invalid: &mut InvalidRecords,
+) -> Result<Vec<Item>, String> {
+    use std::collections::HashMap;
+    use std::io::Cursor;
+
+    use calamine::{open_workbook_from_rs, Reader, Xlsx};
+
+    use crate::item::ItemStatus;
+    use crate::item_id::{self, ItemId};
+    use crate::money::Money;
+
+    let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(contents))
+        .map_err(|e| format!("Failed to read Excel data file '{}': {}", path, e))?;
+    let sheet = match mapping.sheet.as_str() {
+        "" => workbook
+            .sheet_names()
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("Excel data file '{}' has no sheets", path))?,
+        name => name.to_string(),
+    };
+    let range = workbook
+        .worksheet_range(&sheet)
+        .map_err(|e| format!("Failed to read sheet '{}' of '{}': {}", sheet, path, e))?;
+
+    let mut rows = range.rows();
+    let headers: HashMap<String, usize> = rows
+        .next()
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(index, cell)| (cell_text(cell).trim().to_lowercase(), index))
+        .collect();
+    let column = |name: &str| headers.get(&name.trim().to_lowercase()).copied();
+    let required = |name: &str| {
+        column(name).ok_or_else(|| format!("Sheet '{}' of '{}' has no '{}' column", sheet, path, name))
+    };
+    let id_column = required(&mapping.id_column)?;
+    let name_column = required(&mapping.name_column)?;
+    let value_column = required(&mapping.value_column)?;
+    let status_column = column("status");
+    let tags_column = column("tags");
+
+    let mut items = Vec::new();
+    for (index, row) in rows.enumerate() {
+        let cell = |column: usize| row.get(column).map(cell_text).unwrap_or_default();
+        if row.iter().all(|cell| cell_text(cell).trim().is_empty()) {
+            continue;
+        }
+        let item = ItemId::parse(&cell(id_column), item_id::expected_kind()).and_then(|item_id| {
+            let value = Money::parse(&cell(value_column))?;
+            let mut item = Item::new(item_id, cell(name_column), value);
+            if let Some(status) = status_column.map(cell).filter(|status| !status.trim().is_empty()) {
+                item.status = ItemStatus::parse(status.trim())?;
+            }
+            for tag in tags_column.map(cell).unwrap_or_default().split(';').map(str::trim) {
+                if !tag.is_empty() {
+                    item.add_tag(tag);
+                }
+            }
+            Ok(item)
+        });
+        match item {
+            Ok(item) => items.push(item),
+            Err(e) => {
+                let source = row.iter().map(cell_text).collect::<Vec<_>>().join(", ");
+                invalid.skip(format_args!("row {} of sheet '{}'", index + 2, sheet), None, &source, e);
+            }
+        }
+    }
+    Ok(items)
+}
+
+/**
+ * @brief Parse the items of an Excel workbook (unavailable without the `xlsx` feature).
+ */
+#[cfg(not(feature = "xlsx"))]
+pub fn parse_items(
+    contents: &[u8],
+    path: &str,
+    mapping: &XlsxMapping,
+    invalid: &mut InvalidRecords,
+) -> Result<Vec<Item>, String> {
+    let _ = (contents, mapping, invalid);
+    Err(format!(
+        "Data file '{}' is an Excel workbook, but this build lacks the 'xlsx' feature",
+        path
+    ))
+}
+
+/**
+ * @brief Serialize items as an Excel workbook with one results sheet.
+ *
+ * @param items The items to write.
+ * @param mapping Headers of the id, name and value columns and the sheet name.
+ * @return Result<Vec<u8>, String> The file contents, or an error message.
+ */
+#[cfg(feature = "xlsx")]
+pub fn encode_items(items: &[Item], mapping: &XlsxMapping) -> Result<Vec<u8>, String> {
+    let mut workbook = rust_xlsxwriter::Workbook::new();
+    write_results_sheet(workbook.add_worksheet(), items, mapping)
+        .and_then(|()| workbook.save_to_buffer())
+        .map_err(|e| format!("Failed to write Excel workbook: {}", e))
+}
+
+/**
+ * @brief Fill the results sheet: a bold header row, one row per item, and
+ *        the processed column highlighted green (processed) or red (not).
+ */
+#[cfg(feature = "xlsx")]
+fn write_results_sheet(
+    sheet: &mut rust_xlsxwriter::Worksheet,
+    items: &[Item],
+    mapping: &XlsxMapping,
+) -> Result<(), rust_xlsxwriter::XlsxError> {
+    use rust_xlsxwriter::{Color, Format};
+
+    use crate::item_id::ItemId;
+
+    let header = Format::new().set_bold();
+    let highlighted_header = Format::new().set_bold().set_background_color(Color::RGB(0xFFEB9C));
+    let processed = Format::new().set_bold().set_background_color(Color::RGB(0xC6EFCE));
+    let not_processed = Format::new().set_background_color(Color::RGB(0xFFC7CE));
+
+    sheet.set_name(mapping.results_sheet.as_str())?;
+    let headers = [
+        mapping.id_column.as_str(),
+        mapping.name_column.as_str(),
+        mapping.value_column.as_str(),
+        "status",
+        "processed",
+        "tags",
+        "processed_at",
+    ];
+    for (column, title) in (0u16..).zip(headers) {
+        let format = if title == "processed" { &highlighted_header } else { &header };
+        sheet.write_string_with_format(0, column, title, format)?;
+    }
+    for (row, item) in (1u32..).zip(items) {
+        match &item.item_id {
+            ItemId::Int(id) => sheet.write_number(row, 0, *id as f64)?,
+            other => sheet.write_string(row, 0, other.to_string())?,
+        };
+        sheet.write_string(row, 1, &*item.name)?;
+        // Excel numbers are doubles, so very precise amounts are rounded here
+        let value: f64 = item.value.to_string().parse().unwrap_or_default();
+        sheet.write_number(row, 2, value)?;
+        sheet.write_string(row, 3, item.status.name())?;
+        if item.is_processed() {
+            sheet.write_string_with_format(row, 4, "yes", &processed)?;
+        } else {
+            sheet.write_string_with_format(row, 4, "no", &not_processed)?;
+        }
+        sheet.write_string(row, 5, item.tags.join(";"))?;
+        if let Some(processed_at) = item.processed_at {
+            sheet.write_string(row, 6, processed_at.to_rfc3339())?;
+        }
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.autofit();
+    Ok(())
+}
+
+/**
+ * @brief Serialize items as an Excel workbook (unavailable without the `xlsx` feature).
+ */
+#[cfg(not(feature = "xlsx"))]
+pub fn encode_items(items: &[Item], mapping: &XlsxMapping) -> Result<Vec<u8>, String> {
+    let _ = (items, mapping);
+    Err(String::from("Cannot write an Excel workbook: this build lacks the 'xlsx' feature"))
+}
+
+/**
+ * @brief Return a cell as text; whole numbers are written without decimals,
+ *        so an id cell holding 42.0 reads as "42".
+ */
+#[cfg(feature = "xlsx")]
+fn cell_text(cell: &calamine::Data) -> String {
+    use calamine::Data;
+
+    match cell {
+        Data::Empty => String::new(),
+        Data::String(text) => text.clone(),
+        Data::Float(number) if number.fract() == 0.0 && number.abs() < 1e15 => format!("{}", *number as i64),
+        other => other.to_string(),
+    }
+}
+
+// End of rust_sample_project/src/xlsx_format.rs
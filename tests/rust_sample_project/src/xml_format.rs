@@ -0,0 +1,383 @@
+// rust_sample_project/src/xml_format.rs
+
+// Items data files in XML, as exported by legacy upstreams (".xml", input
+// only). There is no fixed schema: an XmlMapping names the element holding
+// one item and where its id, name and value are, either an attribute of the
+// item element ("@id") or the text of a child element ("name"):
+//
+//     <export>
+//       <product sku="42"><title>Widget</title><price>150.75</price></product>
+//     </export>
+//
+// is read with item element "product", id "@sku", name "title" and value
+// "price". Items may appear at any depth; other elements are ignored, and
+// namespace prefixes are ignored when matching names. In strict mode a
+// syntax error or an item lacking a mapped field fails the load; in lenient
+// mode invalid items are skipped with a warning, and a syntax error ends the
+// file, keeping the items read until then.
+
+use std::collections::HashMap;
+
+use quick_xml::events::Event;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::{Reader, XmlVersion};
+
+use crate::config::{XML_ID_FIELD, XML_ITEM_ELEMENT, XML_MODE, XML_NAME_FIELD, XML_VALUE_FIELD};
+use crate::invalid_records::{self, InvalidRecords};
+use crate::item::Item;
+use crate::item_id::{self, ItemId};
+use crate::money::Money;
+
+/**
+ * @enum XmlMode
+ * @brief How forgiving the XML reader is.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XmlMode {
+    /// Fail on malformed XML and on items that cannot be read ("strict").
+    Strict,
+    /// Skip invalid items, stop at malformed XML, keep what was read ("lenient").
+    Lenient,
+}
+
+impl XmlMode {
+    /**
+     * @brief Parse a mode name as used in config.
+     *
+     * @param name One of "strict" or "lenient".
+     * @return Result<XmlMode, String> The mode, or an error message for unknown names.
+     */
+    pub fn parse(name: &str) -> Result<XmlMode, String> {
+        match name {
+            "strict" => Ok(XmlMode::Strict),
+            "lenient" => Ok(XmlMode::Lenient),
+            other => Err(format!("Unknown XML mode '{}' (expected 'strict' or 'lenient')", other)),
+        }
+    }
+}
+
+/**
+ * @struct XmlMapping
+ * @brief Where the items and their fields are in an XML data file.
+ *
+ * A field name starting with '@' is an attribute of the item element; any
+ * other name is a child element whose text is the field.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlMapping {
+    /// Name of the element holding one item.
+    pub item_element: String,
+    /// Attribute ("@id") or child element holding the item id.
+    pub id_field: String,
+    /// Attribute or child element holding the item name.
+    pub name_field: String,
+    /// Attribute or child element holding the item value.
+    pub value_field: String,
+    /// Strict or lenient parsing.
+    pub mode: XmlMode,
+}
+
+impl XmlMapping {
+    /**
+     * @brief Constructs a mapping from its config values.
+     *
+     * @param item_element Name of the element holding one item.
+     * @param id_field Attribute ("@name") or child element holding the id.
+     * @param name_field Attribute or child element holding the name.
+     * @param value_field Attribute or child element holding the value.
+     * @param mode "strict" or "lenient".
+     * @return Result<XmlMapping, String> The mapping, or an error message for
+     *         an empty name or unknown mode.
+     */
+    pub fn new(
+        item_element: &str,
+        id_field: &str,
+        name_field: &str,
+        value_field: &str,
+        mode: &str,
+    ) -> Result<Self, String> {
+        let mode = XmlMode::parse(mode)?;
+        check_field_name(item_element, false)?;
+        for field in [id_field, name_field, value_field] {
+            check_field_name(field, true)?;
+        }
+        Ok(XmlMapping {
+            item_element: item_element.to_string(),
+            id_field: id_field.to_string(),
+            name_field: name_field.to_string(),
+            value_field: value_field.to_string(),
+            mode,
+        })
+    }
+
+    /**
+     * @brief Build an item from the fields collected for one item element.
+     */
+    fn item_from_fields(&self, fields: &HashMap<String, String>) -> Result<Item, String> {
+        let field = |name: &String| {
+            fields
+                .get(name)
+                .ok_or_else(|| format!("missing {}", describe_field(name)))
+        };
+        let item_id = ItemId::parse(field(&self.id_field)?, item_id::expected_kind())?;
+        let name = field(&self.name_field)?.clone();
+        let value = Money::parse(field(&self.value_field)?)?;
+        Ok(Item::new(item_id, name, value))
+    }
+}
+
+impl Default for XmlMapping {
+    /**
+     * @brief The mapping configured by the XML_* defaults in config.rs.
+     */
+    fn default() -> Self {
+        XmlMapping::new(XML_ITEM_ELEMENT, XML_ID_FIELD, XML_NAME_FIELD, XML_VALUE_FIELD, XML_MODE)
+            .expect("the default XML mapping is valid")
+    }
+}
+
+/**
+ * @brief Check a name of the XML mapping.
+ *
+ * @param name An element name, or "@" and an attribute name.
+ * @param attribute_allowed Whether the name may refer to an attribute.
+ * @return Result<(), String> Ok for a usable name, or an error message.
+ */
+pub fn check_field_name(name: &str, attribute_allowed: bool) -> Result<(), String> {
+    let bare = name.strip_prefix('@');
+    if bare.unwrap_or(name).is_empty() || bare.unwrap_or(name).contains(char::is_whitespace) {
+        return Err(format!("'{}' is not an XML element or attribute name", name));
+    }
+    if bare.is_some() && !attribute_allowed {
+        return Err(format!("'{}' must be an element, not an attribute", name));
+    }
+    Ok(())
+}
+
+/**
+ * @brief Parse the items of an XML data file.
+ *
+ * @param contents The file contents.
+ * @param path The file path, used in messages.
+ * @param mapping Where the items and their fields are.
+ * @param invalid Where invalid items are reported when the mode is lenient.
+ * @return Result<Vec<Item>, String> The items, or (in strict mode) an error
+ *         message for malformed XML or an invalid item, quoting its line.
+ */
+pub fn parse_items(
+    contents: &str,
+    path: &str,
+    mapping: &XmlMapping,
+    invalid: &mut InvalidRecords,
+) -> Result<Vec<Item>, String> {
+    let strict = mapping.mode == XmlMode::Strict;
+    let mut reader = Reader::from_str(contents);
+    let config = reader.config_mut();
+    config.check_end_names = strict;
+    config.allow_unmatched_ends = !strict;
+    config.allow_dangling_amp = !strict;
+
+    let mut items = Vec::new();
+    let mut record = 0;
+    // Fields of the item element being read (None outside items), where it
+    // starts, elements open inside it, and the child element whose text is
+    // being collected
+    let mut fields: Option<HashMap<String, String>> = None;
+    let mut item_start = 0;
+    let mut depth = 0usize;
+    let mut child: Option<(String, String)> = None;
+    loop {
+        let offset = reader.buffer_position() as usize;
+        let event = match reader.read_event() {
+            Ok(event) => event,
+            Err(e) if strict => return Err(malformed(contents, path, reader.error_position(), e)),
+            Err(e) => {
+                log_warning!(
+                    "xml_format:parse_items",
+                    "Stopped reading {} at malformed XML (line {}): {}; keeping the {} items read so far.",
+                    path,
+                    invalid_records::line_of(contents.as_bytes(), reader.error_position() as usize),
+                    e,
+                    items.len()
+                );
+                return Ok(items);
+            }
+        };
+        let empty = matches!(event, Event::Empty(_));
+        match event {
+            Event::Start(element) | Event::Empty(element) => match fields.as_mut() {
+                None if element.local_name().as_ref() == mapping.item_element.as_bytes() => {
+                    record += 1;
+                    item_start = offset;
+                    let mut attributes = HashMap::new();
+                    for attribute in element.attributes().flatten() {
+                        let value = attribute
+                            .normalized_value(XmlVersion::Implicit1_0)
+                            .map(|value| value.into_owned())
+                            .unwrap_or_else(|_| String::from_utf8_lossy(&attribute.value).into_owned());
+                        attributes.insert(format!("@{}", local_name(attribute.key.local_name().as_ref())), value);
+                    }
+                    if empty {
+                        let end = reader.buffer_position() as usize;
+                        let element = ItemElement::new(contents, path, record, item_start..end);
+                        finish_item(mapping, &attributes, &element, &mut items, invalid)?;
+                    } else {
+                        fields = Some(attributes);
+                        depth = 0;
+                    }
+                }
+                None => {}
+                Some(item_fields) => {
+                    if depth == 0 {
+                        let name = local_name(element.local_name().as_ref());
+                        if empty {
+                            item_fields.entry(name).or_default();
+                        } else {
+                            child = Some((name, String::new()));
+                        }
+                    }
+                    if !empty {
+                        depth += 1;
+                    }
+                }
+            },
+            Event::Text(text) => {
+                if let (Some((_, buffer)), 1) = (child.as_mut(), depth) {
+                    buffer.push_str(&String::from_utf8_lossy(&text));
+                }
+            }
+            Event::CData(data) => {
+                if let (Some((_, buffer)), 1) = (child.as_mut(), depth) {
+                    buffer.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::GeneralRef(reference) => {
+                if let (Some((_, buffer)), 1) = (child.as_mut(), depth) {
+                    let name = String::from_utf8_lossy(&reference).into_owned();
+                    match reference.resolve_char_ref().ok().flatten() {
+                        Some(character) => buffer.push(character),
+                        None => match resolve_predefined_entity(&name) {
+                            Some(replacement) => buffer.push_str(replacement),
+                            None if strict => {
+                                let error = format!("unknown entity '&{};'", name);
+                                return Err(malformed(contents, path, offset as u64, error));
+                            }
+                            None => buffer.push_str(&format!("&{};", name)),
+                        },
+                    }
+                }
+            }
+            Event::End(_) => match fields.as_mut() {
+                None => {}
+                Some(_) if depth == 0 => {
+                    let item_fields = fields.take().unwrap_or_default();
+                    let end = reader.buffer_position() as usize;
+                    let element = ItemElement::new(contents, path, record, item_start..end);
+                    finish_item(mapping, &item_fields, &element, &mut items, invalid)?;
+                }
+                Some(item_fields) => {
+                    depth -= 1;
+                    if let (0, Some((name, text))) = (depth, child.take()) {
+                        item_fields.entry(name).or_insert_with(|| text.trim().to_string());
+                    }
+                }
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if fields.is_some() {
+        let element = ItemElement::new(contents, path, record, item_start..contents.len());
+        if strict {
+            let message = format!("XML data file '{}' ends inside item #{} (line {})", path, record, element.line);
+            return Err(invalid_records::with_source(message, contents, element.line));
+        }
+        invalid.skip(format_args!("record #{}", record), Some(element.line), element.source, "the file ends inside it");
+    }
+    Ok(items)
+}
+
+/**
+ * @brief Describe malformed XML at a byte offset, quoting its line.
+ */
+fn malformed(contents: &str, path: &str, offset: u64, error: impl std::fmt::Display) -> String {
+    let line = invalid_records::line_of(contents.as_bytes(), offset as usize);
+    let message = format!("Failed to parse XML data file '{}' at line {} (byte {}): {}", path, line, offset, error);
+    invalid_records::with_source(message, contents, line)
+}
+
+/**
+ * @struct ItemElement
+ * @brief Where an item element is in an XML data file, for messages.
+ */
+struct ItemElement<'a> {
+    /// The data file.
+    path: &'a str,
+    /// Number of the item element, counting from 1.
+    record: usize,
+    /// The line it starts on.
+    line: usize,
+    /// Its source text.
+    source: &'a str,
+}
+
+impl<'a> ItemElement<'a> {
+    /**
+     * @brief Locate an item element by its byte range in the file.
+     */
+    fn new(contents: &'a str, path: &'a str, record: usize, span: std::ops::Range<usize>) -> Self {
+        ItemElement {
+            path,
+            record,
+            line: invalid_records::line_of(contents.as_bytes(), span.start),
+            source: contents.get(span).unwrap_or(""),
+        }
+    }
+}
+
+/**
+ * @brief Add the item read from one item element, or handle it being invalid
+ *        according to the mode.
+ *
+ * @return Result<(), String> Ok unless the item is invalid in strict mode.
+ */
+fn finish_item(
+    mapping: &XmlMapping,
+    fields: &HashMap<String, String>,
+    element: &ItemElement,
+    items: &mut Vec<Item>,
+    invalid: &mut InvalidRecords,
+) -> Result<(), String> {
+    match mapping.item_from_fields(fields) {
+        Ok(item) => items.push(item),
+        Err(e) if mapping.mode == XmlMode::Strict => {
+            let message = format!(
+                "Invalid item #{} (line {}) in XML data file '{}': {}",
+                element.record, element.line, element.path, e
+            );
+            return Err(format!("{}; near `{}`", message, invalid_records::snippet(element.source)));
+        }
+        Err(e) => invalid.skip(format_args!("record #{}", element.record), Some(element.line), element.source, e),
+    }
+    Ok(())
+}
+
+/**
+ * @brief Return an element or attribute name as text.
+ */
+fn local_name(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).into_owned()
+}
+
+/**
+ * @brief Describe a mapped field for messages, e.g. "attribute 'id'".
+ */
+fn describe_field(name: &str) -> String {
+    match name.strip_prefix('@') {
+        Some(attribute) => format!("attribute '{}'", attribute),
+        None => format!("element <{}>", name),
+    }
+}
+
+// End of rust_sample_project/src/xml_format.rs